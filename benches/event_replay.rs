@@ -0,0 +1,281 @@
+//! Replay, caching and write-batching benchmarks against the in-memory event store, so
+//! they run without Postgres or Redis. Run with `cargo bench`.
+
+use async_trait::async_trait;
+use banking_es::domain::{Account, AccountEvent};
+use banking_es::infrastructure::cache_service::{
+    CacheMetrics, CacheServiceTrait, CacheTier, CachedAccountEntry,
+};
+use banking_es::infrastructure::event_serialization::EventFormat;
+use banking_es::infrastructure::event_store::EventStoreTrait;
+use banking_es::infrastructure::in_memory_event_store::InMemoryEventStore;
+use banking_es::infrastructure::repository::AccountRepository;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use uuid::Uuid;
+
+const STREAM_LENGTHS: [usize; 3] = [10, 100, 1000];
+/// Events written after the snapshot, which a snapshot-accelerated read still replays.
+const SNAPSHOT_TAIL: usize = 5;
+
+fn account_events(account_id: Uuid, count: usize) -> Vec<AccountEvent> {
+    let mut events = vec![AccountEvent::AccountCreated {
+        account_id,
+        owner_name: "Benchmark".to_string(),
+        initial_balance: Decimal::new(1000, 0),
+        owner_user_id: None,
+        account_type: Default::default(),
+        rules: Default::default(),
+    }];
+    events.extend((1..count).map(|_| AccountEvent::MoneyDeposited {
+        account_id,
+        amount: Decimal::new(10, 0),
+        transaction_id: Uuid::new_v4(),
+    }));
+    events
+}
+
+/// Read-through cache standing in for Redis, mirroring `AccountService::get_account`.
+#[derive(Default)]
+struct MemoryCache {
+    accounts: DashMap<Uuid, Account>,
+    metrics: CacheMetrics,
+}
+
+#[async_trait]
+impl CacheServiceTrait for MemoryCache {
+    async fn get_account(&self, account_id: Uuid) -> anyhow::Result<Option<Account>> {
+        Ok(self.accounts.get(&account_id).map(|a| a.clone()))
+    }
+
+    async fn set_account(&self, account: &Account, _ttl: Option<Duration>) -> anyhow::Result<()> {
+        self.accounts.insert(account.id, account.clone());
+        Ok(())
+    }
+
+    async fn set_account_if_newer(
+        &self,
+        account: &Account,
+        ttl: Option<Duration>,
+    ) -> anyhow::Result<bool> {
+        self.set_account(account, ttl).await?;
+        Ok(true)
+    }
+
+    async fn delete_account(&self, account_id: Uuid) -> anyhow::Result<()> {
+        self.accounts.remove(&account_id);
+        Ok(())
+    }
+
+    async fn get_account_events(
+        &self,
+        _account_id: Uuid,
+    ) -> anyhow::Result<Option<Vec<AccountEvent>>> {
+        Ok(None)
+    }
+
+    async fn set_account_events(
+        &self,
+        _account_id: Uuid,
+        _events: &[(i64, AccountEvent)],
+        _ttl: Option<Duration>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn delete_account_events(&self, _account_id: Uuid) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn invalidate_account(&self, account_id: Uuid) -> anyhow::Result<()> {
+        self.delete_account(account_id).await
+    }
+
+    async fn warmup_cache(&self, _account_ids: Vec<Uuid>) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn inspect_account(
+        &self,
+        account_id: Uuid,
+    ) -> anyhow::Result<Option<CachedAccountEntry>> {
+        Ok(self
+            .accounts
+            .get(&account_id)
+            .map(|account| CachedAccountEntry {
+                account: account.clone(),
+                tier: CacheTier::Memory,
+                created_at: None,
+                last_accessed: None,
+                access_count: None,
+                expired: false,
+            }))
+    }
+
+    async fn flush_accounts(&self) -> anyhow::Result<u64> {
+        let evicted = self.accounts.len() as u64;
+        self.accounts.clear();
+        Ok(evicted)
+    }
+
+    fn get_metrics(&self) -> &CacheMetrics {
+        &self.metrics
+    }
+}
+
+async fn cached_get_by_id(
+    cache: &MemoryCache,
+    repository: &AccountRepository,
+    account_id: Uuid,
+) -> Option<Account> {
+    if let Some(account) = cache.get_account(account_id).await.unwrap() {
+        return Some(account);
+    }
+    let account = repository.get_by_id(account_id).await.unwrap()?;
+    cache.set_account(&account, None).await.unwrap();
+    Some(account)
+}
+
+fn bench_get_by_id(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let _guard = rt.enter();
+
+    let mut group = c.benchmark_group("get_by_id");
+    group.throughput(Throughput::Elements(1));
+
+    for length in STREAM_LENGTHS {
+        let store = Arc::new(InMemoryEventStore::new());
+        let repository = AccountRepository::new(store.clone() as Arc<dyn EventStoreTrait>);
+
+        let cold_id = Uuid::new_v4();
+        let snapshot_id = Uuid::new_v4();
+        rt.block_on(async {
+            store
+                .save_events(cold_id, account_events(cold_id, length), 0)
+                .await
+                .unwrap();
+
+            let events = account_events(snapshot_id, length);
+            let (head, tail) = events.split_at(length - SNAPSHOT_TAIL);
+            store
+                .save_events(snapshot_id, head.to_vec(), 0)
+                .await
+                .unwrap();
+            store.save_snapshot(snapshot_id).await.unwrap();
+            store
+                .save_events(snapshot_id, tail.to_vec(), head.len() as i64)
+                .await
+                .unwrap();
+        });
+
+        let cache = MemoryCache::default();
+        rt.block_on(cached_get_by_id(&cache, &repository, cold_id));
+
+        group.bench_with_input(BenchmarkId::new("cold", length), &cold_id, |b, id| {
+            b.to_async(&rt)
+                .iter(|| async { repository.get_by_id(*id).await.unwrap() })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("snapshot", length),
+            &snapshot_id,
+            |b, id| {
+                b.to_async(&rt)
+                    .iter(|| async { repository.get_by_id(*id).await.unwrap() })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("warm", length), &cold_id, |b, id| {
+            b.to_async(&rt)
+                .iter(|| async { cached_get_by_id(&cache, &repository, *id).await })
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_writes(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let _guard = rt.enter();
+
+    let mut group = c.benchmark_group("writes");
+
+    for length in STREAM_LENGTHS {
+        // Reported as events written per second
+        group.throughput(Throughput::Elements(length as u64));
+
+        group.bench_function(BenchmarkId::new("immediate", length), |b| {
+            b.to_async(&rt).iter_batched(
+                InMemoryEventStore::new,
+                |store| async move {
+                    let account_id = Uuid::new_v4();
+                    for (version, event) in
+                        account_events(account_id, length).into_iter().enumerate()
+                    {
+                        store
+                            .save_events(account_id, vec![event], version as i64)
+                            .await
+                            .unwrap();
+                    }
+                    store
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("batched", length), |b| {
+            b.to_async(&rt).iter_batched(
+                InMemoryEventStore::new,
+                |store| async move {
+                    let account_id = Uuid::new_v4();
+                    store
+                        .save_events(account_id, account_events(account_id, length), 0)
+                        .await
+                        .unwrap();
+                    store
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_replay_formats(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let _guard = rt.enter();
+
+    let mut group = c.benchmark_group("replay_format");
+
+    for length in STREAM_LENGTHS {
+        group.throughput(Throughput::Elements(length as u64));
+
+        for format in [EventFormat::Json, EventFormat::MessagePack] {
+            let store = Arc::new(InMemoryEventStore::new().with_format(format));
+            let repository = AccountRepository::new(store.clone() as Arc<dyn EventStoreTrait>);
+            let account_id = Uuid::new_v4();
+            rt.block_on(async {
+                store
+                    .save_events(account_id, account_events(account_id, length), 0)
+                    .await
+                    .unwrap();
+            });
+
+            group.bench_with_input(
+                BenchmarkId::new(format.as_str(), length),
+                &account_id,
+                |b, id| {
+                    b.to_async(&rt)
+                        .iter(|| async { repository.get_by_id(*id).await.unwrap() })
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_get_by_id, bench_writes, bench_replay_formats);
+criterion_main!(benches);
@@ -0,0 +1,9 @@
+-- Link accounts to the user that owns them so access can be enforced per account
+ALTER TABLE account_projections ADD COLUMN IF NOT EXISTS owner_user_id UUID;
+
+COMMENT ON COLUMN account_projections.owner_user_id IS 'User that owns the account; NULL for accounts created before ownership was tracked';
+
+-- Index for listing a user's accounts
+CREATE INDEX IF NOT EXISTS idx_account_projections_owner_user_id
+    ON account_projections (owner_user_id)
+    WITH (fillfactor = 90);
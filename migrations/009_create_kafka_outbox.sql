@@ -0,0 +1,20 @@
+-- Event batches that could not be published to Kafka, waiting for the outbox relay
+CREATE TABLE IF NOT EXISTS kafka_outbox (
+    id UUID PRIMARY KEY,
+    aggregate_id UUID NOT NULL,
+    version BIGINT NOT NULL,
+    events JSONB NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    published_at TIMESTAMPTZ
+);
+
+-- The relay drains unpublished rows in insertion order
+CREATE INDEX IF NOT EXISTS idx_kafka_outbox_pending
+    ON kafka_outbox (created_at)
+    WHERE published_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_kafka_outbox_aggregate_id ON kafka_outbox (aggregate_id);
+
+COMMENT ON TABLE kafka_outbox IS 'Event batches queued for publication while Kafka is unavailable';
@@ -0,0 +1,9 @@
+-- Last event position processed by each projection, so consumers and rebuilds can resume
+CREATE TABLE IF NOT EXISTS projection_checkpoints (
+    projection_name VARCHAR(255) PRIMARY KEY,
+    position BIGINT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE projection_checkpoints IS 'Durable progress of projection consumers and rebuilds';
+COMMENT ON COLUMN projection_checkpoints.position IS 'Position of the last event applied to the projection';
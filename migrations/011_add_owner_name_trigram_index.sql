@@ -0,0 +1,5 @@
+-- Trigram index so owner name searches (ILIKE substring and similarity) avoid full scans
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_account_projections_owner_name_trgm
+    ON account_projections USING GIN (owner_name gin_trgm_ops);
@@ -0,0 +1,22 @@
+-- Commands whose events could not be saved, kept for inspection and replay
+CREATE TABLE IF NOT EXISTS failed_commands (
+    id UUID PRIMARY KEY,
+    aggregate_id UUID NOT NULL,
+    command JSONB NOT NULL,
+    events JSONB NOT NULL,
+    expected_version BIGINT NOT NULL,
+    error TEXT NOT NULL,
+    context JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    replay_attempts INTEGER NOT NULL DEFAULT 0,
+    last_replay_error TEXT,
+    replayed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_failed_commands_pending
+    ON failed_commands (created_at)
+    WHERE replayed_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_failed_commands_aggregate_id ON failed_commands (aggregate_id);
+
+COMMENT ON TABLE failed_commands IS 'Commands whose events failed to save, for later inspection and replay';
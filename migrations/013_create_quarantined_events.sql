@@ -0,0 +1,14 @@
+-- Copies of stored events that could not be deserialized during replay
+CREATE TABLE IF NOT EXISTS quarantined_events (
+    event_id UUID PRIMARY KEY,
+    aggregate_id UUID NOT NULL,
+    event_type VARCHAR(255) NOT NULL,
+    event_data JSONB NOT NULL,
+    version BIGINT NOT NULL,
+    error TEXT NOT NULL,
+    quarantined_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_quarantined_events_aggregate_id ON quarantined_events (aggregate_id);
+
+COMMENT ON TABLE quarantined_events IS 'Events skipped during replay because they failed to deserialize';
@@ -0,0 +1,4 @@
+-- Encoding of each event's payload, so streams can mix formats during a migration
+ALTER TABLE events ADD COLUMN IF NOT EXISTS event_format VARCHAR(16) NOT NULL DEFAULT 'json';
+
+COMMENT ON COLUMN events.event_format IS 'Payload encoding of event_data: json or msgpack (base64 string)';
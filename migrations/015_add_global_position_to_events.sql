@@ -0,0 +1,6 @@
+-- Store-wide order of events, so readers can page through every stream in commit order
+ALTER TABLE events ADD COLUMN IF NOT EXISTS global_position BIGSERIAL;
+
+CREATE INDEX IF NOT EXISTS idx_events_global_position ON events (global_position);
+
+COMMENT ON COLUMN events.global_position IS 'Increasing position of the event across all aggregates';
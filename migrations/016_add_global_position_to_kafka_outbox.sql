@@ -0,0 +1,2 @@
+-- Store-wide position of each queued batch's last event, published with the batch
+ALTER TABLE kafka_outbox ADD COLUMN IF NOT EXISTS global_position BIGINT;
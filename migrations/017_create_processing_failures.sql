@@ -0,0 +1,21 @@
+-- Event batches the Kafka event processor failed to process, with their retry state
+CREATE TABLE IF NOT EXISTS processing_failures (
+    id UUID PRIMARY KEY,
+    aggregate_id UUID NOT NULL,
+    version BIGINT NOT NULL,
+    global_position BIGINT,
+    kafka_partition INTEGER,
+    kafka_offset BIGINT,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    last_error TEXT NOT NULL,
+    status TEXT NOT NULL,
+    next_retry_at TIMESTAMPTZ,
+    first_failed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT processing_failures_batch UNIQUE (aggregate_id, version)
+);
+
+CREATE INDEX IF NOT EXISTS idx_processing_failures_status
+    ON processing_failures (status, updated_at);
+
+COMMENT ON TABLE processing_failures IS 'Kafka event batches that failed processing, with attempt counts and retry state';
@@ -0,0 +1,23 @@
+-- Accounts exempt from event compaction, e.g. while under litigation or investigation
+CREATE TABLE IF NOT EXISTS account_legal_holds (
+    account_id UUID PRIMARY KEY,
+    reason TEXT NOT NULL,
+    placed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE account_legal_holds IS 'Accounts whose event history must not be compacted';
+
+-- Audit record left behind when a closed account's events are collapsed into its snapshot
+CREATE TABLE IF NOT EXISTS account_tombstones (
+    account_id UUID PRIMARY KEY,
+    final_version BIGINT NOT NULL,
+    final_state JSONB NOT NULL,
+    events_removed BIGINT NOT NULL,
+    closed_at TIMESTAMPTZ NOT NULL,
+    compacted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_tombstones_compacted_at
+    ON account_tombstones (compacted_at);
+
+COMMENT ON TABLE account_tombstones IS 'Closed accounts whose event history was compacted into a final snapshot';
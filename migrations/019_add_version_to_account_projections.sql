@@ -0,0 +1,14 @@
+-- Version of the last event applied to each projection, so replayed events can be recognised
+ALTER TABLE account_projections ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 0;
+
+-- Existing projections are up to date with their streams
+UPDATE account_projections p
+SET version = e.version
+FROM (
+    SELECT aggregate_id, MAX(version) AS version
+    FROM events
+    GROUP BY aggregate_id
+) e
+WHERE p.id = e.aggregate_id;
+
+COMMENT ON COLUMN account_projections.version IS 'Aggregate version of the last event applied to the projection; 0 before any';
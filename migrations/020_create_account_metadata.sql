@@ -0,0 +1,12 @@
+-- Operations tags set on accounts, kept apart from account_projections so the
+-- projection rows stay narrow and reindexing them leaves the tags in place
+CREATE TABLE IF NOT EXISTS account_metadata (
+    account_id UUID NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (account_id, key)
+);
+
+-- Filtering accounts by tag looks up key and value together
+CREATE INDEX IF NOT EXISTS idx_account_metadata_key_value ON account_metadata (key, value);
@@ -0,0 +1,18 @@
+-- Authorization decisions of guarded operations: who asked for what, what it required,
+-- and whether and why it was allowed
+CREATE TABLE IF NOT EXISTS authorization_audit (
+    id UUID PRIMARY KEY,
+    subject_id UUID NOT NULL,
+    subject TEXT NOT NULL,
+    method TEXT NOT NULL,
+    route TEXT NOT NULL,
+    resource TEXT NOT NULL,
+    required TEXT NOT NULL,
+    allowed BOOLEAN NOT NULL,
+    reason TEXT NOT NULL,
+    decided_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Reviews look up one subject's decisions, newest first
+CREATE INDEX IF NOT EXISTS idx_authorization_audit_subject
+    ON authorization_audit (subject_id, decided_at DESC);
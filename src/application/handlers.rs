@@ -1,5 +1,5 @@
 use crate::application::services::AccountService;
-use crate::domain::{Account, AccountCommand, AccountError, AccountEvent};
+use crate::domain::{Account, AccountCommand, AccountError, AccountEvent, AccountRules, AccountType};
 use crate::infrastructure::projections::{AccountProjection, TransactionProjection};
 use crate::infrastructure::repository::AccountRepositoryTrait; // Changed
 use anyhow::Result;
@@ -34,6 +34,9 @@ impl AccountCommandHandler {
             account_id,
             owner_name,
             initial_balance,
+            owner_user_id: None,
+            account_type: AccountType::default(),
+            rules: AccountType::default().default_rules(),
         };
 
         // Account::handle_command for CreateAccount is static-like, doesn't use self's state.
@@ -47,6 +50,11 @@ impl AccountCommandHandler {
             balance: Decimal::ZERO,
             is_active: false, // Initial state before AccountCreated event is applied by store
             version: 0,       // Version before this first event
+            owner_user_id: None,
+            account_type: AccountType::default(),
+            rules: AccountRules::default(),
+            metadata: Default::default(),
+            recent_withdrawals: Vec::new(),
         };
 
         self.repository
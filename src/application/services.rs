@@ -1,17 +1,46 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use crate::domain::{Account, AccountCommand, AccountError, AccountEvent};
-use crate::infrastructure::cache_service::{CacheService, CacheServiceTrait};
+use crate::domain::{
+    Account, AccountCommand, AccountError, AccountEvent, AccountType, AmountPrecision,
+};
+use crate::error::BankingError;
+use crate::infrastructure::auth::Claims;
+use crate::infrastructure::authorization_audit::AuthorizationAudit;
+use crate::infrastructure::cache_service::{
+    CacheMetrics, CacheService, CacheServiceTrait, CachedAccountEntry,
+};
+use crate::infrastructure::config::{
+    AccountIdConfig, AccountTemplatesConfig, AuthorizationAuditConfig, InitialBalancePolicyConfig,
+    ResponseCacheConfig,
+};
+use crate::infrastructure::event_store::{
+    AggregateStats, EventMetadata, EventStoreError, StoredEvent,
+};
+use crate::infrastructure::event_store_timing::EventStoreLatency;
+use crate::infrastructure::kafka_abstraction::CacheInvalidationType;
+use crate::infrastructure::kafka_event_processor::PROJECTION_CHECKPOINT;
+use crate::infrastructure::failed_commands::{FailedCommandError, FailedCommandStore};
+use crate::infrastructure::processing_failures::{
+    ProcessingFailure, ProcessingFailureStatus, ProcessingFailureStore,
+};
 use crate::infrastructure::middleware::RequestMiddleware;
+use crate::infrastructure::outbox::{KafkaHealth, OutboxError, OutboxPublisher};
 use crate::infrastructure::projections::ProjectionStoreTrait;
-use crate::infrastructure::projections::{AccountProjection, TransactionProjection};
-use crate::infrastructure::repository::AccountRepositoryTrait;
+use crate::infrastructure::readiness::ReadinessGate;
+use crate::infrastructure::projections::{
+    AccountProjection, AccountSearch, ProjectionEvent, TransactionProjection,
+};
+use crate::infrastructure::repository::{AccountRepositoryTrait, RepositoryError};
+use crate::infrastructure::scaling::{ScalingManager, ShardId, ShardMetrics};
+use crate::infrastructure::slo::SloTracker;
 use crate::infrastructure::{AccountRepository, EventStore, EventStoreConfig, ProjectionStore};
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::Utc;
 use rust_decimal::Decimal;
+use serde::Serialize;
 use tokio::sync::RwLock;
 use tokio::sync::Semaphore;
 use tracing::{debug, error, info, warn};
@@ -26,6 +55,159 @@ pub struct ServiceMetrics {
     pub projection_errors: std::sync::atomic::AtomicU64,
     pub cache_hits: std::sync::atomic::AtomicU64,
     pub cache_misses: std::sync::atomic::AtomicU64,
+    /// Reads served by replaying the event store because the projections were unavailable.
+    pub projection_fallbacks: std::sync::atomic::AtomicU64,
+    /// Accounts served from the projection store by bulk reads.
+    pub bulk_projection_reads: std::sync::atomic::AtomicU64,
+    /// Accounts replayed from the event store by bulk reads.
+    pub bulk_replay_reads: std::sync::atomic::AtomicU64,
+    /// Projection rows found disagreeing with the event store, or missing, by the verifier.
+    pub projection_mismatches: std::sync::atomic::AtomicU64,
+}
+
+/// Where a read was served from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadSource {
+    Cache,
+    Projection,
+    /// Rebuilt from the event store because the projection store was unavailable or
+    /// had no projection for the account.
+    EventStore,
+    /// The account as last loaded from the event store, served while neither the
+    /// projection store nor the event store could be reached.
+    Stale {
+        as_of: chrono::DateTime<Utc>,
+    },
+}
+
+impl ReadSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReadSource::Cache => "cache",
+            ReadSource::Projection => "projection",
+            ReadSource::EventStore => "event-store",
+            ReadSource::Stale { .. } => "stale",
+        }
+    }
+}
+
+/// A cached account compared against the event store.
+#[derive(Debug, Clone)]
+pub struct CachedAccountInspection {
+    pub entry: CachedAccountEntry,
+    /// Version of the account in the event store, `None` if it no longer exists there.
+    pub current_version: Option<i64>,
+    pub stale: bool,
+}
+
+/// Result of reloading cached accounts from the event store.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CacheRehydrationSummary {
+    pub refreshed: usize,
+    /// Accounts the event store has no events for; their cached entries were evicted.
+    pub not_found: Vec<Uuid>,
+    pub failures: Vec<CacheRehydrationFailure>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheRehydrationFailure {
+    pub account_id: Uuid,
+    pub error: String,
+}
+
+/// A projection field that disagrees with the account replayed from the event store.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectionMismatch {
+    pub field: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// An account's projection row checked against its replayed state. The row carries no
+/// version, so `version` is the event-store version the row was checked against.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectionVerification {
+    pub account_id: Uuid,
+    pub version: i64,
+    pub projection_missing: bool,
+    pub mismatches: Vec<ProjectionMismatch>,
+    /// Whether the row was rewritten from the replayed state.
+    pub repaired: bool,
+}
+
+impl ProjectionVerification {
+    pub fn is_consistent(&self) -> bool {
+        !self.projection_missing && self.mismatches.is_empty()
+    }
+}
+
+/// Result of verifying a sample of accounts; only inconsistent accounts are listed.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProjectionVerificationSummary {
+    pub checked: usize,
+    pub inconsistent: Vec<ProjectionVerification>,
+}
+
+/// What a command would do if it were committed.
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunOutcome {
+    pub account_id: Uuid,
+    pub balance: Decimal,
+    pub version: i64,
+    pub events: Vec<AccountEvent>,
+}
+
+/// Confirmation of a committed deposit or withdrawal, taken from the event it saved.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionReceipt {
+    pub account_id: Uuid,
+    pub amount: Decimal,
+    pub balance_before: Decimal,
+    pub balance_after: Decimal,
+    /// Version of the account's event stream after the transaction.
+    pub version: i64,
+    /// The saved event's transaction id.
+    pub event_id: Uuid,
+    pub timestamp: chrono::DateTime<Utc>,
+}
+
+impl TransactionReceipt {
+    /// The receipt for `events` applied to an account that held `balance_before`.
+    /// Returns `None` if they don't include a deposit, withdrawal or fee.
+    fn for_events(
+        balance_before: Decimal,
+        account: &Account,
+        events: &[AccountEvent],
+    ) -> Option<Self> {
+        events.iter().find_map(|event| match event {
+            AccountEvent::MoneyDeposited {
+                account_id,
+                amount,
+                transaction_id,
+            }
+            | AccountEvent::MoneyWithdrawn {
+                account_id,
+                amount,
+                transaction_id,
+                ..
+            }
+            | AccountEvent::FeeCharged {
+                account_id,
+                amount,
+                transaction_id,
+                ..
+            } => Some(Self {
+                account_id: *account_id,
+                amount: *amount,
+                balance_before,
+                balance_after: account.balance,
+                version: account.version,
+                event_id: *transaction_id,
+                timestamp: Utc::now(),
+            }),
+            _ => None,
+        })
+    }
 }
 
 #[derive(Clone)]
@@ -38,6 +220,19 @@ pub struct AccountService {
     pub middleware: Arc<RequestMiddleware>,
     pub semaphore: Arc<Semaphore>,
     pub max_requests_per_second: usize,
+    event_publisher: Option<Arc<OutboxPublisher>>,
+    failed_commands: Option<Arc<FailedCommandStore>>,
+    processing_failures: Option<Arc<ProcessingFailureStore>>,
+    account_ids: AccountIdConfig,
+    account_templates: AccountTemplatesConfig,
+    initial_balance_policies: InitialBalancePolicyConfig,
+    amount_precision: AmountPrecision,
+    readiness: Arc<ReadinessGate>,
+    scaling_manager: Option<Arc<ScalingManager>>,
+    response_cache: ResponseCacheConfig,
+    event_store_latency: Option<Arc<EventStoreLatency>>,
+    authorization_audit: Arc<AuthorizationAudit>,
+    slo_tracker: Option<Arc<SloTracker>>,
 }
 
 impl AccountService {
@@ -66,6 +261,21 @@ impl AccountService {
             middleware,
             semaphore: Arc::new(Semaphore::new(max_requests_per_second)),
             max_requests_per_second,
+            event_publisher: None,
+            failed_commands: None,
+            processing_failures: None,
+            account_ids: AccountIdConfig::from_env(),
+            account_templates: AccountTemplatesConfig::from_env(),
+            initial_balance_policies: InitialBalancePolicyConfig::from_env(),
+            amount_precision: AmountPrecision::from_env(),
+            readiness: Arc::new(ReadinessGate::ready()),
+            scaling_manager: None,
+            response_cache: ResponseCacheConfig::from_env(),
+            event_store_latency: None,
+            authorization_audit: Arc::new(AuthorizationAudit::new(
+                AuthorizationAuditConfig::from_env(),
+            )),
+            slo_tracker: None,
         };
 
         // Start metrics reporter
@@ -95,40 +305,235 @@ impl AccountService {
         service
     }
 
+    /// Attaches the publisher the repository uses, so its health can be reported.
+    pub fn with_event_publisher(mut self, publisher: Arc<OutboxPublisher>) -> Self {
+        self.event_publisher = Some(publisher);
+        self
+    }
+
+    /// Records commands whose events fail to save so they can be inspected and replayed.
+    pub fn with_failed_command_store(mut self, store: Arc<FailedCommandStore>) -> Self {
+        self.failed_commands = Some(store);
+        self
+    }
+
+    /// Attaches the Kafka event processor's failure log, so it can be listed.
+    pub fn with_processing_failure_store(mut self, store: Arc<ProcessingFailureStore>) -> Self {
+        self.processing_failures = Some(store);
+        self
+    }
+
+    /// Sets the namespace ids of accounts created with an external reference are derived in.
+    pub fn with_account_ids(mut self, account_ids: AccountIdConfig) -> Self {
+        self.account_ids = account_ids;
+        self
+    }
+
+    /// Replaces the rules templates new accounts are created from.
+    pub fn with_account_templates(mut self, templates: AccountTemplatesConfig) -> Self {
+        self.account_templates = templates;
+        self
+    }
+
+    /// Replaces the initial balances accounts of each type may be opened with.
+    pub fn with_initial_balance_policies(mut self, policies: InitialBalancePolicyConfig) -> Self {
+        self.initial_balance_policies = policies;
+        self
+    }
+
+    /// Sets the scale and rounding applied to amounts before they reach the domain.
+    pub fn with_amount_precision(mut self, precision: AmountPrecision) -> Self {
+        self.amount_precision = precision;
+        self
+    }
+
+    /// Holds readiness back until the given gate opens, e.g. after cache warm-up.
+    pub fn with_readiness_gate(mut self, readiness: Arc<ReadinessGate>) -> Self {
+        self.readiness = readiness;
+        self
+    }
+
+    /// Attaches the cluster's scaling manager, so per-shard load can be reported.
+    pub fn with_scaling_manager(mut self, scaling_manager: Arc<ScalingManager>) -> Self {
+        self.scaling_manager = Some(scaling_manager);
+        self
+    }
+
+    /// The attached scaling manager, for operating on the cluster's instances.
+    pub fn scaling_manager(&self) -> Option<Arc<ScalingManager>> {
+        self.scaling_manager.clone()
+    }
+
+    /// Per-shard load, or `None` without a scaling manager.
+    pub fn shard_metrics(&self) -> Option<HashMap<ShardId, ShardMetrics>> {
+        self.scaling_manager
+            .as_ref()
+            .map(|manager| manager.shard_metrics())
+    }
+
+    /// Attaches the event store's latency histograms, so they can be reported.
+    pub fn with_event_store_latency(mut self, latency: Arc<EventStoreLatency>) -> Self {
+        self.event_store_latency = Some(latency);
+        self
+    }
+
+    pub fn event_store_latency(&self) -> Option<Arc<EventStoreLatency>> {
+        self.event_store_latency.clone()
+    }
+
+    /// Replaces the HTTP caching settings for account reads.
+    pub fn with_response_cache(mut self, response_cache: ResponseCacheConfig) -> Self {
+        self.response_cache = response_cache;
+        self
+    }
+
+    pub fn response_cache(&self) -> &ResponseCacheConfig {
+        &self.response_cache
+    }
+
+    /// Replaces the audit of authorization decisions, e.g. with one writing to a store.
+    pub fn with_authorization_audit(mut self, audit: Arc<AuthorizationAudit>) -> Self {
+        self.authorization_audit = audit;
+        self
+    }
+
+    pub fn authorization_audit(&self) -> &Arc<AuthorizationAudit> {
+        &self.authorization_audit
+    }
+
+    /// Tracks response times against the response-time SLO.
+    pub fn with_slo_tracker(mut self, tracker: Arc<SloTracker>) -> Self {
+        self.slo_tracker = Some(tracker);
+        self
+    }
+
+    pub fn slo_tracker(&self) -> Option<&Arc<SloTracker>> {
+        self.slo_tracker.as_ref()
+    }
+
+    pub fn readiness(&self) -> &Arc<ReadinessGate> {
+        &self.readiness
+    }
+
+    /// Kafka and outbox status, or `None` when events aren't published.
+    pub async fn kafka_health(&self) -> Option<KafkaHealth> {
+        match &self.event_publisher {
+            Some(publisher) => Some(publisher.health().await),
+            None => None,
+        }
+    }
+
     pub async fn create_account(
         &self,
         owner_name: String,
         initial_balance: Decimal,
     ) -> Result<Uuid, AccountError> {
-        let start_time = Instant::now();
-        let account_id = Uuid::new_v4();
+        self.create_account_for_owner(owner_name, initial_balance, None)
+            .await
+    }
 
-        // Check for duplicate command
-        if self.is_duplicate_command(account_id).await {
-            return Err(AccountError::InfrastructureError(
-                "Duplicate create account command".to_string(),
-            ));
-        }
+    /// Creates an account linked to the authenticated user that owns it.
+    pub async fn create_account_for_owner(
+        &self,
+        owner_name: String,
+        initial_balance: Decimal,
+        owner_user_id: Option<Uuid>,
+    ) -> Result<Uuid, AccountError> {
+        self.create_account_of_type(
+            owner_name,
+            initial_balance,
+            owner_user_id,
+            AccountType::default(),
+        )
+        .await
+    }
+
+    /// Creates an account of the given type, with the rules its template currently
+    /// resolves to.
+    pub async fn create_account_of_type(
+        &self,
+        owner_name: String,
+        initial_balance: Decimal,
+        owner_user_id: Option<Uuid>,
+        account_type: AccountType,
+    ) -> Result<Uuid, AccountError> {
+        self.create_account_with_reference(
+            owner_name,
+            initial_balance,
+            owner_user_id,
+            account_type,
+            None,
+        )
+        .await
+    }
+
+    /// Creates an account, or, when `external_reference` was already used by the same
+    /// owner, returns the account that reference created. The account id is derived
+    /// from the owner and reference, so the event store's unique (aggregate, version)
+    /// index rejects a second `AccountCreated` and a retried request converges on the
+    /// first account.
+    pub async fn create_account_with_reference(
+        &self,
+        owner_name: String,
+        initial_balance: Decimal,
+        owner_user_id: Option<Uuid>,
+        account_type: AccountType,
+        external_reference: Option<&str>,
+    ) -> Result<Uuid, AccountError> {
+        let start_time = Instant::now();
+        let initial_balance = self.amount_precision.normalize(initial_balance)?;
+        self.initial_balance_policies
+            .policy_for(account_type)
+            .check(initial_balance)?;
+        let account_id = match external_reference {
+            Some(reference) => {
+                let account_id = self.account_ids.id_for_reference(owner_user_id, reference);
+                if self.repository.get_by_id(account_id).await?.is_some() {
+                    info!(
+                        "Account creation with reference {} already produced account {}",
+                        reference, account_id
+                    );
+                    return Ok(account_id);
+                }
+                account_id
+            }
+            None => {
+                let account_id = Uuid::new_v4();
+                // Check for duplicate command
+                if self.is_duplicate_command(account_id).await {
+                    return Err(AccountError::InfrastructureError(
+                        "Duplicate create account command".to_string(),
+                    ));
+                }
+                account_id
+            }
+        };
 
         let command = AccountCommand::CreateAccount {
             account_id,
             owner_name: owner_name.clone(),
             initial_balance,
+            owner_user_id,
+            account_type,
+            rules: self.account_templates.rules_for(account_type),
         };
 
-        let account = Account::default();
+        let account = Account {
+            id: account_id,
+            ..Account::default()
+        };
         let events = account.handle_command(&command)?;
 
         // Save events with retry logic
-        self.repository
-            .save(&account, events.clone())
-            .await
-            .map_err(|e| {
-                self.metrics
-                    .commands_failed
-                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                AccountError::InfrastructureError(e.to_string())
-            })?;
+        if let Err(e) = self.repository.save(&account, events.clone()).await {
+            if external_reference.is_some() && is_version_conflict(&e) {
+                // A concurrent request with the same reference created it first
+                return Ok(account_id);
+            }
+            return Err(self
+                .dead_letter_command(&command, &events, account.version, "create_account", e)
+                .await);
+        }
 
         // Update projections
         let projection = AccountProjection {
@@ -138,6 +543,7 @@ impl AccountService {
             is_active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            owner_user_id,
         };
 
         if let Err(e) = self
@@ -159,202 +565,1176 @@ impl AccountService {
         }
 
         self.metrics
-            .commands_processed
+            .commands_processed
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        Ok(account_id)
+    }
+
+    pub async fn deposit_money(
+        &self,
+        account_id: Uuid,
+        amount: Decimal,
+    ) -> Result<TransactionReceipt, AccountError> {
+        self.deposit_money_with_command_id(account_id, amount, None, None)
+            .await
+    }
+
+    /// Like `deposit_money`, recording `command_id` as the cause of the saved event and
+    /// `issued_by` as the user behind it. A `command_id` already applied to the account
+    /// returns the receipt of that earlier deposit instead of applying it again.
+    pub async fn deposit_money_with_command_id(
+        &self,
+        account_id: Uuid,
+        amount: Decimal,
+        command_id: Option<Uuid>,
+        issued_by: Option<&Claims>,
+    ) -> Result<TransactionReceipt, AccountError> {
+        let start_time = Instant::now();
+        let amount = self.amount_precision.normalize(amount)?;
+
+        if let Some(command_id) = command_id {
+            if let Some(receipt) = self.prior_receipt(account_id, command_id).await? {
+                info!(
+                    "Command {} was already applied to account {}, returning its receipt",
+                    command_id, account_id
+                );
+                return Ok(receipt);
+            }
+        }
+
+        // Check for duplicate command
+        if self.is_duplicate_command(account_id).await {
+            return Err(AccountError::InfrastructureError(
+                "Duplicate deposit command".to_string(),
+            ));
+        }
+
+        let account = self
+            .repository
+            .get_by_id(account_id)
+            .await?
+            .ok_or(AccountError::NotFound)?;
+
+        let command = AccountCommand::DepositMoney { account_id, amount };
+        let events = account.handle_command(&command)?;
+        let balance_before = account.balance;
+
+        // Apply events to a copy: the account is saved at the version it was loaded at
+        let mut updated = account.clone();
+        for event in &events {
+            updated.apply_event(event);
+        }
+        let receipt = TransactionReceipt::for_events(balance_before, &updated, &events)
+            .ok_or_else(|| {
+                AccountError::InfrastructureError(
+                    "deposit produced no transaction event".to_string(),
+                )
+            })?;
+
+        // Save events with retry logic
+        let metadata = EventMetadata::for_command(command_id, issued_by);
+        if let Err(e) = self
+            .repository
+            .save_with_metadata(&account, events.clone(), metadata)
+            .await
+        {
+            if let Some(receipt) = self
+                .skipped_save_receipt(account_id, command_id, &e)
+                .await?
+            {
+                return Ok(receipt);
+            }
+            return Err(self
+                .dead_letter_command(&command, &events, account.version, "deposit_money", e)
+                .await);
+        }
+
+        // Update projections
+        if let Err(e) = self
+            .update_projections_from_events(&events, account.version)
+            .await
+        {
+            self.metrics
+                .projection_errors
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            warn!("Failed to update projections for deposit: {}", e);
+        } else {
+            self.metrics
+                .projection_updates
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+
+        self.metrics
+            .commands_processed
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        Ok(receipt)
+    }
+
+    pub async fn withdraw_money(
+        &self,
+        account_id: Uuid,
+        amount: Decimal,
+    ) -> Result<TransactionReceipt, AccountError> {
+        self.withdraw_money_with_command_id(account_id, amount, None, None)
+            .await
+    }
+
+    /// Like `withdraw_money`, recording `command_id` as the cause of the saved event and
+    /// `issued_by` as the user behind it. A `command_id` already applied to the account
+    /// returns the receipt of that earlier withdrawal instead of applying it again.
+    pub async fn withdraw_money_with_command_id(
+        &self,
+        account_id: Uuid,
+        amount: Decimal,
+        command_id: Option<Uuid>,
+        issued_by: Option<&Claims>,
+    ) -> Result<TransactionReceipt, AccountError> {
+        let start_time = Instant::now();
+        let amount = self.amount_precision.normalize(amount)?;
+
+        if let Some(command_id) = command_id {
+            if let Some(receipt) = self.prior_receipt(account_id, command_id).await? {
+                info!(
+                    "Command {} was already applied to account {}, returning its receipt",
+                    command_id, account_id
+                );
+                return Ok(receipt);
+            }
+        }
+
+        // Check for duplicate command
+        if self.is_duplicate_command(account_id).await {
+            return Err(AccountError::InfrastructureError(
+                "Duplicate withdraw command".to_string(),
+            ));
+        }
+
+        let account = self
+            .repository
+            .get_by_id(account_id)
+            .await?
+            .ok_or(AccountError::NotFound)?;
+
+        let command = AccountCommand::WithdrawMoney { account_id, amount };
+        let events = account.handle_command(&command)?;
+        let balance_before = account.balance;
+
+        // Apply events to a copy: the account is saved at the version it was loaded at
+        let mut updated = account.clone();
+        for event in &events {
+            updated.apply_event(event);
+        }
+        let receipt = TransactionReceipt::for_events(balance_before, &updated, &events)
+            .ok_or_else(|| {
+                AccountError::InfrastructureError(
+                    "withdraw produced no transaction event".to_string(),
+                )
+            })?;
+
+        // Save events with retry logic
+        let metadata = EventMetadata::for_command(command_id, issued_by);
+        if let Err(e) = self
+            .repository
+            .save_with_metadata(&account, events.clone(), metadata)
+            .await
+        {
+            if let Some(receipt) = self
+                .skipped_save_receipt(account_id, command_id, &e)
+                .await?
+            {
+                return Ok(receipt);
+            }
+            return Err(self
+                .dead_letter_command(&command, &events, account.version, "withdraw_money", e)
+                .await);
+        }
+
+        // Update projections
+        if let Err(e) = self
+            .update_projections_from_events(&events, account.version)
+            .await
+        {
+            self.metrics
+                .projection_errors
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            warn!("Failed to update projections for withdrawal: {}", e);
+        } else {
+            self.metrics
+                .projection_updates
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+
+        self.metrics
+            .commands_processed
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        Ok(receipt)
+    }
+
+    /// Charges a fee. Fees aren't held to the minimum balance or overdraft limit; a fee
+    /// that takes the balance below the minimum also records `MinimumBalanceBreached`.
+    pub async fn charge_fee(
+        &self,
+        account_id: Uuid,
+        amount: Decimal,
+        reason: String,
+    ) -> Result<TransactionReceipt, AccountError> {
+        let amount = self.amount_precision.normalize(amount)?;
+        let account = self
+            .repository
+            .get_by_id(account_id)
+            .await?
+            .ok_or(AccountError::NotFound)?;
+
+        let command = AccountCommand::ChargeFee {
+            account_id,
+            amount,
+            reason,
+        };
+        let events = account.handle_command(&command)?;
+        let mut updated = account.clone();
+        for event in &events {
+            updated.apply_event(event);
+        }
+        let receipt = TransactionReceipt::for_events(account.balance, &updated, &events)
+            .ok_or_else(|| {
+                AccountError::InfrastructureError("fee produced no transaction event".to_string())
+            })?;
+
+        if let Err(e) = self.repository.save(&account, events.clone()).await {
+            return Err(self
+                .dead_letter_command(&command, &events, account.version, "charge_fee", e)
+                .await);
+        }
+        if let Some(AccountEvent::MinimumBalanceBreached {
+            minimum, balance, ..
+        }) = events
+            .iter()
+            .find(|event| matches!(event, AccountEvent::MinimumBalanceBreached { .. }))
+        {
+            warn!(
+                "Fee took account {} to {}, below its minimum balance of {}",
+                account_id, balance, minimum
+            );
+        }
+
+        if let Err(e) = self
+            .update_projections_from_events(&events, account.version)
+            .await
+        {
+            self.metrics
+                .projection_errors
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            warn!("Failed to update projections for fee: {}", e);
+        } else {
+            self.metrics
+                .projection_updates
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+
+        self.metrics
+            .commands_processed
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        Ok(receipt)
+    }
+
+    /// When the duplicate-event guard skipped a save because `command_id` was applied
+    /// concurrently, the receipt of that application. Nothing was stored or published for
+    /// the skipped save, so it mustn't get a receipt of its own.
+    async fn skipped_save_receipt(
+        &self,
+        account_id: Uuid,
+        command_id: Option<Uuid>,
+        error: &BankingError,
+    ) -> Result<Option<TransactionReceipt>, AccountError> {
+        match (command_id, error) {
+            (
+                Some(command_id),
+                BankingError::EventStore(EventStoreError::DuplicateEventSkipped { .. }),
+            ) => {
+                info!(
+                    "Command {} was applied to account {} concurrently, returning its receipt",
+                    command_id, account_id
+                );
+                self.prior_receipt(account_id, command_id).await
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The receipt of the transaction `command_id` caused on the account, or `None` if no
+    /// event was caused by it. The events are looked up by causation id, so only a retry
+    /// replays the stream, and only up to them.
+    async fn prior_receipt(
+        &self,
+        account_id: Uuid,
+        command_id: Uuid,
+    ) -> Result<Option<TransactionReceipt>, AccountError> {
+        let matching = self
+            .repository
+            .get_events_caused_by(account_id, command_id)
+            .await?;
+        let Some(last) = matching.last() else {
+            return Ok(None);
+        };
+        // A retry: the balances come from the stream up to the command's events
+        let stored = self
+            .repository
+            .get_events_page(account_id, 0, last.version)
+            .await?;
+
+        let mut account = Account {
+            id: account_id,
+            ..Account::default()
+        };
+        let mut balance_before = account.balance;
+        let mut caused = Vec::new();
+        let mut timestamp = None;
+        for event in &stored {
+            let is_caused = event.metadata.causation_id == Some(command_id);
+            if !is_caused && !caused.is_empty() {
+                break;
+            }
+            let account_event = event.decode().map_err(AccountError::from)?;
+            if is_caused {
+                if caused.is_empty() {
+                    balance_before = account.balance;
+                }
+                caused.push(account_event.clone());
+                timestamp = Some(event.occurred_at);
+            }
+            account.apply_event(&account_event);
+        }
+        Ok(
+            TransactionReceipt::for_events(balance_before, &account, &caused).map(|receipt| {
+                TransactionReceipt {
+                    timestamp: timestamp.unwrap_or(receipt.timestamp),
+                    ..receipt
+                }
+            }),
+        )
+    }
+
+    /// Renames the account's owner. The projection is rewritten straight away so search
+    /// finds the account under its new name; the old name stays in the event stream.
+    pub async fn rename_owner(
+        &self,
+        account_id: Uuid,
+        owner_name: String,
+    ) -> Result<Account, AccountError> {
+        let account = self.repository.rename_owner(account_id, owner_name).await?;
+
+        if let Err(e) = self
+            .projections
+            .upsert_account_now(AccountProjection::from(account.clone()))
+            .await
+        {
+            self.metrics
+                .projection_errors
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            warn!("Failed to update projection for owner rename: {}", e);
+        } else {
+            self.metrics
+                .projection_updates
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+        if let Err(e) = self.cache_service.invalidate_account(account_id).await {
+            warn!(
+                "Failed to invalidate cached account {} after owner rename: {}",
+                account_id, e
+            );
+        }
+
+        self.metrics
+            .commands_processed
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        Ok(account)
+    }
+
+    /// Tags the account with `key`. Like an owner rename, the tag and the account's
+    /// projection are written to the read model straight away so filtered searches see it.
+    pub async fn set_account_metadata(
+        &self,
+        account_id: Uuid,
+        key: String,
+        value: String,
+    ) -> Result<Account, AccountError> {
+        let key = key.trim().to_string();
+        let account = self
+            .repository
+            .set_metadata(account_id, key.clone(), value.clone())
+            .await?;
+        let projected = self
+            .projections
+            .set_account_metadata(account_id, &key, &value)
+            .await;
+        self.finish_metadata_change(&account, projected).await;
+        Ok(account)
+    }
+
+    pub async fn remove_account_metadata(
+        &self,
+        account_id: Uuid,
+        key: String,
+    ) -> Result<Account, AccountError> {
+        let key = key.trim().to_string();
+        let account = self
+            .repository
+            .remove_metadata(account_id, key.clone())
+            .await?;
+        let projected = self
+            .projections
+            .remove_account_metadata(account_id, &key)
+            .await;
+        self.finish_metadata_change(&account, projected).await;
+        Ok(account)
+    }
+
+    pub async fn get_account_metadata(
+        &self,
+        account_id: Uuid,
+    ) -> Result<HashMap<String, String>, AccountError> {
+        self.projections
+            .get_account_metadata(account_id)
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))
+    }
+
+    async fn finish_metadata_change(&self, account: &Account, projected: anyhow::Result<()>) {
+        let account_id = account.id;
+        // Searches by tag join the tags onto the account's projection row, which for a
+        // new account may still be waiting in the batch writer's queue
+        let projected = match projected {
+            Ok(()) => {
+                self.projections
+                    .upsert_account_now(AccountProjection::from(account.clone()))
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = projected {
+            self.metrics
+                .projection_errors
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            warn!(
+                "Failed to update projected metadata of account {}: {}",
+                account_id, e
+            );
+        } else {
+            self.metrics
+                .projection_updates
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+        if let Err(e) = self.cache_service.invalidate_account(account_id).await {
+            warn!(
+                "Failed to invalidate cached account {} after metadata change: {}",
+                account_id, e
+            );
+        }
+
+        self.metrics
+            .commands_processed
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Runs a command's validation against the current aggregate - amount precision,
+    /// status, funds, overdraft and caps - and reports the resulting balance and
+    /// version. Nothing is saved, published or recorded as a processed command.
+    pub async fn dry_run(&self, command: &AccountCommand) -> Result<DryRunOutcome, AccountError> {
+        let command = match command {
+            AccountCommand::CreateAccount {
+                account_id,
+                owner_name,
+                initial_balance,
+                owner_user_id,
+                account_type,
+                rules,
+            } => AccountCommand::CreateAccount {
+                account_id: *account_id,
+                owner_name: owner_name.clone(),
+                initial_balance: self.amount_precision.normalize(*initial_balance)?,
+                owner_user_id: *owner_user_id,
+                account_type: *account_type,
+                rules: rules.clone(),
+            },
+            AccountCommand::DepositMoney { account_id, amount } => AccountCommand::DepositMoney {
+                account_id: *account_id,
+                amount: self.amount_precision.normalize(*amount)?,
+            },
+            AccountCommand::WithdrawMoney { account_id, amount } => {
+                AccountCommand::WithdrawMoney {
+                    account_id: *account_id,
+                    amount: self.amount_precision.normalize(*amount)?,
+                }
+            }
+            AccountCommand::ChargeFee {
+                account_id,
+                amount,
+                reason,
+            } => AccountCommand::ChargeFee {
+                account_id: *account_id,
+                amount: self.amount_precision.normalize(*amount)?,
+                reason: reason.clone(),
+            },
+            AccountCommand::CloseAccount { .. }
+            | AccountCommand::RenameOwner { .. }
+            | AccountCommand::SetMetadata { .. }
+            | AccountCommand::RemoveMetadata { .. } => command.clone(),
+        };
+
+        let mut account = match command {
+            AccountCommand::CreateAccount { .. } => Account::default(),
+            _ => self
+                .repository
+                .get_by_id(command.account_id())
+                .await?
+                .ok_or(AccountError::NotFound)?,
+        };
+        let events = account.handle_command(&command)?;
+        for event in &events {
+            account.apply_event(event);
+        }
+
+        Ok(DryRunOutcome {
+            account_id: command.account_id(),
+            balance: account.balance,
+            version: account.version,
+            events,
+        })
+    }
+
+    pub async fn get_account(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<AccountProjection>, AccountError> {
+        Ok(self.get_account_with_source(account_id).await?.0)
+    }
+
+    /// Reads an account from the cache, then the projections, replaying the event store
+    /// when the projection store can't be reached.
+    pub async fn get_account_with_source(
+        &self,
+        account_id: Uuid,
+    ) -> Result<(Option<AccountProjection>, ReadSource), AccountError> {
+        // Try cache first; a failing cache is treated as a miss
+        match self.cache_service.get_account(account_id).await {
+            Ok(Some(account)) => {
+                self.metrics
+                    .cache_hits
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                return Ok((Some(account.into()), ReadSource::Cache));
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Cache lookup failed for account {}: {}", account_id, e),
+        }
+        self.metrics
+            .cache_misses
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        // Fall back to projections
+        match self.projections.get_account(account_id).await {
+            Ok(account) => Ok((account, ReadSource::Projection)),
+            Err(e) if is_projection_unavailable(&e) => {
+                self.record_projection_fallback(account_id, &e);
+                match self.repository.get_by_id_or_last_known(account_id).await? {
+                    Some(read) if read.stale => Ok((
+                        Some(read.account.into()),
+                        ReadSource::Stale { as_of: read.as_of },
+                    )),
+                    read => Ok((read.map(|read| read.account.into()), ReadSource::EventStore)),
+                }
+            }
+            Err(e) => Err(AccountError::InfrastructureError(e.to_string())),
+        }
+    }
+
+    /// Loads many accounts at once: one projection query for all of them, then event
+    /// replay only for the ids the projection store doesn't have (or for every id, if it
+    /// can't be reached). Accounts that exist nowhere are left out of the result.
+    pub async fn get_accounts(
+        &self,
+        account_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, (AccountProjection, ReadSource)>, AccountError> {
+        let mut accounts = HashMap::with_capacity(account_ids.len());
+
+        match self.projections.get_accounts_by_ids(account_ids).await {
+            Ok(projected) => {
+                for account in projected {
+                    accounts.insert(account.id, (account, ReadSource::Projection));
+                }
+            }
+            Err(e) if is_projection_unavailable(&e) => {
+                warn!(
+                    "Projection store unavailable for bulk read of {} accounts, replaying events: {}",
+                    account_ids.len(),
+                    e
+                );
+                self.metrics
+                    .projection_fallbacks
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+            Err(e) => return Err(AccountError::InfrastructureError(e.to_string())),
+        }
+        self.metrics.bulk_projection_reads.fetch_add(
+            accounts.len() as u64,
+            std::sync::atomic::Ordering::Relaxed,
+        );
+
+        let missing: Vec<Uuid> = account_ids
+            .iter()
+            .copied()
+            .filter(|id| !accounts.contains_key(id))
+            .collect();
+        let replayed = futures::future::try_join_all(
+            missing.iter().map(|id| self.repository.get_by_id(*id)),
+        )
+        .await?;
+        for account in replayed.into_iter().flatten() {
+            self.metrics
+                .bulk_replay_reads
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            accounts.insert(account.id, (account.into(), ReadSource::EventStore));
+        }
+
+        Ok(accounts)
+    }
+
+    pub async fn get_all_accounts(&self) -> Result<Vec<AccountProjection>, AccountError> {
+        self.projections
+            .get_all_accounts()
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))
+    }
+
+    pub async fn get_accounts_by_owner(
+        &self,
+        owner_user_id: Uuid,
+    ) -> Result<Vec<AccountProjection>, AccountError> {
+        self.projections
+            .get_accounts_by_owner(owner_user_id)
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))
+    }
+
+    pub async fn search_accounts(
+        &self,
+        search: &AccountSearch,
+    ) -> Result<Vec<AccountProjection>, AccountError> {
+        self.projections
+            .search_accounts(search)
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))
+    }
+
+    pub async fn count_search_accounts(&self, search: &AccountSearch) -> Result<u64, AccountError> {
+        self.projections
+            .count_search_accounts(search)
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))
+    }
+
+    /// Snapshots an account's current state so later reads only replay newer events.
+    /// Returns the snapshot version, or `None` if the account has no events.
+    pub async fn snapshot_account(&self, account_id: Uuid) -> Result<Option<i64>, AccountError> {
+        Ok(self.repository.create_snapshot(account_id).await?)
+    }
+
+    /// Snapshots every account with more than `min_event_count` events.
+    pub async fn snapshot_accounts_above_threshold(
+        &self,
+        min_event_count: i64,
+    ) -> Result<Vec<(Uuid, i64)>, AccountError> {
+        Ok(self
+            .repository
+            .create_snapshots_above_threshold(min_event_count)
+            .await?)
+    }
+
+    /// Event count and size of an account's stream, or `None` if it has no events.
+    pub async fn account_event_stats(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<AggregateStats>, AccountError> {
+        Ok(self.repository.aggregate_stats(account_id).await?)
+    }
+
+    /// Up to `limit` of the account's stored events after `after_version`, oldest first.
+    pub async fn account_events_page(
+        &self,
+        account_id: Uuid,
+        after_version: i64,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>, AccountError> {
+        self.repository
+            .get_events_page(account_id, after_version, limit)
+            .await
+    }
+
+    /// The `limit` accounts with the most events, candidates for snapshotting or archival.
+    pub async fn largest_event_streams(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<AggregateStats>, AccountError> {
+        Ok(self.repository.top_aggregates_by_event_count(limit).await?)
+    }
+
+    /// Lists the Kafka event processor's recorded failures, most recently updated first.
+    pub async fn processing_failures(
+        &self,
+        status: Option<ProcessingFailureStatus>,
+        limit: i64,
+    ) -> Result<Vec<ProcessingFailure>, AccountError> {
+        let store = self.processing_failures.as_ref().ok_or_else(|| {
+            AccountError::InfrastructureError(
+                "Processing failure store is not configured".to_string(),
+            )
+        })?;
+        store
+            .list(status, limit)
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))
+    }
+
+    /// Compares an account's projection row with its state replayed from the event store.
+    /// With `repair`, an inconsistent row is rewritten from the replayed state. Returns
+    /// `None` if the account has no events.
+    pub async fn verify_projection(
+        &self,
+        account_id: Uuid,
+        repair: bool,
+    ) -> Result<Option<ProjectionVerification>, AccountError> {
+        let Some(account) = self.repository.get_by_id(account_id).await? else {
+            return Ok(None);
+        };
+        let projection = self
+            .projections
+            .get_account_uncached(account_id)
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?;
+
+        let mismatches = match &projection {
+            Some(projection) => projection_mismatches(&account, projection),
+            None => Vec::new(),
+        };
+        let mut verification = ProjectionVerification {
+            account_id,
+            version: account.version,
+            projection_missing: projection.is_none(),
+            mismatches,
+            repaired: false,
+        };
+        if verification.is_consistent() {
+            return Ok(Some(verification));
+        }
+
+        self.metrics
+            .projection_mismatches
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-
-        Ok(account_id)
+        warn!(
+            "Projection of account {} is inconsistent with version {}: missing={}, {:?}",
+            account_id, account.version, verification.projection_missing, verification.mismatches
+        );
+        if repair {
+            let created_at = projection.as_ref().map(|p| p.created_at);
+            let mut repaired = AccountProjection::from(account);
+            if let Some(created_at) = created_at {
+                repaired.created_at = created_at;
+            }
+            self.projections
+                .upsert_account_now(repaired)
+                .await
+                .map_err(|e| AccountError::InfrastructureError(e.to_string()))?;
+            verification.repaired = true;
+            info!("Repaired projection of account {}", account_id);
+        }
+        Ok(Some(verification))
     }
 
-    pub async fn deposit_money(
+    /// Verifies up to `sample_size` accounts picked at random from the event store.
+    pub async fn verify_projections_sample(
         &self,
-        account_id: Uuid,
-        amount: Decimal,
-    ) -> Result<(), AccountError> {
-        let start_time = Instant::now();
-
-        // Check for duplicate command
-        if self.is_duplicate_command(account_id).await {
-            return Err(AccountError::InfrastructureError(
-                "Duplicate deposit command".to_string(),
-            ));
+        sample_size: i64,
+        repair: bool,
+    ) -> Result<ProjectionVerificationSummary, AccountError> {
+        let account_ids = self.repository.sample_account_ids(sample_size).await?;
+        let mut summary = ProjectionVerificationSummary::default();
+        for account_id in account_ids {
+            let Some(verification) = self.verify_projection(account_id, repair).await? else {
+                continue;
+            };
+            summary.checked += 1;
+            if !verification.is_consistent() {
+                summary.inconsistent.push(verification);
+            }
         }
+        Ok(summary)
+    }
 
-        let mut account = self
+    /// Shows what the cache holds for an account and whether it lags the event store.
+    pub async fn inspect_cached_account(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<CachedAccountInspection>, AccountError> {
+        let Some(entry) = self
+            .cache_service
+            .inspect_account(account_id)
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+        let current_version = self
             .repository
             .get_by_id(account_id)
             .await?
-            .ok_or(AccountError::NotFound)?;
+            .map(|account| account.version);
+        let stale = current_version != Some(entry.account.version);
+        Ok(Some(CachedAccountInspection {
+            entry,
+            current_version,
+            stale,
+        }))
+    }
 
-        let command = AccountCommand::DepositMoney { account_id, amount };
-        let events = account.handle_command(&command)?;
+    /// Evicts an account from every cache tier so the next read reloads it. Returns
+    /// whether an entry was cached.
+    pub async fn evict_cached_account(&self, account_id: Uuid) -> Result<bool, AccountError> {
+        let cached = self
+            .cache_service
+            .inspect_account(account_id)
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?
+            .is_some();
+        self.cache_service
+            .invalidate_account(account_id)
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?;
+        self.broadcast_cache_invalidation(account_id, CacheInvalidationType::AccountUpdate)
+            .await;
+        Ok(cached)
+    }
 
-        // Apply events to account
-        for event in &events {
-            account.apply_event(event);
+    /// Evicts every cached account. Returns the number of accounts evicted.
+    pub async fn flush_account_cache(&self) -> Result<u64, AccountError> {
+        let evicted = self
+            .cache_service
+            .flush_accounts()
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?;
+        self.broadcast_cache_invalidation(Uuid::nil(), CacheInvalidationType::FullInvalidation)
+            .await;
+        Ok(evicted)
+    }
+
+    /// Evicts each account and caches it again as replayed from the event store, for when
+    /// cached entries are suspected to be wrong, e.g. after a faulty upcaster. The reloaded
+    /// state replaces whatever was cached, even an entry claiming a newer version. An
+    /// account failing to load doesn't stop the rest.
+    pub async fn rehydrate_cached_accounts(&self, account_ids: &[Uuid]) -> CacheRehydrationSummary {
+        let mut summary = CacheRehydrationSummary::default();
+        for &account_id in account_ids {
+            match self.rehydrate_cached_account(account_id).await {
+                Ok(true) => summary.refreshed += 1,
+                Ok(false) => summary.not_found.push(account_id),
+                Err(e) => {
+                    warn!("Failed to rehydrate cached account {}: {}", account_id, e);
+                    summary.failures.push(CacheRehydrationFailure {
+                        account_id,
+                        error: e.to_string(),
+                    });
+                }
+            }
+            self.broadcast_cache_invalidation(account_id, CacheInvalidationType::AccountUpdate)
+                .await;
         }
+        info!(
+            "Rehydrated {} cached accounts ({} not found, {} failed)",
+            summary.refreshed,
+            summary.not_found.len(),
+            summary.failures.len()
+        );
+        summary
+    }
 
-        // Save events with retry logic
-        self.repository
-            .save(&account, events.clone())
+    /// Returns whether the account exists and was cached again.
+    async fn rehydrate_cached_account(&self, account_id: Uuid) -> Result<bool, AccountError> {
+        self.cache_service
+            .invalidate_account(account_id)
             .await
-            .map_err(|e| {
-                self.metrics
-                    .commands_failed
-                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                AccountError::InfrastructureError(e.to_string())
-            })?;
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?;
+        let Some(account) = self.repository.get_by_id(account_id).await? else {
+            return Ok(false);
+        };
+        self.cache_service
+            .set_account(&account, Some(Duration::from_secs(3600)))
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?;
+        Ok(true)
+    }
 
-        // Update projections
-        if let Err(e) = self.update_projections_from_events(&events).await {
-            self.metrics
-                .projection_errors
-                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            warn!("Failed to update projections for deposit: {}", e);
-        } else {
-            self.metrics
-                .projection_updates
-                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    async fn broadcast_cache_invalidation(
+        &self,
+        account_id: Uuid,
+        invalidation_type: CacheInvalidationType,
+    ) {
+        if let Some(publisher) = &self.event_publisher {
+            if let Err(e) = publisher
+                .publish_cache_invalidation(account_id, invalidation_type, "admin eviction")
+                .await
+            {
+                warn!(
+                    "Failed to broadcast cache invalidation for account {}: {}",
+                    account_id, e
+                );
+            }
         }
+    }
 
-        self.metrics
-            .commands_processed
-            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-
-        Ok(())
+    pub async fn get_account_transactions(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Vec<TransactionProjection>, AccountError> {
+        Ok(self
+            .get_account_transactions_with_source(account_id)
+            .await?
+            .0)
     }
 
-    pub async fn withdraw_money(
+    /// Reads an account's transaction history, replaying the event store when the
+    /// projection store can't be reached.
+    pub async fn get_account_transactions_with_source(
         &self,
         account_id: Uuid,
-        amount: Decimal,
-    ) -> Result<(), AccountError> {
-        let start_time = Instant::now();
+    ) -> Result<(Vec<TransactionProjection>, ReadSource), AccountError> {
+        // Try cache first; a failing cache is treated as a miss
+        match self.cache_service.get_account_events(account_id).await {
+            Ok(Some(events)) => {
+                self.metrics
+                    .cache_hits
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                return Ok((
+                    events.into_iter().map(|e| e.into()).collect(),
+                    ReadSource::Cache,
+                ));
+            }
+            Ok(None) => {}
+            Err(e) => warn!(
+                "Cache lookup failed for transactions of account {}: {}",
+                account_id, e
+            ),
+        }
+        self.metrics
+            .cache_misses
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
-        // Check for duplicate command
-        if self.is_duplicate_command(account_id).await {
+        // Fall back to projections
+        match self.projections.get_account_transactions(account_id).await {
+            Ok(transactions) => Ok((transactions, ReadSource::Projection)),
+            Err(e) if is_projection_unavailable(&e) => {
+                self.record_projection_fallback(account_id, &e);
+                let events = self.repository.get_events(account_id).await?;
+                // Newest first, matching the projection query
+                let transactions = events
+                    .into_iter()
+                    .rev()
+                    .map(|event| {
+                        let account_event = event.decode().map_err(AccountError::from)?;
+                        Ok(TransactionProjection {
+                            id: event.id,
+                            timestamp: event.occurred_at,
+                            ..account_event.into()
+                        })
+                    })
+                    .collect::<Result<Vec<_>, AccountError>>()?;
+                Ok((transactions, ReadSource::EventStore))
+            }
+            Err(e) => Err(AccountError::InfrastructureError(e.to_string())),
+        }
+    }
+
+    /// Re-executes a recorded failed command against the account's current state and marks
+    /// it replayed once its events are saved. The command is validated again, so e.g. a
+    /// withdrawal that would now overdraw the account is rejected.
+    pub async fn replay_failed_command(&self, id: Uuid) -> Result<(), AccountError> {
+        let store = self.failed_commands.as_ref().ok_or_else(|| {
+            AccountError::InfrastructureError("Failed command store is not configured".to_string())
+        })?;
+        let entry = store
+            .get(id)
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?
+            .ok_or_else(|| {
+                AccountError::InfrastructureError(FailedCommandError::NotFound(id).to_string())
+            })?;
+        if entry.replayed_at.is_some() {
             return Err(AccountError::InfrastructureError(
-                "Duplicate withdraw command".to_string(),
+                FailedCommandError::AlreadyReplayed(id).to_string(),
             ));
         }
+        let command = entry
+            .command()
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?;
 
-        let mut account = self
-            .repository
-            .get_by_id(account_id)
-            .await?
-            .ok_or(AccountError::NotFound)?;
+        if let Err(e) = self.execute_replayed_command(&command).await {
+            if let Err(record_err) = store.record_replay_failure(id, &e.to_string()).await {
+                error!("Failed to record replay failure for {}: {}", id, record_err);
+            }
+            return Err(e);
+        }
 
-        let command = AccountCommand::WithdrawMoney { account_id, amount };
-        let events = account.handle_command(&command)?;
+        store
+            .mark_replayed(id)
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?;
+        info!(
+            "Replayed failed command {} for account {}",
+            id,
+            command.account_id()
+        );
+        Ok(())
+    }
 
-        // Apply events to account
-        for event in &events {
-            account.apply_event(event);
-        }
+    async fn execute_replayed_command(&self, command: &AccountCommand) -> Result<(), AccountError> {
+        let account = match command {
+            AccountCommand::CreateAccount { account_id, .. } => Account {
+                id: *account_id,
+                ..Account::default()
+            },
+            _ => self
+                .repository
+                .get_by_id(command.account_id())
+                .await?
+                .ok_or(AccountError::NotFound)?,
+        };
+        let events = account.handle_command(command)?;
 
-        // Save events with retry logic
         self.repository
             .save(&account, events.clone())
             .await
-            .map_err(|e| {
-                self.metrics
-                    .commands_failed
-                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                AccountError::InfrastructureError(e.to_string())
-            })?;
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?;
 
-        // Update projections
-        if let Err(e) = self.update_projections_from_events(&events).await {
-            self.metrics
-                .projection_errors
-                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            warn!("Failed to update projections for withdrawal: {}", e);
+        let projection_result = if let AccountCommand::CreateAccount { .. } = command {
+            let mut created = account;
+            for event in &events {
+                created.apply_event(event);
+            }
+            self.projections
+                .upsert_accounts_batch(vec![created.into()])
+                .await
+                .map_err(|e| AccountError::InfrastructureError(e.to_string()))
         } else {
+            self.update_projections_from_events(&events, account.version).await
+        };
+        if let Err(e) = projection_result {
             self.metrics
-                .projection_updates
+                .projection_errors
                 .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            warn!("Failed to update projections for replayed command: {}", e);
         }
 
         self.metrics
             .commands_processed
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-
         Ok(())
     }
 
-    pub async fn get_account(
+    /// Counts a command whose events couldn't be saved and, when a failed command store is
+    /// configured, records it there. Returns the error for the caller.
+    async fn dead_letter_command(
         &self,
-        account_id: Uuid,
-    ) -> Result<Option<AccountProjection>, AccountError> {
-        // Try cache first
-        if let Some(account) = self
-            .cache_service
-            .get_account(account_id)
-            .await
-            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?
-        {
-            self.metrics
-                .cache_hits
-                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            return Ok(Some(AccountProjection {
-                id: account.id,
-                owner_name: account.owner_name,
-                balance: account.balance,
-                is_active: account.is_active,
-                created_at: Utc::now(),
-                updated_at: Utc::now(),
-            }));
-        }
+        command: &AccountCommand,
+        events: &[AccountEvent],
+        expected_version: i64,
+        operation: &str,
+        error: BankingError,
+    ) -> AccountError {
         self.metrics
-            .cache_misses
+            .commands_failed
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
-        // Fall back to projections
-        self.projections
-            .get_account(account_id)
-            .await
-            .map_err(|e| AccountError::InfrastructureError(e.to_string()))
-    }
-
-    pub async fn get_all_accounts(&self) -> Result<Vec<AccountProjection>, AccountError> {
-        self.projections
-            .get_all_accounts()
-            .await
-            .map_err(|e| AccountError::InfrastructureError(e.to_string()))
-    }
-
-    pub async fn get_account_transactions(
-        &self,
-        account_id: Uuid,
-    ) -> Result<Vec<TransactionProjection>, AccountError> {
-        // Try cache first
-        if let Some(events) = self
-            .cache_service
-            .get_account_events(account_id)
+        // A full produce queue refuses the write before anything is saved, so the client can
+        // retry it; a create for an existing account would fail again if replayed
+        if matches!(
+            error,
+            BankingError::Outbox(OutboxError::QueueFull { .. })
+                | BankingError::EventStore(EventStoreError::AggregateAlreadyExists { .. })
+        ) {
+            return error.into();
+        }
+        let Some(store) = &self.failed_commands else {
+            return error.into();
+        };
+        let context = serde_json::json!({
+            "operation": operation,
+            "event_count": events.len(),
+        });
+        match store
+            .record(command, events, expected_version, &error.to_string(), context)
             .await
-            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?
         {
-            self.metrics
-                .cache_hits
-                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            return Ok(events.into_iter().map(|e| e.into()).collect());
+            Ok(id) => {
+                warn!(
+                    "Recorded failed {} command for account {} as {}: {}",
+                    operation,
+                    command.account_id(),
+                    id,
+                    error
+                );
+                AccountError::InfrastructureError(format!(
+                    "{} (recorded as failed command {})",
+                    error, id
+                ))
+            }
+            Err(e) => {
+                error!(
+                    "Failed to record failed {} command for account {}: {}",
+                    operation,
+                    command.account_id(),
+                    e
+                );
+                error.into()
+            }
         }
+    }
+
+    fn record_projection_fallback(&self, account_id: Uuid, error: &anyhow::Error) {
         self.metrics
-            .cache_misses
+            .projection_fallbacks
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-
-        // Fall back to projections
-        self.projections
-            .get_account_transactions(account_id)
-            .await
-            .map_err(|e| AccountError::InfrastructureError(e.to_string()))
+        warn!(
+            "Projection store unavailable, replaying events for account {}: {}",
+            account_id, error
+        );
     }
 
+    /// Applies events saved after `from_version` to the account projections, at their
+    /// versions so the consumer skips them when it gets them, then records them as
+    /// transactions and refreshes the cached accounts.
     async fn update_projections_from_events(
         &self,
         events: &[crate::domain::AccountEvent],
+        from_version: i64,
     ) -> Result<(), AccountError> {
         let start_time = Instant::now();
 
+        let projected: Vec<ProjectionEvent> = events
+            .iter()
+            .enumerate()
+            .map(|(i, event)| ProjectionEvent {
+                position: None,
+                version: Some(from_version + i as i64 + 1),
+                event: event.clone(),
+            })
+            .collect();
+        self.projections
+            .apply_events_batch(PROJECTION_CHECKPOINT, &projected)
+            .await
+            .map_err(|e| AccountError::InfrastructureError(e.to_string()))?;
+
         for event in events {
             let transaction = TransactionProjection {
                 id: Uuid::new_v4(),
@@ -362,6 +1742,7 @@ impl AccountService {
                 amount: match event {
                     AccountEvent::MoneyDeposited { amount, .. } => amount.clone(),
                     AccountEvent::MoneyWithdrawn { amount, .. } => amount.clone(),
+                    AccountEvent::FeeCharged { amount, .. } => *amount,
                     _ => Decimal::ZERO,
                 },
                 transaction_type: event.event_type().to_string(),
@@ -391,6 +1772,8 @@ impl AccountService {
                             balance: account.balance,
                             is_active: account.is_active,
                             version: 0, // Default version
+                            owner_user_id: account.owner_user_id,
+                            ..Account::default()
                         };
                         self.cache_service
                             .set_account(&account, Some(Duration::from_secs(3600)))
@@ -426,6 +1809,10 @@ impl AccountService {
     pub fn get_metrics(&self) -> &ServiceMetrics {
         &self.metrics
     }
+
+    pub fn cache_metrics(&self) -> &CacheMetrics {
+        self.cache_service.get_metrics()
+    }
 }
 
 impl From<AccountEvent> for TransactionProjection {
@@ -436,6 +1823,7 @@ impl From<AccountEvent> for TransactionProjection {
             amount: match event {
                 AccountEvent::MoneyDeposited { amount, .. } => amount.clone(),
                 AccountEvent::MoneyWithdrawn { amount, .. } => amount.clone(),
+                AccountEvent::FeeCharged { amount, .. } => amount,
                 _ => Decimal::ZERO,
             },
             transaction_type: event.event_type().to_string(),
@@ -444,6 +1832,88 @@ impl From<AccountEvent> for TransactionProjection {
     }
 }
 
+impl From<Account> for AccountProjection {
+    fn from(account: Account) -> Self {
+        AccountProjection {
+            id: account.id,
+            owner_name: account.owner_name,
+            balance: account.balance,
+            is_active: account.is_active,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            owner_user_id: account.owner_user_id,
+        }
+    }
+}
+
+fn projection_mismatches(
+    account: &Account,
+    projection: &AccountProjection,
+) -> Vec<ProjectionMismatch> {
+    let mut mismatches = Vec::new();
+    let mut compare = |field, expected: String, actual: String| {
+        if expected != actual {
+            mismatches.push(ProjectionMismatch {
+                field,
+                expected,
+                actual,
+            });
+        }
+    };
+    compare(
+        "balance",
+        account.balance.normalize().to_string(),
+        projection.balance.normalize().to_string(),
+    );
+    compare(
+        "is_active",
+        account.is_active.to_string(),
+        projection.is_active.to_string(),
+    );
+    compare(
+        "owner_name",
+        account.owner_name.clone(),
+        projection.owner_name.clone(),
+    );
+    compare(
+        "owner_user_id",
+        format!("{:?}", account.owner_user_id),
+        format!("{:?}", projection.owner_user_id),
+    );
+    mismatches
+}
+
+/// Whether a projection store error means the read database couldn't be reached, as
+/// opposed to e.g. a failing query.
+fn is_projection_unavailable(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<sqlx::Error>(),
+        Some(
+            sqlx::Error::Io(_)
+                | sqlx::Error::Tls(_)
+                | sqlx::Error::PoolTimedOut
+                | sqlx::Error::PoolClosed
+                | sqlx::Error::WorkerCrashed
+        )
+    )
+}
+
+/// The id an account created by `owner_user_id` with `reference` gets in the default
+/// namespace.
+pub fn account_id_for_reference(owner_user_id: Option<Uuid>, reference: &str) -> Uuid {
+    AccountIdConfig::default().id_for_reference(owner_user_id, reference)
+}
+
+fn is_version_conflict(error: &BankingError) -> bool {
+    matches!(
+        error,
+        BankingError::EventStore(EventStoreError::OptimisticConcurrencyConflict { .. })
+            | BankingError::EventStore(EventStoreError::AggregateAlreadyExists { .. })
+            | BankingError::Repository(RepositoryError::VersionConflict { .. })
+            | BankingError::Account(AccountError::VersionConflict { .. })
+    )
+}
+
 // Add Default implementation for AccountService
 impl Default for AccountService {
     fn default() -> Self {
@@ -461,6 +1931,7 @@ impl Default for AccountService {
 mod tests {
     use super::*;
     use crate::domain::AccountError;
+    use crate::infrastructure::event_store::StoredEvent;
     use crate::infrastructure::repository::AccountRepositoryTrait;
     use crate::infrastructure::{AccountRepository, EventStore, EventStoreConfig, ProjectionStore};
     use async_trait::async_trait;
@@ -475,13 +1946,22 @@ mod tests {
 
     #[async_trait]
     impl AccountRepositoryTrait for ManualMockAccountRepository {
-        async fn save(&self, _account: &Account, _events: Vec<AccountEvent>) -> Result<()> {
+        async fn save(&self, _account: &Account, _events: Vec<AccountEvent>) -> Result<(), BankingError> {
             match &*self.save_batched_result {
                 Ok(_) => Ok(()),
-                Err(e) => Err(anyhow::anyhow!(e.to_string())),
+                Err(e) => Err(e.clone().into()),
             }
         }
 
+        async fn save_with_metadata(
+            &self,
+            account: &Account,
+            events: Vec<AccountEvent>,
+            _metadata: EventMetadata,
+        ) -> Result<(), BankingError> {
+            self.save(account, events).await
+        }
+
         async fn get_by_id(&self, _id: Uuid) -> Result<Option<Account>, AccountError> {
             match &*self.get_by_id_result {
                 Ok(account) => Ok(account.clone()),
@@ -494,10 +1974,10 @@ mod tests {
             _account_id: Uuid,
             _expected_version: i64,
             _events: Vec<AccountEvent>,
-        ) -> Result<()> {
+        ) -> Result<(), BankingError> {
             match &*self.save_batched_result {
                 Ok(_) => Ok(()),
-                Err(e) => Err(anyhow::anyhow!(e.to_string())),
+                Err(e) => Err(e.clone().into()),
             }
         }
 
@@ -505,14 +1985,14 @@ mod tests {
             &self,
             _account: &Account,
             _events: Vec<AccountEvent>,
-        ) -> Result<()> {
+        ) -> Result<(), BankingError> {
             match &*self.save_batched_result {
                 Ok(_) => Ok(()),
-                Err(e) => Err(anyhow::anyhow!(e.to_string())),
+                Err(e) => Err(e.clone().into()),
             }
         }
 
-        async fn flush_all(&self) -> Result<()> {
+        async fn flush_all(&self) -> Result<(), BankingError> {
             Ok(())
         }
 
@@ -522,21 +2002,92 @@ mod tests {
             &self,
             _owner_name: String,
             _initial_balance: Decimal,
-        ) -> Result<Account> {
+        ) -> Result<Account, BankingError> {
             Ok(Account::default())
         }
 
-        async fn get_account(&self, _account_id: Uuid) -> Result<Option<Account>> {
+        async fn get_account(&self, _account_id: Uuid) -> Result<Option<Account>, BankingError> {
             Ok(None)
         }
 
-        async fn deposit_money(&self, _account_id: Uuid, _amount: Decimal) -> Result<Account> {
+        async fn deposit_money(&self, _account_id: Uuid, _amount: Decimal) -> Result<Account, BankingError> {
+            Ok(Account::default())
+        }
+
+        async fn apply_command(
+            &self,
+            _account_id: Uuid,
+            _command: &AccountCommand,
+            _expected_version: Option<i64>,
+        ) -> Result<Account, BankingError> {
+            Ok(Account::default())
+        }
+
+        async fn withdraw_money(&self, _account_id: Uuid, _amount: Decimal) -> Result<Account, BankingError> {
+            Ok(Account::default())
+        }
+
+        async fn rename_owner(&self, _account_id: Uuid, _owner_name: String) -> Result<Account, BankingError> {
+            Ok(Account::default())
+        }
+
+        async fn set_metadata(&self, _account_id: Uuid, _key: String, _value: String) -> Result<Account, BankingError> {
             Ok(Account::default())
         }
 
-        async fn withdraw_money(&self, _account_id: Uuid, _amount: Decimal) -> Result<Account> {
+        async fn remove_metadata(&self, _account_id: Uuid, _key: String) -> Result<Account, BankingError> {
             Ok(Account::default())
         }
+
+        async fn create_snapshot(&self, _account_id: Uuid) -> Result<Option<i64>, BankingError> {
+            Ok(None)
+        }
+
+        async fn get_events(&self, _id: Uuid) -> Result<Vec<StoredEvent>, AccountError> {
+            Ok(Vec::new())
+        }
+
+        async fn get_events_page(
+            &self,
+            _id: Uuid,
+            _after_version: i64,
+            _limit: i64,
+        ) -> Result<Vec<StoredEvent>, AccountError> {
+            Ok(Vec::new())
+        }
+
+        async fn get_events_caused_by(
+            &self,
+            _id: Uuid,
+            _causation_id: Uuid,
+        ) -> Result<Vec<StoredEvent>, AccountError> {
+            Ok(Vec::new())
+        }
+
+        async fn create_snapshots_above_threshold(
+            &self,
+            _min_event_count: i64,
+        ) -> Result<Vec<(Uuid, i64)>, BankingError> {
+            Ok(Vec::new())
+        }
+
+        async fn aggregate_stats(
+            &self,
+            _account_id: Uuid,
+        ) -> Result<Option<AggregateStats>, BankingError> {
+            Ok(None)
+        }
+
+        async fn top_aggregates_by_event_count(
+            &self,
+            _limit: i64,
+        ) -> Result<Vec<AggregateStats>, BankingError> {
+            Ok(Vec::new())
+        }
+
+        async fn sample_account_ids(&self, _limit: i64) -> Result<Vec<Uuid>, BankingError> {
+            Ok(Vec::new())
+        }
     }
 
     fn account_service_with_mock_repo(
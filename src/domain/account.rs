@@ -1,6 +1,11 @@
-use crate::domain::{AccountCommand, AccountEvent};
+use crate::domain::{
+    AccountCommand, AccountEvent, AccountRules, AccountType, InitialBalanceViolation,
+    VelocityViolation,
+};
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -11,8 +16,30 @@ pub struct Account {
     pub balance: Decimal,
     pub is_active: bool,
     pub version: i64,
+    /// The authenticated user that owns this account, if any.
+    #[serde(default)]
+    pub owner_user_id: Option<Uuid>,
+    #[serde(default)]
+    pub account_type: AccountType,
+    /// Limits resolved for this account when it was created.
+    #[serde(default)]
+    pub rules: AccountRules,
+    /// Free-form tags set by operations, e.g. `segment` or `risk_tier`.
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    /// Withdrawals still inside the velocity window, as (time, amount). Only kept
+    /// when the rules cap velocity.
+    #[serde(default)]
+    pub recent_withdrawals: Vec<(DateTime<Utc>, Decimal)>,
 }
 
+/// Longest metadata key, in bytes.
+pub const MAX_METADATA_KEY_LEN: usize = 64;
+/// Longest metadata value, in bytes.
+pub const MAX_METADATA_VALUE_LEN: usize = 256;
+/// Most metadata entries one account may carry.
+pub const MAX_METADATA_ENTRIES: usize = 32;
+
 #[derive(Debug, thiserror::Error, Clone)]
 pub enum AccountError {
     #[error("Account not found")]
@@ -22,16 +49,45 @@ pub enum AccountError {
         available: Decimal,
         requested: Decimal,
     },
+    #[error("Withdrawal of {requested} would take the balance below the minimum of {minimum}")]
+    BelowMinimumBalance {
+        minimum: Decimal,
+        requested: Decimal,
+    },
     #[error("Account is closed")]
     AccountClosed,
     #[error("Invalid amount: {0}")]
     InvalidAmount(Decimal),
     #[error("Event deserialization error: {0}")]
     EventDeserializationError(String),
+    #[error("Stored data does not match the expected schema: {0}")]
+    SchemaError(String),
     #[error("Infrastructure error: {0}")]
     InfrastructureError(String),
     #[error("Version conflict: expected {expected}, found {actual}")]
     VersionConflict { expected: i64, actual: i64 },
+    #[error("Amount {amount} has more than {scale} decimal places")]
+    ExcessPrecision { amount: Decimal, scale: u32 },
+    #[error("Limit exceeded: limit {limit}, requested {requested}")]
+    LimitExceeded { limit: Decimal, requested: Decimal },
+    #[error("Withdrawal velocity limit exceeded over {window_secs}s: {violation}")]
+    VelocityLimitExceeded {
+        window_secs: u64,
+        violation: VelocityViolation,
+    },
+    #[error("Account already exists")]
+    AlreadyExists,
+    #[error("Initial balance {initial_balance} not allowed: {violation}")]
+    InitialBalanceNotAllowed {
+        initial_balance: Decimal,
+        violation: InitialBalanceViolation,
+    },
+    #[error("Invalid owner name: {0:?}")]
+    InvalidOwnerName(String),
+    #[error("Invalid metadata: {0}")]
+    InvalidMetadata(String),
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
 }
 
 impl Account {
@@ -50,6 +106,11 @@ impl Account {
             balance: initial_balance,
             is_active: true,
             version: 0,
+            owner_user_id: None,
+            account_type: AccountType::default(),
+            rules: AccountRules::default(),
+            metadata: HashMap::new(),
+            recent_withdrawals: Vec::new(),
         })
     }
 
@@ -58,21 +119,49 @@ impl Account {
             AccountEvent::AccountCreated {
                 owner_name,
                 initial_balance,
+                owner_user_id,
+                account_type,
+                rules,
                 ..
             } => {
                 self.owner_name = owner_name.clone();
                 self.balance = *initial_balance;
                 self.is_active = true;
+                self.owner_user_id = *owner_user_id;
+                self.account_type = *account_type;
+                self.rules = rules.clone();
             }
             AccountEvent::MoneyDeposited { amount, .. } => {
                 self.balance += amount;
             }
-            AccountEvent::MoneyWithdrawn { amount, .. } => {
+            AccountEvent::MoneyWithdrawn {
+                amount,
+                withdrawn_at,
+                ..
+            } => {
                 self.balance -= amount;
+                if let (Some(caps), Some(at)) = (&self.rules.withdrawal_velocity, withdrawn_at) {
+                    let since = *at - caps.window();
+                    self.recent_withdrawals.retain(|(time, _)| *time > since);
+                    self.recent_withdrawals.push((*at, *amount));
+                }
             }
             AccountEvent::AccountClosed { .. } => {
                 self.is_active = false;
             }
+            AccountEvent::OwnerNameChanged { new, .. } => {
+                self.owner_name = new.clone();
+            }
+            AccountEvent::MetadataSet { key, value, .. } => {
+                self.metadata.insert(key.clone(), value.clone());
+            }
+            AccountEvent::MetadataRemoved { key, .. } => {
+                self.metadata.remove(key);
+            }
+            AccountEvent::FeeCharged { amount, .. } => {
+                self.balance -= amount;
+            }
+            AccountEvent::MinimumBalanceBreached { .. } => {}
         }
         self.version += 1;
     }
@@ -90,6 +179,9 @@ impl Account {
                 account_id,
                 owner_name,
                 initial_balance,
+                owner_user_id,
+                account_type,
+                rules,
             } => {
                 if *initial_balance < Decimal::ZERO {
                     return Err(AccountError::InvalidAmount(*initial_balance));
@@ -98,12 +190,16 @@ impl Account {
                     account_id: *account_id,
                     owner_name: owner_name.clone(),
                     initial_balance: *initial_balance,
+                    owner_user_id: *owner_user_id,
+                    account_type: *account_type,
+                    rules: rules.clone(),
                 }])
             }
             AccountCommand::DepositMoney { account_id, amount } => {
                 if *amount <= Decimal::ZERO {
                     return Err(AccountError::InvalidAmount(*amount));
                 }
+                check_cap(self.rules.max_deposit_amount, *amount)?;
                 Ok(vec![AccountEvent::MoneyDeposited {
                     account_id: *account_id,
                     amount: *amount,
@@ -114,9 +210,24 @@ impl Account {
                 if *amount <= Decimal::ZERO {
                     return Err(AccountError::InvalidAmount(*amount));
                 }
-                if self.balance < *amount {
+                check_cap(self.rules.max_withdrawal_amount, *amount)?;
+                let now = Utc::now();
+                if let Some(caps) = &self.rules.withdrawal_velocity {
+                    caps.check(&self.recent_withdrawals, now, *amount)?;
+                }
+                let available = self.available_balance();
+                if available < *amount {
+                    // Funds are there, but only the minimum balance would cover it
+                    if self.rules.min_balance > Decimal::ZERO
+                        && self.balance + self.rules.overdraft_limit >= *amount
+                    {
+                        return Err(AccountError::BelowMinimumBalance {
+                            minimum: self.rules.min_balance,
+                            requested: *amount,
+                        });
+                    }
                     return Err(AccountError::InsufficientFunds {
-                        available: self.balance,
+                        available,
                         requested: *amount,
                     });
                 }
@@ -124,6 +235,7 @@ impl Account {
                     account_id: *account_id,
                     amount: *amount,
                     transaction_id: Uuid::new_v4(),
+                    withdrawn_at: Some(now),
                 }])
             }
             AccountCommand::CloseAccount { account_id, reason } => {
@@ -132,8 +244,125 @@ impl Account {
                     reason: reason.clone(),
                 }])
             }
+            AccountCommand::RenameOwner {
+                account_id,
+                owner_name,
+            } => {
+                let owner_name = owner_name.trim();
+                if owner_name.is_empty() {
+                    return Err(AccountError::InvalidOwnerName(owner_name.to_string()));
+                }
+                // Renaming to the current name records nothing
+                if owner_name == self.owner_name {
+                    return Ok(Vec::new());
+                }
+                Ok(vec![AccountEvent::OwnerNameChanged {
+                    account_id: *account_id,
+                    old: self.owner_name.clone(),
+                    new: owner_name.to_string(),
+                }])
+            }
+            AccountCommand::SetMetadata {
+                account_id,
+                key,
+                value,
+            } => {
+                let key = validate_metadata_key(key)?;
+                if value.len() > MAX_METADATA_VALUE_LEN {
+                    return Err(AccountError::InvalidMetadata(format!(
+                        "value of {} is longer than {} bytes",
+                        key, MAX_METADATA_VALUE_LEN
+                    )));
+                }
+                match self.metadata.get(key) {
+                    // Setting the current value records nothing
+                    Some(current) if current == value => return Ok(Vec::new()),
+                    None if self.metadata.len() >= MAX_METADATA_ENTRIES => {
+                        return Err(AccountError::InvalidMetadata(format!(
+                            "accounts carry at most {} entries",
+                            MAX_METADATA_ENTRIES
+                        )));
+                    }
+                    _ => {}
+                }
+                Ok(vec![AccountEvent::MetadataSet {
+                    account_id: *account_id,
+                    key: key.to_string(),
+                    value: value.clone(),
+                }])
+            }
+            AccountCommand::RemoveMetadata { account_id, key } => {
+                let key = validate_metadata_key(key)?;
+                if !self.metadata.contains_key(key) {
+                    return Ok(Vec::new());
+                }
+                Ok(vec![AccountEvent::MetadataRemoved {
+                    account_id: *account_id,
+                    key: key.to_string(),
+                }])
+            }
+            AccountCommand::ChargeFee {
+                account_id,
+                amount,
+                reason,
+            } => {
+                if *amount <= Decimal::ZERO {
+                    return Err(AccountError::InvalidAmount(*amount));
+                }
+                let mut events = vec![AccountEvent::FeeCharged {
+                    account_id: *account_id,
+                    amount: *amount,
+                    reason: reason.clone(),
+                    transaction_id: Uuid::new_v4(),
+                }];
+                // Warn only when this fee crosses the minimum, not on every fee after it
+                let minimum = self.rules.min_balance;
+                let balance = self.balance - *amount;
+                if self.balance >= minimum && balance < minimum {
+                    events.push(AccountEvent::MinimumBalanceBreached {
+                        account_id: *account_id,
+                        minimum,
+                        balance,
+                    });
+                }
+                Ok(events)
+            }
         }
     }
+
+    /// How much can be withdrawn without breaching the minimum balance and overdraft.
+    pub fn available_balance(&self) -> Decimal {
+        (self.balance - self.rules.balance_floor()).max(Decimal::ZERO)
+    }
+}
+
+/// Keys are trimmed and limited to ASCII letters, digits, `_`, `-` and `.`, so they can
+/// be used in query strings as they are.
+fn validate_metadata_key(key: &str) -> Result<&str, AccountError> {
+    let key = key.trim();
+    if key.is_empty() || key.len() > MAX_METADATA_KEY_LEN {
+        return Err(AccountError::InvalidMetadata(format!(
+            "key must be 1 to {} bytes",
+            MAX_METADATA_KEY_LEN
+        )));
+    }
+    if !key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(AccountError::InvalidMetadata(format!(
+            "key {:?} may only contain letters, digits, '_', '-' and '.'",
+            key
+        )));
+    }
+    Ok(key)
+}
+
+fn check_cap(limit: Option<Decimal>, requested: Decimal) -> Result<(), AccountError> {
+    match limit {
+        Some(limit) if requested > limit => Err(AccountError::LimitExceeded { limit, requested }),
+        _ => Ok(()),
+    }
 }
 
 impl Default for Account {
@@ -144,6 +373,11 @@ impl Default for Account {
             balance: Decimal::ZERO,
             is_active: false,
             version: 0,
+            owner_user_id: None,
+            account_type: AccountType::default(),
+            rules: AccountRules::default(),
+            metadata: HashMap::new(),
+            recent_withdrawals: Vec::new(),
         }
     }
 }
@@ -0,0 +1,221 @@
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::domain::AccountError;
+
+/// The product an account was opened as. Decides which rules template it starts with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountType {
+    #[default]
+    Checking,
+    Savings,
+    Credit,
+}
+
+impl AccountType {
+    pub const ALL: [AccountType; 3] = [
+        AccountType::Checking,
+        AccountType::Savings,
+        AccountType::Credit,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountType::Checking => "checking",
+            AccountType::Savings => "savings",
+            AccountType::Credit => "credit",
+        }
+    }
+
+    /// The built-in rules for this type, before any configured overrides.
+    pub fn default_rules(&self) -> AccountRules {
+        match self {
+            AccountType::Checking => AccountRules {
+                overdraft_limit: Decimal::new(500, 0),
+                min_balance: Decimal::ZERO,
+                max_withdrawal_amount: Some(Decimal::new(10_000, 0)),
+                max_deposit_amount: None,
+                withdrawal_velocity: None,
+            },
+            AccountType::Savings => AccountRules {
+                overdraft_limit: Decimal::ZERO,
+                min_balance: Decimal::ZERO,
+                max_withdrawal_amount: Some(Decimal::new(5_000, 0)),
+                max_deposit_amount: None,
+                withdrawal_velocity: Some(VelocityCaps {
+                    window_secs: 30 * 24 * 60 * 60,
+                    max_withdrawals: Some(6),
+                    max_amount: None,
+                }),
+            },
+            AccountType::Credit => AccountRules {
+                overdraft_limit: Decimal::new(5_000, 0),
+                min_balance: Decimal::ZERO,
+                max_withdrawal_amount: Some(Decimal::new(2_500, 0)),
+                max_deposit_amount: None,
+                withdrawal_velocity: Some(VelocityCaps {
+                    window_secs: 24 * 60 * 60,
+                    max_withdrawals: None,
+                    max_amount: Some(Decimal::new(5_000, 0)),
+                }),
+            },
+        }
+    }
+}
+
+impl FromStr for AccountType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "checking" => Ok(AccountType::Checking),
+            "savings" => Ok(AccountType::Savings),
+            "credit" => Ok(AccountType::Credit),
+            other => Err(format!("Unknown account type: {}", other)),
+        }
+    }
+}
+
+/// Limits an account is held to. Resolved from its type's template when the account is
+/// created and recorded on `AccountCreated`, so later template changes don't alter
+/// existing accounts on replay.
+///
+/// The default is the behaviour accounts had before types existed: no overdraft and no
+/// caps.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountRules {
+    /// How far below `min_balance` a withdrawal may take the balance.
+    pub overdraft_limit: Decimal,
+    pub min_balance: Decimal,
+    /// Largest single withdrawal allowed.
+    #[serde(default)]
+    pub max_withdrawal_amount: Option<Decimal>,
+    /// Largest single deposit allowed.
+    #[serde(default)]
+    pub max_deposit_amount: Option<Decimal>,
+    /// Limits on how much may be withdrawn over a rolling window.
+    #[serde(default)]
+    pub withdrawal_velocity: Option<VelocityCaps>,
+}
+
+impl AccountRules {
+    /// The lowest balance a withdrawal may leave behind.
+    pub fn balance_floor(&self) -> Decimal {
+        self.min_balance - self.overdraft_limit
+    }
+}
+
+/// Caps on withdrawals over the last `window_secs`, counting the one being made.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VelocityCaps {
+    pub window_secs: u64,
+    /// Most withdrawals allowed in the window.
+    #[serde(default)]
+    pub max_withdrawals: Option<u32>,
+    /// Most that may be withdrawn in total in the window.
+    #[serde(default)]
+    pub max_amount: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Error)]
+pub enum VelocityViolation {
+    #[error("more than {max} withdrawals")]
+    TooManyWithdrawals { max: u32 },
+    #[error("{total} withdrawn, above the maximum of {max}")]
+    AmountAboveMaximum { max: Decimal, total: Decimal },
+}
+
+impl VelocityCaps {
+    pub fn window(&self) -> Duration {
+        Duration::seconds(self.window_secs.try_into().unwrap_or(i64::MAX))
+    }
+
+    /// Checks a withdrawal of `amount` at `now` against the withdrawals already made,
+    /// given as (time, amount) pairs.
+    pub fn check(
+        &self,
+        recent: &[(DateTime<Utc>, Decimal)],
+        now: DateTime<Utc>,
+        amount: Decimal,
+    ) -> Result<(), AccountError> {
+        let since = now - self.window();
+        let (count, withdrawn) = recent
+            .iter()
+            .filter(|(at, _)| *at > since)
+            .fold((0u32, Decimal::ZERO), |(count, total), (_, amount)| {
+                (count + 1, total + amount)
+            });
+        let total = withdrawn + amount;
+        let violation = if let Some(max) = self.max_withdrawals.filter(|max| count >= *max) {
+            Some(VelocityViolation::TooManyWithdrawals { max })
+        } else {
+            self.max_amount
+                .filter(|max| total > *max)
+                .map(|max| VelocityViolation::AmountAboveMaximum { max, total })
+        };
+        match violation {
+            Some(violation) => Err(AccountError::VelocityLimitExceeded {
+                window_secs: self.window_secs,
+                violation,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Initial balances a new account may be opened with. Checked when the account is
+/// created only, so unlike `AccountRules` it isn't recorded on the account.
+///
+/// The default allows any balance the domain does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitialBalancePolicy {
+    pub min: Option<Decimal>,
+    pub max: Option<Decimal>,
+    /// When false, accounts must be opened with a zero balance.
+    pub allow_nonzero: bool,
+}
+
+impl Default for InitialBalancePolicy {
+    fn default() -> Self {
+        Self {
+            min: None,
+            max: None,
+            allow_nonzero: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Error)]
+pub enum InitialBalanceViolation {
+    #[error("below the minimum of {min}")]
+    BelowMinimum { min: Decimal },
+    #[error("above the maximum of {max}")]
+    AboveMaximum { max: Decimal },
+    #[error("accounts must be opened with a zero balance")]
+    NonzeroNotAllowed,
+}
+
+impl InitialBalancePolicy {
+    pub fn check(&self, initial_balance: Decimal) -> Result<(), AccountError> {
+        let violation = if !self.allow_nonzero && !initial_balance.is_zero() {
+            Some(InitialBalanceViolation::NonzeroNotAllowed)
+        } else if let Some(min) = self.min.filter(|min| initial_balance < *min) {
+            Some(InitialBalanceViolation::BelowMinimum { min })
+        } else {
+            self.max
+                .filter(|max| initial_balance > *max)
+                .map(|max| InitialBalanceViolation::AboveMaximum { max })
+        };
+        match violation {
+            Some(violation) => Err(AccountError::InitialBalanceNotAllowed {
+                initial_balance,
+                violation,
+            }),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::domain::{AccountRules, AccountType};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use rust_decimal::Decimal;
@@ -9,6 +10,12 @@ pub enum AccountCommand {
         account_id: Uuid,
         owner_name: String,
         initial_balance: Decimal,
+        #[serde(default)]
+        owner_user_id: Option<Uuid>,
+        #[serde(default)]
+        account_type: AccountType,
+        #[serde(default)]
+        rules: AccountRules,
     },
     DepositMoney {
         account_id: Uuid,
@@ -22,4 +29,36 @@ pub enum AccountCommand {
         account_id: Uuid,
         reason: String,
     },
-}
\ No newline at end of file
+    RenameOwner {
+        account_id: Uuid,
+        owner_name: String,
+    },
+    SetMetadata {
+        account_id: Uuid,
+        key: String,
+        value: String,
+    },
+    RemoveMetadata {
+        account_id: Uuid,
+        key: String,
+    },
+    ChargeFee {
+        account_id: Uuid,
+        amount: Decimal,
+        reason: String,
+    },
+}
+impl AccountCommand {
+    pub fn account_id(&self) -> Uuid {
+        match self {
+            AccountCommand::CreateAccount { account_id, .. } => *account_id,
+            AccountCommand::DepositMoney { account_id, .. } => *account_id,
+            AccountCommand::WithdrawMoney { account_id, .. } => *account_id,
+            AccountCommand::CloseAccount { account_id, .. } => *account_id,
+            AccountCommand::RenameOwner { account_id, .. } => *account_id,
+            AccountCommand::SetMetadata { account_id, .. } => *account_id,
+            AccountCommand::RemoveMetadata { account_id, .. } => *account_id,
+            AccountCommand::ChargeFee { account_id, .. } => *account_id,
+        }
+    }
+}
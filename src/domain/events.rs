@@ -1,3 +1,4 @@
+use crate::domain::{AccountRules, AccountType};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
@@ -20,6 +21,12 @@ pub enum AccountEvent {
         account_id: Uuid,
         owner_name: String,
         initial_balance: Decimal,
+        #[serde(default)]
+        owner_user_id: Option<Uuid>,
+        #[serde(default)]
+        account_type: AccountType,
+        #[serde(default)]
+        rules: AccountRules,
     },
     MoneyDeposited {
         account_id: Uuid,
@@ -30,11 +37,46 @@ pub enum AccountEvent {
         account_id: Uuid,
         amount: Decimal,
         transaction_id: Uuid,
+        /// When the withdrawal was made, for velocity caps. Missing on withdrawals
+        /// recorded before the caps existed, which then don't count towards them.
+        #[serde(default)]
+        withdrawn_at: Option<DateTime<Utc>>,
     },
     AccountClosed {
         account_id: Uuid,
         reason: String,
     },
+    /// The owner's name was corrected or changed, e.g. after a legal name change.
+    OwnerNameChanged {
+        account_id: Uuid,
+        old: String,
+        new: String,
+    },
+    /// An operations tag was added to the account or its value replaced.
+    MetadataSet {
+        account_id: Uuid,
+        key: String,
+        value: String,
+    },
+    MetadataRemoved {
+        account_id: Uuid,
+        key: String,
+    },
+    /// A fee was taken from the balance. Unlike a withdrawal it may take the balance
+    /// below the account's minimum.
+    FeeCharged {
+        account_id: Uuid,
+        amount: Decimal,
+        reason: String,
+        transaction_id: Uuid,
+    },
+    /// Warning that a fee took the balance below the account's minimum. Changes nothing
+    /// itself; it is recorded so the breach can be followed up.
+    MinimumBalanceBreached {
+        account_id: Uuid,
+        minimum: Decimal,
+        balance: Decimal,
+    },
 }
 
 impl AccountEvent {
@@ -44,6 +86,11 @@ impl AccountEvent {
             AccountEvent::MoneyDeposited { account_id, .. } => *account_id,
             AccountEvent::MoneyWithdrawn { account_id, .. } => *account_id,
             AccountEvent::AccountClosed { account_id, .. } => *account_id,
+            AccountEvent::OwnerNameChanged { account_id, .. } => *account_id,
+            AccountEvent::MetadataSet { account_id, .. } => *account_id,
+            AccountEvent::MetadataRemoved { account_id, .. } => *account_id,
+            AccountEvent::FeeCharged { account_id, .. } => *account_id,
+            AccountEvent::MinimumBalanceBreached { account_id, .. } => *account_id,
         }
     }
 
@@ -53,6 +100,11 @@ impl AccountEvent {
             AccountEvent::MoneyDeposited { .. } => "MoneyDeposited",
             AccountEvent::MoneyWithdrawn { .. } => "MoneyWithdrawn",
             AccountEvent::AccountClosed { .. } => "AccountClosed",
+            AccountEvent::OwnerNameChanged { .. } => "OwnerNameChanged",
+            AccountEvent::MetadataSet { .. } => "MetadataSet",
+            AccountEvent::MetadataRemoved { .. } => "MetadataRemoved",
+            AccountEvent::FeeCharged { .. } => "FeeCharged",
+            AccountEvent::MinimumBalanceBreached { .. } => "MinimumBalanceBreached",
         }
     }
 }
\ No newline at end of file
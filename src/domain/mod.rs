@@ -1,9 +1,13 @@
 pub mod account;
+pub mod account_type;
 pub mod commands;
 pub mod events;
+pub mod money;
 
 pub use account::*;
+pub use account_type::*;
 pub use commands::*;
 pub use events::*;
+pub use money::*;
 
 pub use account::AccountError;
@@ -0,0 +1,85 @@
+use crate::domain::AccountError;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// How amounts with more decimal places than the currency allows are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Refuse the amount.
+    #[default]
+    Reject,
+    /// Round to the nearest unit, ties to the even neighbour (banker's rounding).
+    HalfEven,
+    /// Round to the nearest unit, ties away from zero.
+    HalfUp,
+    /// Drop the extra digits.
+    Down,
+}
+
+impl RoundingMode {
+    fn strategy(&self) -> Option<RoundingStrategy> {
+        match self {
+            RoundingMode::Reject => None,
+            RoundingMode::HalfEven => Some(RoundingStrategy::MidpointNearestEven),
+            RoundingMode::HalfUp => Some(RoundingStrategy::MidpointAwayFromZero),
+            RoundingMode::Down => Some(RoundingStrategy::ToZero),
+        }
+    }
+}
+
+/// The scale money amounts are held to, so fractions of the smallest currency unit
+/// never reach a balance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmountPrecision {
+    /// Decimal places allowed, 2 for cents.
+    pub scale: u32,
+    pub rounding: RoundingMode,
+}
+
+impl Default for AmountPrecision {
+    fn default() -> Self {
+        Self {
+            scale: 2,
+            rounding: RoundingMode::Reject,
+        }
+    }
+}
+
+impl AmountPrecision {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            scale: std::env::var("CURRENCY_SCALE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.scale),
+            rounding: match std::env::var("AMOUNT_ROUNDING")
+                .unwrap_or_default()
+                .to_ascii_lowercase()
+                .as_str()
+            {
+                "half_even" => RoundingMode::HalfEven,
+                "half_up" => RoundingMode::HalfUp,
+                "down" => RoundingMode::Down,
+                _ => default.rounding,
+            },
+        }
+    }
+
+    /// Brings an amount to the configured scale, rounding or rejecting extra digits.
+    /// Trailing zeros don't count, so `10.500` passes at scale 2 as `10.50`.
+    pub fn normalize(&self, amount: Decimal) -> Result<Decimal, AccountError> {
+        if amount.normalize().scale() <= self.scale {
+            let mut normalized = amount;
+            normalized.rescale(self.scale);
+            return Ok(normalized);
+        }
+
+        match self.rounding.strategy() {
+            Some(strategy) => Ok(amount.round_dp_with_strategy(self.scale, strategy)),
+            None => Err(AccountError::ExcessPrecision {
+                amount,
+                scale: self.scale,
+            }),
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::domain::AccountError;
+use crate::infrastructure::auth::AuthError;
+use crate::infrastructure::event_serialization::EventSerializationError;
+use crate::infrastructure::event_store::EventStoreError;
+use crate::infrastructure::failed_commands::FailedCommandError;
+use crate::infrastructure::kafka_abstraction::BankingKafkaError;
+use crate::infrastructure::outbox::OutboxError;
+use crate::infrastructure::projections::ProjectionError;
+use crate::infrastructure::repository::RepositoryError;
+use crate::infrastructure::user_repository::UserRepositoryError;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use thiserror::Error;
+use tracing::error;
+
+/// Error type shared across the crate. Module errors convert into it without losing
+/// their variant, so callers and the web layer can still tell a version conflict from
+/// a database outage.
+#[derive(Debug, Error)]
+pub enum BankingError {
+    #[error(transparent)]
+    Account(#[from] AccountError),
+    #[error(transparent)]
+    Repository(#[from] RepositoryError),
+    #[error(transparent)]
+    EventStore(#[from] EventStoreError),
+    #[error(transparent)]
+    Projection(#[from] ProjectionError),
+    #[error(transparent)]
+    Kafka(#[from] BankingKafkaError),
+    #[error(transparent)]
+    Outbox(#[from] OutboxError),
+    #[error(transparent)]
+    FailedCommand(#[from] FailedCommandError),
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+    #[error(transparent)]
+    User(#[from] UserRepositoryError),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl BankingError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            BankingError::Account(e) => match e {
+                AccountError::NotFound => StatusCode::NOT_FOUND,
+                AccountError::InsufficientFunds { .. }
+                | AccountError::BelowMinimumBalance { .. }
+                | AccountError::InvalidAmount(_)
+                | AccountError::ExcessPrecision { .. }
+                | AccountError::LimitExceeded { .. }
+                | AccountError::VelocityLimitExceeded { .. }
+                | AccountError::InitialBalanceNotAllowed { .. }
+                | AccountError::InvalidOwnerName(_)
+                | AccountError::InvalidMetadata(_) => StatusCode::BAD_REQUEST,
+                AccountError::AccountClosed
+                | AccountError::VersionConflict { .. }
+                | AccountError::AlreadyExists => StatusCode::CONFLICT,
+                AccountError::EventDeserializationError(_) | AccountError::SchemaError(_) => {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+                AccountError::InfrastructureError(_) | AccountError::Unavailable(_) => {
+                    StatusCode::SERVICE_UNAVAILABLE
+                }
+            },
+            BankingError::Repository(e) => match e {
+                RepositoryError::NotFound(_) => StatusCode::NOT_FOUND,
+                RepositoryError::VersionConflict { .. } => StatusCode::CONFLICT,
+                RepositoryError::InfrastructureError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            BankingError::EventStore(e) => match e {
+                EventStoreError::OptimisticConcurrencyConflict { .. }
+                | EventStoreError::AggregateAlreadyExists { .. }
+                | EventStoreError::AggregateTooLarge { .. }
+                | EventStoreError::DuplicateEvent { .. }
+                | EventStoreError::DuplicateEventSkipped { .. } => StatusCode::CONFLICT,
+                EventStoreError::ValidationError(_) | EventStoreError::BatchTooLarge { .. } => {
+                    StatusCode::BAD_REQUEST
+                }
+                EventStoreError::Timeout { .. } => StatusCode::SERVICE_UNAVAILABLE,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            BankingError::Kafka(e) if e.is_unavailable() => StatusCode::SERVICE_UNAVAILABLE,
+            BankingError::Outbox(OutboxError::QueueFull { .. }) => StatusCode::SERVICE_UNAVAILABLE,
+            BankingError::FailedCommand(e) => match e {
+                FailedCommandError::NotFound(_) => StatusCode::NOT_FOUND,
+                FailedCommandError::AlreadyReplayed(_) => StatusCode::CONFLICT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            BankingError::Auth(e) => e.status_and_message().0,
+            BankingError::User(e) => match e {
+                UserRepositoryError::NotFoundById(_)
+                | UserRepositoryError::NotFoundByUsername(_) => StatusCode::NOT_FOUND,
+                UserRepositoryError::UsernameExists(_) | UserRepositoryError::EmailExists(_) => {
+                    StatusCode::CONFLICT
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            BankingError::Projection(_)
+            | BankingError::Kafka(_)
+            | BankingError::Outbox(_)
+            | BankingError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Stable code for errors clients can't tell apart by status alone, e.g. to alert on
+    /// stored events that no longer decode rather than on a passing outage.
+    pub fn error_code(&self) -> Option<&'static str> {
+        match self {
+            BankingError::Account(AccountError::SchemaError(_)) => Some("schema_mismatch"),
+            BankingError::Account(AccountError::EventDeserializationError(_)) => {
+                Some("event_deserialization_failed")
+            }
+            _ => None,
+        }
+    }
+
+    /// The status and client-facing message. Server errors are logged and reported
+    /// without their details.
+    pub fn status_and_message(&self) -> (StatusCode, String) {
+        if let BankingError::Auth(e) = self {
+            return e.status_and_message();
+        }
+        let status = self.status_code();
+        if status.is_server_error() {
+            error!("Request failed: {}", self);
+            let message = status.canonical_reason().unwrap_or("Internal server error");
+            (status, message.to_string())
+        } else {
+            (status, self.to_string())
+        }
+    }
+}
+
+impl IntoResponse for BankingError {
+    fn into_response(self) -> Response {
+        let (status, message) = self.status_and_message();
+        let body = match self.error_code() {
+            Some(code) => serde_json::json!({ "error": message, "code": code }),
+            None => serde_json::json!({ "error": message }),
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+impl From<BankingError> for AccountError {
+    fn from(error: BankingError) -> Self {
+        match error {
+            BankingError::Account(e) => e,
+            BankingError::Repository(RepositoryError::NotFound(_)) => AccountError::NotFound,
+            BankingError::Repository(RepositoryError::VersionConflict { expected, actual }) => {
+                AccountError::VersionConflict { expected, actual }
+            }
+            BankingError::EventStore(EventStoreError::OptimisticConcurrencyConflict {
+                expected,
+                actual: Some(actual),
+                ..
+            }) => AccountError::VersionConflict { expected, actual },
+            BankingError::EventStore(EventStoreError::AggregateAlreadyExists { .. }) => {
+                AccountError::AlreadyExists
+            }
+            BankingError::EventStore(EventStoreError::EventSerialization(e)) => e.into(),
+            BankingError::EventStore(EventStoreError::SerializationError(e)) => {
+                EventSerializationError::Json(e).into()
+            }
+            BankingError::Outbox(e @ OutboxError::QueueFull { .. }) => {
+                AccountError::Unavailable(e.to_string())
+            }
+            other => AccountError::InfrastructureError(other.to_string()),
+        }
+    }
+}
+
+impl From<EventSerializationError> for AccountError {
+    fn from(error: EventSerializationError) -> Self {
+        if error.is_schema_mismatch() {
+            AccountError::SchemaError(error.to_string())
+        } else {
+            AccountError::EventDeserializationError(error.to_string())
+        }
+    }
+}
@@ -1,8 +1,9 @@
-use crate::infrastructure::user_repository::{NewUser, User, UserRepository, UserRepositoryError};
-use argon2::{
-    Argon2,
-    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
+use crate::infrastructure::jwt_keys::{
+    self, JwtKeyRing, KeyRingCipher, KeyRingError, DEFAULT_KID,
 };
+use crate::infrastructure::password::{PasswordHashConfig, PasswordHashing, PasswordVerification};
+use crate::infrastructure::redis_abstraction::{RealRedisClient, RedisClientTrait};
+use crate::infrastructure::user_repository::{NewUser, User, UserRepository, UserRepositoryError};
 use async_trait::async_trait;
 use axum::{
     Json, RequestPartsExt, Router,
@@ -24,15 +25,25 @@ use std::fmt::Display;
 use std::future::Future;
 use std::str::FromStr;
 use std::sync::Arc;
-use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
 use strum_macros::{EnumString, ToString};
 use thiserror::Error;
 use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
 use uuid::Uuid;
-static KEYS: LazyLock<Keys> = LazyLock::new(|| {
-    let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
-    Keys::new(secret.as_bytes())
-});
+
+/// Redis key the shared signing key ring is stored under.
+const SIGNING_KEYS_KEY: &str = "jwt_signing_keys";
+
+/// Failed logins are counted, and lockouts kept, in Redis so the limit holds across
+/// every instance rather than per process.
+fn failed_attempts_key(user_id: Uuid) -> String {
+    format!("auth:failed_attempts:{}", user_id)
+}
+
+fn lockout_key(user_id: Uuid) -> String {
+    format!("auth:lockout:{}", user_id)
+}
 
 #[derive(Debug, Error)]
 pub enum AuthError {
@@ -70,6 +81,8 @@ pub enum AuthError {
     UserRepositoryError(String),
     #[error("Account is locked")]
     AccountLocked,
+    #[error(transparent)]
+    KeyRing(#[from] KeyRingError),
 }
 
 impl From<UserRepositoryError> for AuthError {
@@ -97,6 +110,18 @@ pub struct Claims {
     pub token_type: TokenType,
     pub jti: String,
     pub company: String,
+    // Tokens issued before ownership tracking carry no user id and own no accounts
+    #[serde(default)]
+    pub user_id: Uuid,
+}
+
+impl Claims {
+    /// Admins and bank managers may access any account.
+    pub fn is_privileged(&self) -> bool {
+        self.roles
+            .iter()
+            .any(|role| matches!(role, UserRole::Admin | UserRole::BankManager))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, ToString, EnumString)]
@@ -114,6 +139,29 @@ pub enum UserRole {
     Customer,
 }
 
+/// What token validation does when Redis, and with it the revocation list, can't be
+/// reached. Logins always fail while Redis is down, since lockouts can't be enforced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum RedisDegradation {
+    /// Reject the token.
+    #[default]
+    FailClosed,
+    /// Accept tokens with a valid signature that haven't expired, without checking
+    /// whether they were revoked.
+    FailOpen,
+}
+
+impl RedisDegradation {
+    /// Reads `AUTH_REDIS_DEGRADATION`: `fail_closed` or `fail_open`.
+    pub fn from_env() -> Self {
+        std::env::var("AUTH_REDIS_DEGRADATION")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AuthConfig {
     pub jwt_secret: String,
@@ -171,10 +219,16 @@ pub struct LogoutRequest {
 
 #[derive(Clone)]
 pub struct AuthService {
-    redis_client: Arc<redis::Client>,
+    redis_client: Arc<dyn RedisClientTrait>,
     config: AuthConfig,
     // users: Arc<RwLock<Vec<User>>>,
     user_repository: Arc<UserRepository>,
+    password_hashing: PasswordHashing,
+    credentials_migrated: Arc<AtomicU64>,
+    access_keys: Arc<JwtKeyRing>,
+    signing_keys_cipher: Arc<KeyRingCipher>,
+    redis_degradation: RedisDegradation,
+    degraded_validations: Arc<AtomicU64>,
 }
 
 impl AuthService {
@@ -183,11 +237,168 @@ impl AuthService {
         config: AuthConfig,
         user_repository: Arc<UserRepository>,
     ) -> Self {
+        let access_keys = Arc::new(JwtKeyRing::new(DEFAULT_KID, config.jwt_secret.clone()));
+        jwt_keys::install(access_keys.clone());
+        let signing_keys_cipher = Arc::new(KeyRingCipher::new(&config.jwt_secret));
         Self {
-            redis_client,
+            redis_client: RealRedisClient::new(redis_client.as_ref().clone(), None),
             config,
             // users: Arc::new(RwLock::new(Vec::new())),
             user_repository,
+            password_hashing: PasswordHashing::default(),
+            credentials_migrated: Arc::new(AtomicU64::new(0)),
+            access_keys,
+            signing_keys_cipher,
+            redis_degradation: RedisDegradation::default(),
+            degraded_validations: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Signs and verifies access tokens with `ring` instead of the single configured
+    /// secret. The ring also becomes the one request extractors verify with.
+    pub fn with_signing_keys(mut self, ring: Arc<JwtKeyRing>) -> Self {
+        jwt_keys::install(ring.clone());
+        self.access_keys = ring;
+        self
+    }
+
+    pub fn signing_keys(&self) -> &Arc<JwtKeyRing> {
+        &self.access_keys
+    }
+
+    /// Encrypts the ring saved to Redis with `cipher` instead of one keyed with the JWT
+    /// secret.
+    pub fn with_signing_keys_cipher(mut self, cipher: KeyRingCipher) -> Self {
+        self.signing_keys_cipher = Arc::new(cipher);
+        self
+    }
+
+    /// Signs new access tokens with a new key. Tokens signed with the previous key keep
+    /// verifying for `grace`. The ring is saved to Redis for other instances to load.
+    pub async fn rotate_signing_key(
+        &self,
+        kid: &str,
+        secret: &str,
+        grace: ChronoDuration,
+    ) -> Result<(), AuthError> {
+        self.access_keys.rotate(kid, secret, grace)?;
+        info!("Rotated the JWT signing key to '{}'", kid);
+        self.store_signing_keys().await;
+        Ok(())
+    }
+
+    /// Stops accepting tokens signed with `kid`.
+    pub async fn retire_signing_key(&self, kid: &str) -> Result<(), AuthError> {
+        self.access_keys.retire(kid)?;
+        info!("Retired JWT signing key '{}'", kid);
+        self.store_signing_keys().await;
+        Ok(())
+    }
+
+    /// Replaces the signing keys with the ring saved in Redis, if there is one and it
+    /// isn't older than the local ring. Returns whether the keys were replaced.
+    pub async fn load_signing_keys(&self) -> Result<bool, AuthError> {
+        let mut conn = self.redis_client.get_connection().await?;
+        let saved: Option<String> = conn.get(SIGNING_KEYS_KEY).await?;
+        let Some(saved) = saved else {
+            return Ok(false);
+        };
+        // Rings saved before they were encrypted are plain JSON; the next rotation seals them
+        let json = if saved.starts_with('{') {
+            warn!("JWT signing keys in Redis are not encrypted");
+            saved
+        } else {
+            self.signing_keys_cipher.open(&saved)?
+        };
+        self.access_keys
+            .replace_from_json(&json)
+            .map_err(|e| AuthError::InternalError(format!("Invalid saved signing keys: {}", e)))
+    }
+
+    /// Reloads the signing keys from Redis every `interval`, so keys rotated or retired
+    /// on another instance apply here too.
+    pub fn spawn_signing_key_reload(
+        self: &Arc<Self>,
+        interval: std::time::Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let auth = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                match auth.load_signing_keys().await {
+                    Ok(true) => debug!("Reloaded JWT signing keys from Redis"),
+                    Ok(false) => {}
+                    Err(e) => warn!("Failed to reload JWT signing keys from Redis: {}", e),
+                }
+            }
+        })
+    }
+
+    // The rotation already applies locally, so a Redis outage only delays other instances
+    async fn store_signing_keys(&self) {
+        let result = async {
+            let json = self
+                .access_keys
+                .to_json()
+                .map_err(|e| AuthError::InternalError(e.to_string()))?;
+            let sealed = self.signing_keys_cipher.seal(&json)?;
+            let mut conn = self.redis_client.get_connection().await?;
+            conn.set::<_, _, ()>(SIGNING_KEYS_KEY, sealed).await?;
+            Ok::<_, AuthError>(())
+        }
+        .await;
+        if let Err(e) = result {
+            warn!("Failed to save JWT signing keys to Redis: {}", e);
+        }
+    }
+
+    /// Serves blacklist and rate-limit state from `client` instead, e.g. one that
+    /// follows a Sentinel-managed master.
+    pub fn with_redis_client(mut self, client: Arc<dyn RedisClientTrait>) -> Self {
+        self.redis_client = client;
+        self
+    }
+
+    /// Sets the Argon2id parameters new and upgraded credentials are hashed with.
+    pub fn with_password_hashing(mut self, config: PasswordHashConfig) -> Self {
+        self.password_hashing = PasswordHashing::new(config);
+        self
+    }
+
+    pub fn with_redis_degradation(mut self, policy: RedisDegradation) -> Self {
+        self.redis_degradation = policy;
+        self
+    }
+
+    /// How many tokens were accepted without a revocation check because Redis was down.
+    pub fn degraded_token_validations(&self) -> u64 {
+        self.degraded_validations.load(Ordering::Relaxed)
+    }
+
+    /// How many stored credentials have been upgraded to the current scheme on login.
+    pub fn credentials_migrated(&self) -> u64 {
+        self.credentials_migrated.load(Ordering::Relaxed)
+    }
+
+    /// Checks a password against the user's stored hash. When it matches a legacy
+    /// scheme the credential is re-hashed with the current config and saved, so the
+    /// next login verifies against the upgraded hash.
+    pub async fn verify(&self, user: &User, password: &str) -> Result<bool, AuthError> {
+        match self.password_hashing.verify(password, &user.password_hash)? {
+            PasswordVerification::Invalid => Ok(false),
+            PasswordVerification::Valid { needs_rehash } => {
+                if needs_rehash {
+                    let upgraded = self.password_hashing.hash(password)?;
+                    self.user_repository
+                        .update_password_hash(user.id, &upgraded)
+                        .await?;
+                    self.credentials_migrated.fetch_add(1, Ordering::Relaxed);
+                    info!("Upgraded stored credential for user {}", user.id);
+                }
+                Ok(true)
+            }
         }
     }
 
@@ -206,12 +417,7 @@ impl AuthService {
         // }
 
         // Hash password
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2
-            .hash_password(password.as_bytes(), &salt)
-            .map_err(|e| AuthError::PasswordHashError(e.to_string()))?
-            .to_string();
+        let password_hash = self.password_hashing.hash(password)?;
 
         // let user = User {
         //     id: Uuid::new_v4().to_string(),
@@ -236,6 +442,38 @@ impl AuthService {
         Ok(user)
     }
 
+    /// Whether `user_id` is locked out after too many failed logins, on any instance.
+    pub async fn is_locked_out(&self, user_id: Uuid) -> Result<bool, AuthError> {
+        Ok(self
+            .redis_client
+            .get(&lockout_key(user_id))
+            .await?
+            .is_some())
+    }
+
+    /// Counts a failed login for `user_id`. Once `max_failed_attempts` have failed within
+    /// the lockout duration the user is locked out for that long; returns whether they are.
+    pub async fn record_failed_attempt(&self, user_id: Uuid) -> Result<bool, AuthError> {
+        let lockout =
+            std::time::Duration::from_secs(u64::from(self.config.lockout_duration_minutes) * 60);
+        let attempts = self
+            .redis_client
+            .incr_with_ttl(&failed_attempts_key(user_id), lockout)
+            .await?;
+        if attempts < i64::from(self.config.max_failed_attempts) {
+            return Ok(false);
+        }
+        self.redis_client
+            .set_with_ttl(&lockout_key(user_id), &attempts.to_string(), lockout)
+            .await?;
+        self.redis_client.del(&failed_attempts_key(user_id)).await?;
+        warn!(
+            "Locked out user {} after {} failed login attempts",
+            user_id, attempts
+        );
+        Ok(true)
+    }
+
     pub async fn login(&self, username: &str, password: &str) -> Result<LoginResponse, AuthError> {
         // let mut users = self.users.write().await;
         // let user = users
@@ -257,6 +495,9 @@ impl AuthService {
                 .update_lockout(user.id, None, 0)
                 .await?;
         }
+        if self.is_locked_out(user.id).await? {
+            return Err(AuthError::AccountLocked);
+        }
 
         // Check if account is locked
         // if let Some(locked_until) = user.locked_until {
@@ -268,13 +509,7 @@ impl AuthService {
         // }
 
         // Verify password
-        let parsed_hash = PasswordHash::new(&user.password_hash)
-            .map_err(|e| AuthError::PasswordHashError(e.to_string()))?;
-
-        if !Argon2::default()
-            .verify_password(password.as_bytes(), &parsed_hash)
-            .is_ok()
-        {
+        if !self.verify(&user, password).await? {
             // user.failed_login_attempts += 1;
             // if user.failed_login_attempts >= self.config.max_failed_attempts {
             //     user.locked_until = Some(
@@ -283,22 +518,12 @@ impl AuthService {
             //     return Err(AuthError::InternalError(
             //         "Account locked due to too many failed attempts".into(),
             //     ));
-            let new_failed_attempts = self
-                .user_repository
-                .increment_failed_attempts(user.id)
-                .await?;
-            if new_failed_attempts >= self.config.max_failed_attempts.try_into().unwrap() {
-                let locked_until = Utc::now()
-                    + ChronoDuration::minutes(self.config.lockout_duration_minutes.into());
-
-                self.user_repository
-                    .update_lockout(user.id, Some(locked_until), new_failed_attempts)
-                    .await?;
-
+            if self.record_failed_attempt(user.id).await? {
                 return Err(AuthError::AccountLocked);
             }
             return Err(AuthError::InvalidCredentials);
         }
+        self.redis_client.del(&failed_attempts_key(user.id)).await?;
 
         // Reset failed attempts and update last login
         // user.failed_login_attempts = 0;
@@ -315,10 +540,10 @@ impl AuthService {
             .collect();
         // Generate tokens
         let access_token = self
-            .generate_token(&user.username, &user_roles, TokenType::Access)
+            .generate_token(user.id, &user.username, &user_roles, TokenType::Access)
             .await?;
         let refresh_token = self
-            .generate_token(&user.username, &user_roles, TokenType::Refresh)
+            .generate_token(user.id, &user.username, &user_roles, TokenType::Refresh)
             .await?;
 
         Ok(LoginResponse {
@@ -352,10 +577,10 @@ impl AuthService {
             .collect();
 
         let access_token = self
-            .generate_token(&user.username, &user_roles, TokenType::Access)
+            .generate_token(user.id, &user.username, &user_roles, TokenType::Access)
             .await?;
         let new_refresh_token = self
-            .generate_token(&user.username, &user_roles, TokenType::Refresh)
+            .generate_token(user.id, &user.username, &user_roles, TokenType::Refresh)
             .await?;
 
         Ok(LoginResponse {
@@ -381,23 +606,16 @@ impl AuthService {
             .ok_or(AuthError::UserNotFound)?;
 
         // Verify current password
-        let parsed_hash = PasswordHash::new(&user.password_hash)
-            .map_err(|e| AuthError::PasswordHashError(e.to_string()))?;
-
-        if !Argon2::default()
-            .verify_password(current_password.as_bytes(), &parsed_hash)
-            .is_ok()
+        if self
+            .password_hashing
+            .verify(current_password, &user.password_hash)?
+            == PasswordVerification::Invalid
         {
             return Err(AuthError::InvalidCredentials);
         }
 
         // Hash new password
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let new_password_hash = argon2
-            .hash_password(new_password.as_bytes(), &salt)
-            .map_err(|e| AuthError::PasswordHashError(e.to_string()))?
-            .to_string();
+        let new_password_hash = self.password_hashing.hash(new_password)?;
 
         // user.password_hash = new_password_hash;
         self.user_repository
@@ -438,6 +656,7 @@ impl AuthService {
 
     async fn generate_token(
         &self,
+        user_id: Uuid,
         username: &str,
         roles: &[UserRole],
         token_type: TokenType,
@@ -456,18 +675,17 @@ impl AuthService {
             token_type,
             jti: Uuid::new_v4().to_string(),
             company: "ACME".to_string(),
+            user_id,
         };
 
-        let secret_key = match claims.token_type {
-            TokenType::Access => &self.config.jwt_secret,
-            TokenType::Refresh => &self.config.refresh_token_secret,
-        };
-
-        encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(secret_key.as_bytes()),
-        )
+        match claims.token_type {
+            TokenType::Access => self.access_keys.sign(&claims),
+            TokenType::Refresh => encode(
+                &Header::default(),
+                &claims,
+                &EncodingKey::from_secret(self.config.refresh_token_secret.as_bytes()),
+            ),
+        }
         .map_err(AuthError::JwtError)
     }
 
@@ -476,41 +694,44 @@ impl AuthService {
         token: &str,
         expected_type: TokenType,
     ) -> Result<Claims, AuthError> {
-        // Check if token is blacklisted
-        let mut conn = self.redis_client.get_async_connection().await?;
-        let is_blacklisted: bool = conn
-            .get(format!("blacklist:{}", token))
-            .await
-            .unwrap_or(false);
-
-        if is_blacklisted {
-            return Err(AuthError::TokenBlacklisted);
-        }
-
         // Validate token
         let validation = Validation::new(Algorithm::HS256);
 
-        let secret = match expected_type {
-            TokenType::Access => &self.config.jwt_secret,
-            TokenType::Refresh => &self.config.refresh_token_secret,
+        let token_data = match expected_type {
+            TokenType::Access => self.access_keys.verify::<Claims>(token, &validation)?,
+            TokenType::Refresh => decode::<Claims>(
+                token,
+                &DecodingKey::from_secret(self.config.refresh_token_secret.as_bytes()),
+                &validation,
+            )?,
         };
 
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(secret.as_bytes()),
-            &validation,
-        )?;
-
         if token_data.claims.token_type != expected_type {
             return Err(AuthError::InvalidToken);
         }
 
+        // Check if token is blacklisted
+        match self.redis_client.get(&format!("blacklist:{}", token)).await {
+            Ok(Some(_)) => return Err(AuthError::TokenBlacklisted),
+            Ok(None) => {}
+            Err(e) => match self.redis_degradation {
+                RedisDegradation::FailClosed => return Err(e.into()),
+                RedisDegradation::FailOpen => {
+                    self.degraded_validations.fetch_add(1, Ordering::Relaxed);
+                    warn!(
+                        "Redis unavailable, accepting token of {} without checking revocation: {}",
+                        token_data.claims.sub, e
+                    );
+                }
+            },
+        }
+
         Ok(token_data.claims)
     }
 
     pub async fn blacklist_token(&self, token: &str) -> Result<(), AuthError> {
         let claims = self.validate_token(token, TokenType::Access).await?;
-        let mut conn = self.redis_client.get_async_connection().await?;
+        let mut conn = self.redis_client.get_connection().await?;
 
         // Blacklist token until it expires
         let ttl = claims.exp - Utc::now().timestamp();
@@ -526,7 +747,7 @@ impl AuthService {
     }
 
     pub async fn check_rate_limit(&self, key: &str) -> Result<(), AuthError> {
-        let mut conn = self.redis_client.get_async_connection().await?;
+        let mut conn = self.redis_client.get_connection().await?;
         let current: i64 = conn.incr(format!("rate_limit:{}", key), 1).await?;
 
         if current == 1 {
@@ -586,18 +807,19 @@ where
                 .await
                 .map_err(|_| AuthError::InvalidToken)?;
             // Decode the user data
-            let token_data =
-                decode::<Claims>(bearer.token(), &KEYS.decoding, &Validation::default())
-                    .map_err(|_| AuthError::InvalidToken)?;
+            let token_data = jwt_keys::access_keys()
+                .verify::<Claims>(bearer.token(), &Validation::default())
+                .map_err(|_| AuthError::InvalidToken)?;
 
             Ok(token_data.claims)
         }
     }
 }
 
-impl IntoResponse for AuthError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match &self {
+impl AuthError {
+    /// The status and client-facing message; internal details aren't exposed.
+    pub fn status_and_message(&self) -> (StatusCode, String) {
+        match self {
             AuthError::InvalidCredentials => {
                 (StatusCode::UNAUTHORIZED, "Invalid credentials".to_string())
             }
@@ -617,8 +839,8 @@ impl IntoResponse for AuthError {
                 "Password processing error".to_string(),
             ),
             AuthError::RedisError(_) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Database error".to_string(),
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Authentication is temporarily unavailable".to_string(),
             ),
             AuthError::JwtError(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -650,7 +872,14 @@ impl IntoResponse for AuthError {
                 "Database error".to_string(),
             ),
             AuthError::AccountLocked => (StatusCode::UNAUTHORIZED, "Account is locked".to_string()),
-        };
+            AuthError::KeyRing(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+        }
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let (status, error_message) = self.status_and_message();
 
         let body = Json(serde_json::json!({
             "error": error_message
@@ -660,20 +889,6 @@ impl IntoResponse for AuthError {
     }
 }
 
-struct Keys {
-    encoding: EncodingKey,
-    decoding: DecodingKey,
-}
-
-impl Keys {
-    fn new(secret: &[u8]) -> Self {
-        Self {
-            encoding: EncodingKey::from_secret(secret),
-            decoding: DecodingKey::from_secret(secret),
-        }
-    }
-}
-
 pub async fn authorize(Json(payload): Json<AuthPayload>) -> Result<Json<AuthBody>, AuthError> {
     if payload.client_id.is_empty() || payload.client_secret.is_empty() {
         return Err(AuthError::MissingCredentials);
@@ -692,9 +907,11 @@ pub async fn authorize(Json(payload): Json<AuthPayload>) -> Result<Json<AuthBody
         roles: vec![UserRole::Customer],
         token_type: TokenType::Access,
         jti: Uuid::new_v4().to_string(),
+        user_id: Uuid::nil(),
     };
 
-    let token = encode(&Header::default(), &claims, &KEYS.encoding)
+    let token = jwt_keys::access_keys()
+        .sign(&claims)
         .map_err(|_| AuthError::TokenCreation)?;
 
     Ok(Json(AuthBody::new(token)))
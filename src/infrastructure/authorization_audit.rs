@@ -0,0 +1,157 @@
+use crate::infrastructure::config::AuthorizationAuditConfig;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// The outcome of one authorization check of a guarded operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, sqlx::FromRow)]
+pub struct AuthorizationDecision {
+    /// User id of the caller.
+    pub subject_id: Uuid,
+    /// Subject of the caller's token.
+    pub subject: String,
+    pub method: String,
+    /// Route the operation is mounted at, without a version prefix.
+    pub route: String,
+    /// What was accessed, e.g. `account:<id>`, or the request path for role checks.
+    pub resource: String,
+    /// The role or relationship the operation requires.
+    pub required: String,
+    pub allowed: bool,
+    /// What granted access, or why it was denied.
+    pub reason: String,
+}
+
+/// A decision as kept in the audit store.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AuditedAuthorizationDecision {
+    pub id: Uuid,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub decision: AuthorizationDecision,
+    pub decided_at: DateTime<Utc>,
+}
+
+/// The `authorization_audit` table.
+#[derive(Clone)]
+pub struct AuthorizationAuditStore {
+    pool: PgPool,
+}
+
+impl AuthorizationAuditStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn record(&self, decision: &AuthorizationDecision) -> Result<Uuid, sqlx::Error> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO authorization_audit
+                (id, subject_id, subject, method, route, resource, required, allowed, reason)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(id)
+        .bind(decision.subject_id)
+        .bind(&decision.subject)
+        .bind(&decision.method)
+        .bind(&decision.route)
+        .bind(&decision.resource)
+        .bind(&decision.required)
+        .bind(decision.allowed)
+        .bind(&decision.reason)
+        .execute(&self.pool)
+        .await?;
+        Ok(id)
+    }
+
+    /// Up to `limit` of the subject's decisions, newest first.
+    pub async fn for_subject(
+        &self,
+        subject_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<AuditedAuthorizationDecision>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT id, subject_id, subject, method, route, resource, required, allowed, reason,
+                   decided_at
+            FROM authorization_audit
+            WHERE subject_id = $1
+            ORDER BY decided_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(subject_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+/// Audits the authorization decisions of guarded operations. Every denial is counted
+/// against its route; the decisions the config selects are logged under the
+/// `authorization_audit` target and written to the store, if there is one.
+#[derive(Default)]
+pub struct AuthorizationAudit {
+    config: AuthorizationAuditConfig,
+    store: Option<AuthorizationAuditStore>,
+    denials: DashMap<String, u64>,
+}
+
+impl AuthorizationAudit {
+    pub fn new(config: AuthorizationAuditConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_store(mut self, store: AuthorizationAuditStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Counts and records `decision`. A decision the store fails to take is logged and
+    /// dropped rather than failing the request it belongs to.
+    pub async fn record(&self, decision: AuthorizationDecision) {
+        if !decision.allowed {
+            *self.denials.entry(decision.route.clone()).or_insert(0) += 1;
+        }
+        if !self.config.records(&decision.route, decision.allowed) {
+            return;
+        }
+        info!(
+            target: "authorization_audit",
+            "{} {} {} by {} ({}): {} requires {}: {}",
+            if decision.allowed { "allowed" } else { "denied" },
+            decision.method,
+            decision.route,
+            decision.subject,
+            decision.subject_id,
+            decision.resource,
+            decision.required,
+            decision.reason
+        );
+        if let Some(store) = &self.store {
+            if let Err(e) = store.record(&decision).await {
+                warn!(
+                    "Failed to write authorization decision on {} for {} to the audit store: {}",
+                    decision.route, decision.subject_id, e
+                );
+            }
+        }
+    }
+
+    /// Denied decisions so far, by route.
+    pub fn denials_by_route(&self) -> HashMap<String, u64> {
+        self.denials
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect()
+    }
+}
@@ -6,6 +6,7 @@ use crate::infrastructure::redis_abstraction::{
 };
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use dashmap::DashMap;
 use redis::Client;
 use redis::RedisError;
@@ -16,12 +17,12 @@ use serde_json;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
 use tokio::io::{AsyncRead, AsyncWrite, duplex};
 use tokio::sync::RwLock;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info};
 use uuid::Uuid;
 
 #[derive(Debug, Clone)]
@@ -32,6 +33,42 @@ pub struct CacheConfig {
     pub warmup_batch_size: usize,
     pub warmup_interval: Duration,
     pub eviction_policy: EvictionPolicy,
+    pub backend: CacheBackend,
+    /// Prepended to every Redis key, so environments or tenants sharing a Redis don't
+    /// read each other's entries. Empty for no prefix.
+    pub key_prefix: String,
+    /// Embedded in every Redis key. Bumping it makes entries written under the old
+    /// version miss; they are left to expire on their TTL.
+    pub schema_version: u32,
+    /// How often expired in-process entries are swept, whether or not they are read
+    /// again. Zero disables the sweeper.
+    pub sweep_interval: Duration,
+    /// How long one sweep may run before it stops and leaves the remaining shards to
+    /// the next one.
+    pub sweep_budget: Duration,
+}
+
+/// The version of the cached blobs' layout. Bump it when `Account` or `AccountEvent`
+/// serialize differently, so a deploy doesn't read entries it can't decode.
+pub const CACHE_SCHEMA_VERSION: u32 = 1;
+
+impl CacheConfig {
+    /// The part of every key before the entry kind, e.g. `prod:v1:`.
+    pub fn key_namespace(&self) -> String {
+        if self.key_prefix.is_empty() {
+            format!("v{}:", self.schema_version)
+        } else {
+            format!("{}:v{}:", self.key_prefix, self.schema_version)
+        }
+    }
+
+    pub fn account_key(&self, account_id: Uuid) -> String {
+        format!("{}account:{}", self.key_namespace(), account_id)
+    }
+
+    pub fn events_key(&self, account_id: Uuid) -> String {
+        format!("{}events:{}", self.key_namespace(), account_id)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -41,6 +78,28 @@ pub enum EvictionPolicy {
     TTL,
 }
 
+/// Where cached accounts are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheBackend {
+    /// In-process shards in front of Redis, shared between instances.
+    #[default]
+    Redis,
+    /// In-process only, see `InMemoryCacheService`.
+    InMemory,
+}
+
+impl std::str::FromStr for CacheBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "redis" => Ok(CacheBackend::Redis),
+            "memory" | "in_memory" => Ok(CacheBackend::InMemory),
+            other => Err(format!("unknown cache backend: {}", other)),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct CacheMetrics {
     pub hits: std::sync::atomic::AtomicU64,
@@ -50,6 +109,9 @@ pub struct CacheMetrics {
     pub warmups: std::sync::atomic::AtomicU64,
     pub shard_hits: std::sync::atomic::AtomicU64,
     pub shard_misses: std::sync::atomic::AtomicU64,
+    pub stale_writes: std::sync::atomic::AtomicU64,
+    /// Expired in-process entries removed by the background sweeper.
+    pub entries_swept: std::sync::atomic::AtomicU64,
 }
 
 impl Default for CacheMetrics {
@@ -62,6 +124,8 @@ impl Default for CacheMetrics {
             warmups: std::sync::atomic::AtomicU64::new(0),
             shard_hits: std::sync::atomic::AtomicU64::new(0),
             shard_misses: std::sync::atomic::AtomicU64::new(0),
+            stale_writes: std::sync::atomic::AtomicU64::new(0),
+            entries_swept: std::sync::atomic::AtomicU64::new(0),
         }
     }
 }
@@ -73,12 +137,31 @@ pub struct CacheEntry<T> {
     pub last_accessed: Instant,
     pub access_count: u64,
     pub ttl: Duration,
+    pub version: i64,
 }
 
-impl<T> CacheEntry<T> {
-    fn is_expired(&self) -> bool {
-        self.last_accessed.elapsed() > self.ttl
-    }
+/// Which cache tier an inspected entry was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheTier {
+    Memory,
+    Redis,
+}
+
+/// A cached account as seen by operators. Timestamps are only tracked for the
+/// in-memory tier.
+#[derive(Debug, Clone, Serialize)]
+pub struct CachedAccountEntry {
+    pub account: Account,
+    pub tier: CacheTier,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_accessed: Option<DateTime<Utc>>,
+    pub access_count: Option<u64>,
+    pub expired: bool,
+}
+
+pub(crate) fn instant_to_utc(instant: Instant) -> DateTime<Utc> {
+    Utc::now() - chrono::Duration::from_std(instant.elapsed()).unwrap_or_default()
 }
 
 #[derive(Clone)]
@@ -89,6 +172,7 @@ pub struct CacheService {
     shards: Arc<Vec<DashMap<Uuid, CacheEntry<Account>>>>,
     event_cache: DashMap<Uuid, Vec<(i64, AccountEvent)>>,
     warming_state: Arc<RwLock<WarmingState>>,
+    sweep_cursor: Arc<AtomicUsize>,
 }
 
 #[derive(Debug, Default)]
@@ -102,6 +186,13 @@ struct WarmingState {
 pub trait CacheServiceTrait: Send + Sync {
     async fn get_account(&self, account_id: Uuid) -> Result<Option<Account>>;
     async fn set_account(&self, account: &Account, ttl: Option<Duration>) -> Result<()>;
+    /// Caches `account` only if its version is greater than the cached one, so a slow
+    /// update can't overwrite newer state. Returns whether the write was applied.
+    async fn set_account_if_newer(
+        &self,
+        account: &Account,
+        ttl: Option<Duration>,
+    ) -> Result<bool>;
     async fn delete_account(&self, account_id: Uuid) -> Result<()>;
     async fn get_account_events(&self, account_id: Uuid) -> Result<Option<Vec<AccountEvent>>>;
     async fn set_account_events(
@@ -113,9 +204,80 @@ pub trait CacheServiceTrait: Send + Sync {
     async fn delete_account_events(&self, account_id: Uuid) -> Result<()>;
     async fn invalidate_account(&self, account_id: Uuid) -> Result<()>;
     async fn warmup_cache(&self, account_ids: Vec<Uuid>) -> Result<()>;
+    /// Returns the cached entry for an account without counting as a hit or miss.
+    async fn inspect_account(&self, account_id: Uuid) -> Result<Option<CachedAccountEntry>>;
+    /// Evicts every cached account and event list. Returns the number of accounts evicted.
+    async fn flush_accounts(&self) -> Result<u64>;
+    /// Removes expired in-process entries without waiting for them to be read, for up
+    /// to `budget`. Returns the number removed. Caches without an in-process tier have
+    /// nothing to sweep.
+    fn sweep_expired(&self, _budget: Duration) -> u64 {
+        0
+    }
     fn get_metrics(&self) -> &CacheMetrics;
 }
 
+/// Sweeps `cache` every `interval`, so entries of accounts that are never read again
+/// don't hold memory until the shard fills up.
+pub fn start_cache_sweeper(
+    cache: Arc<dyn CacheServiceTrait + Send + Sync>,
+    interval: Duration,
+    budget: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let swept = cache.sweep_expired(budget);
+            if swept > 0 {
+                debug!("Swept {} expired cache entries", swept);
+            }
+        }
+    })
+}
+
+/// Sweeps `shards` in turn starting at `cursor`, until every shard was visited once or
+/// `budget` ran out. Each shard is swept whole, collecting expired keys under read
+/// locks before removing them, so writers are only blocked one entry at a time.
+/// `cursor` is left at the first unvisited shard.
+pub(crate) fn sweep_shards<T>(
+    shards: &[DashMap<Uuid, CacheEntry<T>>],
+    cursor: &AtomicUsize,
+    budget: Duration,
+    is_expired: impl Fn(&CacheEntry<T>) -> bool,
+) -> u64 {
+    if shards.is_empty() {
+        return 0;
+    }
+    let started = Instant::now();
+    let mut swept = 0;
+    let mut index = cursor.load(Ordering::Relaxed) % shards.len();
+    for _ in 0..shards.len() {
+        let shard = &shards[index];
+        let expired: Vec<Uuid> = shard
+            .iter()
+            .filter(|entry| is_expired(entry.value()))
+            .map(|entry| *entry.key())
+            .collect();
+        for key in expired {
+            // Re-checked, in case the entry was rewritten since it was collected
+            if shard
+                .remove_if(&key, |_, entry| is_expired(entry))
+                .is_some()
+            {
+                swept += 1;
+            }
+        }
+        index = (index + 1) % shards.len();
+        if started.elapsed() >= budget {
+            break;
+        }
+    }
+    cursor.store(index, Ordering::Relaxed);
+    swept
+}
+
 #[async_trait]
 impl CacheServiceTrait for CacheService {
     async fn get_account(&self, account_id: Uuid) -> Result<Option<Account>> {
@@ -126,6 +288,14 @@ impl CacheServiceTrait for CacheService {
         self.set_account(account, ttl).await
     }
 
+    async fn set_account_if_newer(
+        &self,
+        account: &Account,
+        ttl: Option<Duration>,
+    ) -> Result<bool> {
+        self.set_account_if_newer(account, ttl).await
+    }
+
     async fn delete_account(&self, account_id: Uuid) -> Result<()> {
         self.delete_account(account_id).await
     }
@@ -155,6 +325,18 @@ impl CacheServiceTrait for CacheService {
         self.warmup_cache(account_ids).await
     }
 
+    async fn inspect_account(&self, account_id: Uuid) -> Result<Option<CachedAccountEntry>> {
+        self.inspect_account(account_id).await
+    }
+
+    async fn flush_accounts(&self) -> Result<u64> {
+        self.flush_accounts().await
+    }
+
+    fn sweep_expired(&self, budget: Duration) -> u64 {
+        self.sweep_expired(budget)
+    }
+
     fn get_metrics(&self) -> &CacheMetrics {
         self.get_metrics()
     }
@@ -182,23 +364,12 @@ impl CacheService {
             shards,
             event_cache,
             warming_state,
+            sweep_cursor: Arc::new(AtomicUsize::new(0)),
         };
 
-        // Only start background tasks if not in test mode
+        // Only start background tasks if not in test mode; expired entries are swept
+        // by `start_cache_sweeper`
         if std::env::var("RUST_TEST").is_err() {
-            // Start cache cleanup task
-            let shards_clone = service.shards.clone();
-            let config_clone = service.config.clone();
-            tokio::spawn(async move {
-                let mut interval = tokio::time::interval(Duration::from_secs(300)); // Every 5 minutes
-                loop {
-                    interval.tick().await;
-                    for shard in shards_clone.iter() {
-                        shard.retain(|_, entry| !entry.is_expired());
-                    }
-                }
-            });
-
             // Start metrics reporter
             let metrics_clone = service.metrics.clone();
             tokio::spawn(async move {
@@ -285,7 +456,7 @@ impl CacheService {
         // Try Redis cache
         let mut conn = self.redis_client.get_connection().await?;
         use redis::AsyncCommands;
-        let key = format!("account:{}", account_id);
+        let key = self.config.account_key(account_id);
 
         match conn.get(key.as_bytes()).await {
             Ok(redis::Value::Data(data)) => {
@@ -327,7 +498,7 @@ impl CacheService {
         let ttl = ttl.unwrap_or(self.config.default_ttl);
         let mut conn = self.redis_client.get_connection().await?;
         use redis::AsyncCommands;
-        let key = format!("account:{}", account.id);
+        let key = self.config.account_key(account.id);
         let value = serde_json::to_vec(account)?;
 
         conn.set_ex::<_, _, ()>(key.as_bytes(), &value, ttl.as_secs() as u64)
@@ -339,10 +510,43 @@ impl CacheService {
         Ok(())
     }
 
+    pub async fn set_account_if_newer(
+        &self,
+        account: &Account,
+        ttl: Option<Duration>,
+    ) -> Result<bool> {
+        let shard_index = self.get_shard_index(account.id);
+        let cached_version = self.shards[shard_index]
+            .get(&account.id)
+            .map(|entry| entry.version);
+        if cached_version.is_some_and(|version| version >= account.version) {
+            self.metrics.stale_writes.fetch_add(1, Ordering::Relaxed);
+            return Ok(false);
+        }
+
+        // Compare and set in one script so a concurrent writer can't slip in between
+        let ttl = ttl.unwrap_or(self.config.default_ttl);
+        let mut conn = self.redis_client.get_connection().await?;
+        let applied: i64 = redis::Script::new(SET_IF_NEWER_SCRIPT)
+            .key(self.config.account_key(account.id))
+            .arg(serde_json::to_vec(account)?)
+            .arg(account.version)
+            .arg(ttl.as_secs())
+            .invoke_async(&mut conn)
+            .await?;
+        if applied == 0 {
+            self.metrics.stale_writes.fetch_add(1, Ordering::Relaxed);
+            return Ok(false);
+        }
+
+        self.update_in_memory_cache_if_newer(account);
+        Ok(true)
+    }
+
     pub async fn delete_account(&self, account_id: Uuid) -> Result<()> {
         let mut conn = self.redis_client.get_connection().await?;
         use redis::AsyncCommands;
-        let key = format!("account:{}", account_id);
+        let key = self.config.account_key(account_id);
 
         conn.del::<_, ()>(key.as_bytes()).await?;
         self.metrics
@@ -361,7 +565,7 @@ impl CacheService {
 
         let mut conn = self.redis_client.get_connection().await?;
         use redis::AsyncCommands;
-        let key = format!("events:{}", account_id);
+        let key = self.config.events_key(account_id);
 
         match conn.get(key.as_bytes()).await {
             Ok(redis::Value::Data(data)) => {
@@ -408,7 +612,7 @@ impl CacheService {
         let ttl = ttl.unwrap_or(self.config.default_ttl);
         let mut conn = self.redis_client.get_connection().await?;
         use redis::AsyncCommands;
-        let key = format!("events:{}", account_id);
+        let key = self.config.events_key(account_id);
         let value = serde_json::to_vec(events)?;
 
         conn.set_ex::<_, _, ()>(key.as_bytes(), &value, ttl.as_secs() as u64)
@@ -423,7 +627,7 @@ impl CacheService {
     pub async fn delete_account_events(&self, account_id: Uuid) -> Result<()> {
         let mut conn = self.redis_client.get_connection().await?;
         use redis::AsyncCommands;
-        let key = format!("events:{}", account_id);
+        let key = self.config.events_key(account_id);
 
         conn.del::<_, ()>(key.as_bytes()).await?;
         self.metrics
@@ -441,8 +645,8 @@ impl CacheService {
         // Invalidate Redis cache
         let mut conn = self.redis_client.get_connection().await?;
         use redis::AsyncCommands;
-        let account_key = format!("account:{}", account_id);
-        let events_key = format!("events:{}", account_id);
+        let account_key = self.config.account_key(account_id);
+        let events_key = self.config.events_key(account_id);
 
         conn.del::<_, ()>(account_key.as_bytes()).await?;
         conn.del::<_, ()>(events_key.as_bytes()).await?;
@@ -471,8 +675,8 @@ impl CacheService {
             let mut pipeline = redis::pipe();
 
             for &account_id in chunk {
-                let account_key = format!("account:{}", account_id);
-                let events_key = format!("events:{}", account_id);
+                let account_key = self.config.account_key(account_id);
+                let events_key = self.config.events_key(account_id);
                 pipeline.get(account_key);
                 pipeline.get(events_key);
             }
@@ -509,6 +713,82 @@ impl CacheService {
         Ok(())
     }
 
+    pub async fn inspect_account(&self, account_id: Uuid) -> Result<Option<CachedAccountEntry>> {
+        let shard_index = self.get_shard_index(account_id);
+        if let Some(entry) = self.shards[shard_index].get(&account_id) {
+            return Ok(Some(CachedAccountEntry {
+                account: entry.value.clone(),
+                tier: CacheTier::Memory,
+                created_at: Some(instant_to_utc(entry.created_at)),
+                last_accessed: Some(instant_to_utc(entry.last_accessed)),
+                access_count: Some(entry.access_count),
+                expired: self.is_expired(&entry),
+            }));
+        }
+
+        let mut conn = self.redis_client.get_connection().await?;
+        use redis::AsyncCommands;
+        let key = self.config.account_key(account_id);
+        let data: Option<Vec<u8>> = conn.get(key.as_bytes()).await?;
+        match data {
+            Some(data) => Ok(Some(CachedAccountEntry {
+                account: serde_json::from_slice(&data)?,
+                tier: CacheTier::Redis,
+                created_at: None,
+                last_accessed: None,
+                access_count: None,
+                expired: false,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn flush_accounts(&self) -> Result<u64> {
+        let mut evicted: std::collections::HashSet<Uuid> = std::collections::HashSet::new();
+        for shard in self.shards.iter() {
+            evicted.extend(shard.iter().map(|entry| *entry.key()));
+            shard.clear();
+        }
+        self.event_cache.clear();
+
+        // Only this namespace and version; entries of other versions expire on their own
+        let mut conn = self.redis_client.get_connection().await?;
+        let accounts = format!("{}account:", self.config.key_namespace());
+        let events = format!("{}events:", self.config.key_namespace());
+        for prefix in [&accounts, &events] {
+            let keys = scan_keys(&mut conn, &format!("{}*", prefix)).await?;
+            if prefix == &accounts {
+                evicted.extend(
+                    keys.iter()
+                        .filter_map(|key| key.strip_prefix(accounts.as_str()))
+                        .filter_map(|id| Uuid::parse_str(id).ok()),
+                );
+            }
+            for chunk in keys.chunks(500) {
+                redis::cmd("DEL")
+                    .arg(chunk)
+                    .query_async::<_, ()>(&mut conn)
+                    .await?;
+            }
+        }
+
+        self.metrics
+            .evictions
+            .fetch_add(evicted.len() as u64, std::sync::atomic::Ordering::Relaxed);
+        info!("Flushed {} cached accounts", evicted.len());
+        Ok(evicted.len() as u64)
+    }
+
+    pub fn sweep_expired(&self, budget: Duration) -> u64 {
+        let swept = sweep_shards(&self.shards, &self.sweep_cursor, budget, |entry| {
+            self.is_expired(entry)
+        });
+        self.metrics
+            .entries_swept
+            .fetch_add(swept, Ordering::Relaxed);
+        swept
+    }
+
     fn get_shard_index(&self, account_id: Uuid) -> usize {
         (account_id.as_u128() % self.config.shard_count as u128) as usize
     }
@@ -520,11 +800,12 @@ impl CacheService {
     fn update_in_memory_cache(&self, account_id: Uuid, account: Account) {
         let shard_index = self.get_shard_index(account_id);
         let entry = CacheEntry {
-            value: account,
             created_at: Instant::now(),
             last_accessed: Instant::now(),
             access_count: 1,
             ttl: self.config.default_ttl,
+            version: account.version,
+            value: account,
         };
 
         // Apply eviction policy if needed
@@ -535,6 +816,30 @@ impl CacheService {
         self.shards[shard_index].insert(account_id, entry);
     }
 
+    fn update_in_memory_cache_if_newer(&self, account: &Account) {
+        let shard_index = self.get_shard_index(account.id);
+        let shard = &self.shards[shard_index];
+        // Evict before taking the entry lock; eviction iterates the same shard
+        if shard.len() >= self.config.max_size && !shard.contains_key(&account.id) {
+            self.evict_entries(shard_index);
+        }
+
+        match shard.entry(account.id) {
+            dashmap::mapref::entry::Entry::Occupied(entry)
+                if entry.get().version >= account.version => {}
+            entry => {
+                entry.insert(CacheEntry {
+                    value: account.clone(),
+                    created_at: Instant::now(),
+                    last_accessed: Instant::now(),
+                    access_count: 1,
+                    ttl: self.config.default_ttl,
+                    version: account.version,
+                });
+            }
+        }
+    }
+
     fn evict_entries(&self, shard_index: usize) {
         match self.config.eviction_policy {
             EvictionPolicy::LRU => {
@@ -569,6 +874,41 @@ impl CacheService {
     }
 }
 
+/// Writes ARGV[1] to KEYS[1] unless the cached account's version is already at least
+/// ARGV[2]. Returns 1 when the value was written.
+const SET_IF_NEWER_SCRIPT: &str = r#"
+local current = redis.call('GET', KEYS[1])
+if current then
+    local ok, cached = pcall(cjson.decode, current)
+    if ok and tonumber(cached.version) and tonumber(cached.version) >= tonumber(ARGV[2]) then
+        return 0
+    end
+end
+redis.call('SET', KEYS[1], ARGV[1], 'EX', ARGV[3])
+return 1
+"#;
+
+/// Collects the keys matching `pattern` with SCAN, which unlike KEYS doesn't block Redis.
+pub(crate) async fn scan_keys(conn: &mut MultiplexedConnection, pattern: &str) -> Result<Vec<String>> {
+    let mut keys = Vec::new();
+    let mut cursor: u64 = 0;
+    loop {
+        let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg(pattern)
+            .arg("COUNT")
+            .arg(500)
+            .query_async(conn)
+            .await?;
+        keys.extend(batch);
+        if next == 0 {
+            return Ok(keys);
+        }
+        cursor = next;
+    }
+}
+
 impl Default for CacheConfig {
     fn default() -> Self {
         Self {
@@ -578,6 +918,11 @@ impl Default for CacheConfig {
             warmup_batch_size: 100,
             warmup_interval: Duration::from_secs(300),
             eviction_policy: EvictionPolicy::LRU,
+            backend: CacheBackend::default(),
+            key_prefix: String::new(),
+            schema_version: CACHE_SCHEMA_VERSION,
+            sweep_interval: Duration::from_secs(30),
+            sweep_budget: Duration::from_millis(5),
         }
     }
 }
@@ -684,6 +1029,8 @@ mod tests {
             balance: 1000.into(),
             is_active: true,
             version: 1,
+            owner_user_id: None,
+            ..Account::default()
         };
 
         let cache_service = CacheService::new(Arc::new(redis_client), CacheConfig::default());
@@ -693,4 +1040,103 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap().unwrap().id, account_id);
     }
+
+    #[tokio::test]
+    async fn test_set_account_if_newer_rejects_lower_versions() {
+        let client = Client::open("redis://127.0.0.1/").unwrap();
+        let redis_client = TestRedisClient { client };
+        let cache_service = CacheService::new(Arc::new(redis_client), CacheConfig::default());
+        let account = Account {
+            id: Uuid::new_v4(),
+            owner_name: "Test User".to_string(),
+            balance: 1000.into(),
+            is_active: true,
+            version: 5,
+            ..Account::default()
+        };
+        assert!(cache_service
+            .set_account_if_newer(&account, None)
+            .await
+            .unwrap());
+
+        // A slow writer still holding version 4 must not overwrite version 5
+        let stale = Account {
+            balance: 900.into(),
+            version: 4,
+            ..account.clone()
+        };
+        assert!(!cache_service.set_account_if_newer(&stale, None).await.unwrap());
+        // Nor may it win in Redis once the in-memory entry is gone
+        cache_service.shards[cache_service.get_shard_index(account.id)].remove(&account.id);
+        assert!(!cache_service.set_account_if_newer(&stale, None).await.unwrap());
+        assert_eq!(
+            cache_service.get_account(account.id).await.unwrap().unwrap().balance,
+            Decimal::from(1000)
+        );
+
+        let newer = Account {
+            balance: 1100.into(),
+            version: 6,
+            ..account.clone()
+        };
+        assert!(cache_service.set_account_if_newer(&newer, None).await.unwrap());
+        assert_eq!(
+            cache_service.get_account(account.id).await.unwrap().unwrap().balance,
+            Decimal::from(1100)
+        );
+        assert_eq!(cache_service.get_metrics().stale_writes.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_schema_version_bump_misses_old_entries() {
+        let client = Client::open("redis://127.0.0.1/").unwrap();
+        let config = |schema_version| CacheConfig {
+            key_prefix: format!("test-{}", Uuid::new_v4()),
+            schema_version,
+            ..CacheConfig::default()
+        };
+        let old_config = config(1);
+        let new_config = CacheConfig {
+            key_prefix: old_config.key_prefix.clone(),
+            ..config(2)
+        };
+        let old = CacheService::new(
+            Arc::new(TestRedisClient {
+                client: client.clone(),
+            }),
+            old_config,
+        );
+        let new = CacheService::new(Arc::new(TestRedisClient { client }), new_config);
+        let account = Account {
+            id: Uuid::new_v4(),
+            owner_name: "Test User".to_string(),
+            balance: 1000.into(),
+            version: 1,
+            ..Account::default()
+        };
+        old.set_account(&account, None).await.unwrap();
+
+        // The new version never sees the blob written under the old one
+        assert!(new.get_account(account.id).await.unwrap().is_none());
+        let repopulated = Account {
+            balance: 1200.into(),
+            ..account.clone()
+        };
+        new.set_account(&repopulated, None).await.unwrap();
+        new.shards[new.get_shard_index(account.id)].remove(&account.id);
+        assert_eq!(
+            new.get_account(account.id).await.unwrap().unwrap().balance,
+            Decimal::from(1200)
+        );
+
+        // Neither overwrote the other
+        old.shards[old.get_shard_index(account.id)].remove(&account.id);
+        assert_eq!(
+            old.get_account(account.id).await.unwrap().unwrap().balance,
+            Decimal::from(1000)
+        );
+        assert_eq!(old.flush_accounts().await.unwrap(), 1);
+        assert!(new.get_account(account.id).await.unwrap().is_some());
+        new.flush_accounts().await.unwrap();
+    }
 }
@@ -0,0 +1,293 @@
+use crate::infrastructure::config::CompactionConfig;
+use crate::infrastructure::event_store::{EventStoreError, EventStoreTrait};
+use crate::infrastructure::maintenance::MaintenanceTask;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+#[derive(Error, Debug)]
+pub enum CompactionError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Event store error: {0}")]
+    EventStore(#[from] EventStoreError),
+}
+
+/// What is left of a compacted account: its state when it was compacted and how much
+/// history was removed.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountTombstone {
+    pub account_id: Uuid,
+    pub final_version: i64,
+    pub final_state: serde_json::Value,
+    pub events_removed: i64,
+    pub closed_at: DateTime<Utc>,
+    pub compacted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LegalHold {
+    pub account_id: Uuid,
+    pub reason: String,
+    pub placed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum CompactionOutcome {
+    Compacted {
+        events_removed: i64,
+    },
+    /// Still open, or closed within the retention period.
+    NotDue,
+    OnLegalHold,
+    AlreadyCompacted,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompactionReport {
+    pub compacted: Vec<Uuid>,
+    pub events_removed: u64,
+    /// Accounts past retention that were kept because of a legal hold.
+    pub held: u64,
+}
+
+/// Frees the event history of accounts closed for longer than the retention period. Each
+/// account is snapshotted at its final version, a tombstone recording that state is
+/// written and its events are deleted, so it still loads from the snapshot.
+///
+/// A full projection rebuild replays events only, so it won't bring back compacted
+/// accounts; their last state is in `account_tombstones`.
+pub struct EventCompactor {
+    event_store: Arc<dyn EventStoreTrait>,
+    pool: PgPool,
+    config: CompactionConfig,
+}
+
+impl EventCompactor {
+    pub fn new(event_store: Arc<dyn EventStoreTrait>, config: CompactionConfig) -> Self {
+        let pool = event_store.get_pool();
+        Self {
+            event_store,
+            pool,
+            config,
+        }
+    }
+
+    /// Compacts up to `batch_size` accounts that were closed before `now` minus the
+    /// retention period and have no legal hold.
+    pub async fn compact(&self, now: DateTime<Utc>) -> Result<CompactionReport, CompactionError> {
+        let cutoff = self.cutoff(now);
+        let candidates = sqlx::query!(
+            r#"
+            SELECT e.aggregate_id, MAX(e.timestamp) AS "closed_at!",
+                   EXISTS (
+                       SELECT 1 FROM account_legal_holds h WHERE h.account_id = e.aggregate_id
+                   ) AS "on_hold!"
+            FROM events e
+            WHERE e.event_type = 'AccountClosed'
+              AND e.timestamp < $1
+              AND NOT EXISTS (
+                  SELECT 1 FROM account_tombstones t WHERE t.account_id = e.aggregate_id
+              )
+            GROUP BY e.aggregate_id
+            ORDER BY MAX(e.timestamp)
+            LIMIT $2
+            "#,
+            cutoff,
+            self.config.batch_size
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut report = CompactionReport::default();
+        for candidate in candidates {
+            if candidate.on_hold {
+                report.held += 1;
+                continue;
+            }
+            match self
+                .compact_closed_account(candidate.aggregate_id, candidate.closed_at)
+                .await?
+            {
+                Some(removed) => {
+                    report.compacted.push(candidate.aggregate_id);
+                    report.events_removed += removed as u64;
+                }
+                None => report.held += 1,
+            }
+        }
+
+        info!(
+            "Compacted {} closed accounts, removing {} events ({} on legal hold)",
+            report.compacted.len(),
+            report.events_removed,
+            report.held
+        );
+        Ok(report)
+    }
+
+    /// Compacts a single account if it was closed before `now` minus the retention
+    /// period and has no legal hold.
+    pub async fn compact_account(
+        &self,
+        account_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<CompactionOutcome, CompactionError> {
+        let state = sqlx::query!(
+            r#"
+            SELECT (
+                       SELECT MAX(timestamp) FROM events
+                       WHERE aggregate_id = $1 AND event_type = 'AccountClosed'
+                   ) AS closed_at,
+                   EXISTS (
+                       SELECT 1 FROM account_legal_holds WHERE account_id = $1
+                   ) AS "on_hold!",
+                   EXISTS (
+                       SELECT 1 FROM account_tombstones WHERE account_id = $1
+                   ) AS "compacted!"
+            "#,
+            account_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        if state.compacted {
+            return Ok(CompactionOutcome::AlreadyCompacted);
+        }
+        let Some(closed_at) = state.closed_at.filter(|at| *at < self.cutoff(now)) else {
+            return Ok(CompactionOutcome::NotDue);
+        };
+        if state.on_hold {
+            return Ok(CompactionOutcome::OnLegalHold);
+        }
+        Ok(
+            match self.compact_closed_account(account_id, closed_at).await? {
+                Some(events_removed) => CompactionOutcome::Compacted { events_removed },
+                None => CompactionOutcome::OnLegalHold,
+            },
+        )
+    }
+
+    fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - chrono::Duration::from_std(self.config.retention).unwrap_or(chrono::Duration::MAX)
+    }
+
+    /// Snapshots the account and replaces its events with a tombstone. Returns the number
+    /// of events removed, or `None` if it was left alone because a legal hold was placed
+    /// in the meantime.
+    async fn compact_closed_account(
+        &self,
+        account_id: Uuid,
+        closed_at: DateTime<Utc>,
+    ) -> Result<Option<i64>, CompactionError> {
+        let Some(version) = self.event_store.save_snapshot(account_id).await? else {
+            return Ok(None);
+        };
+
+        let mut tx = self.pool.begin().await?;
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO account_tombstones
+                (account_id, final_version, final_state, events_removed, closed_at)
+            SELECT s.aggregate_id, s.version, s.snapshot_data,
+                   (SELECT COUNT(*) FROM events WHERE aggregate_id = $1 AND version <= $2), $3
+            FROM snapshots s
+            WHERE s.aggregate_id = $1 AND s.version = $2
+              AND NOT EXISTS (SELECT 1 FROM account_legal_holds WHERE account_id = $1)
+            ON CONFLICT (account_id) DO NOTHING
+            RETURNING events_removed
+            "#,
+            account_id,
+            version,
+            closed_at
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(inserted) = inserted else {
+            tx.rollback().await?;
+            warn!(
+                "Account {} was not compacted: it is on legal hold or changed",
+                account_id
+            );
+            return Ok(None);
+        };
+
+        sqlx::query!(
+            "DELETE FROM events WHERE aggregate_id = $1 AND version <= $2",
+            account_id,
+            version
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Some(inserted.events_removed))
+    }
+
+    pub async fn tombstone(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<AccountTombstone>, CompactionError> {
+        Ok(sqlx::query_as!(
+            AccountTombstone,
+            r#"
+            SELECT account_id, final_version, final_state, events_removed, closed_at,
+                   compacted_at
+            FROM account_tombstones
+            WHERE account_id = $1
+            "#,
+            account_id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// Exempts an account from compaction until the hold is released. Placing a hold
+    /// again replaces its reason.
+    pub async fn place_legal_hold(
+        &self,
+        account_id: Uuid,
+        reason: &str,
+    ) -> Result<LegalHold, CompactionError> {
+        Ok(sqlx::query_as!(
+            LegalHold,
+            r#"
+            INSERT INTO account_legal_holds (account_id, reason)
+            VALUES ($1, $2)
+            ON CONFLICT (account_id) DO UPDATE SET reason = EXCLUDED.reason
+            RETURNING account_id, reason, placed_at
+            "#,
+            account_id,
+            reason
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    /// Returns whether the account had a hold.
+    pub async fn release_legal_hold(&self, account_id: Uuid) -> Result<bool, CompactionError> {
+        let result = sqlx::query!(
+            "DELETE FROM account_legal_holds WHERE account_id = $1",
+            account_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[async_trait]
+impl MaintenanceTask for EventCompactor {
+    fn name(&self) -> &'static str {
+        "compaction"
+    }
+
+    async fn run(&self) -> anyhow::Result<u64> {
+        Ok(self.compact(Utc::now()).await?.compacted.len() as u64)
+    }
+}
@@ -1,3 +1,9 @@
+use crate::domain::{AccountRules, AccountType, InitialBalancePolicy, VelocityCaps};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use uuid::Uuid;
+
 #[derive(Debug, Clone)]
 pub struct AppConfig {
     pub database_pool_size: u32,
@@ -19,4 +25,1028 @@ impl Default for AppConfig {
             port: 3000,
         }
     }
-} 
\ No newline at end of file
+} 
+
+/// Upper bounds on request body sizes, in bytes.
+#[derive(Debug, Clone)]
+pub struct RequestLimitsConfig {
+    pub max_body_bytes: usize,
+    /// Bulk endpoints such as batch transactions carry many items per request.
+    pub max_bulk_body_bytes: usize,
+}
+
+impl Default for RequestLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 64 * 1024,
+            max_bulk_body_bytes: 10 * 1024 * 1024,
+        }
+    }
+}
+
+impl RequestLimitsConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_body_bytes: std::env::var("MAX_REQUEST_BODY_BYTES")
+                .unwrap_or_else(|_| default.max_body_bytes.to_string())
+                .parse()
+                .unwrap_or(default.max_body_bytes),
+            max_bulk_body_bytes: std::env::var("MAX_BULK_REQUEST_BODY_BYTES")
+                .unwrap_or_else(|_| default.max_bulk_body_bytes.to_string())
+                .parse()
+                .unwrap_or(default.max_bulk_body_bytes),
+        }
+    }
+}
+
+/// Cross-origin settings for browser clients.
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// How long browsers may cache a preflight response.
+    pub max_age: Duration,
+    /// Response headers that client-side scripts are allowed to read.
+    pub expose_headers: Vec<String>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            max_age: Duration::from_secs(3600),
+            expose_headers: vec!["etag".to_string(), "x-request-id".to_string()],
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_age: std::env::var("CORS_MAX_AGE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(default.max_age),
+            expose_headers: std::env::var("CORS_EXPOSE_HEADERS")
+                .map(|v| {
+                    v.split(',')
+                        .map(|h| h.trim().to_string())
+                        .filter(|h| !h.is_empty())
+                        .collect()
+                })
+                .unwrap_or(default.expose_headers),
+        }
+    }
+}
+
+/// Wire formats the API speaks. JSON is always available and the default; MessagePack
+/// is used for requests sent with `Content-Type: application/msgpack` and responses to
+/// requests that prefer it in `Accept`.
+#[derive(Debug, Clone)]
+pub struct ContentNegotiationConfig {
+    pub msgpack_enabled: bool,
+}
+
+impl Default for ContentNegotiationConfig {
+    fn default() -> Self {
+        Self {
+            msgpack_enabled: true,
+        }
+    }
+}
+
+impl ContentNegotiationConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            msgpack_enabled: std::env::var("API_MSGPACK_ENABLED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.msgpack_enabled),
+        }
+    }
+}
+
+/// HTTP caching of account reads. Responses carry an `ETag` and `Cache-Control`, and a
+/// request whose `If-None-Match` still matches gets 304 Not Modified without a body.
+#[derive(Debug, Clone)]
+pub struct ResponseCacheConfig {
+    pub enabled: bool,
+    /// How long clients may reuse a response without revalidating. Zero makes them
+    /// revalidate every time, which is cheap with a 304.
+    pub max_age: Duration,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_age: Duration::ZERO,
+        }
+    }
+}
+
+impl ResponseCacheConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            enabled: std::env::var("RESPONSE_CACHE_ENABLED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.enabled),
+            max_age: std::env::var("RESPONSE_CACHE_MAX_AGE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(default.max_age),
+        }
+    }
+
+    /// Account state is per user, so shared caches must not store it.
+    pub fn cache_control(&self) -> String {
+        if self.max_age.is_zero() {
+            "private, no-cache".to_string()
+        } else {
+            format!("private, max-age={}", self.max_age.as_secs())
+        }
+    }
+}
+
+/// Namespace account ids are derived in unless `ACCOUNT_ID_NAMESPACE` sets another.
+pub const DEFAULT_ACCOUNT_ID_NAMESPACE: Uuid =
+    Uuid::from_u128(0x6d1f_4b0e_9c2a_4e57_8a3d_2f60_b1c7_e945);
+
+/// How account ids are chosen. Accounts created without an external reference get a
+/// random v4 id; with one, a v5 id derived from `namespace`, the owner and the
+/// reference, so re-creating from the same source yields the same id. Integrations
+/// that mint ids themselves can share the namespace to compute them up front.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountIdConfig {
+    pub namespace: Uuid,
+}
+
+impl Default for AccountIdConfig {
+    fn default() -> Self {
+        Self {
+            namespace: DEFAULT_ACCOUNT_ID_NAMESPACE,
+        }
+    }
+}
+
+impl AccountIdConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            namespace: std::env::var("ACCOUNT_ID_NAMESPACE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.namespace),
+        }
+    }
+
+    /// The id an account created by `owner_user_id` with `reference` gets. References
+    /// are scoped per owner, so two users can't collide on the same reference.
+    pub fn id_for_reference(&self, owner_user_id: Option<Uuid>, reference: &str) -> Uuid {
+        let name = format!("{}:{}", owner_user_id.unwrap_or_else(Uuid::nil), reference);
+        Uuid::new_v5(&self.namespace, name.as_bytes())
+    }
+}
+
+/// How the per-request access log line is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AccessLogFormat {
+    /// `GET /api/health 200 0.4ms user=- request_id=...`
+    #[default]
+    Text,
+    /// The same fields as one JSON object.
+    Json,
+}
+
+/// One log line per completed request, under the `access_log` target. 5xx responses are
+/// logged at error level, everything else at info.
+#[derive(Debug, Clone)]
+pub struct AccessLogConfig {
+    pub enabled: bool,
+    pub format: AccessLogFormat,
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            format: AccessLogFormat::Text,
+        }
+    }
+}
+
+impl AccessLogConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            enabled: std::env::var("ACCESS_LOG_ENABLED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.enabled),
+            format: match std::env::var("ACCESS_LOG_FORMAT")
+                .unwrap_or_default()
+                .to_ascii_lowercase()
+                .as_str()
+            {
+                "json" => AccessLogFormat::Json,
+                "text" => AccessLogFormat::Text,
+                _ => default.format,
+            },
+        }
+    }
+}
+
+/// Which authorization decisions are written to the audit store. Denials are counted in
+/// the metrics whatever the mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuthorizationAuditMode {
+    Off,
+    /// Denials, plus the allowed decisions of the routes listed in `allowed_routes`.
+    #[default]
+    Denied,
+    /// Every decision.
+    All,
+}
+
+/// Which authorization decisions of guarded operations are audited.
+#[derive(Debug, Clone, Default)]
+pub struct AuthorizationAuditConfig {
+    pub mode: AuthorizationAuditMode,
+    /// Routes, as mounted without a version prefix (e.g. `/admin/cache/accounts`), whose
+    /// allowed decisions are recorded as well as their denials.
+    pub allowed_routes: HashSet<String>,
+}
+
+impl AuthorizationAuditConfig {
+    pub fn from_env() -> Self {
+        Self {
+            mode: match std::env::var("AUTHORIZATION_AUDIT_MODE")
+                .unwrap_or_default()
+                .to_ascii_lowercase()
+                .as_str()
+            {
+                "off" => AuthorizationAuditMode::Off,
+                "all" => AuthorizationAuditMode::All,
+                _ => AuthorizationAuditMode::Denied,
+            },
+            allowed_routes: std::env::var("AUTHORIZATION_AUDIT_ALLOWED_ROUTES")
+                .map(|v| {
+                    v.split(',')
+                        .map(|route| route.trim().to_string())
+                        .filter(|route| !route.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Whether a decision on `route` with this outcome is written to the audit store.
+    pub fn records(&self, route: &str, allowed: bool) -> bool {
+        match self.mode {
+            AuthorizationAuditMode::Off => false,
+            AuthorizationAuditMode::Denied => !allowed || self.allowed_routes.contains(route),
+            AuthorizationAuditMode::All => true,
+        }
+    }
+}
+
+/// Warn-level log line, under the `slow_query` target, for event-store calls that take
+/// at least `threshold`.
+#[derive(Debug, Clone)]
+pub struct SlowQueryLogConfig {
+    pub enabled: bool,
+    pub threshold: Duration,
+}
+
+impl Default for SlowQueryLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: Duration::from_millis(500),
+        }
+    }
+}
+
+impl SlowQueryLogConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            enabled: std::env::var("EVENT_STORE_SLOW_QUERY_LOG_ENABLED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.enabled),
+            threshold: std::env::var("EVENT_STORE_SLOW_QUERY_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(default.threshold),
+        }
+    }
+}
+
+/// Response-time objective: the `percentile` of responses completed within the last
+/// `window` should take at most `target`. Checked every `evaluation_interval`.
+#[derive(Debug, Clone)]
+pub struct SloConfig {
+    pub enabled: bool,
+    pub target: Duration,
+    /// Between 0 and 100, e.g. 99 for p99.
+    pub percentile: f64,
+    pub window: Duration,
+    pub evaluation_interval: Duration,
+}
+
+impl Default for SloConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            target: Duration::from_millis(500),
+            percentile: 99.0,
+            window: Duration::from_secs(300),
+            evaluation_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+impl SloConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            enabled: std::env::var("SLO_ENABLED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.enabled),
+            target: std::env::var("SLO_TARGET_LATENCY_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(default.target),
+            percentile: std::env::var("SLO_PERCENTILE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|percentile: &f64| *percentile > 0.0 && *percentile <= 100.0)
+                .unwrap_or(default.percentile),
+            window: std::env::var("SLO_WINDOW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|secs: &u64| *secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(default.window),
+            evaluation_interval: std::env::var("SLO_EVALUATION_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|secs: &u64| *secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(default.evaluation_interval),
+        }
+    }
+}
+
+/// How the application's log lines are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines with file and line numbers.
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log pipelines.
+    Json,
+}
+
+/// Structured fields holding balances and amounts, masked when `LOG_REDACT_AMOUNTS` is set.
+pub const AMOUNT_LOG_FIELDS: [&str; 5] = [
+    "amount",
+    "balance",
+    "initial_balance",
+    "balance_before",
+    "balance_after",
+];
+
+/// Log output and redaction. With `redact`, the `redacted_fields` are masked wherever
+/// they are recorded as structured fields of an event or span; values formatted into a
+/// message are written as they are.
+#[derive(Debug, Clone)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    pub redact: bool,
+    pub redacted_fields: Vec<String>,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Pretty,
+            redact: false,
+            redacted_fields: vec!["owner_name".to_string()],
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// Redaction defaults to on when `DEPLOYMENT_ENVIRONMENT` is `production`.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let production = std::env::var("DEPLOYMENT_ENVIRONMENT")
+            .is_ok_and(|environment| environment.eq_ignore_ascii_case("production"));
+        let mut redacted_fields = std::env::var("LOG_REDACTED_FIELDS")
+            .map(|fields| {
+                fields
+                    .split(',')
+                    .map(|field| field.trim().to_string())
+                    .filter(|field| !field.is_empty())
+                    .collect()
+            })
+            .unwrap_or(default.redacted_fields);
+        let redact_amounts = std::env::var("LOG_REDACT_AMOUNTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false);
+        if redact_amounts {
+            redacted_fields.extend(AMOUNT_LOG_FIELDS.iter().map(|field| field.to_string()));
+        }
+        Self {
+            format: match std::env::var("LOG_FORMAT")
+                .unwrap_or_default()
+                .to_ascii_lowercase()
+                .as_str()
+            {
+                "json" => LogFormat::Json,
+                "pretty" => LogFormat::Pretty,
+                _ => default.format,
+            },
+            redact: std::env::var("LOG_REDACT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(production),
+            redacted_fields,
+        }
+    }
+}
+
+/// Security headers added to every response. `None` leaves a header out; handlers that
+/// set one themselves keep their value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityHeadersConfig {
+    pub content_type_options: Option<String>,
+    pub frame_options: Option<String>,
+    /// Only honoured by browsers over HTTPS, so harmless behind a plain-HTTP proxy hop.
+    pub strict_transport_security: Option<String>,
+    /// The default allows the inline script and styles of the bundled `static/index.html`;
+    /// API-only deployments can tighten it to `default-src 'none'; frame-ancestors 'none'`.
+    pub content_security_policy: Option<String>,
+    pub referrer_policy: Option<String>,
+}
+
+impl Default for SecurityHeadersConfig {
+    fn default() -> Self {
+        Self {
+            content_type_options: Some("nosniff".to_string()),
+            frame_options: Some("DENY".to_string()),
+            strict_transport_security: Some("max-age=31536000; includeSubDomains".to_string()),
+            content_security_policy: Some(
+                "default-src 'self'; script-src 'self' 'unsafe-inline'; \
+                 style-src 'self' 'unsafe-inline'; object-src 'none'; base-uri 'self'; \
+                 frame-ancestors 'none'"
+                    .to_string(),
+            ),
+            referrer_policy: Some("no-referrer".to_string()),
+        }
+    }
+}
+
+impl SecurityHeadersConfig {
+    /// Each header can be overridden with its variable, or disabled by setting it empty.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let header = |key: &str, default: Option<String>| match std::env::var(key) {
+            Ok(v) if v.trim().is_empty() => None,
+            Ok(v) => Some(v.trim().to_string()),
+            Err(_) => default,
+        };
+        Self {
+            content_type_options: header(
+                "SECURITY_X_CONTENT_TYPE_OPTIONS",
+                default.content_type_options,
+            ),
+            frame_options: header("SECURITY_X_FRAME_OPTIONS", default.frame_options),
+            strict_transport_security: header(
+                "SECURITY_STRICT_TRANSPORT_SECURITY",
+                default.strict_transport_security,
+            ),
+            content_security_policy: header(
+                "SECURITY_CONTENT_SECURITY_POLICY",
+                default.content_security_policy,
+            ),
+            referrer_policy: header("SECURITY_REFERRER_POLICY", default.referrer_policy),
+        }
+    }
+}
+
+/// Socket and HTTP connection settings for the API listener.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// Pending connections the kernel queues before `accept`; capped by
+    /// `net.core.somaxconn`.
+    pub listen_backlog: u32,
+    /// Disables Nagle's algorithm on accepted connections, so small responses aren't
+    /// delayed.
+    pub tcp_nodelay: bool,
+    /// Whether HTTP/1.1 connections are reused for further requests.
+    pub http_keep_alive: bool,
+    /// How long a connection may wait for the next request's headers before it is
+    /// closed. Bounds both idle keep-alive connections and slow clients.
+    pub keep_alive_idle_timeout: Duration,
+    /// How long shutdown waits for in-flight requests before closing their connections.
+    pub drain_timeout: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_backlog: 1024,
+            tcp_nodelay: true,
+            http_keep_alive: true,
+            keep_alive_idle_timeout: Duration::from_secs(60),
+            drain_timeout: Duration::from_secs(25),
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            listen_backlog: std::env::var("SERVER_LISTEN_BACKLOG")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.listen_backlog),
+            tcp_nodelay: std::env::var("SERVER_TCP_NODELAY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.tcp_nodelay),
+            http_keep_alive: std::env::var("HTTP_KEEP_ALIVE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.http_keep_alive),
+            keep_alive_idle_timeout: std::env::var("HTTP_KEEP_ALIVE_IDLE_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(default.keep_alive_idle_timeout),
+            drain_timeout: std::env::var("SERVER_DRAIN_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(default.drain_timeout),
+        }
+    }
+}
+
+/// How often periodic metrics are logged. The first report waits a random delay of up
+/// to `max_jitter`, so instances started together don't report in lockstep.
+#[derive(Debug, Clone)]
+pub struct MetricsReporterConfig {
+    pub interval: Duration,
+    pub max_jitter: Duration,
+}
+
+impl Default for MetricsReporterConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+            max_jitter: Duration::from_secs(60),
+        }
+    }
+}
+
+impl MetricsReporterConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            interval: std::env::var("METRICS_REPORT_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(default.interval),
+            max_jitter: std::env::var("METRICS_REPORT_JITTER_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(default.max_jitter),
+        }
+    }
+
+    /// Picks the delay before the first report, uniformly within `[0, max_jitter]`.
+    pub fn initial_delay(&self) -> Duration {
+        use rand::Rng;
+        rand::thread_rng().gen_range(Duration::ZERO..=self.max_jitter)
+    }
+}
+
+/// Bounds for the repository's batch flush interval. The interval halves after a flush of
+/// at least `high_watermark` events and doubles after an empty one; reaching the
+/// watermark also triggers a flush straight away.
+///
+/// The buffer holds at most `max_pending_events` events and `max_pending_bytes` bytes of
+/// serialized events (0 leaves either limit off); `overflow` decides what happens to a
+/// write that doesn't fit.
+#[derive(Debug, Clone)]
+pub struct BatchFlushConfig {
+    pub min_interval: Duration,
+    pub max_interval: Duration,
+    pub high_watermark: usize,
+    pub max_pending_events: usize,
+    pub max_pending_bytes: usize,
+    pub overflow: BatchOverflowPolicy,
+}
+
+impl Default for BatchFlushConfig {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_millis(5),
+            max_interval: Duration::from_millis(200),
+            high_watermark: 100,
+            max_pending_events: 10_000,
+            max_pending_bytes: 0,
+            overflow: BatchOverflowPolicy::default(),
+        }
+    }
+}
+
+impl BatchFlushConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            min_interval: std::env::var("BATCH_FLUSH_MIN_INTERVAL_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(default.min_interval),
+            max_interval: std::env::var("BATCH_FLUSH_MAX_INTERVAL_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(default.max_interval),
+            high_watermark: std::env::var("BATCH_FLUSH_HIGH_WATERMARK")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.high_watermark),
+            max_pending_events: std::env::var("BATCH_MAX_PENDING_EVENTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.max_pending_events),
+            max_pending_bytes: std::env::var("BATCH_MAX_PENDING_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.max_pending_bytes),
+            overflow: BatchOverflowPolicy::from_env(),
+        }
+    }
+
+    /// The interval to wait after a flush of `flushed` events.
+    pub fn next_interval(&self, current: Duration, flushed: usize) -> Duration {
+        let next = if flushed >= self.high_watermark {
+            current / 2
+        } else if flushed == 0 {
+            current * 2
+        } else {
+            current
+        };
+        next.max(self.min_interval).min(self.max_interval)
+    }
+}
+
+/// What `save_batched` does with a write that would push the batch buffer past its limits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BatchOverflowPolicy {
+    /// Flush now and wait for the flush to finish before queueing the write.
+    #[default]
+    Backpressure,
+    /// Skip the buffer and save the write straight away.
+    SaveImmediately,
+}
+
+impl BatchOverflowPolicy {
+    pub fn from_env() -> Self {
+        match std::env::var("BATCH_OVERFLOW_POLICY")
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "save_immediately" | "immediate" => BatchOverflowPolicy::SaveImmediately,
+            _ => BatchOverflowPolicy::Backpressure,
+        }
+    }
+}
+
+/// How the Kafka event processor groups consumed events into one projection write.
+/// A batch is written once it holds `max_events` events or its first event has waited
+/// `max_linger`, whichever comes first.
+#[derive(Debug, Clone)]
+pub struct ProjectionBatchConfig {
+    pub max_events: usize,
+    pub max_linger: Duration,
+}
+
+impl Default for ProjectionBatchConfig {
+    fn default() -> Self {
+        Self {
+            max_events: 500,
+            max_linger: Duration::from_millis(50),
+        }
+    }
+}
+
+impl ProjectionBatchConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_events: std::env::var("PROJECTION_BATCH_MAX_EVENTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.max_events),
+            max_linger: std::env::var("PROJECTION_BATCH_MAX_LINGER_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(default.max_linger),
+        }
+    }
+}
+
+/// Schedule for background maintenance (snapshots and the like). Each run is claimed
+/// through a lock held for one `interval`, so across instances it runs once per interval.
+#[derive(Debug, Clone)]
+pub struct MaintenanceConfig {
+    pub enabled: bool,
+    pub interval: Duration,
+    /// Aggregates with more events than this are snapshotted.
+    pub snapshot_min_events: i64,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: Duration::from_secs(3600),
+            snapshot_min_events: 500,
+        }
+    }
+}
+
+impl MaintenanceConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            enabled: std::env::var("MAINTENANCE_ENABLED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.enabled),
+            interval: std::env::var("MAINTENANCE_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|secs: &u64| *secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(default.interval),
+            snapshot_min_events: std::env::var("MAINTENANCE_SNAPSHOT_MIN_EVENTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.snapshot_min_events),
+        }
+    }
+}
+
+/// Compaction of closed accounts' event history into a final snapshot and tombstone.
+#[derive(Debug, Clone)]
+pub struct CompactionConfig {
+    pub enabled: bool,
+    /// How long after closing an account its full history is kept.
+    pub retention: Duration,
+    /// Most accounts compacted in one maintenance run.
+    pub batch_size: i64,
+}
+
+impl Default for CompactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention: Duration::from_secs(7 * 365 * 24 * 3600),
+            batch_size: 100,
+        }
+    }
+}
+
+impl CompactionConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            enabled: std::env::var("COMPACTION_ENABLED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.enabled),
+            retention: std::env::var("COMPACTION_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(|days: u64| Duration::from_secs(days * 24 * 3600))
+                .unwrap_or(default.retention),
+            batch_size: std::env::var("COMPACTION_BATCH_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|size: &i64| *size > 0)
+                .unwrap_or(default.batch_size),
+        }
+    }
+}
+
+/// What replay does with a stored event that can't be deserialized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PoisonEventPolicy {
+    /// Fail loading the whole aggregate.
+    #[default]
+    Fail,
+    /// Log the event, count it and replay the remaining events.
+    Skip,
+    /// Like `Skip`, but also copy the raw event into the `quarantined_events` table.
+    Quarantine,
+}
+
+impl PoisonEventPolicy {
+    pub fn from_env() -> Self {
+        match std::env::var("POISON_EVENT_POLICY")
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "skip" => PoisonEventPolicy::Skip,
+            "quarantine" => PoisonEventPolicy::Quarantine,
+            _ => PoisonEventPolicy::Fail,
+        }
+    }
+}
+
+/// What account reads do while the event store's circuit breaker is open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StaleReadPolicy {
+    /// Fail the read as unavailable.
+    #[default]
+    Fail,
+    /// Serve the account as last loaded from the event store, marked stale, if it was
+    /// loaded within `max_age`.
+    ServeLastKnown { max_age: Duration },
+}
+
+impl StaleReadPolicy {
+    pub fn from_env() -> Self {
+        match std::env::var("STALE_READ_POLICY")
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "serve_last_known" => StaleReadPolicy::ServeLastKnown {
+                max_age: Duration::from_secs(
+                    std::env::var("STALE_READ_MAX_AGE_SECS")
+                        .ok()
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(300),
+                ),
+            },
+            _ => StaleReadPolicy::Fail,
+        }
+    }
+}
+
+/// Rules each account type starts with. Every field of a type's built-in template can be
+/// overridden with `ACCOUNT_<TYPE>_<FIELD>`, e.g. `ACCOUNT_CHECKING_OVERDRAFT_LIMIT`.
+#[derive(Debug, Clone)]
+pub struct AccountTemplatesConfig {
+    templates: HashMap<AccountType, AccountRules>,
+}
+
+impl Default for AccountTemplatesConfig {
+    fn default() -> Self {
+        Self {
+            templates: AccountType::ALL
+                .iter()
+                .map(|account_type| (*account_type, account_type.default_rules()))
+                .collect(),
+        }
+    }
+}
+
+impl AccountTemplatesConfig {
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(account_type: AccountType, field: &str) -> Option<T> {
+            std::env::var(format!(
+                "ACCOUNT_{}_{}",
+                account_type.as_str().to_ascii_uppercase(),
+                field
+            ))
+            .ok()
+            .and_then(|v| v.parse().ok())
+        }
+
+        let mut config = Self::default();
+        for (account_type, rules) in config.templates.iter_mut() {
+            let account_type = *account_type;
+            if let Some(v) = var(account_type, "OVERDRAFT_LIMIT") {
+                rules.overdraft_limit = v;
+            }
+            if let Some(v) = var(account_type, "MIN_BALANCE") {
+                rules.min_balance = v;
+            }
+            if let Some(v) = var(account_type, "MAX_WITHDRAWAL") {
+                rules.max_withdrawal_amount = Some(v);
+            }
+            if let Some(v) = var(account_type, "MAX_DEPOSIT") {
+                rules.max_deposit_amount = Some(v);
+            }
+            if let Some(window_secs) = var(account_type, "VELOCITY_WINDOW_SECS") {
+                rules.withdrawal_velocity = Some(VelocityCaps {
+                    window_secs,
+                    max_withdrawals: var(account_type, "VELOCITY_MAX_WITHDRAWALS"),
+                    max_amount: var(account_type, "VELOCITY_MAX_AMOUNT"),
+                });
+            }
+        }
+        config
+    }
+
+    pub fn with_rules(mut self, account_type: AccountType, rules: AccountRules) -> Self {
+        self.templates.insert(account_type, rules);
+        self
+    }
+
+    /// The rules a new account of this type is created with.
+    pub fn rules_for(&self, account_type: AccountType) -> AccountRules {
+        self.templates
+            .get(&account_type)
+            .cloned()
+            .unwrap_or_else(|| account_type.default_rules())
+    }
+}
+
+/// Initial balances accounts may be opened with. `INITIAL_BALANCE_MIN`,
+/// `INITIAL_BALANCE_MAX` and `INITIAL_BALANCE_ALLOW_NONZERO` apply to every type; each
+/// can be overridden for one type with `ACCOUNT_<TYPE>_INITIAL_BALANCE_<FIELD>`, e.g.
+/// `ACCOUNT_SAVINGS_INITIAL_BALANCE_MIN`.
+#[derive(Debug, Clone, Default)]
+pub struct InitialBalancePolicyConfig {
+    default: InitialBalancePolicy,
+    policies: HashMap<AccountType, InitialBalancePolicy>,
+}
+
+impl InitialBalancePolicyConfig {
+    pub fn from_env() -> Self {
+        /// Applies the variables set under `prefix`. Returns whether there were any.
+        fn apply(policy: &mut InitialBalancePolicy, prefix: &str) -> bool {
+            let var = |field: &str| std::env::var(format!("{}{}", prefix, field)).ok();
+            let mut found = false;
+            if let Some(v) = var("MIN").and_then(|v| v.parse().ok()) {
+                policy.min = Some(v);
+                found = true;
+            }
+            if let Some(v) = var("MAX").and_then(|v| v.parse().ok()) {
+                policy.max = Some(v);
+                found = true;
+            }
+            if let Some(v) = var("ALLOW_NONZERO").and_then(|v| v.parse().ok()) {
+                policy.allow_nonzero = v;
+                found = true;
+            }
+            found
+        }
+
+        let mut default = InitialBalancePolicy::default();
+        apply(&mut default, "INITIAL_BALANCE_");
+        let policies = AccountType::ALL
+            .iter()
+            .filter_map(|account_type| {
+                let prefix = format!(
+                    "ACCOUNT_{}_INITIAL_BALANCE_",
+                    account_type.as_str().to_ascii_uppercase()
+                );
+                let mut policy = default.clone();
+                apply(&mut policy, &prefix).then_some((*account_type, policy))
+            })
+            .collect();
+        Self { default, policies }
+    }
+
+    /// Replaces the policy for every type without one of its own.
+    pub fn with_default(mut self, policy: InitialBalancePolicy) -> Self {
+        self.default = policy;
+        self
+    }
+
+    pub fn with_policy(mut self, account_type: AccountType, policy: InitialBalancePolicy) -> Self {
+        self.policies.insert(account_type, policy);
+        self
+    }
+
+    pub fn policy_for(&self, account_type: AccountType) -> &InitialBalancePolicy {
+        self.policies.get(&account_type).unwrap_or(&self.default)
+    }
+}
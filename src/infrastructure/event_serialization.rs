@@ -0,0 +1,183 @@
+use crate::domain::AccountEvent;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum EventSerializationError {
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("MessagePack encode error: {0}")]
+    MessagePackEncode(#[from] rmp_serde::encode::Error),
+    #[error("MessagePack decode error: {0}")]
+    MessagePackDecode(#[from] rmp_serde::decode::Error),
+    #[error("Invalid binary payload: {0}")]
+    InvalidPayload(String),
+    #[error("Unknown event format: {0}")]
+    UnknownFormat(String),
+}
+
+impl EventSerializationError {
+    /// Whether the payload was well-formed but its fields don't fit `AccountEvent`, as
+    /// opposed to being unreadable in the first place.
+    pub fn is_schema_mismatch(&self) -> bool {
+        match self {
+            EventSerializationError::Json(e) => e.classify() == serde_json::error::Category::Data,
+            EventSerializationError::MessagePackDecode(e) => matches!(
+                e,
+                rmp_serde::decode::Error::TypeMismatch(_)
+                    | rmp_serde::decode::Error::OutOfRange
+                    | rmp_serde::decode::Error::Syntax(_)
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// Encoding of a stored event payload. Recorded per event in `events.event_format`, so
+/// streams written before and after a format change can still be replayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EventFormat {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl EventFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventFormat::Json => "json",
+            EventFormat::MessagePack => "msgpack",
+        }
+    }
+
+    pub fn serializer(&self) -> &'static dyn EventSerializer {
+        match self {
+            EventFormat::Json => &JsonEventSerializer,
+            EventFormat::MessagePack => &MessagePackEventSerializer,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        std::env::var("EVENT_STORE_FORMAT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+impl FromStr for EventFormat {
+    type Err = EventSerializationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(EventFormat::Json),
+            "msgpack" | "messagepack" => Ok(EventFormat::MessagePack),
+            other => Err(EventSerializationError::UnknownFormat(other.to_string())),
+        }
+    }
+}
+
+/// Converts events to and from the value stored in the `event_data` column.
+pub trait EventSerializer: Send + Sync {
+    fn format(&self) -> EventFormat;
+    fn serialize(&self, event: &AccountEvent) -> Result<Value, EventSerializationError>;
+    fn deserialize(&self, data: &Value) -> Result<AccountEvent, EventSerializationError>;
+}
+
+/// Stores the event as a JSON document.
+pub struct JsonEventSerializer;
+
+impl EventSerializer for JsonEventSerializer {
+    fn format(&self) -> EventFormat {
+        EventFormat::Json
+    }
+
+    fn serialize(&self, event: &AccountEvent) -> Result<Value, EventSerializationError> {
+        Ok(serde_json::to_value(event)?)
+    }
+
+    fn deserialize(&self, data: &Value) -> Result<AccountEvent, EventSerializationError> {
+        Ok(AccountEvent::deserialize(data)?)
+    }
+}
+
+/// Stores the event as MessagePack, base64-encoded into a JSON string so it fits the
+/// JSONB column.
+pub struct MessagePackEventSerializer;
+
+impl EventSerializer for MessagePackEventSerializer {
+    fn format(&self) -> EventFormat {
+        EventFormat::MessagePack
+    }
+
+    fn serialize(&self, event: &AccountEvent) -> Result<Value, EventSerializationError> {
+        let bytes = rmp_serde::to_vec_named(event)?;
+        Ok(Value::String(STANDARD.encode(bytes)))
+    }
+
+    fn deserialize(&self, data: &Value) -> Result<AccountEvent, EventSerializationError> {
+        let encoded = data.as_str().ok_or_else(|| {
+            EventSerializationError::InvalidPayload("expected a base64 string".to_string())
+        })?;
+        let bytes = STANDARD
+            .decode(encoded)
+            .map_err(|e| EventSerializationError::InvalidPayload(e.to_string()))?;
+        Ok(rmp_serde::from_slice(&bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::AccountType;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_events_round_trip_through_every_format() {
+        let account_id = Uuid::new_v4();
+        let events = vec![
+            AccountEvent::AccountCreated {
+                account_id,
+                owner_name: "Format Test".to_string(),
+                initial_balance: Decimal::new(12345, 2),
+                owner_user_id: Some(Uuid::new_v4()),
+                account_type: AccountType::Savings,
+                rules: AccountType::Savings.default_rules(),
+            },
+            AccountEvent::MoneyWithdrawn {
+                account_id,
+                amount: Decimal::new(5, 1),
+                transaction_id: Uuid::new_v4(),
+                withdrawn_at: None,
+            },
+        ];
+
+        for format in [EventFormat::Json, EventFormat::MessagePack] {
+            let serializer = format.serializer();
+            assert_eq!(serializer.format(), format);
+            assert_eq!(format.as_str().parse::<EventFormat>().unwrap(), format);
+            for event in &events {
+                let stored = serializer.serialize(event).unwrap();
+                let decoded = serializer.deserialize(&stored).unwrap();
+                assert_eq!(
+                    serde_json::to_value(&decoded).unwrap(),
+                    serde_json::to_value(event).unwrap()
+                );
+            }
+        }
+
+        // A payload read with the wrong format is rejected rather than misread
+        let json = EventFormat::Json
+            .serializer()
+            .serialize(&events[0])
+            .unwrap();
+        assert!(EventFormat::MessagePack
+            .serializer()
+            .deserialize(&json)
+            .is_err());
+    }
+}
@@ -0,0 +1,360 @@
+use crate::domain::{Account, AccountEvent};
+use crate::infrastructure::config::SlowQueryLogConfig;
+use crate::infrastructure::event_store::{
+    AccountSnapshot, AggregateStats, EventMetadata, EventStoreError, EventStoreTrait, StoredEvent,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+use uuid::Uuid;
+
+/// Upper bounds of the latency histogram buckets, in milliseconds. Slower calls are
+/// counted in a final, unbounded bucket.
+pub const LATENCY_BUCKETS_MS: [u64; 11] = [1, 5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000];
+
+/// The `EventStoreTrait` operations that are timed, as named in logs and metrics.
+pub const TIMED_OPERATIONS: [&str; 15] = [
+    "save_events",
+    "save_events_with_metadata",
+    "get_events",
+    "get_events_page",
+    "get_events_caused_by",
+    "get_events_since",
+    "get_current_version",
+    "get_account",
+    "get_all_accounts",
+    "save_snapshot",
+    "snapshot_aggregates_above_threshold",
+    "aggregate_stats",
+    "top_aggregates_by_event_count",
+    "sample_aggregate_ids",
+    "get_snapshot",
+];
+
+#[derive(Debug, Default)]
+pub struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS_MS.len() + 1],
+    count: AtomicU64,
+    total_micros: AtomicU64,
+}
+
+impl LatencyHistogram {
+    pub fn record(&self, elapsed: Duration) {
+        let millis = elapsed.as_millis() as u64;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|&bound| millis <= bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn snapshot(&self) -> LatencyHistogramSnapshot {
+        LatencyHistogramSnapshot {
+            count: self.count(),
+            total_ms: self.total_micros.load(Ordering::Relaxed) as f64 / 1000.0,
+            buckets: self
+                .buckets
+                .iter()
+                .enumerate()
+                .map(|(i, count)| LatencyBucket {
+                    le_ms: LATENCY_BUCKETS_MS.get(i).copied(),
+                    count: count.load(Ordering::Relaxed),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyHistogramSnapshot {
+    pub count: u64,
+    pub total_ms: f64,
+    pub buckets: Vec<LatencyBucket>,
+}
+
+/// Calls that took at most `le_ms` and more than the previous bucket's bound; the last
+/// bucket has no bound.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyBucket {
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
+/// One latency histogram per timed operation.
+#[derive(Debug)]
+pub struct EventStoreLatency {
+    operations: HashMap<&'static str, LatencyHistogram>,
+}
+
+impl Default for EventStoreLatency {
+    fn default() -> Self {
+        Self {
+            operations: TIMED_OPERATIONS
+                .iter()
+                .map(|&operation| (operation, LatencyHistogram::default()))
+                .collect(),
+        }
+    }
+}
+
+impl EventStoreLatency {
+    pub fn operation(&self, operation: &str) -> Option<&LatencyHistogram> {
+        self.operations.get(operation)
+    }
+
+    fn record(&self, operation: &str, elapsed: Duration) {
+        if let Some(histogram) = self.operations.get(operation) {
+            histogram.record(elapsed);
+        }
+    }
+
+    /// Histograms of the operations that have been called at least once.
+    pub fn snapshot(&self) -> BTreeMap<String, LatencyHistogramSnapshot> {
+        self.operations
+            .iter()
+            .filter(|(_, histogram)| histogram.count() > 0)
+            .map(|(operation, histogram)| (operation.to_string(), histogram.snapshot()))
+            .collect()
+    }
+}
+
+/// Event store wrapper that times every call: the latency goes into a per-operation
+/// histogram, and calls at or over the slow-query threshold are logged at warn.
+pub struct TimedEventStore {
+    inner: Arc<dyn EventStoreTrait>,
+    config: SlowQueryLogConfig,
+    latency: Arc<EventStoreLatency>,
+}
+
+impl TimedEventStore {
+    pub fn new(inner: Arc<dyn EventStoreTrait>, config: SlowQueryLogConfig) -> Self {
+        Self {
+            inner,
+            config,
+            latency: Arc::new(EventStoreLatency::default()),
+        }
+    }
+
+    pub fn latency(&self) -> Arc<EventStoreLatency> {
+        self.latency.clone()
+    }
+
+    async fn timed<T>(
+        &self,
+        operation: &'static str,
+        aggregate_id: Option<Uuid>,
+        future: impl Future<Output = Result<T, EventStoreError>>,
+    ) -> Result<T, EventStoreError> {
+        let started = Instant::now();
+        let result = future.await;
+        let elapsed = started.elapsed();
+        self.latency.record(operation, elapsed);
+
+        if self.config.enabled && elapsed >= self.config.threshold {
+            warn!(
+                target: "slow_query",
+                "Slow event store {}: aggregate={} duration_ms={} ok={}",
+                operation,
+                aggregate_id.map_or_else(|| "-".to_string(), |id| id.to_string()),
+                elapsed.as_millis(),
+                result.is_ok()
+            );
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl EventStoreTrait for TimedEventStore {
+    async fn save_events(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+    ) -> Result<(), EventStoreError> {
+        self.timed(
+            "save_events",
+            Some(aggregate_id),
+            self.inner
+                .save_events(aggregate_id, events, expected_version),
+        )
+        .await
+    }
+
+    async fn save_events_with_metadata(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+        metadata: EventMetadata,
+    ) -> Result<(), EventStoreError> {
+        self.timed(
+            "save_events_with_metadata",
+            Some(aggregate_id),
+            self.inner
+                .save_events_with_metadata(aggregate_id, events, expected_version, metadata),
+        )
+        .await
+    }
+
+    async fn get_events(
+        &self,
+        aggregate_id: Uuid,
+        from_version: Option<i64>,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        self.timed(
+            "get_events",
+            Some(aggregate_id),
+            self.inner.get_events(aggregate_id, from_version),
+        )
+        .await
+    }
+
+    async fn get_events_page(
+        &self,
+        aggregate_id: Uuid,
+        after_version: i64,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        self.timed(
+            "get_events_page",
+            Some(aggregate_id),
+            self.inner
+                .get_events_page(aggregate_id, after_version, limit),
+        )
+        .await
+    }
+
+    async fn get_events_caused_by(
+        &self,
+        aggregate_id: Uuid,
+        causation_id: Uuid,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        self.timed(
+            "get_events_caused_by",
+            Some(aggregate_id),
+            self.inner.get_events_caused_by(aggregate_id, causation_id),
+        )
+        .await
+    }
+
+    async fn get_events_since(
+        &self,
+        after_position: i64,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        self.timed(
+            "get_events_since",
+            None,
+            self.inner.get_events_since(after_position, limit),
+        )
+        .await
+    }
+
+    async fn get_current_version(&self, aggregate_id: Uuid) -> Result<i64, EventStoreError> {
+        self.timed(
+            "get_current_version",
+            Some(aggregate_id),
+            self.inner.get_current_version(aggregate_id),
+        )
+        .await
+    }
+
+    async fn get_account(&self, account_id: Uuid) -> Result<Option<Account>, EventStoreError> {
+        self.timed(
+            "get_account",
+            Some(account_id),
+            self.inner.get_account(account_id),
+        )
+        .await
+    }
+
+    async fn get_all_accounts(&self) -> Result<Vec<Account>, EventStoreError> {
+        self.timed("get_all_accounts", None, self.inner.get_all_accounts())
+            .await
+    }
+
+    async fn save_snapshot(&self, aggregate_id: Uuid) -> Result<Option<i64>, EventStoreError> {
+        self.timed(
+            "save_snapshot",
+            Some(aggregate_id),
+            self.inner.save_snapshot(aggregate_id),
+        )
+        .await
+    }
+
+    async fn snapshot_aggregates_above_threshold(
+        &self,
+        min_event_count: i64,
+    ) -> Result<Vec<(Uuid, i64)>, EventStoreError> {
+        self.timed(
+            "snapshot_aggregates_above_threshold",
+            None,
+            self.inner
+                .snapshot_aggregates_above_threshold(min_event_count),
+        )
+        .await
+    }
+
+    async fn aggregate_stats(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<AggregateStats>, EventStoreError> {
+        self.timed(
+            "aggregate_stats",
+            Some(aggregate_id),
+            self.inner.aggregate_stats(aggregate_id),
+        )
+        .await
+    }
+
+    async fn top_aggregates_by_event_count(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<AggregateStats>, EventStoreError> {
+        self.timed(
+            "top_aggregates_by_event_count",
+            None,
+            self.inner.top_aggregates_by_event_count(limit),
+        )
+        .await
+    }
+
+    async fn sample_aggregate_ids(&self, limit: i64) -> Result<Vec<Uuid>, EventStoreError> {
+        self.timed(
+            "sample_aggregate_ids",
+            None,
+            self.inner.sample_aggregate_ids(limit),
+        )
+        .await
+    }
+
+    async fn get_snapshot(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<AccountSnapshot>, EventStoreError> {
+        self.timed(
+            "get_snapshot",
+            Some(aggregate_id),
+            self.inner.get_snapshot(aggregate_id),
+        )
+        .await
+    }
+
+    fn get_pool(&self) -> PgPool {
+        self.inner.get_pool()
+    }
+}
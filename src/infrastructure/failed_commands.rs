@@ -0,0 +1,157 @@
+use crate::domain::{AccountCommand, AccountEvent};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::PgPool;
+use thiserror::Error;
+use uuid::Uuid;
+
+#[derive(Error, Debug)]
+pub enum FailedCommandError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Failed command not found: {0}")]
+    NotFound(Uuid),
+    #[error("Failed command {0} has already been replayed")]
+    AlreadyReplayed(Uuid),
+}
+
+#[derive(Debug, Clone)]
+pub struct FailedCommand {
+    pub id: Uuid,
+    pub aggregate_id: Uuid,
+    pub command: Value,
+    pub events: Value,
+    pub expected_version: i64,
+    pub error: String,
+    pub context: Value,
+    pub created_at: DateTime<Utc>,
+    pub replay_attempts: i32,
+    pub last_replay_error: Option<String>,
+    pub replayed_at: Option<DateTime<Utc>>,
+}
+
+impl FailedCommand {
+    pub fn command(&self) -> Result<AccountCommand, FailedCommandError> {
+        Ok(serde_json::from_value(self.command.clone())?)
+    }
+
+    pub fn events(&self) -> Result<Vec<AccountEvent>, FailedCommandError> {
+        Ok(serde_json::from_value(self.events.clone())?)
+    }
+}
+
+/// Dead-letter table for commands whose events could not be saved, so they can be
+/// inspected and replayed instead of being lost.
+#[derive(Clone)]
+pub struct FailedCommandStore {
+    pool: PgPool,
+}
+
+impl FailedCommandStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn record(
+        &self,
+        command: &AccountCommand,
+        events: &[AccountEvent],
+        expected_version: i64,
+        error: &str,
+        context: Value,
+    ) -> Result<Uuid, FailedCommandError> {
+        let id = Uuid::new_v4();
+        let aggregate_id = command.account_id();
+        let command = serde_json::to_value(command)?;
+        let events = serde_json::to_value(events)?;
+        sqlx::query!(
+            r#"
+            INSERT INTO failed_commands (id, aggregate_id, command, events, expected_version, error, context)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            id,
+            aggregate_id,
+            command,
+            events,
+            expected_version,
+            error,
+            context
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(id)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<Option<FailedCommand>, FailedCommandError> {
+        let entry = sqlx::query_as!(
+            FailedCommand,
+            r#"
+            SELECT id, aggregate_id, command, events, expected_version, error, context,
+                   created_at, replay_attempts, last_replay_error, replayed_at
+            FROM failed_commands
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(entry)
+    }
+
+    /// Returns commands that haven't been replayed yet, oldest first.
+    pub async fn fetch_pending(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<FailedCommand>, FailedCommandError> {
+        let entries = sqlx::query_as!(
+            FailedCommand,
+            r#"
+            SELECT id, aggregate_id, command, events, expected_version, error, context,
+                   created_at, replay_attempts, last_replay_error, replayed_at
+            FROM failed_commands
+            WHERE replayed_at IS NULL
+            ORDER BY created_at ASC
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+
+    pub async fn mark_replayed(&self, id: Uuid) -> Result<(), FailedCommandError> {
+        sqlx::query!(
+            r#"
+            UPDATE failed_commands
+            SET replayed_at = NOW(), replay_attempts = replay_attempts + 1
+            WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn record_replay_failure(
+        &self,
+        id: Uuid,
+        error: &str,
+    ) -> Result<(), FailedCommandError> {
+        sqlx::query!(
+            r#"
+            UPDATE failed_commands
+            SET replay_attempts = replay_attempts + 1, last_replay_error = $2
+            WHERE id = $1
+            "#,
+            id,
+            error
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
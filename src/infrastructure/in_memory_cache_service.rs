@@ -0,0 +1,250 @@
+use crate::domain::{Account, AccountEvent};
+use crate::infrastructure::cache_service::{
+    instant_to_utc, sweep_shards, CacheConfig, CacheEntry, CacheMetrics, CacheServiceTrait,
+    CacheTier, CachedAccountEntry, EvictionPolicy,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Cache that keeps accounts and event lists in process memory only, for tests and
+/// single-node deployments without Redis. Uses the same sharding as `CacheService`:
+/// `max_size` bounds each of the `shard_count` shards and a full shard makes room
+/// according to the `EvictionPolicy`. Entries expire `ttl` after they were written,
+/// as they would in Redis.
+pub struct InMemoryCacheService {
+    config: CacheConfig,
+    metrics: CacheMetrics,
+    shards: Vec<DashMap<Uuid, CacheEntry<Account>>>,
+    event_cache: DashMap<Uuid, CacheEntry<Vec<(i64, AccountEvent)>>>,
+    sweep_cursor: AtomicUsize,
+}
+
+impl InMemoryCacheService {
+    pub fn new(config: CacheConfig) -> Self {
+        let shards = (0..config.shard_count.max(1))
+            .map(|_| DashMap::new())
+            .collect();
+        Self {
+            config,
+            metrics: CacheMetrics::default(),
+            shards,
+            event_cache: DashMap::new(),
+            sweep_cursor: AtomicUsize::new(0),
+        }
+    }
+
+    fn shard(&self, account_id: Uuid) -> &DashMap<Uuid, CacheEntry<Account>> {
+        &self.shards[(account_id.as_u128() % self.shards.len() as u128) as usize]
+    }
+
+    fn entry<T>(&self, value: T, version: i64, ttl: Option<Duration>) -> CacheEntry<T> {
+        CacheEntry {
+            value,
+            created_at: Instant::now(),
+            last_accessed: Instant::now(),
+            access_count: 0,
+            ttl: ttl.unwrap_or(self.config.default_ttl),
+            version,
+        }
+    }
+
+    /// Makes room in a full shard: expired entries go first, then one entry chosen by
+    /// the eviction policy.
+    fn make_room(&self, shard: &DashMap<Uuid, CacheEntry<Account>>, account_id: Uuid) {
+        if shard.len() < self.config.max_size || shard.contains_key(&account_id) {
+            return;
+        }
+        let before = shard.len();
+        shard.retain(|_, entry| !is_expired(entry));
+        let mut evicted = before - shard.len();
+
+        if shard.len() >= self.config.max_size {
+            let victim = match self.config.eviction_policy {
+                EvictionPolicy::LRU => shard
+                    .iter()
+                    .min_by_key(|entry| entry.last_accessed)
+                    .map(|entry| *entry.key()),
+                EvictionPolicy::LFU => shard
+                    .iter()
+                    .min_by_key(|entry| entry.access_count)
+                    .map(|entry| *entry.key()),
+                EvictionPolicy::TTL => shard
+                    .iter()
+                    .min_by_key(|entry| entry.created_at)
+                    .map(|entry| *entry.key()),
+            };
+            if let Some(victim) = victim {
+                shard.remove(&victim);
+                evicted += 1;
+            }
+        }
+        self.metrics
+            .evictions
+            .fetch_add(evicted as u64, Ordering::Relaxed);
+    }
+}
+
+fn is_expired<T>(entry: &CacheEntry<T>) -> bool {
+    entry.created_at.elapsed() >= entry.ttl
+}
+
+#[async_trait]
+impl CacheServiceTrait for InMemoryCacheService {
+    async fn get_account(&self, account_id: Uuid) -> Result<Option<Account>> {
+        let shard = self.shard(account_id);
+        if let Some(mut entry) = shard.get_mut(&account_id) {
+            if !is_expired(&entry) {
+                entry.last_accessed = Instant::now();
+                entry.access_count += 1;
+                self.metrics.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(Some(entry.value.clone()));
+            }
+        }
+        if shard
+            .remove_if(&account_id, |_, entry| is_expired(entry))
+            .is_some()
+        {
+            self.metrics.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        self.metrics.misses.fetch_add(1, Ordering::Relaxed);
+        Ok(None)
+    }
+
+    async fn set_account(&self, account: &Account, ttl: Option<Duration>) -> Result<()> {
+        let shard = self.shard(account.id);
+        self.make_room(shard, account.id);
+        shard.insert(
+            account.id,
+            self.entry(account.clone(), account.version, ttl),
+        );
+        Ok(())
+    }
+
+    async fn set_account_if_newer(&self, account: &Account, ttl: Option<Duration>) -> Result<bool> {
+        let shard = self.shard(account.id);
+        self.make_room(shard, account.id);
+        match shard.entry(account.id) {
+            Entry::Occupied(entry)
+                if !is_expired(entry.get()) && entry.get().version >= account.version =>
+            {
+                self.metrics.stale_writes.fetch_add(1, Ordering::Relaxed);
+                Ok(false)
+            }
+            entry => {
+                entry.insert(self.entry(account.clone(), account.version, ttl));
+                Ok(true)
+            }
+        }
+    }
+
+    async fn delete_account(&self, account_id: Uuid) -> Result<()> {
+        self.shard(account_id).remove(&account_id);
+        self.metrics.evictions.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn get_account_events(&self, account_id: Uuid) -> Result<Option<Vec<AccountEvent>>> {
+        if let Some(entry) = self.event_cache.get(&account_id) {
+            if !is_expired(&entry) {
+                self.metrics.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(Some(
+                    entry.value.iter().map(|(_, event)| event.clone()).collect(),
+                ));
+            }
+        }
+        self.event_cache
+            .remove_if(&account_id, |_, entry| is_expired(entry));
+        self.metrics.misses.fetch_add(1, Ordering::Relaxed);
+        Ok(None)
+    }
+
+    async fn set_account_events(
+        &self,
+        account_id: Uuid,
+        events: &[(i64, AccountEvent)],
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        let version = events.last().map(|(version, _)| *version).unwrap_or(0);
+        self.event_cache
+            .insert(account_id, self.entry(events.to_vec(), version, ttl));
+        Ok(())
+    }
+
+    async fn delete_account_events(&self, account_id: Uuid) -> Result<()> {
+        self.event_cache.remove(&account_id);
+        self.metrics.evictions.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn invalidate_account(&self, account_id: Uuid) -> Result<()> {
+        self.shard(account_id).remove(&account_id);
+        self.event_cache.remove(&account_id);
+        self.metrics.evictions.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// There is no slower tier to load from, so warming only drops expired entries.
+    async fn warmup_cache(&self, account_ids: Vec<Uuid>) -> Result<()> {
+        for account_id in account_ids {
+            self.shard(account_id)
+                .remove_if(&account_id, |_, entry| is_expired(entry));
+        }
+        self.metrics.warmups.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn inspect_account(&self, account_id: Uuid) -> Result<Option<CachedAccountEntry>> {
+        Ok(self
+            .shard(account_id)
+            .get(&account_id)
+            .map(|entry| CachedAccountEntry {
+                account: entry.value.clone(),
+                tier: CacheTier::Memory,
+                created_at: Some(instant_to_utc(entry.created_at)),
+                last_accessed: Some(instant_to_utc(entry.last_accessed)),
+                access_count: Some(entry.access_count),
+                expired: is_expired(&entry),
+            }))
+    }
+
+    async fn flush_accounts(&self) -> Result<u64> {
+        let evicted: usize = self.shards.iter().map(|shard| shard.len()).sum();
+        for shard in &self.shards {
+            shard.clear();
+        }
+        self.event_cache.clear();
+        self.metrics
+            .evictions
+            .fetch_add(evicted as u64, Ordering::Relaxed);
+        Ok(evicted as u64)
+    }
+
+    /// Sweeps the account shards, and the event lists whenever a sweep gets past the
+    /// last shard.
+    fn sweep_expired(&self, budget: Duration) -> u64 {
+        let started = Instant::now();
+        let mut swept = sweep_shards(&self.shards, &self.sweep_cursor, budget, is_expired);
+        if self.sweep_cursor.load(Ordering::Relaxed) == 0 {
+            let remaining = budget.saturating_sub(started.elapsed());
+            swept += sweep_shards(
+                std::slice::from_ref(&self.event_cache),
+                &AtomicUsize::new(0),
+                remaining,
+                is_expired,
+            );
+        }
+        self.metrics
+            .entries_swept
+            .fetch_add(swept, Ordering::Relaxed);
+        swept
+    }
+
+    fn get_metrics(&self) -> &CacheMetrics {
+        &self.metrics
+    }
+}
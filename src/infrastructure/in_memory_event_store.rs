@@ -0,0 +1,459 @@
+use crate::domain::{Account, AccountEvent};
+use crate::infrastructure::event_serialization::EventFormat;
+use crate::infrastructure::event_store::{
+    AccountSnapshot, AggregateStats, EventMetadata, EventStoreError, EventStoreTrait, StoredEvent,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Event store that keeps everything in process memory, for tests and benchmarks that
+/// shouldn't depend on Postgres. Follows the same versioning and optimistic concurrency
+/// rules as `EventStore`.
+pub struct InMemoryEventStore {
+    events: RwLock<HashMap<Uuid, Vec<StoredEvent>>>,
+    snapshots: RwLock<HashMap<Uuid, AccountSnapshot>>,
+    last_position: AtomicI64,
+    format: EventFormat,
+    // Never connected; only here because `EventStoreTrait::get_pool` requires one
+    pool: PgPool,
+}
+
+impl InMemoryEventStore {
+    pub fn new() -> Self {
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect_lazy("postgresql://localhost/in_memory_event_store")
+            .expect("Static database URL is valid");
+
+        Self {
+            events: RwLock::new(HashMap::new()),
+            snapshots: RwLock::new(HashMap::new()),
+            last_position: AtomicI64::new(0),
+            format: EventFormat::default(),
+            pool,
+        }
+    }
+
+    /// Sets the format new events are encoded in.
+    pub fn with_format(mut self, format: EventFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub async fn save_events(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+    ) -> Result<(), EventStoreError> {
+        self.save_events_with_metadata(
+            aggregate_id,
+            events,
+            expected_version,
+            EventMetadata::correlated(),
+        )
+        .await
+    }
+
+    pub async fn save_events_with_metadata(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+        mut metadata: EventMetadata,
+    ) -> Result<(), EventStoreError> {
+        metadata.occurred_at.get_or_insert_with(Utc::now);
+        let serializer = self.format.serializer();
+        let mut store = self.events.write().await;
+        let stream = store.entry(aggregate_id).or_default();
+
+        let current_version = stream.last().map(|e| e.version);
+        if current_version.unwrap_or(0) != expected_version {
+            return Err(EventStoreError::version_conflict(
+                aggregate_id,
+                expected_version,
+                current_version,
+            ));
+        }
+
+        for (version, event) in (expected_version + 1..).zip(events) {
+            stream.push(StoredEvent {
+                id: Uuid::new_v4(),
+                aggregate_id,
+                event_type: event.event_type().to_string(),
+                event_data: serializer.serialize(&event)?,
+                version,
+                occurred_at: Utc::now(),
+                // Taken under the write lock, so positions follow commit order
+                global_position: self.last_position.fetch_add(1, Ordering::Relaxed) + 1,
+                metadata: metadata.clone(),
+                format: self.format,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_events(
+        &self,
+        aggregate_id: Uuid,
+        from_version: Option<i64>,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        let from_version = from_version.unwrap_or(-1);
+        let store = self.events.read().await;
+        Ok(store
+            .get(&aggregate_id)
+            .map(|stream| {
+                stream
+                    .iter()
+                    .filter(|e| e.version > from_version)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    pub async fn get_events_since(
+        &self,
+        after_position: i64,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        let store = self.events.read().await;
+        let mut events: Vec<StoredEvent> = store
+            .values()
+            .flatten()
+            .filter(|e| e.global_position > after_position)
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| e.global_position);
+        events.truncate(limit.max(0) as usize);
+        Ok(events)
+    }
+
+    pub async fn get_current_version(&self, aggregate_id: Uuid) -> Result<i64, EventStoreError> {
+        let store = self.events.read().await;
+        Ok(store
+            .get(&aggregate_id)
+            .and_then(|stream| stream.last())
+            .map_or(0, |e| e.version))
+    }
+
+    pub async fn get_account(&self, account_id: Uuid) -> Result<Option<Account>, EventStoreError> {
+        let snapshot = self.get_snapshot(account_id).await?;
+        let from_version = snapshot.as_ref().map(|s| s.version);
+
+        let events = self.get_events(account_id, from_version).await?;
+        if events.is_empty() && snapshot.is_none() {
+            return Ok(None);
+        }
+
+        let mut account = match snapshot {
+            Some(snapshot) => snapshot.account,
+            None => Account {
+                id: account_id,
+                ..Account::default()
+            },
+        };
+        for event in events {
+            account.apply_event(&event.decode()?);
+        }
+
+        Ok(Some(account))
+    }
+
+    pub async fn get_all_accounts(&self) -> Result<Vec<Account>, EventStoreError> {
+        let mut account_ids: Vec<Uuid> = self.events.read().await.keys().copied().collect();
+        account_ids.sort();
+
+        let mut accounts = Vec::with_capacity(account_ids.len());
+        for account_id in account_ids {
+            if let Some(account) = self.get_account(account_id).await? {
+                accounts.push(account);
+            }
+        }
+        Ok(accounts)
+    }
+
+    /// Snapshots the aggregate's current state. Returns the snapshot version, or `None`
+    /// if the aggregate has no events.
+    pub async fn save_snapshot(&self, aggregate_id: Uuid) -> Result<Option<i64>, EventStoreError> {
+        let Some(account) = self.get_account(aggregate_id).await? else {
+            return Ok(None);
+        };
+        let version = self.get_current_version(aggregate_id).await?;
+
+        self.snapshots
+            .write()
+            .await
+            .insert(aggregate_id, AccountSnapshot { account, version });
+        Ok(Some(version))
+    }
+
+    pub async fn snapshot_aggregates_above_threshold(
+        &self,
+        min_event_count: i64,
+    ) -> Result<Vec<(Uuid, i64)>, EventStoreError> {
+        let candidates: Vec<Uuid> = {
+            let store = self.events.read().await;
+            let snapshots = self.snapshots.read().await;
+            store
+                .iter()
+                .filter(|(id, stream)| {
+                    let latest = stream.last().map_or(0, |e| e.version);
+                    let snapshot_version = snapshots.get(id).map_or(0, |s| s.version);
+                    stream.len() as i64 > min_event_count && latest > snapshot_version
+                })
+                .map(|(id, _)| *id)
+                .collect()
+        };
+
+        let mut snapshotted = Vec::with_capacity(candidates.len());
+        for aggregate_id in candidates {
+            if let Some(version) = self.save_snapshot(aggregate_id).await? {
+                snapshotted.push((aggregate_id, version));
+            }
+        }
+        Ok(snapshotted)
+    }
+
+    pub async fn aggregate_stats(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<AggregateStats>, EventStoreError> {
+        let store = self.events.read().await;
+        Ok(store
+            .get(&aggregate_id)
+            .and_then(|stream| stream_stats(aggregate_id, stream)))
+    }
+
+    pub async fn top_aggregates_by_event_count(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<AggregateStats>, EventStoreError> {
+        let store = self.events.read().await;
+        let mut stats: Vec<AggregateStats> = store
+            .iter()
+            .filter_map(|(id, stream)| stream_stats(*id, stream))
+            .collect();
+        stats.sort_by(|a, b| {
+            (b.event_count, b.total_bytes).cmp(&(a.event_count, a.total_bytes))
+        });
+        stats.truncate(limit.max(0) as usize);
+        Ok(stats)
+    }
+
+    /// Returns the first `limit` aggregates; there's no need to randomise in tests.
+    pub async fn sample_aggregate_ids(&self, limit: i64) -> Result<Vec<Uuid>, EventStoreError> {
+        let store = self.events.read().await;
+        Ok(store.keys().take(limit.max(0) as usize).copied().collect())
+    }
+
+    pub async fn get_snapshot(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<AccountSnapshot>, EventStoreError> {
+        Ok(self.snapshots.read().await.get(&aggregate_id).cloned())
+    }
+}
+
+/// Sizes are compact JSON, so they run slightly below the Postgres store's for the
+/// same events.
+fn stream_stats(aggregate_id: Uuid, stream: &[StoredEvent]) -> Option<AggregateStats> {
+    let (first, last) = (stream.first()?, stream.last()?);
+    let total_bytes = stream
+        .iter()
+        .map(|e| {
+            e.event_data.to_string().len()
+                + serde_json::to_string(&e.metadata).map_or(0, |m| m.len())
+        })
+        .sum::<usize>();
+    Some(AggregateStats {
+        aggregate_id,
+        event_count: stream.len() as i64,
+        total_bytes: total_bytes as i64,
+        first_at: first.occurred_at,
+        last_at: last.occurred_at,
+    })
+}
+
+impl Default for InMemoryEventStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl EventStoreTrait for InMemoryEventStore {
+    async fn save_events(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+    ) -> Result<(), EventStoreError> {
+        self.save_events(aggregate_id, events, expected_version)
+            .await
+    }
+
+    async fn save_events_with_metadata(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+        metadata: EventMetadata,
+    ) -> Result<(), EventStoreError> {
+        self.save_events_with_metadata(aggregate_id, events, expected_version, metadata)
+            .await
+    }
+
+    async fn get_events(
+        &self,
+        aggregate_id: Uuid,
+        from_version: Option<i64>,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        self.get_events(aggregate_id, from_version).await
+    }
+
+    async fn get_events_page(
+        &self,
+        aggregate_id: Uuid,
+        after_version: i64,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        let store = self.events.read().await;
+        Ok(store
+            .get(&aggregate_id)
+            .map(|stream| {
+                stream
+                    .iter()
+                    .filter(|e| e.version > after_version)
+                    .take(limit.max(0) as usize)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn get_events_since(
+        &self,
+        after_position: i64,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        self.get_events_since(after_position, limit).await
+    }
+
+    async fn get_current_version(&self, aggregate_id: Uuid) -> Result<i64, EventStoreError> {
+        self.get_current_version(aggregate_id).await
+    }
+
+    async fn get_account(&self, account_id: Uuid) -> Result<Option<Account>, EventStoreError> {
+        self.get_account(account_id).await
+    }
+
+    async fn get_all_accounts(&self) -> Result<Vec<Account>, EventStoreError> {
+        self.get_all_accounts().await
+    }
+
+    async fn save_snapshot(&self, aggregate_id: Uuid) -> Result<Option<i64>, EventStoreError> {
+        self.save_snapshot(aggregate_id).await
+    }
+
+    async fn snapshot_aggregates_above_threshold(
+        &self,
+        min_event_count: i64,
+    ) -> Result<Vec<(Uuid, i64)>, EventStoreError> {
+        self.snapshot_aggregates_above_threshold(min_event_count)
+            .await
+    }
+
+    async fn aggregate_stats(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<AggregateStats>, EventStoreError> {
+        self.aggregate_stats(aggregate_id).await
+    }
+
+    async fn top_aggregates_by_event_count(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<AggregateStats>, EventStoreError> {
+        self.top_aggregates_by_event_count(limit).await
+    }
+
+    async fn sample_aggregate_ids(&self, limit: i64) -> Result<Vec<Uuid>, EventStoreError> {
+        self.sample_aggregate_ids(limit).await
+    }
+
+    async fn get_snapshot(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<AccountSnapshot>, EventStoreError> {
+        self.get_snapshot(aggregate_id).await
+    }
+
+    fn get_pool(&self) -> PgPool {
+        self.pool.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    fn deposit(account_id: Uuid, amount: i64) -> AccountEvent {
+        AccountEvent::MoneyDeposited {
+            account_id,
+            amount: Decimal::new(amount, 0),
+            transaction_id: Uuid::new_v4(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_with_snapshot_and_version_conflicts() {
+        let store = InMemoryEventStore::new();
+        let account_id = Uuid::new_v4();
+        let created = AccountEvent::AccountCreated {
+            account_id,
+            owner_name: "In Memory".to_string(),
+            initial_balance: Decimal::new(100, 0),
+            owner_user_id: None,
+            account_type: Default::default(),
+            rules: Default::default(),
+        };
+
+        store
+            .save_events(account_id, vec![created], 0)
+            .await
+            .unwrap();
+        store
+            .save_events(account_id, vec![deposit(account_id, 10)], 1)
+            .await
+            .unwrap();
+        assert!(matches!(
+            store
+                .save_events(account_id, vec![deposit(account_id, 10)], 1)
+                .await,
+            Err(EventStoreError::OptimisticConcurrencyConflict { .. })
+        ));
+
+        assert_eq!(store.save_snapshot(account_id).await.unwrap(), Some(2));
+        store
+            .save_events(account_id, vec![deposit(account_id, 5)], 2)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.get_events(account_id, Some(2)).await.unwrap().len(),
+            1
+        );
+        let account = store.get_account(account_id).await.unwrap().unwrap();
+        assert_eq!(account.balance, Decimal::new(115, 0));
+        assert_eq!(account.version, 3);
+    }
+}
@@ -1,22 +1,52 @@
 use crate::application::services::AccountService;
-use crate::infrastructure::auth::{AuthConfig, AuthService};
+use crate::infrastructure::auth::{AuthConfig, AuthService, RedisDegradation};
+use crate::infrastructure::authorization_audit::{AuthorizationAudit, AuthorizationAuditStore};
 use crate::infrastructure::cache_service::{
-    CacheConfig, CacheService, CacheServiceTrait, EvictionPolicy,
+    start_cache_sweeper, CacheBackend, CacheConfig, CacheService, CacheServiceTrait,
+    EvictionPolicy, CACHE_SCHEMA_VERSION,
+};
+use crate::infrastructure::compaction::EventCompactor;
+use crate::infrastructure::config::{
+    AuthorizationAuditConfig, BatchFlushConfig, CompactionConfig, MaintenanceConfig,
+    PoisonEventPolicy, ProjectionBatchConfig, SloConfig, SlowQueryLogConfig, StaleReadPolicy,
 };
 use crate::infrastructure::event_store::{EventStore, EventStoreConfig, EventStoreTrait};
+use crate::infrastructure::event_store_timing::TimedEventStore;
+use crate::infrastructure::failed_commands::FailedCommandStore;
+use crate::infrastructure::in_memory_cache_service::InMemoryCacheService;
+use crate::infrastructure::jwt_keys::{JwtKeyRing, KeyRingCipher};
 use crate::infrastructure::kafka_abstraction::{KafkaConfig, KafkaConsumer, KafkaProducer};
 use crate::infrastructure::kafka_event_processor::KafkaEventProcessor;
 use crate::infrastructure::l1_cache_updater::L1CacheUpdater;
+use crate::infrastructure::maintenance::{MaintenanceScheduler, RedisMaintenanceLock, SnapshotTask};
 use crate::infrastructure::middleware::{
     AccountCreationValidator, RequestMiddleware, TransactionValidator,
 };
+use crate::infrastructure::outbox::{OutboxConfig, OutboxPublisher, OutboxStore};
+use crate::infrastructure::produce_queue::ProduceQueue;
+use crate::infrastructure::password::PasswordHashConfig;
+use crate::infrastructure::processing_failures::ProcessingFailureStore;
 use crate::infrastructure::projections::{ProjectionConfig, ProjectionStore, ProjectionStoreTrait};
-use crate::infrastructure::redis_abstraction::{RealRedisClient, RedisClientTrait};
+use crate::infrastructure::readiness::{
+    KafkaCheck, PostgresCheck, ReadinessConfig, ReadinessGate, RedisCheck,
+};
+use crate::infrastructure::redis_abstraction::{
+    CircuitBreaker, CircuitBreakerConfig, RealRedisClient, RedisClientTrait, SentinelConfig,
+    SentinelRedisClient,
+};
 use crate::infrastructure::repository::{AccountRepository, AccountRepositoryTrait};
 use crate::infrastructure::scaling::{ScalingConfig, ScalingManager};
+use crate::infrastructure::self_check::{
+    CacheRoundTrip, EventStoreRoundTrip, KafkaRoundTrip, ProjectionRoundTrip, SelfCheck,
+    SelfCheckConfig,
+};
+use crate::infrastructure::shutdown::{ShutdownCoordinator, ShutdownPhase};
+use crate::infrastructure::slo::SloTracker;
+use crate::infrastructure::tenant_quota::{TenantQuotaConfig, TenantQuotas};
 use crate::infrastructure::user_repository::UserRepository;
 use anyhow::Result;
 use redis;
+use sqlx::PgPool;
 use std::sync::Arc;
 use std::time::Duration;
 use tracing::{error, info};
@@ -29,28 +59,41 @@ pub struct ServiceContext {
     pub scaling_manager: Arc<ScalingManager>,
     pub kafka_processor: Arc<KafkaEventProcessor>,
     pub l1_cache_updater: Arc<L1CacheUpdater>,
+    repository: Arc<dyn AccountRepositoryTrait + Send + Sync>,
+    pools: Vec<PgPool>,
     warmup_handle: Arc<tokio::task::JoinHandle<()>>,
     l1_handle: Arc<tokio::task::JoinHandle<()>>,
 }
 
 impl ServiceContext {
-    pub async fn shutdown(mut self) {
-        info!("Starting graceful shutdown of services...");
-
-        // Cancel L1 cache updater
-        self.l1_handle.abort();
-        // Wait for it to finish
-        if let Err(e) = Arc::try_unwrap(self.l1_handle).unwrap().await {
-            error!("Error during L1 cache updater shutdown: {}", e);
-        }
-
-        // Cancel warmup task if it's still running
-        self.warmup_handle.abort();
-        if let Err(e) = Arc::try_unwrap(self.warmup_handle).unwrap().await {
-            error!("Error during warmup task shutdown: {}", e);
-        }
-
-        info!("Service shutdown complete");
+    /// Adds the services' own shutdown work: flushing the repository, stopping the
+    /// Kafka consumers and closing the database pools.
+    pub fn register_shutdown_steps(&self, coordinator: &mut ShutdownCoordinator) {
+        let repository = self.repository.clone();
+        coordinator.add_step(ShutdownPhase::FlushRepository, move || async move {
+            Ok(repository.flush_all().await?)
+        });
+
+        // Let the event processor finish its current batch and commit offsets
+        let kafka_processor = self.kafka_processor.clone();
+        let l1_handle = self.l1_handle.clone();
+        let warmup_handle = self.warmup_handle.clone();
+        coordinator.add_step(ShutdownPhase::StopKafka, move || async move {
+            kafka_processor.stop().await;
+            l1_handle.abort();
+            // Cancel warmup task if it's still running
+            warmup_handle.abort();
+            Ok(())
+        });
+
+        // Redis connections are opened per call, so only the Postgres pools hold sockets
+        let pools = self.pools.clone();
+        coordinator.add_step(ShutdownPhase::ClosePools, move || async move {
+            for pool in pools {
+                pool.close().await;
+            }
+            Ok(())
+        });
     }
 
     pub async fn check_background_tasks(mut self) -> Result<()> {
@@ -92,12 +135,27 @@ pub async fn init_all_services() -> Result<ServiceContext> {
     info!("Initializing services...");
 
     // Initialize Redis client Singleton with connection pool
-    let redis_client = Arc::new(redis::Client::open("redis://127.0.0.1/")?);
-    let redis_client_trait = RealRedisClient::new(redis_client.as_ref().clone(), None);
+    let redis_url =
+        std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string());
+    let redis_client = Arc::new(redis::Client::open(redis_url)?);
+    let redis_client_trait: Arc<dyn RedisClientTrait> = match SentinelConfig::from_env() {
+        Some(sentinel_config) => {
+            info!(
+                "Discovering Redis master {} through sentinels",
+                sentinel_config.master_name
+            );
+            Arc::new(SentinelRedisClient::connect(sentinel_config, None).await?)
+        }
+        None => RealRedisClient::new(redis_client.as_ref().clone(), None),
+    };
 
-    // Initialize EventStore with optimized pool size
-    let event_store: Arc<dyn EventStoreTrait + Send + Sync> =
-        Arc::new(EventStore::new_with_pool_size(5).await?);
+    // Initialize EventStore with optimized pool size, timing every call
+    let timed_event_store = TimedEventStore::new(
+        Arc::new(EventStore::new_with_pool_size(5).await?),
+        SlowQueryLogConfig::from_env(),
+    );
+    let event_store_latency = timed_event_store.latency();
+    let event_store: Arc<dyn EventStoreTrait + Send + Sync> = Arc::new(timed_event_store);
 
     // Initialize UserRepository
     let user_repository = Arc::new(UserRepository::new(event_store.get_pool().clone()));
@@ -133,10 +191,27 @@ pub async fn init_all_services() -> Result<ServiceContext> {
             .unwrap_or(30),
     };
 
-    let auth_service = Arc::new(AuthService::new(
-        redis_client.clone(),
-        auth_config,
-        user_repository,
+    let signing_keys = Arc::new(JwtKeyRing::from_env(&auth_config.jwt_secret));
+    let signing_keys_cipher = KeyRingCipher::from_env(&auth_config.jwt_secret);
+    let auth_service = Arc::new(
+        AuthService::new(redis_client.clone(), auth_config, user_repository)
+            .with_redis_client(redis_client_trait.clone())
+            .with_password_hashing(PasswordHashConfig::from_env())
+            .with_redis_degradation(RedisDegradation::from_env())
+            .with_signing_keys(signing_keys)
+            .with_signing_keys_cipher(signing_keys_cipher),
+    );
+    // Keys rotated by another instance take precedence over the configured ones
+    match auth_service.load_signing_keys().await {
+        Ok(true) => info!("Loaded JWT signing keys from Redis"),
+        Ok(false) => {}
+        Err(e) => error!("Failed to load JWT signing keys from Redis: {}", e),
+    }
+    auth_service.spawn_signing_key_reload(Duration::from_secs(
+        std::env::var("JWT_KEYS_RELOAD_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30),
     ));
 
     // Initialize ProjectionStore with optimized config
@@ -152,8 +227,9 @@ pub async fn init_all_services() -> Result<ServiceContext> {
         ..ProjectionConfig::default()
     };
 
-    let projection_store: Arc<dyn ProjectionStoreTrait + Send + Sync> =
-        Arc::new(ProjectionStore::new_with_config(projection_config).await?);
+    let projection_store = ProjectionStore::new_with_config(projection_config).await?;
+    let projection_pool = projection_store.pool();
+    let projection_store: Arc<dyn ProjectionStoreTrait + Send + Sync> = Arc::new(projection_store);
 
     // Initialize CacheService with optimized config
     let cache_config = CacheConfig {
@@ -182,14 +258,160 @@ pub async fn init_all_services() -> Result<ServiceContext> {
                 .unwrap_or(300),
         ),
         eviction_policy: EvictionPolicy::LRU,
+        backend: std::env::var("CACHE_BACKEND")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default(),
+        key_prefix: std::env::var("CACHE_KEY_PREFIX").unwrap_or_default(),
+        schema_version: std::env::var("CACHE_SCHEMA_VERSION")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(CACHE_SCHEMA_VERSION),
+        sweep_interval: Duration::from_millis(
+            std::env::var("CACHE_SWEEP_INTERVAL_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30_000),
+        ),
+        sweep_budget: Duration::from_millis(
+            std::env::var("CACHE_SWEEP_BUDGET_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
+        ),
     };
+    let (sweep_interval, sweep_budget) = (cache_config.sweep_interval, cache_config.sweep_budget);
 
-    let cache_service: Arc<dyn CacheServiceTrait + Send + Sync> =
-        Arc::new(CacheService::new(redis_client_trait.clone(), cache_config));
+    let cache_service: Arc<dyn CacheServiceTrait + Send + Sync> = match cache_config.backend {
+        CacheBackend::Redis => {
+            Arc::new(CacheService::new(redis_client_trait.clone(), cache_config))
+        }
+        CacheBackend::InMemory => {
+            info!("Caching accounts in process memory only");
+            Arc::new(InMemoryCacheService::new(cache_config))
+        }
+    };
+    if !sweep_interval.is_zero() {
+        start_cache_sweeper(cache_service.clone(), sweep_interval, sweep_budget);
+    }
+
+    // Initialize Kafka with optimized config
+    let kafka_config = KafkaConfig {
+        enabled: std::env::var("KAFKA_ENABLED")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse()
+            .unwrap_or(true),
+        bootstrap_servers: std::env::var("KAFKA_BOOTSTRAP_SERVERS")
+            .unwrap_or_else(|_| "localhost:9092".to_string()),
+        group_id: std::env::var("KAFKA_GROUP_ID")
+            .unwrap_or_else(|_| "banking-es-group".to_string()),
+        topic_prefix: std::env::var("KAFKA_TOPIC_PREFIX")
+            .unwrap_or_else(|_| "banking-es".to_string()),
+        producer_acks: std::env::var("KAFKA_PRODUCER_ACKS")
+            .unwrap_or_else(|_| "1".to_string())
+            .parse()
+            .unwrap_or(1),
+        producer_retries: std::env::var("KAFKA_PRODUCER_RETRIES")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse()
+            .unwrap_or(3),
+        consumer_max_poll_interval_ms: std::env::var("KAFKA_CONSUMER_MAX_POLL_INTERVAL_MS")
+            .unwrap_or_else(|_| "300000".to_string())
+            .parse()
+            .unwrap_or(300000),
+        consumer_session_timeout_ms: std::env::var("KAFKA_CONSUMER_SESSION_TIMEOUT_MS")
+            .unwrap_or_else(|_| "10000".to_string())
+            .parse()
+            .unwrap_or(10000),
+        consumer_max_poll_records: std::env::var("KAFKA_CONSUMER_MAX_POLL_RECORDS")
+            .unwrap_or_else(|_| "250".to_string())
+            .parse()
+            .unwrap_or(250),
+        security_protocol: std::env::var("KAFKA_SECURITY_PROTOCOL")
+            .unwrap_or_else(|_| "PLAINTEXT".to_string()),
+        sasl_mechanism: std::env::var("KAFKA_SASL_MECHANISM")
+            .unwrap_or_else(|_| "PLAIN".to_string()),
+        ssl_ca_location: std::env::var("KAFKA_SSL_CA_LOCATION").ok(),
+        auto_offset_reset: std::env::var("KAFKA_AUTO_OFFSET_RESET")
+            .unwrap_or_else(|_| "earliest".to_string()),
+        cache_invalidation_topic: std::env::var("KAFKA_CACHE_INVALIDATION_TOPIC")
+            .unwrap_or_else(|_| "banking-es-cache-invalidation".to_string()),
+        event_topic: std::env::var("KAFKA_EVENT_TOPIC")
+            .unwrap_or_else(|_| "banking-es-events".to_string()),
+        enable_idempotence: std::env::var("KAFKA_ENABLE_IDEMPOTENCE")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse()
+            .unwrap_or(true),
+        transactional_id: std::env::var("KAFKA_TRANSACTIONAL_ID")
+            .ok()
+            .filter(|id| !id.is_empty()),
+        transaction_timeout_ms: std::env::var("KAFKA_TRANSACTION_TIMEOUT_MS")
+            .unwrap_or_else(|_| "60000".to_string())
+            .parse()
+            .unwrap_or(60000),
+        message_timeout_ms: std::env::var("KAFKA_MESSAGE_TIMEOUT_MS")
+            .unwrap_or_else(|_| "30000".to_string())
+            .parse()
+            .unwrap_or(30000),
+        max_events_per_message: std::env::var("KAFKA_MAX_EVENTS_PER_MESSAGE")
+            .unwrap_or_else(|_| "500".to_string())
+            .parse()
+            .unwrap_or(500),
+        idempotency_keys: std::env::var("KAFKA_IDEMPOTENCY_KEYS")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse()
+            .unwrap_or(true),
+        start_offset: std::env::var("KAFKA_START_OFFSET")
+            .ok()
+            .and_then(|v| v.parse().ok()),
+        event_topic_routes: KafkaConfig::parse_event_topic_routes(
+            &std::env::var("KAFKA_EVENT_TOPIC_ROUTES").unwrap_or_default(),
+        ),
+    };
+
+    // Publish saved events to Kafka, falling back to the outbox while it is unreachable
+    let outbox_config = OutboxConfig {
+        relay_interval: Duration::from_millis(
+            std::env::var("OUTBOX_RELAY_INTERVAL_MS")
+                .unwrap_or_else(|_| "1000".to_string())
+                .parse()
+                .unwrap_or(1000),
+        ),
+        relay_batch_size: std::env::var("OUTBOX_RELAY_BATCH_SIZE")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse()
+            .unwrap_or(100),
+        ..OutboxConfig::default()
+    };
+    let event_publisher = Arc::new(OutboxPublisher::new(
+        KafkaProducer::new(kafka_config.clone())?,
+        OutboxStore::new(event_store.get_pool()),
+        outbox_config,
+    ));
+    event_publisher.start_relay();
 
     // Initialize AccountRepository
-    let account_repository: Arc<dyn AccountRepositoryTrait + Send + Sync> =
-        Arc::new(AccountRepository::new(event_store.clone()));
+    let mut repository = AccountRepository::new(event_store.clone())
+        .with_event_publisher(event_publisher.clone())
+        .with_poison_event_policy(PoisonEventPolicy::from_env())
+        .with_batch_flush_config(BatchFlushConfig::from_env())
+        .with_circuit_breaker(Arc::new(CircuitBreaker::new(
+            CircuitBreakerConfig::from_env_with_prefix("EVENT_STORE_BREAKER"),
+        )))
+        .with_stale_read_policy(StaleReadPolicy::from_env());
+    let produce_queue_capacity: usize = std::env::var("OUTBOX_PRODUCE_QUEUE_CAPACITY")
+        .unwrap_or_else(|_| "1000".to_string())
+        .parse()
+        .unwrap_or(1000);
+    if produce_queue_capacity > 0 {
+        // Produce from a bounded queue so a slow broker doesn't hold up writes. Queued
+        // batches are lost if the process crashes; see ProduceQueue
+        repository = repository.with_produce_queue(Arc::new(ProduceQueue::start(
+            event_publisher.clone(),
+            produce_queue_capacity,
+        )));
+    }
+    let account_repository: Arc<dyn AccountRepositoryTrait + Send + Sync> = Arc::new(repository);
 
     // Initialize RequestMiddleware with optimized config
     let rate_limit_config = crate::infrastructure::middleware::RateLimitConfig {
@@ -213,7 +435,15 @@ pub async fn init_all_services() -> Result<ServiceContext> {
             .unwrap_or(500),
     };
 
-    let middleware = Arc::new(RequestMiddleware::new(rate_limit_config));
+    let mut middleware = RequestMiddleware::new(rate_limit_config);
+    let tenant_quota_config = TenantQuotaConfig::from_env();
+    if tenant_quota_config.enabled {
+        middleware = middleware.with_tenant_quotas(TenantQuotas::new(
+            redis_client_trait.clone(),
+            tenant_quota_config,
+        ));
+    }
+    let middleware = Arc::new(middleware);
     middleware.register_validator(
         "create_account".to_string(),
         Box::new(AccountCreationValidator),
@@ -257,6 +487,12 @@ pub async fn init_all_services() -> Result<ServiceContext> {
                 .parse()
                 .unwrap_or(60),
         ),
+        registration_timeout: Duration::from_secs(
+            std::env::var("SCALING_REGISTRATION_TIMEOUT")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+        ),
     };
 
     let scaling_manager = Arc::new(ScalingManager::new(
@@ -264,63 +500,132 @@ pub async fn init_all_services() -> Result<ServiceContext> {
         scaling_config,
     ));
 
+    // Readiness waits for cache warm-up and projection catch-up, then checks dependencies
+    let readiness_config = ReadinessConfig::from_env();
+    let checks = readiness_config.dependencies.clone();
+    let mut readiness_gate = ReadinessGate::new(readiness_config)
+        .with_dependency(Arc::new(PostgresCheck::new(
+            event_store.get_pool(),
+            checks.postgres_query,
+            checks.postgres_timeout,
+        )))
+        .with_dependency(Arc::new(RedisCheck::new(
+            redis_client_trait.clone(),
+            checks.redis_key,
+            checks.redis_timeout,
+        )));
+    if kafka_config.enabled {
+        readiness_gate = readiness_gate.with_dependency(Arc::new(KafkaCheck::new(
+            KafkaProducer::new(kafka_config.clone())?,
+            checks.kafka_timeout,
+        )));
+    }
+
+    // Round-trip through every store before taking traffic, so a misconfigured instance
+    // either refuses to start or never reports ready
+    let self_check_config = SelfCheckConfig::from_env();
+    if self_check_config.enabled && !self_check_config.fail_startup {
+        readiness_gate = readiness_gate.requiring_self_check();
+    }
+    let readiness = Arc::new(readiness_gate);
+    if self_check_config.enabled {
+        let step_timeout = self_check_config.step_timeout;
+        let mut self_check = SelfCheck::new()
+            .with_step(Arc::new(EventStoreRoundTrip::new(
+                event_store.clone(),
+                step_timeout,
+            )))
+            .with_step(Arc::new(CacheRoundTrip::new(
+                cache_service.clone(),
+                step_timeout,
+            )))
+            .with_step(Arc::new(ProjectionRoundTrip::new(
+                projection_store.clone(),
+                step_timeout,
+            )));
+        if kafka_config.enabled {
+            self_check = self_check.with_step(Arc::new(KafkaRoundTrip::new(
+                KafkaProducer::new(kafka_config.clone())?,
+                step_timeout,
+            )));
+        }
+        match self_check.run().await {
+            Ok(_) => readiness.record_self_check(true),
+            Err(e) if self_check_config.fail_startup => return Err(e.into()),
+            Err(e) => {
+                error!("{}; staying not ready", e);
+                readiness.record_self_check(false);
+            }
+        }
+    }
+    readiness.spawn_projection_lag_monitor(projection_store.clone());
+
     // Initialize AccountService
-    let account_service = Arc::new(AccountService::new(
-        account_repository,
+    let mut account_service = AccountService::new(
+        account_repository.clone(),
         projection_store.clone(),
         cache_service.clone(),
         middleware,
         100,
+    )
+    .with_event_publisher(event_publisher)
+    .with_readiness_gate(readiness.clone())
+    .with_scaling_manager(scaling_manager.clone())
+    .with_event_store_latency(event_store_latency);
+    let slo_config = SloConfig::from_env();
+    if slo_config.enabled {
+        let slo_tracker = Arc::new(SloTracker::new(slo_config));
+        slo_tracker.spawn_evaluation();
+        account_service = account_service.with_slo_tracker(slo_tracker);
+    }
+    // Keep commands whose events fail to save in the failed_commands table for replay
+    if std::env::var("FAILED_COMMANDS_ENABLED")
+        .unwrap_or_else(|_| "true".to_string())
+        .parse()
+        .unwrap_or(true)
+    {
+        account_service = account_service.with_failed_command_store(Arc::new(
+            FailedCommandStore::new(event_store.get_pool()),
+        ));
+    }
+    account_service = account_service.with_authorization_audit(Arc::new(
+        AuthorizationAudit::new(AuthorizationAuditConfig::from_env())
+            .with_store(AuthorizationAuditStore::new(event_store.get_pool())),
     ));
+    let processing_failures = Arc::new(ProcessingFailureStore::new(event_store.get_pool()));
+    let account_service =
+        Arc::new(account_service.with_processing_failure_store(processing_failures.clone()));
+
+    // Snapshot large aggregates (and compact closed accounts, if enabled) on a schedule;
+    // a Redis lock keeps it to one instance
+    let maintenance_config = MaintenanceConfig::from_env();
+    if maintenance_config.enabled {
+        let snapshot_task = SnapshotTask::new(
+            event_store.clone(),
+            maintenance_config.snapshot_min_events,
+        );
+        let scheduler = MaintenanceScheduler::new(
+            maintenance_config,
+            Arc::new(RedisMaintenanceLock::new(redis_client_trait.clone())),
+        )
+        .with_task(Arc::new(snapshot_task));
+        let compaction_config = CompactionConfig::from_env();
+        let scheduler = if compaction_config.enabled {
+            scheduler.with_task(Arc::new(EventCompactor::new(
+                event_store.clone(),
+                compaction_config,
+            )))
+        } else {
+            scheduler
+        };
+        Arc::new(scheduler).start();
+    }
 
-    // Initialize Kafka with optimized config
-    let kafka_config = KafkaConfig {
-        enabled: std::env::var("KAFKA_ENABLED")
-            .unwrap_or_else(|_| "true".to_string())
-            .parse()
-            .unwrap_or(true),
-        bootstrap_servers: std::env::var("KAFKA_BOOTSTRAP_SERVERS")
-            .unwrap_or_else(|_| "localhost:9092".to_string()),
-        group_id: std::env::var("KAFKA_GROUP_ID")
-            .unwrap_or_else(|_| "banking-es-group".to_string()),
-        topic_prefix: std::env::var("KAFKA_TOPIC_PREFIX")
-            .unwrap_or_else(|_| "banking-es".to_string()),
-        producer_acks: std::env::var("KAFKA_PRODUCER_ACKS")
-            .unwrap_or_else(|_| "1".to_string())
-            .parse()
-            .unwrap_or(1),
-        producer_retries: std::env::var("KAFKA_PRODUCER_RETRIES")
-            .unwrap_or_else(|_| "3".to_string())
-            .parse()
-            .unwrap_or(3),
-        consumer_max_poll_interval_ms: std::env::var("KAFKA_CONSUMER_MAX_POLL_INTERVAL_MS")
-            .unwrap_or_else(|_| "300000".to_string())
-            .parse()
-            .unwrap_or(300000),
-        consumer_session_timeout_ms: std::env::var("KAFKA_CONSUMER_SESSION_TIMEOUT_MS")
-            .unwrap_or_else(|_| "10000".to_string())
-            .parse()
-            .unwrap_or(10000),
-        consumer_max_poll_records: std::env::var("KAFKA_CONSUMER_MAX_POLL_RECORDS")
-            .unwrap_or_else(|_| "250".to_string())
-            .parse()
-            .unwrap_or(250),
-        security_protocol: std::env::var("KAFKA_SECURITY_PROTOCOL")
-            .unwrap_or_else(|_| "PLAINTEXT".to_string()),
-        sasl_mechanism: std::env::var("KAFKA_SASL_MECHANISM")
-            .unwrap_or_else(|_| "PLAIN".to_string()),
-        ssl_ca_location: std::env::var("KAFKA_SSL_CA_LOCATION").ok(),
-        auto_offset_reset: std::env::var("KAFKA_AUTO_OFFSET_RESET")
-            .unwrap_or_else(|_| "earliest".to_string()),
-        cache_invalidation_topic: std::env::var("KAFKA_CACHE_INVALIDATION_TOPIC")
-            .unwrap_or_else(|_| "banking-es-cache-invalidation".to_string()),
-        event_topic: std::env::var("KAFKA_EVENT_TOPIC")
-            .unwrap_or_else(|_| "banking-es-events".to_string()),
-    };
 
     // Start warmup task early with explicit Arc cloning
     let event_store_for_warmup = event_store.clone();
     let cache_service_for_warmup = cache_service.clone();
+    let readiness_for_warmup = readiness.clone();
     let warmup_handle = Arc::new(tokio::spawn(async move {
         if let Ok(accounts) = event_store_for_warmup.get_all_accounts().await {
             let account_ids: Vec<Uuid> = accounts.iter().map(|a| a.id).collect();
@@ -328,6 +633,8 @@ pub async fn init_all_services() -> Result<ServiceContext> {
                 error!("Cache warmup error: {}", e);
             }
         }
+        // A failed warm-up only means a colder cache; don't hold readiness back forever
+        readiness_for_warmup.mark_cache_warmed();
     }));
 
     // Initialize L1CacheUpdater
@@ -337,12 +644,26 @@ pub async fn init_all_services() -> Result<ServiceContext> {
     )?);
 
     // Initialize KafkaEventProcessor
-    let kafka_processor = Arc::new(KafkaEventProcessor::new(
-        kafka_config,
-        &event_store,
-        &projection_store,
-        &cache_service,
-    )?);
+    let kafka_processor = Arc::new(
+        KafkaEventProcessor::new(
+            kafka_config,
+            &event_store,
+            &projection_store,
+            &cache_service,
+        )?
+        .with_batch_config(ProjectionBatchConfig::from_env())
+        .with_failure_log(processing_failures),
+    );
+
+    // Keep the projections up to date with the events published to Kafka
+    let kafka_processor_handle = kafka_processor.start();
+    tokio::spawn(async move {
+        match kafka_processor_handle.await {
+            Ok(Err(e)) => error!("Kafka event processor error: {}", e),
+            Err(e) => error!("Kafka event processor task failed: {}", e),
+            Ok(Ok(())) => {}
+        }
+    });
 
     // Start L1 cache updater in background
     let l1_updater = l1_cache_updater.clone();
@@ -361,6 +682,8 @@ pub async fn init_all_services() -> Result<ServiceContext> {
         scaling_manager,
         kafka_processor,
         l1_cache_updater,
+        repository: account_repository,
+        pools: vec![event_store.get_pool(), projection_pool],
         warmup_handle,
         l1_handle,
     };
@@ -0,0 +1,323 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use jsonwebtoken::errors::{Error as JwtError, ErrorKind};
+use jsonwebtoken::{
+    decode, decode_header, encode, DecodingKey, EncodingKey, Header, TokenData, Validation,
+};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::digest::{digest, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+use thiserror::Error;
+
+/// Key id of the key built from `JWT_SECRET`. Tokens without a `kid` header were signed
+/// before keys had ids and are checked against it.
+pub const DEFAULT_KID: &str = "default";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum KeyRingError {
+    #[error("Signing key '{0}' already exists")]
+    AlreadyExists(String),
+    #[error("Signing key '{0}' not found")]
+    NotFound(String),
+    #[error("Signing key '{0}' is the current key and can't be retired")]
+    RetiringCurrent(String),
+    #[error("Saved signing keys couldn't be encrypted or decrypted")]
+    Sealing,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct SigningKey {
+    secret: String,
+    created_at: DateTime<Utc>,
+    /// After this, tokens signed with the key are no longer accepted.
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl SigningKey {
+    fn new(secret: String) -> Self {
+        Self {
+            secret,
+            created_at: Utc::now(),
+            expires_at: None,
+        }
+    }
+
+    fn is_valid(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct KeyRingState {
+    current: String,
+    keys: HashMap<String, SigningKey>,
+    /// Bumped by every rotation and retirement, so a reload never replaces the ring with
+    /// an older copy, e.g. one saved before a rotation that failed to reach Redis.
+    #[serde(default)]
+    revision: u64,
+}
+
+/// A key id in the ring, without its secret.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SigningKeyInfo {
+    pub kid: String,
+    pub current: bool,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// HS256 signing keys identified by the JWT `kid` header. Tokens are signed with the
+/// current key and accepted from any key that hasn't expired or been retired, so
+/// rotating the key doesn't log everyone out.
+pub struct JwtKeyRing {
+    state: RwLock<KeyRingState>,
+}
+
+impl JwtKeyRing {
+    /// A ring holding a single key, which signs new tokens.
+    pub fn new(kid: impl Into<String>, secret: impl Into<String>) -> Self {
+        let kid = kid.into();
+        Self {
+            state: RwLock::new(KeyRingState {
+                keys: HashMap::from([(kid.clone(), SigningKey::new(secret.into()))]),
+                current: kid,
+                revision: 0,
+            }),
+        }
+    }
+
+    /// Builds the ring from `JWT_KEYS`, a comma-separated list of `kid=secret` pairs whose
+    /// first entry signs new tokens. `jwt_secret` is kept as the `default` key so tokens
+    /// issued without a key id stay valid; it signs when `JWT_KEYS` is unset.
+    pub fn from_env(jwt_secret: &str) -> Self {
+        let ring = Self::new(DEFAULT_KID, jwt_secret);
+        let configured: Vec<(String, String)> = std::env::var("JWT_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|pair| {
+                let (kid, secret) = pair.split_once('=')?;
+                let (kid, secret) = (kid.trim(), secret.trim());
+                (!kid.is_empty() && !secret.is_empty())
+                    .then(|| (kid.to_string(), secret.to_string()))
+            })
+            .collect();
+        {
+            let mut state = ring.state.write().unwrap();
+            for (kid, secret) in configured.iter().rev() {
+                state
+                    .keys
+                    .insert(kid.clone(), SigningKey::new(secret.clone()));
+                state.current = kid.clone();
+            }
+        }
+        ring
+    }
+
+    pub fn current_kid(&self) -> String {
+        self.state.read().unwrap().current.clone()
+    }
+
+    pub fn keys(&self) -> Vec<SigningKeyInfo> {
+        let state = self.state.read().unwrap();
+        let mut keys: Vec<SigningKeyInfo> = state
+            .keys
+            .iter()
+            .map(|(kid, key)| SigningKeyInfo {
+                kid: kid.clone(),
+                current: *kid == state.current,
+                created_at: key.created_at,
+                expires_at: key.expires_at,
+            })
+            .collect();
+        keys.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.kid.cmp(&b.kid)));
+        keys
+    }
+
+    /// Makes a new key current. The previous key keeps verifying tokens for `grace`,
+    /// which should be at least the token lifetime.
+    pub fn rotate(
+        &self,
+        kid: impl Into<String>,
+        secret: impl Into<String>,
+        grace: ChronoDuration,
+    ) -> Result<(), KeyRingError> {
+        let kid = kid.into();
+        let mut state = self.state.write().unwrap();
+        if state.keys.contains_key(&kid) {
+            return Err(KeyRingError::AlreadyExists(kid));
+        }
+        let previous = state.current.clone();
+        if let Some(key) = state.keys.get_mut(&previous) {
+            key.expires_at = Some(Utc::now() + grace);
+        }
+        state
+            .keys
+            .insert(kid.clone(), SigningKey::new(secret.into()));
+        state.current = kid;
+        state.revision += 1;
+        Ok(())
+    }
+
+    /// Removes a key, so tokens signed with it are rejected straight away.
+    pub fn retire(&self, kid: &str) -> Result<(), KeyRingError> {
+        let mut state = self.state.write().unwrap();
+        if state.current == kid {
+            return Err(KeyRingError::RetiringCurrent(kid.to_string()));
+        }
+        state
+            .keys
+            .remove(kid)
+            .ok_or_else(|| KeyRingError::NotFound(kid.to_string()))?;
+        state.revision += 1;
+        Ok(())
+    }
+
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String, JwtError> {
+        let state = self.state.read().unwrap();
+        let key = &state.keys[&state.current];
+        let header = Header {
+            kid: Some(state.current.clone()),
+            ..Header::default()
+        };
+        encode(
+            &header,
+            claims,
+            &EncodingKey::from_secret(key.secret.as_bytes()),
+        )
+    }
+
+    /// Decodes a token with the key named by its `kid` header. Unknown, retired and
+    /// expired keys fail as an invalid token.
+    pub fn verify<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        validation: &Validation,
+    ) -> Result<TokenData<T>, JwtError> {
+        let kid = decode_header(token)?
+            .kid
+            .unwrap_or_else(|| DEFAULT_KID.to_string());
+        let secret = {
+            let state = self.state.read().unwrap();
+            match state.keys.get(&kid) {
+                Some(key) if key.is_valid(Utc::now()) => key.secret.clone(),
+                _ => return Err(ErrorKind::InvalidToken.into()),
+            }
+        };
+        decode(
+            token,
+            &DecodingKey::from_secret(secret.as_bytes()),
+            validation,
+        )
+    }
+
+    /// The ring as JSON, secrets included, for sharing between instances. Seal it with a
+    /// `KeyRingCipher` before it leaves the process.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&*self.state.read().unwrap())
+    }
+
+    /// Replaces the ring's keys with ones saved by `to_json`, unless the saved ring is
+    /// older than this one. Returns whether the keys changed.
+    pub fn replace_from_json(&self, json: &str) -> Result<bool, serde_json::Error> {
+        let loaded: KeyRingState = serde_json::from_str(json)?;
+        if !loaded.keys.contains_key(&loaded.current) {
+            return Err(serde::de::Error::custom("current signing key is missing"));
+        }
+        let mut state = self.state.write().unwrap();
+        if loaded.revision < state.revision || loaded == *state {
+            return Ok(false);
+        }
+        *state = loaded;
+        Ok(true)
+    }
+}
+
+/// Encrypts the saved ring with AES-256-GCM, so the signing secrets aren't readable by
+/// anyone with access to Redis. Every instance must use the same passphrase.
+pub struct KeyRingCipher {
+    key: LessSafeKey,
+    rng: SystemRandom,
+}
+
+impl KeyRingCipher {
+    /// A cipher keyed with the SHA-256 digest of `passphrase`.
+    pub fn new(passphrase: &str) -> Self {
+        let key = UnboundKey::new(
+            &AES_256_GCM,
+            digest(&SHA256, passphrase.as_bytes()).as_ref(),
+        )
+        .expect("a SHA-256 digest is a valid AES-256 key");
+        Self {
+            key: LessSafeKey::new(key),
+            rng: SystemRandom::new(),
+        }
+    }
+
+    /// Keyed with `JWT_KEYS_ENCRYPTION_KEY`, falling back to `jwt_secret`, which every
+    /// instance already shares.
+    pub fn from_env(jwt_secret: &str) -> Self {
+        match std::env::var("JWT_KEYS_ENCRYPTION_KEY") {
+            Ok(passphrase) if !passphrase.is_empty() => Self::new(&passphrase),
+            _ => Self::new(jwt_secret),
+        }
+    }
+
+    /// Encrypts `plaintext` under a fresh nonce, returned base64-encoded ahead of the
+    /// ciphertext.
+    pub fn seal(&self, plaintext: &str) -> Result<String, KeyRingError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| KeyRingError::Sealing)?;
+        let mut sealed = plaintext.as_bytes().to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut sealed,
+            )
+            .map_err(|_| KeyRingError::Sealing)?;
+        Ok(STANDARD.encode([nonce.as_slice(), &sealed].concat()))
+    }
+
+    /// Decrypts what `seal` returned. Fails if it was sealed under another key or altered.
+    pub fn open(&self, sealed: &str) -> Result<String, KeyRingError> {
+        let sealed = STANDARD.decode(sealed).map_err(|_| KeyRingError::Sealing)?;
+        if sealed.len() < NONCE_LEN {
+            return Err(KeyRingError::Sealing);
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| KeyRingError::Sealing)?;
+        let mut ciphertext = ciphertext.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::empty(), &mut ciphertext)
+            .map_err(|_| KeyRingError::Sealing)?;
+        String::from_utf8(plaintext.to_vec()).map_err(|_| KeyRingError::Sealing)
+    }
+}
+
+static INSTALLED: RwLock<Option<Arc<JwtKeyRing>>> = RwLock::new(None);
+
+static FROM_ENV: LazyLock<Arc<JwtKeyRing>> = LazyLock::new(|| {
+    let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
+    Arc::new(JwtKeyRing::from_env(&secret))
+});
+
+/// Makes `ring` the one request extractors verify access tokens with.
+pub fn install(ring: Arc<JwtKeyRing>) {
+    *INSTALLED.write().unwrap() = Some(ring);
+}
+
+/// The installed ring, or one built from the environment if none was installed.
+pub fn access_keys() -> Arc<JwtKeyRing> {
+    INSTALLED
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| FROM_ENV.clone())
+}
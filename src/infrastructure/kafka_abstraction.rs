@@ -1,11 +1,15 @@
 use async_trait::async_trait;
 use rdkafka::{
     config::ClientConfig,
-    consumer::{Consumer, StreamConsumer},
-    producer::{FutureProducer, FutureRecord},
+    consumer::{
+        BaseConsumer, CommitMode, Consumer, ConsumerContext, Rebalance, StreamConsumer,
+    },
+    producer::{FutureProducer, FutureRecord, Producer},
     Message,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
@@ -20,14 +24,14 @@ use rdkafka::{
     admin::{AdminClient, AdminOptions, NewTopic, TopicReplication},
     client::ClientContext,
     error::{KafkaError, KafkaResult, RDKafkaErrorCode},
-    message::{Header, Headers, OwnedMessage},
+    message::{Header, OwnedHeaders, OwnedMessage},
     util::Timeout,
     Offset, TopicPartitionList,
 };
 use serde_json;
 use thiserror::Error;
 use tokio::time::timeout;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 #[derive(Debug, thiserror::Error)]
 pub enum BankingKafkaError {
@@ -49,12 +53,40 @@ pub enum BankingKafkaError {
     ConfigurationError(String),
     #[error("Timeout error: {0}")]
     TimeoutError(String),
+    #[error("Kafka unavailable: {0}")]
+    KafkaUnavailable(String),
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
 
+impl BankingKafkaError {
+    /// True when the broker could not be reached, as opposed to a rejected or malformed
+    /// request. Callers can fall back to the outbox and retry later.
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self, BankingKafkaError::KafkaUnavailable(_))
+    }
+}
+
+fn is_unavailability_code(code: RDKafkaErrorCode) -> bool {
+    matches!(
+        code,
+        RDKafkaErrorCode::BrokerTransportFailure
+            | RDKafkaErrorCode::AllBrokersDown
+            | RDKafkaErrorCode::Resolve
+            | RDKafkaErrorCode::MessageTimedOut
+            | RDKafkaErrorCode::OperationTimedOut
+            | RDKafkaErrorCode::RequestTimedOut
+            | RDKafkaErrorCode::NetworkException
+            | RDKafkaErrorCode::BrokerNotAvailable
+            | RDKafkaErrorCode::LeaderNotAvailable
+    )
+}
+
 impl From<rdkafka::error::KafkaError> for BankingKafkaError {
     fn from(error: rdkafka::error::KafkaError) -> Self {
+        if error.rdkafka_error_code().is_some_and(is_unavailability_code) {
+            return BankingKafkaError::KafkaUnavailable(error.to_string());
+        }
         match error {
             rdkafka::error::KafkaError::ClientCreation(e) => {
                 BankingKafkaError::ConnectionError(e.to_string())
@@ -76,6 +108,34 @@ impl From<serde_json::Error> for BankingKafkaError {
     }
 }
 
+/// Where the event consumer starts reading a partition the first time it's assigned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartOffset {
+    /// The oldest retained event, to replay history.
+    Earliest,
+    /// Only events published from now on.
+    Latest,
+    /// The first event at or after this time, in epoch milliseconds.
+    FromTimestamp(i64),
+}
+
+impl std::str::FromStr for StartOffset {
+    type Err = String;
+
+    /// Parses `earliest`, `latest` or `from_timestamp:<ms>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "earliest" => Ok(StartOffset::Earliest),
+            "latest" => Ok(StartOffset::Latest),
+            other => other
+                .strip_prefix("from_timestamp:")
+                .and_then(|ms| ms.parse().ok())
+                .map(StartOffset::FromTimestamp)
+                .ok_or_else(|| format!("invalid start offset '{}'", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct KafkaConfig {
     pub enabled: bool,
@@ -93,6 +153,29 @@ pub struct KafkaConfig {
     pub auto_offset_reset: String,
     pub cache_invalidation_topic: String,
     pub event_topic: String,
+    /// Enables the idempotent producer so broker-side retries cannot duplicate
+    /// records. Forces `acks=all`.
+    pub enable_idempotence: bool,
+    /// When set, event batches can be published inside Kafka transactions
+    /// together with consumer offset commits (exactly-once relay).
+    pub transactional_id: Option<String>,
+    pub transaction_timeout_ms: i32,
+    /// How long the producer keeps retrying a record before reporting it undelivered.
+    /// Bounds how quickly an unreachable broker surfaces as `KafkaUnavailable`.
+    pub message_timeout_ms: i32,
+    /// Larger event batches are published as several ordered messages.
+    pub max_events_per_message: usize,
+    /// Tags each event message with an `idempotency-key` header that is the same every
+    /// time the batch is sent, so a batch re-sent after a crash can be recognised.
+    pub idempotency_keys: bool,
+    /// Overrides the committed offset of each event partition when this process is
+    /// first assigned it. `None` resumes from the group's committed offsets, falling
+    /// back to `auto_offset_reset` for a new group.
+    pub start_offset: Option<StartOffset>,
+    /// Topics events of a type are published to instead of `<prefix>-events`, by event
+    /// type (e.g. `MoneyDeposited`). Events stay keyed by account, so each account's
+    /// events are in order within a topic, but not across topics.
+    pub event_topic_routes: HashMap<String, String>,
 }
 
 impl Default for KafkaConfig {
@@ -113,35 +196,412 @@ impl Default for KafkaConfig {
             auto_offset_reset: "earliest".to_string(),
             cache_invalidation_topic: "banking-es-cache-invalidation".to_string(),
             event_topic: "banking-es-events".to_string(),
+            enable_idempotence: true,
+            transactional_id: None,
+            transaction_timeout_ms: 60000,
+            message_timeout_ms: 30000,
+            max_events_per_message: 500,
+            idempotency_keys: true,
+            start_offset: None,
+            event_topic_routes: HashMap::new(),
+        }
+    }
+}
+
+impl KafkaConfig {
+    /// Topic of events without a route.
+    pub fn events_topic(&self) -> String {
+        format!("{}-events", self.topic_prefix)
+    }
+
+    pub fn topic_for_event(&self, event: &AccountEvent) -> String {
+        self.event_topic_routes
+            .get(event.event_type())
+            .cloned()
+            .unwrap_or_else(|| self.events_topic())
+    }
+
+    /// Every topic events can be published to: the default topic and each routed one.
+    pub fn event_topics(&self) -> Vec<String> {
+        let mut topics = vec![self.events_topic()];
+        for topic in self.event_topic_routes.values() {
+            if !topics.contains(topic) {
+                topics.push(topic.clone());
+            }
         }
+        topics[1..].sort();
+        topics
+    }
+
+    /// Parses `KAFKA_EVENT_TOPIC_ROUTES`-style routes: comma-separated
+    /// `EventType=topic` pairs, e.g. `MoneyDeposited=money.deposited`.
+    pub fn parse_event_topic_routes(value: &str) -> HashMap<String, String> {
+        value
+            .split(',')
+            .filter_map(|pair| {
+                let (event_type, topic) = pair.split_once('=')?;
+                let (event_type, topic) = (event_type.trim(), topic.trim());
+                (!event_type.is_empty() && !topic.is_empty())
+                    .then(|| (event_type.to_string(), topic.to_string()))
+            })
+            .collect()
     }
 }
 
+#[derive(Clone)]
+struct ProducerClients {
+    producer: FutureProducer,
+    transactional_producer: Option<FutureProducer>,
+}
+
 #[derive(Clone)]
 pub struct KafkaProducer {
-    producer: Option<FutureProducer>,
+    // Empty until the clients have been created; creation is retried in the background
+    clients: Arc<std::sync::RwLock<Option<ProducerClients>>>,
+    // Cleared when the broker is unreachable, set again on the next successful delivery
+    available: Arc<AtomicBool>,
+    // Serializes transactions: a transactional producer can only have one open at a time.
+    // The flag records whether `init_transactions` has completed.
+    transaction_lock: Arc<tokio::sync::Mutex<bool>>,
     config: KafkaConfig,
 }
 
 impl KafkaProducer {
+    /// Creates the producer. Failing to create the underlying clients is not fatal: the
+    /// producer starts out unavailable and keeps retrying in the background.
     pub fn new(config: KafkaConfig) -> Result<Self, BankingKafkaError> {
-        if !config.enabled {
-            return Ok(Self {
-                producer: None,
-                config,
-            });
+        let producer = Self {
+            clients: Arc::new(std::sync::RwLock::new(None)),
+            available: Arc::new(AtomicBool::new(config.enabled)),
+            transaction_lock: Arc::new(tokio::sync::Mutex::new(false)),
+            config,
+        };
+
+        if !producer.config.enabled {
+            return Ok(producer);
         }
 
-        let producer: FutureProducer = ClientConfig::new()
-            .set("bootstrap.servers", &config.bootstrap_servers)
-            .set("acks", config.producer_acks.to_string())
-            .set("retries", config.producer_retries.to_string())
+        match Self::create_clients(&producer.config) {
+            Ok(clients) => *producer.clients.write().unwrap() = Some(clients),
+            Err(e) => {
+                warn!(
+                    "Failed to create Kafka producer, retrying in background: {}",
+                    e
+                );
+                producer.available.store(false, Ordering::Relaxed);
+                producer.start_client_retry();
+            }
+        }
+
+        Ok(producer)
+    }
+
+    fn create_clients(config: &KafkaConfig) -> Result<ProducerClients, BankingKafkaError> {
+        let producer: FutureProducer = Self::base_client_config(config).create()?;
+
+        let transactional_producer = match &config.transactional_id {
+            Some(transactional_id) => {
+                let producer: FutureProducer = Self::base_client_config(config)
+                    .set("transactional.id", transactional_id)
+                    .set(
+                        "transaction.timeout.ms",
+                        config.transaction_timeout_ms.to_string(),
+                    )
+                    .create()?;
+                Some(producer)
+            }
+            None => None,
+        };
+
+        Ok(ProducerClients {
+            producer,
+            transactional_producer,
+        })
+    }
+
+    fn start_client_retry(&self) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!("No async runtime available; Kafka producer creation will not be retried");
+            return;
+        };
+
+        let clients = Arc::clone(&self.clients);
+        let available = Arc::clone(&self.available);
+        let config = self.config.clone();
+        runtime.spawn(async move {
+            let mut delay = Duration::from_secs(1);
+            loop {
+                tokio::time::sleep(delay).await;
+                match Self::create_clients(&config) {
+                    Ok(created) => {
+                        *clients.write().unwrap() = Some(created);
+                        available.store(true, Ordering::Relaxed);
+                        info!("Kafka producer created after retry");
+                        return;
+                    }
+                    Err(e) => {
+                        warn!("Retrying Kafka producer creation: {}", e);
+                        delay = (delay * 2).min(Duration::from_secs(60));
+                    }
+                }
+            }
+        });
+    }
+
+    fn producer(&self) -> Option<FutureProducer> {
+        self.clients
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|clients| clients.producer.clone())
+    }
+
+    /// Returns the producer to send with, or `None` when Kafka is disabled.
+    fn producer_for_send(&self) -> Result<Option<FutureProducer>, BankingKafkaError> {
+        if !self.config.enabled {
+            return Ok(None);
+        }
+        self.producer().map(Some).ok_or_else(|| {
+            BankingKafkaError::KafkaUnavailable("Kafka producer has not been created".to_string())
+        })
+    }
+
+    /// Whether the broker was reachable on the last delivery or connectivity check.
+    pub fn is_available(&self) -> bool {
+        self.config.enabled && self.available.load(Ordering::Relaxed)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Fetches cluster metadata to check that the broker is reachable, updating
+    /// `is_available` with the result.
+    pub async fn check_connectivity(&self, timeout: Duration) -> Result<(), BankingKafkaError> {
+        let producer = self.producer_for_send()?;
+        let Some(producer) = producer else {
+            return Ok(());
+        };
+
+        let result = tokio::task::spawn_blocking(move || {
+            producer
+                .client()
+                .fetch_metadata(None, timeout)
+                .map(|_| ())
+                .map_err(|e| BankingKafkaError::KafkaUnavailable(e.to_string()))
+        })
+        .await
+        .map_err(|e| BankingKafkaError::Unknown(e.to_string()))?;
+
+        self.record_result(&result);
+        result
+    }
+
+    /// Produces a message to the `<prefix>-self-check` topic and reads it back from the
+    /// offset it was written at, checking that both directions work end to end.
+    pub async fn round_trip(&self, timeout: Duration) -> Result<(), BankingKafkaError> {
+        let Some(producer) = self.producer_for_send()? else {
+            return Ok(());
+        };
+
+        let topic = format!("{}-self-check", self.config.topic_prefix);
+        let key = Uuid::new_v4().to_string();
+        let (partition, offset) = producer
+            .send(
+                FutureRecord::to(&topic).key(&key).payload(&key),
+                Timeout::After(timeout),
+            )
+            .await
+            .map_err(|(e, _)| BankingKafkaError::from(e))?;
+
+        // Assigned rather than subscribed, so no group rebalance is waited for
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &self.config.bootstrap_servers)
+            .set("group.id", format!("{}-self-check", self.config.group_id))
+            .set("enable.auto.commit", "false")
             .create()?;
+        let mut assignment = TopicPartitionList::new();
+        assignment.add_partition_offset(&topic, partition, Offset::Offset(offset))?;
+        consumer.assign(&assignment)?;
 
-        Ok(Self {
-            producer: Some(producer),
-            config,
+        tokio::task::spawn_blocking(move || {
+            let deadline = std::time::Instant::now() + timeout;
+            while let Some(remaining) = deadline.checked_duration_since(std::time::Instant::now()) {
+                match consumer.poll(remaining) {
+                    Some(Ok(message)) if message.key() == Some(key.as_bytes()) => return Ok(()),
+                    Some(Err(e)) => return Err(BankingKafkaError::from(e)),
+                    _ => {}
+                }
+            }
+            Err(BankingKafkaError::TimeoutError(format!(
+                "message produced to {} was not consumed within {:?}",
+                topic, timeout
+            )))
         })
+        .await
+        .map_err(|e| BankingKafkaError::Unknown(e.to_string()))?
+    }
+
+    fn record_result<T>(&self, result: &Result<T, BankingKafkaError>) {
+        match result {
+            Ok(_) => {
+                if !self.available.swap(true, Ordering::Relaxed) {
+                    info!("Kafka is available again");
+                }
+            }
+            Err(e) if e.is_unavailable() => {
+                if self.available.swap(false, Ordering::Relaxed) {
+                    warn!("Kafka became unavailable: {}", e);
+                }
+            }
+            Err(_) => {}
+        }
+    }
+
+    fn base_client_config(config: &KafkaConfig) -> ClientConfig {
+        let mut client_config = ClientConfig::new();
+        client_config
+            .set("bootstrap.servers", &config.bootstrap_servers)
+            .set("retries", config.producer_retries.to_string())
+            .set("message.timeout.ms", config.message_timeout_ms.to_string());
+
+        if config.enable_idempotence || config.transactional_id.is_some() {
+            // Idempotence requires acknowledgement from all in-sync replicas
+            client_config
+                .set("enable.idempotence", "true")
+                .set("acks", "all");
+        } else {
+            client_config
+                .set("enable.idempotence", "false")
+                .set("acks", config.producer_acks.to_string());
+        }
+
+        client_config
+    }
+
+    /// Publishes the given batches atomically. When a consumer is supplied, its
+    /// current positions are committed as part of the same transaction, so a
+    /// relay that crashes mid-way neither loses nor duplicates batches.
+    pub async fn send_event_batches_transactional(
+        &self,
+        batches: Vec<EventBatch>,
+        consumer: Option<&KafkaConsumer>,
+    ) -> Result<(), BankingKafkaError> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        if self.config.transactional_id.is_none() {
+            return Err(BankingKafkaError::ConfigurationError(
+                "transactional_id must be set to publish transactionally".to_string(),
+            ));
+        }
+        let producer = self
+            .clients
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|clients| clients.transactional_producer.clone())
+            .ok_or_else(|| {
+                BankingKafkaError::KafkaUnavailable(
+                    "Kafka transactional producer has not been created".to_string(),
+                )
+            })?;
+        let producer = &producer;
+
+        let mut initialized = self.transaction_lock.lock().await;
+        let transaction_timeout = Duration::from_millis(self.config.transaction_timeout_ms as u64);
+
+        if !*initialized {
+            Self::blocking_transaction_call(producer, move |p| {
+                p.init_transactions(transaction_timeout)
+            })
+            .await?;
+            *initialized = true;
+        }
+
+        Self::blocking_transaction_call(producer, |p| p.begin_transaction()).await?;
+
+        match self
+            .send_in_open_transaction(producer, &batches, consumer, transaction_timeout)
+            .await
+        {
+            Ok(()) => {
+                Self::blocking_transaction_call(producer, move |p| {
+                    p.commit_transaction(transaction_timeout)
+                })
+                .await
+            }
+            Err(e) => {
+                error!("Aborting Kafka transaction: {}", e);
+                Self::blocking_transaction_call(producer, move |p| {
+                    p.abort_transaction(transaction_timeout)
+                })
+                .await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Runs one of the producer's transaction calls on the blocking pool. They wait on
+    /// the broker for up to the transaction timeout, which would otherwise hold a runtime
+    /// worker thread the whole time.
+    async fn blocking_transaction_call<F>(
+        producer: &FutureProducer,
+        call: F,
+    ) -> Result<(), BankingKafkaError>
+    where
+        F: FnOnce(&FutureProducer) -> KafkaResult<()> + Send + 'static,
+    {
+        let producer = producer.clone();
+        tokio::task::spawn_blocking(move || call(&producer))
+            .await
+            .map_err(|e| BankingKafkaError::Unknown(e.to_string()))?
+            .map_err(BankingKafkaError::from)
+    }
+
+    async fn send_in_open_transaction(
+        &self,
+        producer: &FutureProducer,
+        batches: &[EventBatch],
+        consumer: Option<&KafkaConsumer>,
+        transaction_timeout: Duration,
+    ) -> Result<(), BankingKafkaError> {
+        for batch in batches {
+            let key = batch.account_id.to_string();
+            for (topic, chunk) in EventBatch::chunked_by(
+                batch.account_id,
+                batch.events.clone(),
+                batch.version,
+                batch.global_position,
+                self.config.max_events_per_message,
+                |event| self.config.topic_for_event(event),
+            ) {
+                let payload = serde_json::to_vec(&chunk)
+                    .map_err(|e| BankingKafkaError::SerializationError(e.to_string()))?;
+
+                producer
+                    .send(
+                        self.event_record(&topic, &key, &payload, &chunk),
+                        Duration::from_secs(5),
+                    )
+                    .await
+                    .map_err(|(e, _)| BankingKafkaError::from(e))?;
+            }
+        }
+
+        if let Some(consumer) = consumer.and_then(|c| c.consumer.as_ref()) {
+            let group_metadata = consumer.group_metadata().ok_or_else(|| {
+                BankingKafkaError::ConsumerError("Consumer has no group metadata".to_string())
+            })?;
+            let positions = consumer.position()?;
+            Self::blocking_transaction_call(producer, move |p| {
+                p.send_offsets_to_transaction(&positions, &group_metadata, transaction_timeout)
+            })
+            .await?;
+        }
+
+        Ok(())
     }
 
     pub async fn send_event_batch(
@@ -150,47 +610,80 @@ impl KafkaProducer {
         events: Vec<AccountEvent>,
         version: i64,
     ) -> Result<(), BankingKafkaError> {
-        if !self.config.enabled || self.producer.is_none() {
+        self.send_event_batch_with_position(account_id, events, version, None)
+            .await
+    }
+
+    /// Like `send_event_batch`, with the store-wide position of the last event.
+    pub async fn send_event_batch_with_position(
+        &self,
+        account_id: Uuid,
+        events: Vec<AccountEvent>,
+        version: i64,
+        global_position: Option<i64>,
+    ) -> Result<(), BankingKafkaError> {
+        let Some(producer) = self.producer_for_send()? else {
             return Ok(());
-        }
+        };
 
-        let topic = format!("{}-events", self.config.topic_prefix);
         let key = account_id.to_string();
 
-        let batch = EventBatch {
+        // Oversized batches, and batches whose events are routed to different topics, go
+        // out as several messages. If one fails the caller retries the whole batch, so
+        // consumers may see the earlier chunks again.
+        for (topic, batch) in EventBatch::chunked_by(
             account_id,
             events,
             version,
-            timestamp: Utc::now(),
-        };
+            global_position,
+            self.config.max_events_per_message,
+            |event| self.config.topic_for_event(event),
+        ) {
+            let payload = serde_json::to_vec(&batch)
+                .map_err(|e| BankingKafkaError::SerializationError(e.to_string()))?;
 
-        let payload = serde_json::to_vec(&batch)
-            .map_err(|e| BankingKafkaError::SerializationError(e.to_string()))?;
-
-        self.producer
-            .as_ref()
-            .unwrap()
-            .send(
-                FutureRecord::to(&topic)
-                    .key(&key)
-                    .payload(&payload)
-                    .partition(account_id.as_u128() as i32),
-                Duration::from_secs(5),
-            )
-            .await
-            .map_err(|(e, _)| BankingKafkaError::ProducerError(format!("{:?}", e)))?;
+            // Keyed by account so the default partitioner keeps each account's events in order
+            let result = producer
+                .send(
+                    self.event_record(&topic, &key, &payload, &batch),
+                    Duration::from_secs(5),
+                )
+                .await
+                .map(|_| ())
+                .map_err(|(e, _)| BankingKafkaError::from(e));
 
+            self.record_result(&result);
+            result?;
+        }
         Ok(())
     }
 
+    /// Record for one event message, tagged with the batch's idempotency key if enabled.
+    fn event_record<'a>(
+        &self,
+        topic: &'a str,
+        key: &'a str,
+        payload: &'a [u8],
+        batch: &EventBatch,
+    ) -> FutureRecord<'a, str, [u8]> {
+        let record = FutureRecord::to(topic).key(key).payload(payload);
+        if !self.config.idempotency_keys {
+            return record;
+        }
+        record.headers(OwnedHeaders::new().insert(Header {
+            key: IDEMPOTENCY_KEY_HEADER,
+            value: Some(batch.idempotency_key().as_str()),
+        }))
+    }
+
     pub async fn send_cache_update(
         &self,
         account_id: Uuid,
         account: &Account,
     ) -> Result<(), BankingKafkaError> {
-        if !self.config.enabled || self.producer.is_none() {
+        let Some(producer) = self.producer_for_send()? else {
             return Ok(());
-        }
+        };
 
         let topic = format!("{}-cache", self.config.topic_prefix);
         let key = account_id.to_string();
@@ -198,9 +691,7 @@ impl KafkaProducer {
         let payload = serde_json::to_vec(account)
             .map_err(|e| BankingKafkaError::SerializationError(e.to_string()))?;
 
-        self.producer
-            .as_ref()
-            .unwrap()
+        producer
             .send(
                 FutureRecord::to(&topic)
                     .key(&key)
@@ -218,9 +709,9 @@ impl KafkaProducer {
         &self,
         message: &DeadLetterMessage,
     ) -> Result<(), BankingKafkaError> {
-        if !self.config.enabled || self.producer.is_none() {
+        let Some(producer) = self.producer_for_send()? else {
             return Ok(());
-        }
+        };
 
         let topic = format!("{}-dlq", self.config.topic_prefix);
         let key = message.account_id.to_string();
@@ -228,9 +719,7 @@ impl KafkaProducer {
         let payload = serde_json::to_vec(message)
             .map_err(|e| BankingKafkaError::SerializationError(e.to_string()))?;
 
-        self.producer
-            .as_ref()
-            .unwrap()
+        producer
             .send(
                 FutureRecord::to(&topic)
                     .key(&key)
@@ -250,9 +739,9 @@ impl KafkaProducer {
         invalidation_type: CacheInvalidationType,
         reason: String,
     ) -> Result<(), BankingKafkaError> {
-        if !self.config.enabled || self.producer.is_none() {
+        let Some(producer) = self.producer_for_send()? else {
             return Ok(());
-        }
+        };
 
         let message = CacheInvalidationMessage {
             account_id,
@@ -264,9 +753,7 @@ impl KafkaProducer {
         let payload = serde_json::to_vec(&message)?;
         let topic = &self.config.cache_invalidation_topic;
 
-        self.producer
-            .as_ref()
-            .unwrap()
+        producer
             .send(
                 FutureRecord::to(topic)
                     .payload(&payload)
@@ -280,9 +767,111 @@ impl KafkaProducer {
     }
 }
 
+/// Positions event partitions at the configured `StartOffset` on their first
+/// assignment. Later rebalances resume from the committed offsets as usual.
+pub struct StartOffsetContext {
+    topics: Vec<String>,
+    start_offset: Option<StartOffset>,
+    assigned: std::sync::Mutex<std::collections::HashSet<(String, i32)>>,
+}
+
+impl StartOffsetContext {
+    pub fn new(topic: String, start_offset: Option<StartOffset>) -> Self {
+        Self::for_topics(vec![topic], start_offset)
+    }
+
+    /// Positions the partitions of every one of `topics`, e.g. all routed event topics.
+    pub fn for_topics(topics: Vec<String>, start_offset: Option<StartOffset>) -> Self {
+        Self {
+            topics,
+            start_offset,
+            assigned: std::sync::Mutex::new(std::collections::HashSet::new()),
+        }
+    }
+
+    /// The assignment to apply in place of `assigned`, or `None` when every partition
+    /// has been assigned before. `resolve` looks up the offsets for a timestamp start.
+    pub fn initial_assignment(
+        &self,
+        assigned: &TopicPartitionList,
+        resolve: impl FnOnce(TopicPartitionList) -> KafkaResult<TopicPartitionList>,
+    ) -> KafkaResult<Option<TopicPartitionList>> {
+        let Some(start_offset) = self.start_offset else {
+            return Ok(None);
+        };
+        let new_partitions: Vec<(String, i32)> = {
+            let mut seen = self.assigned.lock().unwrap();
+            assigned
+                .elements()
+                .iter()
+                .filter(|elem| self.topics.iter().any(|topic| topic == elem.topic()))
+                .map(|elem| (elem.topic().to_string(), elem.partition()))
+                .filter(|partition| seen.insert(partition.clone()))
+                .collect()
+        };
+        if new_partitions.is_empty() {
+            return Ok(None);
+        }
+
+        let mut starts = TopicPartitionList::new();
+        for (topic, partition) in &new_partitions {
+            let offset = match start_offset {
+                StartOffset::Earliest => Offset::Beginning,
+                StartOffset::Latest => Offset::End,
+                StartOffset::FromTimestamp(ms) => Offset::Offset(ms),
+            };
+            starts.add_partition_offset(topic, *partition, offset)?;
+        }
+        if matches!(start_offset, StartOffset::FromTimestamp(_)) {
+            starts = resolve(starts)?;
+        }
+
+        let mut assignment = TopicPartitionList::new();
+        for elem in assigned.elements() {
+            let offset = starts
+                .find_partition(elem.topic(), elem.partition())
+                .map(|start| start.offset())
+                .unwrap_or(Offset::Stored);
+            assignment.add_partition_offset(elem.topic(), elem.partition(), offset)?;
+        }
+        Ok(Some(assignment))
+    }
+}
+
+impl ClientContext for StartOffsetContext {}
+
+impl ConsumerContext for StartOffsetContext {
+    fn post_rebalance(&self, base_consumer: &BaseConsumer<Self>, rebalance: &Rebalance<'_>) {
+        let Rebalance::Assign(assigned) = rebalance else {
+            return;
+        };
+        let assignment = self.initial_assignment(assigned, |starts| {
+            base_consumer.offsets_for_times(starts, Duration::from_secs(10))
+        });
+        match assignment {
+            Ok(Some(assignment)) => {
+                let topics = self.topics.join(", ");
+                info!(
+                    "Starting {} at {:?} for newly assigned partitions",
+                    topics, self.start_offset
+                );
+                if let Err(e) = base_consumer.assign(&assignment) {
+                    error!("Failed to apply start offset to {}: {}", topics, e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => error!(
+                "Failed to resolve start offset for {}: {}",
+                self.topics.join(", "),
+                e
+            ),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct KafkaConsumer {
-    consumer: Option<Arc<StreamConsumer>>,
+    consumer: Option<Arc<StreamConsumer<StartOffsetContext>>>,
     config: KafkaConfig,
 }
 
@@ -295,10 +884,19 @@ impl KafkaConsumer {
             });
         }
 
-        let consumer: StreamConsumer = ClientConfig::new()
+        let isolation_level = if config.transactional_id.is_some() {
+            "read_committed"
+        } else {
+            "read_uncommitted"
+        };
+
+        let context = StartOffsetContext::for_topics(config.event_topics(), config.start_offset);
+        let consumer: StreamConsumer<StartOffsetContext> = ClientConfig::new()
             .set("bootstrap.servers", &config.bootstrap_servers)
             .set("group.id", &config.group_id)
             .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", &config.auto_offset_reset)
+            .set("isolation.level", isolation_level)
             .set(
                 "max.poll.interval.ms",
                 config.consumer_max_poll_interval_ms.to_string(),
@@ -307,7 +905,7 @@ impl KafkaConsumer {
                 "session.timeout.ms",
                 config.consumer_session_timeout_ms.to_string(),
             )
-            .create()?;
+            .create_with_context(context)?;
 
         Ok(Self {
             consumer: Some(Arc::new(consumer)),
@@ -320,8 +918,9 @@ impl KafkaConsumer {
             return Ok(());
         }
 
-        let topic = format!("{}-events", self.config.topic_prefix);
-        self.consumer.as_ref().unwrap().subscribe(&[&topic])?;
+        let topics = self.config.event_topics();
+        let topics: Vec<&str> = topics.iter().map(String::as_str).collect();
+        self.consumer.as_ref().unwrap().subscribe(&topics)?;
         Ok(())
     }
 
@@ -383,9 +982,14 @@ impl KafkaConsumer {
                     BankingKafkaError::ConsumerError("Empty message payload".to_string())
                 })?;
 
-                let batch: EventBatch = serde_json::from_slice(payload).map_err(|e| {
-                    BankingKafkaError::ConsumerError("Failed to deserialize message".to_string())
+                let mut batch: EventBatch = serde_json::from_slice(payload).map_err(|e| {
+                    BankingKafkaError::ConsumerError(format!(
+                        "Failed to deserialize message: {}",
+                        e
+                    ))
                 })?;
+                batch.partition = Some(msg.partition());
+                batch.offset = Some(msg.offset());
 
                 Ok(Some(batch))
             }
@@ -395,6 +999,48 @@ impl KafkaConsumer {
         }
     }
 
+    /// Commits the positions of the messages polled so far. Having consumed nothing
+    /// yet isn't an error.
+    pub async fn commit_offsets(&self) -> Result<(), BankingKafkaError> {
+        let Some(consumer) = self.consumer.as_ref() else {
+            return Ok(());
+        };
+
+        match consumer.commit_consumer_state(CommitMode::Sync) {
+            Ok(()) => Ok(()),
+            Err(KafkaError::ConsumerCommit(RDKafkaErrorCode::NoOffset)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Offsets this consumer's group has committed on the default events topic, by
+    /// partition. Partitions without a committed offset are left out.
+    pub async fn committed_event_offsets(
+        &self,
+        timeout: Duration,
+    ) -> Result<HashMap<i32, i64>, BankingKafkaError> {
+        let Some(consumer) = self.consumer.as_ref() else {
+            return Ok(HashMap::new());
+        };
+
+        let topic = format!("{}-events", self.config.topic_prefix);
+        let metadata = consumer.fetch_metadata(Some(&topic), timeout)?;
+        let mut tpl = TopicPartitionList::new();
+        for partition in metadata.topics().iter().flat_map(|t| t.partitions()) {
+            tpl.add_partition(&topic, partition.id());
+        }
+
+        let committed = consumer.committed_offsets(tpl, timeout)?;
+        Ok(committed
+            .elements_for_topic(&topic)
+            .iter()
+            .filter_map(|element| match element.offset() {
+                Offset::Offset(offset) => Some((element.partition(), offset)),
+                _ => None,
+            })
+            .collect())
+    }
+
     pub async fn poll_cache_updates(&self) -> Result<Option<Account>, BankingKafkaError> {
         if !self.config.enabled || self.consumer.is_none() {
             return Ok(None);
@@ -464,12 +1110,100 @@ impl KafkaConsumer {
     }
 }
 
+/// Header carrying `EventBatch::idempotency_key` on event messages.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EventBatch {
     pub account_id: Uuid,
     pub events: Vec<AccountEvent>,
     pub version: i64,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Store-wide position of the batch's last event; the batch's events have
+    /// consecutive positions. Absent when the publisher didn't know it.
+    #[serde(default)]
+    pub global_position: Option<i64>,
+    /// Partition the batch was consumed from; set by the consumer, not sent.
+    #[serde(skip)]
+    pub partition: Option<i32>,
+    /// Offset the batch was consumed at; set by the consumer, not sent.
+    #[serde(skip)]
+    pub offset: Option<i64>,
+}
+
+impl EventBatch {
+    /// Identifies the message this batch is sent as: its account and the version of its
+    /// last event. The same events always get the same key, whichever path publishes them
+    /// and however often they are retried.
+    pub fn idempotency_key(&self) -> String {
+        format!("{}:{}", self.account_id, self.version)
+    }
+
+    /// Splits events into ordered batches of at most `max_events`. Each batch's
+    /// `version` and `global_position` are those of its last event, so consumers see the
+    /// same progression as a single batch ending at `version` and `global_position`.
+    pub fn chunked(
+        account_id: Uuid,
+        events: Vec<AccountEvent>,
+        version: i64,
+        global_position: Option<i64>,
+        max_events: usize,
+    ) -> Vec<EventBatch> {
+        Self::chunked_by(
+            account_id,
+            events,
+            version,
+            global_position,
+            max_events,
+            |_| (),
+        )
+        .into_iter()
+        .map(|(_, batch)| batch)
+        .collect()
+    }
+
+    /// Like `chunked`, also starting a new batch wherever `key` changes from one event
+    /// to the next, e.g. where consecutive events go to different topics. Each batch is
+    /// returned with the key of its events.
+    pub fn chunked_by<K: PartialEq>(
+        account_id: Uuid,
+        events: Vec<AccountEvent>,
+        version: i64,
+        global_position: Option<i64>,
+        max_events: usize,
+        key: impl Fn(&AccountEvent) -> K,
+    ) -> Vec<(K, EventBatch)> {
+        let max_events = max_events.max(1);
+        let total = events.len() as i64;
+        let timestamp = Utc::now();
+        let mut batches = Vec::with_capacity(events.len().div_ceil(max_events));
+        let mut sent = 0;
+        let mut events = events.into_iter().peekable();
+        while let Some(first) = events.next() {
+            let chunk_key = key(&first);
+            let mut chunk = vec![first];
+            while chunk.len() < max_events {
+                match events.next_if(|event| key(event) == chunk_key) {
+                    Some(event) => chunk.push(event),
+                    None => break,
+                }
+            }
+            sent += chunk.len() as i64;
+            batches.push((
+                chunk_key,
+                EventBatch {
+                    account_id,
+                    events: chunk,
+                    version: version - (total - sent),
+                    timestamp,
+                    global_position: global_position.map(|position| position - (total - sent)),
+                    partition: None,
+                    offset: None,
+                },
+            ));
+        }
+        batches
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -20,6 +20,15 @@ pub trait DeadLetterQueueTrait: Send + Sync {
         version: i64,
         failure_reason: String,
     ) -> Result<()>;
+    /// Sends events to the DLQ as a message that has used up its retries, so it is kept
+    /// there without being redelivered.
+    async fn park_in_dlq(
+        &self,
+        account_id: Uuid,
+        events: Vec<AccountEvent>,
+        version: i64,
+        failure_reason: String,
+    ) -> Result<()>;
     async fn process_dlq(&self) -> Result<()>;
 }
 
@@ -168,6 +177,30 @@ impl DeadLetterQueueTrait for DeadLetterQueue {
         Ok(())
     }
 
+    async fn park_in_dlq(
+        &self,
+        account_id: Uuid,
+        events: Vec<AccountEvent>,
+        version: i64,
+        failure_reason: String,
+    ) -> Result<()> {
+        let message = DeadLetterMessage {
+            account_id,
+            events,
+            version,
+            original_timestamp: Utc::now(),
+            failure_reason,
+            retry_count: self.max_retries,
+            last_retry: None,
+        };
+
+        self.producer.send_dlq_message(&message).await?;
+        self.metrics
+            .dlq_messages
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        Ok(())
+    }
+
     async fn process_dlq(&self) -> Result<()> {
         if let Some(message) = self.consumer.poll_dlq_message().await? {
             if message.retry_count >= self.max_retries {
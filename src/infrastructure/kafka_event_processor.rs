@@ -1,8 +1,9 @@
 use crate::domain::{Account, AccountEvent};
 use crate::infrastructure::cache_service::{CacheService, CacheServiceTrait};
-use crate::infrastructure::event_store::{EventStore, EventStoreTrait};
+use crate::infrastructure::config::ProjectionBatchConfig;
+use crate::infrastructure::event_store::{EventStoreError, EventStoreTrait};
 use crate::infrastructure::kafka_abstraction::{
-    EventBatch, KafkaConfig, KafkaConsumer, KafkaProducer,
+    BankingKafkaError, EventBatch, KafkaConfig, KafkaConsumer, KafkaProducer,
 };
 use crate::infrastructure::kafka_dlq::{DeadLetterQueue, DeadLetterQueueTrait};
 use crate::infrastructure::kafka_metrics::KafkaMetrics;
@@ -10,17 +11,47 @@ use crate::infrastructure::kafka_monitoring::{MonitoringDashboard, MonitoringDas
 use crate::infrastructure::kafka_recovery::{KafkaRecovery, KafkaRecoveryTrait};
 use crate::infrastructure::kafka_recovery_strategies::{RecoveryStrategies, RecoveryStrategy};
 use crate::infrastructure::kafka_tracing::{KafkaTracing, KafkaTracingTrait};
-use crate::infrastructure::projections::{ProjectionStore, ProjectionStoreTrait};
+use crate::infrastructure::processing_failures::{ProcessingFailureStatus, ProcessingFailureStore};
+use crate::infrastructure::projections::{ProjectionEvent, ProjectionStoreTrait};
+use crate::infrastructure::read_models::ProjectionDispatcher;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use rdkafka::error::KafkaError;
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, RwLock};
+use tokio::task::JoinHandle;
 use tokio::time::sleep;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+/// Checkpoint the processor's projection writes advance.
+pub const PROJECTION_CHECKPOINT: &str = "kafka_event_processor";
+
+/// Attempts a failed batch gets, counting the first, before it stays in the DLQ.
+const DLQ_MAX_RETRIES: u32 = 3;
+/// How long the DLQ waits before redelivering a failed batch for the first time.
+const DLQ_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Where the processor reads event batches from and commits how far it has got.
+#[async_trait]
+pub trait EventBatchSource: Send + Sync {
+    async fn poll_events(&self) -> Result<Option<EventBatch>, BankingKafkaError>;
+    async fn commit_offsets(&self) -> Result<(), BankingKafkaError>;
+}
+
+#[async_trait]
+impl EventBatchSource for KafkaConsumer {
+    async fn poll_events(&self) -> Result<Option<EventBatch>, BankingKafkaError> {
+        KafkaConsumer::poll_events(self).await
+    }
+
+    async fn commit_offsets(&self) -> Result<(), BankingKafkaError> {
+        KafkaConsumer::commit_offsets(self).await
+    }
+}
+
 #[derive(Debug, Default)]
 struct ProcessingState {
     is_processing: bool,
@@ -32,8 +63,11 @@ struct ProcessingState {
 pub struct KafkaEventProcessor {
     producer: KafkaProducer,
     consumer: KafkaConsumer,
+    source: Arc<dyn EventBatchSource>,
+    batch_config: ProjectionBatchConfig,
     event_store: Arc<dyn EventStoreTrait + Send + Sync>,
     projections: Arc<dyn ProjectionStoreTrait + Send + Sync>,
+    read_models: Option<Arc<ProjectionDispatcher>>,
     dlq: Arc<dyn DeadLetterQueueTrait + Send + Sync>,
     recovery: Arc<dyn KafkaRecoveryTrait + Send + Sync>,
     recovery_strategies: Arc<RecoveryStrategies>,
@@ -41,7 +75,12 @@ pub struct KafkaEventProcessor {
     monitoring: Arc<dyn MonitoringDashboardTrait + Send + Sync>,
     tracing: Arc<dyn KafkaTracingTrait + Send + Sync>,
     cache_service: Arc<dyn CacheServiceTrait + Send + Sync>,
+    failure_log: Option<Arc<ProcessingFailureStore>>,
     processing_state: Arc<RwLock<ProcessingState>>,
+    /// Set by `stop` to ask the consume loop to exit.
+    stop_requested: Arc<watch::Sender<bool>>,
+    /// True while the consume loop runs.
+    running: Arc<watch::Sender<bool>>,
 }
 
 impl KafkaEventProcessor {
@@ -59,8 +98,8 @@ impl KafkaEventProcessor {
             producer.clone(),
             consumer.clone(),
             metrics.clone(),
-            3,
-            Duration::from_secs(1),
+            DLQ_MAX_RETRIES,
+            DLQ_RETRY_DELAY,
         ));
 
         let recovery = Arc::new(KafkaRecovery::new(
@@ -85,9 +124,12 @@ impl KafkaEventProcessor {
 
         Ok(Self {
             producer,
+            source: Arc::new(consumer.clone()),
             consumer,
+            batch_config: ProjectionBatchConfig::default(),
             event_store: event_store.clone(),
             projections: projections.clone(),
+            read_models: None,
             dlq,
             recovery,
             recovery_strategies,
@@ -95,11 +137,91 @@ impl KafkaEventProcessor {
             monitoring,
             tracing,
             cache_service: cache_service.clone(),
+            failure_log: None,
             processing_state: Arc::new(RwLock::new(ProcessingState::default())),
+            stop_requested: Arc::new(watch::Sender::new(false)),
+            running: Arc::new(watch::Sender::new(false)),
+        })
+    }
+
+    /// Reads event batches from `source` instead of the Kafka consumer.
+    pub fn with_event_source(mut self, source: Arc<dyn EventBatchSource>) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn with_batch_config(mut self, batch_config: ProjectionBatchConfig) -> Self {
+        self.batch_config = batch_config;
+        self
+    }
+
+    /// Also feeds every applied batch to the dispatcher's projections.
+    pub fn with_projection_dispatcher(mut self, read_models: Arc<ProjectionDispatcher>) -> Self {
+        self.read_models = Some(read_models);
+        self
+    }
+
+    /// Records failed batches and their retries in `failure_log`.
+    pub fn with_failure_log(mut self, failure_log: Arc<ProcessingFailureStore>) -> Self {
+        self.failure_log = Some(failure_log);
+        self
+    }
+
+    /// Runs the consume loop in the background until `stop` is called.
+    pub fn start(&self) -> JoinHandle<Result<()>> {
+        // Marked running before spawning, so a `stop` that follows straight away waits
+        let claimed = self.claim_running();
+        let processor = self.clone();
+        tokio::spawn(async move {
+            claimed?;
+            processor.run().await
         })
     }
 
+    /// Asks the consume loop to finish the batch in hand, commit its offsets and exit,
+    /// then waits until it has. Returns straight away if the loop isn't running.
+    pub async fn stop(&self) {
+        let mut running = self.running.subscribe();
+        if !*running.borrow() {
+            return;
+        }
+
+        info!("Stopping Kafka event processor");
+        self.stop_requested.send_replace(true);
+        let _ = running.wait_for(|running| !running).await;
+    }
+
+    pub fn is_running(&self) -> bool {
+        *self.running.borrow()
+    }
+
+    /// Runs the consume loop on the current task until `stop` is called.
     pub async fn start_processing(&self) -> Result<()> {
+        self.claim_running()?;
+        self.run().await
+    }
+
+    fn claim_running(&self) -> Result<()> {
+        if self.running.send_replace(true) {
+            return Err(anyhow::anyhow!("Kafka event processor is already running"));
+        }
+        self.stop_requested.send_replace(false);
+        Ok(())
+    }
+
+    async fn run(&self) -> Result<()> {
+        let result = self.consume_until_stopped().await;
+
+        // Offsets of batches already handled are committed even if the loop failed
+        if let Err(e) = self.source.commit_offsets().await {
+            error!("Failed to commit offsets on stop: {}", e);
+        }
+        self.running.send_replace(false);
+        info!("Kafka event processor stopped");
+        result
+    }
+
+    async fn consume_until_stopped(&self) -> Result<()> {
         // Initialize tracing
         self.tracing
             .init_tracing()
@@ -109,7 +231,7 @@ impl KafkaEventProcessor {
 
         // Start DLQ processing in background
         let dlq = self.dlq.clone();
-        tokio::spawn(async move {
+        let dlq_handle = tokio::spawn(async move {
             if let Err(e) = dlq.process_dlq().await {
                 error!("DLQ processing failed: {}", e);
             }
@@ -117,7 +239,7 @@ impl KafkaEventProcessor {
 
         // Start monitoring in background
         let mut monitoring = self.monitoring.clone();
-        tokio::spawn(async move {
+        let monitoring_handle = tokio::spawn(async move {
             loop {
                 monitoring.record_metrics();
                 monitoring.check_alerts();
@@ -126,10 +248,21 @@ impl KafkaEventProcessor {
             }
         });
 
-        loop {
-            let start_time = std::time::Instant::now();
+        let result = self.consume(self.stop_requested.subscribe()).await;
+        dlq_handle.abort();
+        monitoring_handle.abort();
+        result
+    }
+
+    async fn consume(&self, stop_requested: watch::Receiver<bool>) -> Result<()> {
+        let mut pending: Vec<EventBatch> = Vec::new();
+        let mut pending_events = 0;
+        let mut linger_until = Instant::now();
+
+        while !*stop_requested.borrow() {
+            let start_time = Instant::now();
 
-            match self.consumer.poll_events().await {
+            let polled = match self.source.poll_events().await {
                 Ok(Some(batch)) => {
                     self.metrics
                         .messages_consumed
@@ -145,36 +278,17 @@ impl KafkaEventProcessor {
                         batch.version,
                     );
 
-                    match self.process_batch(batch.clone()).await {
-                        Ok(_) => {
-                            self.metrics.events_processed.fetch_add(
-                                batch.events.len() as u64,
-                                std::sync::atomic::Ordering::Relaxed,
-                            );
-                        }
-                        Err(e) => {
-                            error!("Failed to process event batch: {}", e);
-                            self.metrics
-                                .processing_errors
-                                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                            self.tracing
-                                .trace_error(&anyhow::anyhow!("{}", e), "batch_processing");
-
-                            // Send to DLQ
-                            self.dlq
-                                .send_to_dlq(
-                                    batch.account_id,
-                                    batch.events,
-                                    batch.version,
-                                    format!("Batch processing failed: {}", e),
-                                )
-                                .await?;
-                        }
+                    if pending.is_empty() {
+                        linger_until = start_time + self.batch_config.max_linger;
                     }
+                    pending_events += batch.events.len();
+                    pending.push(batch);
+                    true
                 }
                 Ok(None) => {
                     // No messages available, continue polling
-                    continue;
+                    tokio::task::yield_now().await;
+                    false
                 }
                 Err(e) => {
                     error!("Error polling Kafka: {}", e);
@@ -192,22 +306,131 @@ impl KafkaEventProcessor {
                                 .trace_error(&anyhow::anyhow!("{}", e), "recovery");
                         }
                     }
+                    true
                 }
+            };
+
+            if !pending.is_empty()
+                && (pending_events >= self.batch_config.max_events
+                    || Instant::now() >= linger_until)
+            {
+                pending_events = 0;
+                self.write_batches(std::mem::take(&mut pending)).await?;
+            }
+
+            if polled {
+                // Record metrics periodically
+                self.tracing.trace_metrics();
+                self.tracing.trace_performance_metrics();
             }
+        }
 
-            // Record metrics periodically
-            self.tracing.trace_metrics();
-            self.tracing.trace_performance_metrics();
+        // Finish the batch in hand so its offsets can be committed on the way out
+        if !pending.is_empty() {
+            self.write_batches(pending).await?;
         }
+
+        Ok(())
     }
 
-    async fn process_batch(&self, batch: EventBatch) -> Result<()> {
-        let start_time = std::time::Instant::now();
+    /// Saves and caches each batch's events, applies all of them to the projections in a
+    /// single transaction, then commits the consumed offsets. Batches that fail go to the
+    /// dead letter queue, so offsets are only committed once every batch is either in the
+    /// projections or in the dead letter queue. Registered read models get the events after
+    /// the account projections; their checkpoints keep a redelivered batch from being
+    /// applied to them twice, as the versions the account projections already hold do.
+    async fn write_batches(&self, batches: Vec<EventBatch>) -> Result<()> {
+        let start_time = Instant::now();
+
+        let mut prepared = Vec::with_capacity(batches.len());
+        for batch in batches {
+            match self.prepare_batch(&batch).await {
+                Ok(()) => prepared.push(batch),
+                Err(e) => self.dead_letter(batch, &e).await?,
+            }
+        }
 
-        // Save events to event store
-        self.event_store
+        if !prepared.is_empty() {
+            let events: Vec<(Option<i64>, AccountEvent)> =
+                prepared.iter().flat_map(positioned_events).collect();
+            let projected: Vec<ProjectionEvent> =
+                prepared.iter().flat_map(projection_events).collect();
+            let applied = async {
+                self.projections
+                    .apply_events_batch(PROJECTION_CHECKPOINT, &projected)
+                    .await?;
+                if let Some(read_models) = &self.read_models {
+                    read_models.dispatch(&events).await?;
+                }
+                Ok::<_, anyhow::Error>(())
+            }
+            .await;
+            match applied {
+                Ok(_) => {
+                    self.metrics
+                        .events_processed
+                        .fetch_add(events.len() as u64, std::sync::atomic::Ordering::Relaxed);
+                    if let Some(failure_log) = &self.failure_log {
+                        let batches: Vec<(Uuid, i64)> = prepared
+                            .iter()
+                            .map(|batch| (batch.account_id, batch.version))
+                            .collect();
+                        if let Err(e) = failure_log.mark_resolved(&batches).await {
+                            warn!("Failed to mark processing failures resolved: {}", e);
+                        }
+                    }
+                    // Each account's cache entry moves past all of its batches at once
+                    let mut by_account: HashMap<Uuid, Vec<&EventBatch>> = HashMap::new();
+                    for batch in &prepared {
+                        by_account.entry(batch.account_id).or_default().push(batch);
+                    }
+                    for (account_id, batches) in by_account {
+                        if let Err(e) = self.update_cached_account(account_id, &batches).await {
+                            warn!("Failed to update cached account {}: {}", account_id, e);
+                        }
+                    }
+                }
+                Err(e) => {
+                    for batch in prepared {
+                        self.dead_letter(batch, &e).await?;
+                    }
+                }
+            }
+        }
+
+        self.metrics.processing_latency.fetch_add(
+            start_time.elapsed().as_millis() as u64,
+            std::sync::atomic::Ordering::Relaxed,
+        );
+
+        if let Err(e) = self.source.commit_offsets().await {
+            warn!("Failed to commit offsets: {}", e);
+        }
+        Ok(())
+    }
+
+    async fn prepare_batch(&self, batch: &EventBatch) -> Result<()> {
+        // Save events to event store, unless this is a redelivery of a batch already saved
+        match self
+            .event_store
             .save_events(batch.account_id, batch.events.clone(), batch.version)
-            .await?;
+            .await
+        {
+            Ok(()) => {}
+            Err(
+                e @ (EventStoreError::OptimisticConcurrencyConflict { .. }
+                | EventStoreError::AggregateAlreadyExists { .. }),
+            ) => {
+                if !self.is_saved(batch).await? {
+                    return Err(e.into());
+                }
+                info!(
+                    "Batch for account {} at version {} was already saved",
+                    batch.account_id, batch.version
+                );
+            }
+            Err(e) => return Err(e.into()),
+        }
 
         // Convert events to versioned format
         let versioned_events: Vec<(i64, AccountEvent)> = batch
@@ -221,46 +444,121 @@ impl KafkaEventProcessor {
         self.cache_service
             .set_account_events(batch.account_id, &versioned_events, None)
             .await?;
+        Ok(())
+    }
 
-        // Get account from projections and apply latest events
-        let account = self.projections.get_account(batch.account_id).await?;
-        if let Some(mut account_proj) = account {
-            // Apply latest events to get final state
-            for event in &batch.events {
-                account_proj = account_proj.apply_event(event)?;
+    /// Whether the store already holds the batch's events at the batch's versions.
+    async fn is_saved(&self, batch: &EventBatch) -> Result<bool> {
+        let stored = self
+            .event_store
+            .get_events(batch.account_id, Some(batch.version))
+            .await?;
+        if stored.len() < batch.events.len() {
+            return Ok(false);
+        }
+        for (stored, event) in stored.iter().zip(&batch.events) {
+            if serde_json::to_value(stored.decode()?)? != serde_json::to_value(event)? {
+                return Ok(false);
             }
+        }
+        Ok(true)
+    }
 
-            // Convert projection to account with updated state
-            let account = Account {
-                id: account_proj.id,
-                owner_name: account_proj.owner_name,
-                balance: account_proj.balance,
-                is_active: account_proj.is_active,
-                version: batch.version + batch.events.len() as i64,
-            };
-
-            // Cache the updated account
-            self.cache_service
-                .set_account(&account, Some(Duration::from_secs(3600)))
-                .await?;
+    /// Applies the account's batches, in order, to its cached account. The projection
+    /// lacks the account's type, rules and metadata, so the entry is only rebuilt from
+    /// the cached account the batches follow on from, or from scratch for a new account;
+    /// otherwise it is invalidated and the next read replays the account.
+    async fn update_cached_account(&self, account_id: Uuid, batches: &[&EventBatch]) -> Result<()> {
+        let (Some(first), Some(last)) = (batches.first(), batches.last()) else {
+            return Ok(());
+        };
+        let latest = last.version + last.events.len() as i64;
+        let mut account = match self.cache_service.get_account(account_id).await? {
+            Some(account) if account.version >= latest => {
+                info!(
+                    "Skipped stale cache update for account {} at version {}",
+                    account_id, latest
+                );
+                return Ok(());
+            }
+            Some(account) if account.version == first.version => account,
+            None if first.version == 0 => Account {
+                id: account_id,
+                ..Account::default()
+            },
+            _ => return self.cache_service.delete_account(account_id).await,
+        };
+        for batch in batches {
+            if batch.version != account.version {
+                return self.cache_service.delete_account(account_id).await;
+            }
+            for event in &batch.events {
+                account.apply_event(event);
+            }
+        }
 
+        // Cache the updated account, unless a later batch already has
+        if self
+            .cache_service
+            .set_account_if_newer(&account, Some(Duration::from_secs(3600)))
+            .await?
+        {
             // Send cache update with final state
             self.producer
-                .send_cache_update(batch.account_id, &account)
+                .send_cache_update(account_id, &account)
                 .await?;
             self.metrics
                 .cache_updates
                 .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        } else {
+            info!(
+                "Skipped stale cache update for account {} at version {}",
+                account.id, account.version
+            );
         }
-
-        self.metrics.processing_latency.fetch_add(
-            start_time.elapsed().as_millis() as u64,
-            std::sync::atomic::Ordering::Relaxed,
-        );
-
         Ok(())
     }
 
+    async fn dead_letter(&self, batch: EventBatch, e: &anyhow::Error) -> Result<()> {
+        error!("Failed to process event batch: {}", e);
+        self.metrics
+            .processing_errors
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.tracing
+            .trace_error(&anyhow::anyhow!("{}", e), "batch_processing");
+
+        let exhausted = match &self.failure_log {
+            Some(failure_log) => match failure_log
+                .record_failure(
+                    &batch,
+                    &e.to_string(),
+                    DLQ_MAX_RETRIES as i32,
+                    DLQ_RETRY_DELAY,
+                )
+                .await
+            {
+                Ok(failure) => failure.status == ProcessingFailureStatus::DeadLettered,
+                Err(log_error) => {
+                    warn!("Failed to record processing failure: {}", log_error);
+                    false
+                }
+            },
+            None => false,
+        };
+
+        // Send to DLQ, which redelivers the batch unless it is out of attempts
+        let reason = format!("Batch processing failed: {}", e);
+        if exhausted {
+            self.dlq
+                .park_in_dlq(batch.account_id, batch.events, batch.version, reason)
+                .await
+        } else {
+            self.dlq
+                .send_to_dlq(batch.account_id, batch.events, batch.version, reason)
+                .await
+        }
+    }
+
     async fn should_trigger_recovery(&self) -> bool {
         let error_rate = self.metrics.get_error_rate();
         let consumer_lag = self
@@ -323,6 +621,30 @@ impl KafkaEventProcessor {
     }
 }
 
+/// The batch's events with their store-wide positions, when the batch has one.
+fn positioned_events(batch: &EventBatch) -> impl Iterator<Item = (Option<i64>, AccountEvent)> + '_ {
+    let first = batch
+        .global_position
+        .map(|position| position - batch.events.len() as i64 + 1);
+    batch
+        .events
+        .iter()
+        .enumerate()
+        .map(move |(i, event)| (first.map(|first| first + i as i64), event.clone()))
+}
+
+/// The batch's events with their positions and the versions they were saved at, so
+/// the projections can tell a redelivered event from a new one.
+fn projection_events(batch: &EventBatch) -> impl Iterator<Item = ProjectionEvent> + '_ {
+    positioned_events(batch)
+        .enumerate()
+        .map(move |(i, (position, event))| ProjectionEvent {
+            position,
+            version: Some(batch.version + i as i64 + 1),
+            event,
+        })
+}
+
 #[derive(Debug)]
 pub struct ProcessingMetrics {
     pub error_rate: f64,
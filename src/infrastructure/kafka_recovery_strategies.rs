@@ -1,4 +1,3 @@
-use crate::domain::{Account, AccountEvent};
 use crate::infrastructure::event_store::{EventStore, EventStoreTrait};
 use crate::infrastructure::kafka_abstraction::{KafkaConfig, KafkaConsumer, KafkaProducer};
 use crate::infrastructure::kafka_dlq::DeadLetterQueue;
@@ -73,8 +72,7 @@ impl RecoveryStrategies {
         for account in accounts {
             let events = self.event_store.get_events(account.id, None).await?;
             for event in events {
-                let account_event: AccountEvent = serde_json::from_value(event.event_data)
-                    .context("Failed to deserialize event")?;
+                let account_event = event.decode().context("Failed to deserialize event")?;
 
                 self.producer
                     .send_event_batch(account.id, vec![account_event], event.version)
@@ -108,8 +106,7 @@ impl RecoveryStrategies {
                 .await?;
 
             for event in events {
-                let account_event: AccountEvent = serde_json::from_value(event.event_data)
-                    .context("Failed to deserialize event")?;
+                let account_event = event.decode().context("Failed to deserialize event")?;
 
                 self.producer
                     .send_event_batch(account.id, vec![account_event], event.version)
@@ -151,8 +148,7 @@ impl RecoveryStrategies {
                     }
                 }
 
-                let account_event: AccountEvent = serde_json::from_value(event.event_data)
-                    .context("Failed to deserialize event")?;
+                let account_event = event.decode().context("Failed to deserialize event")?;
                 batch.push(account_event);
                 current_version = event.version;
             }
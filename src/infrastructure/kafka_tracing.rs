@@ -24,30 +24,122 @@ pub trait KafkaTracingTrait: Send + Sync {
     fn trace_recovery_operation(&self, strategy: &str, account_id: Option<Uuid>, status: &str);
 }
 
+/// Which spans get recorded. Mirrors the standard `OTEL_TRACES_SAMPLER` values.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SamplerConfig {
+    AlwaysOn,
+    AlwaysOff,
+    TraceIdRatio(f64),
+    ParentBased(Box<SamplerConfig>),
+}
+
+impl SamplerConfig {
+    /// Parses an `OTEL_TRACES_SAMPLER` name and its optional `OTEL_TRACES_SAMPLER_ARG`.
+    /// Unknown names fall back to always-on; a missing or invalid ratio defaults to 1.0.
+    pub fn parse(name: &str, arg: Option<&str>) -> Self {
+        let ratio = arg
+            .and_then(|a| a.trim().parse::<f64>().ok())
+            .map(|r| r.clamp(0.0, 1.0))
+            .unwrap_or(1.0);
+
+        match name.trim().to_lowercase().as_str() {
+            "always_off" => SamplerConfig::AlwaysOff,
+            "traceidratio" => SamplerConfig::TraceIdRatio(ratio),
+            "parentbased_always_on" => SamplerConfig::ParentBased(Box::new(SamplerConfig::AlwaysOn)),
+            "parentbased_always_off" => {
+                SamplerConfig::ParentBased(Box::new(SamplerConfig::AlwaysOff))
+            }
+            "parentbased_traceidratio" => {
+                SamplerConfig::ParentBased(Box::new(SamplerConfig::TraceIdRatio(ratio)))
+            }
+            "always_on" => SamplerConfig::AlwaysOn,
+            other => {
+                warn!("Unknown trace sampler '{}', using always_on", other);
+                SamplerConfig::AlwaysOn
+            }
+        }
+    }
+
+    pub fn to_sampler(&self) -> Sampler {
+        match self {
+            SamplerConfig::AlwaysOn => Sampler::AlwaysOn,
+            SamplerConfig::AlwaysOff => Sampler::AlwaysOff,
+            SamplerConfig::TraceIdRatio(ratio) => Sampler::TraceIdRatioBased(*ratio),
+            SamplerConfig::ParentBased(root) => Sampler::ParentBased(Box::new(root.to_sampler())),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TracingConfig {
+    pub service_name: String,
+    pub jaeger_endpoint: String,
+    pub deployment_environment: String,
+    pub sampler: SamplerConfig,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            service_name: "banking-es-kafka".to_string(),
+            jaeger_endpoint: "localhost:6831".to_string(),
+            deployment_environment: "development".to_string(),
+            sampler: SamplerConfig::AlwaysOn,
+        }
+    }
+}
+
+impl TracingConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let sampler_arg = std::env::var("OTEL_TRACES_SAMPLER_ARG").ok();
+        Self {
+            service_name: std::env::var("OTEL_SERVICE_NAME").unwrap_or(defaults.service_name),
+            jaeger_endpoint: std::env::var("JAEGER_AGENT_ENDPOINT")
+                .unwrap_or(defaults.jaeger_endpoint),
+            deployment_environment: std::env::var("DEPLOYMENT_ENVIRONMENT")
+                .unwrap_or(defaults.deployment_environment),
+            sampler: std::env::var("OTEL_TRACES_SAMPLER")
+                .map(|name| SamplerConfig::parse(&name, sampler_arg.as_deref()))
+                .unwrap_or(defaults.sampler),
+        }
+    }
+
+    pub fn trace_config(&self) -> trace::Config {
+        trace::config()
+            .with_sampler(self.sampler.to_sampler())
+            .with_id_generator(RandomIdGenerator::default())
+            .with_resource(Resource::new(vec![
+                KeyValue::new("service.name", self.service_name.clone()),
+                KeyValue::new(
+                    "deployment.environment",
+                    self.deployment_environment.clone(),
+                ),
+            ]))
+    }
+}
+
 #[derive(Clone)]
 pub struct KafkaTracing {
     metrics: Arc<KafkaMetrics>,
+    config: TracingConfig,
 }
 
 impl KafkaTracing {
     pub fn new(metrics: Arc<KafkaMetrics>) -> Self {
-        Self { metrics }
+        Self::with_config(metrics, TracingConfig::from_env())
+    }
+
+    pub fn with_config(metrics: Arc<KafkaMetrics>, config: TracingConfig) -> Self {
+        Self { metrics, config }
     }
 
     pub fn init_tracing(&self) -> Result<(), Box<dyn std::error::Error>> {
         // Configure OpenTelemetry
         let tracer = opentelemetry_jaeger::new_agent_pipeline()
-            .with_service_name("banking-es-kafka")
-            .with_endpoint("localhost:6831")
-            .with_trace_config(
-                opentelemetry_sdk::trace::config()
-                    .with_sampler(opentelemetry_sdk::trace::Sampler::AlwaysOn)
-                    .with_id_generator(opentelemetry_sdk::trace::RandomIdGenerator::default())
-                    .with_resource(opentelemetry_sdk::Resource::new(vec![
-                        opentelemetry::KeyValue::new("service.name", "banking-es-kafka"),
-                        opentelemetry::KeyValue::new("deployment.environment", "production"),
-                    ])),
-            )
+            .with_service_name(self.config.service_name.clone())
+            .with_endpoint(self.config.jaeger_endpoint.clone())
+            .with_trace_config(self.config.trace_config())
             .install_batch(opentelemetry_sdk::runtime::Tokio)?;
 
         // Create OpenTelemetry layer
@@ -91,6 +183,21 @@ impl KafkaTracing {
                 AccountEvent::AccountClosed { reason, .. } => {
                     info!("Processing AccountClosed event: {}", reason);
                 }
+                AccountEvent::OwnerNameChanged { .. } => {
+                    info!("Processing OwnerNameChanged event");
+                }
+                AccountEvent::MetadataSet { key, .. } => {
+                    info!("Processing MetadataSet event: {}", key);
+                }
+                AccountEvent::MetadataRemoved { key, .. } => {
+                    info!("Processing MetadataRemoved event: {}", key);
+                }
+                AccountEvent::FeeCharged { amount, .. } => {
+                    info!("Processing FeeCharged event: {}", amount);
+                }
+                AccountEvent::MinimumBalanceBreached { balance, .. } => {
+                    info!("Processing MinimumBalanceBreached event: {}", balance);
+                }
             }
         }
     }
@@ -230,3 +337,40 @@ impl KafkaTracingTrait for KafkaTracing {
         // Implementation
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ratio_sampler_config() {
+        let config = TracingConfig {
+            sampler: SamplerConfig::parse("traceidratio", Some("0.25")),
+            ..TracingConfig::default()
+        };
+
+        assert_eq!(config.sampler, SamplerConfig::TraceIdRatio(0.25));
+        assert!(matches!(
+            config.sampler.to_sampler(),
+            Sampler::TraceIdRatioBased(ratio) if ratio == 0.25
+        ));
+        // Building the SDK config must accept the sampler
+        let _ = config.trace_config();
+    }
+
+    #[test]
+    fn test_parent_based_sampler_config() {
+        let sampler = SamplerConfig::parse("parentbased_traceidratio", Some("1.5"));
+        assert_eq!(
+            sampler,
+            SamplerConfig::ParentBased(Box::new(SamplerConfig::TraceIdRatio(1.0)))
+        );
+        assert!(matches!(sampler.to_sampler(), Sampler::ParentBased(_)));
+    }
+
+    #[test]
+    fn test_default_sampler_is_always_on() {
+        assert_eq!(TracingConfig::default().sampler, SamplerConfig::AlwaysOn);
+        assert_eq!(SamplerConfig::parse("bogus", None), SamplerConfig::AlwaysOn);
+    }
+}
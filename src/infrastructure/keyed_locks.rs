@@ -0,0 +1,91 @@
+use dashmap::DashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Weak};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// One async mutex per key, created when the key is first locked. The map only holds
+/// weak references: a key's mutex lives as long as its guard and any waiters, and its
+/// entry is removed when the last of them drops, so keys locked once don't accumulate.
+pub struct KeyedLocks<K: Eq + Hash + Clone> {
+    locks: Arc<DashMap<K, Weak<Mutex<()>>>>,
+}
+
+impl<K: Eq + Hash + Clone> Default for KeyedLocks<K> {
+    fn default() -> Self {
+        Self {
+            locks: Arc::new(DashMap::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone> KeyedLocks<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits for the key's lock. Callers holding the same key are served in order.
+    pub async fn lock(&self, key: K) -> KeyedLockGuard<K> {
+        let mutex = self.mutex_for(&key);
+        let guard = mutex.lock_owned().await;
+        KeyedLockGuard {
+            locks: self.locks.clone(),
+            key,
+            guard: Some(guard),
+        }
+    }
+
+    /// Takes the key's lock if nobody holds it.
+    pub fn try_lock(&self, key: K) -> Option<KeyedLockGuard<K>> {
+        let guard = self.mutex_for(&key).try_lock_owned().ok()?;
+        Some(KeyedLockGuard {
+            locks: self.locks.clone(),
+            key,
+            guard: Some(guard),
+        })
+    }
+
+    /// Keys with a live lock, held or waited for.
+    pub fn len(&self) -> usize {
+        self.locks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locks.is_empty()
+    }
+
+    fn mutex_for(&self, key: &K) -> Arc<Mutex<()>> {
+        // The entry stays locked while it is upgraded or replaced, so a guard dropping
+        // at the same time can't remove it in between
+        let mut entry = self.locks.entry(key.clone()).or_default();
+        match entry.upgrade() {
+            Some(mutex) => mutex,
+            None => {
+                let mutex = Arc::new(Mutex::new(()));
+                *entry = Arc::downgrade(&mutex);
+                mutex
+            }
+        }
+    }
+}
+
+/// Holds a key's lock; releases it and reclaims the key's entry, if unused, on drop.
+pub struct KeyedLockGuard<K: Eq + Hash + Clone> {
+    locks: Arc<DashMap<K, Weak<Mutex<()>>>>,
+    key: K,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl<K: Eq + Hash + Clone> KeyedLockGuard<K> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K: Eq + Hash + Clone> Drop for KeyedLockGuard<K> {
+    fn drop(&mut self) {
+        // Release the mutex first so the count below only sees other holders and waiters
+        drop(self.guard.take());
+        self.locks
+            .remove_if(&self.key, |_, mutex| mutex.strong_count() == 0);
+    }
+}
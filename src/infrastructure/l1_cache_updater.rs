@@ -29,11 +29,16 @@ impl L1CacheUpdater {
                     info!("Received cache update for account: {}", account.id);
 
                     // Update L1 cache
-                    if let Err(e) = self.cache_service.set_account(&account, None).await {
-                        error!(
+                    match self.cache_service.set_account_if_newer(&account, None).await {
+                        Ok(true) => {}
+                        Ok(false) => info!(
+                            "Ignored out-of-order cache update for account {} at version {}",
+                            account.id, account.version
+                        ),
+                        Err(e) => error!(
                             "Failed to update L1 cache for account {}: {}",
                             account.id, e
-                        );
+                        ),
                     }
 
                     // Invalidate L1 event cache
@@ -0,0 +1,197 @@
+use crate::infrastructure::config::{LogFormat, LoggingConfig};
+use chrono::Utc;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Written in place of a redacted field's value.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Formats event and span fields as `name=value`, masking the configured fields.
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    fields: Arc<HashSet<String>>,
+}
+
+impl Redactor {
+    pub fn new(fields: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            fields: Arc::new(fields.into_iter().collect()),
+        }
+    }
+
+    /// The configured fields, or none when redaction is off.
+    pub fn from_config(config: &LoggingConfig) -> Self {
+        if config.redact {
+            Self::new(config.redacted_fields.iter().cloned())
+        } else {
+            Self::default()
+        }
+    }
+
+    pub fn masks(&self, field: &str) -> bool {
+        self.fields.contains(field)
+    }
+}
+
+impl<'writer> FormatFields<'writer> for Redactor {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'writer>, fields: R) -> fmt::Result {
+        let mut visitor = TextVisitor {
+            redactor: self,
+            writer,
+            empty: true,
+            result: Ok(()),
+        };
+        fields.record(&mut visitor);
+        visitor.result
+    }
+}
+
+struct TextVisitor<'a, 'writer> {
+    redactor: &'a Redactor,
+    writer: Writer<'writer>,
+    empty: bool,
+    result: fmt::Result,
+}
+
+impl Visit for TextVisitor<'_, '_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if self.result.is_err() {
+            return;
+        }
+        let separator = if self.empty { "" } else { " " };
+        self.empty = false;
+        self.result = if field.name() == "message" {
+            write!(self.writer, "{}{:?}", separator, value)
+        } else if self.redactor.masks(field.name()) {
+            write!(self.writer, "{}{}={}", separator, field.name(), REDACTED)
+        } else {
+            write!(self.writer, "{}{}={:?}", separator, field.name(), value)
+        };
+    }
+}
+
+/// One JSON object per event: timestamp, level, target, the event's fields with the
+/// configured ones masked, and the spans it happened in.
+#[derive(Debug, Clone)]
+pub struct JsonFormat {
+    redactor: Redactor,
+}
+
+impl JsonFormat {
+    pub fn new(redactor: Redactor) -> Self {
+        Self { redactor }
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'writer> FormatFields<'writer> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut visitor = JsonVisitor {
+            redactor: &self.redactor,
+            fields: Map::new(),
+        };
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        let mut line = Map::new();
+        line.insert("timestamp".into(), Utc::now().to_rfc3339().into());
+        line.insert("level".into(), metadata.level().to_string().into());
+        line.insert("target".into(), metadata.target().into());
+        line.insert("fields".into(), Value::Object(visitor.fields));
+        if let Some(scope) = ctx.event_scope() {
+            // Span fields were already formatted, and redacted, by the field formatter
+            let spans = scope
+                .from_root()
+                .map(|span| {
+                    let mut entry = Map::new();
+                    entry.insert("name".into(), span.name().into());
+                    if let Some(fields) = span.extensions().get::<FormattedFields<N>>() {
+                        if !fields.fields.is_empty() {
+                            entry.insert("fields".into(), fields.fields.clone().into());
+                        }
+                    }
+                    Value::Object(entry)
+                })
+                .collect();
+            line.insert("spans".into(), Value::Array(spans));
+        }
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
+
+struct JsonVisitor<'a> {
+    redactor: &'a Redactor,
+    fields: Map<String, Value>,
+}
+
+impl JsonVisitor<'_> {
+    fn insert(&mut self, field: &Field, value: Value) {
+        let value = if self.redactor.masks(field.name()) {
+            REDACTED.into()
+        } else {
+            value
+        };
+        self.fields.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for JsonVisitor<'_> {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, format!("{:?}", value).into());
+    }
+}
+
+/// The application's subscriber, writing to `make_writer` in the configured format.
+pub fn subscriber<W>(config: &LoggingConfig, make_writer: W) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let redactor = Redactor::from_config(config);
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(Level::INFO)
+        .with_writer(make_writer)
+        .fmt_fields(redactor.clone());
+    match config.format {
+        LogFormat::Json => Box::new(builder.event_format(JsonFormat::new(redactor)).finish()),
+        LogFormat::Pretty => Box::new(
+            builder
+                .with_target(false)
+                .with_thread_ids(true)
+                .with_file(true)
+                .with_line_number(true)
+                .finish(),
+        ),
+    }
+}
@@ -0,0 +1,198 @@
+use crate::infrastructure::config::MaintenanceConfig;
+use crate::infrastructure::event_store::EventStoreTrait;
+use crate::infrastructure::redis_abstraction::RedisClientTrait;
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+
+/// Key of the lock claiming a maintenance run.
+pub const MAINTENANCE_LOCK_KEY: &str = "lock:maintenance";
+
+#[derive(Error, Debug)]
+pub enum MaintenanceError {
+    #[error("Maintenance lock error: {0}")]
+    Lock(#[from] redis::RedisError),
+}
+
+/// Claims a maintenance run across instances.
+#[async_trait]
+pub trait MaintenanceLock: Send + Sync {
+    /// Takes `key` for `ttl` unless another holder has it. Returns whether it was taken.
+    async fn try_acquire(&self, key: &str, ttl: Duration) -> Result<bool, MaintenanceError>;
+}
+
+/// Redis lock taken with `RedisClientTrait::acquire_lock`. It isn't released after a run:
+/// holding it for the whole interval stops an instance whose timer fires a little later
+/// from running again.
+pub struct RedisMaintenanceLock {
+    redis_client: Arc<dyn RedisClientTrait>,
+}
+
+impl RedisMaintenanceLock {
+    pub fn new(redis_client: Arc<dyn RedisClientTrait>) -> Self {
+        Self { redis_client }
+    }
+}
+
+#[async_trait]
+impl MaintenanceLock for RedisMaintenanceLock {
+    async fn try_acquire(&self, key: &str, ttl: Duration) -> Result<bool, MaintenanceError> {
+        Ok(self.redis_client.acquire_lock(key, ttl).await?.is_some())
+    }
+}
+
+/// One step of a maintenance run. Returns how many items it processed.
+#[async_trait]
+pub trait MaintenanceTask: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn run(&self) -> anyhow::Result<u64>;
+}
+
+/// Snapshots aggregates with more than `min_event_count` events and new events since
+/// their last snapshot.
+pub struct SnapshotTask {
+    event_store: Arc<dyn EventStoreTrait>,
+    min_event_count: i64,
+}
+
+impl SnapshotTask {
+    pub fn new(event_store: Arc<dyn EventStoreTrait>, min_event_count: i64) -> Self {
+        Self {
+            event_store,
+            min_event_count,
+        }
+    }
+}
+
+#[async_trait]
+impl MaintenanceTask for SnapshotTask {
+    fn name(&self) -> &'static str {
+        "snapshot"
+    }
+
+    async fn run(&self) -> anyhow::Result<u64> {
+        let snapshotted = self
+            .event_store
+            .snapshot_aggregates_above_threshold(self.min_event_count)
+            .await?;
+        Ok(snapshotted.len() as u64)
+    }
+}
+
+/// Outcome of one task in a run.
+#[derive(Debug, Clone)]
+pub struct TaskSummary {
+    pub task: &'static str,
+    /// Items processed, or the error the task failed with.
+    pub result: Result<u64, String>,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct MaintenanceRunSummary {
+    pub tasks: Vec<TaskSummary>,
+    pub duration: Duration,
+}
+
+/// Runs the maintenance tasks every `interval`, on whichever instance claims the run.
+/// Tasks run in the order they were added; one failing doesn't stop the rest.
+pub struct MaintenanceScheduler {
+    config: MaintenanceConfig,
+    lock: Arc<dyn MaintenanceLock>,
+    tasks: Vec<Arc<dyn MaintenanceTask>>,
+    runs: AtomicU64,
+    skipped_runs: AtomicU64,
+}
+
+impl MaintenanceScheduler {
+    pub fn new(config: MaintenanceConfig, lock: Arc<dyn MaintenanceLock>) -> Self {
+        Self {
+            config,
+            lock,
+            tasks: Vec::new(),
+            runs: AtomicU64::new(0),
+            skipped_runs: AtomicU64::new(0),
+        }
+    }
+
+    pub fn with_task(mut self, task: Arc<dyn MaintenanceTask>) -> Self {
+        self.tasks.push(task);
+        self
+    }
+
+    /// Runs started on this instance.
+    pub fn runs(&self) -> u64 {
+        self.runs.load(Ordering::Relaxed)
+    }
+
+    /// Runs skipped because another instance held the lock.
+    pub fn skipped_runs(&self) -> u64 {
+        self.skipped_runs.load(Ordering::Relaxed)
+    }
+
+    /// Claims the lock and runs every task. Returns `None` if another instance has this
+    /// interval's run.
+    pub async fn run_once(&self) -> Result<Option<MaintenanceRunSummary>, MaintenanceError> {
+        if !self
+            .lock
+            .try_acquire(MAINTENANCE_LOCK_KEY, self.config.interval)
+            .await?
+        {
+            self.skipped_runs.fetch_add(1, Ordering::Relaxed);
+            debug!("Maintenance run skipped, another instance holds the lock");
+            return Ok(None);
+        }
+        self.runs.fetch_add(1, Ordering::Relaxed);
+
+        let start = Instant::now();
+        let mut tasks = Vec::with_capacity(self.tasks.len());
+        for task in &self.tasks {
+            let task_start = Instant::now();
+            let result = task.run().await.map_err(|e| format!("{:#}", e));
+            tasks.push(TaskSummary {
+                task: task.name(),
+                result,
+                duration: task_start.elapsed(),
+            });
+        }
+        let summary = MaintenanceRunSummary {
+            tasks,
+            duration: start.elapsed(),
+        };
+        let report: Vec<String> = summary
+            .tasks
+            .iter()
+            .map(|task| match &task.result {
+                Ok(count) => format!("{}: {} in {:?}", task.task, count, task.duration),
+                Err(e) => format!("{}: failed after {:?}: {}", task.task, task.duration, e),
+            })
+            .collect();
+        info!(
+            "Maintenance run finished in {:?} ({})",
+            summary.duration,
+            report.join(", ")
+        );
+        Ok(Some(summary))
+    }
+
+    /// Runs maintenance every interval, starting one interval from now.
+    pub fn start(self: &Arc<Self>) -> JoinHandle<()> {
+        let scheduler = Arc::clone(self);
+        tokio::spawn(async move {
+            let period = scheduler.config.interval;
+            let mut interval =
+                tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if let Err(e) = scheduler.run_once().await {
+                    error!("Maintenance run failed: {}", e);
+                }
+            }
+        })
+    }
+}
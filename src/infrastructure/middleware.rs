@@ -1,4 +1,5 @@
 use crate::domain::Account;
+use crate::infrastructure::tenant_quota::{QuotaExceeded, TenantPermit, TenantQuotas};
 use anyhow::Result;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
@@ -255,6 +256,7 @@ impl RequestValidationRule for TransactionValidator {
 pub struct RequestMiddleware {
     rate_limiter: RateLimiter,
     request_validator: RequestValidator,
+    tenant_quotas: Option<TenantQuotas>,
 }
 
 impl RequestMiddleware {
@@ -262,6 +264,42 @@ impl RequestMiddleware {
         Self {
             rate_limiter: RateLimiter::new(rate_limit_config),
             request_validator: RequestValidator::new(),
+            tenant_quotas: None,
+        }
+    }
+
+    /// Enforces per-tenant quotas; without them every tenant is unlimited.
+    pub fn with_tenant_quotas(mut self, tenant_quotas: TenantQuotas) -> Self {
+        self.tenant_quotas = Some(tenant_quotas);
+        self
+    }
+
+    pub fn tenant_quotas_enabled(&self) -> bool {
+        self.tenant_quotas.is_some()
+    }
+
+    /// Counts a request against the tenant's quotas. The returned permit holds one of the
+    /// tenant's concurrent slots until the request is done; `None` when quotas are off.
+    pub async fn acquire_tenant_quota(
+        &self,
+        tenant: &str,
+    ) -> Result<Option<TenantPermit>, QuotaExceeded> {
+        match &self.tenant_quotas {
+            Some(quotas) => quotas.acquire(tenant).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn reserve_tenant_account(&self, tenant: &str) -> Result<(), QuotaExceeded> {
+        match &self.tenant_quotas {
+            Some(quotas) => quotas.reserve_account(tenant).await,
+            None => Ok(()),
+        }
+    }
+
+    pub async fn release_tenant_account(&self, tenant: &str) {
+        if let Some(quotas) = &self.tenant_quotas {
+            quotas.release_account(tenant).await;
         }
     }
 
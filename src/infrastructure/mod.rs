@@ -1,8 +1,16 @@
 pub mod auth;
+pub mod authorization_audit;
 pub mod cache_service;
+pub mod compaction;
 pub mod config;
+pub mod event_serialization;
 pub mod event_store;
+pub mod event_store_timing;
+pub mod failed_commands;
+pub mod in_memory_cache_service;
+pub mod in_memory_event_store;
 pub mod init;
+pub mod jwt_keys;
 pub mod kafka_abstraction;
 pub mod kafka_dlq;
 pub mod kafka_event_processor;
@@ -11,21 +19,40 @@ pub mod kafka_monitoring;
 pub mod kafka_recovery;
 pub mod kafka_recovery_strategies;
 pub mod kafka_tracing;
+pub mod keyed_locks;
 pub mod l1_cache_updater;
+pub mod logging;
+pub mod maintenance;
 pub mod middleware;
+pub mod outbox;
+pub mod password;
+pub mod processing_failures;
+pub mod produce_queue;
 pub mod projections;
 pub mod rate_limiter;
+pub mod read_models;
+pub mod read_replica;
+pub mod readiness;
 pub mod redis_abstraction;
 pub mod repository;
 pub mod scaling;
+pub mod self_check;
 pub mod sharding;
+pub mod slo;
+pub mod shutdown;
+pub mod tenant_quota;
 pub mod user_repository;
 
 pub use auth::*;
 pub use cache_service::*;
 pub use config::*;
+pub use event_serialization::{EventFormat, EventSerializer};
 pub use event_store::{EventStore, EventStoreConfig};
-pub use kafka_abstraction::KafkaConfig;
+pub use event_store_timing::{EventStoreLatency, TimedEventStore};
+pub use failed_commands::{FailedCommand, FailedCommandError, FailedCommandStore};
+pub use in_memory_cache_service::InMemoryCacheService;
+pub use in_memory_event_store::InMemoryEventStore;
+pub use kafka_abstraction::{KafkaConfig, StartOffset};
 pub use kafka_dlq::*;
 pub use kafka_event_processor::KafkaEventProcessor;
 pub use kafka_metrics::*;
@@ -33,15 +60,28 @@ pub use kafka_monitoring::*;
 pub use kafka_recovery::*;
 pub use kafka_recovery_strategies::*;
 pub use kafka_tracing::*;
+pub use keyed_locks::{KeyedLockGuard, KeyedLocks};
 pub use middleware::*;
+pub use outbox::*;
+pub use password::{HashScheme, PasswordHashConfig, PasswordHashing};
+pub use processing_failures::{
+    ProcessingFailure, ProcessingFailureError, ProcessingFailureStatus, ProcessingFailureStore,
+};
+pub use produce_queue::{BatchPublisher, ProduceQueue, QueuedBatch};
 pub use projections::ProjectionStore;
 pub use projections::*;
 pub use rate_limiter::*;
+pub use read_models::{Projection, ProjectionDispatcher};
+pub use read_replica::ReadReplica;
+pub use readiness::{
+    DependencyCheck, DependencyStatus, ReadinessConfig, ReadinessGate, ReadinessStatus,
+};
 pub use redis_abstraction::*;
 pub use redis_abstraction::{RealRedisClient, RedisClientTrait};
 pub use repository::*;
 pub use repository::{AccountRepository, AccountRepositoryTrait, RepositoryError};
 pub use scaling::*;
 pub use sharding::*;
+pub use shutdown::{ShutdownConfig, ShutdownCoordinator, ShutdownPhase};
 pub use user_repository::*;
 pub use user_repository::{NewUser, User, UserRepository, UserRepositoryError}; // Added re-export
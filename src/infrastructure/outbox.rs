@@ -0,0 +1,403 @@
+use crate::domain::AccountEvent;
+use crate::infrastructure::kafka_abstraction::{
+    BankingKafkaError, CacheInvalidationType, KafkaProducer,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+#[derive(Error, Debug)]
+pub enum OutboxError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Kafka error: {0}")]
+    Kafka(#[from] BankingKafkaError),
+    #[error("Produce queue is full ({capacity} batches waiting)")]
+    QueueFull { capacity: usize },
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboxEntry {
+    pub id: Uuid,
+    pub aggregate_id: Uuid,
+    pub version: i64,
+    pub events: Value,
+    pub global_position: Option<i64>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// Durable queue of event batches waiting to be published to Kafka.
+#[derive(Clone)]
+pub struct OutboxStore {
+    pool: PgPool,
+}
+
+impl OutboxStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn enqueue(
+        &self,
+        aggregate_id: Uuid,
+        version: i64,
+        events: &[AccountEvent],
+        global_position: Option<i64>,
+    ) -> Result<Uuid, OutboxError> {
+        let id = Uuid::new_v4();
+        let events = serde_json::to_value(events)?;
+        sqlx::query!(
+            r#"
+            INSERT INTO kafka_outbox (id, aggregate_id, version, events, global_position)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            id,
+            aggregate_id,
+            version,
+            events,
+            global_position
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(id)
+    }
+
+    /// Returns unpublished entries, oldest first.
+    pub async fn fetch_pending(&self, limit: i64) -> Result<Vec<OutboxEntry>, OutboxError> {
+        let entries = sqlx::query_as!(
+            OutboxEntry,
+            r#"
+            SELECT id, aggregate_id, version, events, global_position, attempts, last_error,
+                   created_at, published_at
+            FROM kafka_outbox
+            WHERE published_at IS NULL
+            ORDER BY created_at ASC, version ASC
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+
+    pub async fn fetch_pending_for_aggregate(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Vec<OutboxEntry>, OutboxError> {
+        let entries = sqlx::query_as!(
+            OutboxEntry,
+            r#"
+            SELECT id, aggregate_id, version, events, global_position, attempts, last_error,
+                   created_at, published_at
+            FROM kafka_outbox
+            WHERE aggregate_id = $1 AND published_at IS NULL
+            ORDER BY created_at ASC, version ASC
+            "#,
+            aggregate_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+
+    pub async fn mark_published(&self, id: Uuid) -> Result<(), OutboxError> {
+        sqlx::query!(
+            "UPDATE kafka_outbox SET published_at = NOW(), attempts = attempts + 1 WHERE id = $1",
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn record_failure(&self, id: Uuid, error: &str) -> Result<(), OutboxError> {
+        sqlx::query!(
+            "UPDATE kafka_outbox SET attempts = attempts + 1, last_error = $2 WHERE id = $1",
+            id,
+            error
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn pending_count(&self) -> Result<i64, OutboxError> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM kafka_outbox WHERE published_at IS NULL"#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboxConfig {
+    pub relay_interval: Duration,
+    pub relay_batch_size: i64,
+    pub connectivity_timeout: Duration,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            relay_interval: Duration::from_secs(1),
+            relay_batch_size: 100,
+            connectivity_timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishOutcome {
+    Published,
+    Queued,
+    /// Kafka is disabled, so there is nothing to publish to.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KafkaHealth {
+    pub enabled: bool,
+    pub available: bool,
+    pub outbox_pending: Option<i64>,
+}
+
+/// Where the outbox relay sends entries. Implementations must tag each batch with its
+/// `EventBatch::idempotency_key`, as `KafkaProducer` does, so a batch sent again after a
+/// crash can be told apart from new events.
+#[async_trait]
+pub trait RelaySink: Send + Sync {
+    async fn send_batch(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        version: i64,
+        global_position: Option<i64>,
+    ) -> Result<(), BankingKafkaError>;
+}
+
+#[async_trait]
+impl RelaySink for KafkaProducer {
+    async fn send_batch(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        version: i64,
+        global_position: Option<i64>,
+    ) -> Result<(), BankingKafkaError> {
+        self.send_event_batch_with_position(aggregate_id, events, version, global_position)
+            .await
+    }
+}
+
+/// Publishes saved events to Kafka, falling back to the outbox while Kafka is unavailable
+/// so that writes never fail because of the broker.
+pub struct OutboxPublisher {
+    producer: KafkaProducer,
+    relay_sink: Arc<dyn RelaySink>,
+    store: OutboxStore,
+    config: OutboxConfig,
+    // Set while the outbox may hold unpublished entries; direct publishes would
+    // overtake them and break per-account ordering. Entries are queued and the flag set
+    // under the lock, and the relay holds it from its fetch until it clears the flag, so
+    // an entry queued mid-relay can't be left behind with the flag cleared.
+    backlog: Mutex<bool>,
+}
+
+impl OutboxPublisher {
+    pub fn new(producer: KafkaProducer, store: OutboxStore, config: OutboxConfig) -> Self {
+        Self {
+            relay_sink: Arc::new(producer.clone()),
+            producer,
+            store,
+            config,
+            // Entries may be left over from a previous run until the relay has checked
+            backlog: Mutex::new(true),
+        }
+    }
+
+    /// Relays outbox entries to `sink` instead of the Kafka producer.
+    pub fn with_relay_sink(mut self, sink: Arc<dyn RelaySink>) -> Self {
+        self.relay_sink = sink;
+        self
+    }
+
+    pub fn store(&self) -> &OutboxStore {
+        &self.store
+    }
+
+    pub async fn publish(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        version: i64,
+        global_position: Option<i64>,
+    ) -> Result<PublishOutcome, OutboxError> {
+        if !self.producer.is_enabled() {
+            return Ok(PublishOutcome::Skipped);
+        }
+
+        if self.producer.is_available() && !*self.backlog.lock().await {
+            match self
+                .producer
+                .send_event_batch_with_position(
+                    aggregate_id,
+                    events.clone(),
+                    version,
+                    global_position,
+                )
+                .await
+            {
+                Ok(()) => return Ok(PublishOutcome::Published),
+                Err(e) if e.is_unavailable() => {
+                    warn!(
+                        "Kafka unavailable, queueing events for account {} in the outbox: {}",
+                        aggregate_id, e
+                    );
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let mut backlog = self.backlog.lock().await;
+        self.store
+            .enqueue(aggregate_id, version, &events, global_position)
+            .await?;
+        *backlog = true;
+        debug!(
+            "Queued events for account {} at version {} in the outbox",
+            aggregate_id, version
+        );
+        Ok(PublishOutcome::Queued)
+    }
+
+    /// Stores events in the outbox without trying Kafka first; the relay publishes them.
+    pub async fn defer(
+        &self,
+        aggregate_id: Uuid,
+        events: &[AccountEvent],
+        version: i64,
+        global_position: Option<i64>,
+    ) -> Result<(), OutboxError> {
+        if !self.producer.is_enabled() {
+            return Ok(());
+        }
+        let mut backlog = self.backlog.lock().await;
+        self.store
+            .enqueue(aggregate_id, version, events, global_position)
+            .await?;
+        *backlog = true;
+        Ok(())
+    }
+
+    /// Publishes pending outbox entries in order, stopping at the first failure.
+    /// Returns the number of entries published. An entry is marked published only once
+    /// the broker has acknowledged it; if the process dies in between, the entry is sent
+    /// again after a restart under the same idempotency key.
+    pub async fn relay_pending(&self) -> Result<usize, OutboxError> {
+        let mut backlog = self.backlog.lock().await;
+        let entries = self
+            .store
+            .fetch_pending(self.config.relay_batch_size)
+            .await?;
+        if entries.is_empty() {
+            *backlog = false;
+            return Ok(0);
+        }
+
+        let mut published = 0;
+        for entry in &entries {
+            let events: Vec<AccountEvent> = serde_json::from_value(entry.events.clone())?;
+            if let Err(e) = self
+                .relay_sink
+                .send_batch(
+                    entry.aggregate_id,
+                    events,
+                    entry.version,
+                    entry.global_position,
+                )
+                .await
+            {
+                self.store.record_failure(entry.id, &e.to_string()).await?;
+                return Err(e.into());
+            }
+            self.store.mark_published(entry.id).await?;
+            published += 1;
+        }
+
+        if (entries.len() as i64) < self.config.relay_batch_size {
+            *backlog = false;
+        }
+        Ok(published)
+    }
+
+    pub fn start_relay(self: &Arc<Self>) {
+        let publisher = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(publisher.config.relay_interval);
+            loop {
+                interval.tick().await;
+                if !publisher.producer.is_enabled() {
+                    return;
+                }
+                if !*publisher.backlog.lock().await {
+                    continue;
+                }
+                if !publisher.producer.is_available()
+                    && publisher
+                        .producer
+                        .check_connectivity(publisher.config.connectivity_timeout)
+                        .await
+                        .is_err()
+                {
+                    continue;
+                }
+                match publisher.relay_pending().await {
+                    Ok(0) => {}
+                    Ok(count) => info!("Relayed {} outbox entries to Kafka", count),
+                    Err(OutboxError::Kafka(e)) if e.is_unavailable() => {
+                        debug!("Outbox relay paused, Kafka unavailable: {}", e)
+                    }
+                    Err(e) => error!("Outbox relay error: {}", e),
+                }
+            }
+        });
+    }
+
+    pub async fn health(&self) -> KafkaHealth {
+        KafkaHealth {
+            enabled: self.producer.is_enabled(),
+            available: self.producer.is_available(),
+            outbox_pending: self.store.pending_count().await.ok(),
+        }
+    }
+
+    /// Tells other instances to drop their cached copy of an account. Not queued in the
+    /// outbox: a missed invalidation only lasts until the entry's TTL runs out.
+    pub async fn publish_cache_invalidation(
+        &self,
+        account_id: Uuid,
+        invalidation_type: CacheInvalidationType,
+        reason: &str,
+    ) -> Result<(), OutboxError> {
+        self.producer
+            .send_cache_invalidation(account_id, invalidation_type, reason.to_string())
+            .await?;
+        Ok(())
+    }
+}
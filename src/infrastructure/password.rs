@@ -0,0 +1,168 @@
+use crate::infrastructure::auth::AuthError;
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+
+/// Argon2id cost parameters new credentials are hashed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PasswordHashConfig {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for PasswordHashConfig {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl PasswordHashConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            memory_kib: std::env::var("PASSWORD_HASH_MEMORY_KIB")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.memory_kib),
+            iterations: std::env::var("PASSWORD_HASH_ITERATIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.iterations),
+            parallelism: std::env::var("PASSWORD_HASH_PARALLELISM")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.parallelism),
+        }
+    }
+}
+
+/// The scheme a stored credential was hashed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashScheme {
+    /// Argon2id with the configured parameters.
+    Current,
+    /// Argon2i, Argon2d, an older Argon2 version, or Argon2id with other parameters.
+    LegacyArgon2,
+    /// `$2a$`, `$2b$` or `$2y$` hashes.
+    Bcrypt,
+    Unknown,
+}
+
+/// Outcome of checking a password against a stored hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordVerification {
+    Invalid,
+    /// The password matched; `needs_rehash` is set when the stored hash isn't in the
+    /// current scheme.
+    Valid {
+        needs_rehash: bool,
+    },
+}
+
+/// Hashes and verifies credentials, recognising the schemes older deployments
+/// stored so they can be upgraded on login.
+#[derive(Debug, Clone)]
+pub struct PasswordHashing {
+    config: PasswordHashConfig,
+}
+
+impl PasswordHashing {
+    pub fn new(config: PasswordHashConfig) -> Self {
+        Self { config }
+    }
+
+    fn argon2(&self) -> Result<Argon2<'static>, AuthError> {
+        let params = Params::new(
+            self.config.memory_kib,
+            self.config.iterations,
+            self.config.parallelism,
+            None,
+        )
+        .map_err(|e| AuthError::PasswordHashError(e.to_string()))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+
+    pub fn hash(&self, password: &str) -> Result<String, AuthError> {
+        let salt = SaltString::generate(&mut OsRng);
+        Ok(self
+            .argon2()?
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| AuthError::PasswordHashError(e.to_string()))?
+            .to_string())
+    }
+
+    pub fn scheme(&self, stored_hash: &str) -> HashScheme {
+        if ["$2a$", "$2b$", "$2y$"]
+            .iter()
+            .any(|prefix| stored_hash.starts_with(prefix))
+        {
+            return HashScheme::Bcrypt;
+        }
+        let Ok(parsed) = PasswordHash::new(stored_hash) else {
+            return HashScheme::Unknown;
+        };
+        if Algorithm::try_from(parsed.algorithm).is_err() {
+            return HashScheme::Unknown;
+        }
+        let is_current = parsed.algorithm == Algorithm::Argon2id.ident()
+            && parsed.version == Some(Version::V0x13.into())
+            && Params::try_from(&parsed).is_ok_and(|params| {
+                params.m_cost() == self.config.memory_kib
+                    && params.t_cost() == self.config.iterations
+                    && params.p_cost() == self.config.parallelism
+            });
+        if is_current {
+            HashScheme::Current
+        } else {
+            HashScheme::LegacyArgon2
+        }
+    }
+
+    pub fn verify(
+        &self,
+        password: &str,
+        stored_hash: &str,
+    ) -> Result<PasswordVerification, AuthError> {
+        let scheme = self.scheme(stored_hash);
+        match scheme {
+            HashScheme::Current | HashScheme::LegacyArgon2 => {
+                let parsed = PasswordHash::new(stored_hash)
+                    .map_err(|e| AuthError::PasswordHashError(e.to_string()))?;
+                // The stored hash carries its own algorithm and parameters
+                if Argon2::default()
+                    .verify_password(password.as_bytes(), &parsed)
+                    .is_ok()
+                {
+                    Ok(PasswordVerification::Valid {
+                        needs_rehash: scheme == HashScheme::LegacyArgon2,
+                    })
+                } else {
+                    Ok(PasswordVerification::Invalid)
+                }
+            }
+            HashScheme::Bcrypt => {
+                let matches = bcrypt::verify(password, stored_hash)
+                    .map_err(|e| AuthError::PasswordHashError(e.to_string()))?;
+                Ok(match matches {
+                    true => PasswordVerification::Valid { needs_rehash: true },
+                    false => PasswordVerification::Invalid,
+                })
+            }
+            HashScheme::Unknown => Err(AuthError::PasswordHashError(
+                "unrecognised password hash format".to_string(),
+            )),
+        }
+    }
+}
+
+impl Default for PasswordHashing {
+    fn default() -> Self {
+        Self::new(PasswordHashConfig::default())
+    }
+}
@@ -0,0 +1,237 @@
+use crate::infrastructure::kafka_abstraction::EventBatch;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
+use uuid::Uuid;
+
+#[derive(Error, Debug)]
+pub enum ProcessingFailureError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Unknown processing failure status: {0}")]
+    UnknownStatus(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessingFailureStatus {
+    /// Sent to the dead letter queue, which redelivers it at `next_retry_at`.
+    Retrying,
+    /// Processed successfully on a later attempt.
+    Resolved,
+    /// Out of attempts; left in the dead letter queue without further retries.
+    DeadLettered,
+}
+
+impl ProcessingFailureStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProcessingFailureStatus::Retrying => "retrying",
+            ProcessingFailureStatus::Resolved => "resolved",
+            ProcessingFailureStatus::DeadLettered => "dead_lettered",
+        }
+    }
+}
+
+impl FromStr for ProcessingFailureStatus {
+    type Err = ProcessingFailureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "retrying" => Ok(ProcessingFailureStatus::Retrying),
+            "resolved" => Ok(ProcessingFailureStatus::Resolved),
+            "dead_lettered" => Ok(ProcessingFailureStatus::DeadLettered),
+            other => Err(ProcessingFailureError::UnknownStatus(other.to_string())),
+        }
+    }
+}
+
+/// A consumed event batch that failed processing, identified by its account and version.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessingFailure {
+    pub id: Uuid,
+    pub aggregate_id: Uuid,
+    pub version: i64,
+    pub global_position: Option<i64>,
+    pub kafka_partition: Option<i32>,
+    pub kafka_offset: Option<i64>,
+    pub attempts: i32,
+    pub last_error: String,
+    pub status: ProcessingFailureStatus,
+    pub next_retry_at: Option<DateTime<Utc>>,
+    pub first_failed_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+struct ProcessingFailureRow {
+    id: Uuid,
+    aggregate_id: Uuid,
+    version: i64,
+    global_position: Option<i64>,
+    kafka_partition: Option<i32>,
+    kafka_offset: Option<i64>,
+    attempts: i32,
+    last_error: String,
+    status: String,
+    next_retry_at: Option<DateTime<Utc>>,
+    first_failed_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<ProcessingFailureRow> for ProcessingFailure {
+    type Error = ProcessingFailureError;
+
+    fn try_from(row: ProcessingFailureRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: row.id,
+            aggregate_id: row.aggregate_id,
+            version: row.version,
+            global_position: row.global_position,
+            kafka_partition: row.kafka_partition,
+            kafka_offset: row.kafka_offset,
+            attempts: row.attempts,
+            last_error: row.last_error,
+            status: row.status.parse()?,
+            next_retry_at: row.next_retry_at,
+            first_failed_at: row.first_failed_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+/// Queryable log of the Kafka event processor's failures. Unlike the dead letter queue
+/// it shows batches while they are still being retried, and how many attempts they took.
+#[derive(Clone)]
+pub struct ProcessingFailureStore {
+    pool: PgPool,
+}
+
+impl ProcessingFailureStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Counts a failed attempt at `batch`. The batch is retried after `retry_delay` until
+    /// it has failed `max_attempts` times, after which it is marked dead-lettered.
+    pub async fn record_failure(
+        &self,
+        batch: &EventBatch,
+        error: &str,
+        max_attempts: i32,
+        retry_delay: Duration,
+    ) -> Result<ProcessingFailure, ProcessingFailureError> {
+        let next_retry_at = Utc::now()
+            + chrono::Duration::from_std(retry_delay).unwrap_or(chrono::Duration::zero());
+        let row = sqlx::query_as!(
+            ProcessingFailureRow,
+            r#"
+            INSERT INTO processing_failures
+                (id, aggregate_id, version, global_position, kafka_partition, kafka_offset,
+                 attempts, last_error, status, next_retry_at)
+            VALUES ($1, $2, $3, $4, $5, $6, 1, $7,
+                    CASE WHEN 1 >= $8 THEN 'dead_lettered' ELSE 'retrying' END,
+                    CASE WHEN 1 >= $8 THEN NULL ELSE $9::timestamptz END)
+            ON CONFLICT (aggregate_id, version) DO UPDATE SET
+                global_position = EXCLUDED.global_position,
+                kafka_partition = EXCLUDED.kafka_partition,
+                kafka_offset = EXCLUDED.kafka_offset,
+                attempts = processing_failures.attempts + 1,
+                last_error = EXCLUDED.last_error,
+                status = CASE WHEN processing_failures.attempts + 1 >= $8
+                              THEN 'dead_lettered' ELSE 'retrying' END,
+                next_retry_at = CASE WHEN processing_failures.attempts + 1 >= $8
+                                     THEN NULL ELSE $9::timestamptz END,
+                updated_at = NOW()
+            RETURNING id, aggregate_id, version, global_position, kafka_partition, kafka_offset,
+                      attempts, last_error, status, next_retry_at, first_failed_at, updated_at
+            "#,
+            Uuid::new_v4(),
+            batch.account_id,
+            batch.version,
+            batch.global_position,
+            batch.partition,
+            batch.offset,
+            error,
+            max_attempts,
+            next_retry_at
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        row.try_into()
+    }
+
+    /// Marks the retrying failures of the given `(account, version)` batches resolved.
+    /// Batches that never failed are ignored.
+    pub async fn mark_resolved(
+        &self,
+        batches: &[(Uuid, i64)],
+    ) -> Result<u64, ProcessingFailureError> {
+        let (aggregate_ids, versions): (Vec<Uuid>, Vec<i64>) = batches.iter().copied().unzip();
+        let result = sqlx::query!(
+            r#"
+            UPDATE processing_failures
+            SET status = 'resolved', next_retry_at = NULL, updated_at = NOW()
+            WHERE status = 'retrying'
+              AND (aggregate_id, version) IN (
+                  SELECT * FROM UNNEST($1::uuid[], $2::bigint[])
+              )
+            "#,
+            &aggregate_ids,
+            &versions
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get(
+        &self,
+        aggregate_id: Uuid,
+        version: i64,
+    ) -> Result<Option<ProcessingFailure>, ProcessingFailureError> {
+        sqlx::query_as!(
+            ProcessingFailureRow,
+            r#"
+            SELECT id, aggregate_id, version, global_position, kafka_partition, kafka_offset,
+                   attempts, last_error, status, next_retry_at, first_failed_at, updated_at
+            FROM processing_failures
+            WHERE aggregate_id = $1 AND version = $2
+            "#,
+            aggregate_id,
+            version
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .map(ProcessingFailure::try_from)
+        .transpose()
+    }
+
+    /// Returns failures, most recently updated first, optionally only those in `status`.
+    pub async fn list(
+        &self,
+        status: Option<ProcessingFailureStatus>,
+        limit: i64,
+    ) -> Result<Vec<ProcessingFailure>, ProcessingFailureError> {
+        sqlx::query_as!(
+            ProcessingFailureRow,
+            r#"
+            SELECT id, aggregate_id, version, global_position, kafka_partition, kafka_offset,
+                   attempts, last_error, status, next_retry_at, first_failed_at, updated_at
+            FROM processing_failures
+            WHERE $1::text IS NULL OR status = $1
+            ORDER BY updated_at DESC
+            LIMIT $2
+            "#,
+            status.map(|status| status.as_str()),
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(ProcessingFailure::try_from)
+        .collect()
+    }
+}
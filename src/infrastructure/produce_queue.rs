@@ -0,0 +1,332 @@
+use crate::domain::AccountEvent;
+use crate::infrastructure::outbox::{OutboxError, OutboxPublisher, PublishOutcome};
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, Notify};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+/// Saved events waiting for the produce worker.
+#[derive(Debug, Clone)]
+pub struct QueuedBatch {
+    pub aggregate_id: Uuid,
+    pub events: Vec<AccountEvent>,
+    pub version: i64,
+    pub global_position: Option<i64>,
+}
+
+/// Where the produce worker sends batches.
+#[async_trait]
+pub trait BatchPublisher: Send + Sync + 'static {
+    /// Publishes a batch, falling back to durable storage if the broker can't take it.
+    async fn publish(&self, batch: &QueuedBatch) -> Result<PublishOutcome, OutboxError>;
+    /// Stores a batch durably for later publishing, without contacting the broker.
+    async fn defer(&self, batch: &QueuedBatch) -> Result<(), OutboxError>;
+}
+
+#[async_trait]
+impl BatchPublisher for OutboxPublisher {
+    async fn publish(&self, batch: &QueuedBatch) -> Result<PublishOutcome, OutboxError> {
+        self.publish(
+            batch.aggregate_id,
+            batch.events.clone(),
+            batch.version,
+            batch.global_position,
+        )
+        .await
+    }
+
+    async fn defer(&self, batch: &QueuedBatch) -> Result<(), OutboxError> {
+        self.defer(
+            batch.aggregate_id,
+            &batch.events,
+            batch.version,
+            batch.global_position,
+        )
+        .await
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ProduceQueueMetrics {
+    pub published: AtomicU64,
+    /// Batches moved to the outbox by a drain instead of being published.
+    pub deferred: AtomicU64,
+    pub failed: AtomicU64,
+    /// Writes refused because the queue was full.
+    pub rejected: AtomicU64,
+}
+
+/// Bounded queue between saving events and producing them, so a slow broker delays
+/// publishing rather than the requests that wrote the events. A single worker drains it
+/// in order. Batches reserved with `try_reserve_for` reach the worker in version order
+/// per account, whichever task saved them and whenever it got round to sending.
+///
+/// The queue lives in memory only. `drain` moves it to the outbox on a clean shutdown,
+/// but batches still queued when the process crashes are never published: their events
+/// stay in the event store and Kafka doesn't see them. Delivery through the queue is
+/// at most once; consumers that need every event must catch up from the event store.
+pub struct ProduceQueue {
+    sender: mpsc::Sender<QueuedBatch>,
+    capacity: usize,
+    // Batches reserved or queued but not yet handled by the worker
+    pending: Arc<AtomicUsize>,
+    spill: Arc<AtomicBool>,
+    idle: Arc<Notify>,
+    metrics: Arc<ProduceQueueMetrics>,
+    sequencer: Arc<Mutex<Sequencer>>,
+}
+
+/// Where a sequenced permit stands in its account's order.
+#[derive(Debug, Clone, Copy)]
+struct SequenceSlot {
+    aggregate_id: Uuid,
+    first_version: i64,
+    ticket: u64,
+}
+
+type ParkedBatch = (mpsc::OwnedPermit<QueuedBatch>, QueuedBatch);
+
+/// Holds back each account's batches until every batch reserved for earlier versions
+/// has been sent or given up. A reservation is taken before its events are saved, and
+/// a save only succeeds on top of the saves before it, so an account's reservations are
+/// never taken out of version order.
+#[derive(Default)]
+struct Sequencer {
+    next_ticket: u64,
+    // Per account, reservations by first version then reservation order; `None` until
+    // the batch is sent
+    aggregates: HashMap<Uuid, BTreeMap<(i64, u64), Option<ParkedBatch>>>,
+}
+
+impl Sequencer {
+    fn reserve(&mut self, aggregate_id: Uuid, first_version: i64) -> SequenceSlot {
+        let ticket = self.next_ticket;
+        self.next_ticket += 1;
+        self.aggregates
+            .entry(aggregate_id)
+            .or_default()
+            .insert((first_version, ticket), None);
+        SequenceSlot {
+            aggregate_id,
+            first_version,
+            ticket,
+        }
+    }
+
+    /// Fills or, given `None`, gives up `slot`, and returns the batches now free to go
+    /// to the worker, in order.
+    fn complete(&mut self, slot: SequenceSlot, batch: Option<ParkedBatch>) -> Vec<ParkedBatch> {
+        let Some(slots) = self.aggregates.get_mut(&slot.aggregate_id) else {
+            return Vec::new();
+        };
+        let key = (slot.first_version, slot.ticket);
+        match batch {
+            Some(batch) => {
+                slots.insert(key, Some(batch));
+            }
+            None => {
+                slots.remove(&key);
+            }
+        }
+        let mut ready = Vec::new();
+        while let Some(entry) = slots.first_entry() {
+            if entry.get().is_none() {
+                break;
+            }
+            ready.extend(entry.remove());
+        }
+        if slots.is_empty() {
+            self.aggregates.remove(&slot.aggregate_id);
+        }
+        ready
+    }
+}
+
+/// A reserved place in the queue. Dropping it unused gives the place back.
+pub struct ProducePermit {
+    permit: Option<mpsc::OwnedPermit<QueuedBatch>>,
+    pending: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
+    sequence: Option<(SequenceSlot, Arc<Mutex<Sequencer>>)>,
+}
+
+impl ProducePermit {
+    pub fn send(mut self, batch: QueuedBatch) {
+        // The worker releases the pending slot once it has handled the batch
+        let Some(permit) = self.permit.take() else {
+            return;
+        };
+        match self.sequence.take() {
+            Some((slot, sequencer)) => {
+                let ready = sequencer
+                    .lock()
+                    .unwrap()
+                    .complete(slot, Some((permit, batch)));
+                for (permit, batch) in ready {
+                    permit.send(batch);
+                }
+            }
+            None => {
+                permit.send(batch);
+            }
+        }
+    }
+}
+
+impl Drop for ProducePermit {
+    fn drop(&mut self) {
+        if self.permit.take().is_some() {
+            // Later batches of the account no longer wait for this one
+            if let Some((slot, sequencer)) = self.sequence.take() {
+                let ready = sequencer.lock().unwrap().complete(slot, None);
+                for (permit, batch) in ready {
+                    permit.send(batch);
+                }
+            }
+            release(&self.pending, &self.idle);
+        }
+    }
+}
+
+fn release(pending: &AtomicUsize, idle: &Notify) {
+    if pending.fetch_sub(1, Ordering::AcqRel) == 1 {
+        idle.notify_waiters();
+    }
+}
+
+impl ProduceQueue {
+    /// Creates the queue and spawns its worker.
+    pub fn start(publisher: Arc<dyn BatchPublisher>, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        let queue = Self {
+            sender,
+            capacity: capacity.max(1),
+            pending: Arc::new(AtomicUsize::new(0)),
+            spill: Arc::new(AtomicBool::new(false)),
+            idle: Arc::new(Notify::new()),
+            metrics: Arc::new(ProduceQueueMetrics::default()),
+            sequencer: Arc::new(Mutex::new(Sequencer::default())),
+        };
+        tokio::spawn(Self::worker(
+            publisher,
+            receiver,
+            queue.pending.clone(),
+            queue.spill.clone(),
+            queue.idle.clone(),
+            queue.metrics.clone(),
+        ));
+        queue
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Batches waiting for the worker, including the one it is publishing.
+    pub fn len(&self) -> usize {
+        self.pending.load(Ordering::Acquire)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn metrics(&self) -> &ProduceQueueMetrics {
+        &self.metrics
+    }
+
+    /// Reserves a place for a batch, failing immediately if the queue is full. Reserve
+    /// before saving, so a full queue turns the write away instead of leaving it
+    /// unpublished.
+    pub fn try_reserve(&self) -> Result<ProducePermit, OutboxError> {
+        match self.sender.clone().try_reserve_owned() {
+            Ok(permit) => {
+                self.pending.fetch_add(1, Ordering::AcqRel);
+                Ok(ProducePermit {
+                    permit: Some(permit),
+                    pending: self.pending.clone(),
+                    idle: self.idle.clone(),
+                    sequence: None,
+                })
+            }
+            Err(_) => {
+                self.metrics.rejected.fetch_add(1, Ordering::Relaxed);
+                Err(OutboxError::QueueFull {
+                    capacity: self.capacity,
+                })
+            }
+        }
+    }
+
+    /// Like `try_reserve`, for a batch of `aggregate_id` whose first event will have
+    /// `first_version`. The batch is held back until the account's batches reserved
+    /// for earlier versions have been sent, or dropped because their save failed.
+    pub fn try_reserve_for(
+        &self,
+        aggregate_id: Uuid,
+        first_version: i64,
+    ) -> Result<ProducePermit, OutboxError> {
+        let mut permit = self.try_reserve()?;
+        let slot = self
+            .sequencer
+            .lock()
+            .unwrap()
+            .reserve(aggregate_id, first_version);
+        permit.sequence = Some((slot, self.sequencer.clone()));
+        Ok(permit)
+    }
+
+    /// Moves every queued batch to the outbox instead of waiting for the broker, and
+    /// returns once the queue is empty. The outbox relay publishes them later. Used at
+    /// shutdown so queued events outlive the process.
+    pub async fn drain(&self) {
+        self.spill.store(true, Ordering::Release);
+        loop {
+            let idle = self.idle.notified();
+            if self.is_empty() {
+                break;
+            }
+            idle.await;
+        }
+        self.spill.store(false, Ordering::Release);
+    }
+
+    async fn worker(
+        publisher: Arc<dyn BatchPublisher>,
+        mut receiver: mpsc::Receiver<QueuedBatch>,
+        pending: Arc<AtomicUsize>,
+        spill: Arc<AtomicBool>,
+        idle: Arc<Notify>,
+        metrics: Arc<ProduceQueueMetrics>,
+    ) {
+        while let Some(batch) = receiver.recv().await {
+            let result = if spill.load(Ordering::Acquire) {
+                publisher.defer(&batch).await.map(|()| {
+                    metrics.deferred.fetch_add(1, Ordering::Relaxed);
+                })
+            } else {
+                publisher.publish(&batch).await.map(|outcome| {
+                    if outcome == PublishOutcome::Queued {
+                        debug!(
+                            "Events for account {} queued in the outbox",
+                            batch.aggregate_id
+                        );
+                    }
+                    metrics.published.fetch_add(1, Ordering::Relaxed);
+                })
+            };
+            if let Err(e) = result {
+                metrics.failed.fetch_add(1, Ordering::Relaxed);
+                error!(
+                    "Failed to publish events for account {} at version {}: {}",
+                    batch.aggregate_id, batch.version, e
+                );
+            }
+            release(&pending, &idle);
+        }
+        warn!("Produce queue closed");
+    }
+}
@@ -1,5 +1,7 @@
 use crate::domain::events::AccountEvent;
+use crate::infrastructure::event_serialization::EventFormat;
 use crate::infrastructure::event_store::DB_POOL;
+use crate::infrastructure::kafka_event_processor::PROJECTION_CHECKPOINT;
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -10,7 +12,7 @@ use sqlx::PgPool;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::{mpsc, OnceCell, RwLock};
+use tokio::sync::{mpsc, watch, OnceCell, RwLock};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
@@ -38,7 +40,7 @@ struct ProjectionMetrics {
     query_duration: std::sync::atomic::AtomicU64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct AccountProjection {
     pub id: Uuid,
     pub owner_name: String,
@@ -46,6 +48,7 @@ pub struct AccountProjection {
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub owner_user_id: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +60,77 @@ pub struct TransactionProjection {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Owner name search over the account projections.
+#[derive(Debug, Clone)]
+pub struct AccountSearch {
+    /// Matched case-insensitively as a substring of the owner name.
+    pub query: String,
+    /// Also match names that are only similar to the query (trigram similarity).
+    pub fuzzy: bool,
+    /// Restricts results to accounts owned by this user.
+    pub owner_user_id: Option<Uuid>,
+    /// Restricts results to accounts tagged with this metadata key and value.
+    pub metadata: Option<(String, String)>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Minimum trigram similarity for a fuzzy owner name match.
+const FUZZY_MATCH_THRESHOLD: f32 = 0.3;
+
+/// Escapes LIKE wildcards so those typed by the user are matched literally.
+fn escape_like(query: &str) -> String {
+    query
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Checkpoint of an in-progress blue-green reindex of the account projections.
+pub const REINDEX_CHECKPOINT: &str = "account_projections_reindex";
+/// Where a reindex builds the new account projections until they are swapped in.
+const REINDEX_SHADOW_TABLE: &str = "account_projections_shadow";
+/// Name the live table takes for the moment between the swap and its drop.
+const REINDEX_RETIRED_TABLE: &str = "account_projections_retired";
+
+/// How a reindex of the account projections ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReindexOutcome {
+    /// The rebuilt projections replaced the live ones.
+    Swapped { replayed: u64, position: i64 },
+    /// Stopped on request; the next reindex resumes after `position`.
+    Cancelled { replayed: u64, position: i64 },
+}
+
+/// What `apply_event_at_version` did with an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectionApply {
+    /// The event was the projection's next version and has been applied.
+    Applied { version: i64 },
+    /// The projection already holds this version, so the event was a replay.
+    Skipped { current: i64 },
+    /// Versions between the projection and the event are missing; nothing was written.
+    Gap { current: i64, received: i64 },
+}
+
+/// An event for `apply_events_batch`, with whatever is known of where it stands.
+#[derive(Debug, Clone)]
+pub struct ProjectionEvent {
+    /// Store-wide position, guarded by the projection's checkpoint.
+    pub position: Option<i64>,
+    /// Version within its aggregate, guarded by the projection's version.
+    pub version: Option<i64>,
+    pub event: AccountEvent,
+}
+
+/// How far a projection has got through the event stream it consumes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectionCheckpoint {
+    pub projection_name: String,
+    pub position: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(Clone)]
 struct CacheEntry<T> {
     data: T,
@@ -115,16 +189,39 @@ static PROJECTION_POOL: OnceCell<Arc<PgPool>> = OnceCell::const_new();
 #[async_trait]
 pub trait ProjectionStoreTrait: Send + Sync {
     async fn get_account(&self, account_id: Uuid) -> Result<Option<AccountProjection>>;
+    async fn get_account_uncached(&self, account_id: Uuid) -> Result<Option<AccountProjection>>;
     async fn get_all_accounts(&self) -> Result<Vec<AccountProjection>>;
+    async fn get_accounts_by_ids(&self, account_ids: &[Uuid]) -> Result<Vec<AccountProjection>>;
+    async fn get_accounts_by_owner(&self, owner_user_id: Uuid) -> Result<Vec<AccountProjection>>;
+    async fn search_accounts(&self, search: &AccountSearch) -> Result<Vec<AccountProjection>>;
+    /// Accounts matching `search`, ignoring its limit and offset.
+    async fn count_search_accounts(&self, search: &AccountSearch) -> Result<u64>;
     async fn get_account_transactions(
         &self,
         account_id: Uuid,
     ) -> Result<Vec<TransactionProjection>>;
     async fn upsert_accounts_batch(&self, accounts: Vec<AccountProjection>) -> Result<()>;
+    async fn upsert_account_now(&self, account: AccountProjection) -> Result<()>;
     async fn insert_transactions_batch(
         &self,
         transactions: Vec<TransactionProjection>,
     ) -> Result<()>;
+    async fn load_checkpoint(&self, projection_name: &str) -> Result<Option<ProjectionCheckpoint>>;
+    async fn save_checkpoint(&self, projection_name: &str, position: i64) -> Result<()>;
+    async fn apply_events_with_checkpoint(
+        &self,
+        projection_name: &str,
+        events: &[(i64, AccountEvent)],
+    ) -> Result<Option<i64>>;
+    async fn apply_events_batch(
+        &self,
+        projection_name: &str,
+        events: &[ProjectionEvent],
+    ) -> Result<Option<i64>>;
+    async fn projection_lag(&self) -> Result<u64>;
+    async fn get_account_metadata(&self, account_id: Uuid) -> Result<HashMap<String, String>>;
+    async fn set_account_metadata(&self, account_id: Uuid, key: &str, value: &str) -> Result<()>;
+    async fn remove_account_metadata(&self, account_id: Uuid, key: &str) -> Result<()>;
 }
 
 #[async_trait]
@@ -133,10 +230,30 @@ impl ProjectionStoreTrait for ProjectionStore {
         self.get_account(account_id).await
     }
 
+    async fn get_account_uncached(&self, account_id: Uuid) -> Result<Option<AccountProjection>> {
+        self.get_account_uncached(account_id).await
+    }
+
     async fn get_all_accounts(&self) -> Result<Vec<AccountProjection>> {
         self.get_all_accounts().await
     }
 
+    async fn get_accounts_by_ids(&self, account_ids: &[Uuid]) -> Result<Vec<AccountProjection>> {
+        self.get_accounts_by_ids(account_ids).await
+    }
+
+    async fn get_accounts_by_owner(&self, owner_user_id: Uuid) -> Result<Vec<AccountProjection>> {
+        self.get_accounts_by_owner(owner_user_id).await
+    }
+
+    async fn search_accounts(&self, search: &AccountSearch) -> Result<Vec<AccountProjection>> {
+        self.search_accounts(search).await
+    }
+
+    async fn count_search_accounts(&self, search: &AccountSearch) -> Result<u64> {
+        self.count_search_accounts(search).await
+    }
+
     async fn get_account_transactions(
         &self,
         account_id: Uuid,
@@ -148,15 +265,64 @@ impl ProjectionStoreTrait for ProjectionStore {
         self.upsert_accounts_batch(accounts).await
     }
 
+    async fn upsert_account_now(&self, account: AccountProjection) -> Result<()> {
+        self.upsert_account_now(account).await
+    }
+
     async fn insert_transactions_batch(
         &self,
         transactions: Vec<TransactionProjection>,
     ) -> Result<()> {
         self.insert_transactions_batch(transactions).await
     }
+
+    async fn load_checkpoint(&self, projection_name: &str) -> Result<Option<ProjectionCheckpoint>> {
+        self.load_checkpoint(projection_name).await
+    }
+
+    async fn save_checkpoint(&self, projection_name: &str, position: i64) -> Result<()> {
+        self.save_checkpoint(projection_name, position).await
+    }
+
+    async fn apply_events_with_checkpoint(
+        &self,
+        projection_name: &str,
+        events: &[(i64, AccountEvent)],
+    ) -> Result<Option<i64>> {
+        self.apply_events_with_checkpoint(projection_name, events)
+            .await
+    }
+
+    async fn apply_events_batch(
+        &self,
+        projection_name: &str,
+        events: &[ProjectionEvent],
+    ) -> Result<Option<i64>> {
+        self.apply_events_batch(projection_name, events).await
+    }
+
+    async fn projection_lag(&self) -> Result<u64> {
+        self.projection_lag().await
+    }
+
+    async fn get_account_metadata(&self, account_id: Uuid) -> Result<HashMap<String, String>> {
+        self.get_account_metadata(account_id).await
+    }
+
+    async fn set_account_metadata(&self, account_id: Uuid, key: &str, value: &str) -> Result<()> {
+        self.set_account_metadata(account_id, key, value).await
+    }
+
+    async fn remove_account_metadata(&self, account_id: Uuid, key: &str) -> Result<()> {
+        self.remove_account_metadata(account_id, key).await
+    }
 }
 
 impl ProjectionStore {
+    pub fn pool(&self) -> PgPool {
+        self.pool.clone()
+    }
+
     pub fn new(pool: PgPool) -> Self {
         Self::from_pool_with_config(pool, ProjectionConfig::default())
     }
@@ -275,7 +441,7 @@ impl ProjectionStore {
         let account: Option<AccountProjection> = sqlx::query_as!(
             AccountProjection,
             r#"
-            SELECT id, owner_name, balance, is_active, created_at, updated_at
+            SELECT id, owner_name, balance, is_active, created_at, updated_at, owner_user_id
             FROM account_projections
             WHERE id = $1
             "#,
@@ -308,6 +474,25 @@ impl ProjectionStore {
         Ok(account)
     }
 
+    /// Reads an account's row from the database, ignoring the cache.
+    pub async fn get_account_uncached(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<AccountProjection>> {
+        let account = sqlx::query_as!(
+            AccountProjection,
+            r#"
+            SELECT id, owner_name, balance, is_active, created_at, updated_at, owner_user_id
+            FROM account_projections
+            WHERE id = $1
+            "#,
+            account_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(account)
+    }
+
     pub async fn get_account_transactions(
         &self,
         account_id: Uuid,
@@ -376,7 +561,7 @@ impl ProjectionStore {
         let accounts = sqlx::query_as!(
             AccountProjection,
             r#"
-            SELECT id, owner_name, balance, is_active, created_at, updated_at
+            SELECT id, owner_name, balance, is_active, created_at, updated_at, owner_user_id
             FROM account_projections
             WHERE is_active = true
             ORDER BY created_at DESC
@@ -394,6 +579,946 @@ impl ProjectionStore {
         Ok(accounts)
     }
 
+    /// Fetches the projections of the given accounts in one query. Ids without a
+    /// projection are simply absent from the result.
+    pub async fn get_accounts_by_ids(&self, account_ids: &[Uuid]) -> Result<Vec<AccountProjection>> {
+        let start_time = Instant::now();
+
+        let accounts = sqlx::query_as!(
+            AccountProjection,
+            r#"
+            SELECT id, owner_name, balance, is_active, created_at, updated_at, owner_user_id
+            FROM account_projections
+            WHERE id = ANY($1)
+            "#,
+            account_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.metrics.query_duration.fetch_add(
+            start_time.elapsed().as_micros() as u64,
+            std::sync::atomic::Ordering::Relaxed,
+        );
+
+        Ok(accounts)
+    }
+
+    pub async fn get_accounts_by_owner(&self, owner_user_id: Uuid) -> Result<Vec<AccountProjection>> {
+        let start_time = Instant::now();
+
+        let accounts = sqlx::query_as!(
+            AccountProjection,
+            r#"
+            SELECT id, owner_name, balance, is_active, created_at, updated_at, owner_user_id
+            FROM account_projections
+            WHERE owner_user_id = $1
+            ORDER BY created_at DESC
+            "#,
+            owner_user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.metrics.query_duration.fetch_add(
+            start_time.elapsed().as_micros() as u64,
+            std::sync::atomic::Ordering::Relaxed,
+        );
+
+        Ok(accounts)
+    }
+
+    /// Searches accounts by owner name. Exact matches come first, then prefix matches,
+    /// then other substring matches, then fuzzy matches by similarity; ties are broken by
+    /// owner name and id so pages are stable.
+    pub async fn search_accounts(&self, search: &AccountSearch) -> Result<Vec<AccountProjection>> {
+        let start_time = Instant::now();
+
+        let escaped = escape_like(&search.query);
+        let (metadata_key, metadata_value) = search.metadata.clone().unzip();
+
+        let accounts = sqlx::query_as!(
+            AccountProjection,
+            r#"
+            SELECT id, owner_name, balance, is_active, created_at, updated_at, owner_user_id
+            FROM account_projections
+            WHERE ($2::uuid IS NULL OR owner_user_id = $2)
+              AND (
+                owner_name ILIKE '%' || $1 || '%'
+                OR ($4 AND similarity(owner_name, $3) >= $5)
+              )
+              AND ($8::text IS NULL OR EXISTS (
+                SELECT 1 FROM account_metadata m
+                WHERE m.account_id = account_projections.id AND m.key = $8 AND m.value = $9
+              ))
+            ORDER BY
+                CASE
+                    WHEN lower(owner_name) = lower($3) THEN 0
+                    WHEN owner_name ILIKE $1 || '%' THEN 1
+                    WHEN owner_name ILIKE '%' || $1 || '%' THEN 2
+                    ELSE 3
+                END,
+                similarity(owner_name, $3) DESC,
+                owner_name ASC,
+                id ASC
+            LIMIT $6 OFFSET $7
+            "#,
+            escaped,
+            search.owner_user_id,
+            search.query,
+            search.fuzzy,
+            FUZZY_MATCH_THRESHOLD,
+            search.limit,
+            search.offset,
+            metadata_key,
+            metadata_value
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.metrics.query_duration.fetch_add(
+            start_time.elapsed().as_micros() as u64,
+            std::sync::atomic::Ordering::Relaxed,
+        );
+
+        Ok(accounts)
+    }
+
+    /// Counts the accounts `search` matches across all pages.
+    pub async fn count_search_accounts(&self, search: &AccountSearch) -> Result<u64> {
+        let escaped = escape_like(&search.query);
+        let (metadata_key, metadata_value) = search.metadata.clone().unzip();
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM account_projections
+            WHERE ($2::uuid IS NULL OR owner_user_id = $2)
+              AND (
+                owner_name ILIKE '%' || $1 || '%'
+                OR ($4 AND similarity(owner_name, $3) >= $5)
+              )
+              AND ($6::text IS NULL OR EXISTS (
+                SELECT 1 FROM account_metadata m
+                WHERE m.account_id = account_projections.id AND m.key = $6 AND m.value = $7
+              ))
+            "#,
+            escaped,
+            search.owner_user_id,
+            search.query,
+            search.fuzzy,
+            FUZZY_MATCH_THRESHOLD,
+            metadata_key,
+            metadata_value
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count as u64)
+    }
+
+    /// Queues account projections for the background batch writer.
+    pub async fn upsert_accounts_batch(&self, accounts: Vec<AccountProjection>) -> Result<()> {
+        self.update_sender
+            .send(ProjectionUpdate::AccountBatch(accounts))
+            .map_err(|_| anyhow::anyhow!("Projection update processor has stopped"))
+    }
+
+    /// Writes an account projection right away rather than through the batch writer,
+    /// and refreshes its cached copy.
+    pub async fn upsert_account_now(&self, account: AccountProjection) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        Self::bulk_upsert_accounts(&mut tx, std::slice::from_ref(&account)).await?;
+        tx.commit().await?;
+
+        self.account_cache.write().await.insert(
+            account.id,
+            CacheEntry {
+                data: account,
+                last_accessed: Instant::now(),
+                version: self
+                    .cache_version
+                    .load(std::sync::atomic::Ordering::Relaxed),
+                ttl: Duration::from_secs(self.config.cache_ttl_secs),
+            },
+        );
+        Ok(())
+    }
+
+    /// Queues transaction projections for the background batch writer.
+    pub async fn insert_transactions_batch(
+        &self,
+        transactions: Vec<TransactionProjection>,
+    ) -> Result<()> {
+        self.update_sender
+            .send(ProjectionUpdate::TransactionBatch(transactions))
+            .map_err(|_| anyhow::anyhow!("Projection update processor has stopped"))
+    }
+
+    pub async fn load_checkpoint(
+        &self,
+        projection_name: &str,
+    ) -> Result<Option<ProjectionCheckpoint>> {
+        let checkpoint = sqlx::query_as!(
+            ProjectionCheckpoint,
+            r#"
+            SELECT projection_name, position, updated_at
+            FROM projection_checkpoints
+            WHERE projection_name = $1
+            "#,
+            projection_name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(checkpoint)
+    }
+
+    /// Sets the checkpoint unconditionally, e.g. to reset a projection before a full rebuild.
+    pub async fn save_checkpoint(&self, projection_name: &str, position: i64) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        Self::upsert_checkpoint(&mut tx, projection_name, position).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Applies `(position, event)` pairs to the account projections and advances the
+    /// projection's checkpoint in the same transaction. Events at or before the stored
+    /// checkpoint have already been applied and are skipped, so redelivered events are
+    /// never counted twice. Returns the checkpoint after the update.
+    pub async fn apply_events_with_checkpoint(
+        &self,
+        projection_name: &str,
+        events: &[(i64, AccountEvent)],
+    ) -> Result<Option<i64>> {
+        self.apply_events_in_transaction(
+            projection_name,
+            events
+                .iter()
+                .map(|(position, event)| (Some(*position), None, event))
+                .collect(),
+        )
+        .await
+    }
+
+    /// Like `apply_events_with_checkpoint`, for events that may not know their position,
+    /// such as consumed batches and replays from the dead letter queue. Events without a
+    /// position leave the checkpoint where it is. Events with a version are applied only
+    /// if it is their projection's next one, as with `apply_event_at_version`: versions
+    /// the projection already holds are skipped, so a redelivered event isn't applied
+    /// twice, and a gap fails the whole batch.
+    pub async fn apply_events_batch(
+        &self,
+        projection_name: &str,
+        events: &[ProjectionEvent],
+    ) -> Result<Option<i64>> {
+        self.apply_events_in_transaction(
+            projection_name,
+            events
+                .iter()
+                .map(|event| (event.position, event.version, &event.event))
+                .collect(),
+        )
+        .await
+    }
+
+    /// Applies `(position, version, event)`s in one transaction; see
+    /// `apply_events_with_checkpoint` and `apply_events_batch`.
+    async fn apply_events_in_transaction(
+        &self,
+        projection_name: &str,
+        events: Vec<(Option<i64>, Option<i64>, &AccountEvent)>,
+    ) -> Result<Option<i64>> {
+        let mut tx = self.pool.begin().await?;
+
+        let checkpoint = sqlx::query_scalar!(
+            "SELECT position FROM projection_checkpoints WHERE projection_name = $1 FOR UPDATE",
+            projection_name
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let mut position = checkpoint;
+        let mut pending = Vec::with_capacity(events.len());
+        for (event_position, version, event) in events {
+            match event_position {
+                Some(event_position) if position.is_none_or(|p| event_position > p) => {
+                    pending.push((version, event));
+                    position = Some(event_position);
+                }
+                Some(_) => {}
+                None => pending.push((version, event)),
+            }
+        }
+
+        if pending.is_empty() {
+            let _ = tx.rollback().await;
+            return Ok(checkpoint);
+        }
+
+        let ids: Vec<Uuid> = pending.iter().map(|(_, e)| e.aggregate_id()).collect();
+        let mut accounts: HashMap<Uuid, AccountProjection> = sqlx::query_as!(
+            AccountProjection,
+            r#"
+            SELECT id, owner_name, balance, is_active, created_at, updated_at, owner_user_id
+            FROM account_projections
+            WHERE id = ANY($1)
+            FOR UPDATE
+            "#,
+            &ids
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|a| (a.id, a))
+        .collect();
+        let mut versions: HashMap<Uuid, i64> =
+            sqlx::query_as("SELECT id, version FROM account_projections WHERE id = ANY($1)")
+                .bind(&ids)
+                .fetch_all(&mut *tx)
+                .await?
+                .into_iter()
+                .collect();
+
+        let now = Utc::now();
+        let mut applied = HashMap::new();
+        for (version, event) in &pending {
+            let account_id = event.aggregate_id();
+            let stored = versions.get(&account_id).copied().unwrap_or(0);
+            match version {
+                Some(version) if *version <= stored => {
+                    debug!(
+                        "Skipping {} v{} for account {}, projection is at v{}",
+                        event.event_type(),
+                        version,
+                        account_id,
+                        stored
+                    );
+                    continue;
+                }
+                Some(version) if *version != stored + 1 => anyhow::bail!(
+                    "Projection for account {} is at v{} but received {} v{}",
+                    account_id,
+                    stored,
+                    event.event_type(),
+                    version
+                ),
+                _ => {}
+            }
+            let current = match (accounts.get(&account_id), event) {
+                (Some(account), _) => account.clone(),
+                (None, AccountEvent::AccountCreated { .. }) => AccountProjection {
+                    id: account_id,
+                    owner_name: String::new(),
+                    balance: Decimal::ZERO,
+                    is_active: true,
+                    created_at: now,
+                    updated_at: now,
+                    owner_user_id: None,
+                },
+                (None, _) => {
+                    warn!(
+                        "Skipping {} for account {} with no projection",
+                        event.event_type(),
+                        account_id
+                    );
+                    continue;
+                }
+            };
+            let mut updated = current.apply_event(event)?;
+            updated.updated_at = now;
+            accounts.insert(account_id, updated);
+            versions.insert(account_id, stored + 1);
+            *applied.entry(account_id).or_insert(0) += 1;
+            Self::apply_metadata_event(&mut tx, event).await?;
+        }
+
+        let updated: Vec<AccountProjection> = accounts.into_values().collect();
+        Self::bulk_upsert_accounts(&mut tx, &updated).await?;
+        Self::advance_versions(&mut tx, "account_projections", &applied).await?;
+        if let Some(position) = position {
+            Self::upsert_checkpoint(&mut tx, projection_name, position).await?;
+        }
+        tx.commit().await?;
+
+        {
+            let mut cache = self.account_cache.write().await;
+            let version = self
+                .cache_version
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            for account in updated {
+                cache.insert(
+                    account.id,
+                    CacheEntry {
+                        data: account,
+                        last_accessed: Instant::now(),
+                        version,
+                        ttl: Duration::from_secs(self.config.cache_ttl_secs),
+                    },
+                );
+            }
+        }
+
+        self.metrics.events_processed.fetch_add(
+            applied.values().sum::<i64>() as u64,
+            std::sync::atomic::Ordering::Relaxed,
+        );
+
+        Ok(position)
+    }
+
+    /// Applies an event to its account's projection only if `version` is the projection's
+    /// next version, so re-applying events after a consumer crash neither counts them twice
+    /// nor steps over missing ones. Versions the projection already holds are skipped; a
+    /// later version means events are missing, and nothing is written so the caller can
+    /// fetch them instead of moving past them.
+    pub async fn apply_event_at_version(
+        &self,
+        event: &AccountEvent,
+        version: i64,
+    ) -> Result<ProjectionApply> {
+        let account_id = event.aggregate_id();
+        let mut tx = self.pool.begin().await?;
+
+        let current: Option<i64> =
+            sqlx::query_scalar("SELECT version FROM account_projections WHERE id = $1 FOR UPDATE")
+                .bind(account_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let stored = current.unwrap_or(0);
+        if version <= stored {
+            let _ = tx.rollback().await;
+            debug!(
+                "Skipping {} v{} for account {}, projection is at v{}",
+                event.event_type(),
+                version,
+                account_id,
+                stored
+            );
+            return Ok(ProjectionApply::Skipped { current: stored });
+        }
+        if version != stored + 1 {
+            let _ = tx.rollback().await;
+            warn!(
+                "Projection for account {} is at v{} but received {} v{}",
+                account_id,
+                stored,
+                event.event_type(),
+                version
+            );
+            return Ok(ProjectionApply::Gap {
+                current: stored,
+                received: version,
+            });
+        }
+
+        let now = Utc::now();
+        let projection: Option<AccountProjection> = sqlx::query_as(
+            r#"
+            SELECT id, owner_name, balance, is_active, created_at, updated_at, owner_user_id
+            FROM account_projections
+            WHERE id = $1
+            "#,
+        )
+        .bind(account_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let current = match (projection, event) {
+            (Some(projection), _) => projection,
+            (None, AccountEvent::AccountCreated { .. }) => AccountProjection {
+                id: account_id,
+                owner_name: String::new(),
+                balance: Decimal::ZERO,
+                is_active: true,
+                created_at: now,
+                updated_at: now,
+                owner_user_id: None,
+            },
+            (None, _) => anyhow::bail!(
+                "{} v{} for account {} with no projection",
+                event.event_type(),
+                version,
+                account_id
+            ),
+        };
+        let mut updated = current.apply_event(event)?;
+        updated.updated_at = now;
+
+        Self::bulk_upsert_accounts(&mut tx, std::slice::from_ref(&updated)).await?;
+        Self::apply_metadata_event(&mut tx, event).await?;
+        sqlx::query("UPDATE account_projections SET version = $2 WHERE id = $1")
+            .bind(account_id)
+            .bind(version)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        let cache_version = self
+            .cache_version
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.account_cache.write().await.insert(
+            account_id,
+            CacheEntry {
+                data: updated,
+                last_accessed: Instant::now(),
+                version: cache_version,
+                ttl: Duration::from_secs(self.config.cache_ttl_secs),
+            },
+        );
+        self.metrics
+            .events_processed
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        Ok(ProjectionApply::Applied { version })
+    }
+
+    /// The account's operations tags. Reindexing rebuilds only `account_projections`, so
+    /// tags survive it as they are.
+    pub async fn get_account_metadata(&self, account_id: Uuid) -> Result<HashMap<String, String>> {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT key, value FROM account_metadata WHERE account_id = $1")
+                .bind(account_id)
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Writes a tag straight away rather than waiting for its event to be projected.
+    pub async fn set_account_metadata(
+        &self,
+        account_id: Uuid,
+        key: &str,
+        value: &str,
+    ) -> Result<()> {
+        Self::upsert_metadata(&self.pool, account_id, key, value).await
+    }
+
+    pub async fn remove_account_metadata(&self, account_id: Uuid, key: &str) -> Result<()> {
+        Self::delete_metadata(&self.pool, account_id, key).await
+    }
+
+    /// Number of events stored past the consumer's checkpoint: the newest event's position
+    /// less the position the account projections have applied up to. Both are index
+    /// lookups, so the readiness monitor can poll this however large the store grows.
+    pub async fn projection_lag(&self) -> Result<u64> {
+        let lag = sqlx::query_scalar!(
+            r#"
+            SELECT (SELECT COALESCE(MAX(global_position), 0) FROM events)
+                - COALESCE(
+                    (SELECT position FROM projection_checkpoints WHERE projection_name = $1),
+                    0
+                ) AS "lag!"
+            "#,
+            PROJECTION_CHECKPOINT
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(lag.max(0) as u64)
+    }
+
+    /// Rebuilds the account projections from the event store in batches, resuming from
+    /// the projection's checkpoint if an earlier rebuild was interrupted. Events are
+    /// replayed in `global_position` order and each batch starts after the last position
+    /// applied, so a batch costs the same however far into the store the rebuild is.
+    /// Returns the number of events replayed by this call.
+    pub async fn rebuild_account_projections(
+        &self,
+        projection_name: &str,
+        batch_size: i64,
+    ) -> Result<u64> {
+        let mut position = self
+            .load_checkpoint(projection_name)
+            .await?
+            .map_or(0, |c| c.position);
+        let mut replayed = 0;
+
+        loop {
+            let rows: Vec<(i64, serde_json::Value, String)> = sqlx::query_as(
+                r#"
+                SELECT global_position, event_data, event_format
+                FROM events
+                WHERE global_position > $1
+                ORDER BY global_position
+                LIMIT $2
+                "#,
+            )
+            .bind(position)
+            .bind(batch_size)
+            .fetch_all(&self.pool)
+            .await?;
+
+            let row_count = rows.len() as i64;
+            let events = rows
+                .iter()
+                .map(|(global_position, data, format)| {
+                    let event = format
+                        .parse::<EventFormat>()?
+                        .serializer()
+                        .deserialize(data)?;
+                    Ok((*global_position, event))
+                })
+                .collect::<Result<Vec<(i64, AccountEvent)>>>()?;
+
+            self.apply_events_with_checkpoint(projection_name, &events)
+                .await?;
+            replayed += row_count as u64;
+            if let Some((last, _)) = events.last() {
+                position = *last;
+            }
+
+            if row_count < batch_size {
+                break;
+            }
+        }
+
+        info!(
+            "Rebuilt projection {}: replayed {} events",
+            projection_name, replayed
+        );
+        Ok(replayed)
+    }
+
+    /// Rebuilds the account projections from the event store without disturbing reads.
+    /// Events are replayed in `global_position` order into a shadow table while reads keep
+    /// using the live one. Once the shadow has caught up, the live table is locked, the last
+    /// events are applied and the tables are swapped in the same transaction, so readers
+    /// see the old model or the new one and never a partial one. Each batch advances
+    /// `REINDEX_CHECKPOINT`; `cancel` stops the build between batches and the next call
+    /// resumes from there. Closed accounts whose history was compacted away keep their
+    /// current rows. Only one reindex may run at a time.
+    pub async fn reindex_account_projections(
+        &self,
+        batch_size: i64,
+        cancel: &watch::Receiver<bool>,
+    ) -> Result<ReindexOutcome> {
+        let mut position = match self.load_checkpoint(REINDEX_CHECKPOINT).await? {
+            Some(checkpoint) => checkpoint.position,
+            None => {
+                self.prepare_reindex_shadow().await?;
+                0
+            }
+        };
+        let mut replayed = 0;
+
+        loop {
+            if *cancel.borrow() {
+                info!(
+                    "Reindex cancelled after {} events, at position {}",
+                    replayed, position
+                );
+                return Ok(ReindexOutcome::Cancelled { replayed, position });
+            }
+
+            let mut tx = self.pool.begin().await?;
+            let (count, last) = Self::replay_into_shadow(&mut tx, position, batch_size).await?;
+            if let Some(last) = last {
+                Self::upsert_checkpoint(&mut tx, REINDEX_CHECKPOINT, last).await?;
+                position = last;
+            }
+            tx.commit().await?;
+            replayed += count;
+
+            if (count as i64) < batch_size {
+                break;
+            }
+        }
+
+        let (count, position) = self.swap_in_reindexed(position).await?;
+        replayed += count;
+        info!(
+            "Reindexed account projections: replayed {} events up to position {}",
+            replayed, position
+        );
+        Ok(ReindexOutcome::Swapped { replayed, position })
+    }
+
+    /// Starts a reindex with an empty shadow table shaped like the live one, holding only
+    /// the compacted accounts, whose events are gone.
+    async fn prepare_reindex_shadow(&self) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(&format!("DROP TABLE IF EXISTS {}", REINDEX_SHADOW_TABLE))
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&format!(
+            "CREATE TABLE {} (LIKE account_projections INCLUDING ALL)",
+            REINDEX_SHADOW_TABLE
+        ))
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO {}
+            SELECT p.* FROM account_projections p
+            JOIN account_tombstones t ON t.account_id = p.id
+            "#,
+            REINDEX_SHADOW_TABLE
+        ))
+        .execute(&mut *tx)
+        .await?;
+        Self::upsert_checkpoint(&mut tx, REINDEX_CHECKPOINT, 0).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Applies up to `limit` events after `after` to the shadow table, returning how many
+    /// were replayed and the position of the last one.
+    async fn replay_into_shadow(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        after: i64,
+        limit: i64,
+    ) -> Result<(u64, Option<i64>)> {
+        let rows: Vec<(i64, serde_json::Value, String, DateTime<Utc>)> = sqlx::query_as(
+            r#"
+            SELECT global_position, event_data, event_format, timestamp
+            FROM events
+            WHERE global_position > $1
+            ORDER BY global_position
+            LIMIT $2
+            "#,
+        )
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&mut **tx)
+        .await?;
+        let Some(last) = rows.last().map(|(position, ..)| *position) else {
+            return Ok((0, None));
+        };
+
+        let events = rows
+            .iter()
+            .map(|(_, data, format, timestamp)| {
+                let event = format.parse::<EventFormat>()?.serializer().deserialize(data)?;
+                Ok((event, *timestamp))
+            })
+            .collect::<Result<Vec<(AccountEvent, DateTime<Utc>)>>>()?;
+
+        let ids: Vec<Uuid> = events.iter().map(|(e, _)| e.aggregate_id()).collect();
+        let mut accounts: HashMap<Uuid, AccountProjection> = sqlx::query_as(&format!(
+            r#"
+            SELECT id, owner_name, balance, is_active, created_at, updated_at, owner_user_id
+            FROM {}
+            WHERE id = ANY($1)
+            FOR UPDATE
+            "#,
+            REINDEX_SHADOW_TABLE
+        ))
+        .bind(&ids)
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .map(|a: AccountProjection| (a.id, a))
+        .collect();
+
+        let mut applied = HashMap::new();
+        for (event, timestamp) in &events {
+            let account_id = event.aggregate_id();
+            let current = match (accounts.get(&account_id), event) {
+                (Some(account), _) => account.clone(),
+                (None, AccountEvent::AccountCreated { .. }) => AccountProjection {
+                    id: account_id,
+                    owner_name: String::new(),
+                    balance: Decimal::ZERO,
+                    is_active: true,
+                    created_at: *timestamp,
+                    updated_at: *timestamp,
+                    owner_user_id: None,
+                },
+                (None, _) => continue,
+            };
+            let mut updated = current.apply_event(event)?;
+            updated.updated_at = *timestamp;
+            accounts.insert(account_id, updated);
+            *applied.entry(account_id).or_insert(0) += 1;
+        }
+
+        let accounts: Vec<AccountProjection> = accounts.into_values().collect();
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO {} (id, owner_name, balance, is_active, created_at, updated_at, owner_user_id)
+            SELECT * FROM UNNEST($1::uuid[], $2::text[], $3::decimal[], $4::boolean[], $5::timestamptz[], $6::timestamptz[], $7::uuid[])
+            ON CONFLICT (id) DO UPDATE SET
+                owner_name = EXCLUDED.owner_name,
+                balance = EXCLUDED.balance,
+                is_active = EXCLUDED.is_active,
+                updated_at = EXCLUDED.updated_at,
+                owner_user_id = EXCLUDED.owner_user_id
+            "#,
+            REINDEX_SHADOW_TABLE
+        ))
+        .bind(accounts.iter().map(|a| a.id).collect::<Vec<_>>())
+        .bind(accounts.iter().map(|a| a.owner_name.clone()).collect::<Vec<_>>())
+        .bind(accounts.iter().map(|a| a.balance).collect::<Vec<_>>())
+        .bind(accounts.iter().map(|a| a.is_active).collect::<Vec<_>>())
+        .bind(accounts.iter().map(|a| a.created_at).collect::<Vec<_>>())
+        .bind(accounts.iter().map(|a| a.updated_at).collect::<Vec<_>>())
+        .bind(accounts.iter().map(|a| a.owner_user_id).collect::<Vec<_>>())
+        .execute(&mut **tx)
+        .await?;
+        Self::advance_versions(tx, REINDEX_SHADOW_TABLE, &applied).await?;
+
+        Ok((events.len() as u64, Some(last)))
+    }
+
+    /// Applies the events stored since `position` to the shadow table and swaps it in for
+    /// the live one, returning the events applied and the final position.
+    async fn swap_in_reindexed(&self, mut position: i64) -> Result<(u64, i64)> {
+        let mut tx = self.pool.begin().await?;
+        // The event processor locks its checkpoint before the table; doing the same here
+        // keeps the two from deadlocking
+        sqlx::query(
+            "SELECT position FROM projection_checkpoints WHERE projection_name = $1 FOR UPDATE",
+        )
+        .bind(PROJECTION_CHECKPOINT)
+        .fetch_optional(&mut *tx)
+        .await?;
+        sqlx::query("LOCK TABLE account_projections IN ACCESS EXCLUSIVE MODE")
+            .execute(&mut *tx)
+            .await?;
+
+        let (count, last) = Self::replay_into_shadow(&mut tx, position, i64::MAX).await?;
+        if let Some(last) = last {
+            position = last;
+        }
+
+        for statement in [
+            format!(
+                "ALTER TABLE account_projections RENAME TO {}",
+                REINDEX_RETIRED_TABLE
+            ),
+            format!(
+                "ALTER TABLE {} RENAME TO account_projections",
+                REINDEX_SHADOW_TABLE
+            ),
+            format!("DROP TABLE {}", REINDEX_RETIRED_TABLE),
+        ] {
+            sqlx::query(&statement).execute(&mut *tx).await?;
+        }
+
+        // The new table already holds every event up to `position`, so the event
+        // processor must not apply them again
+        sqlx::query(
+            r#"
+            INSERT INTO projection_checkpoints (projection_name, position, updated_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (projection_name) DO UPDATE SET
+                position = GREATEST(projection_checkpoints.position, EXCLUDED.position),
+                updated_at = EXCLUDED.updated_at
+            "#,
+        )
+        .bind(PROJECTION_CHECKPOINT)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM projection_checkpoints WHERE projection_name = $1")
+            .bind(REINDEX_CHECKPOINT)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        self.account_cache.write().await.clear();
+        Ok((count, position))
+    }
+
+    pub(crate) async fn upsert_checkpoint(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        projection_name: &str,
+        position: i64,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO projection_checkpoints (projection_name, position, updated_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (projection_name) DO UPDATE SET
+                position = EXCLUDED.position,
+                updated_at = EXCLUDED.updated_at
+            "#,
+            projection_name,
+            position
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mirrors a tag change into `account_metadata`; other events leave it alone.
+    async fn apply_metadata_event(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        event: &AccountEvent,
+    ) -> Result<()> {
+        match event {
+            AccountEvent::MetadataSet {
+                account_id,
+                key,
+                value,
+            } => Self::upsert_metadata(&mut **tx, *account_id, key, value).await,
+            AccountEvent::MetadataRemoved { account_id, key } => {
+                Self::delete_metadata(&mut **tx, *account_id, key).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn upsert_metadata(
+        executor: impl sqlx::PgExecutor<'_>,
+        account_id: Uuid,
+        key: &str,
+        value: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO account_metadata (account_id, key, value, updated_at)
+            VALUES ($1, $2, $3, NOW())
+            ON CONFLICT (account_id, key) DO UPDATE SET
+                value = EXCLUDED.value,
+                updated_at = EXCLUDED.updated_at
+            "#,
+        )
+        .bind(account_id)
+        .bind(key)
+        .bind(value)
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_metadata(
+        executor: impl sqlx::PgExecutor<'_>,
+        account_id: Uuid,
+        key: &str,
+    ) -> Result<()> {
+        sqlx::query("DELETE FROM account_metadata WHERE account_id = $1 AND key = $2")
+            .bind(account_id)
+            .bind(key)
+            .execute(executor)
+            .await?;
+        Ok(())
+    }
+
+    /// Moves each account's projection version on by the number of events just applied
+    /// to it in `table`.
+    async fn advance_versions(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        table: &str,
+        applied: &HashMap<Uuid, i64>,
+    ) -> Result<()> {
+        if applied.is_empty() {
+            return Ok(());
+        }
+        let (ids, counts): (Vec<Uuid>, Vec<i64>) = applied.iter().map(|(id, n)| (*id, *n)).unzip();
+        sqlx::query(&format!(
+            r#"
+            UPDATE {table} p
+            SET version = p.version + a.applied
+            FROM UNNEST($1::uuid[], $2::bigint[]) AS a(id, applied)
+            WHERE p.id = a.id
+            "#
+        ))
+        .bind(ids)
+        .bind(counts)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
     async fn update_processor(
         pool: PgPool,
         mut receiver: mpsc::UnboundedReceiver<ProjectionUpdate>,
@@ -520,23 +1645,26 @@ impl ProjectionStore {
         let is_actives: Vec<bool> = accounts.iter().map(|a| a.is_active).collect();
         let created_ats: Vec<DateTime<Utc>> = accounts.iter().map(|a| a.created_at).collect();
         let updated_ats: Vec<DateTime<Utc>> = accounts.iter().map(|a| a.updated_at).collect();
+        let owner_user_ids: Vec<Option<Uuid>> = accounts.iter().map(|a| a.owner_user_id).collect();
 
         sqlx::query!(
             r#"
-            INSERT INTO account_projections (id, owner_name, balance, is_active, created_at, updated_at)
-            SELECT * FROM UNNEST($1::uuid[], $2::text[], $3::decimal[], $4::boolean[], $5::timestamptz[], $6::timestamptz[])
+            INSERT INTO account_projections (id, owner_name, balance, is_active, created_at, updated_at, owner_user_id)
+            SELECT * FROM UNNEST($1::uuid[], $2::text[], $3::decimal[], $4::boolean[], $5::timestamptz[], $6::timestamptz[], $7::uuid[])
             ON CONFLICT (id) DO UPDATE SET
                 owner_name = EXCLUDED.owner_name,
                 balance = EXCLUDED.balance,
                 is_active = EXCLUDED.is_active,
-                updated_at = EXCLUDED.updated_at
+                updated_at = EXCLUDED.updated_at,
+                owner_user_id = COALESCE(EXCLUDED.owner_user_id, account_projections.owner_user_id)
             "#,
             &ids,
             &owner_names,
             &balances,
             &is_actives,
             &created_ats,
-            &updated_ats
+            &updated_ats,
+            &owner_user_ids as &[Option<Uuid>]
         )
         .execute(&mut **tx)
         .await?;
@@ -660,21 +1788,30 @@ impl AccountProjection {
             AccountEvent::AccountCreated {
                 owner_name,
                 initial_balance,
+                owner_user_id,
                 ..
             } => {
                 projection.owner_name = owner_name.clone();
                 projection.balance = *initial_balance;
                 projection.is_active = true;
+                projection.owner_user_id = *owner_user_id;
             }
             AccountEvent::MoneyDeposited { amount, .. } => {
                 projection.balance += *amount;
             }
-            AccountEvent::MoneyWithdrawn { amount, .. } => {
+            AccountEvent::MoneyWithdrawn { amount, .. }
+            | AccountEvent::FeeCharged { amount, .. } => {
                 projection.balance -= *amount;
             }
             AccountEvent::AccountClosed { .. } => {
                 projection.is_active = false;
             }
+            AccountEvent::OwnerNameChanged { new, .. } => {
+                projection.owner_name = new.clone();
+            }
+            // Tags are projected into `account_metadata` instead
+            AccountEvent::MetadataSet { .. } | AccountEvent::MetadataRemoved { .. } => {}
+            AccountEvent::MinimumBalanceBreached { .. } => {}
         }
         Ok(projection)
     }
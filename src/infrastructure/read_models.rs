@@ -0,0 +1,105 @@
+use crate::domain::AccountEvent;
+use crate::infrastructure::projections::ProjectionStore;
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use tracing::debug;
+
+/// An independent read model fed by the event processor, such as balances, statements or
+/// analytics. Each projection only sees the event types it is interested in and keeps its
+/// own checkpoint in `projection_checkpoints`, under `name()`.
+#[async_trait]
+pub trait Projection: Send + Sync {
+    /// Checkpoint name; must be unique among registered projections.
+    fn name(&self) -> &str;
+
+    /// Whether events of this type, as given by `AccountEvent::event_type`, are applied.
+    fn interested_in(&self, event_type: &str) -> bool;
+
+    /// Applies one event in the transaction that also advances the checkpoint.
+    async fn apply(&self, event: &AccountEvent, tx: &mut Transaction<'_, Postgres>)
+        -> Result<()>;
+}
+
+/// Hands consumed events to every registered projection.
+#[derive(Clone)]
+pub struct ProjectionDispatcher {
+    pool: PgPool,
+    projections: Vec<Arc<dyn Projection>>,
+}
+
+impl ProjectionDispatcher {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            projections: Vec::new(),
+        }
+    }
+
+    pub fn register(mut self, projection: Arc<dyn Projection>) -> Self {
+        self.projections.push(projection);
+        self
+    }
+
+    pub fn projections(&self) -> &[Arc<dyn Projection>] {
+        &self.projections
+    }
+
+    /// Applies `(position, event)` pairs to each projection in turn, one transaction per
+    /// projection. Events at or before a projection's checkpoint are skipped, so
+    /// redelivered events reach only the projections that missed them. The checkpoint moves
+    /// past uninteresting events too; events without a position are always applied and
+    /// leave it where it is.
+    pub async fn dispatch(&self, events: &[(Option<i64>, AccountEvent)]) -> Result<()> {
+        for projection in &self.projections {
+            self.dispatch_to(projection.as_ref(), events).await?;
+        }
+        Ok(())
+    }
+
+    async fn dispatch_to(
+        &self,
+        projection: &dyn Projection,
+        events: &[(Option<i64>, AccountEvent)],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let checkpoint: Option<i64> = sqlx::query_scalar(
+            "SELECT position FROM projection_checkpoints WHERE projection_name = $1 FOR UPDATE",
+        )
+        .bind(projection.name())
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let mut position = checkpoint;
+        let mut applied = 0;
+        for (event_position, event) in events {
+            match event_position {
+                Some(event_position) if position.is_none_or(|p| *event_position > p) => {
+                    position = Some(*event_position);
+                }
+                Some(_) => continue,
+                None => {}
+            }
+            if projection.interested_in(event.event_type()) {
+                projection.apply(event, &mut tx).await?;
+                applied += 1;
+            }
+        }
+
+        if position != checkpoint {
+            if let Some(position) = position {
+                ProjectionStore::upsert_checkpoint(&mut tx, projection.name(), position).await?;
+            }
+        }
+        tx.commit().await?;
+
+        debug!(
+            "Projection {} applied {} of {} events",
+            projection.name(),
+            applied,
+            events.len()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use sqlx::PgPool;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How long a replica's availability and lag are trusted before they are checked again.
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A Postgres read replica event reads can be served from while it answers and is no
+/// more than `max_lag` behind the primary. The check is cached for `check_interval`, so
+/// reads don't each pay for it.
+pub struct ReadReplica {
+    pool: PgPool,
+    max_lag: Duration,
+    check_interval: Duration,
+    /// When the replica was last checked, and whether it was usable then.
+    state: Mutex<Option<(Instant, bool)>>,
+}
+
+impl ReadReplica {
+    pub fn new(pool: PgPool, max_lag: Duration) -> Self {
+        Self {
+            pool,
+            max_lag,
+            check_interval: DEFAULT_CHECK_INTERVAL,
+            state: Mutex::new(None),
+        }
+    }
+
+    pub fn with_check_interval(mut self, check_interval: Duration) -> Self {
+        self.check_interval = check_interval;
+        self
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// How far the replica's replay is behind the WAL it has received; zero when it is
+    /// caught up, or when the pool is connected to a primary.
+    pub async fn lag(&self) -> Result<Duration, sqlx::Error> {
+        let seconds: f64 = sqlx::query_scalar(
+            r#"
+            SELECT COALESCE(
+                CASE WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+                     ELSE EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp())
+                END, 0)::float8
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(Duration::from_secs_f64(seconds.max(0.0)))
+    }
+
+    /// Whether reads should go to the replica, checking it again if the last check is
+    /// older than `check_interval`.
+    pub async fn usable(&self) -> bool {
+        if let Some((checked_at, usable)) = *self.state.lock().unwrap() {
+            if checked_at.elapsed() < self.check_interval {
+                return usable;
+            }
+        }
+        let (usable, reason) = match self.lag().await {
+            Ok(lag) if lag <= self.max_lag => (true, None),
+            Ok(lag) => (
+                false,
+                Some(format!("{:?} behind, limit {:?}", lag, self.max_lag)),
+            ),
+            Err(e) => (false, Some(e.to_string())),
+        };
+        let previous = self.state.lock().unwrap().replace((Instant::now(), usable));
+        // Only changes are logged, not every check while the replica stays down
+        let was_usable = previous.is_none_or(|(_, usable)| usable);
+        match reason {
+            Some(reason) if was_usable => {
+                warn!(
+                    "Read replica not usable, reading from the primary: {}",
+                    reason
+                )
+            }
+            None if !was_usable => info!("Read replica is usable again"),
+            _ => {}
+        }
+        usable
+    }
+
+    /// Routes reads to the primary until the next check, after a read on the replica
+    /// failed with `error`.
+    pub fn mark_unavailable(&self, error: &sqlx::Error) {
+        warn!(
+            "Read on the replica failed, reading from the primary: {}",
+            error
+        );
+        *self.state.lock().unwrap() = Some((Instant::now(), false));
+    }
+}
+
+/// Errors that mean the server couldn't be reached or stopped serving, as opposed to
+/// errors in the query itself, which the primary would return too.
+pub fn is_unavailable_error(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::PoolTimedOut
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed => true,
+        // Connection exceptions, shutdowns and conflicts with recovery on a standby
+        sqlx::Error::Database(e) => e.code().is_some_and(|code| {
+            code.starts_with("08") || code.starts_with("57P") || code == "40001"
+        }),
+        _ => false,
+    }
+}
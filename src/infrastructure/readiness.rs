@@ -0,0 +1,421 @@
+use crate::infrastructure::kafka_abstraction::KafkaProducer;
+use crate::infrastructure::projections::ProjectionStoreTrait;
+use crate::infrastructure::redis_abstraction::RedisClientTrait;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Mutex};
+use tracing::{info, warn};
+
+/// What a fresh instance waits for before it reports ready.
+#[derive(Debug, Clone)]
+pub struct ReadinessConfig {
+    /// Wait for the startup cache warm-up to finish.
+    pub require_cache_warmup: bool,
+    /// Largest projection lag, in events, an instance may start serving with.
+    pub max_projection_lag: u64,
+    /// How often projection lag is checked until the instance is ready.
+    pub check_interval: Duration,
+    pub dependencies: DependencyChecksConfig,
+}
+
+impl Default for ReadinessConfig {
+    fn default() -> Self {
+        Self {
+            require_cache_warmup: true,
+            max_projection_lag: 100,
+            check_interval: Duration::from_secs(1),
+            dependencies: DependencyChecksConfig::default(),
+        }
+    }
+}
+
+impl ReadinessConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            require_cache_warmup: std::env::var("READINESS_REQUIRE_CACHE_WARMUP")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.require_cache_warmup),
+            max_projection_lag: std::env::var("READINESS_MAX_PROJECTION_LAG")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.max_projection_lag),
+            check_interval: std::env::var("READINESS_CHECK_INTERVAL_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(default.check_interval),
+            dependencies: DependencyChecksConfig::from_env(),
+        }
+    }
+}
+
+/// How the readiness probe checks each dependency once the instance has warmed up.
+#[derive(Debug, Clone)]
+pub struct DependencyChecksConfig {
+    /// Run against Postgres; any successful execution counts as healthy.
+    pub postgres_query: String,
+    pub postgres_timeout: Duration,
+    /// Key that must exist in Redis; `None` only sends `PING`.
+    pub redis_key: Option<String>,
+    pub redis_timeout: Duration,
+    /// Timeout for fetching the Kafka cluster metadata.
+    pub kafka_timeout: Duration,
+    /// How long a check's result is reused, so frequent probes don't hammer dependencies.
+    pub cache_ttl: Duration,
+}
+
+impl Default for DependencyChecksConfig {
+    fn default() -> Self {
+        Self {
+            postgres_query: "SELECT 1".to_string(),
+            postgres_timeout: Duration::from_secs(1),
+            redis_key: None,
+            redis_timeout: Duration::from_millis(500),
+            kafka_timeout: Duration::from_secs(2),
+            cache_ttl: Duration::from_secs(5),
+        }
+    }
+}
+
+impl DependencyChecksConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let millis = |key: &str, default: Duration| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(default)
+        };
+        Self {
+            postgres_query: std::env::var("READINESS_POSTGRES_QUERY")
+                .ok()
+                .filter(|query| !query.trim().is_empty())
+                .unwrap_or(default.postgres_query),
+            postgres_timeout: millis("READINESS_POSTGRES_TIMEOUT_MS", default.postgres_timeout),
+            redis_key: std::env::var("READINESS_REDIS_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            redis_timeout: millis("READINESS_REDIS_TIMEOUT_MS", default.redis_timeout),
+            kafka_timeout: millis("READINESS_KAFKA_TIMEOUT_MS", default.kafka_timeout),
+            cache_ttl: millis("READINESS_DEPENDENCY_CACHE_MS", default.cache_ttl),
+        }
+    }
+}
+
+/// A dependency the instance can't serve without, checked by the readiness probe.
+#[async_trait]
+pub trait DependencyCheck: Send + Sync {
+    /// Name the dependency is reported under.
+    fn name(&self) -> &str;
+
+    /// How long `check` may run before the dependency counts as down.
+    fn timeout(&self) -> Duration;
+
+    async fn check(&self) -> Result<()>;
+}
+
+/// Runs a query against Postgres.
+pub struct PostgresCheck {
+    pool: PgPool,
+    query: String,
+    timeout: Duration,
+}
+
+impl PostgresCheck {
+    pub fn new(pool: PgPool, query: String, timeout: Duration) -> Self {
+        Self {
+            pool,
+            query,
+            timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl DependencyCheck for PostgresCheck {
+    fn name(&self) -> &str {
+        "postgres"
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    async fn check(&self) -> Result<()> {
+        sqlx::query(&self.query).execute(&self.pool).await?;
+        Ok(())
+    }
+}
+
+/// Sends `PING` to Redis, or checks that a given key exists.
+pub struct RedisCheck {
+    client: Arc<dyn RedisClientTrait>,
+    key: Option<String>,
+    timeout: Duration,
+}
+
+impl RedisCheck {
+    pub fn new(client: Arc<dyn RedisClientTrait>, key: Option<String>, timeout: Duration) -> Self {
+        Self {
+            client,
+            key,
+            timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl DependencyCheck for RedisCheck {
+    fn name(&self) -> &str {
+        "redis"
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    async fn check(&self) -> Result<()> {
+        let mut conn = self.client.get_connection().await?;
+        match &self.key {
+            Some(key) => {
+                let exists: bool = redis::cmd("EXISTS").arg(key).query_async(&mut conn).await?;
+                anyhow::ensure!(exists, "key {} is missing", key);
+            }
+            None => {
+                let _: String = redis::cmd("PING").query_async(&mut conn).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fetches the Kafka cluster metadata.
+pub struct KafkaCheck {
+    producer: KafkaProducer,
+    timeout: Duration,
+}
+
+impl KafkaCheck {
+    pub fn new(producer: KafkaProducer, timeout: Duration) -> Self {
+        Self { producer, timeout }
+    }
+}
+
+#[async_trait]
+impl DependencyCheck for KafkaCheck {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    async fn check(&self) -> Result<()> {
+        self.producer.check_connectivity(self.timeout).await?;
+        Ok(())
+    }
+}
+
+/// Result of a dependency's latest check.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyStatus {
+    pub name: String,
+    pub healthy: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// A dependency check with its last result, reused for `cache_ttl`. Concurrent probes
+/// wait for the check in flight rather than starting their own.
+struct CachedCheck {
+    check: Arc<dyn DependencyCheck>,
+    last: Mutex<Option<(Instant, DependencyStatus)>>,
+}
+
+impl CachedCheck {
+    async fn status(&self, cache_ttl: Duration) -> DependencyStatus {
+        let mut last = self.last.lock().await;
+        if let Some((checked_at, status)) = last.as_ref() {
+            if checked_at.elapsed() < cache_ttl {
+                return status.clone();
+            }
+        }
+
+        let started = Instant::now();
+        let result = match tokio::time::timeout(self.check.timeout(), self.check.check()).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow::anyhow!(
+                "timed out after {:?}",
+                self.check.timeout()
+            )),
+        };
+        let status = DependencyStatus {
+            name: self.check.name().to_string(),
+            healthy: result.is_ok(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            error: result.err().map(|e| e.to_string()),
+        };
+        if let Some(error) = &status.error {
+            warn!("Readiness check of {} failed: {}", status.name, error);
+        }
+        *last = Some((Instant::now(), status.clone()));
+        status
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessStatus {
+    pub ready: bool,
+    pub cache_warmed: bool,
+    /// False while a required startup self-check hasn't passed.
+    pub self_check_passed: bool,
+    /// `None` until the first lag check completes.
+    pub projection_lag: Option<u64>,
+    pub max_projection_lag: u64,
+}
+
+/// Tracks whether this instance has warmed up enough to take traffic. Once both the
+/// cache and the projections have caught up it latches ready, so a later burst of lag
+/// doesn't pull a serving instance out of rotation.
+pub struct ReadinessGate {
+    config: ReadinessConfig,
+    cache_warmed: AtomicBool,
+    self_check_passed: AtomicBool,
+    projection_lag: AtomicU64,
+    ready: watch::Sender<bool>,
+    dependencies: Vec<CachedCheck>,
+}
+
+/// Stored in `projection_lag` until the first check.
+const LAG_UNKNOWN: u64 = u64::MAX;
+
+impl ReadinessGate {
+    pub fn new(config: ReadinessConfig) -> Self {
+        let cache_warmed = !config.require_cache_warmup;
+        Self {
+            config,
+            cache_warmed: AtomicBool::new(cache_warmed),
+            self_check_passed: AtomicBool::new(true),
+            projection_lag: AtomicU64::new(LAG_UNKNOWN),
+            ready: watch::Sender::new(false),
+            dependencies: Vec::new(),
+        }
+    }
+
+    /// Adds a dependency the readiness probe checks once the instance has warmed up.
+    pub fn with_dependency(mut self, check: Arc<dyn DependencyCheck>) -> Self {
+        self.dependencies.push(CachedCheck {
+            check,
+            last: Mutex::new(None),
+        });
+        self
+    }
+
+    /// Holds the instance not ready until `record_self_check` reports a passed self-check.
+    pub fn requiring_self_check(self) -> Self {
+        self.self_check_passed.store(false, Ordering::Relaxed);
+        self
+    }
+
+    /// Checks every dependency concurrently, reusing results younger than the cache TTL.
+    pub async fn check_dependencies(&self) -> Vec<DependencyStatus> {
+        let cache_ttl = self.config.dependencies.cache_ttl;
+        futures::future::join_all(
+            self.dependencies
+                .iter()
+                .map(|dependency| dependency.status(cache_ttl)),
+        )
+        .await
+    }
+
+    /// A gate that is ready from the start, for services without startup work.
+    pub fn ready() -> Self {
+        let gate = Self::new(ReadinessConfig {
+            require_cache_warmup: false,
+            ..ReadinessConfig::default()
+        });
+        gate.record_projection_lag(0);
+        gate
+    }
+
+    pub fn mark_cache_warmed(&self) {
+        self.cache_warmed.store(true, Ordering::Relaxed);
+        self.evaluate();
+    }
+
+    pub fn record_self_check(&self, passed: bool) {
+        self.self_check_passed.store(passed, Ordering::Relaxed);
+        self.evaluate();
+    }
+
+    pub fn record_projection_lag(&self, lag: u64) {
+        self.projection_lag.store(lag, Ordering::Relaxed);
+        self.evaluate();
+    }
+
+    pub fn is_ready(&self) -> bool {
+        *self.ready.borrow()
+    }
+
+    pub fn status(&self) -> ReadinessStatus {
+        let lag = self.projection_lag.load(Ordering::Relaxed);
+        ReadinessStatus {
+            ready: self.is_ready(),
+            cache_warmed: self.cache_warmed.load(Ordering::Relaxed),
+            self_check_passed: self.self_check_passed.load(Ordering::Relaxed),
+            projection_lag: (lag != LAG_UNKNOWN).then_some(lag),
+            max_projection_lag: self.config.max_projection_lag,
+        }
+    }
+
+    /// Resolves once the instance is ready.
+    pub async fn wait_until_ready(&self) {
+        let _ = self.ready.subscribe().wait_for(|ready| *ready).await;
+    }
+
+    fn evaluate(&self) {
+        if self.is_ready() {
+            return;
+        }
+        let lag = self.projection_lag.load(Ordering::Relaxed);
+        if self.cache_warmed.load(Ordering::Relaxed)
+            && self.self_check_passed.load(Ordering::Relaxed)
+            && lag <= self.config.max_projection_lag
+        {
+            self.ready.send_replace(true);
+            info!("Instance is ready (projection lag {})", lag);
+        }
+    }
+
+    /// Checks projection lag every `check_interval` until the instance is ready.
+    pub fn spawn_projection_lag_monitor(
+        self: &Arc<Self>,
+        projections: Arc<dyn ProjectionStoreTrait + 'static>,
+    ) -> tokio::task::JoinHandle<()> {
+        let gate = self.clone();
+        tokio::spawn(async move {
+            while !gate.is_ready() {
+                match projections.projection_lag().await {
+                    Ok(lag) => gate.record_projection_lag(lag),
+                    Err(e) => warn!("Failed to check projection lag: {}", e),
+                }
+                tokio::time::sleep(gate.config.check_interval).await;
+            }
+        })
+    }
+}
+
+impl Default for ReadinessGate {
+    fn default() -> Self {
+        Self::ready()
+    }
+}
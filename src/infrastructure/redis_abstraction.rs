@@ -9,12 +9,56 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{RwLock, Semaphore, Mutex};
+use tracing::{info, warn};
+use uuid::Uuid;
 // Required for the trait methods even if not used by RealRedisConnection directly for all methods now
 // use mockall::automock; // Removed: no longer used
 #[allow(unused_imports)]
 use redis::ToRedisArgs;
 use redis::aio::ConnectionLike;
 
+/// Takes the lock if it is free and bumps the key's fencing counter.
+const ACQUIRE_LOCK_SCRIPT: &str = r#"
+if redis.call("SET", KEYS[1], ARGV[1], "NX", "PX", ARGV[2]) then
+    return redis.call("INCR", KEYS[2])
+end
+return false
+"#;
+
+const RELEASE_LOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+const EXTEND_LOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+/// Bumps a counter, starting its expiry on the first increment so it counts over a
+/// fixed window.
+const INCR_WITH_TTL_SCRIPT: &str = r#"
+local count = redis.call("INCR", KEYS[1])
+if count == 1 then
+    redis.call("PEXPIRE", KEYS[1], ARGV[1])
+end
+return count
+"#;
+
+/// Proof of holding a lock taken with `RedisClientTrait::acquire_lock`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockToken {
+    /// Identifies the holder; only it can release or extend the lock.
+    pub id: String,
+    /// Grows each time the key is acquired. Passed along with guarded writes, it lets
+    /// the target reject a holder whose lock expired and was taken by someone else.
+    pub fence: u64,
+}
+
 /// Defines a trait for a Redis client that can provide connections.
 /// This allows for mocking the client itself in unit tests.
 #[async_trait]
@@ -35,6 +79,71 @@ pub trait RedisClientTrait: Send + Sync {
     async fn get(&self, key: &str) -> Result<Option<String>, RedisError>;
     async fn set(&self, key: &str, value: &str) -> Result<(), RedisError>;
     async fn del(&self, key: &str) -> Result<(), RedisError>;
+
+    /// Takes a cross-instance lock on `key` for `ttl`, or returns `None` if another holder
+    /// has it. The lock expires by itself if its holder dies without releasing it.
+    async fn acquire_lock(
+        &self,
+        key: &str,
+        ttl: Duration,
+    ) -> Result<Option<LockToken>, RedisError> {
+        let mut conn = self.get_connection().await?;
+        let id = Uuid::new_v4().to_string();
+        let fence: Option<u64> = redis::Script::new(ACQUIRE_LOCK_SCRIPT)
+            .key(key)
+            .key(format!("{}:fence", key))
+            .arg(&id)
+            .arg(ttl.as_millis() as u64)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(fence.map(|fence| LockToken { id, fence }))
+    }
+
+    /// Releases the lock if `token` still holds it. Returns false when it had expired,
+    /// so someone else may have held it since.
+    async fn release_lock(&self, key: &str, token: &LockToken) -> Result<bool, RedisError> {
+        let mut conn = self.get_connection().await?;
+        let released: i64 = redis::Script::new(RELEASE_LOCK_SCRIPT)
+            .key(key)
+            .arg(&token.id)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(released == 1)
+    }
+
+    /// Resets the lock's expiry to `ttl` from now if `token` still holds it.
+    async fn extend_lock(
+        &self,
+        key: &str,
+        token: &LockToken,
+        ttl: Duration,
+    ) -> Result<bool, RedisError> {
+        let mut conn = self.get_connection().await?;
+        let extended: i64 = redis::Script::new(EXTEND_LOCK_SCRIPT)
+            .key(key)
+            .arg(&token.id)
+            .arg(ttl.as_millis() as u64)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(extended == 1)
+    }
+
+    /// Adds one to the counter at `key` and returns the new count. The counter expires
+    /// `ttl` after its first increment, taken in the same atomic step.
+    async fn incr_with_ttl(&self, key: &str, ttl: Duration) -> Result<i64, RedisError> {
+        let mut conn = self.get_connection().await?;
+        redis::Script::new(INCR_WITH_TTL_SCRIPT)
+            .key(key)
+            .arg(ttl.as_millis() as u64)
+            .invoke_async(&mut conn)
+            .await
+    }
+
+    /// Sets `key` to `value`, expiring it after `ttl`.
+    async fn set_with_ttl(&self, key: &str, value: &str, ttl: Duration) -> Result<(), RedisError> {
+        let mut conn = self.get_connection().await?;
+        conn.pset_ex(key, value, ttl.as_millis() as u64).await
+    }
 }
 
 /// Concrete implementation of `RedisClientTrait` using a `redis::Client` (aliased as `NativeRedisClient`).
@@ -270,6 +379,27 @@ impl Default for CircuitBreakerConfig {
     }
 }
 
+impl CircuitBreakerConfig {
+    /// Reads `<PREFIX>_FAILURE_THRESHOLD` and `<PREFIX>_RESET_TIMEOUT_SECS`, keeping the
+    /// defaults for anything unset.
+    pub fn from_env_with_prefix(prefix: &str) -> Self {
+        let default = Self::default();
+        Self {
+            failure_threshold: std::env::var(format!("{}_FAILURE_THRESHOLD", prefix))
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|threshold: &u64| *threshold > 0)
+                .unwrap_or(default.failure_threshold),
+            reset_timeout: std::env::var(format!("{}_RESET_TIMEOUT_SECS", prefix))
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(default.reset_timeout),
+            ..default
+        }
+    }
+}
+
 #[derive(Debug)]
 enum CircuitState {
     Closed,
@@ -292,7 +422,9 @@ impl CircuitBreaker {
         }
     }
 
-    async fn allow_request(&self) -> bool {
+    /// Whether a call may go ahead. An open breaker lets calls through again once
+    /// `reset_timeout` has passed.
+    pub async fn allow_request(&self) -> bool {
         let mut state = self.state.write().await;
         match *state {
             CircuitState::Closed => true,
@@ -312,7 +444,7 @@ impl CircuitBreaker {
         }
     }
 
-    async fn record_success(&self) {
+    pub async fn record_success(&self) {
         let mut state = self.state.write().await;
         match *state {
             CircuitState::HalfOpen => {
@@ -323,7 +455,8 @@ impl CircuitBreaker {
         }
     }
 
-    async fn record_failure(&self) {
+    /// Opens the breaker once `failure_threshold` failures have been recorded.
+    pub async fn record_failure(&self) {
         let failures = self.failure_count.fetch_add(1, Ordering::SeqCst) + 1;
         if failures >= self.config.failure_threshold {
             let mut state = self.state.write().await;
@@ -645,6 +778,314 @@ impl RedisClientTrait for LoadSheddingRedisClient {
     }
 }
 
+/// Sentinel deployment the Redis master is discovered from.
+#[derive(Debug, Clone)]
+pub struct SentinelConfig {
+    /// Sentinel addresses, e.g. `redis://sentinel-1:26379`.
+    pub sentinels: Vec<String>,
+    /// Name the master is monitored under.
+    pub master_name: String,
+    /// How often the sentinels are asked whether the master has moved.
+    pub check_interval: Duration,
+}
+
+impl SentinelConfig {
+    /// Reads `REDIS_SENTINELS` (comma separated), `REDIS_SENTINEL_MASTER` and
+    /// `REDIS_SENTINEL_CHECK_INTERVAL_MS`. Returns `None` when no sentinels are set.
+    pub fn from_env() -> Option<Self> {
+        let sentinels: Vec<String> = std::env::var("REDIS_SENTINELS")
+            .ok()?
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        if sentinels.is_empty() {
+            return None;
+        }
+        Some(Self {
+            sentinels,
+            master_name: std::env::var("REDIS_SENTINEL_MASTER")
+                .unwrap_or_else(|_| "mymaster".to_string()),
+            check_interval: Duration::from_millis(
+                std::env::var("REDIS_SENTINEL_CHECK_INTERVAL_MS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(1000),
+            ),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentinelStatus {
+    pub master_name: String,
+    /// Address of the master in use, `None` while none could be discovered.
+    pub current_master: Option<String>,
+    /// How many times the client has switched to a new master.
+    pub failovers: u64,
+}
+
+/// The master in use, with the connection every command shares until it fails.
+#[derive(Clone)]
+struct MasterNode {
+    /// Tells nodes apart when the master is reconnected at the same address.
+    id: u64,
+    address: String,
+    conn: MultiplexedConnection,
+}
+
+/// `RedisClientTrait` implementation that follows the master a Sentinel deployment
+/// reports. The master is re-discovered when it can't be reached or refuses writes,
+/// and a background check picks up failovers the client hasn't run into yet.
+#[derive(Clone)]
+pub struct SentinelRedisClient {
+    state: Arc<SentinelState>,
+}
+
+struct SentinelState {
+    config: SentinelConfig,
+    pool_config: RedisPoolConfig,
+    master: RwLock<Option<MasterNode>>,
+    // Held while the sentinels are queried, so concurrent failures discover once
+    discovery: Mutex<()>,
+    next_node_id: AtomicU64,
+    failovers: AtomicU64,
+}
+
+impl SentinelRedisClient {
+    /// Discovers the current master and starts watching for failovers. Fails when no
+    /// sentinel reports a reachable master.
+    pub async fn connect(
+        config: SentinelConfig,
+        pool_config: Option<RedisPoolConfig>,
+    ) -> Result<Self, RedisError> {
+        let state = Arc::new(SentinelState {
+            config,
+            pool_config: pool_config.unwrap_or_default(),
+            master: RwLock::new(None),
+            discovery: Mutex::new(()),
+            next_node_id: AtomicU64::new(0),
+            failovers: AtomicU64::new(0),
+        });
+        state.refresh_master(None).await?;
+
+        // The check stops once the last clone of the client is dropped
+        let weak = Arc::downgrade(&state);
+        let check_interval = state.config.check_interval;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(check_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let Some(state) = weak.upgrade() else {
+                    break;
+                };
+                if let Err(e) = state.refresh_master(None).await {
+                    warn!("Redis sentinel check failed: {}", e);
+                }
+            }
+        });
+
+        Ok(Self { state })
+    }
+
+    pub async fn status(&self) -> SentinelStatus {
+        SentinelStatus {
+            master_name: self.state.config.master_name.clone(),
+            current_master: self
+                .state
+                .master
+                .read()
+                .await
+                .as_ref()
+                .map(|m| m.address.clone()),
+            failovers: self.state.failovers.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl SentinelState {
+    async fn open(&self, client: &NativeRedisClient) -> Result<MultiplexedConnection, RedisError> {
+        let timeout = self.pool_config.connection_timeout;
+        client
+            .get_multiplexed_async_connection_with_timeouts(timeout, timeout)
+            .await
+    }
+
+    /// Asks each sentinel in turn for the master and returns the first address that
+    /// confirms it is a master, with a connection to it. A sentinel can briefly report a
+    /// demoted master while a failover is in progress.
+    async fn discover_master(&self) -> Result<(String, MultiplexedConnection), RedisError> {
+        let mut last_error = None;
+        for sentinel in &self.config.sentinels {
+            match self.query_sentinel(sentinel).await {
+                Ok(master) => return Ok(master),
+                Err(e) => {
+                    warn!(
+                        "Sentinel {} did not report a usable master: {}",
+                        sentinel, e
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            RedisError::from((RedisErrorKind::ClientError, "No sentinels configured"))
+        }))
+    }
+
+    async fn query_sentinel(
+        &self,
+        sentinel: &str,
+    ) -> Result<(String, MultiplexedConnection), RedisError> {
+        let mut conn = self.open(&NativeRedisClient::open(sentinel)?).await?;
+        let (host, port): (String, u16) = redis::cmd("SENTINEL")
+            .arg("get-master-addr-by-name")
+            .arg(&self.config.master_name)
+            .query_async::<_, Option<(String, u16)>>(&mut conn)
+            .await?
+            .ok_or_else(|| {
+                RedisError::from((RedisErrorKind::ResponseError, "Unknown sentinel master"))
+            })?;
+
+        let address = format!("redis://{}:{}/", host, port);
+        let mut master = self
+            .open(&NativeRedisClient::open(address.as_str())?)
+            .await?;
+        let role: Vec<RedisValue> = redis::cmd("ROLE").query_async(&mut master).await?;
+        match role.first().map(String::from_redis_value) {
+            Some(Ok(role)) if role == "master" => Ok((address, master)),
+            _ => Err(RedisError::from((
+                RedisErrorKind::ReadOnly,
+                "Reported master is not a master",
+            ))),
+        }
+    }
+
+    /// Re-discovers the master. A `failed` node is replaced even when the sentinels still
+    /// report its address, since its connection may be broken; callers that raced on the
+    /// same failure find it already replaced and reuse the new one. Without `failed` the
+    /// node is only replaced once the master has moved. The sentinels are queried without
+    /// holding the master lock, so commands keep using the current master meanwhile.
+    async fn refresh_master(&self, failed: Option<u64>) -> Result<MasterNode, RedisError> {
+        let _discovery = self.discovery.lock().await;
+        let current = self.master.read().await.clone();
+        if let (Some(current), Some(failed)) = (&current, failed) {
+            if current.id != failed {
+                return Ok(current.clone());
+            }
+        }
+
+        let (address, conn) = self.discover_master().await?;
+        match &current {
+            Some(current) if failed.is_none() && current.address == address => {
+                return Ok(current.clone());
+            }
+            Some(previous) if previous.address == address => {
+                info!(
+                    "Reconnected to Redis master {} at {}",
+                    self.config.master_name, address
+                );
+            }
+            Some(previous) => {
+                self.failovers.fetch_add(1, Ordering::Relaxed);
+                warn!(
+                    "Redis master {} moved from {} to {}",
+                    self.config.master_name, previous.address, address
+                );
+            }
+            None => info!(
+                "Using Redis master {} at {}",
+                self.config.master_name, address
+            ),
+        }
+        let node = MasterNode {
+            id: self.next_node_id.fetch_add(1, Ordering::Relaxed),
+            address,
+            conn,
+        };
+        *self.master.write().await = Some(node.clone());
+        Ok(node)
+    }
+
+    async fn current_master(&self) -> Result<MasterNode, RedisError> {
+        if let Some(master) = self.master.read().await.as_ref() {
+            return Ok(master.clone());
+        }
+        self.refresh_master(None).await
+    }
+
+    fn is_failover_error(error: &RedisError) -> bool {
+        error.is_io_error()
+            || error.is_connection_refusal()
+            || error.is_connection_dropped()
+            || error.is_timeout()
+            || error.kind() == RedisErrorKind::ReadOnly
+    }
+
+    /// Runs `op` on the master's shared connection, retrying once on a newly discovered
+    /// master when the failure looks like a failover.
+    async fn with_master<T, F, Fut>(&self, op: F) -> Result<T, RedisError>
+    where
+        F: Fn(MultiplexedConnection) -> Fut,
+        Fut: std::future::Future<Output = Result<T, RedisError>>,
+    {
+        let master = self.current_master().await?;
+        match op(master.conn.clone()).await {
+            Err(e) if Self::is_failover_error(&e) => {
+                warn!(
+                    "Redis master {} failed, re-discovering: {}",
+                    master.address, e
+                );
+                let master = self.refresh_master(Some(master.id)).await?;
+                op(master.conn).await
+            }
+            result => result,
+        }
+    }
+}
+
+#[async_trait]
+impl RedisClientTrait for SentinelRedisClient {
+    async fn get_connection(&self) -> Result<MultiplexedConnection, RedisError> {
+        self.state.with_master(|conn| async move { Ok(conn) }).await
+    }
+
+    fn clone_client(&self) -> Arc<dyn RedisClientTrait> {
+        Arc::new(self.clone())
+    }
+
+    async fn get_pooled_connection(
+        &self,
+    ) -> Result<Box<dyn RedisConnectionCommands + Send>, RedisError> {
+        let conn = self.get_connection().await?;
+        Ok(Box::new(RedisConnection::new(conn)))
+    }
+
+    fn get_pool_config(&self) -> RedisPoolConfig {
+        self.state.pool_config.clone()
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, RedisError> {
+        self.state
+            .with_master(|mut conn| async move { conn.get(key).await })
+            .await
+    }
+
+    async fn set(&self, key: &str, value: &str) -> Result<(), RedisError> {
+        self.state
+            .with_master(|mut conn| async move { conn.set(key, value).await })
+            .await
+    }
+
+    async fn del(&self, key: &str) -> Result<(), RedisError> {
+        self.state
+            .with_master(|mut conn| async move { conn.del(key).await })
+            .await
+    }
+}
+
 pub struct RedisClient {
     conn: Arc<Mutex<MultiplexedConnection>>,
 }
@@ -1,20 +1,36 @@
-use crate::domain::{Account, AccountError, AccountEvent};
+use crate::domain::{Account, AccountCommand, AccountError, AccountEvent, AccountType};
+use crate::error::BankingError;
 use crate::infrastructure::cache_service::{CacheConfig, CacheService, EvictionPolicy};
-use crate::infrastructure::event_store::{EventPriority, EventStore, EventStoreTrait};
+use crate::infrastructure::config::{
+    BatchFlushConfig, BatchOverflowPolicy, MetricsReporterConfig, PoisonEventPolicy,
+    StaleReadPolicy,
+};
+use crate::infrastructure::event_store::{
+    AggregateStats, EventMetadata, EventStore, EventStoreError, EventStoreTrait, StoredEvent,
+};
 use crate::infrastructure::kafka_abstraction::KafkaConfig;
 use crate::infrastructure::kafka_event_processor::KafkaEventProcessor;
+use crate::infrastructure::outbox::{OutboxPublisher, PublishOutcome};
+use crate::infrastructure::produce_queue::{ProduceQueue, QueuedBatch};
 use crate::infrastructure::projections::ProjectionStore;
-use crate::infrastructure::redis_abstraction::RealRedisClient;
+use crate::infrastructure::redis_abstraction::CircuitBreaker;
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
+use serde::Serialize;
 use sqlx::postgres::PgPoolOptions;
 use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex, RwLock};
-use std::time::{Duration, Instant};
+use std::time::Instant;
+use tokio::sync::{oneshot, Notify};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+/// Times `apply_command` runs a command that keeps losing version races.
+const APPLY_COMMAND_MAX_ATTEMPTS: u32 = 5;
+
 #[derive(Debug, thiserror::Error)]
 pub enum RepositoryError {
     #[error("Account not found: {0}")]
@@ -27,32 +43,112 @@ pub enum RepositoryError {
 
 #[async_trait]
 pub trait AccountRepositoryTrait: Send + Sync {
-    async fn create_account(&self, owner_name: String, initial_balance: Decimal)
-        -> Result<Account>;
-    async fn get_account(&self, account_id: Uuid) -> Result<Option<Account>>;
-    async fn deposit_money(&self, account_id: Uuid, amount: Decimal) -> Result<Account>;
-    async fn withdraw_money(&self, account_id: Uuid, amount: Decimal) -> Result<Account>;
-    async fn save_immediate(&self, account: &Account, events: Vec<AccountEvent>) -> Result<()>;
-    async fn save(&self, account: &Account, events: Vec<AccountEvent>) -> Result<()>;
+    async fn create_account(
+        &self,
+        owner_name: String,
+        initial_balance: Decimal,
+    ) -> Result<Account, BankingError>;
+    async fn get_account(&self, account_id: Uuid) -> Result<Option<Account>, BankingError>;
+    async fn deposit_money(
+        &self,
+        account_id: Uuid,
+        amount: Decimal,
+    ) -> Result<Account, BankingError>;
+    async fn withdraw_money(
+        &self,
+        account_id: Uuid,
+        amount: Decimal,
+    ) -> Result<Account, BankingError>;
+    /// Changes the account's owner name, recording the old and new names in an
+    /// `OwnerNameChanged` event.
+    async fn rename_owner(
+        &self,
+        account_id: Uuid,
+        owner_name: String,
+    ) -> Result<Account, BankingError>;
+    /// Tags the account with `key`, replacing any value the key already has.
+    async fn set_metadata(
+        &self,
+        account_id: Uuid,
+        key: String,
+        value: String,
+    ) -> Result<Account, BankingError>;
+    /// Removes the account's `key` tag; removing a tag that isn't set records nothing.
+    async fn remove_metadata(&self, account_id: Uuid, key: String)
+        -> Result<Account, BankingError>;
+    async fn save_immediate(
+        &self,
+        account: &Account,
+        events: Vec<AccountEvent>,
+    ) -> Result<(), BankingError>;
+    async fn save(&self, account: &Account, events: Vec<AccountEvent>) -> Result<(), BankingError>;
+    /// Saves like `save`, storing `metadata` with the events, e.g. the command that caused
+    /// them and who issued it.
+    async fn save_with_metadata(
+        &self,
+        account: &Account,
+        events: Vec<AccountEvent>,
+        metadata: EventMetadata,
+    ) -> Result<(), BankingError>;
+    async fn apply_command(
+        &self,
+        account_id: Uuid,
+        command: &AccountCommand,
+        expected_version: Option<i64>,
+    ) -> Result<Account, BankingError>;
     async fn get_by_id(&self, id: Uuid) -> Result<Option<Account>, AccountError>;
+    /// Like `get_by_id`, but while the event store's circuit breaker is open the stale read
+    /// policy may allow the account as last loaded to be returned instead.
+    async fn get_by_id_or_last_known(&self, id: Uuid) -> Result<Option<AccountRead>, AccountError> {
+        Ok(self.get_by_id(id).await?.map(AccountRead::current))
+    }
+    /// All stored events for an account, oldest first.
+    async fn get_events(&self, id: Uuid) -> Result<Vec<StoredEvent>, AccountError>;
+    /// Up to `limit` of the account's events after `after_version`, oldest first.
+    async fn get_events_page(
+        &self,
+        id: Uuid,
+        after_version: i64,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>, AccountError>;
+    /// The account's events caused by `causation_id`, oldest first.
+    async fn get_events_caused_by(
+        &self,
+        id: Uuid,
+        causation_id: Uuid,
+    ) -> Result<Vec<StoredEvent>, AccountError>;
     async fn save_batched(
         &self,
         account_id: Uuid,
         expected_version: i64,
         events: Vec<AccountEvent>,
-    ) -> Result<()>;
-    async fn flush_all(&self) -> Result<()>;
+    ) -> Result<(), BankingError>;
+    async fn flush_all(&self) -> Result<(), BankingError>;
     fn start_batch_flush_task(&self);
+    async fn create_snapshot(&self, account_id: Uuid) -> Result<Option<i64>, BankingError>;
+    async fn create_snapshots_above_threshold(
+        &self,
+        min_event_count: i64,
+    ) -> Result<Vec<(Uuid, i64)>, BankingError>;
+    async fn aggregate_stats(&self, account_id: Uuid)
+        -> Result<Option<AggregateStats>, BankingError>;
+    async fn top_aggregates_by_event_count(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<AggregateStats>, BankingError>;
+    async fn sample_account_ids(&self, limit: i64) -> Result<Vec<Uuid>, BankingError>;
 }
 
 #[derive(Debug, Clone)]
 struct CacheEntry<T> {
     data: T,
     created_at: Instant,
-    last_accessed: Instant,
-    version: i64,
+    cached_at: DateTime<Utc>,
 }
 
+/// Last-known accounts are only pruned of expired entries once there are this many.
+const LAST_KNOWN_PRUNE_THRESHOLD: usize = 10_000;
+
 #[derive(Debug, Default)]
 struct RepositoryMetrics {
     cache_hits: std::sync::atomic::AtomicU64,
@@ -60,90 +156,733 @@ struct RepositoryMetrics {
     batch_flushes: std::sync::atomic::AtomicU64,
     events_processed: std::sync::atomic::AtomicU64,
     errors: std::sync::atomic::AtomicU64,
+    poison_events: std::sync::atomic::AtomicU64,
+    reports: std::sync::atomic::AtomicU64,
+    flush_interval_ms: std::sync::atomic::AtomicU64,
+    pending_events: std::sync::atomic::AtomicU64,
+    pending_bytes: std::sync::atomic::AtomicU64,
+    buffer_overflows: std::sync::atomic::AtomicU64,
+}
+
+impl RepositoryMetrics {
+    fn snapshot(&self) -> RepositoryMetricsSnapshot {
+        use std::sync::atomic::Ordering::Relaxed;
+        RepositoryMetricsSnapshot {
+            cache_hits: self.cache_hits.load(Relaxed),
+            cache_misses: self.cache_misses.load(Relaxed),
+            batch_flushes: self.batch_flushes.load(Relaxed),
+            events_processed: self.events_processed.load(Relaxed),
+            errors: self.errors.load(Relaxed),
+            poison_events: self.poison_events.load(Relaxed),
+            reports: self.reports.load(Relaxed),
+            flush_interval_ms: self.flush_interval_ms.load(Relaxed),
+            pending_events: self.pending_events.load(Relaxed),
+            pending_bytes: self.pending_bytes.load(Relaxed),
+            buffer_overflows: self.buffer_overflows.load(Relaxed),
+        }
+    }
+}
+
+/// Point-in-time copy of the repository counters.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RepositoryMetricsSnapshot {
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub batch_flushes: u64,
+    pub events_processed: u64,
+    pub errors: u64,
+    pub poison_events: u64,
+    /// Number of periodic metrics reports emitted so far.
+    pub reports: u64,
+    /// Current wait between batch flushes; 0 until the flush task starts.
+    pub flush_interval_ms: u64,
+    /// Events waiting in the batch buffer.
+    pub pending_events: u64,
+    /// Serialized size of the buffered events; only tracked while a byte limit is set.
+    pub pending_bytes: u64,
+    /// `save_batched` calls that found the buffer full.
+    pub buffer_overflows: u64,
+}
+
+impl RepositoryMetricsSnapshot {
+    pub fn cache_hit_rate(&self) -> f64 {
+        let lookups = self.cache_hits + self.cache_misses;
+        if lookups > 0 {
+            (self.cache_hits as f64 / lookups as f64) * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// An aggregate rebuilt from its events, along with any events replay had to skip.
+#[derive(Debug, Clone)]
+pub struct LoadedAccount {
+    pub account: Account,
+    pub skipped_events: Vec<SkippedEvent>,
+}
+
+/// An account as read by `get_by_id_or_last_known`.
+#[derive(Debug, Clone)]
+pub struct AccountRead {
+    pub account: Account,
+    /// Whether `account` is the last-known state served while the event store was
+    /// unavailable, rather than its current state.
+    pub stale: bool,
+    /// When `account` was read from the event store.
+    pub as_of: DateTime<Utc>,
+}
+
+impl AccountRead {
+    pub fn current(account: Account) -> Self {
+        Self {
+            account,
+            stale: false,
+            as_of: Utc::now(),
+        }
+    }
+}
+
+/// A stored event that couldn't be deserialized during replay.
+#[derive(Debug, Clone)]
+pub struct SkippedEvent {
+    pub event_id: Uuid,
+    pub version: i64,
+    pub error: String,
+}
+
+/// A `save_batched` call waiting for the next flush.
+struct PendingWrite {
+    expected_version: i64,
+    events: Vec<AccountEvent>,
+    done: oneshot::Sender<Result<(), BankingError>>,
+}
+
+#[derive(Default)]
+struct PendingEvents {
+    writes: HashMap<Uuid, Vec<PendingWrite>>,
+    event_count: usize,
+    bytes: usize,
 }
 
 #[derive(Clone)]
 pub struct AccountRepository {
     event_store: Arc<dyn EventStoreTrait + 'static>,
-    pending_events: Arc<Mutex<HashMap<Uuid, Vec<AccountEvent>>>>,
-    account_cache: Arc<RwLock<HashMap<Uuid, CacheEntry<Account>>>>,
-    flush_interval: Duration,
+    pending_events: Arc<Mutex<PendingEvents>>,
+    /// Accounts as last loaded from the event store, kept for the stale read policy.
+    last_known: Arc<RwLock<HashMap<Uuid, CacheEntry<Account>>>>,
+    flush_config: BatchFlushConfig,
+    flush_requested: Arc<Notify>,
+    flush_completed: Arc<Notify>,
+    flush_task_started: Arc<AtomicBool>,
     metrics: Arc<RepositoryMetrics>,
+    event_publisher: Option<Arc<OutboxPublisher>>,
+    produce_queue: Option<Arc<ProduceQueue>>,
+    poison_event_policy: PoisonEventPolicy,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    stale_read_policy: StaleReadPolicy,
 }
 
 impl AccountRepository {
     pub fn new(event_store: Arc<dyn EventStoreTrait + 'static>) -> Self {
+        Self::new_with_reporter_config(event_store, MetricsReporterConfig::from_env())
+    }
+
+    pub fn new_with_reporter_config(
+        event_store: Arc<dyn EventStoreTrait + 'static>,
+        reporter_config: MetricsReporterConfig,
+    ) -> Self {
         let repo = Self {
             event_store,
-            pending_events: Arc::new(Mutex::new(HashMap::new())),
-            account_cache: Arc::new(RwLock::new(HashMap::new())),
-            flush_interval: Duration::from_millis(50),
+            pending_events: Arc::new(Mutex::new(PendingEvents::default())),
+            last_known: Arc::new(RwLock::new(HashMap::new())),
+            flush_config: BatchFlushConfig::default(),
+            flush_requested: Arc::new(Notify::new()),
+            flush_completed: Arc::new(Notify::new()),
+            flush_task_started: Arc::new(AtomicBool::new(false)),
             metrics: Arc::new(RepositoryMetrics::default()),
+            event_publisher: None,
+            produce_queue: None,
+            poison_event_policy: PoisonEventPolicy::default(),
+            circuit_breaker: None,
+            stale_read_policy: StaleReadPolicy::default(),
         };
 
-        repo.start_batch_flush_task();
-        repo.start_metrics_reporter();
+        repo.start_metrics_reporter(reporter_config);
 
         repo
     }
 
-    pub async fn save(&self, account: &Account, events: Vec<AccountEvent>) -> Result<()> {
-        Ok(self
-            .event_store
-            .save_events(account.id, events, account.version)
-            .await?)
+    /// Publishes saved events to Kafka, queueing them in the outbox while Kafka is down.
+    pub fn with_event_publisher(mut self, publisher: Arc<OutboxPublisher>) -> Self {
+        self.event_publisher = Some(publisher);
+        self
+    }
+
+    /// Hands saved events to a bounded produce queue instead of publishing them inline,
+    /// so a slow broker doesn't hold up writes. Writes are refused while the queue is full.
+    pub fn with_produce_queue(mut self, queue: Arc<ProduceQueue>) -> Self {
+        self.produce_queue = Some(queue);
+        self
+    }
+
+    /// Sets how replay treats stored events that fail to deserialize.
+    pub fn with_poison_event_policy(mut self, policy: PoisonEventPolicy) -> Self {
+        self.poison_event_policy = policy;
+        self
+    }
+
+    /// Guards account loads with `breaker`: event store failures count towards opening it,
+    /// and loads fail as unavailable without touching the store while it's open.
+    pub fn with_circuit_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Sets what `get_by_id_or_last_known` does while the circuit breaker is open.
+    pub fn with_stale_read_policy(mut self, policy: StaleReadPolicy) -> Self {
+        self.stale_read_policy = policy;
+        self
+    }
+
+    /// Sets the bounds the batch flush interval adapts within, and the batch buffer's limits.
+    pub fn with_batch_flush_config(mut self, config: BatchFlushConfig) -> Self {
+        self.flush_config = config;
+        self
+    }
+
+    /// Number of events skipped during replay because they failed to deserialize.
+    pub fn poison_events_skipped(&self) -> u64 {
+        self.metrics
+            .poison_events
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn metrics_snapshot(&self) -> RepositoryMetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    pub async fn save(
+        &self,
+        account: &Account,
+        events: Vec<AccountEvent>,
+    ) -> Result<(), BankingError> {
+        self.save_and_publish(account.id, events, account.version)
+            .await
+    }
+
+    pub async fn save_with_metadata(
+        &self,
+        account: &Account,
+        events: Vec<AccountEvent>,
+        metadata: EventMetadata,
+    ) -> Result<(), BankingError> {
+        self.save_and_publish_with_metadata(account.id, events, account.version, metadata)
+            .await
+    }
+
+    /// Runs `command` against the account's current state: loads it, lets the domain turn
+    /// the command into events, saves them and returns the updated account. With
+    /// `expected_version` the command only applies to that version and a mismatch is
+    /// returned as a conflict. Without it, a concurrent write makes the command run again
+    /// on the fresh state, up to `APPLY_COMMAND_MAX_ATTEMPTS` times.
+    pub async fn apply_command(
+        &self,
+        account_id: Uuid,
+        command: &AccountCommand,
+        expected_version: Option<i64>,
+    ) -> Result<Account, BankingError> {
+        let mut attempt = 1;
+        loop {
+            let mut account = match (self.get_by_id(account_id).await?, command) {
+                (Some(account), AccountCommand::CreateAccount { .. }) => {
+                    return Err(EventStoreError::AggregateAlreadyExists {
+                        aggregate_id: account_id,
+                        version: account.version,
+                    }
+                    .into());
+                }
+                (Some(account), _) => account,
+                (None, AccountCommand::CreateAccount { .. }) => Account {
+                    id: account_id,
+                    ..Account::default()
+                },
+                (None, _) => return Err(AccountError::NotFound.into()),
+            };
+            if let Some(expected) = expected_version {
+                if account.version != expected {
+                    return Err(EventStoreError::version_conflict(
+                        account_id,
+                        expected,
+                        Some(account.version),
+                    )
+                    .into());
+                }
+            }
+
+            let events = account.handle_command(command)?;
+            if events.is_empty() {
+                return Ok(account);
+            }
+            match self
+                .save_and_publish(account_id, events.clone(), account.version)
+                .await
+            {
+                Ok(()) => {
+                    for event in &events {
+                        account.apply_event(event);
+                    }
+                    return Ok(account);
+                }
+                Err(BankingError::EventStore(
+                    EventStoreError::OptimisticConcurrencyConflict { .. },
+                )) if expected_version.is_none() && attempt < APPLY_COMMAND_MAX_ATTEMPTS => {
+                    debug!(
+                        "Version conflict applying command to account {}, retrying (attempt {})",
+                        account_id, attempt
+                    );
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn save_and_publish(
+        &self,
+        account_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+    ) -> Result<(), BankingError> {
+        self.save_and_publish_with_metadata(
+            account_id,
+            events,
+            expected_version,
+            EventMetadata::correlated(),
+        )
+        .await
+    }
+
+    async fn save_and_publish_with_metadata(
+        &self,
+        account_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+        metadata: EventMetadata,
+    ) -> Result<(), BankingError> {
+        // Take a place in the queue before saving, so a full queue refuses the write
+        // instead of leaving committed events unpublished
+        let permit = match &self.produce_queue {
+            Some(queue) => Some(queue.try_reserve_for(account_id, expected_version + 1)?),
+            None => None,
+        };
+        if permit.is_none() && self.event_publisher.is_none() {
+            return Ok(self
+                .event_store
+                .save_events_with_metadata(account_id, events, expected_version, metadata)
+                .await?);
+        }
+
+        let version = expected_version + events.len() as i64;
+        self.event_store
+            .save_events_with_metadata(account_id, events.clone(), expected_version, metadata)
+            .await?;
+
+        // The events are committed at this point; publishing problems must not fail the write
+        let global_position = self.saved_position(account_id, version).await;
+        if let Some(permit) = permit {
+            permit.send(QueuedBatch {
+                aggregate_id: account_id,
+                events,
+                version,
+                global_position,
+            });
+            return Ok(());
+        }
+        let Some(publisher) = &self.event_publisher else {
+            return Ok(());
+        };
+        match publisher
+            .publish(account_id, events, version, global_position)
+            .await
+        {
+            Ok(PublishOutcome::Queued) => {
+                debug!("Events for account {} queued in the outbox", account_id)
+            }
+            Ok(_) => {}
+            Err(e) => {
+                self.metrics
+                    .errors
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                error!(
+                    "Failed to publish events for account {} at version {}: {}",
+                    account_id, version, e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn saved_position(&self, account_id: Uuid, version: i64) -> Option<i64> {
+        match self.event_store.get_events(account_id, Some(version - 1)).await {
+            Ok(saved) => saved.last().map(|event| event.global_position),
+            Err(e) => {
+                warn!(
+                    "Publishing events for account {} without their position: {}",
+                    account_id, e
+                );
+                None
+            }
+        }
     }
 
     pub async fn get_by_id(&self, id: Uuid) -> Result<Option<Account>, AccountError> {
-        let stored_events = self.event_store.get_events(id, None).await.map_err(|e| {
-            error!("Failed to get events for account {}: {}", id, e);
-            AccountError::InfrastructureError(format!("Event store error: {}", e))
+        Ok(self.load_account(id).await?.map(|loaded| loaded.account))
+    }
+
+    /// Returns the account, or while the circuit breaker is open and the stale read policy
+    /// allows it, the account as last loaded if that isn't too old.
+    pub async fn get_by_id_or_last_known(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<AccountRead>, AccountError> {
+        let error = match self.get_by_id(id).await {
+            Ok(account) => return Ok(account.map(AccountRead::current)),
+            Err(error @ AccountError::Unavailable(_)) => error,
+            Err(error) => return Err(error),
+        };
+        let StaleReadPolicy::ServeLastKnown { max_age } = self.stale_read_policy else {
+            return Err(error);
+        };
+        let last_known = self.last_known.read().unwrap().get(&id).cloned();
+        match last_known {
+            Some(entry) if entry.created_at.elapsed() <= max_age => {
+                warn!(
+                    "Serving account {} as of {} while the event store is unavailable",
+                    id, entry.cached_at
+                );
+                Ok(Some(AccountRead {
+                    account: entry.data,
+                    stale: true,
+                    as_of: entry.cached_at,
+                }))
+            }
+            _ => Err(error),
+        }
+    }
+
+    /// Rebuilds an account from its snapshot and events, applying the poison event policy
+    /// to events that fail to deserialize. Fails as unavailable while the circuit breaker
+    /// is open.
+    pub async fn load_account(&self, id: Uuid) -> Result<Option<LoadedAccount>, AccountError> {
+        let Some(breaker) = &self.circuit_breaker else {
+            return self.replay_account(id).await;
+        };
+        if !breaker.allow_request().await {
+            return Err(AccountError::Unavailable(
+                "Event store circuit breaker is open".to_string(),
+            ));
+        }
+        let loaded = self.replay_account(id).await;
+        match &loaded {
+            Err(AccountError::InfrastructureError(_)) => breaker.record_failure().await,
+            Ok(account) => {
+                breaker.record_success().await;
+                if let Some(loaded) = account {
+                    self.remember(&loaded.account);
+                }
+            }
+            Err(_) => breaker.record_success().await,
+        }
+        loaded
+    }
+
+    /// Keeps `account` as the last-known state to serve under the stale read policy.
+    fn remember(&self, account: &Account) {
+        let StaleReadPolicy::ServeLastKnown { max_age } = self.stale_read_policy else {
+            return;
+        };
+        let mut last_known = self.last_known.write().unwrap();
+        if last_known.len() >= LAST_KNOWN_PRUNE_THRESHOLD {
+            last_known.retain(|_, entry| entry.created_at.elapsed() <= max_age);
+        }
+        last_known.insert(
+            account.id,
+            CacheEntry {
+                data: account.clone(),
+                created_at: Instant::now(),
+                cached_at: Utc::now(),
+            },
+        );
+    }
+
+    async fn replay_account(&self, id: Uuid) -> Result<Option<LoadedAccount>, AccountError> {
+        let snapshot = self.event_store.get_snapshot(id).await.map_err(|e| {
+            error!("Failed to get snapshot for account {}: {}", id, e);
+            event_store_error(e)
         })?;
-        if stored_events.is_empty() {
+        let from_version = snapshot.as_ref().map(|s| s.version);
+
+        let stored_events = self
+            .event_store
+            .get_events(id, from_version)
+            .await
+            .map_err(|e| {
+                error!("Failed to get events for account {}: {}", id, e);
+                event_store_error(e)
+            })?;
+        if stored_events.is_empty() && snapshot.is_none() {
             return Ok(None);
         }
-        let mut account = Account::default();
-        account.id = id;
+        let mut account = match snapshot {
+            Some(snapshot) => snapshot.account,
+            None => Account {
+                id,
+                ..Account::default()
+            },
+        };
+        let mut skipped_events = Vec::new();
+        let last_version = stored_events.last().map(|e| e.version);
         for event in stored_events {
-            let account_event: AccountEvent =
-                serde_json::from_value(event.event_data).map_err(|e| {
-                    AccountError::InfrastructureError(format!("Deserialization error: {}", e))
-                })?;
+            let account_event = match event.decode() {
+                Ok(account_event) => account_event,
+                Err(e) if self.poison_event_policy == PoisonEventPolicy::Fail => {
+                    error!(
+                        "Event {} (version {}) of account {} does not decode: {}",
+                        event.id, event.version, id, e
+                    );
+                    return Err(e.into());
+                }
+                Err(e) => {
+                    let error = e.to_string();
+                    self.handle_poison_event(&event, &error).await?;
+                    skipped_events.push(SkippedEvent {
+                        event_id: event.id,
+                        version: event.version,
+                        error,
+                    });
+                    continue;
+                }
+            };
 
             account.apply_event(&account_event);
         }
-        Ok(Some(account))
+
+        if !skipped_events.is_empty() {
+            // Skipped events still occupy their versions, so later saves must follow them
+            if let Some(version) = last_version {
+                account.version = version;
+            }
+            warn!(
+                "Loaded account {} with {} skipped poison event(s)",
+                id,
+                skipped_events.len()
+            );
+        }
+        Ok(Some(LoadedAccount {
+            account,
+            skipped_events,
+        }))
+    }
+
+    async fn handle_poison_event(
+        &self,
+        event: &StoredEvent,
+        error: &str,
+    ) -> Result<(), AccountError> {
+        if self.poison_event_policy == PoisonEventPolicy::Quarantine {
+            sqlx::query!(
+                r#"
+                INSERT INTO quarantined_events (event_id, aggregate_id, event_type, event_data, version, error)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (event_id) DO NOTHING
+                "#,
+                event.id,
+                event.aggregate_id,
+                event.event_type,
+                event.event_data,
+                event.version,
+                error
+            )
+            .execute(&self.event_store.get_pool())
+            .await
+            .map_err(|e| {
+                AccountError::InfrastructureError(format!(
+                    "Failed to quarantine event {}: {}",
+                    event.id, e
+                ))
+            })?;
+        }
+
+        self.metrics
+            .poison_events
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        warn!(
+            "Skipping poison event {} (version {}) of account {}: {}",
+            event.id, event.version, event.aggregate_id, error
+        );
+        Ok(())
+    }
+
+    /// Queues a write for the next flush and waits for its result.
+    async fn enqueue_batched(
+        &self,
+        account_id: Uuid,
+        expected_version: i64,
+        events: Vec<AccountEvent>,
+    ) -> Result<(), BankingError> {
+        self.start_batch_flush_task();
+        let size = self.serialized_size(&events);
+        let (done, result) = oneshot::channel();
+        let mut overflowed = false;
+        let queued = loop {
+            // Registered before looking at the buffer so a flush finishing in between
+            // still wakes us
+            let flushed = self.flush_completed.notified();
+            tokio::pin!(flushed);
+            flushed.as_mut().enable();
+            {
+                let mut pending = self.pending_events.lock().unwrap();
+                if !self.exceeds_buffer_limits(&pending, events.len(), size) {
+                    pending.event_count += events.len();
+                    pending.bytes += size;
+                    pending
+                        .writes
+                        .entry(account_id)
+                        .or_default()
+                        .push(PendingWrite {
+                            expected_version,
+                            events,
+                            done,
+                        });
+                    self.record_buffer_depth(&pending);
+                    break pending.event_count;
+                }
+            }
+
+            if !overflowed {
+                overflowed = true;
+                self.metrics
+                    .buffer_overflows
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+            match self.flush_config.overflow {
+                BatchOverflowPolicy::SaveImmediately => {
+                    debug!(
+                        "Batch buffer full, saving events for account {} immediately",
+                        account_id
+                    );
+                    return self
+                        .save_and_publish(account_id, events, expected_version)
+                        .await;
+                }
+                BatchOverflowPolicy::Backpressure => {
+                    self.flush_requested.notify_one();
+                    flushed.await;
+                }
+            }
+        };
+        if queued >= self.flush_config.high_watermark {
+            self.flush_requested.notify_one();
+        }
+        result.await.map_err(|_| {
+            anyhow::anyhow!("Batch flush dropped the write for account {}", account_id)
+        })?
+    }
+
+    /// Whether adding a write of `events` events and `bytes` bytes would take the buffer
+    /// past its limits. A write larger than the limits on its own still goes into an empty
+    /// buffer, so it can't wait forever.
+    fn exceeds_buffer_limits(&self, pending: &PendingEvents, events: usize, bytes: usize) -> bool {
+        let config = &self.flush_config;
+        pending.event_count > 0
+            && ((config.max_pending_events > 0
+                && pending.event_count + events > config.max_pending_events)
+                || (config.max_pending_bytes > 0
+                    && pending.bytes + bytes > config.max_pending_bytes))
+    }
+
+    /// Size of `events` as JSON, or 0 when no byte limit is configured.
+    fn serialized_size(&self, events: &[AccountEvent]) -> usize {
+        if self.flush_config.max_pending_bytes == 0 {
+            return 0;
+        }
+        events
+            .iter()
+            .map(|event| serde_json::to_vec(event).map_or(0, |bytes| bytes.len()))
+            .sum()
+    }
+
+    fn record_buffer_depth(&self, pending: &PendingEvents) {
+        use std::sync::atomic::Ordering::Relaxed;
+        self.metrics
+            .pending_events
+            .store(pending.event_count as u64, Relaxed);
+        self.metrics.pending_bytes.store(pending.bytes as u64, Relaxed);
+    }
+
+    /// Saves every queued write, returning how many events were flushed. Accounts are
+    /// written concurrently; an account's writes go in the order they were queued.
+    /// Writes held back by a full buffer are woken once the flush finishes.
+    async fn flush_pending(&self) -> usize {
+        let pending = {
+            let mut pending = self.pending_events.lock().unwrap();
+            let taken = std::mem::take(&mut *pending);
+            self.record_buffer_depth(&pending);
+            taken
+        };
+        if pending.writes.is_empty() {
+            self.flush_completed.notify_waiters();
+            return 0;
+        }
+
+        futures::future::join_all(pending.writes.into_iter().map(
+            |(account_id, writes)| async move {
+                for write in writes {
+                    let result = self
+                        .save_and_publish(account_id, write.events, write.expected_version)
+                        .await;
+                    if result.is_err() {
+                        self.metrics
+                            .errors
+                            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    }
+                    let _ = write.done.send(result);
+                }
+            },
+        ))
+        .await;
+
+        self.metrics
+            .batch_flushes
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.metrics.events_processed.fetch_add(
+            pending.event_count as u64,
+            std::sync::atomic::Ordering::Relaxed,
+        );
+        self.flush_completed.notify_waiters();
+        pending.event_count
     }
 
-    fn start_metrics_reporter(&self) {
+    fn start_metrics_reporter(&self, config: MetricsReporterConfig) {
         let metrics = Arc::clone(&self.metrics);
+        let start = tokio::time::Instant::now() + config.initial_delay();
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            let mut interval = tokio::time::interval_at(start, config.interval);
             loop {
                 interval.tick().await;
-                let hits = metrics
-                    .cache_hits
-                    .load(std::sync::atomic::Ordering::Relaxed);
-                let misses = metrics
-                    .cache_misses
-                    .load(std::sync::atomic::Ordering::Relaxed);
-                let flushes = metrics
-                    .batch_flushes
-                    .load(std::sync::atomic::Ordering::Relaxed);
-                let processed = metrics
-                    .events_processed
-                    .load(std::sync::atomic::Ordering::Relaxed);
-                let errors = metrics.errors.load(std::sync::atomic::Ordering::Relaxed);
-
-                let hit_rate = if hits + misses > 0 {
-                    (hits as f64 / (hits + misses) as f64) * 100.0
-                } else {
-                    0.0
-                };
-
+                metrics
+                    .reports
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let snapshot = metrics.snapshot();
                 info!(
-                    "Repository Metrics - Cache Hit Rate: {:.1}%, Batch Flushes: {}, Events Processed: {}, Errors: {}",
-                    hit_rate, flushes, processed, errors
+                    "Repository Metrics - Cache Hit Rate: {:.1}%, Batch Flushes: {}, Events Processed: {}, Errors: {}, Poison Events: {}, Pending Events: {}",
+                    snapshot.cache_hit_rate(),
+                    snapshot.batch_flushes,
+                    snapshot.events_processed,
+                    snapshot.errors,
+                    snapshot.poison_events,
+                    snapshot.pending_events
                 );
             }
         });
@@ -156,60 +895,252 @@ impl AccountRepositoryTrait for AccountRepository {
         &self,
         owner_name: String,
         initial_balance: Decimal,
-    ) -> Result<Account> {
-        // Implementation needed
-        unimplemented!()
+    ) -> Result<Account, BankingError> {
+        let account_id = Uuid::new_v4();
+        let account_type = AccountType::default();
+        self.apply_command(
+            account_id,
+            &AccountCommand::CreateAccount {
+                account_id,
+                owner_name,
+                initial_balance,
+                owner_user_id: None,
+                account_type,
+                rules: account_type.default_rules(),
+            },
+            None,
+        )
+        .await
     }
 
-    async fn get_account(&self, account_id: Uuid) -> Result<Option<Account>> {
-        // Implementation needed
-        unimplemented!()
+    async fn get_account(&self, account_id: Uuid) -> Result<Option<Account>, BankingError> {
+        Ok(self.get_by_id(account_id).await?)
     }
 
-    async fn deposit_money(&self, account_id: Uuid, amount: Decimal) -> Result<Account> {
-        // Implementation needed
-        unimplemented!()
+    async fn deposit_money(
+        &self,
+        account_id: Uuid,
+        amount: Decimal,
+    ) -> Result<Account, BankingError> {
+        self.apply_command(
+            account_id,
+            &AccountCommand::DepositMoney { account_id, amount },
+            None,
+        )
+        .await
     }
 
-    async fn withdraw_money(&self, account_id: Uuid, amount: Decimal) -> Result<Account> {
-        // Implementation needed
-        unimplemented!()
+    async fn withdraw_money(
+        &self,
+        account_id: Uuid,
+        amount: Decimal,
+    ) -> Result<Account, BankingError> {
+        self.apply_command(
+            account_id,
+            &AccountCommand::WithdrawMoney { account_id, amount },
+            None,
+        )
+        .await
     }
 
-    async fn save_immediate(&self, account: &Account, events: Vec<AccountEvent>) -> Result<()> {
-        Ok(self
-            .event_store
-            .save_events(account.id, events, account.version)
-            .await?)
+    async fn rename_owner(
+        &self,
+        account_id: Uuid,
+        owner_name: String,
+    ) -> Result<Account, BankingError> {
+        self.apply_command(
+            account_id,
+            &AccountCommand::RenameOwner {
+                account_id,
+                owner_name,
+            },
+            None,
+        )
+        .await
+    }
+
+    async fn set_metadata(
+        &self,
+        account_id: Uuid,
+        key: String,
+        value: String,
+    ) -> Result<Account, BankingError> {
+        self.apply_command(
+            account_id,
+            &AccountCommand::SetMetadata {
+                account_id,
+                key,
+                value,
+            },
+            None,
+        )
+        .await
+    }
+
+    async fn remove_metadata(
+        &self,
+        account_id: Uuid,
+        key: String,
+    ) -> Result<Account, BankingError> {
+        self.apply_command(
+            account_id,
+            &AccountCommand::RemoveMetadata { account_id, key },
+            None,
+        )
+        .await
     }
 
-    async fn save(&self, account: &Account, events: Vec<AccountEvent>) -> Result<()> {
+    async fn save_immediate(
+        &self,
+        account: &Account,
+        events: Vec<AccountEvent>,
+    ) -> Result<(), BankingError> {
+        self.save_and_publish(account.id, events, account.version)
+            .await
+    }
+
+    async fn save(&self, account: &Account, events: Vec<AccountEvent>) -> Result<(), BankingError> {
         self.save(account, events).await
     }
 
+    async fn save_with_metadata(
+        &self,
+        account: &Account,
+        events: Vec<AccountEvent>,
+        metadata: EventMetadata,
+    ) -> Result<(), BankingError> {
+        self.save_with_metadata(account, events, metadata).await
+    }
+
+    async fn apply_command(
+        &self,
+        account_id: Uuid,
+        command: &AccountCommand,
+        expected_version: Option<i64>,
+    ) -> Result<Account, BankingError> {
+        self.apply_command(account_id, command, expected_version)
+            .await
+    }
+
     async fn get_by_id(&self, id: Uuid) -> Result<Option<Account>, AccountError> {
         self.get_by_id(id).await
     }
 
+    async fn get_by_id_or_last_known(&self, id: Uuid) -> Result<Option<AccountRead>, AccountError> {
+        self.get_by_id_or_last_known(id).await
+    }
+
+    async fn get_events(&self, id: Uuid) -> Result<Vec<StoredEvent>, AccountError> {
+        self.event_store.get_events(id, None).await.map_err(|e| {
+            error!("Failed to get events for account {}: {}", id, e);
+            event_store_error(e)
+        })
+    }
+
+    async fn get_events_page(
+        &self,
+        id: Uuid,
+        after_version: i64,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>, AccountError> {
+        self.event_store
+            .get_events_page(id, after_version, limit)
+            .await
+            .map_err(|e| {
+                error!("Failed to get events for account {}: {}", id, e);
+                event_store_error(e)
+            })
+    }
+
+    async fn get_events_caused_by(
+        &self,
+        id: Uuid,
+        causation_id: Uuid,
+    ) -> Result<Vec<StoredEvent>, AccountError> {
+        self.event_store
+            .get_events_caused_by(id, causation_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to get events for account {}: {}", id, e);
+                event_store_error(e)
+            })
+    }
+
     async fn save_batched(
         &self,
         account_id: Uuid,
         expected_version: i64,
         events: Vec<AccountEvent>,
-    ) -> Result<()> {
+    ) -> Result<(), BankingError> {
+        self.enqueue_batched(account_id, expected_version, events)
+            .await
+    }
+
+    async fn flush_all(&self) -> Result<(), BankingError> {
+        // Failed writes are reported to their own callers
+        self.flush_pending().await;
+        if let Some(queue) = &self.produce_queue {
+            queue.drain().await;
+        }
+        Ok(())
+    }
+
+    fn start_batch_flush_task(&self) {
+        if self
+            .flush_task_started
+            .swap(true, std::sync::atomic::Ordering::SeqCst)
+        {
+            return;
+        }
+        let repo = self.clone();
+        tokio::spawn(async move {
+            let config = repo.flush_config.clone();
+            let mut interval = config.max_interval;
+            loop {
+                repo.metrics.flush_interval_ms.store(
+                    interval.as_millis() as u64,
+                    std::sync::atomic::Ordering::Relaxed,
+                );
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = repo.flush_requested.notified() => {}
+                }
+                let flushed = repo.flush_pending().await;
+                interval = config.next_interval(interval, flushed);
+            }
+        });
+    }
+
+    async fn create_snapshot(&self, account_id: Uuid) -> Result<Option<i64>, BankingError> {
+        Ok(self.event_store.save_snapshot(account_id).await?)
+    }
+
+    async fn create_snapshots_above_threshold(
+        &self,
+        min_event_count: i64,
+    ) -> Result<Vec<(Uuid, i64)>, BankingError> {
         Ok(self
             .event_store
-            .save_events(account_id, events, expected_version)
+            .snapshot_aggregates_above_threshold(min_event_count)
             .await?)
     }
 
-    async fn flush_all(&self) -> Result<()> {
-        // If you have a flush method in KafkaEventProcessor, call it here. Otherwise, this can be a no-op.
-        Ok(())
+    async fn aggregate_stats(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<AggregateStats>, BankingError> {
+        Ok(self.event_store.aggregate_stats(account_id).await?)
     }
 
-    fn start_batch_flush_task(&self) {
-        // If you want to periodically flush Kafka, implement it here. Otherwise, this can be a no-op.
+    async fn top_aggregates_by_event_count(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<AggregateStats>, BankingError> {
+        Ok(self.event_store.top_aggregates_by_event_count(limit).await?)
+    }
+
+    async fn sample_account_ids(&self, limit: i64) -> Result<Vec<Uuid>, BankingError> {
+        Ok(self.event_store.sample_aggregate_ids(limit).await?)
     }
 }
 
@@ -219,6 +1150,7 @@ mod tests {
     use crate::domain::Account;
     use crate::infrastructure::event_store::EventStore;
     use std::sync::Arc;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_get_by_id_not_found() {
@@ -239,6 +1171,176 @@ mod tests {
         assert!(result.is_ok());
         assert!(result.unwrap().is_none());
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_first_metrics_report_is_jittered() {
+        let config = MetricsReporterConfig {
+            interval: Duration::from_secs(60),
+            max_jitter: Duration::from_secs(10),
+        };
+        let event_store = Arc::new(crate::infrastructure::InMemoryEventStore::new())
+            as Arc<dyn EventStoreTrait + 'static>;
+        let repo = AccountRepository::new_with_reporter_config(event_store, config);
+        assert_eq!(repo.metrics_snapshot().reports, 0);
+
+        // Reported inside the jitter window, well before a full interval has passed
+        tokio::time::sleep(Duration::from_secs(10) + Duration::from_millis(1)).await;
+        assert_eq!(repo.metrics_snapshot().reports, 1);
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(repo.metrics_snapshot().reports, 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_burst_flushes_before_max_interval() {
+        let event_store = Arc::new(crate::infrastructure::InMemoryEventStore::new());
+        let repo =
+            AccountRepository::new(event_store.clone() as Arc<dyn EventStoreTrait + 'static>)
+                .with_batch_flush_config(BatchFlushConfig {
+                    min_interval: Duration::from_millis(10),
+                    max_interval: Duration::from_secs(60),
+                    high_watermark: 3,
+                    ..BatchFlushConfig::default()
+                });
+        let created = |account_id| {
+            vec![AccountEvent::AccountCreated {
+                account_id,
+                owner_name: "Batch Flush Test".to_string(),
+                initial_balance: Decimal::new(100, 0),
+                owner_user_id: None,
+                account_type: Default::default(),
+                rules: Default::default(),
+            }]
+        };
+
+        let account_ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let burst = futures::future::join_all(
+            account_ids
+                .iter()
+                .map(|id| repo.save_batched(*id, 0, created(*id))),
+        );
+        let results = tokio::time::timeout(Duration::from_secs(1), burst)
+            .await
+            .expect("a burst past the watermark should flush before the max interval");
+        assert!(results.iter().all(|r| r.is_ok()));
+        for id in &account_ids {
+            assert_eq!(event_store.get_events(*id, None).await.unwrap().len(), 1);
+        }
+
+        // A full flush shortens the interval
+        let snapshot = repo.metrics_snapshot();
+        assert_eq!(snapshot.batch_flushes, 1);
+        assert_eq!(snapshot.events_processed, 3);
+        assert_eq!(snapshot.flush_interval_ms, 30_000);
+    }
+
+    fn bounded_repo(
+        event_store: Arc<crate::infrastructure::InMemoryEventStore>,
+        overflow: BatchOverflowPolicy,
+    ) -> AccountRepository {
+        AccountRepository::new(event_store as Arc<dyn EventStoreTrait + 'static>)
+            .with_batch_flush_config(BatchFlushConfig {
+                max_interval: Duration::from_secs(60),
+                max_pending_events: 2,
+                overflow,
+                ..BatchFlushConfig::default()
+            })
+    }
+
+    fn opened(account_id: Uuid) -> Vec<AccountEvent> {
+        vec![AccountEvent::AccountCreated {
+            account_id,
+            owner_name: "Bounded Buffer Test".to_string(),
+            initial_balance: Decimal::new(100, 0),
+            owner_user_id: None,
+            account_type: Default::default(),
+            rules: Default::default(),
+        }]
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_full_buffer_waits_for_a_flush() {
+        let event_store = Arc::new(crate::infrastructure::InMemoryEventStore::new());
+        let repo = bounded_repo(event_store.clone(), BatchOverflowPolicy::Backpressure);
+
+        let account_ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let queued: Vec<_> = account_ids[..2]
+            .iter()
+            .map(|id| {
+                let (repo, id) = (repo.clone(), *id);
+                tokio::spawn(async move { repo.save_batched(id, 0, opened(id)).await })
+            })
+            .collect();
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        assert_eq!(repo.metrics_snapshot().pending_events, 2);
+        assert_eq!(repo.metrics_snapshot().batch_flushes, 0);
+
+        // The third write flushes the first two rather than growing the buffer
+        let overflow = {
+            let (repo, id) = (repo.clone(), account_ids[2]);
+            tokio::spawn(async move { repo.save_batched(id, 0, opened(id)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        for write in queued {
+            assert!(write.await.unwrap().is_ok());
+        }
+        let snapshot = repo.metrics_snapshot();
+        assert_eq!(snapshot.buffer_overflows, 1);
+        assert_eq!(snapshot.batch_flushes, 1);
+        assert_eq!(snapshot.pending_events, 1);
+
+        repo.flush_all().await.unwrap();
+        assert!(overflow.await.unwrap().is_ok());
+        for id in &account_ids {
+            assert_eq!(event_store.get_events(*id, None).await.unwrap().len(), 1);
+        }
+        assert_eq!(repo.metrics_snapshot().pending_events, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_full_buffer_saves_immediately() {
+        let event_store = Arc::new(crate::infrastructure::InMemoryEventStore::new());
+        let repo = bounded_repo(event_store.clone(), BatchOverflowPolicy::SaveImmediately);
+
+        let account_ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let queued: Vec<_> = account_ids[..2]
+            .iter()
+            .map(|id| {
+                let (repo, id) = (repo.clone(), *id);
+                tokio::spawn(async move { repo.save_batched(id, 0, opened(id)).await })
+            })
+            .collect();
+        tokio::time::sleep(Duration::from_millis(1)).await;
+
+        // The overflowing write is saved without waiting; the buffered ones still wait
+        let overflow_id = account_ids[2];
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            repo.save_batched(overflow_id, 0, opened(overflow_id)),
+        )
+        .await
+        .expect("an overflowing write should not wait for a flush")
+        .unwrap();
+        assert_eq!(
+            event_store.get_events(overflow_id, None).await.unwrap().len(),
+            1
+        );
+        let snapshot = repo.metrics_snapshot();
+        assert_eq!(snapshot.buffer_overflows, 1);
+        assert_eq!(snapshot.batch_flushes, 0);
+        assert_eq!(snapshot.pending_events, 2);
+        assert!(event_store
+            .get_events(account_ids[0], None)
+            .await
+            .unwrap()
+            .is_empty());
+
+        repo.flush_all().await.unwrap();
+        for write in queued {
+            assert!(write.await.unwrap().is_ok());
+        }
+        assert_eq!(repo.metrics_snapshot().pending_events, 0);
+    }
 }
 
 impl Default for AccountRepository {
@@ -247,3 +1349,13 @@ impl Default for AccountRepository {
         AccountRepository::new(event_store)
     }
 }
+
+/// Keeps stored data that no longer decodes apart from the event store being unreachable.
+fn event_store_error(error: EventStoreError) -> AccountError {
+    match error {
+        EventStoreError::SerializationError(_) | EventStoreError::EventSerialization(_) => {
+            AccountError::from(BankingError::from(error))
+        }
+        other => AccountError::InfrastructureError(format!("Event store error: {}", other)),
+    }
+}
@@ -1,4 +1,5 @@
 use super::config::AppConfig;
+use crate::infrastructure::cache_service::scan_keys;
 use crate::infrastructure::redis_abstraction::{RedisClient, RedisClientTrait};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
@@ -6,6 +7,7 @@ use dashmap::DashMap;
 use redis;
 use redis::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
@@ -15,6 +17,9 @@ use uuid::Uuid;
 
 pub type ShardId = u32;
 
+const REGISTRATION_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const REGISTRATION_MAX_BACKOFF: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceInstance {
     pub id: String,
@@ -32,6 +37,8 @@ pub enum InstanceStatus {
     Starting,
     Stopping,
     Failed,
+    /// Finishing its in-flight work; scaling no longer counts it as active.
+    Draining,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -43,6 +50,77 @@ pub struct InstanceMetrics {
     pub latency_ms: u64,
 }
 
+/// Load on one shard, attributed from its owning instance's metrics. Instances report
+/// totals rather than per-shard counts, so an instance's requests and errors are split
+/// evenly across the shards assigned to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShardMetrics {
+    pub instance_id: String,
+    pub request_count: f64,
+    pub error_count: f64,
+    /// This shard's fraction of the requests across all instances.
+    pub request_share: f64,
+    /// Errors per request.
+    pub error_rate: f64,
+}
+
+/// Aggregates instance metrics by shard assignment, skipping failed instances. A shard
+/// claimed by more than one instance, e.g. mid-rebalance, is attributed to the one with
+/// the latest heartbeat.
+pub fn aggregate_shard_metrics<'a>(
+    instances: impl IntoIterator<Item = &'a ServiceInstance>,
+) -> HashMap<ShardId, ShardMetrics> {
+    let mut owners: HashMap<ShardId, &ServiceInstance> = HashMap::new();
+    for instance in instances {
+        if instance.status == InstanceStatus::Failed {
+            continue;
+        }
+        for shard in &instance.shard_assignments {
+            let owner = owners.entry(*shard).or_insert(instance);
+            if instance.last_heartbeat > owner.last_heartbeat {
+                *owner = instance;
+            }
+        }
+    }
+
+    // Only count each instance's shards that it still owns
+    let mut owned_shards: HashMap<&str, usize> = HashMap::new();
+    for owner in owners.values() {
+        *owned_shards.entry(owner.id.as_str()).or_default() += 1;
+    }
+    let total_requests: u64 = owners
+        .values()
+        .map(|owner| (owner.id.as_str(), owner.metrics.request_count))
+        .collect::<HashMap<_, _>>()
+        .values()
+        .sum();
+
+    owners
+        .into_iter()
+        .map(|(shard, owner)| {
+            let shards = owned_shards[owner.id.as_str()] as f64;
+            let request_count = owner.metrics.request_count as f64 / shards;
+            let error_count = owner.metrics.error_count as f64 / shards;
+            let metrics = ShardMetrics {
+                instance_id: owner.id.clone(),
+                request_count,
+                error_count,
+                request_share: if total_requests > 0 {
+                    request_count / total_requests as f64
+                } else {
+                    0.0
+                },
+                error_rate: if request_count > 0.0 {
+                    error_count / request_count
+                } else {
+                    0.0
+                },
+            };
+            (shard, metrics)
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScalingConfig {
     pub min_instances: usize,
@@ -52,6 +130,8 @@ pub struct ScalingConfig {
     pub cooldown_period: Duration,
     pub health_check_interval: Duration,
     pub instance_timeout: Duration,
+    /// How long startup keeps retrying instance registration before giving up.
+    pub registration_timeout: Duration,
 }
 
 impl Default for ScalingConfig {
@@ -64,6 +144,7 @@ impl Default for ScalingConfig {
             cooldown_period: Duration::from_secs(300), // 5 minutes
             health_check_interval: Duration::from_secs(30),
             instance_timeout: Duration::from_secs(60),
+            registration_timeout: Duration::from_secs(60),
         }
     }
 }
@@ -107,10 +188,153 @@ impl ScalingManager {
             .await?;
 
         self.instances.insert(instance_id.clone(), instance);
+        *self.instance_id.write().await = Some(instance_id.clone());
         info!("Registered new instance: {}", instance_id);
         Ok(())
     }
 
+    /// Registers the instance, retrying with exponential backoff so a transient Redis
+    /// failure at startup doesn't take the process down. Gives up after
+    /// `registration_timeout`.
+    pub async fn register_instance_with_retry(&self, instance: ServiceInstance) -> Result<()> {
+        let deadline = Instant::now() + self.config.registration_timeout;
+        let mut backoff = REGISTRATION_INITIAL_BACKOFF;
+        let mut attempt = 1;
+
+        loop {
+            match self.register_instance(instance.clone()).await {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(anyhow::anyhow!(
+                            "Failed to register instance {} after {} attempts: {}",
+                            instance.id,
+                            attempt,
+                            e
+                        ));
+                    }
+                    warn!(
+                        "Instance registration attempt {} failed: {}. Retrying in {:?}",
+                        attempt, e, backoff
+                    );
+                    tokio::time::sleep(backoff.min(deadline - now)).await;
+                    backoff = (backoff * 2).min(REGISTRATION_MAX_BACKOFF);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Removes the instance from Redis and the local registry so peers stop routing to
+    /// it immediately instead of waiting for its heartbeat to time out.
+    pub async fn deregister_instance(&self, instance_id: &str) -> Result<()> {
+        self.remove_instance(instance_id).await.map(|_| ())
+    }
+
+    /// Deregisters an instance on an operator's behalf, typically one that died without
+    /// deregistering itself. Returns whether Redis had a record of it.
+    pub async fn force_deregister_instance(&self, instance_id: &str) -> Result<bool> {
+        let removed = self.remove_instance(instance_id).await?;
+        if removed {
+            warn!("Force-deregistered instance: {}", instance_id);
+        }
+        Ok(removed)
+    }
+
+    async fn remove_instance(&self, instance_id: &str) -> Result<bool> {
+        let key = format!("instance:{}", instance_id);
+        let mut conn = self.redis_client.get_connection().await?;
+        let removed: i64 = redis::cmd("DEL").arg(key).query_async(&mut conn).await?;
+
+        self.instances.remove(instance_id);
+        let mut registered = self.instance_id.write().await;
+        if registered.as_deref() == Some(instance_id) {
+            *registered = None;
+        }
+        info!("Deregistered instance: {}", instance_id);
+        Ok(removed > 0)
+    }
+
+    /// Every instance registered in Redis, including those owned by other processes,
+    /// ordered by id. Records that fail to parse are skipped.
+    pub async fn list_instances(&self) -> Result<Vec<ServiceInstance>> {
+        let mut conn = self.redis_client.get_connection().await?;
+        let keys = scan_keys(&mut conn, "instance:*").await?;
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let values: Vec<Option<String>> =
+            redis::cmd("MGET").arg(&keys).query_async(&mut conn).await?;
+
+        let mut instances: Vec<ServiceInstance> = keys
+            .iter()
+            .zip(values)
+            .filter_map(|(key, value)| match serde_json::from_str(&value?) {
+                Ok(instance) => Some(instance),
+                Err(e) => {
+                    warn!("Skipping unreadable instance record {}: {}", key, e);
+                    None
+                }
+            })
+            .collect();
+        instances.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(instances)
+    }
+
+    /// Marks an instance `Draining` in Redis, wherever it runs, so it is no longer
+    /// treated as active. Returns the updated record, or `None` if it isn't registered.
+    pub async fn mark_draining(&self, instance_id: &str) -> Result<Option<ServiceInstance>> {
+        let key = format!("instance:{}", instance_id);
+        let mut conn = self.redis_client.get_connection().await?;
+        let value: Option<String> = redis::cmd("GET").arg(&key).query_async(&mut conn).await?;
+        let Some(value) = value else {
+            return Ok(None);
+        };
+
+        let mut instance: ServiceInstance = serde_json::from_str(&value)?;
+        instance.status = InstanceStatus::Draining;
+        // Keep the record's remaining lifetime, so a dead instance still expires
+        let ttl: i64 = redis::cmd("TTL").arg(&key).query_async(&mut conn).await?;
+        redis::cmd("SETEX")
+            .arg(&key)
+            .arg(if ttl > 0 { ttl } else { 60 })
+            .arg(serde_json::to_string(&instance)?)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+
+        if let Some(mut local) = self.instances.get_mut(instance_id) {
+            local.status = InstanceStatus::Draining;
+        }
+        info!("Marked instance {} as draining", instance_id);
+        Ok(Some(instance))
+    }
+
+    /// Changes the status peers and the load balancer see for this instance, e.g. from
+    /// `Starting` to `Active` once it has warmed up.
+    pub async fn update_instance_status(
+        &self,
+        instance_id: &str,
+        status: InstanceStatus,
+    ) -> Result<()> {
+        if let Some(mut instance) = self.instances.get_mut(instance_id) {
+            instance.status = status;
+            instance.last_heartbeat = Utc::now();
+
+            let key = format!("instance:{}", instance_id);
+            let value = serde_json::to_string(&*instance)?;
+
+            let mut conn = self.redis_client.get_connection().await?;
+            redis::cmd("SETEX")
+                .arg(key)
+                .arg(60)
+                .arg(value)
+                .query_async::<_, ()>(&mut conn)
+                .await?;
+        }
+        Ok(())
+    }
+
     pub async fn update_instance_metrics(
         &self,
         instance_id: &str,
@@ -266,6 +490,14 @@ impl ScalingManager {
         metrics.clone()
     }
 
+    /// Per-shard load across the instances this manager knows of, to spot hotspots and
+    /// inform rebalancing.
+    pub fn shard_metrics(&self) -> HashMap<ShardId, ShardMetrics> {
+        let instances: Vec<ServiceInstance> =
+            self.instances.iter().map(|i| i.value().clone()).collect();
+        aggregate_shard_metrics(&instances)
+    }
+
     pub async fn get_total_instances(&self) -> usize {
         // Implementation needed
         1 // Default value
@@ -353,4 +585,111 @@ mod tests {
         assert_eq!(instance.metrics.cpu_usage, 0.7);
         assert_eq!(instance.metrics.memory_usage, 0.8);
     }
+
+    fn instance_with_shards(
+        id: &str,
+        shards: Vec<ShardId>,
+        request_count: u64,
+        error_count: u64,
+    ) -> ServiceInstance {
+        ServiceInstance {
+            id: id.to_string(),
+            host: "localhost".to_string(),
+            port: 8080,
+            status: InstanceStatus::Active,
+            metrics: InstanceMetrics {
+                request_count,
+                error_count,
+                ..InstanceMetrics::default()
+            },
+            shard_assignments: shards,
+            last_heartbeat: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_shard_metrics_split_instance_load_across_its_shards() {
+        let instances = vec![
+            instance_with_shards("a", vec![0, 1], 600, 60),
+            instance_with_shards("b", vec![2], 400, 0),
+            ServiceInstance {
+                status: InstanceStatus::Failed,
+                ..instance_with_shards("failed", vec![3], 1_000, 1_000)
+            },
+        ];
+
+        let shards = aggregate_shard_metrics(&instances);
+
+        assert_eq!(shards.len(), 3);
+        assert!(!shards.contains_key(&3));
+        for shard in [0, 1] {
+            let metrics = &shards[&shard];
+            assert_eq!(metrics.instance_id, "a");
+            assert_eq!(metrics.request_count, 300.0);
+            assert_eq!(metrics.error_count, 30.0);
+            assert_eq!(metrics.request_share, 0.3);
+            assert_eq!(metrics.error_rate, 0.1);
+        }
+        let hottest = &shards[&2];
+        assert_eq!(hottest.instance_id, "b");
+        assert_eq!(hottest.request_count, 400.0);
+        assert_eq!(hottest.request_share, 0.4);
+        assert_eq!(hottest.error_rate, 0.0);
+    }
+
+    #[test]
+    fn test_contested_shard_goes_to_the_latest_heartbeat() {
+        let stale = ServiceInstance {
+            last_heartbeat: Utc::now() - chrono::Duration::seconds(30),
+            ..instance_with_shards("stale", vec![7, 8], 100, 0)
+        };
+        let fresh = instance_with_shards("fresh", vec![7], 50, 5);
+
+        let shards = aggregate_shard_metrics(&[stale, fresh]);
+
+        assert_eq!(shards[&7].instance_id, "fresh");
+        assert_eq!(shards[&7].request_count, 50.0);
+        // The stale instance keeps shard 8, which now carries all of its load
+        assert_eq!(shards[&8].instance_id, "stale");
+        assert_eq!(shards[&8].request_count, 100.0);
+    }
+
+    #[tokio::test]
+    async fn test_deregistration_removes_instance() {
+        let client = Client::open("redis://127.0.0.1/").unwrap();
+        let redis_client = RealRedisClient::new(client, None);
+        let config = ScalingConfig {
+            registration_timeout: Duration::from_secs(5),
+            ..ScalingConfig::default()
+        };
+        let manager = ScalingManager::new(redis_client.clone(), config);
+
+        let instance = ServiceInstance {
+            id: "deregistered-instance".to_string(),
+            host: "localhost".to_string(),
+            port: 8080,
+            status: InstanceStatus::Active,
+            metrics: InstanceMetrics::default(),
+            shard_assignments: vec![],
+            last_heartbeat: Utc::now(),
+        };
+
+        manager.register_instance_with_retry(instance).await.unwrap();
+        assert!(manager.instances.contains_key("deregistered-instance"));
+
+        manager
+            .deregister_instance("deregistered-instance")
+            .await
+            .unwrap();
+        assert!(!manager.instances.contains_key("deregistered-instance"));
+        assert!(manager.instance_id.read().await.is_none());
+
+        let mut conn = redis_client.get_connection().await.unwrap();
+        let exists: bool = redis::cmd("EXISTS")
+            .arg("instance:deregistered-instance")
+            .query_async(&mut conn)
+            .await
+            .unwrap();
+        assert!(!exists);
+    }
 }
@@ -0,0 +1,304 @@
+use crate::domain::{Account, AccountEvent};
+use crate::infrastructure::cache_service::CacheServiceTrait;
+use crate::infrastructure::event_store::EventStoreTrait;
+use crate::infrastructure::kafka_abstraction::KafkaProducer;
+use crate::infrastructure::projections::{AccountProjection, ProjectionStoreTrait};
+use crate::infrastructure::readiness::{DependencyCheck, DependencyStatus};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Owner name of the throwaway accounts the self-check writes.
+pub const SELF_CHECK_OWNER: &str = "startup-self-check";
+
+/// The projection row the self-check rewrites on every start. Projections can't be
+/// deleted, so one fixed, inactive row is reused rather than leaving one per start.
+pub const SELF_CHECK_ACCOUNT_ID: Uuid = Uuid::from_u128(0x5e1f_c4ec_0000_4000_8000_0000_0000_0001);
+
+/// Round trips through the stores run once at startup, before the instance takes traffic.
+#[derive(Debug, Clone)]
+pub struct SelfCheckConfig {
+    pub enabled: bool,
+    /// How long each step may take before it counts as failed.
+    pub step_timeout: Duration,
+    /// Fail startup when a step fails; otherwise the instance starts but stays not ready.
+    pub fail_startup: bool,
+}
+
+impl Default for SelfCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            step_timeout: Duration::from_secs(5),
+            fail_startup: true,
+        }
+    }
+}
+
+impl SelfCheckConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            enabled: std::env::var("STARTUP_SELF_CHECK_ENABLED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.enabled),
+            step_timeout: std::env::var("STARTUP_SELF_CHECK_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(default.step_timeout),
+            fail_startup: std::env::var("STARTUP_SELF_CHECK_FAIL_STARTUP")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.fail_startup),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Startup self-check failed at {step}: {error}")]
+pub struct SelfCheckError {
+    pub step: String,
+    pub error: String,
+}
+
+/// Runs its steps in order and stops at the first one that fails.
+pub struct SelfCheck {
+    steps: Vec<Arc<dyn DependencyCheck>>,
+}
+
+impl SelfCheck {
+    pub fn new() -> Self {
+        Self { steps: Vec::new() }
+    }
+
+    pub fn with_step(mut self, step: Arc<dyn DependencyCheck>) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// The status of every step, or the first failure.
+    pub async fn run(&self) -> Result<Vec<DependencyStatus>, SelfCheckError> {
+        let mut statuses = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            let started = Instant::now();
+            let result = match tokio::time::timeout(step.timeout(), step.check()).await {
+                Ok(result) => result,
+                Err(_) => Err(anyhow::anyhow!("timed out after {:?}", step.timeout())),
+            };
+            if let Err(e) = result {
+                warn!("Startup self-check of {} failed: {}", step.name(), e);
+                return Err(SelfCheckError {
+                    step: step.name().to_string(),
+                    error: e.to_string(),
+                });
+            }
+            statuses.push(DependencyStatus {
+                name: step.name().to_string(),
+                healthy: true,
+                latency_ms: started.elapsed().as_millis() as u64,
+                error: None,
+            });
+        }
+        info!("Startup self-check passed ({} steps)", statuses.len());
+        Ok(statuses)
+    }
+}
+
+impl Default for SelfCheck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Saves a new throwaway account, reads it back and deletes it again.
+pub struct EventStoreRoundTrip {
+    event_store: Arc<dyn EventStoreTrait + Send + Sync>,
+    timeout: Duration,
+}
+
+impl EventStoreRoundTrip {
+    pub fn new(event_store: Arc<dyn EventStoreTrait + Send + Sync>, timeout: Duration) -> Self {
+        Self {
+            event_store,
+            timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl DependencyCheck for EventStoreRoundTrip {
+    fn name(&self) -> &str {
+        "event_store"
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    async fn check(&self) -> Result<()> {
+        let account_id = Uuid::new_v4();
+        self.event_store
+            .save_events(
+                account_id,
+                vec![AccountEvent::AccountCreated {
+                    account_id,
+                    owner_name: SELF_CHECK_OWNER.to_string(),
+                    initial_balance: Decimal::ZERO,
+                    owner_user_id: None,
+                    account_type: Default::default(),
+                    rules: Default::default(),
+                }],
+                0,
+            )
+            .await?;
+        let events = self.event_store.get_events(account_id, None).await?;
+        let read_back = matches!(
+            events.first().map(|event| event.decode()),
+            Some(Ok(AccountEvent::AccountCreated { owner_name, .. })) if owner_name == SELF_CHECK_OWNER
+        );
+
+        if let Err(e) = sqlx::query("DELETE FROM events WHERE aggregate_id = $1")
+            .bind(account_id)
+            .execute(&self.event_store.get_pool())
+            .await
+        {
+            warn!("Failed to delete self-check account {}: {}", account_id, e);
+        }
+        anyhow::ensure!(
+            events.len() == 1 && read_back,
+            "saved event was not read back, got {} event(s)",
+            events.len()
+        );
+        Ok(())
+    }
+}
+
+/// Caches a throwaway account, reads it back and evicts it.
+pub struct CacheRoundTrip {
+    cache: Arc<dyn CacheServiceTrait + Send + Sync>,
+    timeout: Duration,
+}
+
+impl CacheRoundTrip {
+    pub fn new(cache: Arc<dyn CacheServiceTrait + Send + Sync>, timeout: Duration) -> Self {
+        Self { cache, timeout }
+    }
+}
+
+#[async_trait]
+impl DependencyCheck for CacheRoundTrip {
+    fn name(&self) -> &str {
+        "cache"
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    async fn check(&self) -> Result<()> {
+        let account = Account {
+            id: Uuid::new_v4(),
+            owner_name: SELF_CHECK_OWNER.to_string(),
+            ..Account::default()
+        };
+        self.cache
+            .set_account(&account, Some(Duration::from_secs(60)))
+            .await?;
+        let cached = self.cache.get_account(account.id).await;
+        self.cache.invalidate_account(account.id).await?;
+        anyhow::ensure!(
+            cached?.is_some_and(|cached| cached.owner_name == SELF_CHECK_OWNER),
+            "cached account was not read back"
+        );
+        Ok(())
+    }
+}
+
+/// Writes the self-check projection row and reads it back past any projection cache.
+pub struct ProjectionRoundTrip {
+    projections: Arc<dyn ProjectionStoreTrait + Send + Sync>,
+    timeout: Duration,
+}
+
+impl ProjectionRoundTrip {
+    pub fn new(
+        projections: Arc<dyn ProjectionStoreTrait + Send + Sync>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            projections,
+            timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl DependencyCheck for ProjectionRoundTrip {
+    fn name(&self) -> &str {
+        "projections"
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    async fn check(&self) -> Result<()> {
+        let now = Utc::now();
+        self.projections
+            .upsert_account_now(AccountProjection {
+                id: SELF_CHECK_ACCOUNT_ID,
+                owner_name: SELF_CHECK_OWNER.to_string(),
+                balance: Decimal::ZERO,
+                is_active: false,
+                created_at: now,
+                updated_at: now,
+                owner_user_id: None,
+            })
+            .await?;
+        let projection = self
+            .projections
+            .get_account_uncached(SELF_CHECK_ACCOUNT_ID)
+            .await?;
+        anyhow::ensure!(
+            projection.is_some_and(|projection| projection.owner_name == SELF_CHECK_OWNER),
+            "projection was not read back"
+        );
+        Ok(())
+    }
+}
+
+/// Produces a message to Kafka and consumes it back.
+pub struct KafkaRoundTrip {
+    producer: KafkaProducer,
+    timeout: Duration,
+}
+
+impl KafkaRoundTrip {
+    pub fn new(producer: KafkaProducer, timeout: Duration) -> Self {
+        Self { producer, timeout }
+    }
+}
+
+#[async_trait]
+impl DependencyCheck for KafkaRoundTrip {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    async fn check(&self) -> Result<()> {
+        self.producer.round_trip(self.timeout).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// The stages of a shutdown, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ShutdownPhase {
+    /// Stop accepting new requests.
+    StopIntake,
+    /// Wait for in-flight requests to finish.
+    DrainInFlight,
+    /// Write out anything the repository still has buffered.
+    FlushRepository,
+    /// Stop the Kafka consumers and commit their offsets.
+    StopKafka,
+    /// Leave the cluster so no shards are routed here.
+    Deregister,
+    /// Close the Redis and Postgres connection pools.
+    ClosePools,
+    /// Export any spans still buffered.
+    FlushTracing,
+}
+
+impl ShutdownPhase {
+    pub const ALL: [ShutdownPhase; 7] = [
+        ShutdownPhase::StopIntake,
+        ShutdownPhase::DrainInFlight,
+        ShutdownPhase::FlushRepository,
+        ShutdownPhase::StopKafka,
+        ShutdownPhase::Deregister,
+        ShutdownPhase::ClosePools,
+        ShutdownPhase::FlushTracing,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShutdownPhase::StopIntake => "stop_intake",
+            ShutdownPhase::DrainInFlight => "drain_in_flight",
+            ShutdownPhase::FlushRepository => "flush_repository",
+            ShutdownPhase::StopKafka => "stop_kafka",
+            ShutdownPhase::Deregister => "deregister",
+            ShutdownPhase::ClosePools => "close_pools",
+            ShutdownPhase::FlushTracing => "flush_tracing",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShutdownConfig {
+    /// Used for phases without their own entry in `phase_timeouts`.
+    pub default_timeout: Duration,
+    pub phase_timeouts: HashMap<ShutdownPhase, Duration>,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            default_timeout: Duration::from_secs(10),
+            // In-flight requests get longer than the bookkeeping phases
+            phase_timeouts: HashMap::from([(
+                ShutdownPhase::DrainInFlight,
+                Duration::from_secs(30),
+            )]),
+        }
+    }
+}
+
+impl ShutdownConfig {
+    /// Reads `SHUTDOWN_TIMEOUT_MS` and `SHUTDOWN_<PHASE>_TIMEOUT_MS`, e.g.
+    /// `SHUTDOWN_DRAIN_IN_FLIGHT_TIMEOUT_MS`.
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(timeout) = timeout_from_env("SHUTDOWN_TIMEOUT_MS") {
+            config.default_timeout = timeout;
+        }
+        for phase in ShutdownPhase::ALL {
+            let var = format!(
+                "SHUTDOWN_{}_TIMEOUT_MS",
+                phase.as_str().to_ascii_uppercase()
+            );
+            if let Some(timeout) = timeout_from_env(&var) {
+                config.phase_timeouts.insert(phase, timeout);
+            }
+        }
+        config
+    }
+
+    pub fn timeout_for(&self, phase: ShutdownPhase) -> Duration {
+        self.phase_timeouts
+            .get(&phase)
+            .copied()
+            .unwrap_or(self.default_timeout)
+    }
+}
+
+fn timeout_from_env(var: &str) -> Option<Duration> {
+    std::env::var(var)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_millis)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PhaseResult {
+    Completed,
+    Failed(String),
+    TimedOut,
+}
+
+#[derive(Debug, Clone)]
+pub struct PhaseOutcome {
+    pub phase: ShutdownPhase,
+    pub result: PhaseResult,
+    pub elapsed: Duration,
+}
+
+type ShutdownStep = Box<dyn FnOnce() -> BoxFuture<'static, anyhow::Result<()>> + Send>;
+
+/// Runs shutdown work in phase order. Each phase gets its own timeout; a phase that
+/// fails or times out is logged and the next one still runs, so a stuck Kafka commit
+/// can't keep the pools open forever.
+pub struct ShutdownCoordinator {
+    config: ShutdownConfig,
+    steps: HashMap<ShutdownPhase, Vec<ShutdownStep>>,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(config: ShutdownConfig) -> Self {
+        Self {
+            config,
+            steps: HashMap::new(),
+        }
+    }
+
+    /// Adds work to a phase. Steps within a phase run in the order they were added.
+    pub fn add_step<F, Fut>(&mut self, phase: ShutdownPhase, step: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.steps
+            .entry(phase)
+            .or_default()
+            .push(Box::new(move || Box::pin(step())));
+    }
+
+    /// Runs every phase that has steps, in `ShutdownPhase` order.
+    pub async fn run(mut self) -> Vec<PhaseOutcome> {
+        let mut outcomes = Vec::new();
+        for phase in ShutdownPhase::ALL {
+            let Some(steps) = self.steps.remove(&phase) else {
+                continue;
+            };
+            let timeout = self.config.timeout_for(phase);
+            info!("Shutdown phase {} starting", phase.as_str());
+            let started = Instant::now();
+
+            let result = match tokio::time::timeout(timeout, async move {
+                for step in steps {
+                    step().await?;
+                }
+                Ok::<_, anyhow::Error>(())
+            })
+            .await
+            {
+                Ok(Ok(())) => PhaseResult::Completed,
+                Ok(Err(e)) => PhaseResult::Failed(e.to_string()),
+                Err(_) => PhaseResult::TimedOut,
+            };
+
+            let elapsed = started.elapsed();
+            match &result {
+                PhaseResult::Completed => {
+                    info!(
+                        "Shutdown phase {} completed in {:?}",
+                        phase.as_str(),
+                        elapsed
+                    )
+                }
+                PhaseResult::Failed(e) => {
+                    error!("Shutdown phase {} failed: {}", phase.as_str(), e)
+                }
+                PhaseResult::TimedOut => {
+                    warn!(
+                        "Shutdown phase {} timed out after {:?}",
+                        phase.as_str(),
+                        timeout
+                    )
+                }
+            }
+            outcomes.push(PhaseOutcome {
+                phase,
+                result,
+                elapsed,
+            });
+        }
+        outcomes
+    }
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self::new(ShutdownConfig::default())
+    }
+}
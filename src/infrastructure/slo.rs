@@ -0,0 +1,157 @@
+use crate::infrastructure::config::SloConfig;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Most response times kept for the window. Under heavier traffic the oldest are dropped
+/// early, so the percentile covers a shorter span than the window.
+const MAX_SAMPLES: usize = 100_000;
+
+/// Told when the response-time SLO starts and stops being met.
+pub trait SloAlert: Send + Sync {
+    fn breached(&self, status: &SloStatus);
+    fn recovered(&self, status: &SloStatus);
+}
+
+/// Logs breaches at warn and recoveries at info, under the `slo` target.
+pub struct LogSloAlert;
+
+impl SloAlert for LogSloAlert {
+    fn breached(&self, status: &SloStatus) {
+        warn!(
+            target: "slo",
+            "Response-time SLO breached: p{} is {:.1}ms over {} responses, target {:.1}ms",
+            status.percentile,
+            status.latency_ms.unwrap_or_default(),
+            status.samples,
+            status.target_ms
+        );
+    }
+
+    fn recovered(&self, status: &SloStatus) {
+        info!(
+            target: "slo",
+            "Response-time SLO recovered: p{} is {:.1}ms over {} responses, target {:.1}ms",
+            status.percentile,
+            status.latency_ms.unwrap_or_default(),
+            status.samples,
+            status.target_ms
+        );
+    }
+}
+
+/// The outcome of one SLO evaluation.
+#[derive(Debug, Clone, Serialize)]
+pub struct SloStatus {
+    pub healthy: bool,
+    /// The configured percentile of the window's response times, `None` while the window
+    /// is empty.
+    pub latency_ms: Option<f64>,
+    pub target_ms: f64,
+    pub percentile: f64,
+    pub samples: usize,
+}
+
+/// Keeps the response times of the last `window` and checks their percentile against the
+/// target, alerting when the SLO goes from met to breached and back. An empty window
+/// counts as met.
+pub struct SloTracker {
+    config: SloConfig,
+    samples: Mutex<VecDeque<(Instant, Duration)>>,
+    healthy: AtomicBool,
+    alert: Arc<dyn SloAlert>,
+}
+
+impl SloTracker {
+    pub fn new(config: SloConfig) -> Self {
+        Self {
+            config,
+            samples: Mutex::new(VecDeque::new()),
+            healthy: AtomicBool::new(true),
+            alert: Arc::new(LogSloAlert),
+        }
+    }
+
+    /// Replaces the default logging alert.
+    pub fn with_alert(mut self, alert: Arc<dyn SloAlert>) -> Self {
+        self.alert = alert;
+        self
+    }
+
+    pub fn record(&self, latency: Duration) {
+        self.record_at(Instant::now(), latency);
+    }
+
+    /// Records a response that completed at `at` after `latency`.
+    pub fn record_at(&self, at: Instant, latency: Duration) {
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() >= MAX_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back((at, latency));
+    }
+
+    /// Whether the SLO was met at the last evaluation.
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    pub fn evaluate(&self) -> SloStatus {
+        self.evaluate_at(Instant::now())
+    }
+
+    /// Drops responses that have left the window as of `now`, then checks the rest,
+    /// alerting if the outcome differs from the last evaluation's.
+    pub fn evaluate_at(&self, now: Instant) -> SloStatus {
+        let mut latencies: Vec<Duration> = {
+            let mut samples = self.samples.lock().unwrap();
+            while samples
+                .front()
+                .is_some_and(|(at, _)| now.saturating_duration_since(*at) > self.config.window)
+            {
+                samples.pop_front();
+            }
+            samples.iter().map(|(_, latency)| *latency).collect()
+        };
+        latencies.sort_unstable();
+        let latency = nearest_rank(&latencies, self.config.percentile);
+        let healthy = latency.is_none_or(|latency| latency <= self.config.target);
+        let status = SloStatus {
+            healthy,
+            latency_ms: latency.map(|latency| latency.as_micros() as f64 / 1000.0),
+            target_ms: self.config.target.as_micros() as f64 / 1000.0,
+            percentile: self.config.percentile,
+            samples: latencies.len(),
+        };
+
+        match (self.healthy.swap(healthy, Ordering::Relaxed), healthy) {
+            (true, false) => self.alert.breached(&status),
+            (false, true) => self.alert.recovered(&status),
+            _ => {}
+        }
+        status
+    }
+
+    /// Evaluates the SLO every `evaluation_interval` for as long as the process runs.
+    pub fn spawn_evaluation(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(tracker.config.evaluation_interval).await;
+                tracker.evaluate();
+            }
+        })
+    }
+}
+
+/// The smallest latency at least `percentile` percent of `sorted` don't exceed.
+fn nearest_rank(sorted: &[Duration], percentile: f64) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
@@ -0,0 +1,374 @@
+use crate::infrastructure::redis_abstraction::RedisClientTrait;
+use axum::{
+    http::{header::RETRY_AFTER, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use redis::RedisError;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::warn;
+
+/// Tenant the requests of tokens without a company are counted under.
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Increments the counter in `KEYS[1]` unless that takes it past `ARGV[1]`, and gives it
+/// a TTL of `ARGV[2]` ms when `ARGV[2]` is non-zero. Returns whether the increment was
+/// kept and the counter's remaining TTL.
+const TAKE_SCRIPT: &str = r#"
+local count = redis.call('INCR', KEYS[1])
+if tonumber(ARGV[2]) > 0 and (count == 1 or ARGV[3] == '1') then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+if count > tonumber(ARGV[1]) then
+    redis.call('DECR', KEYS[1])
+    return {0, redis.call('PTTL', KEYS[1])}
+end
+return {1, redis.call('PTTL', KEYS[1])}
+"#;
+
+/// What a tenant may use; zero leaves that resource unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TenantQuota {
+    pub requests_per_second: u32,
+    pub max_concurrent: u32,
+    pub max_accounts: u64,
+}
+
+impl Default for TenantQuota {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 100,
+            max_concurrent: 20,
+            max_accounts: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TenantQuotaConfig {
+    pub enabled: bool,
+    /// Applies to every tenant without an override.
+    pub default_quota: TenantQuota,
+    pub overrides: HashMap<String, TenantQuota>,
+    /// How long an instance's in-flight count outlives its last request, so counts held
+    /// by a crashed instance don't block the tenant for good.
+    pub concurrency_ttl: Duration,
+    /// Sent as `Retry-After` when the account quota is used up, which only frees up when
+    /// the quota is raised.
+    pub accounts_retry_after: Duration,
+}
+
+impl Default for TenantQuotaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_quota: TenantQuota::default(),
+            overrides: HashMap::new(),
+            concurrency_ttl: Duration::from_secs(60),
+            accounts_retry_after: Duration::from_secs(3600),
+        }
+    }
+}
+
+impl TenantQuotaConfig {
+    /// Reads `TENANT_QUOTA_*`. `TENANT_QUOTA_OVERRIDES` is a comma-separated list of
+    /// `tenant=requests_per_second/max_concurrent/max_accounts` entries; a blank field
+    /// keeps the default, e.g. `acme=500//10000`.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let default_quota = TenantQuota {
+            requests_per_second: std::env::var("TENANT_QUOTA_REQUESTS_PER_SECOND")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.default_quota.requests_per_second),
+            max_concurrent: std::env::var("TENANT_QUOTA_MAX_CONCURRENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.default_quota.max_concurrent),
+            max_accounts: std::env::var("TENANT_QUOTA_MAX_ACCOUNTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.default_quota.max_accounts),
+        };
+        Self {
+            enabled: std::env::var("TENANT_QUOTA_ENABLED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.enabled),
+            overrides: parse_overrides(
+                &std::env::var("TENANT_QUOTA_OVERRIDES").unwrap_or_default(),
+                default_quota,
+            ),
+            default_quota,
+            concurrency_ttl: std::env::var("TENANT_QUOTA_CONCURRENCY_TTL_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(default.concurrency_ttl),
+            accounts_retry_after: std::env::var("TENANT_QUOTA_ACCOUNTS_RETRY_AFTER_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(default.accounts_retry_after),
+        }
+    }
+
+    pub fn quota_for(&self, tenant: &str) -> TenantQuota {
+        self.overrides
+            .get(tenant)
+            .copied()
+            .unwrap_or(self.default_quota)
+    }
+}
+
+fn parse_overrides(value: &str, default_quota: TenantQuota) -> HashMap<String, TenantQuota> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (tenant, limits) = entry.split_once('=')?;
+            let tenant = tenant.trim();
+            if tenant.is_empty() {
+                return None;
+            }
+            let mut fields = limits.split('/').map(str::trim);
+            let mut quota = default_quota;
+            if let Some(v) = fields.next().and_then(|v| v.parse().ok()) {
+                quota.requests_per_second = v;
+            }
+            if let Some(v) = fields.next().and_then(|v| v.parse().ok()) {
+                quota.max_concurrent = v;
+            }
+            if let Some(v) = fields.next().and_then(|v| v.parse().ok()) {
+                quota.max_accounts = v;
+            }
+            Some((tenant.to_string(), quota))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaResource {
+    RequestRate,
+    Concurrency,
+    Accounts,
+}
+
+impl fmt::Display for QuotaResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            QuotaResource::RequestRate => "request rate",
+            QuotaResource::Concurrency => "concurrent request",
+            QuotaResource::Accounts => "account",
+        })
+    }
+}
+
+#[derive(Error, Debug, Clone)]
+#[error("Tenant {tenant} exceeded its {resource} quota")]
+pub struct QuotaExceeded {
+    pub tenant: String,
+    pub resource: QuotaResource,
+    /// When the tenant can expect the quota to have room again.
+    pub retry_after: Duration,
+}
+
+impl QuotaExceeded {
+    /// `retry_after` in whole seconds, rounded up so clients don't retry early.
+    pub fn retry_after_secs(&self) -> u64 {
+        self.retry_after.as_millis().div_ceil(1000).max(1) as u64
+    }
+}
+
+impl IntoResponse for QuotaExceeded {
+    fn into_response(self) -> Response {
+        let retry_after = self.retry_after_secs();
+        let body = Json(serde_json::json!({
+            "error": self.to_string(),
+            "tenant": self.tenant,
+        }));
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(RETRY_AFTER, retry_after.to_string())],
+            body,
+        )
+            .into_response()
+    }
+}
+
+/// Per-tenant quotas counted in Redis, so they hold across instances. Redis being down
+/// lets requests through rather than failing every tenant.
+pub struct TenantQuotas {
+    redis: Arc<dyn RedisClientTrait>,
+    config: TenantQuotaConfig,
+}
+
+impl fmt::Debug for TenantQuotas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TenantQuotas")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// Holds one of the tenant's concurrent request slots until dropped.
+pub struct TenantPermit {
+    redis: Arc<dyn RedisClientTrait>,
+    key: Option<String>,
+}
+
+impl Drop for TenantPermit {
+    fn drop(&mut self) {
+        let Some(key) = self.key.take() else {
+            return;
+        };
+        let redis = self.redis.clone();
+        tokio::spawn(async move {
+            if let Err(e) = decrement(redis.as_ref(), &key).await {
+                warn!("Failed to release tenant quota slot {}: {}", key, e);
+            }
+        });
+    }
+}
+
+async fn decrement(redis: &dyn RedisClientTrait, key: &str) -> Result<(), RedisError> {
+    let mut conn = redis.get_connection().await?;
+    redis::cmd("DECR")
+        .arg(key)
+        .query_async::<_, i64>(&mut conn)
+        .await?;
+    Ok(())
+}
+
+impl TenantQuotas {
+    pub fn new(redis: Arc<dyn RedisClientTrait>, config: TenantQuotaConfig) -> Self {
+        Self { redis, config }
+    }
+
+    pub fn config(&self) -> &TenantQuotaConfig {
+        &self.config
+    }
+
+    /// Counts a request against the tenant's rate and takes one of its concurrent slots.
+    pub async fn acquire(&self, tenant: &str) -> Result<TenantPermit, QuotaExceeded> {
+        let quota = self.config.quota_for(tenant);
+        if quota.requests_per_second > 0 {
+            let second = chrono::Utc::now().timestamp();
+            let key = format!("tenant_quota:{}:rps:{}", tenant, second);
+            self.take(
+                tenant,
+                QuotaResource::RequestRate,
+                &key,
+                quota.requests_per_second as u64,
+                Duration::from_secs(1),
+                false,
+            )
+            .await?;
+        }
+        let mut permit = TenantPermit {
+            redis: self.redis.clone(),
+            key: None,
+        };
+        if quota.max_concurrent > 0 {
+            let key = format!("tenant_quota:{}:in_flight", tenant);
+            if self
+                .take(
+                    tenant,
+                    QuotaResource::Concurrency,
+                    &key,
+                    quota.max_concurrent as u64,
+                    self.config.concurrency_ttl,
+                    true,
+                )
+                .await?
+            {
+                permit.key = Some(key);
+            }
+        }
+        Ok(permit)
+    }
+
+    /// Counts a new account against the tenant's account quota. Undo it with
+    /// `release_account` if the account isn't created after all.
+    pub async fn reserve_account(&self, tenant: &str) -> Result<(), QuotaExceeded> {
+        let quota = self.config.quota_for(tenant);
+        if quota.max_accounts > 0 {
+            self.take(
+                tenant,
+                QuotaResource::Accounts,
+                &accounts_key(tenant),
+                quota.max_accounts,
+                Duration::ZERO,
+                false,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn release_account(&self, tenant: &str) {
+        if self.config.quota_for(tenant).max_accounts == 0 {
+            return;
+        }
+        if let Err(e) = decrement(self.redis.as_ref(), &accounts_key(tenant)).await {
+            warn!(
+                "Failed to release account quota of tenant {}: {}",
+                tenant, e
+            );
+        }
+    }
+
+    /// Takes one unit of `key`, up to `limit`. Returns whether the unit was counted, which
+    /// is false when Redis couldn't be reached.
+    async fn take(
+        &self,
+        tenant: &str,
+        resource: QuotaResource,
+        key: &str,
+        limit: u64,
+        ttl: Duration,
+        refresh_ttl: bool,
+    ) -> Result<bool, QuotaExceeded> {
+        let result: Result<(i64, i64), RedisError> = async {
+            let mut conn = self.redis.get_connection().await?;
+            redis::Script::new(TAKE_SCRIPT)
+                .key(key)
+                .arg(limit)
+                .arg(ttl.as_millis() as u64)
+                .arg(if refresh_ttl { "1" } else { "0" })
+                .invoke_async(&mut conn)
+                .await
+        }
+        .await;
+        match result {
+            Ok((1, _)) => Ok(true),
+            Ok((_, ttl_ms)) => {
+                warn!("Tenant {} exceeded its {} quota", tenant, resource);
+                let retry_after = match resource {
+                    QuotaResource::Accounts => self.config.accounts_retry_after,
+                    QuotaResource::Concurrency => Duration::from_secs(1),
+                    QuotaResource::RequestRate => Duration::from_millis(ttl_ms.max(0) as u64),
+                };
+                Err(QuotaExceeded {
+                    tenant: tenant.to_string(),
+                    resource,
+                    retry_after,
+                })
+            }
+            Err(e) => {
+                warn!(
+                    "Tenant quota check failed, letting the request through: {}",
+                    e
+                );
+                Ok(false)
+            }
+        }
+    }
+}
+
+fn accounts_key(tenant: &str) -> String {
+    format!("tenant_quota:{}:accounts", tenant)
+}
@@ -1,11 +1,13 @@
 pub mod application;
 pub mod domain;
+pub mod error;
 pub mod infrastructure;
 pub mod web;
 
 // Re-export commonly used types
 pub use application::AccountService;
 pub use domain::AccountError;
+pub use error::BankingError;
 pub use infrastructure::repository::AccountRepositoryTrait;
 pub use infrastructure::{
     config::AppConfig, AccountRepository, EventStore, EventStoreConfig, KafkaConfig,
@@ -5,16 +5,13 @@ use crate::infrastructure::kafka_abstraction::KafkaConfig;
 use crate::infrastructure::projections::ProjectionStore;
 use crate::infrastructure::redis_abstraction::RealRedisClient;
 use crate::infrastructure::redis_abstraction::RedisClient;
-use crate::infrastructure::scaling::{ScalingConfig, ScalingManager, ServiceInstance};
+use crate::infrastructure::scaling::{
+    InstanceMetrics, InstanceStatus, ScalingConfig, ScalingManager, ServiceInstance,
+};
+use crate::infrastructure::shutdown::{ShutdownConfig, ShutdownCoordinator, ShutdownPhase};
 use crate::web::routes::create_router;
 use anyhow::Result;
-use axum::{
-    http::Method,
-    response::Html,
-    routing::IntoMakeService,
-    routing::{get, post},
-    Router,
-};
+use axum::response::Html;
 use chrono::Utc;
 use dotenv;
 use redis;
@@ -22,19 +19,13 @@ use sqlx::PgPool;
 use std::time::Duration;
 use std::{net::SocketAddr, sync::Arc};
 use tokio::signal;
-use tower_http::cors::CorsLayer;
-use tracing::{error, info, Level};
+use tracing::{error, info};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use uuid::Uuid;
 
-use tokio::net::TcpListener;
-use tower::ServiceBuilder;
-use tower_http::compression::CompressionLayer;
-use tower_http::services::ServeDir;
-use tower_http::trace::TraceLayer;
-
 mod application;
 mod domain;
+mod error;
 mod infrastructure;
 mod web;
 
@@ -46,7 +37,7 @@ use opentelemetry::sdk::export::trace::SpanExporter;
 use opentelemetry::trace::TracerProvider;
 
 use crate::infrastructure::init::init_all_services;
-use infrastructure::config::AppConfig;
+use infrastructure::config::{LoggingConfig, ServerConfig};
 
 async fn root() -> Html<&'static str> {
     Html(
@@ -62,15 +53,15 @@ async fn root() -> Html<&'static str> {
             </head>
             <body>
                 <h1>Banking Service API</h1>
-                <p>Welcome to the Banking Service API. Available endpoints:</p>
-                <div class="endpoint">GET /health - Health check endpoint</div>
-                <div class="endpoint">GET /metrics - Service metrics</div>
-                <div class="endpoint">POST /accounts - Create new account</div>
-                <div class="endpoint">GET /accounts/{id} - Get account details</div>
-                <div class="endpoint">POST /accounts/{id}/deposit - Deposit money</div>
-                <div class="endpoint">POST /accounts/{id}/withdraw - Withdraw money</div>
-                <div class="endpoint">GET /accounts/{id}/transactions - Get account transactions</div>
-                <div class="endpoint">POST /batch/transactions - Batch process transactions</div>
+                <p>Welcome to the Banking Service API. Endpoints are served under /v1, and unprefixed as a deprecated alias:</p>
+                <div class="endpoint">GET /v1/api/health - Health check endpoint</div>
+                <div class="endpoint">GET /v1/api/metrics - Service metrics</div>
+                <div class="endpoint">POST /v1/api/accounts - Create new account</div>
+                <div class="endpoint">GET /v1/api/accounts/{id} - Get account details</div>
+                <div class="endpoint">PUT /v1/api/accounts/{id}/deposit - Deposit money</div>
+                <div class="endpoint">PUT /v1/api/accounts/{id}/withdraw - Withdraw money</div>
+                <div class="endpoint">GET /v1/api/accounts/{id}/transactions - Get account transactions</div>
+                <div class="endpoint">POST /v1/api/transactions/batch - Batch process transactions</div>
             </body>
         </html>
     "#,
@@ -79,29 +70,18 @@ async fn root() -> Html<&'static str> {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Load environment variables first, they pick the log format and redaction
+    dotenv::dotenv().ok();
+
     // Initialize tracing with better formatting
-    tracing_subscriber::fmt()
-        .with_max_level(Level::INFO)
-        .with_target(false)
-        .with_thread_ids(true)
-        .with_file(true)
-        .with_line_number(true)
-        .init();
+    let logging_config = LoggingConfig::from_env();
+    infrastructure::logging::subscriber(&logging_config, std::io::stdout).init();
 
     info!("Starting high-performance banking service");
 
-    // Load environment variables
-    dotenv::dotenv().ok();
-
     // Initialize all services with background tasks
     let service_context = init_all_services().await?;
 
-    // Clone services once for router state
-    let router_state = (
-        service_context.account_service.clone(),
-        service_context.auth_service.clone(),
-    );
-
     // Clone for shutdown before checking tasks
     let service_context_for_shutdown = service_context.clone();
 
@@ -111,46 +91,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Err(e.into());
     }
 
-    // Build the router with optimized middleware stack
-    let app = Router::new()
-        // Auth operations
-        .route("/api/auth/register", post(web::handlers::register))
-        .route("/api/auth/login", post(web::handlers::login))
-        .route("/api/auth/logout", post(web::handlers::logout))
-        // Account operations
-        .route("/api/accounts", post(web::handlers::create_account))
-        .route("/api/accounts/{id}", get(web::handlers::get_account))
-        .route(
-            "/api/accounts/{id}/deposit",
-            post(web::handlers::deposit_money),
-        )
-        .route(
-            "/api/accounts/{id}/withdraw",
-            post(web::handlers::withdraw_money),
-        )
-        .route(
-            "/api/accounts/{id}/transactions",
-            get(web::handlers::get_account_transactions),
-        )
-        // Batch operations for high throughput
-        .route(
-            "/api/transactions/batch",
-            post(web::handlers::batch_transactions),
-        )
-        // Health and metrics
-        .route("/api/health", get(web::handlers::health_check))
-        .route("/api/metrics", get(web::handlers::metrics))
-        // Add optimized middleware stack
-        .layer(
-            ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
-                .layer(CompressionLayer::new())
-                .layer(CorsLayer::permissive())
-                .into_inner(),
-        )
-        .with_state(router_state)
-        // Serve static files as fallback
-        .fallback_service(ServeDir::new("static"));
+    // The same router the integration tests drive, with every API version and middleware
+    let app = create_router(
+        service_context_for_shutdown.account_service.clone(),
+        service_context_for_shutdown.auth_service.clone(),
+    );
 
     // Setup TCP listener with optimized settings
     let port = std::env::var("PORT")
@@ -159,22 +104,86 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .unwrap_or(3000);
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
 
-    let listener = TcpListener::bind(addr).await?;
-    configure_tcp_listener(&listener)?;
+    let server_config = ServerConfig::from_env();
+    let listener = web::server::bind(addr, &server_config)?;
 
     info!("Server running on {}", addr);
 
-    // Start the server with graceful shutdown
-    let server = axum::serve(listener, app);
-    let graceful = server.with_graceful_shutdown(shutdown_signal());
+    // Announce this instance to the cluster; retried so a Redis hiccup at boot isn't fatal
+    let scaling_manager = service_context_for_shutdown.scaling_manager.clone();
+    let instance = ServiceInstance {
+        id: Uuid::new_v4().to_string(),
+        host: std::env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string()),
+        port,
+        status: InstanceStatus::Starting,
+        metrics: InstanceMetrics::default(),
+        shard_assignments: Vec::new(),
+        last_heartbeat: Utc::now(),
+    };
+    let instance_id = instance.id.clone();
+    scaling_manager.register_instance_with_retry(instance).await?;
+
+    // Only take traffic from the load balancer once warmed up
+    let readiness = service_context_for_shutdown
+        .account_service
+        .readiness()
+        .clone();
+    let scaling_manager_for_readiness = scaling_manager.clone();
+    let instance_id_for_readiness = instance_id.clone();
+    tokio::spawn(async move {
+        readiness.wait_until_ready().await;
+        if let Err(e) = scaling_manager_for_readiness
+            .update_instance_status(&instance_id_for_readiness, InstanceStatus::Active)
+            .await
+        {
+            error!("Failed to mark instance active: {}", e);
+        }
+    });
+
+    // Serve until a shutdown signal; intake is stopped by the coordinator below
+    let (stop_intake, intake_stopped) = tokio::sync::oneshot::channel::<()>();
+    let mut server = tokio::spawn(web::server::serve(
+        listener,
+        app,
+        server_config,
+        async {
+            let _ = intake_stopped.await;
+        },
+    ));
 
-    if let Err(e) = graceful.await {
-        error!("Server error: {}", e);
-        return Err(e.into());
+    tokio::select! {
+        _ = shutdown_signal() => {}
+        result = &mut server => {
+            // The server only returns on its own when it fails
+            let e: Box<dyn std::error::Error> = match result {
+                Ok(Err(e)) => e.into(),
+                Err(e) => e.into(),
+                Ok(Ok(())) => "server stopped unexpectedly".into(),
+            };
+            error!("Server error: {}", e);
+            return Err(e);
+        }
     }
 
-    // Graceful shutdown of services
-    service_context_for_shutdown.shutdown().await;
+    let mut coordinator = ShutdownCoordinator::new(ShutdownConfig::from_env());
+    coordinator.add_step(ShutdownPhase::StopIntake, move || async move {
+        let _ = stop_intake.send(());
+        Ok(())
+    });
+    coordinator.add_step(ShutdownPhase::DrainInFlight, move || async move {
+        server.await??;
+        Ok(())
+    });
+    service_context_for_shutdown.register_shutdown_steps(&mut coordinator);
+    // Leave the cluster once this instance has stopped consuming
+    coordinator.add_step(ShutdownPhase::Deregister, move || async move {
+        scaling_manager.deregister_instance(&instance_id).await
+    });
+    coordinator.add_step(ShutdownPhase::FlushTracing, || async {
+        tokio::task::spawn_blocking(opentelemetry::global::shutdown_tracer_provider).await?;
+        Ok(())
+    });
+    coordinator.run().await;
     info!("Server shutdown complete");
 
     Ok(())
@@ -205,37 +214,3 @@ async fn shutdown_signal() {
 
     info!("Shutting down gracefully...");
 }
-
-fn configure_tcp_listener(listener: &TcpListener) -> Result<(), Box<dyn std::error::Error>> {
-    use libc::{setsockopt, IPPROTO_TCP, SOL_SOCKET, SO_REUSEADDR, SO_REUSEPORT, TCP_NODELAY};
-    use std::os::unix::io::AsRawFd;
-
-    let fd = listener.as_raw_fd();
-    let optval: libc::c_int = 1;
-
-    unsafe {
-        setsockopt(
-            fd,
-            SOL_SOCKET,
-            SO_REUSEADDR,
-            &optval as *const _ as *const libc::c_void,
-            std::mem::size_of_val(&optval) as libc::socklen_t,
-        );
-        setsockopt(
-            fd,
-            SOL_SOCKET,
-            SO_REUSEPORT,
-            &optval as *const _ as *const libc::c_void,
-            std::mem::size_of_val(&optval) as libc::socklen_t,
-        );
-        setsockopt(
-            fd,
-            IPPROTO_TCP,
-            TCP_NODELAY,
-            &optval as *const _ as *const libc::c_void,
-            std::mem::size_of_val(&optval) as libc::socklen_t,
-        );
-    }
-
-    Ok(())
-}
@@ -0,0 +1,155 @@
+use axum::{
+    extract::Request,
+    http::{header::ACCEPT, HeaderMap, HeaderValue, StatusCode, Uri},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use tower::Layer;
+
+/// Header set on responses served through the unversioned alias.
+pub const DEPRECATION_HEADER: &str = "deprecation";
+
+/// A version of the HTTP API. Each one is mounted under its own prefix, e.g.
+/// `/v1/api/accounts`; the unversioned paths are a temporary alias of `LEGACY_ALIAS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiVersion {
+    V1,
+}
+
+impl ApiVersion {
+    pub const ALL: &'static [ApiVersion] = &[ApiVersion::V1];
+    /// The version the unversioned paths serve.
+    pub const LEGACY_ALIAS: ApiVersion = ApiVersion::V1;
+
+    pub fn number(&self) -> u32 {
+        match self {
+            ApiVersion::V1 => 1,
+        }
+    }
+
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "/v1",
+        }
+    }
+
+    pub fn from_number(number: u32) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|version| version.number() == number)
+    }
+
+    /// The version `path` is mounted under and the path without its prefix, or `None`
+    /// for an unversioned path.
+    pub fn split_path(path: &str) -> Option<(Self, &str)> {
+        Self::ALL.iter().find_map(|version| {
+            let rest = path.strip_prefix(version.prefix())?;
+            (rest.is_empty() || rest.starts_with('/')).then_some((*version, rest))
+        })
+    }
+
+    /// Media type clients send in `Accept` to ask for this version on an unversioned path.
+    pub fn media_type(&self) -> String {
+        format!("application/vnd.banking.v{}+json", self.number())
+    }
+}
+
+/// `path` without its version prefix, if it has one.
+pub fn unversioned_path(path: &str) -> &str {
+    ApiVersion::split_path(path).map_or(path, |(_, rest)| rest)
+}
+
+/// The version number asked for with `application/vnd.banking.v<N>+json` in `Accept`, if
+/// any. The first such media type wins.
+fn requested_version(headers: &HeaderMap) -> Option<u32> {
+    let accept = headers.get(ACCEPT)?.to_str().ok()?;
+    accept.split(',').find_map(|range| {
+        range
+            .split(';')
+            .next()?
+            .trim()
+            .to_ascii_lowercase()
+            .strip_prefix("application/vnd.banking.v")?
+            .strip_suffix("+json")?
+            .parse()
+            .ok()
+    })
+}
+
+/// Routes an unversioned request to the version named in its `Accept` header, answering
+/// 406 for versions that don't exist. Versioned paths are left alone; the path decides.
+pub async fn select_api_version(mut request: Request, next: Next) -> Response {
+    if ApiVersion::split_path(request.uri().path()).is_some() {
+        return next.run(request).await;
+    }
+    let Some(number) = requested_version(request.headers()) else {
+        return next.run(request).await;
+    };
+    let Some(version) = ApiVersion::from_number(number) else {
+        return (
+            StatusCode::NOT_ACCEPTABLE,
+            format!("API version {} is not supported", number),
+        )
+            .into_response();
+    };
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+    match format!("{}{}", version.prefix(), path_and_query).parse::<Uri>() {
+        Ok(uri) => *request.uri_mut() = uri,
+        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    }
+    next.run(request).await
+}
+
+/// Marks responses of the unversioned alias as deprecated, so clients notice before it's
+/// removed.
+pub async fn mark_legacy_alias(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .insert(DEPRECATION_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+/// Wraps `router` so `select_api_version` runs before routing, where rewriting the path
+/// still picks the route.
+pub fn with_version_negotiation(router: Router) -> Router {
+    Router::new().fallback_service(middleware::from_fn(select_api_version).layer(router))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_path() {
+        assert_eq!(
+            ApiVersion::split_path("/v1/api/accounts"),
+            Some((ApiVersion::V1, "/api/accounts"))
+        );
+        assert_eq!(ApiVersion::split_path("/api/accounts"), None);
+        assert_eq!(ApiVersion::split_path("/v10/api/accounts"), None);
+        assert_eq!(unversioned_path("/v1/api/accounts"), "/api/accounts");
+        assert_eq!(unversioned_path("/api/accounts"), "/api/accounts");
+    }
+
+    #[test]
+    fn test_requested_version() {
+        let accept = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT, value.parse().unwrap());
+            requested_version(&headers)
+        };
+        assert_eq!(requested_version(&HeaderMap::new()), None);
+        assert_eq!(accept("application/json"), None);
+        assert_eq!(accept(&ApiVersion::V1.media_type()), Some(1));
+        assert_eq!(
+            accept("application/json, application/vnd.banking.v2+json;q=0.9"),
+            Some(2)
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{
+    application::AccountService,
+    infrastructure::{auth::Claims, authorization_audit::AuthorizationDecision},
+    web::api_version::unversioned_path,
+};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// A guard's decision, before the route it was made on is filled in.
+struct GuardDecision {
+    subject_id: Uuid,
+    subject: String,
+    resource: Option<String>,
+    required: String,
+    allowed: bool,
+    reason: String,
+}
+
+tokio::task_local! {
+    /// Decisions made by the guards of the request being handled.
+    static DECISIONS: Arc<Mutex<Vec<GuardDecision>>>;
+}
+
+/// Notes a guard's decision for `audit_authorization` to record once the handler has
+/// returned. `resource` defaults to the request path. Decisions made outside a request,
+/// e.g. in unit tests, aren't recorded.
+pub(crate) fn note_decision(
+    claims: &Claims,
+    resource: Option<String>,
+    required: &str,
+    allowed: bool,
+    reason: &str,
+) {
+    let _ = DECISIONS.try_with(|decisions| {
+        decisions.lock().unwrap().push(GuardDecision {
+            subject_id: claims.user_id,
+            subject: claims.sub.clone(),
+            resource,
+            required: required.to_string(),
+            allowed,
+            reason: reason.to_string(),
+        });
+    });
+}
+
+/// Records the authorization decisions the request's guards made with the service's
+/// authorization audit, against the route the request matched.
+pub async fn audit_authorization(
+    State(service): State<Arc<AccountService>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = unversioned_path(request.uri().path()).to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(path.clone(), |matched| {
+            unversioned_path(matched.as_str()).to_string()
+        });
+    let method = request.method().to_string();
+
+    let decisions = Arc::new(Mutex::new(Vec::new()));
+    let response = DECISIONS.scope(decisions.clone(), next.run(request)).await;
+
+    let decisions = std::mem::take(&mut *decisions.lock().unwrap());
+    for decision in decisions {
+        service
+            .authorization_audit()
+            .record(AuthorizationDecision {
+                subject_id: decision.subject_id,
+                subject: decision.subject,
+                method: method.clone(),
+                route: route.clone(),
+                resource: decision.resource.unwrap_or_else(|| path.clone()),
+                required: decision.required,
+                allowed: decision.allowed,
+                reason: decision.reason,
+            })
+            .await;
+    }
+    response
+}
@@ -1,5 +1,9 @@
+pub mod api_version;
+pub mod authorization_audit;
 pub mod handlers;
+pub mod pagination;
 pub mod routes;
+pub mod server;
 
 pub use handlers::*;
 pub use routes::*;
\ No newline at end of file
@@ -0,0 +1,112 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub const DEFAULT_PAGE_LIMIT: i64 = 50;
+pub const MAX_PAGE_LIMIT: i64 = 500;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PageError {
+    #[error("Invalid page cursor")]
+    InvalidCursor,
+}
+
+/// One page of a list response. Passing `next_cursor` back as `cursor` fetches the page
+/// after it; it is `None` on the last page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+    /// Items across all pages, only counted when requested with `include_total=true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_count: Option<u64>,
+}
+
+/// Pagination query parameters shared by the list endpoints.
+#[derive(Debug, Default, Deserialize)]
+pub struct PageParams {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+/// A page to fetch, resolved from `PageParams`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRequest {
+    pub offset: i64,
+    pub limit: i64,
+    pub include_total: bool,
+}
+
+impl PageParams {
+    /// Uses `default_limit` when no limit is given and clamps it to `1..=max_limit`.
+    pub fn resolve(&self, default_limit: i64, max_limit: i64) -> Result<PageRequest, PageError> {
+        let offset = match &self.cursor {
+            Some(cursor) => decode_cursor(cursor)?,
+            None => 0,
+        };
+        Ok(PageRequest {
+            offset,
+            limit: self.limit.unwrap_or(default_limit).clamp(1, max_limit),
+            include_total: self.include_total,
+        })
+    }
+}
+
+/// Cursors are opaque to clients; they currently hold the offset of the next page.
+fn encode_cursor(offset: i64) -> String {
+    URL_SAFE_NO_PAD.encode(format!("o:{}", offset))
+}
+
+fn decode_cursor(cursor: &str) -> Result<i64, PageError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(cursor)
+        .map_err(|_| PageError::InvalidCursor)?;
+    std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|decoded| decoded.strip_prefix("o:"))
+        .and_then(|offset| offset.parse().ok())
+        .filter(|offset: &i64| *offset >= 0)
+        .ok_or(PageError::InvalidCursor)
+}
+
+impl<T> Page<T> {
+    /// Builds a page from up to `limit + 1` items fetched at the request's offset. The
+    /// extra item isn't returned; it only shows there is another page.
+    pub fn from_lookahead(
+        mut items: Vec<T>,
+        request: &PageRequest,
+        total_count: Option<u64>,
+    ) -> Self {
+        let has_more = items.len() as i64 > request.limit;
+        items.truncate(request.limit as usize);
+        Self {
+            next_cursor: has_more.then(|| encode_cursor(request.offset + request.limit)),
+            items,
+            has_more,
+            total_count,
+        }
+    }
+
+    /// Takes the requested page out of a list that was loaded in full.
+    pub fn from_all(items: Vec<T>, request: &PageRequest) -> Self {
+        let total_count = items.len() as u64;
+        let items = items
+            .into_iter()
+            .skip(request.offset as usize)
+            .take(request.limit as usize + 1)
+            .collect();
+        Self::from_lookahead(items, request, request.include_total.then_some(total_count))
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+            has_more: self.has_more,
+            total_count: self.total_count,
+        }
+    }
+}
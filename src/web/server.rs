@@ -0,0 +1,117 @@
+use crate::infrastructure::config::ServerConfig;
+use axum::Router;
+use hyper_util::rt::{TokioIo, TokioTimer};
+use hyper_util::service::TowerToHyperService;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
+
+/// Binds the API listener with address and port reuse and the configured backlog.
+pub fn bind(addr: SocketAddr, config: &ServerConfig) -> std::io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(config.listen_backlog.min(i32::MAX as u32) as i32)?;
+    TcpListener::from_std(socket.into())
+}
+
+/// HTTP/1 connection settings derived from `config`.
+pub fn http1_builder(config: &ServerConfig) -> hyper::server::conn::http1::Builder {
+    let mut builder = hyper::server::conn::http1::Builder::new();
+    builder
+        .timer(TokioTimer::new())
+        .keep_alive(config.http_keep_alive)
+        // Runs while waiting for each request's headers, including idle keep-alive time
+        .header_read_timeout(config.keep_alive_idle_timeout);
+    builder
+}
+
+/// Serves `app` on `listener` until `shutdown` resolves, then stops accepting and waits
+/// up to `config.drain_timeout` for open connections to finish their in-flight requests.
+/// Connections still busy after that are closed, abandoning their requests.
+pub async fn serve<F>(
+    listener: TcpListener,
+    app: Router,
+    config: ServerConfig,
+    shutdown: F,
+) -> std::io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (signal_tx, signal_rx) = watch::channel(());
+    tokio::spawn(async move {
+        shutdown.await;
+        drop(signal_rx);
+    });
+    let mut connections = JoinSet::new();
+
+    loop {
+        let (stream, remote_addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    // Usually out of file descriptors; give connections a moment to close
+                    warn!("Failed to accept connection: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            },
+            _ = signal_tx.closed() => break,
+        };
+        if let Err(e) = stream.set_nodelay(config.tcp_nodelay) {
+            debug!("Failed to set TCP_NODELAY for {}: {}", remote_addr, e);
+        }
+
+        while connections.try_join_next().is_some() {}
+
+        let builder = http1_builder(&config);
+        let service = TowerToHyperService::new(app.clone());
+        let signal_tx = signal_tx.clone();
+        connections.spawn(async move {
+            let conn = builder
+                .serve_connection(TokioIo::new(stream), service)
+                .with_upgrades();
+            tokio::pin!(conn);
+            let mut draining = false;
+            loop {
+                tokio::select! {
+                    result = conn.as_mut() => {
+                        if let Err(e) = result {
+                            debug!("Connection from {} ended: {}", remote_addr, e);
+                        }
+                        break;
+                    }
+                    _ = signal_tx.closed(), if !draining => {
+                        draining = true;
+                        conn.as_mut().graceful_shutdown();
+                    }
+                }
+            }
+        });
+    }
+
+    drop(listener);
+    let drained = tokio::time::timeout(config.drain_timeout, async {
+        while connections.join_next().await.is_some() {}
+    })
+    .await;
+    if drained.is_err() {
+        // Idle connections close as soon as draining starts, so each one left is
+        // serving a request
+        warn!(
+            "Shutdown drain timed out after {:?}, abandoning {} in-flight requests",
+            config.drain_timeout,
+            connections.len()
+        );
+        connections.shutdown().await;
+    }
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    middleware,
+    routing::get,
+    Router,
+};
+use banking_es::{
+    infrastructure::{
+        auth::{Claims, TokenType, UserRole},
+        config::{AccessLogConfig, AccessLogFormat},
+        jwt_keys::{self, JwtKeyRing},
+    },
+    web::routes::{access_log, REQUEST_ID_HEADER},
+};
+use chrono::{Duration, Utc};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tower::ServiceExt;
+use tracing_subscriber::fmt::MakeWriter;
+use uuid::Uuid;
+
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedLogs {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+impl Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for CapturedLogs {
+    type Writer = CapturedLogs;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+fn app(format: AccessLogFormat) -> Router {
+    Router::new()
+        .route("/ok", get(|| async { "ok" }))
+        .route(
+            "/broken",
+            get(|| async { (StatusCode::INTERNAL_SERVER_ERROR, "broken") }),
+        )
+        .layer(middleware::from_fn_with_state(
+            AccessLogConfig {
+                enabled: true,
+                format,
+            },
+            access_log,
+        ))
+}
+
+fn token() -> String {
+    let ring = Arc::new(JwtKeyRing::new("access-log", "access_log_secret"));
+    jwt_keys::install(ring.clone());
+    let now = Utc::now();
+    ring.sign(&Claims {
+        sub: "alice".to_string(),
+        exp: (now + Duration::hours(1)).timestamp(),
+        iat: now.timestamp(),
+        roles: vec![UserRole::Customer],
+        token_type: TokenType::Access,
+        jti: Uuid::new_v4().to_string(),
+        company: "ACME".to_string(),
+        user_id: Uuid::new_v4(),
+    })
+    .unwrap()
+}
+
+fn capture() -> (CapturedLogs, tracing::subscriber::DefaultGuard) {
+    let logs = CapturedLogs::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(logs.clone())
+        .with_ansi(false)
+        .finish();
+    (logs, tracing::subscriber::set_default(subscriber))
+}
+
+#[tokio::test]
+async fn test_each_request_gets_one_json_line_with_its_fields() {
+    let token = token();
+    let (logs, _guard) = capture();
+
+    let response = app(AccessLogFormat::Json)
+        .oneshot(
+            Request::builder()
+                .uri("/ok?q=secret")
+                .header("Authorization", format!("Bearer {}", token))
+                .header(REQUEST_ID_HEADER, "req-123")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.headers()[REQUEST_ID_HEADER], "req-123");
+
+    let lines = logs.lines();
+    assert_eq!(lines.len(), 1, "{:?}", lines);
+    assert!(lines[0].contains(" INFO access_log: "), "{}", lines[0]);
+    let json: serde_json::Value =
+        serde_json::from_str(lines[0].split_once("access_log: ").unwrap().1).unwrap();
+    assert_eq!(json["method"], "GET");
+    assert_eq!(json["path"], "/ok");
+    assert_eq!(json["status"], 200);
+    assert_eq!(json["user"], "alice");
+    assert_eq!(json["request_id"], "req-123");
+    assert!(json["latency_ms"].as_f64().unwrap() >= 0.0);
+}
+
+#[tokio::test]
+async fn test_server_errors_are_logged_at_error_level_as_text() {
+    let (logs, _guard) = capture();
+
+    let response = app(AccessLogFormat::Text)
+        .oneshot(
+            Request::builder()
+                .uri("/broken")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    // Requests without an id get a generated one
+    let request_id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+    assert!(Uuid::parse_str(request_id).is_ok());
+
+    let lines = logs.lines();
+    assert_eq!(lines.len(), 1, "{:?}", lines);
+    assert!(
+        lines[0].contains(" ERROR access_log: GET /broken 500 "),
+        "{}",
+        lines[0]
+    );
+    assert!(
+        lines[0].ends_with(&format!("ms user=- request_id={}", request_id)),
+        "{}",
+        lines[0]
+    );
+}
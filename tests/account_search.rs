@@ -0,0 +1,220 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use banking_es::{
+    application::services::AccountService,
+    infrastructure::{
+        auth::{AuthConfig, AuthService, Claims, TokenType, UserRole},
+        cache_service::{CacheConfig, CacheService, CacheServiceTrait},
+        event_store::{EventStore, EventStoreTrait},
+        middleware::RequestMiddleware,
+        projections::{ProjectionStore, ProjectionStoreTrait},
+        redis_abstraction::RealRedisClient,
+        repository::{AccountRepository, AccountRepositoryTrait},
+        user_repository::UserRepository,
+    },
+    web::{handlers::AccountSearchResult, pagination::Page, routes::create_router},
+};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_JWT_SECRET: &str = "account_search_test_secret";
+
+async fn setup() -> (axum::Router, PgPool) {
+    std::env::set_var("JWT_SECRET", TEST_JWT_SECRET);
+
+    let pool = common::pool().await;
+
+    let event_store = Arc::new(EventStore::new(pool.clone())) as Arc<dyn EventStoreTrait + 'static>;
+    let repository =
+        Arc::new(AccountRepository::new(event_store)) as Arc<dyn AccountRepositoryTrait + 'static>;
+    let redis_client = redis::Client::open("redis://localhost:6379").unwrap();
+    let service = Arc::new(AccountService::new(
+        repository,
+        Arc::new(ProjectionStore::new(pool.clone())) as Arc<dyn ProjectionStoreTrait + 'static>,
+        Arc::new(CacheService::new(
+            RealRedisClient::new(redis_client.clone(), None),
+            CacheConfig::default(),
+        )) as Arc<dyn CacheServiceTrait + 'static>,
+        Arc::new(RequestMiddleware::default()),
+        100,
+    ));
+    let auth_service = Arc::new(AuthService::new(
+        Arc::new(redis_client),
+        AuthConfig {
+            jwt_secret: TEST_JWT_SECRET.to_string(),
+            refresh_token_secret: "account_search_refresh_secret".to_string(),
+            access_token_expiry: 3600,
+            refresh_token_expiry: 604800,
+            rate_limit_requests: 1000,
+            rate_limit_window: 60,
+            max_failed_attempts: 5,
+            lockout_duration_minutes: 30,
+        },
+        Arc::new(UserRepository::new(pool.clone())),
+    ));
+
+    (create_router(service, auth_service), pool)
+}
+
+fn token_for(role: UserRole, user_id: Uuid) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let claims = Claims {
+        sub: "account-search-test".to_string(),
+        exp: now + 3600,
+        iat: now,
+        roles: vec![role],
+        token_type: TokenType::Access,
+        jti: Uuid::new_v4().to_string(),
+        company: "banking-es".to_string(),
+        user_id,
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(TEST_JWT_SECRET.as_bytes()),
+    )
+    .unwrap()
+}
+
+async fn insert_projection(pool: &PgPool, owner_name: &str, owner_user_id: Option<Uuid>) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO account_projections (id, owner_name, balance, is_active, created_at, updated_at, owner_user_id)
+        VALUES ($1, $2, 0, true, NOW(), NOW(), $3)
+        "#,
+    )
+    .bind(id)
+    .bind(owner_name)
+    .bind(owner_user_id)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+async fn search(
+    router: &axum::Router,
+    token: &str,
+    query: &str,
+) -> (StatusCode, Option<Page<AccountSearchResult>>) {
+    let request = Request::builder()
+        .uri(format!("/api/accounts/search?{}", query))
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).ok())
+}
+
+/// A token unique to this run, so searches only see the rows the test inserted.
+fn unique_token() -> String {
+    format!("zq{}", &Uuid::new_v4().simple().to_string()[..10])
+}
+
+#[tokio::test]
+async fn test_exact_prefix_and_no_match_queries() {
+    let (router, pool) = setup().await;
+    let admin = token_for(UserRole::Admin, Uuid::new_v4());
+    let token = unique_token();
+
+    let exact = insert_projection(&pool, &token, None).await;
+    let prefix = insert_projection(&pool, &format!("{} Smith", token), None).await;
+    let substring = insert_projection(&pool, &format!("Jane {}", token), None).await;
+
+    let (status, results) = search(&router, &admin, &format!("q={}", token.to_uppercase())).await;
+    assert_eq!(status, StatusCode::OK);
+    let ids: Vec<Uuid> = results.unwrap().items.iter().map(|a| a.id).collect();
+    assert_eq!(ids, vec![exact, prefix, substring]);
+
+    let (_, results) = search(&router, &admin, &format!("q={}%20sm", token)).await;
+    let ids: Vec<Uuid> = results.unwrap().items.iter().map(|a| a.id).collect();
+    assert_eq!(ids, vec![prefix]);
+
+    let (status, results) = search(&router, &admin, &format!("q={}_nomatch", token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(results.unwrap().items.is_empty());
+
+    let (status, _) = search(&router, &admin, "q=%20").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_results_are_ordered_deterministically_and_paginated() {
+    let (router, pool) = setup().await;
+    let admin = token_for(UserRole::Admin, Uuid::new_v4());
+    let token = unique_token();
+
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        ids.push(insert_projection(&pool, &format!("{} Duplicate", token), None).await);
+    }
+    ids.sort();
+
+    // Follow the cursors two at a time; only the first page is asked for a total
+    let mut seen = Vec::new();
+    let mut query = format!("q={}&limit=2&include_total=true", token);
+    let mut pages = Vec::new();
+    loop {
+        let (status, results) = search(&router, &admin, &query).await;
+        assert_eq!(status, StatusCode::OK);
+        let page = results.unwrap();
+        seen.extend(page.items.iter().map(|a| a.id));
+        pages.push((page.items.len(), page.has_more, page.total_count));
+        match page.next_cursor {
+            Some(cursor) => query = format!("q={}&limit=2&cursor={}", token, cursor),
+            None => break,
+        }
+    }
+    assert_eq!(pages, vec![(2, true, Some(3)), (1, false, None)]);
+    assert_eq!(seen, ids);
+
+    let (status, _) = search(&router, &admin, &format!("q={}&cursor=bogus", token)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_customers_only_find_their_own_accounts() {
+    let (router, pool) = setup().await;
+    let customer_id = Uuid::new_v4();
+    let token = unique_token();
+
+    let own = insert_projection(&pool, &format!("{} Own", token), Some(customer_id)).await;
+    insert_projection(&pool, &format!("{} Other", token), Some(Uuid::new_v4())).await;
+
+    let customer = token_for(UserRole::Customer, customer_id);
+    let (_, results) = search(&router, &customer, &format!("q={}", token)).await;
+    let ids: Vec<Uuid> = results.unwrap().items.iter().map(|a| a.id).collect();
+    assert_eq!(ids, vec![own]);
+
+    let manager = token_for(UserRole::BankManager, Uuid::new_v4());
+    let (_, results) = search(&router, &manager, &format!("q={}", token)).await;
+    assert_eq!(results.unwrap().items.len(), 2);
+}
+
+#[tokio::test]
+async fn test_fuzzy_search_matches_misspelled_names() {
+    let (router, pool) = setup().await;
+    let admin = token_for(UserRole::Admin, Uuid::new_v4());
+    let token = unique_token();
+
+    let id = insert_projection(&pool, &format!("{} Johnson", token), None).await;
+    let misspelled = format!("{}%20Jonson", token);
+
+    let (_, results) = search(&router, &admin, &format!("q={}", misspelled)).await;
+    assert!(results.unwrap().items.is_empty());
+
+    let (_, results) = search(&router, &admin, &format!("q={}&fuzzy=true", misspelled)).await;
+    // Rows left behind by earlier runs can be similar too, but score lower
+    let ids: Vec<Uuid> = results.unwrap().items.iter().map(|a| a.id).collect();
+    assert_eq!(ids.first(), Some(&id));
+}
@@ -0,0 +1,93 @@
+use banking_es::{
+    domain::{Account, AccountRules},
+    infrastructure::projections::AccountProjection,
+    web::handlers::{AccountStatus, AccountSummary},
+};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+fn account() -> Account {
+    Account {
+        id: Uuid::new_v4(),
+        owner_name: "Summary Test".to_string(),
+        balance: Decimal::new(12050, 2),
+        is_active: true,
+        version: 4,
+        owner_user_id: Some(Uuid::new_v4()),
+        account_type: Default::default(),
+        rules: AccountRules {
+            overdraft_limit: Decimal::new(50, 0),
+            min_balance: Decimal::new(20, 0),
+            ..AccountRules::default()
+        },
+        metadata: Default::default(),
+        recent_withdrawals: Vec::new(),
+    }
+}
+
+#[test]
+fn test_summary_has_the_public_fields_only() {
+    let account = account();
+    let summary = AccountSummary::from(&account);
+    assert_eq!(summary.status, AccountStatus::Active);
+    assert_eq!(summary.available_balance, Some(Decimal::new(15050, 2)));
+
+    let json = serde_json::to_value(&summary).unwrap();
+    let mut fields: Vec<&str> = json
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    fields.sort();
+    assert_eq!(
+        fields,
+        vec![
+            "as_of",
+            "available_balance",
+            "balance",
+            "id",
+            "owner_name",
+            "status",
+            "version"
+        ]
+    );
+    assert_eq!(json["id"], account.id.to_string());
+    assert_eq!(json["balance"], 120.5);
+    assert_eq!(json["available_balance"], 150.5);
+    assert_eq!(json["status"], "active");
+    assert_eq!(json["version"], 4);
+    let body = json.to_string();
+    for internal in ["owner_user_id", "rules", "account_type", "is_active"] {
+        assert!(!body.contains(internal), "{} leaked: {}", internal, body);
+    }
+}
+
+#[test]
+fn test_closed_accounts_and_projections_summarize_consistently() {
+    let closed = Account {
+        is_active: false,
+        ..account()
+    };
+    let summary = AccountSummary::from(&closed);
+    assert_eq!(summary.status, AccountStatus::Closed);
+    assert_eq!(summary.available_balance, Some(Decimal::ZERO));
+
+    let updated_at = Utc::now();
+    let projection = AccountProjection {
+        id: closed.id,
+        owner_name: closed.owner_name.clone(),
+        balance: closed.balance,
+        is_active: false,
+        created_at: updated_at,
+        updated_at,
+        owner_user_id: closed.owner_user_id,
+    };
+    let json = serde_json::to_value(AccountSummary::from(&projection)).unwrap();
+    assert_eq!(json["status"], "closed");
+    assert_eq!(json["balance"], 120.5);
+    assert!(json["available_balance"].is_null() && json["version"].is_null());
+    assert_eq!(json["as_of"], serde_json::to_value(updated_at).unwrap());
+    assert!(json.get("owner_user_id").is_none() && json.get("created_at").is_none());
+}
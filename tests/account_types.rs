@@ -0,0 +1,207 @@
+use banking_es::{
+    domain::{
+        Account, AccountCommand, AccountError, AccountEvent, AccountType, VelocityCaps,
+        VelocityViolation,
+    },
+    infrastructure::{
+        config::AccountTemplatesConfig, event_store::EventStoreTrait,
+        in_memory_event_store::InMemoryEventStore, repository::AccountRepository,
+    },
+};
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Opens an account of the given type with its built-in template and applies the
+/// creation event.
+fn open(account_type: AccountType, initial_balance: Decimal) -> Account {
+    let account_id = Uuid::new_v4();
+    let mut account = Account {
+        id: account_id,
+        ..Account::default()
+    };
+    let events = account
+        .handle_command(&AccountCommand::CreateAccount {
+            account_id,
+            owner_name: "Account Types".to_string(),
+            initial_balance,
+            owner_user_id: None,
+            account_type,
+            rules: AccountTemplatesConfig::default().rules_for(account_type),
+        })
+        .unwrap();
+    for event in &events {
+        account.apply_event(event);
+    }
+    account
+}
+
+fn withdraw(account: &Account, amount: Decimal) -> Result<(), AccountError> {
+    account
+        .handle_command(&AccountCommand::WithdrawMoney {
+            account_id: account.id,
+            amount,
+        })
+        .map(|_| ())
+}
+
+#[test]
+fn test_savings_account_rejects_an_overdraft() {
+    let account = open(AccountType::Savings, Decimal::new(100, 0));
+
+    assert!(withdraw(&account, Decimal::new(100, 0)).is_ok());
+    assert!(matches!(
+        withdraw(&account, Decimal::new(101, 0)),
+        Err(AccountError::InsufficientFunds { available, .. }) if available == Decimal::new(100, 0)
+    ));
+}
+
+#[test]
+fn test_checking_account_overdraws_up_to_its_limit() {
+    let account = open(AccountType::Checking, Decimal::new(100, 0));
+    let limit = AccountType::Checking.default_rules().overdraft_limit;
+
+    assert_eq!(account.available_balance(), Decimal::new(100, 0) + limit);
+    assert!(withdraw(&account, Decimal::new(100, 0) + limit).is_ok());
+    assert!(matches!(
+        withdraw(&account, Decimal::new(101, 0) + limit),
+        Err(AccountError::InsufficientFunds { .. })
+    ));
+}
+
+#[test]
+fn test_withdrawal_above_the_per_transaction_cap_is_rejected() {
+    let account = open(AccountType::Savings, Decimal::new(100_000, 0));
+    let cap = AccountType::Savings
+        .default_rules()
+        .max_withdrawal_amount
+        .unwrap();
+
+    assert!(withdraw(&account, cap).is_ok());
+    assert!(matches!(
+        withdraw(&account, cap + Decimal::ONE),
+        Err(AccountError::LimitExceeded { limit, .. }) if limit == cap
+    ));
+}
+
+#[tokio::test]
+async fn test_rules_recorded_at_creation_survive_replay() {
+    let account_id = Uuid::new_v4();
+    let event_store = Arc::new(InMemoryEventStore::new());
+    let created = Account::default()
+        .handle_command(&AccountCommand::CreateAccount {
+            account_id,
+            owner_name: "Account Types".to_string(),
+            initial_balance: Decimal::ZERO,
+            owner_user_id: None,
+            account_type: AccountType::Credit,
+            rules: AccountType::Credit.default_rules(),
+        })
+        .unwrap();
+    event_store
+        .save_events(account_id, created, 0)
+        .await
+        .unwrap();
+
+    let repository = AccountRepository::new(event_store as Arc<dyn EventStoreTrait + 'static>);
+    let replayed = repository.get_by_id(account_id).await.unwrap().unwrap();
+    assert_eq!(replayed.account_type, AccountType::Credit);
+    assert_eq!(replayed.rules, AccountType::Credit.default_rules());
+    assert!(withdraw(&replayed, Decimal::new(1_000, 0)).is_ok());
+}
+
+/// Withdraws `amount` and applies the resulting events, as the service would.
+fn withdraw_and_apply(account: &mut Account, amount: Decimal) -> Result<(), AccountError> {
+    let events = account.handle_command(&AccountCommand::WithdrawMoney {
+        account_id: account.id,
+        amount,
+    })?;
+    for event in &events {
+        account.apply_event(event);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_savings_account_caps_withdrawals_per_window() {
+    let mut account = open(AccountType::Savings, Decimal::new(1_000, 0));
+    let caps = account.rules.withdrawal_velocity.clone().unwrap();
+    let max = caps.max_withdrawals.unwrap();
+
+    for _ in 0..max {
+        withdraw_and_apply(&mut account, Decimal::ONE).unwrap();
+    }
+    assert!(matches!(
+        withdraw(&account, Decimal::ONE),
+        Err(AccountError::VelocityLimitExceeded {
+            violation: VelocityViolation::TooManyWithdrawals { max: m },
+            window_secs,
+        }) if m == max && window_secs == caps.window_secs
+    ));
+}
+
+#[test]
+fn test_credit_account_caps_the_amount_withdrawn_per_window() {
+    let mut account = open(AccountType::Credit, Decimal::ZERO);
+    let max = account
+        .rules
+        .withdrawal_velocity
+        .clone()
+        .unwrap()
+        .max_amount
+        .unwrap();
+    let half = max / Decimal::TWO;
+
+    withdraw_and_apply(&mut account, half).unwrap();
+    withdraw_and_apply(&mut account, half).unwrap();
+    assert!(matches!(
+        withdraw(&account, Decimal::ONE),
+        Err(AccountError::VelocityLimitExceeded {
+            violation: VelocityViolation::AmountAboveMaximum { total, .. },
+            ..
+        }) if total == max + Decimal::ONE
+    ));
+}
+
+#[test]
+fn test_withdrawals_outside_the_window_do_not_count() {
+    let caps = VelocityCaps {
+        window_secs: 60,
+        max_withdrawals: Some(1),
+        max_amount: None,
+    };
+    let now = Utc::now();
+    let earlier = [(now - Duration::seconds(61), Decimal::ONE)];
+
+    assert!(caps.check(&earlier, now, Decimal::ONE).is_ok());
+    assert!(caps
+        .check(
+            &[(now - Duration::seconds(59), Decimal::ONE)],
+            now,
+            Decimal::ONE
+        )
+        .is_err());
+}
+
+#[test]
+fn test_withdrawals_recorded_without_a_time_do_not_count() {
+    let mut account = open(AccountType::Savings, Decimal::new(1_000, 0));
+    let max = account
+        .rules
+        .withdrawal_velocity
+        .as_ref()
+        .and_then(|caps| caps.max_withdrawals)
+        .unwrap();
+    for _ in 0..max {
+        account.apply_event(&AccountEvent::MoneyWithdrawn {
+            account_id: account.id,
+            amount: Decimal::ONE,
+            transaction_id: Uuid::new_v4(),
+            withdrawn_at: None,
+        });
+    }
+
+    assert!(account.recent_withdrawals.is_empty());
+    assert!(withdraw(&account, Decimal::ONE).is_ok());
+}
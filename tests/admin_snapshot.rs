@@ -0,0 +1,276 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use banking_es::{
+    application::services::AccountService,
+    domain::AccountEvent,
+    infrastructure::{
+        auth::{AuthConfig, AuthService, Claims, TokenType, UserRole},
+        cache_service::{CacheConfig, CacheService, CacheServiceTrait},
+        event_store::{EventStore, EventStoreTrait},
+        middleware::RequestMiddleware,
+        projections::{ProjectionStore, ProjectionStoreTrait},
+        redis_abstraction::RealRedisClient,
+        repository::{AccountRepository, AccountRepositoryTrait},
+        user_repository::UserRepository,
+    },
+    web::{handlers::SnapshotResponse, routes::create_router},
+};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_JWT_SECRET: &str = "admin_snapshot_test_secret";
+
+struct TestApp {
+    router: axum::Router,
+    event_store: Arc<EventStore>,
+    repository: Arc<AccountRepository>,
+    pool: PgPool,
+}
+
+async fn setup() -> TestApp {
+    // Claims are decoded with the key read from JWT_SECRET on first use
+    std::env::set_var("JWT_SECRET", TEST_JWT_SECRET);
+
+    let pool = common::pool().await;
+
+    let event_store = Arc::new(EventStore::new(pool.clone()));
+    let repository = Arc::new(AccountRepository::new(
+        event_store.clone() as Arc<dyn EventStoreTrait + 'static>
+    ));
+    let redis_client = redis::Client::open("redis://localhost:6379").unwrap();
+    let cache_service = Arc::new(CacheService::new(
+        RealRedisClient::new(redis_client.clone(), None),
+        CacheConfig::default(),
+    )) as Arc<dyn CacheServiceTrait + 'static>;
+    let projections =
+        Arc::new(ProjectionStore::new(pool.clone())) as Arc<dyn ProjectionStoreTrait + 'static>;
+    let service = Arc::new(AccountService::new(
+        repository.clone() as Arc<dyn AccountRepositoryTrait + 'static>,
+        projections,
+        cache_service,
+        Arc::new(RequestMiddleware::default()),
+        100,
+    ));
+    let auth_service = Arc::new(AuthService::new(
+        Arc::new(redis_client),
+        AuthConfig {
+            jwt_secret: TEST_JWT_SECRET.to_string(),
+            refresh_token_secret: "admin_snapshot_refresh_secret".to_string(),
+            access_token_expiry: 3600,
+            refresh_token_expiry: 604800,
+            rate_limit_requests: 1000,
+            rate_limit_window: 60,
+            max_failed_attempts: 5,
+            lockout_duration_minutes: 30,
+        },
+        Arc::new(UserRepository::new(pool.clone())),
+    ));
+
+    TestApp {
+        router: create_router(service, auth_service),
+        event_store,
+        repository,
+        pool,
+    }
+}
+
+fn token_for(roles: Vec<UserRole>) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let claims = Claims {
+        sub: "snapshot-test".to_string(),
+        exp: now + 3600,
+        iat: now,
+        roles,
+        token_type: TokenType::Access,
+        jti: Uuid::new_v4().to_string(),
+        company: "banking-es".to_string(),
+        user_id: Uuid::new_v4(),
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(TEST_JWT_SECRET.as_bytes()),
+    )
+    .unwrap()
+}
+
+/// Inserts an account's events directly so the test doesn't depend on the batching writer.
+async fn seed_account(pool: &PgPool, deposits: &[i64]) -> Uuid {
+    let account_id = Uuid::new_v4();
+    let mut events = vec![AccountEvent::AccountCreated {
+        account_id,
+        owner_name: "Snapshot Test".to_string(),
+        initial_balance: Decimal::new(100, 0),
+        owner_user_id: None,
+        account_type: Default::default(),
+        rules: Default::default(),
+    }];
+    events.extend(deposits.iter().map(|amount| AccountEvent::MoneyDeposited {
+        account_id,
+        amount: Decimal::new(*amount, 0),
+        transaction_id: Uuid::new_v4(),
+    }));
+
+    for (i, event) in events.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO events (id, aggregate_id, event_type, event_data, version, timestamp) \
+             VALUES ($1, $2, $3, $4, $5, '2025-06-15T00:00:00Z')",
+        )
+        .bind(Uuid::new_v4())
+        .bind(account_id)
+        .bind(event.event_type())
+        .bind(serde_json::to_value(event).unwrap())
+        .bind(i as i64 + 1)
+        .execute(pool)
+        .await
+        .expect("Failed to seed event");
+    }
+    account_id
+}
+
+async fn cleanup(pool: &PgPool, account_id: Uuid) {
+    let _ = sqlx::query("DELETE FROM events WHERE aggregate_id = $1")
+        .bind(account_id)
+        .execute(pool)
+        .await;
+    let _ = sqlx::query("DELETE FROM snapshots WHERE aggregate_id = $1")
+        .bind(account_id)
+        .execute(pool)
+        .await;
+}
+
+fn post(uri: &str, token: &str, body: &str) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_snapshot_route_creates_snapshot_used_by_reads() {
+    let app = setup().await;
+    let account_id = seed_account(&app.pool, &[50, 25]).await;
+
+    let response = app
+        .router
+        .clone()
+        .oneshot(post(
+            &format!("/admin/accounts/{}/snapshot", account_id),
+            &token_for(vec![UserRole::Admin]),
+            "",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let snapshot: SnapshotResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(snapshot.account_id, account_id);
+    assert_eq!(snapshot.snapshot_version, 3);
+
+    // Drop the snapshotted events: reads can now only succeed by starting from the snapshot.
+    sqlx::query("DELETE FROM events WHERE aggregate_id = $1 AND version <= $2")
+        .bind(account_id)
+        .bind(snapshot.snapshot_version)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let account = app
+        .repository
+        .get_by_id(account_id)
+        .await
+        .unwrap()
+        .expect("Account should be rebuilt from its snapshot");
+    assert_eq!(account.balance, Decimal::new(175, 0));
+
+    // A fresh store has an empty snapshot cache, so this read goes to the snapshots table.
+    let fresh_store = EventStore::new(app.pool.clone());
+    let account = fresh_store
+        .get_account(account_id)
+        .await
+        .unwrap()
+        .expect("Account should be rebuilt from its stored snapshot");
+    assert_eq!(account.balance, Decimal::new(175, 0));
+    assert!(app
+        .event_store
+        .get_snapshot(account_id)
+        .await
+        .unwrap()
+        .is_some());
+
+    cleanup(&app.pool, account_id).await;
+}
+
+#[tokio::test]
+async fn test_bulk_snapshot_route_snapshots_accounts_above_threshold() {
+    let app = setup().await;
+    let busy_account = seed_account(&app.pool, &[10, 10, 10, 10]).await;
+    let quiet_account = seed_account(&app.pool, &[]).await;
+
+    let response = app
+        .router
+        .clone()
+        .oneshot(post(
+            "/admin/snapshots",
+            &token_for(vec![UserRole::Admin]),
+            r#"{"min_event_count": 3}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let snapshotted: Vec<SnapshotResponse> =
+        serde_json::from_value(body["snapshots"].clone()).unwrap();
+
+    assert!(snapshotted
+        .iter()
+        .any(|s| s.account_id == busy_account && s.snapshot_version == 5));
+    assert!(!snapshotted.iter().any(|s| s.account_id == quiet_account));
+
+    cleanup(&app.pool, busy_account).await;
+    cleanup(&app.pool, quiet_account).await;
+}
+
+#[tokio::test]
+async fn test_snapshot_routes_require_admin() {
+    let app = setup().await;
+    let token = token_for(vec![UserRole::Customer]);
+
+    let response = app
+        .router
+        .clone()
+        .oneshot(post(
+            &format!("/admin/accounts/{}/snapshot", Uuid::new_v4()),
+            &token,
+            "",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .router
+        .clone()
+        .oneshot(post(
+            "/admin/snapshots",
+            &token,
+            r#"{"min_event_count": 0}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::{
+        event_store::{EventStore, EventStoreTrait},
+        in_memory_event_store::InMemoryEventStore,
+    },
+};
+use rust_decimal::Decimal;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::Row;
+use uuid::Uuid;
+
+fn created(account_id: Uuid) -> AccountEvent {
+    AccountEvent::AccountCreated {
+        account_id,
+        owner_name: "Aggregate Stats Test".to_string(),
+        initial_balance: Decimal::new(100, 0),
+        owner_user_id: None,
+        account_type: Default::default(),
+        rules: Default::default(),
+    }
+}
+
+fn deposit(account_id: Uuid) -> AccountEvent {
+    AccountEvent::MoneyDeposited {
+        account_id,
+        amount: Decimal::new(25, 0),
+        transaction_id: Uuid::new_v4(),
+    }
+}
+
+/// Saves an account with `deposits` deposits after its creation event.
+async fn seed(store: &dyn EventStoreTrait, deposits: usize) -> Uuid {
+    let account_id = Uuid::new_v4();
+    let mut events = vec![created(account_id)];
+    events.extend((0..deposits).map(|_| deposit(account_id)));
+    store.save_events(account_id, events, 0).await.unwrap();
+    account_id
+}
+
+#[tokio::test]
+async fn test_aggregate_stats_match_the_stored_events() {
+    let database_url = common::database_url();
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+    let store = EventStore::new(pool.clone());
+
+    let account_id = seed(&store, 3).await;
+    let stats = store.aggregate_stats(account_id).await.unwrap().unwrap();
+    let events = store.get_events(account_id, None).await.unwrap();
+    assert_eq!(stats.aggregate_id, account_id);
+    assert_eq!(stats.event_count, 4);
+    assert_eq!(stats.first_at, events.first().unwrap().occurred_at);
+    assert_eq!(stats.last_at, events.last().unwrap().occurred_at);
+
+    let row_sizes: Vec<i32> = sqlx::query(
+        "SELECT octet_length(event_data::text) + octet_length(metadata::text) AS size
+         FROM events WHERE aggregate_id = $1",
+    )
+    .bind(account_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap()
+    .iter()
+    .map(|row| row.get("size"))
+    .collect();
+    assert_eq!(row_sizes.len(), 4);
+    assert_eq!(
+        stats.total_bytes,
+        row_sizes.iter().map(|size| *size as i64).sum::<i64>()
+    );
+
+    // A later event moves only the count, size and last timestamp
+    store
+        .save_events(account_id, vec![deposit(account_id)], 4)
+        .await
+        .unwrap();
+    let grown = store.aggregate_stats(account_id).await.unwrap().unwrap();
+    assert_eq!(grown.event_count, 5);
+    assert!(grown.total_bytes > stats.total_bytes);
+    assert!(grown.last_at >= stats.last_at);
+    assert_eq!(grown.first_at, stats.first_at);
+
+    assert!(store
+        .aggregate_stats(Uuid::new_v4())
+        .await
+        .unwrap()
+        .is_none());
+
+    let top = store.top_aggregates_by_event_count(3).await.unwrap();
+    assert!(!top.is_empty() && top.len() <= 3);
+    assert!(top.windows(2).all(|w| w[0].event_count >= w[1].event_count));
+    assert!(top[0].event_count >= grown.event_count);
+}
+
+#[tokio::test]
+async fn test_top_aggregates_are_ordered_by_event_count() {
+    let store = InMemoryEventStore::new();
+    let small = seed(&store, 1).await;
+    let large = seed(&store, 6).await;
+    let medium = seed(&store, 3).await;
+
+    let top = store.top_aggregates_by_event_count(2).await.unwrap();
+    let ids: Vec<Uuid> = top.iter().map(|stats| stats.aggregate_id).collect();
+    assert_eq!(ids, vec![large, medium]);
+    assert_eq!(top[0].event_count, 7);
+    assert_eq!(top[1].event_count, 4);
+
+    let stats = store.aggregate_stats(small).await.unwrap().unwrap();
+    let events = store.get_events(small, None).await.unwrap();
+    let expected_bytes: usize = events
+        .iter()
+        .map(|e| e.event_data.to_string().len() + serde_json::to_string(&e.metadata).unwrap().len())
+        .sum();
+    assert_eq!(stats.event_count, 2);
+    assert_eq!(stats.total_bytes, expected_bytes as i64);
+    assert!(stats.first_at <= stats.last_at);
+    assert_eq!(
+        store.top_aggregates_by_event_count(10).await.unwrap().len(),
+        3
+    );
+}
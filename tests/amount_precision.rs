@@ -0,0 +1,143 @@
+mod common;
+
+use banking_es::{
+    application::services::AccountService,
+    domain::{Account, AccountCommand, AccountError, AccountEvent, AmountPrecision, RoundingMode},
+    infrastructure::{
+        cache_service::{CacheConfig, CacheService, CacheServiceTrait},
+        event_store::EventStoreTrait,
+        in_memory_event_store::InMemoryEventStore,
+        middleware::RequestMiddleware,
+        projections::{ProjectionStore, ProjectionStoreTrait},
+        redis_abstraction::RealRedisClient,
+        repository::{AccountRepository, AccountRepositoryTrait},
+    },
+};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+#[test]
+fn test_sub_cent_amount_is_rejected_by_default() {
+    let precision = AmountPrecision::default();
+
+    assert!(matches!(
+        precision.normalize(dec("10.005")),
+        Err(AccountError::ExcessPrecision { scale: 2, .. })
+    ));
+    // Trailing zeros aren't extra precision
+    assert_eq!(precision.normalize(dec("10.500")).unwrap(), dec("10.50"));
+    assert_eq!(precision.normalize(dec("10")).unwrap().scale(), 2);
+    // Amounts parsed from JSON numbers keep their short form
+    assert_eq!(
+        precision
+            .normalize(Decimal::from_f64(100.1).unwrap())
+            .unwrap(),
+        dec("100.10")
+    );
+}
+
+#[test]
+fn test_half_even_rounding_when_enabled() {
+    let precision = AmountPrecision {
+        scale: 2,
+        rounding: RoundingMode::HalfEven,
+    };
+
+    assert_eq!(precision.normalize(dec("1.005")).unwrap(), dec("1.00"));
+    assert_eq!(precision.normalize(dec("1.015")).unwrap(), dec("1.02"));
+    assert_eq!(precision.normalize(dec("1.0051")).unwrap(), dec("1.01"));
+    assert_eq!(precision.normalize(dec("-2.345")).unwrap(), dec("-2.34"));
+}
+
+async fn service(precision: AmountPrecision) -> (AccountService, Arc<InMemoryEventStore>) {
+    let pool = common::pool().await;
+
+    let event_store = Arc::new(InMemoryEventStore::new());
+    let repository = Arc::new(AccountRepository::new(
+        event_store.clone() as Arc<dyn EventStoreTrait + 'static>
+    ));
+    let redis_client = redis::Client::open("redis://localhost:6379").unwrap();
+    let service = AccountService::new(
+        repository as Arc<dyn AccountRepositoryTrait + 'static>,
+        Arc::new(ProjectionStore::new(pool)) as Arc<dyn ProjectionStoreTrait + 'static>,
+        Arc::new(CacheService::new(
+            RealRedisClient::new(redis_client, None),
+            CacheConfig::default(),
+        )) as Arc<dyn CacheServiceTrait + 'static>,
+        Arc::new(RequestMiddleware::default()),
+        100,
+    )
+    .with_amount_precision(precision);
+    (service, event_store)
+}
+
+async fn open_account(event_store: &InMemoryEventStore) -> Uuid {
+    let account_id = Uuid::new_v4();
+    event_store
+        .save_events(
+            account_id,
+            vec![AccountEvent::AccountCreated {
+                account_id,
+                owner_name: "Amount Precision".to_string(),
+                initial_balance: dec("100"),
+                owner_user_id: None,
+                account_type: Default::default(),
+                rules: Default::default(),
+            }],
+            0,
+        )
+        .await
+        .unwrap();
+    account_id
+}
+
+#[test]
+fn test_events_carry_the_normalized_amount() {
+    let precision = AmountPrecision {
+        scale: 2,
+        rounding: RoundingMode::HalfEven,
+    };
+    let account_id = Uuid::new_v4();
+    let account = Account {
+        id: account_id,
+        is_active: true,
+        ..Account::default()
+    };
+
+    let events = account
+        .handle_command(&AccountCommand::DepositMoney {
+            account_id,
+            amount: precision.normalize(dec("12.345")).unwrap(),
+        })
+        .unwrap();
+    assert!(matches!(
+        &events[..],
+        [AccountEvent::MoneyDeposited { amount, .. }] if amount.to_string() == "12.34"
+    ));
+}
+
+#[tokio::test]
+async fn test_rejected_amount_stores_nothing() {
+    let (service, event_store) = service(AmountPrecision::default()).await;
+    let account_id = open_account(&event_store).await;
+
+    assert!(matches!(
+        service.withdraw_money(account_id, dec("0.001")).await,
+        Err(AccountError::ExcessPrecision { .. })
+    ));
+    assert_eq!(
+        event_store
+            .get_events(account_id, None)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
@@ -0,0 +1,137 @@
+use banking_es::{
+    domain::{AccountCommand, AccountError},
+    infrastructure::{
+        in_memory_event_store::InMemoryEventStore,
+        repository::{AccountRepository, AccountRepositoryTrait},
+    },
+};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn repository() -> AccountRepository {
+    AccountRepository::new(Arc::new(InMemoryEventStore::new()))
+}
+
+fn create(account_id: Uuid) -> AccountCommand {
+    AccountCommand::CreateAccount {
+        account_id,
+        owner_name: "Apply Command".to_string(),
+        initial_balance: Decimal::new(100, 0),
+        owner_user_id: None,
+        account_type: Default::default(),
+        rules: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_different_commands_go_through_the_same_path() {
+    let repository = repository();
+    let account_id = Uuid::new_v4();
+
+    let account = repository
+        .apply_command(account_id, &create(account_id), Some(0))
+        .await
+        .unwrap();
+    assert_eq!(
+        (account.balance, account.version),
+        (Decimal::new(100, 0), 1)
+    );
+
+    let deposit = AccountCommand::DepositMoney {
+        account_id,
+        amount: Decimal::new(25, 0),
+    };
+    let account = repository
+        .apply_command(account_id, &deposit, Some(1))
+        .await
+        .unwrap();
+    assert_eq!(
+        (account.balance, account.version),
+        (Decimal::new(125, 0), 2)
+    );
+
+    let close = AccountCommand::CloseAccount {
+        account_id,
+        reason: "done".to_string(),
+    };
+    let account = repository
+        .apply_command(account_id, &close, None)
+        .await
+        .unwrap();
+    assert!(!account.is_active);
+    assert_eq!(account.version, 3);
+
+    // The stored state matches what was returned, and the domain rules still apply
+    let stored = repository.get_by_id(account_id).await.unwrap().unwrap();
+    assert_eq!((stored.balance, stored.version), (account.balance, 3));
+    let error = repository
+        .apply_command(account_id, &deposit, None)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(AccountError::from(error), AccountError::AccountClosed),
+        "deposit on a closed account should fail"
+    );
+}
+
+#[tokio::test]
+async fn test_expected_version_and_existence_are_checked() {
+    let repository = repository();
+    let account_id = Uuid::new_v4();
+    let deposit = AccountCommand::DepositMoney {
+        account_id,
+        amount: Decimal::new(5, 0),
+    };
+
+    let error = repository
+        .apply_command(account_id, &deposit, None)
+        .await
+        .unwrap_err();
+    assert!(matches!(AccountError::from(error), AccountError::NotFound));
+
+    repository
+        .apply_command(account_id, &create(account_id), None)
+        .await
+        .unwrap();
+    let error = repository
+        .apply_command(account_id, &create(account_id), None)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        AccountError::from(error),
+        AccountError::AlreadyExists
+    ));
+    let error = repository
+        .apply_command(account_id, &deposit, Some(2))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        AccountError::from(error),
+        AccountError::VersionConflict {
+            expected: 2,
+            actual: 1
+        }
+    ));
+}
+
+#[tokio::test]
+async fn test_unversioned_commands_retry_past_concurrent_writes() {
+    let repository = repository();
+    let account_id = Uuid::new_v4();
+    repository
+        .apply_command(account_id, &create(account_id), None)
+        .await
+        .unwrap();
+
+    let (deposited, withdrawn) = tokio::join!(
+        AccountRepositoryTrait::deposit_money(&repository, account_id, Decimal::new(30, 0)),
+        AccountRepositoryTrait::withdraw_money(&repository, account_id, Decimal::new(10, 0))
+    );
+    deposited.unwrap();
+    withdrawn.unwrap();
+
+    let account = repository.get_by_id(account_id).await.unwrap().unwrap();
+    assert_eq!(account.balance, Decimal::new(120, 0));
+    assert_eq!(account.version, 3);
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use banking_es::{
+    application::services::{AccountService, ReadSource},
+    domain::AccountEvent,
+    infrastructure::{
+        cache_service::{CacheConfig, CacheService, CacheServiceTrait},
+        event_store::EventStoreTrait,
+        in_memory_event_store::InMemoryEventStore,
+        middleware::RequestMiddleware,
+        projections::{ProjectionStore, ProjectionStoreTrait},
+        redis_abstraction::RealRedisClient,
+        repository::{AccountRepository, AccountRepositoryTrait},
+    },
+};
+use rust_decimal::Decimal;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use uuid::Uuid;
+
+async fn setup() -> (
+    AccountService,
+    Arc<InMemoryEventStore>,
+    Arc<ProjectionStore>,
+) {
+    let pool = common::pool().await;
+
+    let event_store = Arc::new(InMemoryEventStore::new());
+    let projections = Arc::new(ProjectionStore::new(pool));
+    let repository = Arc::new(AccountRepository::new(
+        event_store.clone() as Arc<dyn EventStoreTrait + 'static>
+    ));
+    let redis_client = redis::Client::open("redis://localhost:6379").unwrap();
+    let service = AccountService::new(
+        repository as Arc<dyn AccountRepositoryTrait + 'static>,
+        projections.clone() as Arc<dyn ProjectionStoreTrait + 'static>,
+        Arc::new(CacheService::new(
+            RealRedisClient::new(redis_client, None),
+            CacheConfig::default(),
+        )) as Arc<dyn CacheServiceTrait + 'static>,
+        Arc::new(RequestMiddleware::default()),
+        100,
+    );
+    (service, event_store, projections)
+}
+
+/// Stores an account's creation event, balance 100.
+async fn store_account(event_store: &InMemoryEventStore) -> Uuid {
+    let account_id = Uuid::new_v4();
+    event_store
+        .save_events(
+            account_id,
+            vec![AccountEvent::AccountCreated {
+                account_id,
+                owner_name: "Bulk Read".to_string(),
+                initial_balance: Decimal::new(100, 0),
+                owner_user_id: None,
+                account_type: Default::default(),
+                rules: Default::default(),
+            }],
+            0,
+        )
+        .await
+        .unwrap();
+    account_id
+}
+
+/// Projects an account with a balance the event store doesn't have, so the source of
+/// a read shows in its balance.
+async fn project_account(projections: &ProjectionStore, account_id: Uuid) {
+    projections
+        .apply_events_with_checkpoint(
+            &format!("bulk-read-test-{}", account_id),
+            &[(
+                1,
+                AccountEvent::AccountCreated {
+                    account_id,
+                    owner_name: "Bulk Read".to_string(),
+                    initial_balance: Decimal::new(250, 0),
+                    owner_user_id: None,
+                    account_type: Default::default(),
+                    rules: Default::default(),
+                },
+            )],
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_bulk_read_replays_only_unprojected_accounts() {
+    let (service, event_store, projections) = setup().await;
+    let projected = [
+        store_account(&event_store).await,
+        store_account(&event_store).await,
+    ];
+    for account_id in projected {
+        project_account(&projections, account_id).await;
+    }
+    let unprojected = [
+        store_account(&event_store).await,
+        store_account(&event_store).await,
+    ];
+    let unknown = Uuid::new_v4();
+
+    let ids: Vec<Uuid> = projected
+        .iter()
+        .chain(unprojected.iter())
+        .copied()
+        .chain([unknown])
+        .collect();
+    let accounts = service.get_accounts(&ids).await.unwrap();
+
+    assert_eq!(accounts.len(), 4);
+    for account_id in projected {
+        let (account, source) = &accounts[&account_id];
+        assert_eq!(*source, ReadSource::Projection);
+        assert_eq!(account.balance, Decimal::new(250, 0));
+    }
+    for account_id in unprojected {
+        let (account, source) = &accounts[&account_id];
+        assert_eq!(*source, ReadSource::EventStore);
+        assert_eq!(account.balance, Decimal::new(100, 0));
+    }
+    assert!(!accounts.contains_key(&unknown));
+
+    let metrics = service.get_metrics();
+    assert_eq!(metrics.bulk_projection_reads.load(Ordering::Relaxed), 2);
+    assert_eq!(metrics.bulk_replay_reads.load(Ordering::Relaxed), 2);
+}
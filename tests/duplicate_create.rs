@@ -0,0 +1,109 @@
+mod common;
+
+use banking_es::{
+    domain::{Account, AccountError, AccountEvent},
+    infrastructure::{
+        event_store::{EventStore, EventStoreError},
+        in_memory_event_store::InMemoryEventStore,
+        repository::{AccountRepository, AccountRepositoryTrait},
+    },
+};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn created(account_id: Uuid, owner_name: &str) -> AccountEvent {
+    AccountEvent::AccountCreated {
+        account_id,
+        owner_name: owner_name.to_string(),
+        initial_balance: Decimal::new(100, 0),
+        owner_user_id: None,
+        account_type: Default::default(),
+        rules: Default::default(),
+    }
+}
+
+async fn create(
+    repository: &AccountRepository,
+    account_id: Uuid,
+    owner_name: &str,
+) -> Result<(), AccountError> {
+    let account = Account {
+        id: account_id,
+        ..Account::default()
+    };
+    AccountRepositoryTrait::save(repository, &account, vec![created(account_id, owner_name)])
+        .await
+        .map_err(AccountError::from)
+}
+
+#[tokio::test]
+async fn test_racing_creates_for_the_same_account_leave_one_winner() {
+    let pool = common::pool().await;
+    let repository = AccountRepository::new(Arc::new(EventStore::new(pool)));
+
+    for _ in 0..5 {
+        let account_id = Uuid::new_v4();
+        let (first, second) = tokio::join!(
+            create(&repository, account_id, "First"),
+            create(&repository, account_id, "Second")
+        );
+        let (winner, loser) = match (&first, &second) {
+            (Ok(()), Err(e)) => ("First", e),
+            (Err(e), Ok(())) => ("Second", e),
+            _ => panic!(
+                "expected exactly one create to succeed: {:?} {:?}",
+                first, second
+            ),
+        };
+        assert!(matches!(loser, AccountError::AlreadyExists), "{:?}", loser);
+
+        // Only the winner's event is stored, and the account carries on from it
+        let account = repository.get_by_id(account_id).await.unwrap().unwrap();
+        assert_eq!(account.owner_name, winner);
+        assert_eq!(account.version, 1);
+        assert!(matches!(
+            create(&repository, account_id, "Late").await,
+            Err(AccountError::AlreadyExists)
+        ));
+        let deposit = AccountEvent::MoneyDeposited {
+            account_id,
+            amount: Decimal::new(5, 0),
+            transaction_id: Uuid::new_v4(),
+        };
+        AccountRepositoryTrait::save(&repository, &account, vec![deposit])
+            .await
+            .unwrap();
+        assert_eq!(
+            repository
+                .get_by_id(account_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .version,
+            2
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_in_memory_store_reports_existing_aggregates_the_same_way() {
+    let store = InMemoryEventStore::new();
+    let account_id = Uuid::new_v4();
+    store
+        .save_events(account_id, vec![created(account_id, "First")], 0)
+        .await
+        .unwrap();
+    assert!(matches!(
+        store
+            .save_events(account_id, vec![created(account_id, "Second")], 0)
+            .await,
+        Err(EventStoreError::AggregateAlreadyExists { version: 1, .. })
+    ));
+    assert!(matches!(
+        store
+            .save_events(account_id, vec![created(account_id, "Third")], 3)
+            .await,
+        Err(EventStoreError::OptimisticConcurrencyConflict { .. })
+    ));
+}
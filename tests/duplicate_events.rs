@@ -0,0 +1,194 @@
+mod common;
+
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::event_store::{
+        DuplicateEventGuard, DuplicateEventPolicy, EventMetadata, EventStore, EventStoreConfig,
+        EventStoreError,
+    },
+};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+async fn store(policy: DuplicateEventPolicy) -> EventStore {
+    let pool = common::pool().await;
+    EventStore::new_with_config_and_pool(
+        pool,
+        EventStoreConfig {
+            duplicate_events: DuplicateEventGuard {
+                policy,
+                ..DuplicateEventGuard::default()
+            },
+            ..EventStoreConfig::default()
+        },
+    )
+}
+
+/// A deposit of 100, under a transaction id of its own like every command's.
+fn deposit(account_id: Uuid) -> AccountEvent {
+    AccountEvent::MoneyDeposited {
+        account_id,
+        amount: Decimal::new(100, 0),
+        transaction_id: Uuid::new_v4(),
+    }
+}
+
+/// Metadata of events caused by `command_id`.
+fn caused_by(command_id: Uuid) -> EventMetadata {
+    EventMetadata {
+        causation_id: Some(command_id),
+        ..EventMetadata::correlated()
+    }
+}
+
+/// Creates an account holding one deposit caused by `command_id`, so the stream is at
+/// version 2.
+async fn account_with_deposit(event_store: &EventStore, command_id: Uuid) -> Uuid {
+    let account_id = Uuid::new_v4();
+    event_store
+        .save_events(
+            account_id,
+            vec![AccountEvent::AccountCreated {
+                account_id,
+                owner_name: "Duplicate Event Test".to_string(),
+                initial_balance: Decimal::ZERO,
+                owner_user_id: None,
+                account_type: Default::default(),
+                rules: Default::default(),
+            }],
+            0,
+        )
+        .await
+        .unwrap();
+    event_store
+        .save_events_with_metadata(
+            account_id,
+            vec![deposit(account_id)],
+            1,
+            caused_by(command_id),
+        )
+        .await
+        .unwrap();
+    account_id
+}
+
+#[tokio::test]
+async fn test_duplicate_deposit_is_rejected_under_the_guard() {
+    let event_store = store(DuplicateEventPolicy::Reject).await;
+    let command_id = Uuid::new_v4();
+    let account_id = account_with_deposit(&event_store, command_id).await;
+
+    let result = event_store
+        .save_events_with_metadata(
+            account_id,
+            vec![deposit(account_id)],
+            2,
+            caused_by(command_id),
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(EventStoreError::DuplicateEvent { aggregate_id, version: 3 })
+            if aggregate_id == account_id
+    ));
+    assert_eq!(
+        event_store.get_current_version(account_id).await.unwrap(),
+        2
+    );
+
+    // A different amount isn't a duplicate
+    event_store
+        .save_events_with_metadata(
+            account_id,
+            vec![AccountEvent::MoneyDeposited {
+                account_id,
+                amount: Decimal::new(50, 0),
+                transaction_id: Uuid::new_v4(),
+            }],
+            2,
+            caused_by(command_id),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        event_store.get_current_version(account_id).await.unwrap(),
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_duplicate_deposit_is_skipped_when_configured() {
+    let event_store = store(DuplicateEventPolicy::Skip).await;
+    let command_id = Uuid::new_v4();
+    let account_id = account_with_deposit(&event_store, command_id).await;
+
+    // Reported as skipped, so callers don't publish or project events that weren't stored
+    let result = event_store
+        .save_events_with_metadata(
+            account_id,
+            vec![deposit(account_id)],
+            2,
+            caused_by(command_id),
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(EventStoreError::DuplicateEventSkipped { aggregate_id, version: 3 })
+            if aggregate_id == account_id
+    ));
+    assert_eq!(
+        event_store.get_current_version(account_id).await.unwrap(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_duplicate_deposit_is_allowed_without_the_guard() {
+    let event_store = store(DuplicateEventPolicy::Allow).await;
+    let command_id = Uuid::new_v4();
+    let account_id = account_with_deposit(&event_store, command_id).await;
+
+    event_store
+        .save_events_with_metadata(
+            account_id,
+            vec![deposit(account_id)],
+            2,
+            caused_by(command_id),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        event_store.get_current_version(account_id).await.unwrap(),
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_repeats_of_other_or_no_commands_are_not_duplicates() {
+    let event_store = store(DuplicateEventPolicy::Reject).await;
+    let account_id = account_with_deposit(&event_store, Uuid::new_v4()).await;
+
+    // The same deposit from another command, e.g. a customer paying twice on purpose
+    event_store
+        .save_events_with_metadata(
+            account_id,
+            vec![deposit(account_id)],
+            2,
+            caused_by(Uuid::new_v4()),
+        )
+        .await
+        .unwrap();
+    // Without a causation nothing marks the deposit as a repeated command
+    event_store
+        .save_events(account_id, vec![deposit(account_id)], 3)
+        .await
+        .unwrap();
+    event_store
+        .save_events(account_id, vec![deposit(account_id)], 4)
+        .await
+        .unwrap();
+    assert_eq!(
+        event_store.get_current_version(account_id).await.unwrap(),
+        5
+    );
+}
@@ -0,0 +1,231 @@
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use banking_es::{
+    domain::AccountError,
+    error::BankingError,
+    infrastructure::{
+        auth::AuthError,
+        event_serialization::EventFormat,
+        event_store::{EventMetadata, EventStoreError, StoredEvent},
+        failed_commands::FailedCommandError,
+        kafka_abstraction::BankingKafkaError,
+        outbox::OutboxError,
+        projections::ProjectionError,
+        repository::RepositoryError,
+        user_repository::UserRepositoryError,
+    },
+};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+#[test]
+fn test_account_errors_map_to_client_statuses() {
+    let cases = [
+        (AccountError::NotFound, StatusCode::NOT_FOUND),
+        (
+            AccountError::InsufficientFunds {
+                available: Decimal::ZERO,
+                requested: Decimal::ONE,
+            },
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            AccountError::InvalidAmount(Decimal::NEGATIVE_ONE),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            AccountError::LimitExceeded {
+                limit: Decimal::ONE,
+                requested: Decimal::TEN,
+            },
+            StatusCode::BAD_REQUEST,
+        ),
+        (AccountError::AccountClosed, StatusCode::CONFLICT),
+        (AccountError::AlreadyExists, StatusCode::CONFLICT),
+        (
+            AccountError::VersionConflict {
+                expected: 1,
+                actual: 2,
+            },
+            StatusCode::CONFLICT,
+        ),
+        (
+            AccountError::InfrastructureError("db down".to_string()),
+            StatusCode::SERVICE_UNAVAILABLE,
+        ),
+        (
+            AccountError::SchemaError("missing field `amount`".to_string()),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ),
+        (
+            AccountError::EventDeserializationError("truncated".to_string()),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ),
+    ];
+    for (source, status) in cases {
+        let error = BankingError::from(source);
+        assert!(matches!(error, BankingError::Account(_)));
+        assert_eq!(error.status_code(), status, "{}", error);
+    }
+}
+
+#[test]
+fn test_infrastructure_errors_keep_their_variant() {
+    let conflict = BankingError::from(EventStoreError::OptimisticConcurrencyConflict {
+        aggregate_id: Uuid::new_v4(),
+        expected: 3,
+        actual: Some(4),
+    });
+    assert!(matches!(
+        conflict,
+        BankingError::EventStore(EventStoreError::OptimisticConcurrencyConflict { .. })
+    ));
+    assert_eq!(conflict.status_code(), StatusCode::CONFLICT);
+
+    let cases = [
+        (
+            BankingError::from(RepositoryError::NotFound(Uuid::new_v4())),
+            StatusCode::NOT_FOUND,
+        ),
+        (
+            BankingError::from(EventStoreError::BatchTooLarge {
+                aggregate_id: Uuid::new_v4(),
+                count: 10,
+                max: 5,
+            }),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            BankingError::from(EventStoreError::AggregateTooLarge {
+                aggregate_id: Uuid::new_v4(),
+                version: 1_000_000,
+                max: 1_000_000,
+            }),
+            StatusCode::CONFLICT,
+        ),
+        (
+            BankingError::from(ProjectionError::CacheError("evicted".to_string())),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ),
+        (
+            BankingError::from(BankingKafkaError::KafkaUnavailable("no broker".to_string())),
+            StatusCode::SERVICE_UNAVAILABLE,
+        ),
+        (
+            BankingError::from(OutboxError::Kafka(BankingKafkaError::Unknown(
+                "?".to_string(),
+            ))),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ),
+        (
+            BankingError::from(FailedCommandError::AlreadyReplayed(Uuid::new_v4())),
+            StatusCode::CONFLICT,
+        ),
+        (
+            BankingError::from(UserRepositoryError::UsernameExists("alice".to_string())),
+            StatusCode::CONFLICT,
+        ),
+        (
+            BankingError::from(AuthError::RateLimitExceeded),
+            StatusCode::TOO_MANY_REQUESTS,
+        ),
+        (
+            BankingError::from(anyhow::anyhow!("unexpected")),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ),
+    ];
+    for (error, status) in cases {
+        assert_eq!(error.status_code(), status, "{}", error);
+    }
+}
+
+#[tokio::test]
+async fn test_response_hides_server_error_details() {
+    let response = BankingError::from(AccountError::InfrastructureError(
+        "password=hunter2".to_string(),
+    ))
+    .into_response();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"], "Service Unavailable");
+    assert!(body.get("code").is_none());
+
+    let response = BankingError::from(AccountError::NotFound).into_response();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"], "Account not found");
+}
+
+#[test]
+fn test_conversion_back_to_account_error_is_not_lossy() {
+    let error = AccountError::from(BankingError::from(
+        EventStoreError::OptimisticConcurrencyConflict {
+            aggregate_id: Uuid::new_v4(),
+            expected: 3,
+            actual: Some(4),
+        },
+    ));
+    assert!(matches!(
+        error,
+        AccountError::VersionConflict {
+            expected: 3,
+            actual: 4
+        }
+    ));
+    assert!(matches!(
+        AccountError::from(BankingError::from(AccountError::AccountClosed)),
+        AccountError::AccountClosed
+    ));
+}
+
+fn stored_event(event_data: serde_json::Value, format: EventFormat) -> StoredEvent {
+    StoredEvent {
+        id: Uuid::new_v4(),
+        aggregate_id: Uuid::new_v4(),
+        version: 1,
+        event_type: "MoneyDeposited".to_string(),
+        event_data,
+        metadata: EventMetadata::default(),
+        occurred_at: chrono::Utc::now(),
+        global_position: 1,
+        format,
+    }
+}
+
+#[tokio::test]
+async fn test_malformed_stored_event_surfaces_as_schema_error() {
+    let malformed = stored_event(
+        serde_json::json!({ "type": "MoneyDeposited", "amount": "not a number" }),
+        EventFormat::Json,
+    );
+    let error = AccountError::from(malformed.decode().unwrap_err());
+    assert!(matches!(error, AccountError::SchemaError(_)), "{}", error);
+
+    let error = BankingError::from(error);
+    assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(error.error_code(), Some("schema_mismatch"));
+    let body = axum::body::to_bytes(error.into_response().into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["code"], "schema_mismatch");
+    assert_eq!(body["error"], "Internal Server Error");
+
+    // A payload that can't be read at all is not a schema mismatch
+    let unreadable = stored_event(serde_json::json!("not base64!"), EventFormat::MessagePack);
+    let error = AccountError::from(unreadable.decode().unwrap_err());
+    assert!(
+        matches!(error, AccountError::EventDeserializationError(_)),
+        "{}",
+        error
+    );
+    assert_eq!(
+        BankingError::from(error).error_code(),
+        Some("event_deserialization_failed")
+    );
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::{
+        event_store::{EventStore, EventStoreConfig, EventStoreError},
+        kafka_abstraction::EventBatch,
+    },
+};
+use rust_decimal::Decimal;
+use std::sync::atomic::Ordering;
+use uuid::Uuid;
+
+const MAX_EVENTS: usize = 3;
+const MAX_AGGREGATE_EVENTS: i64 = 5;
+
+fn deposits(account_id: Uuid, count: usize) -> Vec<AccountEvent> {
+    (0..count)
+        .map(|_| AccountEvent::MoneyDeposited {
+            account_id,
+            amount: Decimal::new(10, 0),
+            transaction_id: Uuid::new_v4(),
+        })
+        .collect()
+}
+
+async fn capped_event_store() -> EventStore {
+    let pool = common::pool().await;
+    let config = EventStoreConfig {
+        max_events_per_save: MAX_EVENTS,
+        max_events_per_aggregate: MAX_AGGREGATE_EVENTS,
+        ..EventStoreConfig::default_in_memory_for_tests().unwrap()
+    };
+    EventStore::new_with_config_and_pool(pool, config)
+}
+
+#[tokio::test]
+async fn test_oversized_save_is_rejected_without_appending() {
+    let event_store = capped_event_store().await;
+    let account_id = Uuid::new_v4();
+
+    let error = event_store
+        .save_events(account_id, deposits(account_id, MAX_EVENTS + 1), 0)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        EventStoreError::BatchTooLarge { count, max, .. } if count == MAX_EVENTS + 1 && max == MAX_EVENTS
+    ));
+    assert!(event_store
+        .get_events(account_id, None)
+        .await
+        .unwrap()
+        .is_empty());
+
+    event_store
+        .save_events(account_id, deposits(account_id, MAX_EVENTS), 0)
+        .await
+        .unwrap();
+    assert_eq!(
+        event_store
+            .get_events(account_id, None)
+            .await
+            .unwrap()
+            .len(),
+        MAX_EVENTS
+    );
+}
+
+#[tokio::test]
+async fn test_append_past_the_aggregate_limit_is_rejected() {
+    let event_store = capped_event_store().await;
+    let account_id = Uuid::new_v4();
+
+    event_store
+        .save_events(account_id, deposits(account_id, 3), 0)
+        .await
+        .unwrap();
+    // Three more would take the aggregate to 6; two fill it exactly
+    let error = event_store
+        .save_events(account_id, deposits(account_id, 3), 3)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        EventStoreError::AggregateTooLarge { version: 3, max, .. } if max == MAX_AGGREGATE_EVENTS
+    ));
+    event_store
+        .save_events(account_id, deposits(account_id, 2), 3)
+        .await
+        .unwrap();
+    assert!(matches!(
+        event_store
+            .save_events(account_id, deposits(account_id, 1), 5)
+            .await,
+        Err(EventStoreError::AggregateTooLarge { version: 5, .. })
+    ));
+
+    assert_eq!(
+        event_store
+            .get_events(account_id, None)
+            .await
+            .unwrap()
+            .len(),
+        MAX_AGGREGATE_EVENTS as usize
+    );
+    assert_eq!(
+        event_store
+            .get_metrics()
+            .aggregates_too_large
+            .load(Ordering::Relaxed),
+        2
+    );
+}
+
+#[test]
+fn test_oversized_kafka_batch_is_chunked_in_version_order() {
+    let account_id = Uuid::new_v4();
+    let events = deposits(account_id, 7);
+
+    // Seven events ending at version 12 started at version 6
+    let batches = EventBatch::chunked(account_id, events.clone(), 12, Some(40), MAX_EVENTS);
+
+    let sizes: Vec<usize> = batches.iter().map(|b| b.events.len()).collect();
+    assert_eq!(sizes, vec![3, 3, 1]);
+    let versions: Vec<i64> = batches.iter().map(|b| b.version).collect();
+    assert_eq!(versions, vec![8, 11, 12]);
+    let positions: Vec<Option<i64>> = batches.iter().map(|b| b.global_position).collect();
+    assert_eq!(positions, vec![Some(36), Some(39), Some(40)]);
+
+    let rejoined: Vec<AccountEvent> = batches.into_iter().flat_map(|b| b.events).collect();
+    assert_eq!(
+        serde_json::to_value(&rejoined).unwrap(),
+        serde_json::to_value(&events).unwrap()
+    );
+}
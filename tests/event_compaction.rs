@@ -0,0 +1,196 @@
+mod common;
+
+use banking_es::{
+    domain::AccountCommand,
+    infrastructure::{
+        compaction::{CompactionOutcome, EventCompactor},
+        config::CompactionConfig,
+        event_store::{EventStore, EventStoreTrait},
+        repository::AccountRepository,
+    },
+};
+use chrono::{Duration as ChronoDuration, Utc};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+const RETENTION_DAYS: i64 = 30;
+
+async fn setup() -> (Arc<EventStore>, AccountRepository, EventCompactor) {
+    let pool = common::pool().await;
+    let event_store = Arc::new(EventStore::new(pool));
+    let repository = AccountRepository::new(event_store.clone() as Arc<dyn EventStoreTrait>);
+    let compactor = EventCompactor::new(
+        event_store.clone(),
+        CompactionConfig {
+            enabled: true,
+            retention: Duration::from_secs(RETENTION_DAYS as u64 * 24 * 3600),
+            batch_size: 100,
+        },
+    );
+    (event_store, repository, compactor)
+}
+
+/// Creates an account, moves some money and closes it. Returns its final version.
+async fn closed_account(repository: &AccountRepository, account_id: Uuid) -> i64 {
+    let commands = [
+        AccountCommand::CreateAccount {
+            account_id,
+            owner_name: "Compaction".to_string(),
+            initial_balance: Decimal::new(100, 0),
+            owner_user_id: None,
+            account_type: Default::default(),
+            rules: Default::default(),
+        },
+        AccountCommand::DepositMoney {
+            account_id,
+            amount: Decimal::new(50, 0),
+        },
+        AccountCommand::WithdrawMoney {
+            account_id,
+            amount: Decimal::new(30, 0),
+        },
+        AccountCommand::CloseAccount {
+            account_id,
+            reason: "Customer left".to_string(),
+        },
+    ];
+    let mut version = 0;
+    for command in &commands {
+        version = repository
+            .apply_command(account_id, command, None)
+            .await
+            .unwrap()
+            .version;
+    }
+    version
+}
+
+#[tokio::test]
+async fn test_closed_account_past_retention_is_compacted_to_a_tombstone() {
+    let (event_store, repository, compactor) = setup().await;
+    let account_id = Uuid::new_v4();
+    let final_version = closed_account(&repository, account_id).await;
+    assert_eq!(final_version, 4);
+
+    // Within the retention window the history is kept
+    assert_eq!(
+        compactor
+            .compact_account(account_id, Utc::now())
+            .await
+            .unwrap(),
+        CompactionOutcome::NotDue
+    );
+    assert_eq!(
+        event_store
+            .get_events(account_id, None)
+            .await
+            .unwrap()
+            .len(),
+        4
+    );
+
+    let past_retention = Utc::now() + ChronoDuration::days(RETENTION_DAYS + 1);
+    assert_eq!(
+        compactor
+            .compact_account(account_id, past_retention)
+            .await
+            .unwrap(),
+        CompactionOutcome::Compacted { events_removed: 4 }
+    );
+    assert!(event_store
+        .get_events(account_id, None)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let tombstone = compactor.tombstone(account_id).await.unwrap().unwrap();
+    assert_eq!(tombstone.final_version, 4);
+    assert_eq!(tombstone.events_removed, 4);
+    assert_eq!(tombstone.final_state["is_active"], false);
+
+    // The account still loads, closed and with its final balance, from the snapshot
+    let account = repository.get_by_id(account_id).await.unwrap().unwrap();
+    assert!(!account.is_active);
+    assert_eq!(account.balance, Decimal::new(120, 0));
+    assert_eq!(account.version, 4);
+
+    assert_eq!(
+        compactor
+            .compact_account(account_id, past_retention)
+            .await
+            .unwrap(),
+        CompactionOutcome::AlreadyCompacted
+    );
+}
+
+#[tokio::test]
+async fn test_legal_hold_exempts_an_account_until_released() {
+    let (event_store, repository, compactor) = setup().await;
+    let account_id = Uuid::new_v4();
+    closed_account(&repository, account_id).await;
+    let past_retention = Utc::now() + ChronoDuration::days(RETENTION_DAYS + 1);
+
+    let hold = compactor
+        .place_legal_hold(account_id, "Litigation 2026-114")
+        .await
+        .unwrap();
+    assert_eq!(hold.reason, "Litigation 2026-114");
+    assert_eq!(
+        compactor
+            .compact_account(account_id, past_retention)
+            .await
+            .unwrap(),
+        CompactionOutcome::OnLegalHold
+    );
+    assert_eq!(
+        event_store
+            .get_events(account_id, None)
+            .await
+            .unwrap()
+            .len(),
+        4
+    );
+    assert!(compactor.tombstone(account_id).await.unwrap().is_none());
+
+    assert!(compactor.release_legal_hold(account_id).await.unwrap());
+    assert!(!compactor.release_legal_hold(account_id).await.unwrap());
+    assert_eq!(
+        compactor
+            .compact_account(account_id, past_retention)
+            .await
+            .unwrap(),
+        CompactionOutcome::Compacted { events_removed: 4 }
+    );
+}
+
+#[tokio::test]
+async fn test_open_accounts_are_never_due() {
+    let (_, repository, compactor) = setup().await;
+    let account_id = Uuid::new_v4();
+    repository
+        .apply_command(
+            account_id,
+            &AccountCommand::CreateAccount {
+                account_id,
+                owner_name: "Still Open".to_string(),
+                initial_balance: Decimal::new(10, 0),
+                owner_user_id: None,
+                account_type: Default::default(),
+                rules: Default::default(),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    let far_future = Utc::now() + ChronoDuration::days(RETENTION_DAYS * 100);
+    assert_eq!(
+        compactor
+            .compact_account(account_id, far_future)
+            .await
+            .unwrap(),
+        CompactionOutcome::NotDue
+    );
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::event_store::{EventMetadata, EventStore},
+};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+async fn setup() -> EventStore {
+    let pool = common::pool().await;
+    EventStore::new(pool)
+}
+
+fn command_events(account_id: Uuid) -> Vec<AccountEvent> {
+    vec![
+        AccountEvent::AccountCreated {
+            account_id,
+            owner_name: "Metadata Test".to_string(),
+            initial_balance: Decimal::new(100, 0),
+            owner_user_id: None,
+            account_type: Default::default(),
+            rules: Default::default(),
+        },
+        AccountEvent::MoneyDeposited {
+            account_id,
+            amount: Decimal::new(10, 0),
+            transaction_id: Uuid::new_v4(),
+        },
+    ]
+}
+
+#[tokio::test]
+async fn test_metadata_round_trips_for_every_event_of_a_command() {
+    let event_store = setup().await;
+    let account_id = Uuid::new_v4();
+    let metadata = EventMetadata {
+        correlation_id: Some(Uuid::new_v4()),
+        causation_id: Some(Uuid::new_v4()),
+        user_id: Some(Uuid::new_v4().to_string()),
+        user_role: Some("BankManager".to_string()),
+        source: "api".to_string(),
+        tags: vec!["test".to_string()],
+        ..EventMetadata::default()
+    };
+
+    event_store
+        .save_events_with_metadata(account_id, command_events(account_id), 0, metadata.clone())
+        .await
+        .unwrap();
+
+    let events = event_store.get_events(account_id, None).await.unwrap();
+    assert_eq!(events.len(), 2);
+    let occurred_at = events[0]
+        .metadata
+        .occurred_at
+        .expect("occurred_at is set on save");
+    for event in &events {
+        assert_eq!(
+            event.metadata,
+            EventMetadata {
+                occurred_at: Some(occurred_at),
+                ..metadata.clone()
+            }
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_events_saved_together_share_a_correlation_id() {
+    let event_store = setup().await;
+    let first = Uuid::new_v4();
+    let second = Uuid::new_v4();
+
+    event_store
+        .save_events(first, command_events(first), 0)
+        .await
+        .unwrap();
+    event_store
+        .save_events(second, command_events(second), 0)
+        .await
+        .unwrap();
+
+    let first_events = event_store.get_events(first, None).await.unwrap();
+    let correlation_id = first_events[0].metadata.correlation_id;
+    assert!(correlation_id.is_some());
+    assert_eq!(first_events[1].metadata.correlation_id, correlation_id);
+
+    let second_events = event_store.get_events(second, None).await.unwrap();
+    assert_ne!(second_events[0].metadata.correlation_id, correlation_id);
+}
+
+#[tokio::test]
+async fn test_events_are_found_by_what_caused_them() {
+    let event_store = setup().await;
+    let account_id = Uuid::new_v4();
+    let command_id = Uuid::new_v4();
+    let mut events = command_events(account_id).into_iter();
+
+    event_store
+        .save_events(account_id, vec![events.next().unwrap()], 0)
+        .await
+        .unwrap();
+    event_store
+        .save_events_with_metadata(
+            account_id,
+            events.collect(),
+            1,
+            EventMetadata {
+                causation_id: Some(command_id),
+                ..EventMetadata::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let caused = event_store
+        .get_events_caused_by(account_id, command_id)
+        .await
+        .unwrap();
+    assert_eq!(caused.len(), 1);
+    assert_eq!(caused[0].version, 2);
+    assert!(event_store
+        .get_events_caused_by(account_id, Uuid::new_v4())
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::event_store::{EventStore, StoredEvent},
+};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+// The tests change the partitions of the shared events table, so they take turns
+static PARTITIONS: Mutex<()> = Mutex::const_new(());
+
+fn events(account_id: Uuid) -> Vec<AccountEvent> {
+    let mut events = vec![AccountEvent::AccountCreated {
+        account_id,
+        owner_name: "Partition Test".to_string(),
+        initial_balance: Decimal::ZERO,
+        owner_user_id: None,
+        account_type: Default::default(),
+        rules: Default::default(),
+    }];
+    for amount in 1..=3 {
+        events.push(AccountEvent::MoneyDeposited {
+            account_id,
+            amount: Decimal::new(amount, 0),
+            transaction_id: Uuid::new_v4(),
+        });
+    }
+    events
+}
+
+/// The tables events are partitioned into, by name.
+async fn partitions(pool: &PgPool) -> Vec<String> {
+    sqlx::query_scalar(
+        r#"
+        SELECT child.relname::text
+        FROM pg_inherits
+        JOIN pg_class child ON child.oid = pg_inherits.inhrelid
+        WHERE pg_inherits.inhparent = 'events'::regclass
+        ORDER BY child.relname
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+/// The partition Postgres routes `aggregate_id`'s events to.
+async fn partition_for(pool: &PgPool, aggregate_id: Uuid) -> String {
+    let count = partitions(pool).await.len() as i32;
+    sqlx::query_scalar(
+        r#"
+        SELECT 'events_p' || remainder
+        FROM generate_series(0, $1 - 1) AS remainder
+        WHERE satisfies_hash_partition('events'::regclass, $1, remainder, $2::uuid)
+        "#,
+    )
+    .bind(count)
+    .bind(aggregate_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+/// The tables actually holding `aggregate_id`'s events.
+async fn tables_holding(pool: &PgPool, aggregate_id: Uuid) -> Vec<String> {
+    sqlx::query_scalar(
+        "SELECT DISTINCT tableoid::regclass::text FROM events WHERE aggregate_id = $1",
+    )
+    .bind(aggregate_id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_repartition_moves_stored_events_into_the_new_partitions() {
+    let _turn = PARTITIONS.lock().await;
+    let pool = common::pool().await;
+    let event_store = EventStore::new(pool.clone());
+    event_store.repartition(8).await.unwrap();
+    let names =
+        |count: u32| -> Vec<String> { (0..count).map(|p| format!("events_p{}", p)).collect() };
+    assert_eq!(partitions(&pool).await, names(8));
+    assert_eq!(event_store.repartition(8).await.unwrap(), 0);
+
+    let account_id = Uuid::new_v4();
+    event_store
+        .save_events(account_id, events(account_id), 0)
+        .await
+        .unwrap();
+    let before = event_store.get_events(account_id, None).await.unwrap();
+
+    let moved = event_store.repartition(6).await.unwrap();
+    assert!(moved >= before.len() as u64);
+    assert_eq!(partitions(&pool).await, names(6));
+    assert_eq!(
+        tables_holding(&pool, account_id).await,
+        vec![partition_for(&pool, account_id).await]
+    );
+    let after = event_store.get_events(account_id, None).await.unwrap();
+    let key = |events: &[StoredEvent]| -> Vec<(Uuid, i64, i64)> {
+        events
+            .iter()
+            .map(|e| (e.id, e.version, e.global_position))
+            .collect()
+    };
+    assert_eq!(key(&after), key(&before));
+
+    event_store.repartition(8).await.unwrap();
+    assert_eq!(partitions(&pool).await, names(8));
+}
+
+#[tokio::test]
+async fn test_aggregates_are_stored_and_replayed_from_their_partitions() {
+    let _turn = PARTITIONS.lock().await;
+    let pool = common::pool().await;
+    let event_store = EventStore::new(pool.clone());
+    event_store.repartition(8).await.unwrap();
+
+    // Two aggregates that hash to different partitions
+    let first = Uuid::new_v4();
+    let first_partition = partition_for(&pool, first).await;
+    let (second, second_partition) = loop {
+        let candidate = Uuid::new_v4();
+        let partition = partition_for(&pool, candidate).await;
+        if partition != first_partition {
+            break (candidate, partition);
+        }
+    };
+
+    // Interleaved, so the two partitions are written in turn
+    let (first_events, second_events) = (events(first), events(second));
+    for i in 0..first_events.len() {
+        event_store
+            .save_events(first, vec![first_events[i].clone()], i as i64)
+            .await
+            .unwrap();
+        event_store
+            .save_events(second, vec![second_events[i].clone()], i as i64)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(tables_holding(&pool, first).await, vec![first_partition]);
+    assert_eq!(tables_holding(&pool, second).await, vec![second_partition]);
+    for (aggregate_id, written) in [(first, &first_events), (second, &second_events)] {
+        let stored = event_store.get_events(aggregate_id, None).await.unwrap();
+        let versions: Vec<i64> = stored.iter().map(|event| event.version).collect();
+        assert_eq!(versions, vec![1, 2, 3, 4]);
+        assert!(stored
+            .windows(2)
+            .all(|pair| pair[0].global_position < pair[1].global_position));
+        for (event, expected) in stored.iter().zip(written.iter()) {
+            assert_eq!(
+                serde_json::to_value(event.decode().unwrap()).unwrap(),
+                serde_json::to_value(expected).unwrap()
+            );
+        }
+    }
+
+    // Saves still detect a stale version within a partition
+    assert!(event_store
+        .save_events(first, events(first)[1..2].to_vec(), 2)
+        .await
+        .is_err());
+}
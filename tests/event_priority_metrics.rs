@@ -0,0 +1,80 @@
+mod common;
+
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::event_store::{EventPriority, EventStore},
+};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+fn account_events(account_id: Uuid, deposits: usize) -> Vec<AccountEvent> {
+    let mut events = vec![AccountEvent::AccountCreated {
+        account_id,
+        owner_name: "Priority Metrics".to_string(),
+        initial_balance: Decimal::new(10, 0),
+        owner_user_id: None,
+        account_type: Default::default(),
+        rules: Default::default(),
+    }];
+    events.extend((0..deposits).map(|_| AccountEvent::MoneyDeposited {
+        account_id,
+        amount: Decimal::new(1, 0),
+        transaction_id: Uuid::new_v4(),
+    }));
+    events
+}
+
+#[tokio::test]
+async fn test_events_are_counted_by_priority() {
+    let pool = common::pool().await;
+    let event_store = EventStore::new(pool);
+
+    // Saves without a priority count as Normal
+    let account_id = Uuid::new_v4();
+    event_store
+        .save_events(account_id, account_events(account_id, 1), 0)
+        .await
+        .unwrap();
+    for (priority, deposits) in [
+        (EventPriority::Low, 0),
+        (EventPriority::High, 2),
+        (EventPriority::Critical, 3),
+        (EventPriority::High, 0),
+    ] {
+        let account_id = Uuid::new_v4();
+        event_store
+            .save_events_with_priority(
+                account_id,
+                account_events(account_id, deposits),
+                0,
+                priority,
+            )
+            .await
+            .unwrap();
+    }
+
+    let by_priority = event_store.get_metrics().events_by_priority;
+    assert_eq!(by_priority.get(EventPriority::Low), 1);
+    assert_eq!(by_priority.get(EventPriority::Normal), 2);
+    assert_eq!(by_priority.get(EventPriority::High), 4);
+    assert_eq!(by_priority.get(EventPriority::Critical), 4);
+
+    // A rejected save isn't counted
+    let account_id = Uuid::new_v4();
+    assert!(event_store
+        .save_events_with_priority(
+            account_id,
+            account_events(account_id, 0),
+            5,
+            EventPriority::Critical,
+        )
+        .await
+        .is_err());
+    assert_eq!(
+        event_store
+            .get_metrics()
+            .events_by_priority
+            .get(EventPriority::Critical),
+        4
+    );
+}
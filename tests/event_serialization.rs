@@ -0,0 +1,85 @@
+mod common;
+
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::{
+        event_serialization::EventFormat,
+        event_store::{EventStore, EventStoreConfig, EventStoreTrait},
+        repository::AccountRepository,
+    },
+};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn store_with_format(pool: PgPool, format: EventFormat) -> EventStore {
+    let config = EventStoreConfig {
+        event_format: format,
+        ..EventStoreConfig::default_in_memory_for_tests().unwrap()
+    };
+    EventStore::new_with_config_and_pool(pool, config)
+}
+
+#[tokio::test]
+async fn test_stream_with_mixed_formats_replays() {
+    let pool = common::pool().await;
+    let json_store = store_with_format(pool.clone(), EventFormat::Json);
+    let msgpack_store = Arc::new(store_with_format(pool, EventFormat::MessagePack));
+    let account_id = Uuid::new_v4();
+
+    json_store
+        .save_events(
+            account_id,
+            vec![AccountEvent::AccountCreated {
+                account_id,
+                owner_name: "Mixed Formats".to_string(),
+                initial_balance: Decimal::new(100, 0),
+                owner_user_id: None,
+                account_type: Default::default(),
+                rules: Default::default(),
+            }],
+            0,
+        )
+        .await
+        .unwrap();
+    // The format switched after the account was created
+    msgpack_store
+        .save_events(
+            account_id,
+            vec![
+                AccountEvent::MoneyDeposited {
+                    account_id,
+                    amount: Decimal::new(50, 0),
+                    transaction_id: Uuid::new_v4(),
+                },
+                AccountEvent::MoneyWithdrawn {
+                    account_id,
+                    amount: Decimal::new(30, 0),
+                    transaction_id: Uuid::new_v4(),
+                    withdrawn_at: None,
+                },
+            ],
+            1,
+        )
+        .await
+        .unwrap();
+
+    let events = msgpack_store.get_events(account_id, None).await.unwrap();
+    let formats: Vec<EventFormat> = events.iter().map(|e| e.format).collect();
+    assert_eq!(
+        formats,
+        vec![
+            EventFormat::Json,
+            EventFormat::MessagePack,
+            EventFormat::MessagePack
+        ]
+    );
+    assert!(events[1].event_data.is_string());
+
+    let repository = AccountRepository::new(msgpack_store as Arc<dyn EventStoreTrait + 'static>);
+    let account = repository.get_by_id(account_id).await.unwrap().unwrap();
+    assert_eq!(account.owner_name, "Mixed Formats");
+    assert_eq!(account.balance, Decimal::new(120, 0));
+    assert_eq!(account.version, 3);
+}
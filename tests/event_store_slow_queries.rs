@@ -0,0 +1,254 @@
+use async_trait::async_trait;
+use banking_es::{
+    domain::{Account, AccountEvent},
+    infrastructure::{
+        config::SlowQueryLogConfig,
+        event_store::{
+            AccountSnapshot, AggregateStats, EventMetadata, EventStoreError, EventStoreTrait,
+            StoredEvent,
+        },
+        event_store_timing::TimedEventStore,
+        in_memory_event_store::InMemoryEventStore,
+    },
+};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing_subscriber::fmt::MakeWriter;
+use uuid::Uuid;
+
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedLogs {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+impl Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for CapturedLogs {
+    type Writer = CapturedLogs;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+fn capture() -> (CapturedLogs, tracing::subscriber::DefaultGuard) {
+    let logs = CapturedLogs::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(logs.clone())
+        .with_ansi(false)
+        .finish();
+    (logs, tracing::subscriber::set_default(subscriber))
+}
+
+/// In-memory event store whose event reads take `read_delay`.
+struct SlowEventStore {
+    inner: InMemoryEventStore,
+    read_delay: Duration,
+}
+
+#[async_trait]
+impl EventStoreTrait for SlowEventStore {
+    async fn save_events(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+    ) -> Result<(), EventStoreError> {
+        self.inner
+            .save_events(aggregate_id, events, expected_version)
+            .await
+    }
+
+    async fn save_events_with_metadata(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+        metadata: EventMetadata,
+    ) -> Result<(), EventStoreError> {
+        self.inner
+            .save_events_with_metadata(aggregate_id, events, expected_version, metadata)
+            .await
+    }
+
+    async fn get_events(
+        &self,
+        aggregate_id: Uuid,
+        from_version: Option<i64>,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        tokio::time::sleep(self.read_delay).await;
+        self.inner.get_events(aggregate_id, from_version).await
+    }
+
+    async fn get_events_since(
+        &self,
+        after_position: i64,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        self.inner.get_events_since(after_position, limit).await
+    }
+
+    async fn get_current_version(&self, aggregate_id: Uuid) -> Result<i64, EventStoreError> {
+        self.inner.get_current_version(aggregate_id).await
+    }
+
+    async fn get_account(&self, account_id: Uuid) -> Result<Option<Account>, EventStoreError> {
+        self.inner.get_account(account_id).await
+    }
+
+    async fn get_all_accounts(&self) -> Result<Vec<Account>, EventStoreError> {
+        self.inner.get_all_accounts().await
+    }
+
+    async fn save_snapshot(&self, aggregate_id: Uuid) -> Result<Option<i64>, EventStoreError> {
+        self.inner.save_snapshot(aggregate_id).await
+    }
+
+    async fn snapshot_aggregates_above_threshold(
+        &self,
+        min_event_count: i64,
+    ) -> Result<Vec<(Uuid, i64)>, EventStoreError> {
+        self.inner
+            .snapshot_aggregates_above_threshold(min_event_count)
+            .await
+    }
+
+    async fn aggregate_stats(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<AggregateStats>, EventStoreError> {
+        self.inner.aggregate_stats(aggregate_id).await
+    }
+
+    async fn top_aggregates_by_event_count(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<AggregateStats>, EventStoreError> {
+        self.inner.top_aggregates_by_event_count(limit).await
+    }
+
+    async fn sample_aggregate_ids(&self, limit: i64) -> Result<Vec<Uuid>, EventStoreError> {
+        self.inner.sample_aggregate_ids(limit).await
+    }
+
+    async fn get_snapshot(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<AccountSnapshot>, EventStoreError> {
+        self.inner.get_snapshot(aggregate_id).await
+    }
+
+    fn get_pool(&self) -> PgPool {
+        self.inner.get_pool()
+    }
+}
+
+fn timed_store(enabled: bool) -> TimedEventStore {
+    TimedEventStore::new(
+        Arc::new(SlowEventStore {
+            inner: InMemoryEventStore::new(),
+            read_delay: Duration::from_millis(60),
+        }),
+        SlowQueryLogConfig {
+            enabled,
+            threshold: Duration::from_millis(50),
+        },
+    )
+}
+
+async fn create_account(store: &TimedEventStore) -> Uuid {
+    let account_id = Uuid::new_v4();
+    store
+        .save_events(
+            account_id,
+            vec![AccountEvent::AccountCreated {
+                account_id,
+                owner_name: "Slow Reader".to_string(),
+                initial_balance: Decimal::new(100, 0),
+                owner_user_id: None,
+                account_type: Default::default(),
+                rules: Default::default(),
+            }],
+            0,
+        )
+        .await
+        .unwrap();
+    account_id
+}
+
+#[tokio::test]
+async fn test_slow_operation_is_logged_with_aggregate_and_duration() {
+    let store = timed_store(true);
+    let (logs, _guard) = capture();
+
+    let account_id = create_account(&store).await;
+    assert_eq!(store.get_events(account_id, None).await.unwrap().len(), 1);
+
+    // Only the slow read is logged, not the fast save
+    let lines = logs.lines();
+    assert_eq!(lines.len(), 1, "{:?}", lines);
+    assert!(
+        lines[0].contains(" WARN slow_query: Slow event store get_events: "),
+        "{}",
+        lines[0]
+    );
+    assert!(
+        lines[0].contains(&format!("aggregate={} ", account_id)),
+        "{}",
+        lines[0]
+    );
+    let duration_ms: u64 = lines[0]
+        .split("duration_ms=")
+        .nth(1)
+        .and_then(|rest| rest.split(' ').next())
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(duration_ms >= 60, "{}", lines[0]);
+}
+
+#[tokio::test]
+async fn test_latency_is_recorded_per_operation_even_when_logging_is_off() {
+    let store = timed_store(false);
+    let (logs, _guard) = capture();
+
+    let account_id = create_account(&store).await;
+    store.get_events(account_id, None).await.unwrap();
+    store.get_events(account_id, Some(1)).await.unwrap();
+    assert!(logs.lines().is_empty(), "{:?}", logs.lines());
+
+    let latency = store.latency().snapshot();
+    assert_eq!(latency.len(), 2);
+    assert_eq!(latency["save_events"].count, 1);
+    let reads = &latency["get_events"];
+    assert_eq!(reads.count, 2);
+    assert!(reads.total_ms >= 120.0);
+    // Both reads land in buckets above 50ms
+    let slow_reads: u64 = reads
+        .buckets
+        .iter()
+        .filter(|bucket| bucket.le_ms.is_none_or(|le_ms| le_ms > 50))
+        .map(|bucket| bucket.count)
+        .sum();
+    assert_eq!(slow_reads, 2);
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use axum::http::StatusCode;
+use banking_es::{
+    domain::AccountEvent,
+    error::BankingError,
+    infrastructure::event_store::{
+        EventStore, EventStoreConfig, EventStoreError, EventStoreTrait, OperationTimeouts,
+    },
+};
+use rust_decimal::Decimal;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{Connection, PgConnection};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// The advisory lock every event insert takes; holding it stalls all saves.
+const GLOBAL_POSITION_LOCK_ID: i64 = 0x6576_656e_7473;
+
+fn created(account_id: Uuid) -> AccountEvent {
+    AccountEvent::AccountCreated {
+        account_id,
+        owner_name: "Timeout Test".to_string(),
+        initial_balance: Decimal::new(10, 0),
+        owner_user_id: None,
+        account_type: Default::default(),
+        rules: Default::default(),
+    }
+}
+
+#[test]
+fn test_operation_timeouts_are_read_from_env() {
+    std::env::set_var("EVENT_STORE_READ_TIMEOUT_MS", "250");
+    std::env::set_var("EVENT_STORE_WRITE_TIMEOUT_MS", "not-a-number");
+    std::env::set_var("EVENT_STORE_BULK_TIMEOUT_MS", "90000");
+    let timeouts = OperationTimeouts::from_env();
+    assert_eq!(timeouts.read, Duration::from_millis(250));
+    assert_eq!(timeouts.write, OperationTimeouts::default().write);
+    assert_eq!(timeouts.bulk, Duration::from_secs(90));
+    assert_eq!(timeouts.longest(), Duration::from_secs(90));
+    for key in [
+        "EVENT_STORE_READ_TIMEOUT_MS",
+        "EVENT_STORE_WRITE_TIMEOUT_MS",
+        "EVENT_STORE_BULK_TIMEOUT_MS",
+    ] {
+        std::env::remove_var(key);
+    }
+}
+
+#[tokio::test]
+async fn test_slow_queries_time_out_with_a_typed_error() {
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect(&common::database_url())
+        .await
+        .expect("Failed to connect to database");
+    let config = EventStoreConfig {
+        timeouts: OperationTimeouts {
+            read: Duration::from_millis(300),
+            write: Duration::from_millis(300),
+            bulk: Duration::from_secs(5),
+        },
+        ..EventStoreConfig::default_in_memory_for_tests().unwrap()
+    };
+    let event_store = EventStore::new_with_config_and_pool(pool, config);
+    let store: &dyn EventStoreTrait = &event_store;
+    let mut blocker = PgConnection::connect(&common::database_url())
+        .await
+        .unwrap();
+
+    // A save stuck behind another writer's lock is cancelled, and nothing is written
+    let account_id = Uuid::new_v4();
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(GLOBAL_POSITION_LOCK_ID)
+        .execute(&mut blocker)
+        .await
+        .unwrap();
+    let start = Instant::now();
+    let error = store
+        .save_events(account_id, vec![created(account_id)], 0)
+        .await
+        .unwrap_err();
+    assert!(
+        start.elapsed() < Duration::from_secs(2),
+        "{:?}",
+        start.elapsed()
+    );
+    assert!(
+        matches!(
+            error,
+            EventStoreError::Timeout {
+                operation: "save_events",
+                ..
+            }
+        ),
+        "{:?}",
+        error
+    );
+    assert_eq!(
+        BankingError::from(error).status_code(),
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(GLOBAL_POSITION_LOCK_ID)
+        .execute(&mut blocker)
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(store.get_events(account_id, None).await.unwrap().is_empty());
+
+    // A read blocked by a table lock fails the same way
+    store
+        .save_events(account_id, vec![created(account_id)], 0)
+        .await
+        .unwrap();
+    let mut tx = blocker.begin().await.unwrap();
+    sqlx::query("LOCK TABLE events IN ACCESS EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    let error = store.get_events(account_id, None).await.unwrap_err();
+    assert!(
+        matches!(
+            error,
+            EventStoreError::Timeout {
+                operation: "get_events",
+                timeout
+            } if timeout == Duration::from_millis(300)
+        ),
+        "{:?}",
+        error
+    );
+    tx.rollback().await.unwrap();
+
+    // The store keeps working once the slow statements are gone
+    assert_eq!(store.get_events(account_id, None).await.unwrap().len(), 1);
+    assert_eq!(
+        event_store
+            .get_metrics()
+            .operation_timeouts
+            .load(Ordering::Relaxed),
+        2
+    );
+}
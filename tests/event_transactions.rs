@@ -0,0 +1,86 @@
+mod common;
+
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::event_store::{EventStore, EventStoreError},
+};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+async fn event_store() -> EventStore {
+    let pool = common::pool().await;
+    EventStore::new(pool)
+}
+
+fn opened(account_id: Uuid) -> Vec<AccountEvent> {
+    vec![AccountEvent::AccountCreated {
+        account_id,
+        owner_name: "Event Transaction".to_string(),
+        initial_balance: Decimal::new(100, 0),
+        owner_user_id: None,
+        account_type: Default::default(),
+        rules: Default::default(),
+    }]
+}
+
+fn deposited(account_id: Uuid) -> Vec<AccountEvent> {
+    vec![AccountEvent::MoneyDeposited {
+        account_id,
+        amount: Decimal::new(10, 0),
+        transaction_id: Uuid::new_v4(),
+    }]
+}
+
+#[tokio::test]
+async fn test_appends_to_two_aggregates_commit_together() {
+    let event_store = event_store().await;
+    let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let mut tx = event_store.begin().await.unwrap();
+    tx.append(first, opened(first), 0).await.unwrap();
+    tx.append(second, opened(second), 0).await.unwrap();
+    tx.append(first, deposited(first), 1).await.unwrap();
+    tx.commit().await.unwrap();
+
+    assert_eq!(event_store.get_events(first, None).await.unwrap().len(), 2);
+    assert_eq!(event_store.get_events(second, None).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_failed_append_rolls_back_both_aggregates() {
+    let event_store = event_store().await;
+    let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let mut tx = event_store.begin().await.unwrap();
+    tx.append(first, opened(first), 0).await.unwrap();
+    tx.append(second, opened(second), 0).await.unwrap();
+    // Forced failure: the second aggregate is at version 1, not 5
+    assert!(matches!(
+        tx.append(second, deposited(second), 5).await,
+        Err(EventStoreError::OptimisticConcurrencyConflict { .. })
+    ));
+    tx.rollback().await.unwrap();
+
+    assert!(event_store.get_events(first, None).await.unwrap().is_empty());
+    assert!(event_store.get_events(second, None).await.unwrap().is_empty());
+    // Neither aggregate is left looking as if it had been created
+    event_store.save_events(first, opened(first), 0).await.unwrap();
+    event_store.save_events(second, opened(second), 0).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_dropped_transaction_is_rolled_back() {
+    let event_store = event_store().await;
+    let account_id = Uuid::new_v4();
+
+    {
+        let mut tx = event_store.begin().await.unwrap();
+        tx.append(account_id, opened(account_id), 0).await.unwrap();
+    }
+
+    assert!(event_store
+        .get_events(account_id, None)
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,261 @@
+mod common;
+
+use async_trait::async_trait;
+use banking_es::{
+    application::services::AccountService,
+    domain::{Account, AccountCommand, AccountEvent},
+    infrastructure::{
+        cache_service::{CacheConfig, CacheService, CacheServiceTrait},
+        event_store::{
+            AccountSnapshot, AggregateStats, EventMetadata, EventStoreError, EventStoreTrait,
+            StoredEvent,
+        },
+        failed_commands::FailedCommandStore,
+        in_memory_event_store::InMemoryEventStore,
+        middleware::RequestMiddleware,
+        projections::{ProjectionStore, ProjectionStoreTrait},
+        redis_abstraction::RealRedisClient,
+        repository::{AccountRepository, AccountRepositoryTrait},
+    },
+};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const INJECTED_ERROR: &str = "injected save failure";
+
+/// In-memory event store whose saves fail while `failing` is set.
+struct FlakyEventStore {
+    inner: InMemoryEventStore,
+    failing: AtomicBool,
+}
+
+#[async_trait]
+impl EventStoreTrait for FlakyEventStore {
+    async fn save_events(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+    ) -> Result<(), EventStoreError> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(EventStoreError::InternalError(INJECTED_ERROR.to_string()));
+        }
+        self.inner
+            .save_events(aggregate_id, events, expected_version)
+            .await
+    }
+
+    async fn save_events_with_metadata(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+        metadata: EventMetadata,
+    ) -> Result<(), EventStoreError> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(EventStoreError::InternalError(INJECTED_ERROR.to_string()));
+        }
+        self.inner
+            .save_events_with_metadata(aggregate_id, events, expected_version, metadata)
+            .await
+    }
+
+    async fn get_events(
+        &self,
+        aggregate_id: Uuid,
+        from_version: Option<i64>,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        self.inner.get_events(aggregate_id, from_version).await
+    }
+
+    async fn get_events_since(
+        &self,
+        after_position: i64,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        self.inner.get_events_since(after_position, limit).await
+    }
+
+    async fn get_current_version(&self, aggregate_id: Uuid) -> Result<i64, EventStoreError> {
+        self.inner.get_current_version(aggregate_id).await
+    }
+
+    async fn get_account(&self, account_id: Uuid) -> Result<Option<Account>, EventStoreError> {
+        self.inner.get_account(account_id).await
+    }
+
+    async fn get_all_accounts(&self) -> Result<Vec<Account>, EventStoreError> {
+        self.inner.get_all_accounts().await
+    }
+
+    async fn save_snapshot(&self, aggregate_id: Uuid) -> Result<Option<i64>, EventStoreError> {
+        self.inner.save_snapshot(aggregate_id).await
+    }
+
+    async fn snapshot_aggregates_above_threshold(
+        &self,
+        min_event_count: i64,
+    ) -> Result<Vec<(Uuid, i64)>, EventStoreError> {
+        self.inner
+            .snapshot_aggregates_above_threshold(min_event_count)
+            .await
+    }
+
+    async fn aggregate_stats(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<AggregateStats>, EventStoreError> {
+        self.inner.aggregate_stats(aggregate_id).await
+    }
+
+    async fn top_aggregates_by_event_count(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<AggregateStats>, EventStoreError> {
+        self.inner.top_aggregates_by_event_count(limit).await
+    }
+
+    async fn sample_aggregate_ids(&self, limit: i64) -> Result<Vec<Uuid>, EventStoreError> {
+        self.inner.sample_aggregate_ids(limit).await
+    }
+
+    async fn get_snapshot(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<AccountSnapshot>, EventStoreError> {
+        self.inner.get_snapshot(aggregate_id).await
+    }
+
+    fn get_pool(&self) -> PgPool {
+        self.inner.get_pool()
+    }
+}
+
+async fn setup() -> (
+    Arc<AccountService>,
+    Arc<FlakyEventStore>,
+    Arc<FailedCommandStore>,
+) {
+    let pool = common::pool().await;
+
+    let event_store = Arc::new(FlakyEventStore {
+        inner: InMemoryEventStore::new(),
+        failing: AtomicBool::new(true),
+    });
+    let repository = Arc::new(AccountRepository::new(
+        event_store.clone() as Arc<dyn EventStoreTrait + 'static>
+    ));
+    let redis_client = redis::Client::open("redis://localhost:6379").unwrap();
+    let failed_commands = Arc::new(FailedCommandStore::new(pool.clone()));
+    let service = AccountService::new(
+        repository as Arc<dyn AccountRepositoryTrait + 'static>,
+        Arc::new(ProjectionStore::new(pool)) as Arc<dyn ProjectionStoreTrait + 'static>,
+        Arc::new(CacheService::new(
+            RealRedisClient::new(redis_client, None),
+            CacheConfig::default(),
+        )) as Arc<dyn CacheServiceTrait + 'static>,
+        Arc::new(RequestMiddleware::default()),
+        100,
+    )
+    .with_failed_command_store(failed_commands.clone());
+
+    (Arc::new(service), event_store, failed_commands)
+}
+
+/// Pulls the failed command id out of the error returned to the caller.
+fn failed_command_id(error: &str) -> Uuid {
+    let id = error
+        .rsplit("recorded as failed command ")
+        .next()
+        .unwrap()
+        .trim_end_matches(')');
+    Uuid::parse_str(id).unwrap()
+}
+
+#[tokio::test]
+async fn test_failed_save_is_recorded_with_error_detail() {
+    let (service, event_store, failed_commands) = setup().await;
+    let account_id = Uuid::new_v4();
+    event_store
+        .inner
+        .save_events(
+            account_id,
+            vec![AccountEvent::AccountCreated {
+                account_id,
+                owner_name: "Failed Save".to_string(),
+                initial_balance: Decimal::new(100, 0),
+                owner_user_id: None,
+                account_type: Default::default(),
+                rules: Default::default(),
+            }],
+            0,
+        )
+        .await
+        .unwrap();
+
+    let error = service
+        .deposit_money(account_id, Decimal::new(25, 0))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains(INJECTED_ERROR));
+
+    let entry = failed_commands
+        .get(failed_command_id(&error))
+        .await
+        .unwrap()
+        .expect("failed command should be recorded");
+    assert_eq!(entry.aggregate_id, account_id);
+    assert!(entry.error.contains(INJECTED_ERROR));
+    assert_eq!(entry.context["operation"], "deposit_money");
+    assert!(entry.replayed_at.is_none());
+    assert!(matches!(
+        entry.command().unwrap(),
+        AccountCommand::DepositMoney { amount, .. } if amount == Decimal::new(25, 0)
+    ));
+    assert!(matches!(
+        entry.events().unwrap().as_slice(),
+        [AccountEvent::MoneyDeposited { .. }]
+    ));
+}
+
+#[tokio::test]
+async fn test_replay_saves_recorded_command_once() {
+    let (service, event_store, failed_commands) = setup().await;
+
+    let error = service
+        .create_account("Replayed Owner".to_string(), Decimal::new(50, 0))
+        .await
+        .unwrap_err()
+        .to_string();
+    let id = failed_command_id(&error);
+    let account_id = failed_commands.get(id).await.unwrap().unwrap().aggregate_id;
+    assert!(event_store.get_account(account_id).await.unwrap().is_none());
+
+    // A replay while saves still fail is recorded and leaves the command pending
+    assert!(service.replay_failed_command(id).await.is_err());
+    let entry = failed_commands.get(id).await.unwrap().unwrap();
+    assert_eq!(entry.replay_attempts, 1);
+    assert!(entry.last_replay_error.unwrap().contains(INJECTED_ERROR));
+    assert!(entry.replayed_at.is_none());
+
+    event_store.failing.store(false, Ordering::SeqCst);
+    service.replay_failed_command(id).await.unwrap();
+
+    let account = event_store.get_account(account_id).await.unwrap().unwrap();
+    assert_eq!(account.owner_name, "Replayed Owner");
+    assert_eq!(account.balance, Decimal::new(50, 0));
+    assert!(failed_commands
+        .get(id)
+        .await
+        .unwrap()
+        .unwrap()
+        .replayed_at
+        .is_some());
+
+    // Replaying twice would apply the command twice
+    assert!(service.replay_failed_command(id).await.is_err());
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::{
+        event_store::{EventStore, EventStoreTrait},
+        in_memory_event_store::InMemoryEventStore,
+    },
+};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use uuid::Uuid;
+
+async fn postgres_store() -> Arc<dyn EventStoreTrait> {
+    let pool = common::pool().await;
+    Arc::new(EventStore::new(pool))
+}
+
+fn opened(account_id: Uuid) -> AccountEvent {
+    AccountEvent::AccountCreated {
+        account_id,
+        owner_name: "Global Position Test".to_string(),
+        initial_balance: Decimal::new(100, 0),
+        owner_user_id: None,
+        account_type: Default::default(),
+        rules: Default::default(),
+    }
+}
+
+fn deposited(account_id: Uuid) -> AccountEvent {
+    AccountEvent::MoneyDeposited {
+        account_id,
+        amount: Decimal::new(10, 0),
+        transaction_id: Uuid::new_v4(),
+    }
+}
+
+async fn last_position(event_store: &dyn EventStoreTrait, account_id: Uuid) -> i64 {
+    let events = event_store.get_events(account_id, None).await.unwrap();
+    events.last().unwrap().global_position
+}
+
+/// Interleaves writes to two accounts and returns the positions in write order.
+async fn interleaved_writes(event_store: &dyn EventStoreTrait) -> (Uuid, Uuid, Vec<i64>) {
+    let first = Uuid::new_v4();
+    let second = Uuid::new_v4();
+    let mut positions = Vec::new();
+
+    event_store
+        .save_events(first, vec![opened(first)], 0)
+        .await
+        .unwrap();
+    positions.push(last_position(event_store, first).await);
+    event_store
+        .save_events(second, vec![opened(second), deposited(second)], 0)
+        .await
+        .unwrap();
+    positions.push(last_position(event_store, second).await);
+    event_store
+        .save_events(first, vec![deposited(first)], 1)
+        .await
+        .unwrap();
+    positions.push(last_position(event_store, first).await);
+
+    (first, second, positions)
+}
+
+#[tokio::test]
+async fn test_positions_increase_across_aggregates() {
+    let event_store = postgres_store().await;
+    let (first, second, positions) = interleaved_writes(event_store.as_ref()).await;
+    assert!(positions.windows(2).all(|w| w[0] < w[1]), "{:?}", positions);
+
+    // A catch-up read sees both accounts' events in the order they were written
+    let start = event_store.get_events(first, None).await.unwrap()[0].global_position - 1;
+    let since = event_store.get_events_since(start, 1000).await.unwrap();
+    assert!(since
+        .windows(2)
+        .all(|w| w[0].global_position < w[1].global_position));
+    let ours: Vec<(Uuid, i64)> = since
+        .iter()
+        .filter(|e| e.aggregate_id == first || e.aggregate_id == second)
+        .map(|e| (e.aggregate_id, e.version))
+        .collect();
+    assert_eq!(ours, vec![(first, 1), (second, 1), (second, 2), (first, 2)]);
+
+    let limited = event_store.get_events_since(start, 2).await.unwrap();
+    assert_eq!(limited.len(), 2);
+    assert_eq!(limited[0].global_position, since[0].global_position);
+}
+
+#[tokio::test]
+async fn test_concurrent_writes_are_read_back_in_position_order() {
+    let event_store = postgres_store().await;
+    let marker = Uuid::new_v4();
+    event_store
+        .save_events(marker, vec![opened(marker)], 0)
+        .await
+        .unwrap();
+    let start = last_position(event_store.as_ref(), marker).await;
+
+    let accounts: Vec<Uuid> = (0..10).map(|_| Uuid::new_v4()).collect();
+    futures::future::try_join_all(accounts.iter().map(|id| {
+        let event_store = event_store.clone();
+        async move {
+            event_store
+                .save_events(*id, vec![opened(*id), deposited(*id)], 0)
+                .await
+        }
+    }))
+    .await
+    .unwrap();
+
+    let since = event_store.get_events_since(start, 1000).await.unwrap();
+    assert!(since
+        .windows(2)
+        .all(|w| w[0].global_position < w[1].global_position));
+    for id in &accounts {
+        let versions: Vec<i64> = since
+            .iter()
+            .filter(|e| e.aggregate_id == *id)
+            .map(|e| e.version)
+            .collect();
+        assert_eq!(versions, vec![1, 2]);
+    }
+}
+
+#[tokio::test]
+async fn test_in_memory_positions_increase_across_aggregates() {
+    let event_store = InMemoryEventStore::new();
+    let (first, second, positions) = interleaved_writes(&event_store).await;
+    assert_eq!(positions, vec![1, 3, 4]);
+
+    let since = event_store.get_events_since(1, 10).await.unwrap();
+    assert_eq!(
+        since
+            .iter()
+            .map(|e| (e.aggregate_id, e.version))
+            .collect::<Vec<_>>(),
+        vec![(second, 1), (second, 2), (first, 2)]
+    );
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use banking_es::{
+    application::services::AccountService,
+    domain::{AccountError, AccountType, InitialBalancePolicy, InitialBalanceViolation},
+    infrastructure::{
+        cache_service::{CacheConfig, CacheService, CacheServiceTrait},
+        config::InitialBalancePolicyConfig,
+        event_store::EventStoreTrait,
+        in_memory_event_store::InMemoryEventStore,
+        middleware::RequestMiddleware,
+        projections::{ProjectionStore, ProjectionStoreTrait},
+        redis_abstraction::RealRedisClient,
+        repository::{AccountRepository, AccountRepositoryTrait},
+    },
+};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+fn bounded(min: i64, max: i64) -> InitialBalancePolicy {
+    InitialBalancePolicy {
+        min: Some(Decimal::new(min, 0)),
+        max: Some(Decimal::new(max, 0)),
+        allow_nonzero: true,
+    }
+}
+
+#[test]
+fn test_policy_rejects_balances_outside_its_bounds() {
+    let policy = bounded(10, 1_000);
+
+    assert!(policy.check(Decimal::new(10, 0)).is_ok());
+    assert!(policy.check(Decimal::new(1_000, 0)).is_ok());
+    assert!(matches!(
+        policy.check(Decimal::new(9, 0)),
+        Err(AccountError::InitialBalanceNotAllowed {
+            violation: InitialBalanceViolation::BelowMinimum { min },
+            ..
+        }) if min == Decimal::new(10, 0)
+    ));
+    assert!(matches!(
+        policy.check(Decimal::new(1_001, 0)),
+        Err(AccountError::InitialBalanceNotAllowed {
+            violation: InitialBalanceViolation::AboveMaximum { max },
+            ..
+        }) if max == Decimal::new(1_000, 0)
+    ));
+}
+
+#[test]
+fn test_policy_can_require_a_zero_opening_balance() {
+    let policy = InitialBalancePolicy {
+        allow_nonzero: false,
+        ..InitialBalancePolicy::default()
+    };
+
+    assert!(policy.check(Decimal::ZERO).is_ok());
+    assert!(matches!(
+        policy.check(Decimal::ONE),
+        Err(AccountError::InitialBalanceNotAllowed {
+            violation: InitialBalanceViolation::NonzeroNotAllowed,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_type_policy_overrides_the_default() {
+    let policies = InitialBalancePolicyConfig::default()
+        .with_default(bounded(0, 100))
+        .with_policy(AccountType::Savings, bounded(50, 10_000));
+
+    assert_eq!(policies.policy_for(AccountType::Checking), &bounded(0, 100));
+    assert_eq!(
+        policies.policy_for(AccountType::Savings),
+        &bounded(50, 10_000)
+    );
+}
+
+async fn service(policies: InitialBalancePolicyConfig) -> AccountService {
+    let pool = common::pool().await;
+
+    let event_store = Arc::new(InMemoryEventStore::new());
+    let repository = Arc::new(AccountRepository::new(
+        event_store as Arc<dyn EventStoreTrait + 'static>,
+    ));
+    let redis_client = redis::Client::open("redis://localhost:6379").unwrap();
+    AccountService::new(
+        repository as Arc<dyn AccountRepositoryTrait + 'static>,
+        Arc::new(ProjectionStore::new(pool)) as Arc<dyn ProjectionStoreTrait + 'static>,
+        Arc::new(CacheService::new(
+            RealRedisClient::new(redis_client, None),
+            CacheConfig::default(),
+        )) as Arc<dyn CacheServiceTrait + 'static>,
+        Arc::new(RequestMiddleware::default()),
+        100,
+    )
+    .with_initial_balance_policies(policies)
+}
+
+#[tokio::test]
+async fn test_create_account_enforces_the_configured_policy() {
+    let service =
+        service(InitialBalancePolicyConfig::default().with_default(bounded(10, 1_000))).await;
+
+    assert!(matches!(
+        service
+            .create_account("Below Minimum".to_string(), Decimal::new(5, 0))
+            .await,
+        Err(AccountError::InitialBalanceNotAllowed {
+            violation: InitialBalanceViolation::BelowMinimum { .. },
+            ..
+        })
+    ));
+    assert!(matches!(
+        service
+            .create_account("Above Maximum".to_string(), Decimal::new(5_000, 0))
+            .await,
+        Err(AccountError::InitialBalanceNotAllowed {
+            violation: InitialBalanceViolation::AboveMaximum { .. },
+            ..
+        })
+    ));
+}
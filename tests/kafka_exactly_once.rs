@@ -0,0 +1,114 @@
+//! Exactly-once publishing tests. These need a running Kafka broker and are only
+//! compiled with `cargo test --features kafka-integration-tests`.
+#![cfg(feature = "kafka-integration-tests")]
+
+use banking_es::domain::AccountEvent;
+use banking_es::infrastructure::kafka_abstraction::{
+    EventBatch, KafkaConfig, KafkaConsumer, KafkaProducer,
+};
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use rust_decimal::Decimal;
+use std::time::Duration;
+use uuid::Uuid;
+
+fn test_config() -> KafkaConfig {
+    let run_id = Uuid::new_v4();
+    KafkaConfig {
+        bootstrap_servers: std::env::var("KAFKA_BOOTSTRAP_SERVERS")
+            .unwrap_or_else(|_| "localhost:9092".to_string()),
+        group_id: format!("banking-es-eos-test-{}", run_id),
+        topic_prefix: format!("banking-es-eos-test-{}", run_id),
+        transactional_id: Some(format!("banking-es-eos-test-{}", run_id)),
+        auto_offset_reset: "earliest".to_string(),
+        ..Default::default()
+    }
+}
+
+fn test_batch(account_id: Uuid) -> EventBatch {
+    EventBatch {
+        account_id,
+        events: vec![AccountEvent::MoneyDeposited {
+            account_id,
+            amount: Decimal::new(100, 0),
+            transaction_id: Uuid::new_v4(),
+        }],
+        version: 1,
+        timestamp: chrono::Utc::now(),
+        global_position: None,
+        partition: None,
+        offset: None,
+    }
+}
+
+async fn count_batches_for(config: &KafkaConfig, account_id: Uuid) -> usize {
+    let consumer = KafkaConsumer::new(config.clone()).expect("Failed to create consumer");
+    consumer
+        .subscribe_to_events()
+        .await
+        .expect("Failed to subscribe");
+
+    let mut count = 0;
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(15);
+    while tokio::time::Instant::now() < deadline {
+        if let Ok(Some(batch)) = consumer.poll_events().await {
+            if batch.account_id == account_id {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+#[tokio::test]
+async fn test_no_duplicates_after_producer_retry() {
+    let config = test_config();
+    let account_id = Uuid::new_v4();
+    let batch = test_batch(account_id);
+
+    // First attempt: a producer sends the batch inside a transaction and dies before
+    // committing.
+    let crashed: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", &config.bootstrap_servers)
+        .set("transactional.id", config.transactional_id.as_ref().unwrap())
+        .create()
+        .expect("Failed to create producer");
+    crashed
+        .init_transactions(Duration::from_secs(30))
+        .expect("Failed to init transactions");
+    crashed.begin_transaction().expect("Failed to begin transaction");
+    let payload = serde_json::to_vec(&batch).unwrap();
+    crashed
+        .send(
+            FutureRecord::to(&format!("{}-events", config.topic_prefix))
+                .key(&account_id.to_string())
+                .payload(&payload),
+            Duration::from_secs(5),
+        )
+        .await
+        .expect("Failed to send record");
+    drop(crashed);
+
+    // Retry: a new producer with the same transactional id fences the old one, which
+    // aborts its open transaction, and publishes the batch again.
+    let producer = KafkaProducer::new(config.clone()).expect("Failed to create producer");
+    producer
+        .send_event_batches_transactional(vec![batch], None)
+        .await
+        .expect("Transactional publish failed");
+
+    assert_eq!(count_batches_for(&config, account_id).await, 1);
+}
+
+#[tokio::test]
+async fn test_transactional_publish_requires_transactional_id() {
+    let config = KafkaConfig {
+        transactional_id: None,
+        ..test_config()
+    };
+    let producer = KafkaProducer::new(config).expect("Failed to create producer");
+    let result = producer
+        .send_event_batches_transactional(vec![test_batch(Uuid::new_v4())], None)
+        .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use banking_es::domain::AccountEvent;
+use banking_es::infrastructure::{
+    event_store::{EventStore, EventStoreTrait},
+    kafka_abstraction::{KafkaConfig, KafkaProducer},
+    outbox::{OutboxConfig, OutboxPublisher, OutboxStore},
+    repository::{AccountRepository, AccountRepositoryTrait},
+};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Nothing listens on port 1, and the short message timeout makes deliveries fail fast.
+fn unreachable_kafka_config() -> KafkaConfig {
+    KafkaConfig {
+        bootstrap_servers: "127.0.0.1:1".to_string(),
+        topic_prefix: format!("banking-es-outbox-test-{}", Uuid::new_v4()),
+        message_timeout_ms: 1000,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_unreachable_broker_is_reported_as_kafka_unavailable() {
+    let producer =
+        KafkaProducer::new(unreachable_kafka_config()).expect("Producer construction is non-fatal");
+    let account_id = Uuid::new_v4();
+
+    let result = producer
+        .send_event_batch(
+            account_id,
+            vec![AccountEvent::AccountClosed {
+                account_id,
+                reason: "test".to_string(),
+            }],
+            1,
+        )
+        .await;
+
+    let error = result.expect_err("Sending to an unreachable broker should fail");
+    assert!(error.is_unavailable(), "unexpected error: {}", error);
+    assert!(!producer.is_available());
+}
+
+#[tokio::test]
+async fn test_writes_commit_and_enqueue_to_outbox_when_kafka_is_unreachable() {
+    let pool = common::pool().await;
+    let producer = KafkaProducer::new(unreachable_kafka_config()).unwrap();
+    let outbox = OutboxStore::new(pool.clone());
+    let publisher = Arc::new(OutboxPublisher::new(
+        producer,
+        outbox.clone(),
+        OutboxConfig::default(),
+    ));
+
+    let event_store = Arc::new(EventStore::new(pool.clone()));
+    let repository = AccountRepository::new(event_store.clone() as Arc<dyn EventStoreTrait>)
+        .with_event_publisher(publisher.clone());
+
+    let account_id = Uuid::new_v4();
+    repository
+        .save_batched(
+            account_id,
+            0,
+            vec![AccountEvent::AccountCreated {
+                account_id,
+                owner_name: "Outbox Test".to_string(),
+                initial_balance: Decimal::new(100, 0),
+                owner_user_id: None,
+                account_type: Default::default(),
+                rules: Default::default(),
+            }],
+        )
+        .await
+        .expect("Write should succeed while Kafka is down");
+    repository
+        .save_batched(
+            account_id,
+            1,
+            vec![AccountEvent::MoneyDeposited {
+                account_id,
+                amount: Decimal::new(25, 0),
+                transaction_id: Uuid::new_v4(),
+            }],
+        )
+        .await
+        .expect("Write should succeed while Kafka is down");
+
+    let stored = event_store.get_events(account_id, None).await.unwrap();
+    assert_eq!(stored.len(), 2);
+
+    let pending = outbox.fetch_pending_for_aggregate(account_id).await.unwrap();
+    assert_eq!(
+        pending.iter().map(|entry| entry.version).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    let queued: Vec<AccountEvent> = serde_json::from_value(pending[1].events.clone()).unwrap();
+    assert!(matches!(
+        queued.as_slice(),
+        [AccountEvent::MoneyDeposited { amount, .. }] if *amount == Decimal::new(25, 0)
+    ));
+
+    let health = publisher.health().await;
+    assert!(health.enabled);
+    assert!(health.outbox_pending.unwrap_or_default() >= 2);
+
+    sqlx::query("DELETE FROM kafka_outbox WHERE aggregate_id = $1")
+        .bind(account_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM events WHERE aggregate_id = $1")
+        .bind(account_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
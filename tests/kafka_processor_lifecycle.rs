@@ -0,0 +1,108 @@
+mod common;
+
+use banking_es::infrastructure::{
+    cache_service::CacheServiceTrait,
+    event_store::EventStoreTrait,
+    in_memory_event_store::InMemoryEventStore,
+    kafka_abstraction::KafkaConfig,
+    kafka_event_processor::KafkaEventProcessor,
+    projections::{ProjectionStore, ProjectionStoreTrait},
+};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn processor(config: KafkaConfig) -> KafkaEventProcessor {
+    let database_url = common::database_url();
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect_lazy(&database_url)
+        .unwrap();
+
+    let event_store: Arc<dyn EventStoreTrait + Send + Sync> = Arc::new(InMemoryEventStore::new());
+    let projections: Arc<dyn ProjectionStoreTrait + Send + Sync> =
+        Arc::new(ProjectionStore::new(pool));
+    let cache: Arc<dyn CacheServiceTrait + Send + Sync> = Arc::new(common::NoopCache::default());
+    KafkaEventProcessor::new(config, &event_store, &projections, &cache).unwrap()
+}
+
+#[tokio::test]
+async fn test_stop_ends_the_consume_loop() {
+    let processor = processor(KafkaConfig {
+        enabled: false,
+        ..Default::default()
+    });
+    assert!(!processor.is_running());
+
+    let handle = processor.start();
+    assert!(processor.is_running());
+    // A second loop over the same consumer is refused
+    assert!(processor.start().await.unwrap().is_err());
+
+    tokio::time::timeout(Duration::from_secs(5), processor.stop())
+        .await
+        .expect("stop waits for the loop to exit");
+    assert!(!processor.is_running());
+    handle.await.unwrap().unwrap();
+
+    // Stopping again is a no-op
+    processor.stop().await;
+}
+
+/// Needs a running Kafka broker; compiled with `--features kafka-integration-tests`.
+#[cfg(feature = "kafka-integration-tests")]
+#[tokio::test]
+async fn test_stop_commits_the_offset_of_the_processed_event() {
+    use banking_es::infrastructure::kafka_abstraction::{KafkaConsumer, KafkaProducer};
+    use rust_decimal::Decimal;
+
+    let run_id = Uuid::new_v4();
+    let config = KafkaConfig {
+        bootstrap_servers: std::env::var("KAFKA_BOOTSTRAP_SERVERS")
+            .unwrap_or_else(|_| "localhost:9092".to_string()),
+        group_id: format!("banking-es-lifecycle-test-{}", run_id),
+        topic_prefix: format!("banking-es-lifecycle-test-{}", run_id),
+        auto_offset_reset: "earliest".to_string(),
+        ..Default::default()
+    };
+    let account_id = Uuid::new_v4();
+    KafkaProducer::new(config.clone())
+        .unwrap()
+        .send_event_batch(
+            account_id,
+            vec![AccountEvent::MoneyDeposited {
+                account_id,
+                amount: Decimal::new(100, 0),
+                transaction_id: Uuid::new_v4(),
+            }],
+            0,
+        )
+        .await
+        .unwrap();
+
+    let processor = processor(config.clone());
+    let handle = processor.start();
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(30);
+    while processor.get_processing_metrics().await.events_processed == 0 {
+        assert!(
+            tokio::time::Instant::now() < deadline,
+            "event was not processed"
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    processor.stop().await;
+    assert!(!processor.is_running());
+    tokio::time::timeout(Duration::from_secs(5), handle)
+        .await
+        .expect("consume loop exits after stop")
+        .unwrap()
+        .unwrap();
+
+    let committed = KafkaConsumer::new(config)
+        .unwrap()
+        .committed_event_offsets(Duration::from_secs(10))
+        .await
+        .unwrap();
+    assert_eq!(committed.values().sum::<i64>(), 1);
+}
@@ -0,0 +1,160 @@
+use banking_es::infrastructure::kafka_abstraction::{StartOffset, StartOffsetContext};
+use rdkafka::{Offset, TopicPartitionList};
+
+const TOPIC: &str = "banking-es-events";
+
+fn assignment(partitions: &[i32]) -> TopicPartitionList {
+    let mut tpl = TopicPartitionList::new();
+    for partition in partitions {
+        tpl.add_partition(TOPIC, *partition);
+    }
+    tpl
+}
+
+fn offset_of(tpl: &TopicPartitionList, topic: &str, partition: i32) -> Offset {
+    tpl.find_partition(topic, partition).unwrap().offset()
+}
+
+fn no_resolve(_: TopicPartitionList) -> rdkafka::error::KafkaResult<TopicPartitionList> {
+    panic!("only timestamp starts are resolved")
+}
+
+#[test]
+fn test_parse_start_offset() {
+    assert_eq!("earliest".parse(), Ok(StartOffset::Earliest));
+    assert_eq!("LATEST".parse(), Ok(StartOffset::Latest));
+    assert_eq!(
+        "from_timestamp:1700000000000".parse(),
+        Ok(StartOffset::FromTimestamp(1_700_000_000_000))
+    );
+    assert!("from_timestamp:soon".parse::<StartOffset>().is_err());
+}
+
+#[test]
+fn test_first_assignment_seeks_to_start_offset() {
+    let context = StartOffsetContext::new(TOPIC.to_string(), Some(StartOffset::Earliest));
+    let mut assigned = assignment(&[0, 1]);
+    assigned.add_partition("banking-es-cache", 0);
+
+    let positions = context
+        .initial_assignment(&assigned, no_resolve)
+        .unwrap()
+        .expect("new partitions are positioned");
+    assert_eq!(offset_of(&positions, TOPIC, 0), Offset::Beginning);
+    assert_eq!(offset_of(&positions, TOPIC, 1), Offset::Beginning);
+    // Other topics keep their committed offsets
+    assert_eq!(offset_of(&positions, "banking-es-cache", 0), Offset::Stored);
+
+    // Reassigned after a rebalance: resume from the committed offsets
+    assert!(context
+        .initial_assignment(&assignment(&[0, 1]), no_resolve)
+        .unwrap()
+        .is_none());
+
+    // Only a partition new to this consumer is moved
+    let positions = context
+        .initial_assignment(&assignment(&[1, 2]), no_resolve)
+        .unwrap()
+        .unwrap();
+    assert_eq!(offset_of(&positions, TOPIC, 1), Offset::Stored);
+    assert_eq!(offset_of(&positions, TOPIC, 2), Offset::Beginning);
+}
+
+#[test]
+fn test_latest_and_timestamp_positions() {
+    let latest = StartOffsetContext::new(TOPIC.to_string(), Some(StartOffset::Latest));
+    let positions = latest
+        .initial_assignment(&assignment(&[0]), no_resolve)
+        .unwrap()
+        .unwrap();
+    assert_eq!(offset_of(&positions, TOPIC, 0), Offset::End);
+
+    let timestamp = 1_700_000_000_000;
+    let context = StartOffsetContext::new(
+        TOPIC.to_string(),
+        Some(StartOffset::FromTimestamp(timestamp)),
+    );
+    let positions = context
+        .initial_assignment(&assignment(&[0]), |starts| {
+            assert_eq!(offset_of(&starts, TOPIC, 0), Offset::Offset(timestamp));
+            // The broker answers with the first offset at or after the timestamp
+            let mut resolved = TopicPartitionList::new();
+            resolved.add_partition_offset(TOPIC, 0, Offset::Offset(42))?;
+            Ok(resolved)
+        })
+        .unwrap()
+        .unwrap();
+    assert_eq!(offset_of(&positions, TOPIC, 0), Offset::Offset(42));
+}
+
+#[test]
+fn test_no_start_offset_keeps_committed_positions() {
+    let context = StartOffsetContext::new(TOPIC.to_string(), None);
+    assert!(context
+        .initial_assignment(&assignment(&[0]), no_resolve)
+        .unwrap()
+        .is_none());
+}
+
+/// Needs a running Kafka broker; compiled with `--features kafka-integration-tests`.
+#[cfg(feature = "kafka-integration-tests")]
+#[tokio::test]
+async fn test_earliest_replays_events_the_group_already_committed() {
+    use banking_es::domain::AccountEvent;
+    use banking_es::infrastructure::kafka_abstraction::{
+        EventBatch, KafkaConfig, KafkaConsumer, KafkaProducer,
+    };
+    use rust_decimal::Decimal;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    async fn next_batch(consumer: &KafkaConsumer) -> EventBatch {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(30);
+        loop {
+            if let Some(batch) = consumer.poll_events().await.unwrap() {
+                return batch;
+            }
+            assert!(tokio::time::Instant::now() < deadline, "no event consumed");
+        }
+    }
+
+    let run_id = Uuid::new_v4();
+    let config = KafkaConfig {
+        bootstrap_servers: std::env::var("KAFKA_BOOTSTRAP_SERVERS")
+            .unwrap_or_else(|_| "localhost:9092".to_string()),
+        group_id: format!("banking-es-start-offset-test-{}", run_id),
+        topic_prefix: format!("banking-es-start-offset-test-{}", run_id),
+        auto_offset_reset: "earliest".to_string(),
+        ..Default::default()
+    };
+    let account_id = Uuid::new_v4();
+    KafkaProducer::new(config.clone())
+        .unwrap()
+        .send_event_batch(
+            account_id,
+            vec![AccountEvent::MoneyDeposited {
+                account_id,
+                amount: Decimal::new(100, 0),
+                transaction_id: Uuid::new_v4(),
+            }],
+            0,
+        )
+        .await
+        .unwrap();
+
+    // Consume and commit the event, as a previous run of the group would have
+    {
+        let consumer = KafkaConsumer::new(config.clone()).unwrap();
+        consumer.subscribe_to_events().await.unwrap();
+        assert_eq!(next_batch(&consumer).await.account_id, account_id);
+        consumer.commit_offsets().await.unwrap();
+    }
+
+    let consumer = KafkaConsumer::new(KafkaConfig {
+        start_offset: Some(StartOffset::Earliest),
+        ..config
+    })
+    .unwrap();
+    consumer.subscribe_to_events().await.unwrap();
+    assert_eq!(next_batch(&consumer).await.account_id, account_id);
+}
@@ -0,0 +1,144 @@
+use banking_es::domain::AccountEvent;
+use banking_es::infrastructure::kafka_abstraction::{EventBatch, KafkaConfig};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+fn events(account_id: Uuid) -> Vec<AccountEvent> {
+    vec![
+        AccountEvent::AccountCreated {
+            account_id,
+            owner_name: "Topic Routing".to_string(),
+            initial_balance: Decimal::ZERO,
+            owner_user_id: None,
+            account_type: Default::default(),
+            rules: Default::default(),
+        },
+        AccountEvent::MoneyDeposited {
+            account_id,
+            amount: Decimal::new(25, 0),
+            transaction_id: Uuid::new_v4(),
+        },
+        AccountEvent::MoneyDeposited {
+            account_id,
+            amount: Decimal::new(5, 0),
+            transaction_id: Uuid::new_v4(),
+        },
+        AccountEvent::MoneyWithdrawn {
+            account_id,
+            amount: Decimal::new(10, 0),
+            transaction_id: Uuid::new_v4(),
+            withdrawn_at: None,
+        },
+    ]
+}
+
+fn routed_config(topic_prefix: &str) -> KafkaConfig {
+    KafkaConfig {
+        topic_prefix: topic_prefix.to_string(),
+        event_topic_routes: KafkaConfig::parse_event_topic_routes(&format!(
+            "MoneyDeposited={0}-money.deposited, AccountCreated={0}-account.lifecycle",
+            topic_prefix
+        )),
+        ..KafkaConfig::default()
+    }
+}
+
+#[test]
+fn test_events_are_routed_by_type_with_the_rest_on_the_default_topic() {
+    let config = routed_config("routing");
+    assert_eq!(
+        config.event_topics(),
+        vec![
+            "routing-events",
+            "routing-account.lifecycle",
+            "routing-money.deposited"
+        ]
+    );
+
+    let account_id = Uuid::new_v4();
+    let batches = EventBatch::chunked_by(account_id, events(account_id), 4, Some(40), 500, |e| {
+        config.topic_for_event(e)
+    });
+    let routed: Vec<(&str, usize, i64, Option<i64>)> = batches
+        .iter()
+        .map(|(topic, batch)| {
+            (
+                topic.as_str(),
+                batch.events.len(),
+                batch.version,
+                batch.global_position,
+            )
+        })
+        .collect();
+    // Consecutive events for the same topic stay together; each message carries the
+    // version of its last event, so per-topic order is the account's order
+    assert_eq!(
+        routed,
+        vec![
+            ("routing-account.lifecycle", 1, 1, Some(37)),
+            ("routing-money.deposited", 2, 3, Some(39)),
+            ("routing-events", 1, 4, Some(40)),
+        ]
+    );
+
+    // Without routes every event goes to the one events topic
+    let unrouted = KafkaConfig {
+        topic_prefix: "routing".to_string(),
+        event_topic_routes: HashMap::new(),
+        ..KafkaConfig::default()
+    };
+    assert_eq!(unrouted.event_topics(), vec!["routing-events"]);
+    let batches = EventBatch::chunked_by(account_id, events(account_id), 4, None, 500, |e| {
+        unrouted.topic_for_event(e)
+    });
+    assert_eq!(batches.len(), 1);
+}
+
+/// Needs a running Kafka broker; only compiled with `--features kafka-integration-tests`.
+#[cfg(feature = "kafka-integration-tests")]
+#[tokio::test]
+async fn test_money_deposited_is_produced_to_its_mapped_topic() {
+    use banking_es::infrastructure::kafka_abstraction::KafkaProducer;
+    use futures::StreamExt;
+    use rdkafka::config::ClientConfig;
+    use rdkafka::consumer::{Consumer, StreamConsumer};
+    use rdkafka::Message;
+    use std::time::Duration;
+
+    let run_id = Uuid::new_v4();
+    let config = KafkaConfig {
+        bootstrap_servers: std::env::var("KAFKA_BOOTSTRAP_SERVERS")
+            .unwrap_or_else(|_| "localhost:9092".to_string()),
+        ..routed_config(&format!("banking-es-routing-test-{}", run_id))
+    };
+    let deposits_topic = format!("{}-money.deposited", config.topic_prefix);
+
+    let account_id = Uuid::new_v4();
+    let producer = KafkaProducer::new(config.clone()).expect("Failed to create producer");
+    producer
+        .send_event_batch(account_id, events(account_id), 4)
+        .await
+        .expect("Failed to publish");
+
+    let consumer: StreamConsumer = ClientConfig::new()
+        .set("bootstrap.servers", &config.bootstrap_servers)
+        .set("group.id", format!("banking-es-routing-test-{}", run_id))
+        .set("auto.offset.reset", "earliest")
+        .create()
+        .expect("Failed to create consumer");
+    consumer.subscribe(&[&deposits_topic]).unwrap();
+
+    let message = tokio::time::timeout(Duration::from_secs(15), consumer.stream().next())
+        .await
+        .expect("No message on the mapped topic")
+        .unwrap()
+        .unwrap();
+    assert_eq!(message.key(), Some(account_id.to_string().as_bytes()));
+    let batch: EventBatch = serde_json::from_slice(message.payload().unwrap()).unwrap();
+    assert_eq!(batch.version, 3);
+    assert!(batch
+        .events
+        .iter()
+        .all(|event| matches!(event, AccountEvent::MoneyDeposited { .. })));
+}
@@ -0,0 +1,64 @@
+use banking_es::infrastructure::keyed_locks::KeyedLocks;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_entry_is_reclaimed_once_every_guard_drops() {
+    let locks = KeyedLocks::new();
+    let account_id = Uuid::new_v4();
+
+    let guard = locks.lock(account_id).await;
+    assert_eq!(locks.len(), 1);
+    drop(guard);
+    assert!(locks.is_empty());
+
+    // Accounts touched once leave nothing behind
+    for _ in 0..1_000 {
+        drop(locks.lock(Uuid::new_v4()).await);
+    }
+    assert!(locks.is_empty());
+}
+
+#[tokio::test]
+async fn test_waiters_keep_the_entry_until_they_are_done() {
+    let locks = Arc::new(KeyedLocks::new());
+    let account_id = Uuid::new_v4();
+
+    let guard = locks.lock(account_id).await;
+    let waiter = tokio::spawn({
+        let locks = locks.clone();
+        async move {
+            let guard = locks.lock(account_id).await;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            drop(guard);
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    // The holder leaves while someone is queued: the entry must survive for them
+    drop(guard);
+    assert_eq!(locks.len(), 1);
+    assert!(locks.try_lock(account_id).is_none());
+
+    waiter.await.unwrap();
+    assert!(locks.is_empty());
+}
+
+#[tokio::test]
+async fn test_same_key_is_serialized_and_other_keys_are_not() {
+    let locks = KeyedLocks::new();
+    let first = Uuid::new_v4();
+    let second = Uuid::new_v4();
+
+    let guard = locks.lock(first).await;
+    assert_eq!(guard.key(), &first);
+    assert!(locks.try_lock(first).is_none());
+    let other = locks.try_lock(second).expect("another key is free");
+    assert_eq!(locks.len(), 2);
+
+    drop(guard);
+    drop(other);
+    assert!(locks.try_lock(first).is_some());
+    assert!(locks.is_empty());
+}
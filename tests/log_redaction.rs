@@ -0,0 +1,108 @@
+use banking_es::infrastructure::{
+    config::{LogFormat, LoggingConfig, AMOUNT_LOG_FIELDS},
+    logging::{self, REDACTED},
+};
+use std::io;
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::fmt::MakeWriter;
+use uuid::Uuid;
+
+/// Collects everything the subscriber writes.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Captured {
+    fn output(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl io::Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Captured {
+    type Writer = Captured;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+fn log_account_created(config: LoggingConfig, account_id: Uuid) -> String {
+    let captured = Captured::default();
+    tracing::subscriber::with_default(logging::subscriber(&config, captured.clone()), || {
+        let span = tracing::info_span!("create_account", owner_name = "Jane Doe");
+        let _guard = span.enter();
+        tracing::info!(
+            account_id = %account_id,
+            owner_name = "Jane Doe",
+            amount = 250,
+            "Account created"
+        );
+    });
+    captured.output()
+}
+
+#[test]
+fn test_json_line_masks_the_sensitive_field() {
+    let account_id = Uuid::new_v4();
+    let output = log_account_created(
+        LoggingConfig {
+            format: LogFormat::Json,
+            redact: true,
+            ..LoggingConfig::default()
+        },
+        account_id,
+    );
+
+    let line: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+    assert_eq!(line["level"], "INFO");
+    assert_eq!(line["fields"]["message"], "Account created");
+    assert_eq!(line["fields"]["owner_name"], REDACTED);
+    assert_eq!(line["fields"]["account_id"], account_id.to_string());
+    // Amounts are only masked when flagged
+    assert_eq!(line["fields"]["amount"], 250);
+    assert_eq!(line["spans"][0]["name"], "create_account");
+    assert!(!output.contains("Jane Doe"));
+}
+
+#[test]
+fn test_flagged_amounts_are_masked() {
+    let mut redacted_fields = vec!["owner_name".to_string()];
+    redacted_fields.extend(AMOUNT_LOG_FIELDS.iter().map(|field| field.to_string()));
+    let output = log_account_created(
+        LoggingConfig {
+            format: LogFormat::Json,
+            redact: true,
+            redacted_fields,
+        },
+        Uuid::new_v4(),
+    );
+
+    let line: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+    assert_eq!(line["fields"]["amount"], REDACTED);
+}
+
+#[test]
+fn test_pretty_output_masks_and_redaction_can_be_turned_off() {
+    let redacted = log_account_created(
+        LoggingConfig {
+            redact: true,
+            ..LoggingConfig::default()
+        },
+        Uuid::new_v4(),
+    );
+    assert!(redacted.contains(&format!("owner_name={}", REDACTED)));
+    assert!(!redacted.contains("Jane Doe"));
+
+    let plain = log_account_created(LoggingConfig::default(), Uuid::new_v4());
+    assert!(plain.contains("Jane Doe"));
+}
@@ -0,0 +1,178 @@
+use async_trait::async_trait;
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::{
+        config::MaintenanceConfig,
+        in_memory_event_store::InMemoryEventStore,
+        maintenance::{
+            MaintenanceError, MaintenanceLock, MaintenanceScheduler, MaintenanceTask, SnapshotTask,
+            MAINTENANCE_LOCK_KEY,
+        },
+    },
+};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+/// Behaves like the Redis lock: a key can be taken again once its TTL has passed.
+#[derive(Default)]
+struct ExpiringLock {
+    expiries: Mutex<HashMap<String, Instant>>,
+    attempts: AtomicU64,
+}
+
+#[async_trait]
+impl MaintenanceLock for ExpiringLock {
+    async fn try_acquire(&self, key: &str, ttl: Duration) -> Result<bool, MaintenanceError> {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+        let now = Instant::now();
+        let mut expiries = self.expiries.lock().unwrap();
+        match expiries.get(key) {
+            Some(expires_at) if *expires_at > now => Ok(false),
+            _ => {
+                expiries.insert(key.to_string(), now + ttl);
+                Ok(true)
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct CountingTask {
+    runs: Mutex<Vec<Instant>>,
+}
+
+#[async_trait]
+impl MaintenanceTask for CountingTask {
+    fn name(&self) -> &'static str {
+        "counting"
+    }
+
+    async fn run(&self) -> anyhow::Result<u64> {
+        self.runs.lock().unwrap().push(Instant::now());
+        Ok(1)
+    }
+}
+
+struct FailingTask;
+
+#[async_trait]
+impl MaintenanceTask for FailingTask {
+    fn name(&self) -> &'static str {
+        "failing"
+    }
+
+    async fn run(&self) -> anyhow::Result<u64> {
+        anyhow::bail!("archive unavailable")
+    }
+}
+
+fn config(interval: Duration) -> MaintenanceConfig {
+    MaintenanceConfig {
+        enabled: true,
+        interval,
+        snapshot_min_events: 2,
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_instances_share_one_run_per_interval() {
+    let interval = Duration::from_secs(60);
+    let lock = Arc::new(ExpiringLock::default());
+    let task = Arc::new(CountingTask::default());
+    let instances: Vec<Arc<MaintenanceScheduler>> = (0..2)
+        .map(|_| {
+            Arc::new(
+                MaintenanceScheduler::new(config(interval), lock.clone()).with_task(task.clone()),
+            )
+        })
+        .collect();
+    let start = Instant::now();
+    let handles: Vec<_> = instances
+        .iter()
+        .map(|scheduler| scheduler.start())
+        .collect();
+
+    tokio::time::sleep(interval * 3 + interval / 2).await;
+
+    // Both instances tried every interval, and the lock let exactly one of them run
+    assert_eq!(lock.attempts.load(Ordering::Relaxed), 6);
+    let runs = task.runs.lock().unwrap().clone();
+    assert_eq!(runs.len(), 3);
+    for (i, ran_at) in runs.iter().enumerate() {
+        assert_eq!(*ran_at - start, interval * (i as u32 + 1));
+    }
+    let started: u64 = instances.iter().map(|scheduler| scheduler.runs()).sum();
+    let skipped: u64 = instances
+        .iter()
+        .map(|scheduler| scheduler.skipped_runs())
+        .sum();
+    assert_eq!((started, skipped), (3, 3));
+    assert!(lock
+        .expiries
+        .lock()
+        .unwrap()
+        .contains_key(MAINTENANCE_LOCK_KEY));
+    for handle in handles {
+        handle.abort();
+    }
+}
+
+#[tokio::test]
+async fn test_a_run_snapshots_large_aggregates_and_reports_each_task() {
+    let event_store = Arc::new(InMemoryEventStore::new());
+    let (large, small) = (Uuid::new_v4(), Uuid::new_v4());
+    for (account_id, deposits) in [(large, 3), (small, 0)] {
+        let mut events = vec![AccountEvent::AccountCreated {
+            account_id,
+            owner_name: "Maintenance Test".to_string(),
+            initial_balance: Decimal::new(10, 0),
+            owner_user_id: None,
+            account_type: Default::default(),
+            rules: Default::default(),
+        }];
+        for _ in 0..deposits {
+            events.push(AccountEvent::MoneyDeposited {
+                account_id,
+                amount: Decimal::new(1, 0),
+                transaction_id: Uuid::new_v4(),
+            });
+        }
+        event_store
+            .save_events(account_id, events, 0)
+            .await
+            .unwrap();
+    }
+
+    let scheduler = MaintenanceScheduler::new(
+        config(Duration::from_secs(60)),
+        Arc::new(ExpiringLock::default()),
+    )
+    .with_task(Arc::new(FailingTask))
+    .with_task(Arc::new(SnapshotTask::new(event_store.clone(), 2)));
+
+    // A failing task doesn't stop the ones after it
+    let summary = scheduler.run_once().await.unwrap().unwrap();
+    let results: Vec<(&str, Result<u64, String>)> = summary
+        .tasks
+        .iter()
+        .map(|task| (task.task, task.result.clone()))
+        .collect();
+    assert_eq!(
+        results,
+        vec![
+            ("failing", Err("archive unavailable".to_string())),
+            ("snapshot", Ok(1))
+        ]
+    );
+    assert!(event_store.get_snapshot(large).await.unwrap().is_some());
+    assert!(event_store.get_snapshot(small).await.unwrap().is_none());
+
+    // The lock is held for the rest of the interval
+    assert!(scheduler.run_once().await.unwrap().is_none());
+    assert_eq!((scheduler.runs(), scheduler.skipped_runs()), (1, 1));
+}
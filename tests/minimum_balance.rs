@@ -0,0 +1,102 @@
+use banking_es::{
+    domain::{Account, AccountCommand, AccountError, AccountEvent, AccountType},
+    infrastructure::config::AccountTemplatesConfig,
+};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// Opens a savings account that must keep 100 and may not overdraw.
+fn open(initial_balance: Decimal) -> Account {
+    let account_id = Uuid::new_v4();
+    let mut rules = AccountTemplatesConfig::default().rules_for(AccountType::Savings);
+    rules.min_balance = Decimal::new(100, 0);
+    rules.overdraft_limit = Decimal::ZERO;
+    let mut account = Account {
+        id: account_id,
+        ..Account::default()
+    };
+    let events = account
+        .handle_command(&AccountCommand::CreateAccount {
+            account_id,
+            owner_name: "Minimum Balance".to_string(),
+            initial_balance,
+            owner_user_id: None,
+            account_type: AccountType::Savings,
+            rules,
+        })
+        .unwrap();
+    for event in &events {
+        account.apply_event(event);
+    }
+    account
+}
+
+fn charge_fee(account: &Account, amount: Decimal) -> Vec<AccountEvent> {
+    account
+        .handle_command(&AccountCommand::ChargeFee {
+            account_id: account.id,
+            amount,
+            reason: "monthly maintenance".to_string(),
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_withdrawal_below_the_minimum_balance_is_blocked() {
+    let account = open(Decimal::new(150, 0));
+    let withdraw = |amount| {
+        account.handle_command(&AccountCommand::WithdrawMoney {
+            account_id: account.id,
+            amount,
+        })
+    };
+
+    assert!(withdraw(Decimal::new(50, 0)).is_ok());
+    // The balance covers it, but only by dipping into the minimum
+    assert!(matches!(
+        withdraw(Decimal::new(51, 0)),
+        Err(AccountError::BelowMinimumBalance { minimum, requested })
+            if minimum == Decimal::new(100, 0) && requested == Decimal::new(51, 0)
+    ));
+    // More than the balance is still insufficient funds
+    assert!(matches!(
+        withdraw(Decimal::new(151, 0)),
+        Err(AccountError::InsufficientFunds { available, .. }) if available == Decimal::new(50, 0)
+    ));
+}
+
+#[test]
+fn test_fee_that_crosses_the_minimum_balance_records_a_breach() {
+    let mut account = open(Decimal::new(110, 0));
+
+    let events = charge_fee(&account, Decimal::new(5, 0));
+    assert_eq!(events.len(), 1);
+    assert!(matches!(events[0], AccountEvent::FeeCharged { .. }));
+    for event in &events {
+        account.apply_event(event);
+    }
+
+    let events = charge_fee(&account, Decimal::new(15, 0));
+    assert!(matches!(events[0], AccountEvent::FeeCharged { .. }));
+    assert!(matches!(
+        events[1],
+        AccountEvent::MinimumBalanceBreached { minimum, balance, .. }
+            if minimum == Decimal::new(100, 0) && balance == Decimal::new(90, 0)
+    ));
+    for event in &events {
+        account.apply_event(event);
+    }
+    assert_eq!(account.balance, Decimal::new(90, 0));
+
+    // Fees aren't blocked by the minimum, and an account already below it isn't warned again
+    let events = charge_fee(&account, Decimal::new(10, 0));
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        account.handle_command(&AccountCommand::ChargeFee {
+            account_id: account.id,
+            amount: Decimal::ZERO,
+            reason: "nothing".to_string(),
+        }),
+        Err(AccountError::InvalidAmount(_))
+    ));
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use async_trait::async_trait;
+use banking_es::domain::AccountEvent;
+use banking_es::infrastructure::{
+    kafka_abstraction::{BankingKafkaError, EventBatch, KafkaConfig, KafkaProducer},
+    outbox::{OutboxConfig, OutboxPublisher, OutboxStore, RelaySink},
+};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Topic that keeps one message per idempotency key, the way a deduplicating consumer
+/// sees the stream. `crash_on` makes the relay die right after a send is acknowledged.
+#[derive(Default)]
+struct KeyedTopic {
+    attempts: Mutex<Vec<String>>,
+    messages: Mutex<HashMap<String, EventBatch>>,
+    crash_on: Mutex<Option<Uuid>>,
+}
+
+#[async_trait]
+impl RelaySink for KeyedTopic {
+    async fn send_batch(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        version: i64,
+        global_position: Option<i64>,
+    ) -> Result<(), BankingKafkaError> {
+        let crash = {
+            let mut crash_on = self.crash_on.lock().unwrap();
+            *crash_on == Some(aggregate_id) && crash_on.take().is_some()
+        };
+        for batch in EventBatch::chunked(aggregate_id, events, version, global_position, 500) {
+            let key = batch.idempotency_key();
+            self.attempts.lock().unwrap().push(key.clone());
+            self.messages.lock().unwrap().entry(key).or_insert(batch);
+        }
+        if crash {
+            panic!("relay crashed after the broker acknowledged account {}", aggregate_id);
+        }
+        Ok(())
+    }
+}
+
+fn publisher(store: &OutboxStore, topic: Arc<KeyedTopic>) -> Arc<OutboxPublisher> {
+    let producer = KafkaProducer::new(KafkaConfig {
+        bootstrap_servers: "127.0.0.1:1".to_string(),
+        ..Default::default()
+    })
+    .unwrap();
+    Arc::new(
+        OutboxPublisher::new(
+            producer,
+            store.clone(),
+            OutboxConfig {
+                relay_batch_size: 10_000,
+                ..OutboxConfig::default()
+            },
+        )
+        .with_relay_sink(topic),
+    )
+}
+
+#[tokio::test]
+async fn test_entry_resent_after_crash_keeps_its_idempotency_key() {
+    let pool = common::pool().await;
+    let store = OutboxStore::new(pool);
+
+    let account_id = Uuid::new_v4();
+    let events = vec![
+        AccountEvent::AccountCreated {
+            account_id,
+            owner_name: "Relay Crash".to_string(),
+            initial_balance: Decimal::new(100, 0),
+            owner_user_id: None,
+            account_type: Default::default(),
+            rules: Default::default(),
+        },
+        AccountEvent::MoneyDeposited {
+            account_id,
+            amount: Decimal::new(25, 0),
+            transaction_id: Uuid::new_v4(),
+        },
+    ];
+    store.enqueue(account_id, 2, &events, None).await.unwrap();
+
+    let topic = Arc::new(KeyedTopic::default());
+    *topic.crash_on.lock().unwrap() = Some(account_id);
+
+    // Published, then the process dies before the entry is marked
+    let first_run = publisher(&store, topic.clone());
+    let crashed = tokio::spawn(async move { first_run.relay_pending().await }).await;
+    assert!(crashed.unwrap_err().is_panic());
+    assert_eq!(
+        store
+            .fetch_pending_for_aggregate(account_id)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // The restarted relay sends the entry again, under the same key
+    let restarted = publisher(&store, topic.clone());
+    restarted.relay_pending().await.unwrap();
+    assert!(store
+        .fetch_pending_for_aggregate(account_id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let key = format!("{}:2", account_id);
+    let attempts = topic.attempts.lock().unwrap().clone();
+    assert_eq!(attempts.iter().filter(|k| **k == key).count(), 2);
+    let messages = topic.messages.lock().unwrap();
+    let account_messages: Vec<&EventBatch> = messages
+        .values()
+        .filter(|batch| batch.account_id == account_id)
+        .collect();
+    assert_eq!(account_messages.len(), 1);
+    assert_eq!(account_messages[0].events.len(), 2);
+}
+
+#[test]
+fn test_idempotency_key_is_stable_across_chunking() {
+    let account_id = Uuid::new_v4();
+    let events: Vec<AccountEvent> = (0..3)
+        .map(|_| AccountEvent::MoneyDeposited {
+            account_id,
+            amount: Decimal::ONE,
+            transaction_id: Uuid::new_v4(),
+        })
+        .collect();
+
+    let keys = |max_events| -> Vec<String> {
+        EventBatch::chunked(account_id, events.clone(), 7, None, max_events)
+            .iter()
+            .map(EventBatch::idempotency_key)
+            .collect()
+    };
+
+    assert_eq!(keys(2), keys(2));
+    assert_eq!(
+        keys(2),
+        vec![format!("{}:6", account_id), format!("{}:7", account_id)]
+    );
+    assert_eq!(keys(500), vec![format!("{}:7", account_id)]);
+}
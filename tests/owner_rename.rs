@@ -0,0 +1,99 @@
+mod common;
+
+use banking_es::{
+    application::services::AccountService,
+    domain::{AccountError, AccountEvent},
+    infrastructure::{
+        cache_service::{CacheConfig, CacheService, CacheServiceTrait},
+        event_store::{EventStore, EventStoreTrait},
+        middleware::RequestMiddleware,
+        projections::{AccountSearch, ProjectionStore, ProjectionStoreTrait},
+        redis_abstraction::RealRedisClient,
+        repository::{AccountRepository, AccountRepositoryTrait},
+    },
+};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use uuid::Uuid;
+
+async fn setup() -> (AccountService, Arc<dyn EventStoreTrait + 'static>) {
+    let pool = common::pool().await;
+
+    let event_store = Arc::new(EventStore::new(pool.clone())) as Arc<dyn EventStoreTrait + 'static>;
+    let repository = Arc::new(AccountRepository::new(event_store.clone()))
+        as Arc<dyn AccountRepositoryTrait + 'static>;
+    let redis_client = redis::Client::open("redis://localhost:6379").unwrap();
+    let service = AccountService::new(
+        repository,
+        Arc::new(ProjectionStore::new(pool)) as Arc<dyn ProjectionStoreTrait + 'static>,
+        Arc::new(CacheService::new(
+            RealRedisClient::new(redis_client, None),
+            CacheConfig::default(),
+        )) as Arc<dyn CacheServiceTrait + 'static>,
+        Arc::new(RequestMiddleware::default()),
+        100,
+    );
+    (service, event_store)
+}
+
+fn search_for(query: &str) -> AccountSearch {
+    AccountSearch {
+        query: query.to_string(),
+        fuzzy: false,
+        owner_user_id: None,
+        metadata: None,
+        limit: 10,
+        offset: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_renamed_owner_is_searchable_and_recorded_in_the_stream() {
+    let (service, event_store) = setup().await;
+    let suffix = Uuid::new_v4().simple().to_string();
+    let old_name = format!("Before Rename {}", suffix);
+    let new_name = format!("After Rename {}", suffix);
+
+    let account_id = service
+        .create_account(old_name.clone(), Decimal::new(100, 0))
+        .await
+        .unwrap();
+    let account = service
+        .rename_owner(account_id, new_name.clone())
+        .await
+        .unwrap();
+    assert_eq!(account.owner_name, new_name);
+
+    let found = service
+        .search_accounts(&search_for(&new_name))
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, account_id);
+    assert!(service
+        .search_accounts(&search_for(&old_name))
+        .await
+        .unwrap()
+        .is_empty());
+
+    // The old name is kept in the account's history
+    let events = event_store.get_events(account_id, None).await.unwrap();
+    assert!(events.iter().any(|event| matches!(
+        event.decode(),
+        Ok(AccountEvent::OwnerNameChanged { old, new, .. }) if old == old_name && new == new_name
+    )));
+}
+
+#[tokio::test]
+async fn test_blank_owner_name_is_rejected() {
+    let (service, _) = setup().await;
+    let account_id = service
+        .create_account("Blank Rename".to_string(), Decimal::ZERO)
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        service.rename_owner(account_id, "   ".to_string()).await,
+        Err(AccountError::InvalidOwnerName(_))
+    ));
+}
@@ -0,0 +1,68 @@
+use banking_es::web::pagination::{Page, PageError, PageParams};
+
+fn params(limit: i64, cursor: Option<String>, include_total: bool) -> PageParams {
+    PageParams {
+        limit: Some(limit),
+        cursor,
+        include_total,
+    }
+}
+
+#[test]
+fn test_pages_of_a_loaded_list_follow_their_cursors_to_the_end() {
+    let items: Vec<u32> = (1..=7).collect();
+    let mut cursor = None;
+    let mut pages = Vec::new();
+    loop {
+        let request = params(3, cursor, true).resolve(50, 500).unwrap();
+        let page = Page::from_all(items.clone(), &request);
+        assert_eq!(page.total_count, Some(7));
+        assert_eq!(page.has_more, page.next_cursor.is_some());
+        pages.push(page.items);
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    assert_eq!(pages, vec![vec![1, 2, 3], vec![4, 5, 6], vec![7]]);
+
+    // A page ending exactly at the last item has nothing after it
+    let request = params(7, None, false).resolve(50, 500).unwrap();
+    let page = Page::from_all(items, &request);
+    assert_eq!(page.items.len(), 7);
+    assert!(!page.has_more && page.next_cursor.is_none());
+    assert_eq!(page.total_count, None);
+}
+
+#[test]
+fn test_lookahead_pages_drop_the_extra_item() {
+    let request = PageParams::default().resolve(2, 10).unwrap();
+    let page = Page::from_lookahead(vec!["a", "b", "c"], &request, None);
+    assert_eq!(page.items, vec!["a", "b"]);
+    assert!(page.has_more);
+
+    let next = params(2, page.next_cursor, false).resolve(2, 10).unwrap();
+    assert_eq!(next.offset, 2);
+    let json = serde_json::to_value(Page::from_lookahead(vec!["c"], &next, None)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({ "items": ["c"], "next_cursor": null, "has_more": false })
+    );
+}
+
+#[test]
+fn test_limits_are_clamped_and_bad_cursors_rejected() {
+    assert_eq!(params(0, None, false).resolve(20, 100).unwrap().limit, 1);
+    assert_eq!(
+        params(5000, None, false).resolve(20, 100).unwrap().limit,
+        100
+    );
+    for cursor in ["not base64!", "bzotMQ", "eDo1"] {
+        assert_eq!(
+            params(10, Some(cursor.to_string()), false).resolve(20, 100),
+            Err(PageError::InvalidCursor),
+            "{}",
+            cursor
+        );
+    }
+}
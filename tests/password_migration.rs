@@ -0,0 +1,171 @@
+mod common;
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use banking_es::infrastructure::{
+    auth::{AuthConfig, AuthService},
+    password::{HashScheme, PasswordHashConfig, PasswordHashing, PasswordVerification},
+    user_repository::{NewUser, UserRepository},
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const PASSWORD: &str = "correct horse battery staple";
+
+/// Hashes the way an older deployment did: Argon2i with a small memory cost.
+fn legacy_hash(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::new(
+        Algorithm::Argon2i,
+        Version::V0x13,
+        Params::new(4096, 3, 1, None).unwrap(),
+    )
+    .hash_password(password.as_bytes(), &salt)
+    .unwrap()
+    .to_string()
+}
+
+#[test]
+fn test_hash_scheme_detection() {
+    let hashing = PasswordHashing::default();
+
+    assert_eq!(
+        hashing.scheme(&hashing.hash(PASSWORD).unwrap()),
+        HashScheme::Current
+    );
+    assert_eq!(
+        hashing.scheme(&legacy_hash(PASSWORD)),
+        HashScheme::LegacyArgon2
+    );
+    assert_eq!(
+        hashing.scheme("$2b$12$R9h/cIPz0gi.URNNX3kh2OPST9/PgBkqquzi.Ss7KIUgO2t0jWMUW"),
+        HashScheme::Bcrypt
+    );
+    assert_eq!(hashing.scheme("plaintext"), HashScheme::Unknown);
+
+    // Argon2id with other costs still needs upgrading
+    let stronger = PasswordHashing::new(PasswordHashConfig {
+        memory_kib: 32768,
+        ..PasswordHashConfig::default()
+    });
+    assert_eq!(
+        stronger.scheme(&hashing.hash(PASSWORD).unwrap()),
+        HashScheme::LegacyArgon2
+    );
+}
+
+#[test]
+fn test_bcrypt_hash_is_verified_and_flagged_for_rehash() {
+    let hashing = PasswordHashing::default();
+    let stored = bcrypt::hash(PASSWORD, 4).unwrap();
+
+    assert_eq!(
+        hashing.verify(PASSWORD, &stored).unwrap(),
+        PasswordVerification::Valid { needs_rehash: true }
+    );
+    assert_eq!(
+        hashing.verify("wrong password", &stored).unwrap(),
+        PasswordVerification::Invalid
+    );
+}
+
+#[tokio::test]
+async fn test_verify_upgrades_bcrypt_hash() {
+    let pool = common::pool().await;
+    let user_repository = Arc::new(UserRepository::new(pool));
+
+    let username = format!("bcrypt_{}", Uuid::new_v4().simple());
+    let email = format!("{}@example.com", username);
+    let user = user_repository
+        .create(&NewUser {
+            username: &username,
+            email: &email,
+            password_hash: bcrypt::hash(PASSWORD, 4).unwrap(),
+            roles: vec!["customer".to_string()],
+        })
+        .await
+        .unwrap();
+
+    let auth_service = AuthService::new(
+        Arc::new(redis::Client::open("redis://localhost:6379").unwrap()),
+        AuthConfig {
+            jwt_secret: "password_migration_secret".to_string(),
+            refresh_token_secret: "password_migration_refresh_secret".to_string(),
+            access_token_expiry: 3600,
+            refresh_token_expiry: 604800,
+            rate_limit_requests: 1000,
+            rate_limit_window: 60,
+            max_failed_attempts: 5,
+            lockout_duration_minutes: 30,
+        },
+        user_repository.clone(),
+    );
+
+    assert!(auth_service.verify(&user, PASSWORD).await.unwrap());
+    let upgraded = user_repository.find_by_id(user.id).await.unwrap().unwrap();
+    assert!(upgraded.password_hash.starts_with("$argon2id$"));
+    assert_eq!(auth_service.credentials_migrated(), 1);
+
+    assert!(auth_service.verify(&upgraded, PASSWORD).await.unwrap());
+    assert!(!auth_service
+        .verify(&upgraded, "wrong password")
+        .await
+        .unwrap());
+    assert_eq!(auth_service.credentials_migrated(), 1);
+
+    user_repository.delete(user.id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_login_upgrades_legacy_hash() {
+    let pool = common::pool().await;
+    let user_repository = Arc::new(UserRepository::new(pool));
+
+    let username = format!("legacy_{}", Uuid::new_v4().simple());
+    let email = format!("{}@example.com", username);
+    let legacy = legacy_hash(PASSWORD);
+    let user = user_repository
+        .create(&NewUser {
+            username: &username,
+            email: &email,
+            password_hash: legacy.clone(),
+            roles: vec!["customer".to_string()],
+        })
+        .await
+        .unwrap();
+
+    let auth_service = AuthService::new(
+        Arc::new(redis::Client::open("redis://localhost:6379").unwrap()),
+        AuthConfig {
+            jwt_secret: "password_migration_secret".to_string(),
+            refresh_token_secret: "password_migration_refresh_secret".to_string(),
+            access_token_expiry: 3600,
+            refresh_token_expiry: 604800,
+            rate_limit_requests: 1000,
+            rate_limit_window: 60,
+            max_failed_attempts: 5,
+            lockout_duration_minutes: 30,
+        },
+        user_repository.clone(),
+    );
+
+    auth_service.login(&username, PASSWORD).await.unwrap();
+
+    let upgraded = user_repository
+        .find_by_id(user.id)
+        .await
+        .unwrap()
+        .unwrap()
+        .password_hash;
+    assert_ne!(upgraded, legacy);
+    assert!(upgraded.starts_with("$argon2id$"));
+    assert_eq!(auth_service.credentials_migrated(), 1);
+
+    // The upgraded hash verifies and isn't migrated again
+    auth_service.login(&username, PASSWORD).await.unwrap();
+    assert_eq!(auth_service.credentials_migrated(), 1);
+
+    user_repository.delete(user.id).await.unwrap();
+}
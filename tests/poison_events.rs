@@ -0,0 +1,143 @@
+mod common;
+
+use banking_es::{
+    domain::{AccountError, AccountEvent},
+    infrastructure::{
+        config::PoisonEventPolicy,
+        event_store::{EventStore, EventStoreTrait},
+        repository::AccountRepository,
+    },
+};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+async fn insert_raw_event(
+    pool: &PgPool,
+    account_id: Uuid,
+    version: i64,
+    event_type: &str,
+    event_data: serde_json::Value,
+) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO events (id, aggregate_id, event_type, event_data, version, timestamp, metadata)
+        VALUES ($1, $2, $3, $4, $5, NOW(), '{}')
+        "#,
+    )
+    .bind(id)
+    .bind(account_id)
+    .bind(event_type)
+    .bind(event_data)
+    .bind(version)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+/// Stores a valid account with a malformed event between its creation and a deposit.
+/// Returns the repository, account id and the malformed event's id.
+async fn setup(policy: PoisonEventPolicy) -> (AccountRepository, PgPool, Uuid, Uuid) {
+    let pool = common::pool().await;
+    let event_store = Arc::new(EventStore::new(pool.clone()));
+    let account_id = Uuid::new_v4();
+    event_store
+        .save_events(
+            account_id,
+            vec![AccountEvent::AccountCreated {
+                account_id,
+                owner_name: "Poison Test".to_string(),
+                initial_balance: Decimal::new(100, 0),
+                owner_user_id: None,
+                account_type: Default::default(),
+                rules: Default::default(),
+            }],
+            0,
+        )
+        .await
+        .unwrap();
+
+    let poison_id = insert_raw_event(
+        &pool,
+        account_id,
+        2,
+        "MoneyDeposited",
+        serde_json::json!({ "type": "MoneyDeposited", "amount": "not a number" }),
+    )
+    .await;
+    let deposit = AccountEvent::MoneyDeposited {
+        account_id,
+        amount: Decimal::new(25, 0),
+        transaction_id: Uuid::new_v4(),
+    };
+    insert_raw_event(
+        &pool,
+        account_id,
+        3,
+        "MoneyDeposited",
+        serde_json::to_value(&deposit).unwrap(),
+    )
+    .await;
+
+    let repository = AccountRepository::new(event_store as Arc<dyn EventStoreTrait + 'static>)
+        .with_poison_event_policy(policy);
+    (repository, pool, account_id, poison_id)
+}
+
+#[tokio::test]
+async fn test_fail_policy_rejects_the_aggregate() {
+    let (repository, _, account_id, _) = setup(PoisonEventPolicy::Fail).await;
+
+    let error = repository.get_by_id(account_id).await.unwrap_err();
+    assert!(matches!(error, AccountError::SchemaError(_)), "{}", error);
+    assert_eq!(repository.poison_events_skipped(), 0);
+}
+
+#[tokio::test]
+async fn test_skip_policy_replays_remaining_events() {
+    let (repository, pool, account_id, poison_id) = setup(PoisonEventPolicy::Skip).await;
+
+    let loaded = repository.load_account(account_id).await.unwrap().unwrap();
+    assert_eq!(loaded.account.balance, Decimal::new(125, 0));
+    // The next save must follow the skipped event's version
+    assert_eq!(loaded.account.version, 3);
+    assert_eq!(loaded.skipped_events.len(), 1);
+    assert_eq!(loaded.skipped_events[0].event_id, poison_id);
+    assert_eq!(loaded.skipped_events[0].version, 2);
+    assert_eq!(repository.poison_events_skipped(), 1);
+
+    let quarantined: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM quarantined_events WHERE event_id = $1")
+            .bind(poison_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(quarantined, 0);
+}
+
+#[tokio::test]
+async fn test_quarantine_policy_copies_the_raw_event() {
+    let (repository, pool, account_id, poison_id) = setup(PoisonEventPolicy::Quarantine).await;
+
+    let account = repository.get_by_id(account_id).await.unwrap().unwrap();
+    assert_eq!(account.balance, Decimal::new(125, 0));
+    // Loading again doesn't duplicate the quarantined copy
+    repository.get_by_id(account_id).await.unwrap();
+
+    let (aggregate_id, version, event_data, error): (Uuid, i64, serde_json::Value, String) =
+        sqlx::query_as(
+            "SELECT aggregate_id, version, event_data, error FROM quarantined_events WHERE event_id = $1",
+        )
+        .bind(poison_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(aggregate_id, account_id);
+    assert_eq!(version, 2);
+    assert_eq!(event_data["amount"], "not a number");
+    assert!(!error.is_empty());
+    assert_eq!(repository.poison_events_skipped(), 2);
+}
@@ -0,0 +1,40 @@
+use banking_es::infrastructure::event_store::{EventStore, EventStoreConfig, EventStoreTrait};
+
+#[tokio::test]
+async fn test_pool_holds_min_connections_once_constructed() {
+    let config = EventStoreConfig {
+        max_connections: 8,
+        min_connections: 4,
+        test_before_acquire: false,
+        warm_up_pool: true,
+        ..EventStoreConfig::default_in_memory_for_tests().unwrap()
+    };
+    let store = EventStore::new_with_config(config)
+        .await
+        .expect("Failed to create event store");
+
+    let (total, idle) = store.pool_stats();
+    assert!(idle >= 4, "{} idle connections", idle);
+    assert!(total <= 8);
+    assert!(!store.get_pool().options().get_test_before_acquire());
+}
+
+#[tokio::test]
+async fn test_warm_up_fills_a_lazily_connected_pool() {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(4)
+        .connect_lazy(
+            &EventStoreConfig::default_in_memory_for_tests()
+                .unwrap()
+                .database_url,
+        )
+        .unwrap();
+    assert_eq!(pool.num_idle(), 0);
+
+    EventStore::warm_up(&pool, 3).await.unwrap();
+    assert!(pool.num_idle() >= 3, "{} idle connections", pool.num_idle());
+
+    // Asking for more than the pool may hold opens only up to its maximum
+    EventStore::warm_up(&pool, 10).await.unwrap();
+    assert_eq!(pool.size(), 4);
+}
@@ -0,0 +1,372 @@
+mod common;
+
+use async_trait::async_trait;
+use banking_es::{
+    domain::{Account, AccountEvent},
+    infrastructure::{
+        cache_service::CacheServiceTrait,
+        config::ProjectionBatchConfig,
+        event_store::{
+            AccountSnapshot, AggregateStats, EventMetadata, EventStoreError, EventStoreTrait,
+            StoredEvent,
+        },
+        in_memory_event_store::InMemoryEventStore,
+        kafka_abstraction::{BankingKafkaError, EventBatch, KafkaConfig},
+        kafka_event_processor::{EventBatchSource, KafkaEventProcessor},
+        processing_failures::{ProcessingFailure, ProcessingFailureStatus, ProcessingFailureStore},
+        projections::{ProjectionStore, ProjectionStoreTrait},
+    },
+};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// In-memory event store whose saves fail a set number of times per account.
+#[derive(Default)]
+struct FlakyEventStore {
+    inner: InMemoryEventStore,
+    failures_left: Mutex<HashMap<Uuid, u32>>,
+}
+
+impl FlakyEventStore {
+    fn check(&self, aggregate_id: Uuid) -> Result<(), EventStoreError> {
+        let mut failures_left = self.failures_left.lock().unwrap();
+        match failures_left.get_mut(&aggregate_id) {
+            Some(left) if *left > 0 => {
+                *left -= 1;
+                Err(EventStoreError::InternalError(
+                    "injected save failure".to_string(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl EventStoreTrait for FlakyEventStore {
+    async fn save_events(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+    ) -> Result<(), EventStoreError> {
+        self.check(aggregate_id)?;
+        self.inner
+            .save_events(aggregate_id, events, expected_version)
+            .await
+    }
+
+    async fn save_events_with_metadata(
+        &self,
+        aggregate_id: Uuid,
+        events: Vec<AccountEvent>,
+        expected_version: i64,
+        metadata: EventMetadata,
+    ) -> Result<(), EventStoreError> {
+        self.check(aggregate_id)?;
+        self.inner
+            .save_events_with_metadata(aggregate_id, events, expected_version, metadata)
+            .await
+    }
+
+    async fn get_events(
+        &self,
+        aggregate_id: Uuid,
+        from_version: Option<i64>,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        self.inner.get_events(aggregate_id, from_version).await
+    }
+
+    async fn get_events_since(
+        &self,
+        after_position: i64,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>, EventStoreError> {
+        self.inner.get_events_since(after_position, limit).await
+    }
+
+    async fn get_current_version(&self, aggregate_id: Uuid) -> Result<i64, EventStoreError> {
+        self.inner.get_current_version(aggregate_id).await
+    }
+
+    async fn get_account(&self, account_id: Uuid) -> Result<Option<Account>, EventStoreError> {
+        self.inner.get_account(account_id).await
+    }
+
+    async fn get_all_accounts(&self) -> Result<Vec<Account>, EventStoreError> {
+        self.inner.get_all_accounts().await
+    }
+
+    async fn save_snapshot(&self, aggregate_id: Uuid) -> Result<Option<i64>, EventStoreError> {
+        self.inner.save_snapshot(aggregate_id).await
+    }
+
+    async fn snapshot_aggregates_above_threshold(
+        &self,
+        min_event_count: i64,
+    ) -> Result<Vec<(Uuid, i64)>, EventStoreError> {
+        self.inner
+            .snapshot_aggregates_above_threshold(min_event_count)
+            .await
+    }
+
+    async fn aggregate_stats(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<AggregateStats>, EventStoreError> {
+        self.inner.aggregate_stats(aggregate_id).await
+    }
+
+    async fn top_aggregates_by_event_count(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<AggregateStats>, EventStoreError> {
+        self.inner.top_aggregates_by_event_count(limit).await
+    }
+
+    async fn sample_aggregate_ids(&self, limit: i64) -> Result<Vec<Uuid>, EventStoreError> {
+        self.inner.sample_aggregate_ids(limit).await
+    }
+
+    async fn get_snapshot(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<AccountSnapshot>, EventStoreError> {
+        self.inner.get_snapshot(aggregate_id).await
+    }
+
+    fn get_pool(&self) -> PgPool {
+        self.inner.get_pool()
+    }
+}
+
+/// Hands out batches as the test delivers them.
+#[derive(Default)]
+struct QueuedSource {
+    batches: Mutex<VecDeque<EventBatch>>,
+}
+
+#[async_trait]
+impl EventBatchSource for QueuedSource {
+    async fn poll_events(&self) -> Result<Option<EventBatch>, BankingKafkaError> {
+        Ok(self.batches.lock().unwrap().pop_front())
+    }
+
+    async fn commit_offsets(&self) -> Result<(), BankingKafkaError> {
+        Ok(())
+    }
+}
+
+fn created(account_id: Uuid, offset: i64) -> EventBatch {
+    EventBatch {
+        account_id,
+        events: vec![AccountEvent::AccountCreated {
+            account_id,
+            owner_name: "Failure Log Test".to_string(),
+            initial_balance: Decimal::new(10, 0),
+            owner_user_id: None,
+            account_type: Default::default(),
+            rules: Default::default(),
+        }],
+        version: 0,
+        timestamp: chrono::Utc::now(),
+        global_position: None,
+        partition: Some(0),
+        offset: Some(offset),
+    }
+}
+
+/// Delivers `batch` and waits for the failure log to count `attempts` failures for it,
+/// or to stop retrying it.
+async fn deliver(
+    source: &QueuedSource,
+    failures: &ProcessingFailureStore,
+    batch: EventBatch,
+    until: impl Fn(&ProcessingFailure) -> bool,
+) -> ProcessingFailure {
+    let (account_id, version) = (batch.account_id, batch.version);
+    source.batches.lock().unwrap().push_back(batch);
+    tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(failure) = failures.get(account_id, version).await.unwrap() {
+                if until(&failure) {
+                    return failure;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("failure log not updated")
+}
+
+#[tokio::test]
+async fn test_failure_log_tracks_attempts_until_success_or_dead_letter() {
+    let pool = common::pool().await;
+
+    let flaky = FlakyEventStore::default();
+    let recovers = Uuid::new_v4();
+    let never_recovers = Uuid::new_v4();
+    flaky
+        .failures_left
+        .lock()
+        .unwrap()
+        .extend([(recovers, 2), (never_recovers, u32::MAX)]);
+
+    let source = Arc::new(QueuedSource::default());
+    let failures = Arc::new(ProcessingFailureStore::new(pool.clone()));
+    let event_store: Arc<dyn EventStoreTrait + Send + Sync> = Arc::new(flaky);
+    let projections: Arc<dyn ProjectionStoreTrait + Send + Sync> =
+        Arc::new(ProjectionStore::new(pool));
+    let cache: Arc<dyn CacheServiceTrait + Send + Sync> = Arc::new(common::NoopCache::default());
+    let processor = KafkaEventProcessor::new(
+        KafkaConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        &event_store,
+        &projections,
+        &cache,
+    )
+    .unwrap()
+    .with_event_source(source.clone())
+    .with_batch_config(ProjectionBatchConfig {
+        max_events: 1,
+        max_linger: Duration::ZERO,
+    })
+    .with_failure_log(failures.clone());
+    let handle = processor.start();
+
+    // Each failed delivery counts an attempt and schedules the DLQ's redelivery
+    let failure = deliver(&source, &failures, created(recovers, 1), |f| {
+        f.attempts == 1
+    })
+    .await;
+    assert_eq!(failure.status, ProcessingFailureStatus::Retrying);
+    assert!(failure.last_error.contains("injected save failure"));
+    assert!(failure
+        .next_retry_at
+        .is_some_and(|at| at > failure.updated_at));
+    let failure = deliver(&source, &failures, created(recovers, 2), |f| {
+        f.attempts == 2
+    })
+    .await;
+    assert_eq!(failure.status, ProcessingFailureStatus::Retrying);
+    assert_eq!(
+        (failure.kafka_partition, failure.kafka_offset),
+        (Some(0), Some(2))
+    );
+
+    // The third delivery succeeds and resolves the entry
+    let failure = deliver(&source, &failures, created(recovers, 3), |f| {
+        f.status != ProcessingFailureStatus::Retrying
+    })
+    .await;
+    assert_eq!(failure.status, ProcessingFailureStatus::Resolved);
+    assert_eq!(failure.attempts, 2);
+    assert!(failure.next_retry_at.is_none());
+
+    // A batch that keeps failing stops being retried after its third attempt
+    for offset in 1..=2 {
+        let failure = deliver(&source, &failures, created(never_recovers, offset), |f| {
+            f.attempts == offset as i32
+        })
+        .await;
+        assert_eq!(failure.status, ProcessingFailureStatus::Retrying);
+    }
+    let failure = deliver(&source, &failures, created(never_recovers, 3), |f| {
+        f.attempts == 3
+    })
+    .await;
+    assert_eq!(failure.status, ProcessingFailureStatus::DeadLettered);
+    assert!(failure.next_retry_at.is_none());
+
+    let dead_lettered = failures
+        .list(Some(ProcessingFailureStatus::DeadLettered), 1000)
+        .await
+        .unwrap();
+    assert!(dead_lettered
+        .iter()
+        .any(|f| f.aggregate_id == never_recovers));
+    assert!(!dead_lettered.iter().any(|f| f.aggregate_id == recovers));
+
+    processor.stop().await;
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_redelivered_batches_are_applied_to_projections_once() {
+    let pool = common::pool().await;
+
+    let source = Arc::new(QueuedSource::default());
+    let event_store: Arc<dyn EventStoreTrait + Send + Sync> = Arc::new(FlakyEventStore::default());
+    let projections: Arc<dyn ProjectionStoreTrait + Send + Sync> =
+        Arc::new(ProjectionStore::new(pool.clone()));
+    let cache: Arc<dyn CacheServiceTrait + Send + Sync> = Arc::new(common::NoopCache::default());
+    let processor = KafkaEventProcessor::new(
+        KafkaConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        &event_store,
+        &projections,
+        &cache,
+    )
+    .unwrap()
+    .with_event_source(source.clone())
+    .with_batch_config(ProjectionBatchConfig {
+        max_events: 1,
+        max_linger: Duration::ZERO,
+    });
+
+    let account_id = Uuid::new_v4();
+    let deposit = EventBatch {
+        account_id,
+        events: vec![AccountEvent::MoneyDeposited {
+            account_id,
+            amount: Decimal::new(5, 0),
+            transaction_id: Uuid::new_v4(),
+        }],
+        version: 1,
+        timestamp: chrono::Utc::now(),
+        global_position: None,
+        partition: Some(0),
+        offset: Some(2),
+    };
+    // The consumer stopped before committing its offsets, so both batches come again
+    source.batches.lock().unwrap().extend([
+        created(account_id, 1),
+        deposit.clone(),
+        created(account_id, 1),
+        deposit,
+    ]);
+    let handle = processor.start();
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while processor.get_processing_metrics().await.events_processed < 4 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("redelivered batches not processed");
+
+    let (balance, version): (Decimal, i64) =
+        sqlx::query_as("SELECT balance, version FROM account_projections WHERE id = $1")
+            .bind(account_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(balance, Decimal::new(15, 0));
+    assert_eq!(version, 2);
+    assert_eq!(
+        processor.get_processing_metrics().await.processing_errors,
+        0
+    );
+
+    processor.stop().await;
+    handle.await.unwrap().unwrap();
+}
@@ -0,0 +1,216 @@
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use banking_es::{
+    domain::{Account, AccountError, AccountEvent},
+    error::BankingError,
+    infrastructure::{
+        in_memory_event_store::InMemoryEventStore,
+        outbox::{OutboxError, PublishOutcome},
+        produce_queue::{BatchPublisher, ProduceQueue, QueuedBatch},
+        repository::{AccountRepository, AccountRepositoryTrait},
+    },
+};
+use rust_decimal::Decimal;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+/// A broker that takes one permit per publish, so it stalls until the test adds permits.
+struct SlowBroker {
+    permits: Semaphore,
+    published: Mutex<Vec<Uuid>>,
+    deferred: Mutex<Vec<Uuid>>,
+}
+
+#[async_trait]
+impl BatchPublisher for SlowBroker {
+    async fn publish(&self, batch: &QueuedBatch) -> Result<PublishOutcome, OutboxError> {
+        self.permits.acquire().await.unwrap().forget();
+        self.published.lock().unwrap().push(batch.aggregate_id);
+        Ok(PublishOutcome::Published)
+    }
+
+    async fn defer(&self, batch: &QueuedBatch) -> Result<(), OutboxError> {
+        self.deferred.lock().unwrap().push(batch.aggregate_id);
+        Ok(())
+    }
+}
+
+fn setup(
+    capacity: usize,
+) -> (
+    Arc<SlowBroker>,
+    Arc<ProduceQueue>,
+    Arc<InMemoryEventStore>,
+    AccountRepository,
+) {
+    let broker = Arc::new(SlowBroker {
+        permits: Semaphore::new(0),
+        published: Mutex::new(Vec::new()),
+        deferred: Mutex::new(Vec::new()),
+    });
+    let queue = Arc::new(ProduceQueue::start(broker.clone(), capacity));
+    let store = Arc::new(InMemoryEventStore::new());
+    let repository = AccountRepository::new(store.clone()).with_produce_queue(queue.clone());
+    (broker, queue, store, repository)
+}
+
+async fn create(repository: &AccountRepository) -> (Uuid, Result<(), BankingError>) {
+    let account_id = Uuid::new_v4();
+    let account =
+        Account::new(account_id, "Produce Queue Test".to_string(), Decimal::ZERO).unwrap();
+    let event = AccountEvent::AccountCreated {
+        account_id,
+        owner_name: account.owner_name.clone(),
+        initial_balance: Decimal::ZERO,
+        owner_user_id: None,
+        account_type: Default::default(),
+        rules: Default::default(),
+    };
+    let result = AccountRepositoryTrait::save(repository, &account, vec![event]).await;
+    (account_id, result)
+}
+
+async fn wait_until(condition: impl Fn() -> bool) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("condition not reached");
+}
+
+#[tokio::test]
+async fn test_writes_stay_fast_under_a_slow_broker_until_the_queue_is_full() {
+    let (broker, queue, store, repository) = setup(2);
+
+    // The worker holds the first batch while the broker stalls, and two more fit in the queue
+    let start = Instant::now();
+    let mut saved = Vec::new();
+    for _ in 0..3 {
+        let (account_id, result) = create(&repository).await;
+        result.unwrap();
+        saved.push(account_id);
+        if saved.len() == 1 {
+            // Let the worker take the first batch off the queue
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+    assert!(
+        start.elapsed() < Duration::from_secs(1),
+        "{:?}",
+        start.elapsed()
+    );
+
+    // A full queue refuses the write with a 503 and nothing is saved
+    let (rejected, result) = create(&repository).await;
+    let error = result.unwrap_err();
+    assert!(matches!(
+        error,
+        BankingError::Outbox(OutboxError::QueueFull { capacity: 2 })
+    ));
+    assert_eq!(error.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(matches!(
+        AccountError::from(error),
+        AccountError::Unavailable(_)
+    ));
+    assert!(store.get_events(rejected, None).await.unwrap().is_empty());
+    assert_eq!(queue.metrics().rejected.load(Ordering::Relaxed), 1);
+
+    // Once the broker catches up the batches go out in order and writes are accepted again
+    broker.permits.add_permits(10);
+    wait_until(|| queue.is_empty()).await;
+    assert_eq!(*broker.published.lock().unwrap(), saved);
+    create(&repository).await.1.unwrap();
+    wait_until(|| queue.metrics().published.load(Ordering::Relaxed) == 4).await;
+}
+
+#[tokio::test]
+async fn test_drain_moves_queued_batches_to_the_outbox() {
+    let (broker, queue, _store, repository) = setup(4);
+    let mut saved = Vec::new();
+    for _ in 0..3 {
+        let (account_id, result) = create(&repository).await;
+        result.unwrap();
+        saved.push(account_id);
+    }
+    assert_eq!(queue.len(), 3);
+
+    // Flushing at shutdown waits for the batch being published and defers the rest
+    let flush = tokio::spawn(async move { repository.flush_all().await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!flush.is_finished());
+    broker.permits.add_permits(1);
+    flush.await.unwrap().unwrap();
+
+    assert!(queue.is_empty());
+    assert_eq!(*broker.published.lock().unwrap(), saved[..1]);
+    assert_eq!(*broker.deferred.lock().unwrap(), saved[1..]);
+    assert_eq!(queue.metrics().deferred.load(Ordering::Relaxed), 2);
+}
+
+/// Records the version of each batch it publishes.
+#[derive(Default)]
+struct VersionRecorder {
+    published: Mutex<Vec<i64>>,
+}
+
+#[async_trait]
+impl BatchPublisher for VersionRecorder {
+    async fn publish(&self, batch: &QueuedBatch) -> Result<PublishOutcome, OutboxError> {
+        self.published.lock().unwrap().push(batch.version);
+        Ok(PublishOutcome::Published)
+    }
+
+    async fn defer(&self, _batch: &QueuedBatch) -> Result<(), OutboxError> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_batches_of_one_account_are_produced_in_version_order() {
+    let recorder = Arc::new(VersionRecorder::default());
+    let queue = ProduceQueue::start(recorder.clone(), 16);
+    let account_id = Uuid::new_v4();
+    let batch = |version: i64| QueuedBatch {
+        aggregate_id: account_id,
+        events: vec![AccountEvent::MoneyDeposited {
+            account_id,
+            amount: Decimal::ONE,
+            transaction_id: Uuid::new_v4(),
+        }],
+        version,
+        global_position: None,
+    };
+
+    // Places are reserved in save order; a reservation whose save failed is dropped
+    let mut permits: Vec<_> = (1..=6)
+        .map(|version| queue.try_reserve_for(account_id, version).unwrap())
+        .collect();
+    let failed_save = permits.remove(3);
+
+    // Each task sends its batch after a delay that puts them out of order
+    let tasks: Vec<_> = permits
+        .into_iter()
+        .zip([1, 2, 3, 5, 6])
+        .map(|(permit, version)| {
+            let batch = batch(version);
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(10 * (7 - version as u64))).await;
+                permit.send(batch);
+            })
+        })
+        .collect();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(recorder.published.lock().unwrap().is_empty());
+    drop(failed_save);
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    wait_until(|| queue.is_empty()).await;
+    assert_eq!(*recorder.published.lock().unwrap(), vec![1, 2, 3, 5, 6]);
+}
@@ -0,0 +1,356 @@
+mod common;
+
+use async_trait::async_trait;
+use banking_es::{
+    domain::{AccountEvent, AccountType},
+    infrastructure::{
+        cache_service::{CacheConfig, CacheServiceTrait},
+        config::ProjectionBatchConfig,
+        event_store::EventStoreTrait,
+        in_memory_cache_service::InMemoryCacheService,
+        in_memory_event_store::InMemoryEventStore,
+        kafka_abstraction::{BankingKafkaError, EventBatch, KafkaConfig},
+        kafka_event_processor::{EventBatchSource, KafkaEventProcessor, PROJECTION_CHECKPOINT},
+        projections::{
+            AccountProjection, AccountSearch, ProjectionCheckpoint, ProjectionEvent,
+            ProjectionStoreTrait, TransactionProjection,
+        },
+    },
+};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Hands out the queued batches, then reports no new messages.
+#[derive(Default)]
+struct QueuedSource {
+    batches: Mutex<VecDeque<EventBatch>>,
+    commits: AtomicUsize,
+}
+
+#[async_trait]
+impl EventBatchSource for QueuedSource {
+    async fn poll_events(&self) -> Result<Option<EventBatch>, BankingKafkaError> {
+        Ok(self.batches.lock().unwrap().pop_front())
+    }
+
+    async fn commit_offsets(&self) -> Result<(), BankingKafkaError> {
+        self.commits.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Records the size of each batched projection write.
+#[derive(Default)]
+struct RecordingProjections {
+    writes: Mutex<Vec<usize>>,
+}
+
+#[async_trait]
+impl ProjectionStoreTrait for RecordingProjections {
+    async fn get_account(&self, _account_id: Uuid) -> anyhow::Result<Option<AccountProjection>> {
+        Ok(None)
+    }
+
+    async fn get_account_uncached(
+        &self,
+        _account_id: Uuid,
+    ) -> anyhow::Result<Option<AccountProjection>> {
+        Ok(None)
+    }
+
+    async fn get_all_accounts(&self) -> anyhow::Result<Vec<AccountProjection>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_accounts_by_ids(
+        &self,
+        _account_ids: &[Uuid],
+    ) -> anyhow::Result<Vec<AccountProjection>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_accounts_by_owner(
+        &self,
+        _owner_user_id: Uuid,
+    ) -> anyhow::Result<Vec<AccountProjection>> {
+        Ok(Vec::new())
+    }
+
+    async fn search_accounts(
+        &self,
+        _search: &AccountSearch,
+    ) -> anyhow::Result<Vec<AccountProjection>> {
+        Ok(Vec::new())
+    }
+
+    async fn count_search_accounts(&self, _search: &AccountSearch) -> anyhow::Result<u64> {
+        Ok(0)
+    }
+
+    async fn get_account_transactions(
+        &self,
+        _account_id: Uuid,
+    ) -> anyhow::Result<Vec<TransactionProjection>> {
+        Ok(Vec::new())
+    }
+
+    async fn upsert_accounts_batch(&self, _accounts: Vec<AccountProjection>) -> anyhow::Result<()> {
+        panic!("the processor writes through apply_events_batch")
+    }
+
+    async fn upsert_account_now(&self, _account: AccountProjection) -> anyhow::Result<()> {
+        panic!("the processor writes through apply_events_batch")
+    }
+
+    async fn insert_transactions_batch(
+        &self,
+        _transactions: Vec<TransactionProjection>,
+    ) -> anyhow::Result<()> {
+        panic!("the processor writes through apply_events_batch")
+    }
+
+    async fn load_checkpoint(
+        &self,
+        _projection_name: &str,
+    ) -> anyhow::Result<Option<ProjectionCheckpoint>> {
+        Ok(None)
+    }
+
+    async fn save_checkpoint(&self, _projection_name: &str, _position: i64) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn apply_events_with_checkpoint(
+        &self,
+        _projection_name: &str,
+        _events: &[(i64, AccountEvent)],
+    ) -> anyhow::Result<Option<i64>> {
+        panic!("the processor writes through apply_events_batch")
+    }
+
+    async fn apply_events_batch(
+        &self,
+        projection_name: &str,
+        events: &[ProjectionEvent],
+    ) -> anyhow::Result<Option<i64>> {
+        assert_eq!(projection_name, PROJECTION_CHECKPOINT);
+        self.writes.lock().unwrap().push(events.len());
+        Ok(events.iter().filter_map(|event| event.position).max())
+    }
+
+    async fn projection_lag(&self) -> anyhow::Result<u64> {
+        Ok(0)
+    }
+
+    async fn get_account_metadata(
+        &self,
+        _account_id: Uuid,
+    ) -> anyhow::Result<HashMap<String, String>> {
+        Ok(HashMap::new())
+    }
+
+    async fn set_account_metadata(
+        &self,
+        _account_id: Uuid,
+        _key: &str,
+        _value: &str,
+    ) -> anyhow::Result<()> {
+        panic!("the processor writes through apply_events_batch")
+    }
+
+    async fn remove_account_metadata(&self, _account_id: Uuid, _key: &str) -> anyhow::Result<()> {
+        panic!("the processor writes through apply_events_batch")
+    }
+}
+
+fn burst(count: usize) -> VecDeque<EventBatch> {
+    (0..count)
+        .map(|i| {
+            let account_id = Uuid::new_v4();
+            EventBatch {
+                account_id,
+                events: vec![AccountEvent::AccountCreated {
+                    account_id,
+                    owner_name: format!("Batch {}", i),
+                    initial_balance: Decimal::new(10, 0),
+                    owner_user_id: None,
+                    account_type: Default::default(),
+                    rules: Default::default(),
+                }],
+                version: 0,
+                timestamp: chrono::Utc::now(),
+                global_position: Some(i as i64 + 1),
+                partition: None,
+                offset: None,
+            }
+        })
+        .collect()
+}
+
+fn processor(
+    batches: VecDeque<EventBatch>,
+    batch_config: ProjectionBatchConfig,
+) -> (
+    KafkaEventProcessor,
+    Arc<QueuedSource>,
+    Arc<RecordingProjections>,
+) {
+    processor_with_cache(
+        batches,
+        batch_config,
+        Arc::new(common::NoopCache::default()),
+    )
+}
+
+fn processor_with_cache(
+    batches: VecDeque<EventBatch>,
+    batch_config: ProjectionBatchConfig,
+    cache: Arc<dyn CacheServiceTrait + Send + Sync>,
+) -> (
+    KafkaEventProcessor,
+    Arc<QueuedSource>,
+    Arc<RecordingProjections>,
+) {
+    let source = Arc::new(QueuedSource {
+        batches: Mutex::new(batches),
+        commits: AtomicUsize::new(0),
+    });
+    let recording = Arc::new(RecordingProjections::default());
+    let event_store: Arc<dyn EventStoreTrait + Send + Sync> = Arc::new(InMemoryEventStore::new());
+    let projections: Arc<dyn ProjectionStoreTrait + Send + Sync> = recording.clone();
+    let processor = KafkaEventProcessor::new(
+        KafkaConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        &event_store,
+        &projections,
+        &cache,
+    )
+    .unwrap()
+    .with_event_source(source.clone())
+    .with_batch_config(batch_config);
+    (processor, source, recording)
+}
+
+async fn wait_until(condition: impl Fn() -> bool) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("condition not reached");
+}
+
+#[tokio::test]
+async fn test_a_burst_of_events_is_written_and_committed_once() {
+    let (processor, source, projections) = processor(
+        burst(20),
+        ProjectionBatchConfig {
+            max_events: 100,
+            max_linger: Duration::from_millis(200),
+        },
+    );
+    let handle = processor.start();
+
+    wait_until(|| source.commits.load(Ordering::SeqCst) > 0).await;
+    assert_eq!(*projections.writes.lock().unwrap(), vec![20]);
+    assert_eq!(source.commits.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        processor.get_processing_metrics().await.events_processed,
+        20
+    );
+
+    // Nothing else arrives, so nothing else is written
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(projections.writes.lock().unwrap().len(), 1);
+
+    processor.stop().await;
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_batches_are_cut_at_the_event_limit() {
+    let (processor, source, projections) = processor(
+        burst(7),
+        ProjectionBatchConfig {
+            max_events: 3,
+            max_linger: Duration::from_secs(60),
+        },
+    );
+    let handle = processor.start();
+
+    wait_until(|| projections.writes.lock().unwrap().len() == 2).await;
+    assert_eq!(source.commits.load(Ordering::SeqCst), 2);
+
+    // Stopping writes the partial batch before the final commit
+    processor.stop().await;
+    handle.await.unwrap().unwrap();
+    assert_eq!(*projections.writes.lock().unwrap(), vec![3, 3, 1]);
+    assert_eq!(source.commits.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn test_cached_accounts_keep_their_type_rules_and_metadata() {
+    let account_id = Uuid::new_v4();
+    let batch = |events: Vec<AccountEvent>, version: i64| EventBatch {
+        account_id,
+        events,
+        version,
+        timestamp: chrono::Utc::now(),
+        global_position: Some(version + 1),
+        partition: None,
+        offset: None,
+    };
+    let batches = VecDeque::from([
+        batch(
+            vec![AccountEvent::AccountCreated {
+                account_id,
+                owner_name: "Cached Savings".to_string(),
+                initial_balance: Decimal::new(10, 0),
+                owner_user_id: None,
+                account_type: AccountType::Savings,
+                rules: AccountType::Savings.default_rules(),
+            }],
+            0,
+        ),
+        batch(
+            vec![AccountEvent::MetadataSet {
+                account_id,
+                key: "branch".to_string(),
+                value: "north".to_string(),
+            }],
+            1,
+        ),
+    ]);
+    let cache = Arc::new(InMemoryCacheService::new(CacheConfig::default()));
+    let (processor, source, _) = processor_with_cache(
+        batches,
+        ProjectionBatchConfig {
+            max_events: 1,
+            max_linger: Duration::ZERO,
+        },
+        cache.clone(),
+    );
+    let handle = processor.start();
+
+    // The second batch is applied to the account the first one cached
+    wait_until(|| source.commits.load(Ordering::SeqCst) >= 2).await;
+    let account = cache.get_account(account_id).await.unwrap().unwrap();
+    assert_eq!(account.version, 2);
+    assert_eq!(account.balance, Decimal::new(10, 0));
+    assert_eq!(account.account_type, AccountType::Savings);
+    assert_eq!(account.rules, AccountType::Savings.default_rules());
+    assert_eq!(
+        account.metadata.get("branch").map(String::as_str),
+        Some("north")
+    );
+
+    processor.stop().await;
+    handle.await.unwrap().unwrap();
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use banking_es::domain::AccountEvent;
+use banking_es::infrastructure::projections::ProjectionStore;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+fn account_events(account_id: Uuid) -> Vec<(i64, AccountEvent)> {
+    let mut events = vec![(
+        1,
+        AccountEvent::AccountCreated {
+            account_id,
+            owner_name: "Checkpoint Test".to_string(),
+            initial_balance: Decimal::new(100, 0),
+            owner_user_id: None,
+            account_type: Default::default(),
+            rules: Default::default(),
+        },
+    )];
+    events.extend((2..=5).map(|position| {
+        (
+            position,
+            AccountEvent::MoneyDeposited {
+                account_id,
+                amount: Decimal::new(10, 0),
+                transaction_id: Uuid::new_v4(),
+            },
+        )
+    }));
+    events
+}
+
+async fn stored_balance(pool: &PgPool, account_id: Uuid) -> Decimal {
+    sqlx::query_scalar("SELECT balance FROM account_projections WHERE id = $1")
+        .bind(account_id)
+        .fetch_one(pool)
+        .await
+        .expect("Account projection should exist")
+}
+
+async fn cleanup(pool: &PgPool, projection_name: &str, account_id: Uuid) {
+    let _ = sqlx::query("DELETE FROM projection_checkpoints WHERE projection_name = $1")
+        .bind(projection_name)
+        .execute(pool)
+        .await;
+    let _ = sqlx::query("DELETE FROM account_projections WHERE id = $1")
+        .bind(account_id)
+        .execute(pool)
+        .await;
+}
+
+#[tokio::test]
+async fn test_processing_resumes_from_checkpoint_after_restart() {
+    let pool = common::pool().await;
+    let projection_name = format!("checkpoint-test-{}", Uuid::new_v4());
+    let account_id = Uuid::new_v4();
+    let events = account_events(account_id);
+
+    let store = ProjectionStore::new(pool.clone());
+    assert!(store.load_checkpoint(&projection_name).await.unwrap().is_none());
+
+    let position = store
+        .apply_events_with_checkpoint(&projection_name, &events[..3])
+        .await
+        .unwrap();
+    assert_eq!(position, Some(3));
+    drop(store);
+
+    // "Restart": a new store has no in-memory state, only what was committed.
+    let store = ProjectionStore::new(pool.clone());
+    let checkpoint = store
+        .load_checkpoint(&projection_name)
+        .await
+        .unwrap()
+        .expect("Checkpoint should survive the restart");
+    assert_eq!(checkpoint.position, 3);
+
+    // The consumer redelivers from the start; already-applied events must be skipped.
+    let position = store
+        .apply_events_with_checkpoint(&projection_name, &events)
+        .await
+        .unwrap();
+    assert_eq!(position, Some(5));
+    assert_eq!(stored_balance(&pool, account_id).await, Decimal::new(140, 0));
+
+    // Nothing new to apply leaves the projection and checkpoint untouched.
+    let position = store
+        .apply_events_with_checkpoint(&projection_name, &events)
+        .await
+        .unwrap();
+    assert_eq!(position, Some(5));
+    assert_eq!(stored_balance(&pool, account_id).await, Decimal::new(140, 0));
+
+    cleanup(&pool, &projection_name, account_id).await;
+}
+
+#[tokio::test]
+async fn test_failed_update_does_not_advance_checkpoint() {
+    let pool = common::pool().await;
+    let projection_name = format!("checkpoint-test-{}", Uuid::new_v4());
+    let account_id = Uuid::new_v4();
+    let events = account_events(account_id);
+
+    let store = ProjectionStore::new(pool.clone());
+    store
+        .apply_events_with_checkpoint(&projection_name, &events[..2])
+        .await
+        .unwrap();
+
+    // Owner names are limited to 255 characters, so this upsert fails and rolls back.
+    let invalid = vec![(
+        3,
+        AccountEvent::AccountCreated {
+            account_id,
+            owner_name: "x".repeat(300),
+            initial_balance: Decimal::ZERO,
+            owner_user_id: None,
+            account_type: Default::default(),
+            rules: Default::default(),
+        },
+    )];
+    assert!(store
+        .apply_events_with_checkpoint(&projection_name, &invalid)
+        .await
+        .is_err());
+
+    let checkpoint = store.load_checkpoint(&projection_name).await.unwrap().unwrap();
+    assert_eq!(checkpoint.position, 2);
+
+    let position = store
+        .apply_events_with_checkpoint(&projection_name, &events[2..])
+        .await
+        .unwrap();
+    assert_eq!(position, Some(5));
+    assert_eq!(stored_balance(&pool, account_id).await, Decimal::new(140, 0));
+
+    cleanup(&pool, &projection_name, account_id).await;
+}
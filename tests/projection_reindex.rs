@@ -0,0 +1,135 @@
+mod common;
+
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::{
+        event_store::{EventStore, EventStoreTrait},
+        projections::{ProjectionStore, ReindexOutcome, REINDEX_CHECKPOINT},
+    },
+};
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::{watch, Mutex};
+use uuid::Uuid;
+
+/// Reindexing swaps the shared projections table, so the tests take turns.
+static REINDEX: Mutex<()> = Mutex::const_new(());
+
+async fn pool() -> PgPool {
+    let database_url = common::database_url();
+    PgPoolOptions::new()
+        .max_connections(10)
+        .connect(&database_url)
+        .await
+        .expect("Failed to connect to database")
+}
+
+/// Saves an account's events, then leaves its projection stale so the rebuilt model is
+/// easy to tell apart from the old one.
+async fn stale_account(pool: &PgPool, initial: i64, deposited: i64) -> Uuid {
+    let account_id = Uuid::new_v4();
+    EventStore::new(pool.clone())
+        .save_events(
+            account_id,
+            vec![
+                AccountEvent::AccountCreated {
+                    account_id,
+                    owner_name: "Reindexed".to_string(),
+                    initial_balance: Decimal::new(initial, 0),
+                    owner_user_id: None,
+                    account_type: Default::default(),
+                    rules: Default::default(),
+                },
+                AccountEvent::MoneyDeposited {
+                    account_id,
+                    amount: Decimal::new(deposited, 0),
+                    transaction_id: Uuid::new_v4(),
+                },
+            ],
+            0,
+        )
+        .await
+        .unwrap();
+
+    sqlx::query(
+        r#"
+        INSERT INTO account_projections (id, owner_name, balance, is_active, created_at, updated_at)
+        VALUES ($1, 'Stale', 0, true, NOW(), NOW())
+        ON CONFLICT (id) DO UPDATE SET owner_name = 'Stale', balance = 0
+        "#,
+    )
+    .bind(account_id)
+    .execute(pool)
+    .await
+    .unwrap();
+    account_id
+}
+
+/// Balances of the given accounts in one read, in the order given.
+async fn balances(store: &ProjectionStore, ids: &[Uuid]) -> Vec<Option<Decimal>> {
+    let accounts = store.get_accounts_by_ids(ids).await.unwrap();
+    ids.iter()
+        .map(|id| accounts.iter().find(|a| a.id == *id).map(|a| a.balance))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_reads_see_the_old_or_new_model_during_reindex() {
+    let _turn = REINDEX.lock().await;
+    let pool = pool().await;
+    let ids = [
+        stale_account(&pool, 100, 50).await,
+        stale_account(&pool, 10, 5).await,
+    ];
+    let store = ProjectionStore::new(pool.clone());
+    let old = vec![Some(Decimal::ZERO), Some(Decimal::ZERO)];
+    let new = vec![Some(Decimal::new(150, 0)), Some(Decimal::new(15, 0))];
+
+    let done = Arc::new(AtomicBool::new(false));
+    let reader = {
+        let (store, done, old, new) = (store.clone(), done.clone(), old.clone(), new.clone());
+        tokio::spawn(async move {
+            let mut reads = 0;
+            while !done.load(Ordering::SeqCst) {
+                let seen = balances(&store, &ids).await;
+                assert!(
+                    seen == old || seen == new,
+                    "read a partial model: {:?}",
+                    seen
+                );
+                reads += 1;
+            }
+            reads
+        })
+    };
+
+    let (_, cancel) = watch::channel(false);
+    let outcome = store.reindex_account_projections(100, &cancel).await.unwrap();
+    done.store(true, Ordering::SeqCst);
+
+    assert!(matches!(outcome, ReindexOutcome::Swapped { .. }));
+    assert!(reader.await.unwrap() > 0);
+    assert_eq!(balances(&store, &ids).await, new);
+    assert!(store.load_checkpoint(REINDEX_CHECKPOINT).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_cancelled_reindex_resumes_and_leaves_reads_alone() {
+    let _turn = REINDEX.lock().await;
+    let pool = pool().await;
+    let ids = [stale_account(&pool, 40, 2).await];
+    let store = ProjectionStore::new(pool);
+
+    let (stop, cancel) = watch::channel(true);
+    let outcome = store.reindex_account_projections(100, &cancel).await.unwrap();
+    assert!(matches!(outcome, ReindexOutcome::Cancelled { .. }));
+    assert_eq!(balances(&store, &ids).await, vec![Some(Decimal::ZERO)]);
+    assert!(store.load_checkpoint(REINDEX_CHECKPOINT).await.unwrap().is_some());
+
+    stop.send(false).unwrap();
+    let outcome = store.reindex_account_projections(100, &cancel).await.unwrap();
+    assert!(matches!(outcome, ReindexOutcome::Swapped { .. }));
+    assert_eq!(balances(&store, &ids).await, vec![Some(Decimal::new(42, 0))]);
+}
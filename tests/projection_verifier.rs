@@ -0,0 +1,198 @@
+mod common;
+
+use banking_es::{
+    application::services::AccountService,
+    domain::AccountEvent,
+    infrastructure::{
+        cache_service::{CacheConfig, CacheServiceTrait},
+        event_store::{EventStore, EventStoreTrait},
+        in_memory_cache_service::InMemoryCacheService,
+        in_memory_event_store::InMemoryEventStore,
+        middleware::RequestMiddleware,
+        projections::{AccountProjection, ProjectionStore, ProjectionStoreTrait},
+        repository::{AccountRepository, AccountRepositoryTrait},
+    },
+};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn service(
+    event_store: Arc<dyn EventStoreTrait + 'static>,
+    projections: ProjectionStore,
+) -> AccountService {
+    AccountService::new(
+        Arc::new(AccountRepository::new(event_store)) as Arc<dyn AccountRepositoryTrait + 'static>,
+        Arc::new(projections) as Arc<dyn ProjectionStoreTrait + 'static>,
+        Arc::new(InMemoryCacheService::new(CacheConfig::default()))
+            as Arc<dyn CacheServiceTrait + 'static>,
+        Arc::new(RequestMiddleware::default()),
+        100,
+    )
+}
+
+/// Saves an account with a deposit and writes its matching projection row.
+async fn seed(event_store: &dyn EventStoreTrait, projections: &ProjectionStore) -> Uuid {
+    let account_id = Uuid::new_v4();
+    let events = vec![
+        AccountEvent::AccountCreated {
+            account_id,
+            owner_name: "Projection Verifier Test".to_string(),
+            initial_balance: Decimal::new(100, 0),
+            owner_user_id: None,
+            account_type: Default::default(),
+            rules: Default::default(),
+        },
+        AccountEvent::MoneyDeposited {
+            account_id,
+            amount: Decimal::new(50, 0),
+            transaction_id: Uuid::new_v4(),
+        },
+    ];
+    event_store
+        .save_events(account_id, events, 0)
+        .await
+        .unwrap();
+    projections
+        .upsert_account_now(AccountProjection {
+            id: account_id,
+            owner_name: "Projection Verifier Test".to_string(),
+            balance: Decimal::new(150, 0),
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            owner_user_id: None,
+        })
+        .await
+        .unwrap();
+    account_id
+}
+
+async fn desync(pool: &PgPool, account_id: Uuid) {
+    sqlx::query(
+        "UPDATE account_projections SET balance = balance + 25, is_active = false WHERE id = $1",
+    )
+    .bind(account_id)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_desynced_projection_is_flagged_and_repaired() {
+    let pool = common::pool().await;
+    let event_store: Arc<dyn EventStoreTrait> = Arc::new(EventStore::new(pool.clone()));
+    let projections = ProjectionStore::new(pool.clone());
+    let account_id = seed(event_store.as_ref(), &projections).await;
+    let service = service(event_store, projections.clone());
+
+    let verification = service
+        .verify_projection(account_id, false)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(verification.is_consistent(), "{:?}", verification);
+    assert_eq!(verification.version, 2);
+
+    // Cached reads still show the old row, so the verifier must read the database
+    projections.get_account(account_id).await.unwrap();
+    desync(&pool, account_id).await;
+
+    let verification = service
+        .verify_projection(account_id, false)
+        .await
+        .unwrap()
+        .unwrap();
+    let fields: Vec<&str> = verification.mismatches.iter().map(|m| m.field).collect();
+    assert_eq!(fields, vec!["balance", "is_active"]);
+    assert_eq!(verification.mismatches[0].expected, "150");
+    assert_eq!(verification.mismatches[0].actual, "175");
+    assert!(!verification.repaired);
+    assert_eq!(
+        service
+            .get_metrics()
+            .projection_mismatches
+            .load(Ordering::Relaxed),
+        1
+    );
+
+    let verification = service
+        .verify_projection(account_id, true)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(verification.repaired);
+    let row = projections
+        .get_account_uncached(account_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(row.balance, Decimal::new(150, 0));
+    assert!(row.is_active);
+    assert!(service
+        .verify_projection(account_id, false)
+        .await
+        .unwrap()
+        .unwrap()
+        .is_consistent());
+
+    // A missing row is repaired by writing it again
+    sqlx::query("DELETE FROM account_projections WHERE id = $1")
+        .bind(account_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let verification = service
+        .verify_projection(account_id, true)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(verification.projection_missing && verification.repaired);
+    assert!(projections
+        .get_account_uncached(account_id)
+        .await
+        .unwrap()
+        .is_some());
+    assert_eq!(
+        service
+            .get_metrics()
+            .projection_mismatches
+            .load(Ordering::Relaxed),
+        3
+    );
+
+    assert!(service
+        .verify_projection(Uuid::new_v4(), true)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_sampled_scan_reports_only_inconsistent_accounts() {
+    let pool = common::pool().await;
+    let event_store = Arc::new(InMemoryEventStore::new());
+    let projections = ProjectionStore::new(pool.clone());
+    let consistent = seed(event_store.as_ref(), &projections).await;
+    let drifted = seed(event_store.as_ref(), &projections).await;
+    desync(&pool, drifted).await;
+    let service = service(event_store, projections.clone());
+
+    let summary = service.verify_projections_sample(10, true).await.unwrap();
+    assert_eq!(summary.checked, 2);
+    assert_eq!(summary.inconsistent.len(), 1);
+    assert_eq!(summary.inconsistent[0].account_id, drifted);
+    assert!(summary.inconsistent[0].repaired);
+
+    let summary = service.verify_projections_sample(10, false).await.unwrap();
+    assert_eq!(summary.checked, 2);
+    assert!(summary.inconsistent.is_empty(), "{:?}", summary);
+    assert!(projections
+        .get_account_uncached(consistent)
+        .await
+        .unwrap()
+        .is_some());
+}
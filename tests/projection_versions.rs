@@ -0,0 +1,174 @@
+mod common;
+
+use banking_es::domain::AccountEvent;
+use banking_es::infrastructure::projections::{ProjectionApply, ProjectionStore};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// An account's history, versions starting at 1: created with 100, then deposits of 10.
+fn account_events(account_id: Uuid, deposits: i64) -> Vec<(i64, AccountEvent)> {
+    let mut events = vec![(
+        1,
+        AccountEvent::AccountCreated {
+            account_id,
+            owner_name: "Version Test".to_string(),
+            initial_balance: Decimal::new(100, 0),
+            owner_user_id: None,
+            account_type: Default::default(),
+            rules: Default::default(),
+        },
+    )];
+    events.extend((2..=deposits + 1).map(|version| {
+        (
+            version,
+            AccountEvent::MoneyDeposited {
+                account_id,
+                amount: Decimal::new(10, 0),
+                transaction_id: Uuid::new_v4(),
+            },
+        )
+    }));
+    events
+}
+
+async fn stored(pool: &PgPool, account_id: Uuid) -> (Decimal, i64) {
+    sqlx::query_as("SELECT balance, version FROM account_projections WHERE id = $1")
+        .bind(account_id)
+        .fetch_one(pool)
+        .await
+        .expect("Account projection should exist")
+}
+
+async fn cleanup(pool: &PgPool, account_id: Uuid) {
+    let _ = sqlx::query("DELETE FROM account_projections WHERE id = $1")
+        .bind(account_id)
+        .execute(pool)
+        .await;
+}
+
+#[tokio::test]
+async fn test_events_in_order_are_applied() {
+    let pool = common::pool().await;
+    let store = ProjectionStore::new(pool.clone());
+    let account_id = Uuid::new_v4();
+
+    for (version, event) in account_events(account_id, 3) {
+        let action = store.apply_event_at_version(&event, version).await.unwrap();
+        assert_eq!(action, ProjectionApply::Applied { version });
+    }
+
+    assert_eq!(stored(&pool, account_id).await, (Decimal::new(130, 0), 4));
+    cleanup(&pool, account_id).await;
+}
+
+#[tokio::test]
+async fn test_replayed_events_are_skipped() {
+    let pool = common::pool().await;
+    let store = ProjectionStore::new(pool.clone());
+    let account_id = Uuid::new_v4();
+    let events = account_events(account_id, 2);
+
+    for (version, event) in &events {
+        store.apply_event_at_version(event, *version).await.unwrap();
+    }
+    // The consumer crashed before committing its offsets and sees the events again
+    for (version, event) in &events {
+        let action = store.apply_event_at_version(event, *version).await.unwrap();
+        assert_eq!(action, ProjectionApply::Skipped { current: 3 });
+    }
+
+    assert_eq!(stored(&pool, account_id).await, (Decimal::new(120, 0), 3));
+    cleanup(&pool, account_id).await;
+}
+
+#[tokio::test]
+async fn test_gap_is_reported_and_nothing_is_written() {
+    let pool = common::pool().await;
+    let store = ProjectionStore::new(pool.clone());
+    let account_id = Uuid::new_v4();
+    let events = account_events(account_id, 3);
+
+    store
+        .apply_event_at_version(&events[0].1, events[0].0)
+        .await
+        .unwrap();
+    // Version 2 never arrived
+    let action = store
+        .apply_event_at_version(&events[2].1, events[2].0)
+        .await
+        .unwrap();
+    assert_eq!(
+        action,
+        ProjectionApply::Gap {
+            current: 1,
+            received: 3
+        }
+    );
+    assert_eq!(stored(&pool, account_id).await, (Decimal::new(100, 0), 1));
+
+    // Once the missing event is applied, the rest follow
+    for (version, event) in &events[1..] {
+        let action = store.apply_event_at_version(event, *version).await.unwrap();
+        assert_eq!(action, ProjectionApply::Applied { version: *version });
+    }
+    assert_eq!(stored(&pool, account_id).await, (Decimal::new(130, 0), 4));
+    cleanup(&pool, account_id).await;
+}
+
+#[tokio::test]
+async fn test_gap_before_the_account_exists() {
+    let pool = common::pool().await;
+    let store = ProjectionStore::new(pool);
+    let account_id = Uuid::new_v4();
+    let events = account_events(account_id, 1);
+
+    let action = store
+        .apply_event_at_version(&events[1].1, events[1].0)
+        .await
+        .unwrap();
+    assert_eq!(
+        action,
+        ProjectionApply::Gap {
+            current: 0,
+            received: 2
+        }
+    );
+    assert!(store
+        .get_account_uncached(account_id)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_batch_writer_keeps_the_version_current() {
+    let pool = common::pool().await;
+    let store = ProjectionStore::new(pool.clone());
+    let projection_name = format!("version-test-{}", Uuid::new_v4());
+    let account_id = Uuid::new_v4();
+    let events = account_events(account_id, 2);
+
+    let positioned: Vec<(i64, AccountEvent)> = events
+        .iter()
+        .map(|(version, event)| (*version, event.clone()))
+        .collect();
+    store
+        .apply_events_with_checkpoint(&projection_name, &positioned)
+        .await
+        .unwrap();
+    assert_eq!(stored(&pool, account_id).await, (Decimal::new(120, 0), 3));
+
+    // A versioned replay after the batch write changes nothing
+    let action = store
+        .apply_event_at_version(&events[2].1, events[2].0)
+        .await
+        .unwrap();
+    assert_eq!(action, ProjectionApply::Skipped { current: 3 });
+
+    let _ = sqlx::query("DELETE FROM projection_checkpoints WHERE projection_name = $1")
+        .bind(&projection_name)
+        .execute(&pool)
+        .await;
+    cleanup(&pool, account_id).await;
+}
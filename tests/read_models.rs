@@ -0,0 +1,145 @@
+mod common;
+
+use async_trait::async_trait;
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::{
+        projections::ProjectionStore,
+        read_models::{Projection, ProjectionDispatcher},
+    },
+};
+use rust_decimal::Decimal;
+use sqlx::{Postgres, Transaction};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Projection that records the event types it is handed.
+struct RecordingProjection {
+    name: String,
+    interests: &'static [&'static str],
+    received: Mutex<Vec<&'static str>>,
+}
+
+impl RecordingProjection {
+    fn new(interests: &'static [&'static str]) -> Arc<Self> {
+        Arc::new(Self {
+            name: format!("read_model_test_{}", Uuid::new_v4().simple()),
+            interests,
+            received: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn received(&self) -> Vec<&'static str> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Projection for RecordingProjection {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn interested_in(&self, event_type: &str) -> bool {
+        self.interests.contains(&event_type)
+    }
+
+    async fn apply(
+        &self,
+        event: &AccountEvent,
+        _tx: &mut Transaction<'_, Postgres>,
+    ) -> anyhow::Result<()> {
+        self.received.lock().unwrap().push(event.event_type());
+        Ok(())
+    }
+}
+
+fn account_history(account_id: Uuid) -> Vec<(Option<i64>, AccountEvent)> {
+    vec![
+        (
+            Some(1),
+            AccountEvent::AccountCreated {
+                account_id,
+                owner_name: "Read Models".to_string(),
+                initial_balance: Decimal::new(100, 0),
+                owner_user_id: None,
+                account_type: Default::default(),
+                rules: Default::default(),
+            },
+        ),
+        (
+            Some(2),
+            AccountEvent::MoneyDeposited {
+                account_id,
+                amount: Decimal::new(50, 0),
+                transaction_id: Uuid::new_v4(),
+            },
+        ),
+        (
+            Some(3),
+            AccountEvent::MoneyWithdrawn {
+                account_id,
+                amount: Decimal::new(20, 0),
+                transaction_id: Uuid::new_v4(),
+                withdrawn_at: None,
+            },
+        ),
+        (
+            Some(4),
+            AccountEvent::AccountClosed {
+                account_id,
+                reason: "Read model test".to_string(),
+            },
+        ),
+    ]
+}
+
+#[tokio::test]
+async fn test_each_projection_receives_only_its_event_types() {
+    let pool = common::pool().await;
+    let balances = RecordingProjection::new(&["MoneyDeposited", "MoneyWithdrawn"]);
+    let lifecycle = RecordingProjection::new(&["AccountCreated", "AccountClosed"]);
+    let dispatcher = ProjectionDispatcher::new(pool.clone())
+        .register(balances.clone())
+        .register(lifecycle.clone());
+
+    dispatcher
+        .dispatch(&account_history(Uuid::new_v4()))
+        .await
+        .unwrap();
+
+    assert_eq!(balances.received(), vec!["MoneyDeposited", "MoneyWithdrawn"]);
+    assert_eq!(lifecycle.received(), vec!["AccountCreated", "AccountClosed"]);
+
+    // Each projection has its own checkpoint, past the events it ignored as well
+    let store = ProjectionStore::new(pool);
+    for projection in [&balances, &lifecycle] {
+        let checkpoint = store.load_checkpoint(&projection.name).await.unwrap();
+        assert_eq!(checkpoint.unwrap().position, 4);
+    }
+}
+
+#[tokio::test]
+async fn test_redelivered_events_are_not_applied_again() {
+    let pool = common::pool().await;
+    let balances = RecordingProjection::new(&["MoneyDeposited", "MoneyWithdrawn"]);
+    let lifecycle = RecordingProjection::new(&["AccountCreated", "AccountClosed"]);
+    let events = account_history(Uuid::new_v4());
+
+    // The balances projection saw the first half of the stream before a restart
+    ProjectionDispatcher::new(pool.clone())
+        .register(balances.clone())
+        .dispatch(&events[..2])
+        .await
+        .unwrap();
+
+    ProjectionDispatcher::new(pool)
+        .register(balances.clone())
+        .register(lifecycle.clone())
+        .dispatch(&events)
+        .await
+        .unwrap();
+
+    assert_eq!(balances.received(), vec!["MoneyDeposited", "MoneyWithdrawn"]);
+    assert_eq!(lifecycle.received(), vec!["AccountCreated", "AccountClosed"]);
+}
@@ -0,0 +1,176 @@
+use async_trait::async_trait;
+use banking_es::infrastructure::{
+    maintenance::{MaintenanceLock, RedisMaintenanceLock},
+    redis_abstraction::{LockToken, RedisClientTrait, RedisConnectionCommands, RedisPoolConfig},
+};
+use redis::{aio::MultiplexedConnection, ErrorKind, RedisError};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A client without a server whose locks live in memory, standing in for Redis the
+/// way a test double of `RedisClientTrait` would.
+#[derive(Clone, Default)]
+struct InMemoryLocks {
+    locks: Arc<Mutex<HashMap<String, (LockToken, Instant)>>>,
+}
+
+fn no_server() -> RedisError {
+    RedisError::from((ErrorKind::IoError, "no server in this test"))
+}
+
+#[async_trait]
+impl RedisClientTrait for InMemoryLocks {
+    async fn get_connection(&self) -> Result<MultiplexedConnection, RedisError> {
+        Err(no_server())
+    }
+
+    fn clone_client(&self) -> Arc<dyn RedisClientTrait> {
+        Arc::new(self.clone())
+    }
+
+    async fn get_pooled_connection(
+        &self,
+    ) -> Result<Box<dyn RedisConnectionCommands + Send>, RedisError> {
+        Err(no_server())
+    }
+
+    fn get_pool_config(&self) -> RedisPoolConfig {
+        RedisPoolConfig::default()
+    }
+
+    async fn get(&self, _key: &str) -> Result<Option<String>, RedisError> {
+        Err(no_server())
+    }
+
+    async fn set(&self, _key: &str, _value: &str) -> Result<(), RedisError> {
+        Err(no_server())
+    }
+
+    async fn del(&self, _key: &str) -> Result<(), RedisError> {
+        Err(no_server())
+    }
+
+    async fn acquire_lock(
+        &self,
+        key: &str,
+        ttl: Duration,
+    ) -> Result<Option<LockToken>, RedisError> {
+        let mut locks = self.locks.lock().unwrap();
+        let fence = match locks.get(key) {
+            Some((_, expires_at)) if *expires_at > Instant::now() => return Ok(None),
+            Some((token, _)) => token.fence + 1,
+            None => 1,
+        };
+        let token = LockToken {
+            id: format!("holder-{}", fence),
+            fence,
+        };
+        locks.insert(key.to_string(), (token.clone(), Instant::now() + ttl));
+        Ok(Some(token))
+    }
+}
+
+#[tokio::test]
+async fn test_lock_methods_can_be_mocked() {
+    let client = InMemoryLocks::default();
+    let token = client
+        .acquire_lock("lock:mocked", Duration::from_secs(60))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token.fence, 1);
+
+    // The maintenance scheduler's lock goes through the client's lock methods
+    let lock = RedisMaintenanceLock::new(client.clone_client());
+    assert!(!lock
+        .try_acquire("lock:mocked", Duration::from_secs(60))
+        .await
+        .unwrap());
+    assert!(lock
+        .try_acquire("lock:other", Duration::from_secs(60))
+        .await
+        .unwrap());
+
+    // Methods the double leaves alone talk to Redis, and fail without it
+    assert!(client.release_lock("lock:mocked", &token).await.is_err());
+}
+
+/// Needs a running Redis; compiled with `--features redis-integration-tests`.
+#[cfg(feature = "redis-integration-tests")]
+mod with_redis {
+    use banking_es::infrastructure::redis_abstraction::{RealRedisClient, RedisClientTrait};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    fn client() -> Arc<dyn RedisClientTrait> {
+        let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string());
+        RealRedisClient::new(redis::Client::open(url).unwrap(), None)
+    }
+
+    fn key() -> String {
+        format!("lock:test:{}", Uuid::new_v4())
+    }
+
+    #[tokio::test]
+    async fn test_two_contenders_cannot_both_hold_the_lock() {
+        let (first, second) = (client(), client());
+        let key = key();
+        let ttl = Duration::from_secs(10);
+
+        for _ in 0..5 {
+            let (a, b) = tokio::join!(
+                first.acquire_lock(&key, ttl),
+                second.acquire_lock(&key, ttl)
+            );
+            let (winner, holder, loser) = match (a.unwrap(), b.unwrap()) {
+                (Some(token), None) => (token, &first, &second),
+                (None, Some(token)) => (token, &second, &first),
+                other => panic!("expected exactly one holder: {:?}", other),
+            };
+            assert!(loser.acquire_lock(&key, ttl).await.unwrap().is_none());
+
+            // Only the holder's token releases or extends it
+            let mut forged = winner.clone();
+            forged.id = Uuid::new_v4().to_string();
+            assert!(!loser.release_lock(&key, &forged).await.unwrap());
+            assert!(!loser.extend_lock(&key, &forged, ttl).await.unwrap());
+            assert!(holder.extend_lock(&key, &winner, ttl).await.unwrap());
+            assert!(holder.release_lock(&key, &winner).await.unwrap());
+            assert!(!holder.release_lock(&key, &winner).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stale_lock_expires_and_fences_out_its_holder() {
+        let client = client();
+        let key = key();
+        let stale = client
+            .acquire_lock(&key, Duration::from_millis(200))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(client
+            .acquire_lock(&key, Duration::from_secs(10))
+            .await
+            .unwrap()
+            .is_none());
+
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        let fresh = client
+            .acquire_lock(&key, Duration::from_secs(10))
+            .await
+            .unwrap()
+            .expect("expired lock is free again");
+        assert!(fresh.fence > stale.fence);
+
+        // The stale holder can no longer touch the new holder's lock
+        assert!(!client.release_lock(&key, &stale).await.unwrap());
+        assert!(!client
+            .extend_lock(&key, &stale, Duration::from_secs(10))
+            .await
+            .unwrap());
+        assert!(client.release_lock(&key, &fresh).await.unwrap());
+    }
+}
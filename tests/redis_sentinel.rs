@@ -0,0 +1,69 @@
+use banking_es::infrastructure::redis_abstraction::{
+    RedisPoolConfig, SentinelConfig, SentinelRedisClient,
+};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_connect_fails_without_a_reachable_sentinel() {
+    let config = SentinelConfig {
+        sentinels: vec!["redis://127.0.0.1:1/".to_string()],
+        master_name: "mymaster".to_string(),
+        check_interval: Duration::from_millis(100),
+    };
+    let pool_config = RedisPoolConfig {
+        connection_timeout: Duration::from_millis(500),
+        ..Default::default()
+    };
+    assert!(SentinelRedisClient::connect(config, Some(pool_config))
+        .await
+        .is_err());
+}
+
+/// Needs a Sentinel-managed master with at least one replica; compiled with
+/// `--features redis-sentinel-tests`. Forces a failover and expects the client to
+/// follow the promoted replica.
+#[cfg(feature = "redis-sentinel-tests")]
+#[tokio::test]
+async fn test_operations_recover_after_master_change() {
+    use banking_es::infrastructure::redis_abstraction::RedisClientTrait;
+    use uuid::Uuid;
+
+    let config = SentinelConfig::from_env().expect("REDIS_SENTINELS must be set");
+    let client = SentinelRedisClient::connect(config.clone(), None)
+        .await
+        .unwrap();
+    let before = client.status().await;
+    let old_master = before.current_master.clone().expect("master discovered");
+    assert_eq!(before.failovers, 0);
+
+    let key = format!("sentinel-test:{}", Uuid::new_v4());
+    client.set(&key, "before").await.unwrap();
+
+    let sentinel = redis::Client::open(config.sentinels[0].as_str()).unwrap();
+    let mut conn = sentinel.get_multiplexed_async_connection().await.unwrap();
+    redis::cmd("SENTINEL")
+        .arg("FAILOVER")
+        .arg(&config.master_name)
+        .query_async::<_, ()>(&mut conn)
+        .await
+        .unwrap();
+
+    // Writes fail while the replica is promoted, then succeed against the new master
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(60);
+    loop {
+        if client.set(&key, "after").await.is_ok() {
+            let status = client.status().await;
+            if status.current_master.as_deref() != Some(old_master.as_str()) {
+                assert!(status.failovers >= 1);
+                break;
+            }
+        }
+        assert!(
+            tokio::time::Instant::now() < deadline,
+            "client did not follow the failover"
+        );
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    assert_eq!(client.get(&key).await.unwrap().as_deref(), Some("after"));
+    client.del(&key).await.unwrap();
+}
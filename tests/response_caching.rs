@@ -0,0 +1,158 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use banking_es::{
+    application::services::AccountService,
+    infrastructure::{
+        auth::{AuthConfig, AuthService, Claims, TokenType, UserRole},
+        cache_service::{CacheConfig, CacheService, CacheServiceTrait},
+        config::ResponseCacheConfig,
+        event_store::{EventStore, EventStoreTrait},
+        middleware::RequestMiddleware,
+        projections::{ProjectionStore, ProjectionStoreTrait},
+        redis_abstraction::RealRedisClient,
+        repository::{AccountRepository, AccountRepositoryTrait},
+        user_repository::UserRepository,
+    },
+    web::routes::create_router,
+};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_JWT_SECRET: &str = "response_caching_test_secret";
+
+async fn setup(response_cache: ResponseCacheConfig) -> (axum::Router, PgPool) {
+    std::env::set_var("JWT_SECRET", TEST_JWT_SECRET);
+
+    let pool = common::pool().await;
+
+    let event_store = Arc::new(EventStore::new(pool.clone())) as Arc<dyn EventStoreTrait + 'static>;
+    let repository =
+        Arc::new(AccountRepository::new(event_store)) as Arc<dyn AccountRepositoryTrait + 'static>;
+    let redis_client = redis::Client::open("redis://localhost:6379").unwrap();
+    let service = Arc::new(
+        AccountService::new(
+            repository,
+            Arc::new(ProjectionStore::new(pool.clone())) as Arc<dyn ProjectionStoreTrait + 'static>,
+            Arc::new(CacheService::new(
+                RealRedisClient::new(redis_client.clone(), None),
+                CacheConfig::default(),
+            )) as Arc<dyn CacheServiceTrait + 'static>,
+            Arc::new(RequestMiddleware::default()),
+            100,
+        )
+        .with_response_cache(response_cache),
+    );
+    let auth_service = Arc::new(AuthService::new(
+        Arc::new(redis_client),
+        AuthConfig {
+            jwt_secret: TEST_JWT_SECRET.to_string(),
+            refresh_token_secret: "response_caching_refresh_secret".to_string(),
+            access_token_expiry: 3600,
+            refresh_token_expiry: 604800,
+            rate_limit_requests: 1000,
+            rate_limit_window: 60,
+            max_failed_attempts: 5,
+            lockout_duration_minutes: 30,
+        },
+        Arc::new(UserRepository::new(pool.clone())),
+    ));
+
+    (create_router(service, auth_service), pool)
+}
+
+fn admin_token() -> String {
+    let now = chrono::Utc::now().timestamp();
+    let claims = Claims {
+        sub: "response-caching-test".to_string(),
+        exp: now + 3600,
+        iat: now,
+        roles: vec![UserRole::Admin],
+        token_type: TokenType::Access,
+        jti: Uuid::new_v4().to_string(),
+        company: "banking-es".to_string(),
+        user_id: Uuid::new_v4(),
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(TEST_JWT_SECRET.as_bytes()),
+    )
+    .unwrap()
+}
+
+async fn insert_projection(pool: &PgPool) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO account_projections (id, owner_name, balance, is_active, created_at, updated_at)
+        VALUES ($1, 'Response Caching', 42, true, NOW(), NOW())
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+async fn get_account(
+    router: &axum::Router,
+    account_id: Uuid,
+    if_none_match: Option<&str>,
+) -> axum::response::Response {
+    let mut request = Request::builder()
+        .uri(format!("/api/accounts/{}", account_id))
+        .header(header::AUTHORIZATION, format!("Bearer {}", admin_token()));
+    if let Some(etag) = if_none_match {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    router
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_matching_if_none_match_returns_304() {
+    let (router, pool) = setup(ResponseCacheConfig::default()).await;
+    let account_id = insert_projection(&pool).await;
+
+    let response = get_account(&router, account_id, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "private, no-cache");
+    let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+
+    let response = get_account(&router, account_id, Some(&etag)).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[header::ETAG], etag.as_str());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(body.is_empty());
+
+    // A tag for some other state gets the full response
+    let response = get_account(&router, account_id, Some("\"stale\"")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_disabled_caching_sends_no_validators() {
+    let (router, pool) = setup(ResponseCacheConfig {
+        enabled: false,
+        max_age: Duration::ZERO,
+    })
+    .await;
+    let account_id = insert_projection(&pool).await;
+
+    let response = get_account(&router, account_id, Some("*")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!response.headers().contains_key(header::ETAG));
+    assert!(!response.headers().contains_key(header::CACHE_CONTROL));
+}
@@ -0,0 +1,145 @@
+use axum::{routing::get, Router};
+use banking_es::{infrastructure::config::ServerConfig, web::server};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+#[test]
+fn test_server_config_is_read_from_env() {
+    std::env::set_var("SERVER_LISTEN_BACKLOG", "4096");
+    std::env::set_var("SERVER_TCP_NODELAY", "false");
+    std::env::set_var("HTTP_KEEP_ALIVE", "false");
+    std::env::set_var("HTTP_KEEP_ALIVE_IDLE_TIMEOUT_SECS", "5");
+    std::env::set_var("SERVER_DRAIN_TIMEOUT_MS", "1500");
+    assert_eq!(
+        ServerConfig::from_env(),
+        ServerConfig {
+            listen_backlog: 4096,
+            tcp_nodelay: false,
+            http_keep_alive: false,
+            keep_alive_idle_timeout: Duration::from_secs(5),
+            drain_timeout: Duration::from_millis(1500),
+        }
+    );
+
+    // Unparseable values keep the defaults
+    std::env::set_var("SERVER_LISTEN_BACKLOG", "lots");
+    for key in [
+        "SERVER_TCP_NODELAY",
+        "HTTP_KEEP_ALIVE",
+        "HTTP_KEEP_ALIVE_IDLE_TIMEOUT_SECS",
+        "SERVER_DRAIN_TIMEOUT_MS",
+    ] {
+        std::env::remove_var(key);
+    }
+    assert_eq!(ServerConfig::from_env(), ServerConfig::default());
+    std::env::remove_var("SERVER_LISTEN_BACKLOG");
+}
+
+async fn start(config: ServerConfig) -> SocketAddr {
+    let listener = server::bind("127.0.0.1:0".parse().unwrap(), &config).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = Router::new().route("/", get(|| async { "ok" }));
+    tokio::spawn(server::serve(listener, app, config, std::future::pending()));
+    addr
+}
+
+/// Sends one request and returns the response head.
+async fn request(stream: &mut TcpStream) -> String {
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    let mut buf = vec![0; 1024];
+    let n = stream.read(&mut buf).await.unwrap();
+    String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase()
+}
+
+/// Waits for the server to close the connection and returns how long that took.
+async fn closed_after(stream: &mut TcpStream) -> Duration {
+    let start = Instant::now();
+    let mut buf = [0; 64];
+    let n = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+        .await
+        .expect("connection stays open")
+        .unwrap();
+    assert_eq!(n, 0);
+    start.elapsed()
+}
+
+#[tokio::test]
+async fn test_idle_keep_alive_connections_are_closed() {
+    let addr = start(ServerConfig {
+        listen_backlog: 16,
+        keep_alive_idle_timeout: Duration::from_millis(300),
+        ..ServerConfig::default()
+    })
+    .await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let response = request(&mut stream).await;
+    assert!(response.starts_with("http/1.1 200"), "{}", response);
+    assert!(!response.contains("connection: close"));
+
+    // The connection is reused for a second request, then closed once idle
+    assert!(request(&mut stream).await.starts_with("http/1.1 200"));
+    let idle = closed_after(&mut stream).await;
+    assert!(idle >= Duration::from_millis(200), "{:?}", idle);
+}
+
+#[tokio::test]
+async fn test_disabled_keep_alive_closes_after_each_response() {
+    let addr = start(ServerConfig {
+        http_keep_alive: false,
+        ..ServerConfig::default()
+    })
+    .await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let response = request(&mut stream).await;
+    assert!(response.starts_with("http/1.1 200"), "{}", response);
+    assert!(response.contains("connection: close"), "{}", response);
+    assert!(closed_after(&mut stream).await < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn test_shutdown_abandons_requests_still_running_after_the_drain_timeout() {
+    let config = ServerConfig {
+        drain_timeout: Duration::from_millis(300),
+        ..ServerConfig::default()
+    };
+    let listener = server::bind("127.0.0.1:0".parse().unwrap(), &config).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (entered_tx, mut entered_rx) = tokio::sync::mpsc::unbounded_channel::<()>();
+    let app = Router::new().route(
+        "/",
+        get(move || async move {
+            let _ = entered_tx.send(());
+            std::future::pending::<&str>().await
+        }),
+    );
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(server::serve(listener, app, config, async {
+        let _ = stopped.await;
+    }));
+
+    let mut hung = TcpStream::connect(addr).await.unwrap();
+    hung.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    entered_rx.recv().await.unwrap();
+
+    let start = Instant::now();
+    stop.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server hangs on the in-flight request")
+        .unwrap()
+        .unwrap();
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(300), "{:?}", elapsed);
+
+    // The abandoned request's connection is closed without a response
+    assert!(closed_after(&mut hung).await < Duration::from_secs(1));
+}
@@ -0,0 +1,72 @@
+use banking_es::infrastructure::shutdown::{
+    PhaseResult, ShutdownConfig, ShutdownCoordinator, ShutdownPhase,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn record(
+    coordinator: &mut ShutdownCoordinator,
+    log: &Arc<Mutex<Vec<ShutdownPhase>>>,
+    phase: ShutdownPhase,
+) {
+    let log = log.clone();
+    coordinator.add_step(phase, move || async move {
+        log.lock().unwrap().push(phase);
+        Ok(())
+    });
+}
+
+#[tokio::test]
+async fn test_phases_run_in_order() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut coordinator = ShutdownCoordinator::default();
+    // Registered out of order, as main and the service context each add their own
+    for phase in [
+        ShutdownPhase::FlushTracing,
+        ShutdownPhase::Deregister,
+        ShutdownPhase::StopIntake,
+        ShutdownPhase::ClosePools,
+        ShutdownPhase::FlushRepository,
+        ShutdownPhase::DrainInFlight,
+        ShutdownPhase::StopKafka,
+    ] {
+        record(&mut coordinator, &log, phase);
+    }
+
+    let outcomes = coordinator.run().await;
+
+    assert_eq!(*log.lock().unwrap(), ShutdownPhase::ALL);
+    assert_eq!(
+        outcomes.iter().map(|o| o.phase).collect::<Vec<_>>(),
+        ShutdownPhase::ALL
+    );
+    assert!(outcomes.iter().all(|o| o.result == PhaseResult::Completed));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_stuck_or_failing_phase_does_not_block_later_phases() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut coordinator = ShutdownCoordinator::new(ShutdownConfig {
+        default_timeout: Duration::from_secs(1),
+        phase_timeouts: HashMap::new(),
+    });
+    coordinator.add_step(ShutdownPhase::DrainInFlight, || async {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        Ok(())
+    });
+    coordinator.add_step(ShutdownPhase::StopKafka, || async {
+        Err(anyhow::anyhow!("commit failed"))
+    });
+    record(&mut coordinator, &log, ShutdownPhase::ClosePools);
+
+    let outcomes = coordinator.run().await;
+
+    assert_eq!(outcomes[0].result, PhaseResult::TimedOut);
+    assert_eq!(
+        outcomes[1].result,
+        PhaseResult::Failed("commit failed".to_string())
+    );
+    assert_eq!(outcomes[2].result, PhaseResult::Completed);
+    assert_eq!(*log.lock().unwrap(), vec![ShutdownPhase::ClosePools]);
+}
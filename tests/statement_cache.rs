@@ -0,0 +1,90 @@
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::event_store::{EventStore, EventStoreConfig},
+};
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use uuid::Uuid;
+
+/// A store on a single connection, so every query it runs shows up in that session's
+/// `pg_prepared_statements`.
+async fn store(statement_cache_capacity: usize) -> (EventStore, PgPool) {
+    let config = EventStoreConfig {
+        statement_cache_capacity,
+        ..EventStoreConfig::default_in_memory_for_tests().unwrap()
+    };
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect_with(config.connect_options().unwrap())
+        .await
+        .expect("Failed to connect to database");
+    (
+        EventStore::new_with_config_and_pool(pool.clone(), config),
+        pool,
+    )
+}
+
+/// Statements prepared on the pool's connection whose text contains `fragment`.
+async fn prepared(pool: &PgPool, fragment: &str) -> i64 {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pg_prepared_statements WHERE statement LIKE '%' || $1 || '%'",
+    )
+    .bind(fragment)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+/// Saves a new account with `deposits` deposits in one batch, then reads it back twice.
+async fn exercise(store: &EventStore, deposits: usize) -> Uuid {
+    let account_id = Uuid::new_v4();
+    let mut events = vec![AccountEvent::AccountCreated {
+        account_id,
+        owner_name: "Statement Cache".to_string(),
+        initial_balance: Decimal::ZERO,
+        owner_user_id: None,
+        account_type: Default::default(),
+        rules: Default::default(),
+    }];
+    events.extend((0..deposits).map(|_| AccountEvent::MoneyDeposited {
+        account_id,
+        amount: Decimal::ONE,
+        transaction_id: Uuid::new_v4(),
+    }));
+    store.save_events(account_id, events, 0).await.unwrap();
+    for _ in 0..2 {
+        let page = store.get_events_page(account_id, 0, 100).await.unwrap();
+        assert_eq!(page.len(), deposits + 1);
+    }
+    account_id
+}
+
+async fn cleanup(pool: &PgPool, account_ids: &[Uuid]) {
+    let _ = sqlx::query("DELETE FROM events WHERE aggregate_id = ANY($1)")
+        .bind(account_ids)
+        .execute(pool)
+        .await;
+}
+
+#[tokio::test]
+async fn test_repeated_queries_reuse_one_prepared_statement() {
+    let (store, pool) = store(100).await;
+
+    // Batches of different sizes share the insert statement
+    let accounts = [exercise(&store, 0).await, exercise(&store, 4).await];
+
+    assert_eq!(prepared(&pool, "INSERT INTO events").await, 1);
+    assert_eq!(prepared(&pool, "LIMIT $3").await, 1);
+    cleanup(&pool, &accounts).await;
+}
+
+#[tokio::test]
+async fn test_disabled_cache_keeps_no_prepared_statements() {
+    let (store, pool) = store(0).await;
+
+    let accounts = [exercise(&store, 2).await];
+
+    assert_eq!(prepared(&pool, "INSERT INTO events").await, 0);
+    assert_eq!(prepared(&pool, "LIMIT $3").await, 0);
+    cleanup(&pool, &accounts).await;
+}
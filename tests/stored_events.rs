@@ -0,0 +1,102 @@
+mod common;
+
+use banking_es::{
+    domain::AccountEvent,
+    infrastructure::{
+        event_store::{EventStore, EventStoreTrait, StoredEvent},
+        in_memory_event_store::InMemoryEventStore,
+    },
+};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use uuid::Uuid;
+
+async fn postgres_store() -> Arc<dyn EventStoreTrait> {
+    let pool = common::pool().await;
+    Arc::new(EventStore::new(pool))
+}
+
+async fn save_and_load(event_store: &dyn EventStoreTrait) -> (Uuid, Vec<StoredEvent>) {
+    let account_id = Uuid::new_v4();
+    let transaction_id = Uuid::new_v4();
+    event_store
+        .save_events(
+            account_id,
+            vec![
+                AccountEvent::AccountCreated {
+                    account_id,
+                    owner_name: "Stored Event Test".to_string(),
+                    initial_balance: Decimal::new(100, 0),
+                    owner_user_id: None,
+                    account_type: Default::default(),
+                    rules: Default::default(),
+                },
+                AccountEvent::MoneyDeposited {
+                    account_id,
+                    amount: Decimal::new(25, 0),
+                    transaction_id,
+                },
+            ],
+            0,
+        )
+        .await
+        .unwrap();
+    (
+        account_id,
+        event_store.get_events(account_id, None).await.unwrap(),
+    )
+}
+
+fn assert_populated(account_id: Uuid, events: &[StoredEvent], saved_after: chrono::DateTime<Utc>) {
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|e| e.aggregate_id == account_id));
+    assert_eq!(
+        events.iter().map(|e| e.version).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(
+        events
+            .iter()
+            .map(|e| e.event_type.as_str())
+            .collect::<Vec<_>>(),
+        vec!["AccountCreated", "MoneyDeposited"]
+    );
+    assert!(matches!(
+        events[1].decode().unwrap(),
+        AccountEvent::MoneyDeposited { amount, .. } if amount == Decimal::new(25, 0)
+    ));
+    assert!(events[0].metadata.correlation_id.is_some());
+    assert_eq!(
+        events[0].metadata.correlation_id,
+        events[1].metadata.correlation_id
+    );
+    for event in events {
+        assert!(event.occurred_at >= saved_after && event.occurred_at <= Utc::now());
+    }
+    assert!(events[0].global_position > 0);
+    assert!(events[1].global_position > events[0].global_position);
+}
+
+#[tokio::test]
+async fn test_postgres_store_populates_stored_event_fields() {
+    let event_store = postgres_store().await;
+    let saved_after = Utc::now() - chrono::Duration::seconds(1);
+    let (account_id, events) = save_and_load(event_store.as_ref()).await;
+    assert_populated(account_id, &events, saved_after);
+
+    // Positions keep increasing across aggregates
+    let (_, later) = save_and_load(event_store.as_ref()).await;
+    assert!(later[0].global_position > events[1].global_position);
+}
+
+#[tokio::test]
+async fn test_in_memory_store_populates_stored_event_fields() {
+    let event_store = InMemoryEventStore::new();
+    let saved_after = Utc::now();
+    let (account_id, events) = save_and_load(&event_store).await;
+    assert_populated(account_id, &events, saved_after);
+
+    let (_, later) = save_and_load(&event_store).await;
+    assert_eq!(later[0].global_position, events[1].global_position + 1);
+}